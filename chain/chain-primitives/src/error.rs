@@ -5,11 +5,12 @@ use near_primitives::time::Utc;
 
 use near_primitives::block::BlockValidityError;
 use near_primitives::challenge::{ChunkProofs, ChunkState};
-use near_primitives::errors::{EpochError, StorageError};
+use near_primitives::errors::{ChunkValidationError, EpochError, StorageError};
 use near_primitives::serialize::to_base;
 use near_primitives::shard_layout::ShardLayoutError;
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
 use near_primitives::types::{BlockHeight, EpochId, ShardId};
+use near_primitives::utils::TimestampError;
 
 #[derive(thiserror::Error, Debug)]
 pub enum QueryError {
@@ -79,6 +80,9 @@ pub enum Error {
     /// Block time is from too much in the future.
     #[error("Invalid Block Time: Too far in the future: {0}")]
     InvalidBlockFutureTime(DateTime<Utc>),
+    /// Block timestamp can't be converted to a time.
+    #[error("Invalid Block Time: {0}")]
+    InvalidBlockTimestamp(TimestampError),
     /// Block height is invalid (not previous + 1).
     #[error("Invalid Block Height {0}")]
     InvalidBlockHeight(BlockHeight),
@@ -127,6 +131,9 @@ pub enum Error {
     /// Invalid chunk.
     #[error("Invalid Chunk")]
     InvalidChunk,
+    /// Chunk header failed the basic checks.
+    #[error("Invalid Chunk Header: {0}")]
+    InvalidChunkHeader(ChunkValidationError),
     /// One of the chunks has invalid proofs
     #[error("Invalid Chunk Proofs")]
     InvalidChunkProofs(Box<ChunkProofs>),
@@ -246,9 +253,11 @@ impl Error {
             | Error::DBNotFoundErr(_) => false,
             Error::InvalidBlockPastTime(_, _)
             | Error::InvalidBlockFutureTime(_)
+            | Error::InvalidBlockTimestamp(_)
             | Error::InvalidBlockHeight(_)
             | Error::InvalidBlockProposer
             | Error::InvalidChunk
+            | Error::InvalidChunkHeader(_)
             | Error::InvalidChunkProofs(_)
             | Error::InvalidChunkState(_)
             | Error::InvalidChunkMask
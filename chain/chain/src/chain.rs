@@ -21,7 +21,7 @@ use near_primitives::challenge::{
 use near_primitives::checked_feature;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::merkle::{
-    combine_hash, merklize, verify_path, Direction, MerklePath, MerklePathItem,
+    combine_hash, merklize, verify_path, BlockMerkleProof, Direction, MerklePathItem,
 };
 use near_primitives::receipt::Receipt;
 use near_primitives::sharding::{
@@ -33,7 +33,7 @@ use near_primitives::syncing::{
     get_num_state_parts, ReceiptProofResponse, RootProof, ShardStateSyncResponseHeader,
     ShardStateSyncResponseHeaderV1, ShardStateSyncResponseHeaderV2, StateHeaderKey, StatePartKey,
 };
-use near_primitives::transaction::ExecutionOutcomeWithIdAndProof;
+use near_primitives::transaction::{ExecutionOutcome, ExecutionOutcomeWithIdAndProof};
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{
     AccountId, Balance, BlockExtra, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash,
@@ -41,10 +41,10 @@ use near_primitives::types::{
 };
 use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
+use near_primitives::validation::{gas_limit_bounds, validate_chunk_header_basic};
 use near_primitives::views::{
-    BlockStatusView, ExecutionOutcomeWithIdView, ExecutionStatusView, FinalExecutionOutcomeView,
-    FinalExecutionOutcomeWithReceiptView, FinalExecutionStatus, LightClientBlockView,
-    SignedTransactionView,
+    BlockStatusView, ExecutionOutcomeWithIdView, FinalExecutionOutcomeView,
+    FinalExecutionOutcomeWithReceiptView, LightClientBlockView, SignedTransactionView,
 };
 use near_store::{DBCol, ShardTries, StoreUpdate};
 
@@ -871,7 +871,7 @@ impl Chain {
                 chain_store_update.get_chain_store().get_all_block_hashes_by_height(height)
             {
                 let blocks_current_height =
-                    blocks_current_height.values().flatten().cloned().collect::<Vec<_>>();
+                    blocks_current_height.all_hashes().cloned().collect::<Vec<_>>();
                 if let Some(block_hash) = blocks_current_height.first() {
                     let prev_hash = *chain_store_update.get_block_header(block_hash)?.prev_hash();
                     let prev_block_refcount = *chain_store_update.get_block_refcount(&prev_hash)?;
@@ -935,7 +935,7 @@ impl Chain {
     ) -> Result<(), Error> {
         if let Ok(blocks_current_height) = self.store.get_all_block_hashes_by_height(height) {
             let blocks_current_height =
-                blocks_current_height.values().flatten().cloned().collect::<Vec<_>>();
+                blocks_current_height.all_hashes().cloned().collect::<Vec<_>>();
             for block_hash in blocks_current_height.iter() {
                 let mut current_hash = *block_hash;
                 loop {
@@ -1224,7 +1224,7 @@ impl Chain {
         for height in tail..gc_height {
             if let Ok(blocks_current_height) = self.store.get_all_block_hashes_by_height(height) {
                 let blocks_current_height =
-                    blocks_current_height.values().flatten().cloned().collect::<Vec<_>>();
+                    blocks_current_height.all_hashes().cloned().collect::<Vec<_>>();
                 for block_hash in blocks_current_height {
                     let runtime_adapter = self.runtime_adapter();
                     let mut chain_store_update = self.mut_store().store_update();
@@ -2486,9 +2486,9 @@ impl Chain {
     fn get_recursive_transaction_results(
         &mut self,
         id: &CryptoHash,
-    ) -> Result<Vec<ExecutionOutcomeWithIdView>, Error> {
-        let outcome: ExecutionOutcomeWithIdView = self.get_execution_outcome(id)?.into();
-        let receipt_ids = outcome.outcome.receipt_ids.clone();
+    ) -> Result<Vec<ExecutionOutcomeWithIdAndProof>, Error> {
+        let outcome = self.get_execution_outcome(id)?;
+        let receipt_ids = outcome.outcome_with_id.outcome.receipt_ids.clone();
         let mut results = vec![outcome];
         for receipt_id in &receipt_ids {
             results.extend(self.get_recursive_transaction_results(receipt_id)?);
@@ -2500,34 +2500,18 @@ impl Chain {
         &mut self,
         transaction_hash: &CryptoHash,
     ) -> Result<FinalExecutionOutcomeView, Error> {
-        let mut outcomes = self.get_recursive_transaction_results(transaction_hash)?;
-        let mut looking_for_id = (*transaction_hash).into();
-        let num_outcomes = outcomes.len();
-        let status = outcomes
+        let outcomes = self.get_recursive_transaction_results(transaction_hash)?;
+        let outcomes_by_id: HashMap<&CryptoHash, &ExecutionOutcome> = outcomes
             .iter()
-            .find_map(|outcome_with_id| {
-                if outcome_with_id.id == looking_for_id {
-                    match &outcome_with_id.outcome.status {
-                        ExecutionStatusView::Unknown if num_outcomes == 1 => {
-                            Some(FinalExecutionStatus::NotStarted)
-                        }
-                        ExecutionStatusView::Unknown => Some(FinalExecutionStatus::Started),
-                        ExecutionStatusView::Failure(e) => {
-                            Some(FinalExecutionStatus::Failure(e.clone()))
-                        }
-                        ExecutionStatusView::SuccessValue(v) => {
-                            Some(FinalExecutionStatus::SuccessValue(v.clone()))
-                        }
-                        ExecutionStatusView::SuccessReceiptId(id) => {
-                            looking_for_id = *id;
-                            None
-                        }
-                    }
-                } else {
-                    None
-                }
-            })
-            .expect("results should resolve to a final outcome");
+            .map(|outcome| (outcome.id(), &outcome.outcome_with_id.outcome))
+            .collect();
+        let status = outcomes[0]
+            .outcome_with_id
+            .outcome
+            .flatten_receipt_chain(|receipt_id| outcomes_by_id.get(receipt_id).cloned().cloned())
+            .into();
+        let mut outcomes: Vec<ExecutionOutcomeWithIdView> =
+            outcomes.into_iter().map(Into::into).collect();
         let receipts_outcome = outcomes.split_off(1);
         let transaction: SignedTransactionView = self
             .store
@@ -2784,17 +2768,19 @@ impl Chain {
     }
 
     /// Get merkle proof for block with hash `block_hash` in the merkle tree of `head_block_hash`.
+    /// The root of the proof is the `block_merkle_root` of the head block, unless the block is
+    /// the head itself, in which case the proof has an empty path and the block hash as root.
     pub fn get_block_proof(
         &mut self,
         block_hash: &CryptoHash,
         head_block_hash: &CryptoHash,
-    ) -> Result<MerklePath, Error> {
+    ) -> Result<BlockMerkleProof, Error> {
         let leaf_index = self.mut_store().get_block_merkle_tree(block_hash)?.size();
         let tree_size = self.mut_store().get_block_merkle_tree(head_block_hash)?.size();
         if leaf_index >= tree_size {
             if block_hash == head_block_hash {
                 // special case if the block to prove is the same as head
-                return Ok(vec![]);
+                return Ok(BlockMerkleProof { path: vec![], root: *block_hash });
             }
             return Err(Error::Other(format!(
                 "block {} is ahead of head block {}",
@@ -2835,7 +2821,8 @@ impl Chain {
             level += 1;
             counter *= 2;
         }
-        Ok(path)
+        let root = *self.get_block_header(head_block_hash)?.block_merkle_root();
+        Ok(BlockMerkleProof { path, root })
     }
 }
 
@@ -4303,6 +4290,7 @@ impl<'a> ChainUpdate<'a> {
     /// Do basic validation of the information that we can get from the chunk headers in `block`
     fn validate_chunk_headers(&mut self, block: &Block, prev_block: &Block) -> Result<(), Error> {
         let prev_chunk_headers = Chain::get_prev_chunk_headers(&*self.runtime_adapter, prev_block)?;
+        let num_shards = self.runtime_adapter.num_shards(block.header().epoch_id())?;
         for (chunk_header, prev_chunk_header) in
             block.chunks().iter().zip(prev_chunk_headers.iter())
         {
@@ -4310,6 +4298,12 @@ impl<'a> ChainUpdate<'a> {
                 if &chunk_header.prev_block_hash() != block.header().prev_hash() {
                     return Err(Error::InvalidChunk.into());
                 }
+                validate_chunk_header_basic(
+                    chunk_header,
+                    num_shards,
+                    gas_limit_bounds(prev_chunk_header.gas_limit()),
+                )
+                .map_err(Error::InvalidChunkHeader)?;
             } else {
                 if prev_chunk_header != chunk_header {
                     return Err(Error::InvalidChunk.into());
@@ -4473,6 +4467,7 @@ impl<'a> ChainUpdate<'a> {
             self.block_economics_config.min_gas_price(protocol_version),
             self.block_economics_config.max_gas_price(protocol_version),
             self.block_economics_config.gas_price_adjustment_rate(protocol_version),
+            protocol_version,
         ) {
             byzantine_assert!(false);
             return Err(Error::InvalidGasPrice.into());
@@ -4652,8 +4647,9 @@ impl<'a> ChainUpdate<'a> {
         on_challenge: &mut dyn FnMut(ChallengeBody),
     ) -> Result<(), Error> {
         // Refuse blocks from the too distant future.
-        if header.timestamp() > Clock::utc() + Duration::seconds(ACCEPTABLE_TIME_DIFFERENCE) {
-            return Err(Error::InvalidBlockFutureTime(header.timestamp()).into());
+        let timestamp = header.try_timestamp().map_err(Error::InvalidBlockTimestamp)?;
+        if timestamp > Clock::utc() + Duration::seconds(ACCEPTABLE_TIME_DIFFERENCE) {
+            return Err(Error::InvalidBlockFutureTime(timestamp).into());
         }
 
         // First I/O cost, delay as much as possible.
@@ -7,7 +7,7 @@ use lru::LruCache;
 use near_primitives::time::Utc;
 
 use near_chain_primitives::error::Error;
-use near_primitives::block::{Approval, Tip};
+use near_primitives::block::{Approval, BlocksPerHeight, Tip};
 use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
@@ -27,15 +27,16 @@ use near_primitives::transaction::{
 use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{
-    AccountId, BlockExtra, BlockHeight, BlockHeightDelta, EpochId, GCCount, NumBlocks, ShardId,
-    StateChanges, StateChangesExt, StateChangesForSplitStates, StateChangesKinds,
-    StateChangesKindsExt, StateChangesRequest,
+    AccountId, BlockExtra, BlockHeight, BlockHeightDelta, EpochId, GCCount, NumBlocks,
+    RawStateChangesWithTrieKey, ShardId, StateChanges, StateChangesExt, StateChangesForSplitStates,
+    StateChangesKinds, StateChangesKindsExt, StateChangesPage, StateChangesPagination,
+    StateChangesRequest,
 };
 use near_primitives::utils::{get_block_shard_id, index_to_bytes, to_timestamp};
 use near_primitives::views::LightClientBlockView;
 use near_store::{
-    read_with_cache, DBCol, KeyForStateChanges, ShardTries, Store, StoreUpdate, WrappedTrieChanges,
-    CHUNK_TAIL_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY,
+    read_with_cache, DBCol, KeyForStateChanges, ShardTries, Store, StoreOverlay, StoreUpdate,
+    WrappedTrieChanges, CHUNK_TAIL_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY,
     LARGEST_TARGET_HEIGHT_KEY, LATEST_KNOWN_KEY, TAIL_KEY,
 };
 
@@ -310,7 +311,7 @@ pub struct ChainStore {
     /// Cache with height to hash on the main chain.
     height: LruCache<Vec<u8>, CryptoHash>,
     /// Cache with height to block hash on any chain.
-    block_hash_per_height: LruCache<Vec<u8>, HashMap<EpochId, HashSet<CryptoHash>>>,
+    block_hash_per_height: LruCache<Vec<u8>, BlocksPerHeight>,
     /// Cache with height and shard_id to any chunk hash.
     chunk_hash_per_height_shard: LruCache<Vec<u8>, ChunkHash>,
     /// Next block hashes for each block on the canonical chain
@@ -488,56 +489,158 @@ impl ChainStore {
         base_block_hash: &CryptoHash,
         validity_period: BlockHeight,
     ) -> Result<(), InvalidTxError> {
-        // if both are on the canonical chain, comparing height is sufficient
-        // we special case this because it is expected that this scenario will happen in most cases.
-        let base_height =
-            self.get_block_header(base_block_hash).map_err(|_| InvalidTxError::Expired)?.height();
-        let prev_height = prev_block_header.height();
-        if let Ok(base_block_hash_by_height) = self.get_block_hash_by_height(base_height) {
-            if &base_block_hash_by_height == base_block_hash {
-                if let Ok(prev_hash) = self.get_block_hash_by_height(prev_height) {
-                    if &prev_hash == prev_block_header.hash() {
-                        if prev_height <= base_height + validity_period {
-                            return Ok(());
-                        } else {
-                            return Err(InvalidTxError::Expired);
-                        }
+        check_transaction_validity_period(self, prev_block_header, base_block_hash, validity_period)
+    }
+
+    /// Returns a read-only view of the chain as of now.  Blocks saved after
+    /// the call aren't visible through it.
+    pub fn snapshot(&self) -> ChainStoreSnapshot<'_> {
+        ChainStoreSnapshot {
+            overlay: StoreOverlay::new(&self.store),
+            genesis_height: self.genesis_height,
+        }
+    }
+}
+
+/// Read-only view of a [`ChainStore`], see [`ChainStore::snapshot`].
+pub struct ChainStoreSnapshot<'a> {
+    overlay: StoreOverlay<'a>,
+    genesis_height: BlockHeight,
+}
+
+impl<'a> ChainStoreSnapshot<'a> {
+    /// Same as [`ChainStore::check_transaction_validity_period`], but reads
+    /// the chain from the snapshot.
+    pub fn check_transaction_validity_period(
+        &mut self,
+        prev_block_header: &BlockHeader,
+        base_block_hash: &CryptoHash,
+        validity_period: BlockHeight,
+    ) -> Result<(), InvalidTxError> {
+        check_transaction_validity_period(self, prev_block_header, base_block_hash, validity_period)
+    }
+}
+
+/// Chain reads needed by [`check_transaction_validity_period`].
+trait ValidityPeriodReads {
+    fn genesis_height(&self) -> BlockHeight;
+    /// Returns the height and the previous block hash of the given block.
+    fn get_height_and_prev_hash(
+        &mut self,
+        hash: &CryptoHash,
+    ) -> Result<(BlockHeight, CryptoHash), Error>;
+    fn get_hash_by_height(&mut self, height: BlockHeight) -> Result<CryptoHash, Error>;
+}
+
+impl ValidityPeriodReads for ChainStore {
+    fn genesis_height(&self) -> BlockHeight {
+        self.genesis_height
+    }
+
+    fn get_height_and_prev_hash(
+        &mut self,
+        hash: &CryptoHash,
+    ) -> Result<(BlockHeight, CryptoHash), Error> {
+        let header = self.get_block_header(hash)?;
+        Ok((header.height(), *header.prev_hash()))
+    }
+
+    fn get_hash_by_height(&mut self, height: BlockHeight) -> Result<CryptoHash, Error> {
+        self.get_block_hash_by_height(height)
+    }
+}
+
+impl<'a> ValidityPeriodReads for ChainStoreSnapshot<'a> {
+    fn genesis_height(&self) -> BlockHeight {
+        self.genesis_height
+    }
+
+    fn get_height_and_prev_hash(
+        &mut self,
+        hash: &CryptoHash,
+    ) -> Result<(BlockHeight, CryptoHash), Error> {
+        let header: BlockHeader = option_to_not_found(
+            self.overlay.get_ser(DBCol::BlockHeader, hash.as_ref()),
+            &format!("BLOCK HEADER: {}", hash),
+        )?;
+        Ok((header.height(), *header.prev_hash()))
+    }
+
+    fn get_hash_by_height(&mut self, height: BlockHeight) -> Result<CryptoHash, Error> {
+        option_to_not_found(
+            self.overlay.get_ser(DBCol::BlockHeight, &index_to_bytes(height)),
+            &format!("BLOCK HEIGHT: {}", height),
+        )
+    }
+}
+
+fn check_transaction_validity_period(
+    reads: &mut impl ValidityPeriodReads,
+    prev_block_header: &BlockHeader,
+    base_block_hash: &CryptoHash,
+    validity_period: BlockHeight,
+) -> Result<(), InvalidTxError> {
+    // if both are on the canonical chain, comparing height is sufficient
+    // we special case this because it is expected that this scenario will happen in most cases.
+    let (base_height, _) =
+        reads.get_height_and_prev_hash(base_block_hash).map_err(|_| InvalidTxError::Expired)?;
+    let prev_height = prev_block_header.height();
+    if let Ok(base_block_hash_by_height) = reads.get_hash_by_height(base_height) {
+        if &base_block_hash_by_height == base_block_hash {
+            if let Ok(prev_hash) = reads.get_hash_by_height(prev_height) {
+                if &prev_hash == prev_block_header.hash() {
+                    if prev_height <= base_height + validity_period {
+                        return Ok(());
+                    } else {
+                        return Err(InvalidTxError::Expired);
                     }
                 }
             }
         }
+    }
 
-        // if the base block height is smaller than `last_final_height` we only need to check
-        // whether the base block is the same as the one with that height on the canonical fork.
-        // Otherwise we walk back the chain to check whether base block is on the same chain.
-        let last_final_height = self
-            .get_block_height(prev_block_header.last_final_block())
-            .map_err(|_| InvalidTxError::InvalidChain)?;
+    // if the base block height is smaller than `last_final_height` we only need to check
+    // whether the base block is the same as the one with that height on the canonical fork.
+    // Otherwise we walk back the chain to check whether base block is on the same chain.
+    let last_final_height = if prev_block_header.last_final_block() == &CryptoHash::default() {
+        reads.genesis_height()
+    } else {
+        reads
+            .get_height_and_prev_hash(prev_block_header.last_final_block())
+            .map_err(|_| InvalidTxError::InvalidChain)?
+            .0
+    };
 
-        if prev_height > base_height + validity_period {
-            Err(InvalidTxError::Expired)
-        } else if last_final_height >= base_height {
-            let base_block_hash_by_height = self
-                .get_block_hash_by_height(base_height)
-                .map_err(|_| InvalidTxError::InvalidChain)?;
-            if &base_block_hash_by_height == base_block_hash {
-                if prev_height <= base_height + validity_period {
-                    Ok(())
-                } else {
-                    Err(InvalidTxError::Expired)
-                }
-            } else {
-                Err(InvalidTxError::InvalidChain)
-            }
-        } else {
-            let header = self
-                .get_header_on_chain_by_height(prev_block_header.hash(), base_height)
-                .map_err(|_| InvalidTxError::InvalidChain)?;
-            if header.hash() == base_block_hash {
+    if prev_height > base_height + validity_period {
+        Err(InvalidTxError::Expired)
+    } else if last_final_height >= base_height {
+        let base_block_hash_by_height =
+            reads.get_hash_by_height(base_height).map_err(|_| InvalidTxError::InvalidChain)?;
+        if &base_block_hash_by_height == base_block_hash {
+            if prev_height <= base_height + validity_period {
                 Ok(())
             } else {
-                Err(InvalidTxError::InvalidChain)
+                Err(InvalidTxError::Expired)
             }
+        } else {
+            Err(InvalidTxError::InvalidChain)
+        }
+    } else {
+        // Walk back from the previous block to the base block height.
+        let mut hash = *prev_block_header.hash();
+        let (mut height, mut prev_hash) =
+            reads.get_height_and_prev_hash(&hash).map_err(|_| InvalidTxError::InvalidChain)?;
+        while height > base_height {
+            hash = prev_hash;
+            let (header_height, header_prev_hash) =
+                reads.get_height_and_prev_hash(&hash).map_err(|_| InvalidTxError::InvalidChain)?;
+            height = header_height;
+            prev_hash = header_prev_hash;
+        }
+        if height == base_height && &hash == base_block_hash {
+            Ok(())
+        } else {
+            Err(InvalidTxError::InvalidChain)
         }
     }
 }
@@ -563,11 +666,11 @@ impl ChainStore {
             .unwrap_or_default())
     }
 
-    /// Returns a hashmap of epoch id -> set of all blocks got for current (height, epoch_id)
+    /// Returns all blocks got for the height, grouped by epoch id.
     pub fn get_all_block_hashes_by_height(
         &mut self,
         height: BlockHeight,
-    ) -> Result<&HashMap<EpochId, HashSet<CryptoHash>>, Error> {
+    ) -> Result<&BlocksPerHeight, Error> {
         option_to_not_found(
             read_with_cache(
                 &self.store,
@@ -677,11 +780,14 @@ impl ChainStore {
     /// account data and the access keys are internally-serialized and we have to deserialize those
     /// values appropriately. Code and data changes are simple blobs of data, so we return them as
     /// base64-encoded blobs.
+    ///
+    /// Only `StateChangesRequest::AccountPrefix` requests are paginated, other requests always
+    /// return a single page.
     pub fn get_state_changes(
         &self,
         block_hash: &CryptoHash,
         state_changes_request: &StateChangesRequest,
-    ) -> Result<StateChanges, Error> {
+    ) -> Result<StateChangesPage, Error> {
         // We store the trie changes under a compound key: `block_hash + trie_key`, so when we
         // query the changes, we reverse the process by splitting the key using simple slicing of an
         // array of bytes, essentially, extracting `trie_key`.
@@ -703,7 +809,7 @@ impl ChainStore {
         //         left working with a key that was used in the trie.
         //    2.2. Parse the trie key with a relevant KeyFor* implementation to ensure consistency
 
-        Ok(match state_changes_request {
+        let changes = match state_changes_request {
             StateChangesRequest::AccountChanges { account_ids } => {
                 let mut changes = StateChanges::new();
                 for account_id in account_ids {
@@ -760,7 +866,69 @@ impl ChainStore {
                 }
                 changes
             }
-        })
+            StateChangesRequest::AccountPrefix { account_id_prefix, pagination } => {
+                return self.get_state_changes_by_account_prefix(
+                    block_hash,
+                    account_id_prefix,
+                    pagination.as_ref(),
+                );
+            }
+        };
+        Ok(StateChangesPage { changes, next_from_key: None })
+    }
+
+    /// Changes are stored ordered by trie key, and the keys of each column start with the account
+    /// id, so the changes of accounts with a common prefix are a contiguous range per column.
+    /// Within a page, only those ranges are read and reading stops at the limit.
+    ///
+    /// An empty prefix would read the changes of the whole block, so it's rejected, as is a zero
+    /// limit which would never make progress.
+    fn get_state_changes_by_account_prefix(
+        &self,
+        block_hash: &CryptoHash,
+        account_id_prefix: &str,
+        pagination: Option<&StateChangesPagination>,
+    ) -> Result<StateChangesPage, Error> {
+        if account_id_prefix.is_empty() {
+            return Err(Error::InvalidStateRequest("account id prefix must not be empty".into()));
+        }
+        if pagination.map_or(false, |pagination| pagination.limit == 0) {
+            return Err(Error::InvalidStateRequest("page limit must be positive".into()));
+        }
+        // A prefix with characters which can't appear in account ids matches no account, but
+        // could match keys past the account id, e.g. the data keys after the `,` separator.
+        let is_account_id_char =
+            |c: u8| c.is_ascii_lowercase() || c.is_ascii_digit() || b"-_.".contains(&c);
+        if !account_id_prefix.bytes().all(is_account_id_char) {
+            return Ok(StateChangesPage { changes: vec![], next_from_key: None });
+        }
+        let limit = pagination.map_or(usize::MAX, |pagination| pagination.limit);
+        let from_key = pagination.and_then(|pagination| pagination.from_key.as_deref());
+        let mut raw_changes = vec![];
+        for raw_prefix in
+            trie_key_parsers::get_raw_prefixes_for_account_id_prefix(account_id_prefix)
+        {
+            // The prefixes are in key order, skip the columns before the cursor.
+            if from_key.and_then(|from_key| from_key.first()) > raw_prefix.first() {
+                continue;
+            }
+            let storage_key = KeyForStateChanges::from_raw_key(block_hash, &raw_prefix);
+            let block_prefix_len = storage_key.as_ref().len() - raw_prefix.len();
+            let changes_per_key_prefix = self
+                .store
+                .iter_prefix(DBCol::StateChanges, storage_key.as_ref())
+                .skip_while(|(key, _)| Some(&key[block_prefix_len..]) < from_key);
+            for (key, value) in changes_per_key_prefix {
+                if raw_changes.len() == limit {
+                    let next_from_key = Some(key[block_prefix_len..].to_vec());
+                    let changes = StateChanges::from_changes(raw_changes.into_iter().map(Ok))?;
+                    return Ok(StateChangesPage { changes, next_from_key });
+                }
+                raw_changes.push(RawStateChangesWithTrieKey::try_from_slice(&value)?);
+            }
+        }
+        let changes = StateChanges::from_changes(raw_changes.into_iter().map(Ok))?;
+        Ok(StateChangesPage { changes, next_from_key: None })
     }
 
     pub fn get_store_statistics(&self) -> Option<StoreStatistics> {
@@ -1124,7 +1292,7 @@ struct ChainStoreCacheUpdate {
     chunk_extras: HashMap<(CryptoHash, ShardUId), ChunkExtra>,
     chunks: HashMap<ChunkHash, ShardChunk>,
     partial_chunks: HashMap<ChunkHash, PartialEncodedChunk>,
-    block_hash_per_height: HashMap<BlockHeight, HashMap<EpochId, HashSet<CryptoHash>>>,
+    block_hash_per_height: HashMap<BlockHeight, BlocksPerHeight>,
     chunk_hash_per_height_shard: HashMap<(BlockHeight, ShardId), ChunkHash>,
     height_to_hashes: HashMap<BlockHeight, Option<CryptoHash>>,
     next_block_hashes: HashMap<CryptoHash, CryptoHash>,
@@ -2209,7 +2377,7 @@ impl<'a> ChainStoreUpdate<'a> {
         self.gc_col(DBCol::StateDlInfos, &block_hash_vec);
 
         // 4. Update or delete block_hash_per_height
-        self.gc_col_block_per_height(&block_hash, height, block.header().epoch_id())?;
+        self.gc_col_block_per_height(&block_hash, height)?;
 
         match gc_mode {
             GCMode::Fork(_) => {
@@ -2248,17 +2416,13 @@ impl<'a> ChainStoreUpdate<'a> {
         &mut self,
         block_hash: &CryptoHash,
         height: BlockHeight,
-        epoch_id: &EpochId,
     ) -> Result<(), Error> {
         let mut store_update = self.store().store_update();
-        let epoch_to_hashes_ref = self.chain_store.get_all_block_hashes_by_height(height)?;
-        let mut epoch_to_hashes = epoch_to_hashes_ref.clone();
-        let hashes = epoch_to_hashes.get_mut(epoch_id).ok_or_else(|| {
-            near_chain_primitives::Error::Other("current epoch id should exist".into())
-        })?;
-        hashes.remove(block_hash);
-        if hashes.is_empty() {
-            epoch_to_hashes.remove(epoch_id);
+        let mut epoch_to_hashes = self.chain_store.get_all_block_hashes_by_height(height)?.clone();
+        if !epoch_to_hashes.remove(block_hash) {
+            return Err(near_chain_primitives::Error::Other(
+                "block should exist at its height".into(),
+            ));
         }
         let key = index_to_bytes(height).to_vec();
         if epoch_to_hashes.is_empty() {
@@ -2518,13 +2682,7 @@ impl<'a> ChainStoreUpdate<'a> {
         let block = source_store.get_block(block_hash)?.clone();
         let header = block.header();
         let height = header.height();
-        let tip = Tip {
-            height,
-            last_block_hash: *block_hash,
-            prev_block_hash: *header.prev_hash(),
-            epoch_id: header.epoch_id().clone(),
-            next_epoch_id: header.next_epoch_id().clone(),
-        };
+        let tip = Tip::from_header(header);
         chain_store_update.head = Some(tip.clone());
         chain_store_update.tail = Some(height);
         chain_store_update.chunk_tail = Some(height);
@@ -2636,11 +2794,9 @@ impl<'a> ChainStoreUpdate<'a> {
             let mut map =
                 match self.chain_store.get_all_block_hashes_by_height(block.header().height()) {
                     Ok(m) => m.clone(),
-                    Err(_) => HashMap::new(),
+                    Err(_) => BlocksPerHeight::default(),
                 };
-            map.entry(block.header().epoch_id().clone())
-                .or_insert_with(|| HashSet::new())
-                .insert(*hash);
+            map.insert(block.header().epoch_id().clone(), *hash);
             store_update.set_ser(
                 DBCol::BlockPerHeight,
                 &index_to_bytes(block.header().height()),
@@ -3130,18 +3286,25 @@ mod tests {
     use strum::IntoEnumIterator;
 
     use near_chain_configs::{GCConfig, GenesisConfig};
-    use near_crypto::KeyType;
+    use near_chain_primitives::error::Error;
+    use near_crypto::{KeyType, PublicKey};
+    use near_primitives::account::{AccessKey, Account};
     use near_primitives::block::{Block, Tip};
     use near_primitives::epoch_manager::block_info::BlockInfo;
     use near_primitives::errors::InvalidTxError;
-    use near_primitives::hash::hash;
-    use near_primitives::types::{BlockHeight, EpochId, GCCount, NumBlocks};
+    use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::{
+        AccountId, BlockHeight, EpochId, GCCount, NumBlocks, RawStateChange,
+        RawStateChangesWithTrieKey, StateChangeCause, StateChangeValue, StateChangesPagination,
+        StateChangesRequest,
+    };
     use near_primitives::utils::index_to_bytes;
     use near_primitives::validator_signer::InMemoryValidatorSigner;
     use near_store::test_utils::create_test_store;
-    use near_store::DBCol;
+    use near_store::{DBCol, KeyForStateChanges};
 
-    use crate::store::{ChainStoreAccess, GCMode};
+    use crate::store::{ChainStore, ChainStoreAccess, GCMode};
     use crate::store_validator::StoreValidator;
     use crate::test_utils::KeyValueRuntime;
     use crate::{Chain, ChainGenesis, DoomslugThresholdMode, RuntimeAdapter};
@@ -3333,6 +3496,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tx_validity_snapshot() {
+        let transaction_validity_period = 5;
+        let mut chain = get_chain();
+        let genesis = chain.get_block_by_height(0).unwrap().clone();
+        let signer = Arc::new(InMemoryValidatorSigner::from_seed(
+            "test1".parse().unwrap(),
+            KeyType::ED25519,
+            "test1",
+        ));
+        let mut blocks = vec![];
+        let mut prev_block = genesis;
+        for i in 1..(transaction_validity_period + 2) {
+            let mut store_update = chain.mut_store().store_update();
+            let block = Block::empty_with_height(&prev_block, i, &*signer.clone());
+            prev_block = block.clone();
+            store_update.save_block_header(block.header().clone()).unwrap();
+            store_update
+                .update_height_if_not_challenged(block.header().height(), *block.hash())
+                .unwrap();
+            blocks.push(block);
+            store_update.commit().unwrap();
+        }
+        let chain_store = ChainStore::new(
+            chain.store().store().clone(),
+            chain.store().get_genesis_height(),
+            true,
+        );
+        let mut snapshot = chain_store.snapshot();
+        let cur_header = blocks.last().unwrap().header();
+        for (base_block, expected) in
+            [(&blocks[1], Ok(())), (&blocks[0], Err(InvalidTxError::Expired))]
+        {
+            assert_eq!(
+                snapshot.check_transaction_validity_period(
+                    cur_header,
+                    base_block.hash(),
+                    transaction_validity_period
+                ),
+                expected
+            );
+            assert_eq!(
+                chain.mut_store().check_transaction_validity_period(
+                    cur_header,
+                    base_block.hash(),
+                    transaction_validity_period
+                ),
+                expected
+            );
+        }
+
+        // Blocks saved after the snapshot was taken aren't visible through it.
+        let new_block = Block::empty_with_height(
+            blocks.last().unwrap(),
+            transaction_validity_period + 2,
+            &*signer,
+        );
+        let mut store_update = chain.mut_store().store_update();
+        store_update.save_block_header(new_block.header().clone()).unwrap();
+        store_update
+            .update_height_if_not_challenged(new_block.header().height(), *new_block.hash())
+            .unwrap();
+        store_update.commit().unwrap();
+        assert!(chain
+            .mut_store()
+            .check_transaction_validity_period(
+                new_block.header(),
+                new_block.hash(),
+                transaction_validity_period
+            )
+            .is_ok());
+        assert_eq!(
+            snapshot.check_transaction_validity_period(
+                new_block.header(),
+                new_block.hash(),
+                transaction_validity_period
+            ),
+            Err(InvalidTxError::Expired)
+        );
+    }
+
     #[test]
     fn test_cache_invalidation() {
         let mut chain = get_chain();
@@ -3548,8 +3792,7 @@ mod tests {
                 .mut_store()
                 .get_all_block_hashes_by_height(5)
                 .unwrap()
-                .values()
-                .flatten()
+                .all_hashes()
                 .collect::<Vec<_>>(),
             vec![blocks[5].hash()]
         );
@@ -3727,4 +3970,115 @@ mod tests {
             assert_eq!(store_update.chunk_tail().unwrap(), 0);
         }
     }
+
+    #[test]
+    fn test_get_state_changes_by_account_prefix() {
+        let chain_store = ChainStore::new(create_test_store(), 0, true);
+        let block_hash = hash(b"block");
+        let other_block_hash = hash(b"other block");
+        let mut account_ids: Vec<AccountId> = ["abridge", "bridg", "bridge", "bridge.near", "cc"]
+            .iter()
+            .map(|account_id| account_id.parse().unwrap())
+            .collect();
+        account_ids.extend((0..20).map(|i| format!("{}.bridge", i).parse().unwrap()));
+        account_ids.extend((0..20).map(|i| format!("bridge{}", i).parse().unwrap()));
+        let mut store_update = chain_store.store().store_update();
+        for (i, account_id) in account_ids.iter().enumerate() {
+            let public_key = PublicKey::from_seed(KeyType::ED25519, account_id.as_ref());
+            let trie_keys_and_values = [
+                (
+                    TrieKey::Account { account_id: account_id.clone() },
+                    Account::new(i as u128, 0, CryptoHash::default(), 100).try_to_vec().unwrap(),
+                ),
+                (TrieKey::ContractCode { account_id: account_id.clone() }, vec![i as u8]),
+                (
+                    TrieKey::AccessKey { account_id: account_id.clone(), public_key },
+                    AccessKey::full_access().try_to_vec().unwrap(),
+                ),
+                (
+                    TrieKey::ContractData { account_id: account_id.clone(), key: b"a".to_vec() },
+                    vec![1],
+                ),
+                (
+                    TrieKey::ContractData { account_id: account_id.clone(), key: b"b".to_vec() },
+                    vec![2],
+                ),
+            ];
+            for (trie_key, value) in trie_keys_and_values {
+                let changes = vec![
+                    RawStateChange { cause: StateChangeCause::InitialState, data: None },
+                    RawStateChange { cause: StateChangeCause::InitialState, data: Some(value) },
+                ];
+                let changes = RawStateChangesWithTrieKey { trie_key, changes };
+                for block_hash in [&block_hash, &other_block_hash] {
+                    let key = KeyForStateChanges::from_trie_key(block_hash, &changes.trie_key);
+                    store_update.set_ser(DBCol::StateChanges, key.as_ref(), &changes).unwrap();
+                }
+            }
+        }
+        store_update.commit().unwrap();
+
+        let get_page = |account_id_prefix: &str, pagination: Option<StateChangesPagination>| {
+            let request = StateChangesRequest::AccountPrefix {
+                account_id_prefix: account_id_prefix.to_string(),
+                pagination,
+            };
+            chain_store.get_state_changes(&block_hash, &request).unwrap()
+        };
+
+        let all = get_page("bridge", None);
+        assert_eq!(all.next_from_key, None);
+        // 22 accounts with 5 changed trie keys, two changes each.
+        assert_eq!(all.changes.len(), 22 * 5 * 2);
+        let mut matched: Vec<_> =
+            all.changes.iter().map(|change| change.value.affected_account_id().clone()).collect();
+        matched.dedup();
+        assert!(matched.iter().all(|account_id| account_id.as_ref().starts_with("bridge")));
+        let mut expected: Vec<_> = account_ids
+            .iter()
+            .filter(|account_id| account_id.as_ref().starts_with("bridge"))
+            .cloned()
+            .collect();
+        expected.sort();
+        // Account changes come first, ordered by account id.
+        assert_eq!(&matched[..expected.len()], expected.as_slice());
+        assert!(matches!(all.changes[0].value, StateChangeValue::AccountDeletion { .. }));
+        assert!(matches!(all.changes[1].value, StateChangeValue::AccountUpdate { .. }));
+        assert_eq!(get_page("bridge.near", None).changes.len(), 5 * 2);
+        assert_eq!(get_page("d", None).changes.len(), 0);
+        assert_eq!(get_page("bridge,", None).changes.len(), 0);
+        for pagination in [None, Some(StateChangesPagination { from_key: None, limit: 1 })] {
+            let request = StateChangesRequest::AccountPrefix {
+                account_id_prefix: "".to_string(),
+                pagination,
+            };
+            assert!(matches!(
+                chain_store.get_state_changes(&block_hash, &request),
+                Err(Error::InvalidStateRequest(_))
+            ));
+        }
+        let request = StateChangesRequest::AccountPrefix {
+            account_id_prefix: "bridge".to_string(),
+            pagination: Some(StateChangesPagination { from_key: None, limit: 0 }),
+        };
+        assert!(matches!(
+            chain_store.get_state_changes(&block_hash, &request),
+            Err(Error::InvalidStateRequest(_))
+        ));
+
+        for limit in [1, 3, 7, 110, 1000] {
+            let mut paged = vec![];
+            let mut from_key = None;
+            loop {
+                let page = get_page("bridge", Some(StateChangesPagination { from_key, limit }));
+                assert!(page.changes.len() <= limit * 2);
+                paged.extend(page.changes);
+                from_key = page.next_from_key;
+                if from_key.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(format!("{:?}", paged), format!("{:?}", all.changes), "limit {}", limit);
+        }
+    }
 }
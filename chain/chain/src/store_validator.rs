@@ -239,7 +239,8 @@ impl StoreValidator {
                     );
                 }
                 DBCol::OutcomeIds => {
-                    let (block_hash, _) = get_block_shard_id_rev(key_ref)?;
+                    let (block_hash, _) = get_block_shard_id_rev(key_ref)
+                        .map_err(|err| StoreValidatorError::DBCorruption(err.into()))?;
                     let outcome_ids = Vec::<CryptoHash>::try_from_slice(value_ref)?;
                     // TransactionResult which can be indexed by Outcome id exists
                     self.check(
@@ -1,10 +1,10 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 use borsh::BorshSerialize;
 use strum::EnumCount;
 use thiserror::Error;
 
-use near_primitives::block::{Block, BlockHeader, Tip};
+use near_primitives::block::{Block, BlockHeader, BlocksPerHeight, Tip};
 use near_primitives::epoch_manager::block_info::BlockInfo;
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::hash::CryptoHash;
@@ -220,18 +220,11 @@ pub(crate) fn block_indexed_by_height(
     block: &Block,
 ) -> Result<(), StoreValidatorError> {
     let height = block.header().height();
-    let block_hashes: HashSet<CryptoHash> = unwrap_or_err_db!(
-        sv.store.get_ser::<HashMap<EpochId, HashSet<CryptoHash>>>(
-            DBCol::BlockPerHeight,
-            &index_to_bytes(height)
-        ),
-        "Can't get HashMap for Height {:?} from DBCol::BlockPerHeight",
+    let block_hashes = unwrap_or_err_db!(
+        sv.store.get_ser::<BlocksPerHeight>(DBCol::BlockPerHeight, &index_to_bytes(height)),
+        "Can't get BlocksPerHeight for Height {:?} from DBCol::BlockPerHeight",
         height
-    )
-    .values()
-    .flatten()
-    .cloned()
-    .collect();
+    );
     if !block_hashes.contains(block_hash) {
         err!("Block {:?} is not found in DBCol::BlockPerHeight", block);
     }
@@ -769,6 +769,7 @@ impl RuntimeAdapter for KeyValueRuntime {
                             output_data_receivers: vec![],
                             input_data_ids: vec![],
                             actions: vec![Action::Transfer(TransferAction { deposit: amount })],
+                            congestion: None,
                         }),
                     };
                     let receipt_hash = receipt.get_hash();
@@ -9,19 +9,15 @@ use near_primitives::challenge::{
 };
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::merklize;
-use near_primitives::sharding::{
-    ShardChunk, ShardChunkHeader, ShardChunkHeaderV1, ShardChunkHeaderV2, ShardChunkHeaderV3,
-};
+use near_primitives::sharding::{ShardChunk, ShardChunkHeader, ShardChunkHeaderV1};
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{AccountId, BlockHeight, EpochId, Nonce};
+use near_primitives::validation::gas_limit_bounds;
 
 use crate::{byzantine_assert, Chain};
 use crate::{ChainStore, Error, RuntimeAdapter};
 
-/// Gas limit cannot be adjusted for more than 0.1% at a time.
-const GAS_LIMIT_ADJUSTMENT_FACTOR: u64 = 1000;
-
 /// Verifies that chunk's proofs in the header match the body.
 pub fn validate_chunk_proofs(
     chunk: &ShardChunk,
@@ -29,11 +25,7 @@ pub fn validate_chunk_proofs(
 ) -> Result<bool, Error> {
     let correct_chunk_hash = match chunk {
         ShardChunk::V1(chunk) => ShardChunkHeaderV1::compute_hash(&chunk.header.inner),
-        ShardChunk::V2(chunk) => match &chunk.header {
-            ShardChunkHeader::V1(header) => ShardChunkHeaderV1::compute_hash(&header.inner),
-            ShardChunkHeader::V2(header) => ShardChunkHeaderV2::compute_hash(&header.inner),
-            ShardChunkHeader::V3(header) => ShardChunkHeaderV3::compute_hash(&header.inner),
-        },
+        ShardChunk::V2(chunk) => chunk.header.compute_hash(),
     };
 
     let header_hash = match chunk {
@@ -170,10 +162,8 @@ pub fn validate_chunk_with_chunk_extra(
         return Err(Error::InvalidReceiptsProof.into());
     }
 
-    let prev_gas_limit = prev_chunk_extra.gas_limit();
-    if chunk_header.gas_limit() < prev_gas_limit - prev_gas_limit / GAS_LIMIT_ADJUSTMENT_FACTOR
-        || chunk_header.gas_limit() > prev_gas_limit + prev_gas_limit / GAS_LIMIT_ADJUSTMENT_FACTOR
-    {
+    let (min_gas_limit, max_gas_limit) = gas_limit_bounds(prev_chunk_extra.gas_limit());
+    if chunk_header.gas_limit() < min_gas_limit || chunk_header.gas_limit() > max_gas_limit {
         return Err(Error::InvalidGasLimit.into());
     }
 
@@ -96,8 +96,10 @@ use near_chain::{
 use near_network::types::{
     NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest, WrappedInstant,
 };
+use near_pool::types::PoolOrder;
 use near_pool::{PoolIteratorWrapper, TransactionPool};
 use near_primitives::block::Tip;
+use near_primitives::errors::ChunkValidationError;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::merkle::{merklize, verify_path, MerklePath};
 use near_primitives::receipt::Receipt;
@@ -113,6 +115,7 @@ use near_primitives::types::{
     AccountId, Balance, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash, ShardId, StateRoot,
 };
 use near_primitives::utils::MaybeValidated;
+use near_primitives::validation::validate_chunk_header_basic;
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::ProtocolVersion;
 use near_primitives::{checked_feature, unwrap_or_return};
@@ -517,8 +520,12 @@ impl ShardsManager {
         );
     }
 
-    pub fn get_pool_iterator(&mut self, shard_id: ShardId) -> Option<PoolIteratorWrapper<'_>> {
-        self.tx_pools.get_mut(&shard_id).map(|pool| pool.pool_iterator())
+    pub fn get_pool_iterator(
+        &mut self,
+        shard_id: ShardId,
+        order: PoolOrder,
+    ) -> Option<PoolIteratorWrapper<'_>> {
+        self.tx_pools.get_mut(&shard_id).map(|pool| pool.pool_iterator_with_order(order))
     }
 
     pub fn cares_about_shard_this_or_next_epoch(
@@ -1470,9 +1477,10 @@ impl ShardsManager {
     }
 
     /// Validate a chunk header
-    /// 1) check that the chunk header is signed by the correct chunk producer for the chunk at
+    /// 1) check that the chunk header passes `validate_chunk_header_basic`
+    /// 2) check that the chunk header is signed by the correct chunk producer for the chunk at
     ///    the height for the shard
-    /// 2) check that the chunk header is compatible with the current protocol version
+    /// 3) check that the chunk header is compatible with the current protocol version
     ///
     // Note that this function only does partial validation. Full validation is only possible
     // after the previous block of the chunk is processed. To be able to process partial encoded
@@ -1490,7 +1498,7 @@ impl ShardsManager {
         header: &ShardChunkHeader,
     ) -> Result<(), Error> {
         let chunk_hash = header.chunk_hash();
-        // 1.  check signature
+        // 2.  check signature
         // Ideally, validating the chunk header needs the previous block to be accepted already.
         // However, we want to be able to validate chunk header in advance so we can save
         // the corresponding parts and receipts before the previous block is processed
@@ -1527,6 +1535,28 @@ impl ShardsManager {
             }
         };
 
+        // 1. check the header on its own, before anything else is done with it. The gas limit
+        //    can only be checked against the previous chunk, which the chain does when the
+        //    chunk is included in a block.
+        let num_shards = self.runtime_adapter.num_shards(&epoch_id)?;
+        if let Err(err) = validate_chunk_header_basic(header, num_shards, (0, Gas::MAX)) {
+            debug!(target: "chunks", "Chunk header {:?} is invalid: {}", chunk_hash, err);
+            return match err {
+                // The number of shards depends on the epoch, see the comment about
+                // `epoch_id_confirmed` below.
+                ChunkValidationError::InvalidShardId { .. } if !epoch_id_confirmed => {
+                    Err(near_chain::Error::from(DBNotFoundErr(
+                        format!("block {:?}", header.prev_block_hash()).to_string(),
+                    ))
+                    .into())
+                }
+                _ => {
+                    byzantine_assert!(false);
+                    Err(Error::InvalidChunkHeader)
+                }
+            };
+        }
+
         match self.runtime_adapter.verify_chunk_header_signature(header, &epoch_id, &ancestor_hash)
         {
             Ok(false) => {
@@ -1549,7 +1579,7 @@ impl ShardsManager {
             }
         }
 
-        // 2. check protocol version
+        // 3. check protocol version
         let protocol_version = self.runtime_adapter.get_epoch_protocol_version(&epoch_id)?;
         if !header.version_range().contains(protocol_version) {
             return if epoch_id_confirmed {
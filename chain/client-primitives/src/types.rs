@@ -21,8 +21,8 @@ use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
     BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
     FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
-    QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView, StateChangesRequestView,
-    StateChangesView,
+    QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView, StateChangesPageView,
+    StateChangesRequestView, StateChangesView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 
@@ -584,7 +584,7 @@ impl From<near_chain_primitives::Error> for GetStateChangesError {
 }
 
 impl Message for GetStateChanges {
-    type Result = Result<StateChangesView, GetStateChangesError>;
+    type Result = Result<StateChangesPageView, GetStateChangesError>;
 }
 
 pub struct GetStateChangesInBlock {
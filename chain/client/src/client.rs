@@ -23,8 +23,10 @@ use near_chunks::{ProcessPartialEncodedChunkResult, ShardsManager};
 use near_network::types::{
     FullPeerInfo, NetworkClientResponses, NetworkRequests, PeerManagerAdapter,
 };
+use near_pool::types::PoolOrder;
 use near_primitives::block::{Approval, ApprovalInner, ApprovalMessage, Block, BlockHeader, Tip};
 use near_primitives::challenge::{Challenge, ChallengeBody};
+use near_primitives::checked_feature;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, MerklePath};
 use near_primitives::receipt::Receipt;
@@ -696,34 +698,42 @@ impl Client {
             runtime_adapter.get_epoch_id_from_prev_block(prev_block_header.hash())?;
         let protocol_version = runtime_adapter.get_epoch_protocol_version(&next_epoch_id)?;
 
-        let transactions = if let Some(mut iter) = shards_mgr.get_pool_iterator(shard_id) {
-            let transaction_validity_period = chain.transaction_validity_period;
-            runtime_adapter.prepare_transactions(
-                prev_block_header.gas_price(),
-                chunk_extra.gas_limit(),
-                &next_epoch_id,
-                shard_id,
-                *chunk_extra.state_root(),
-                // while the height of the next block that includes the chunk might not be prev_height + 1,
-                // passing it will result in a more conservative check and will not accidentally allow
-                // invalid transactions to be included.
-                prev_block_header.height() + 1,
-                &mut iter,
-                &mut |tx: &SignedTransaction| -> bool {
-                    chain
-                        .mut_store()
-                        .check_transaction_validity_period(
-                            prev_block_header,
-                            &tx.transaction.block_hash,
-                            transaction_validity_period,
-                        )
-                        .is_ok()
-                },
-                protocol_version,
-            )?
-        } else {
-            vec![]
-        };
+        let pool_order =
+            if checked_feature!("protocol_feature_priority_fee", PriorityFee, protocol_version) {
+                PoolOrder::PriorityFee
+            } else {
+                PoolOrder::RoundRobin
+            };
+        let transactions =
+            if let Some(mut iter) = shards_mgr.get_pool_iterator(shard_id, pool_order) {
+                let transaction_validity_period = chain.transaction_validity_period;
+                // All transactions are checked against the same view of the chain.
+                let mut chain_snapshot = chain.store().snapshot();
+                runtime_adapter.prepare_transactions(
+                    prev_block_header.gas_price(),
+                    chunk_extra.gas_limit(),
+                    &next_epoch_id,
+                    shard_id,
+                    *chunk_extra.state_root(),
+                    // while the height of the next block that includes the chunk might not be prev_height + 1,
+                    // passing it will result in a more conservative check and will not accidentally allow
+                    // invalid transactions to be included.
+                    prev_block_header.height() + 1,
+                    &mut iter,
+                    &mut |tx: &SignedTransaction| -> bool {
+                        chain_snapshot
+                            .check_transaction_validity_period(
+                                prev_block_header,
+                                &tx.transaction.block_hash,
+                                transaction_validity_period,
+                            )
+                            .is_ok()
+                    },
+                    protocol_version,
+                )?
+            } else {
+                vec![]
+            };
         // Reintroduce valid transactions back to the pool. They will be removed when the chunk is
         // included into the block.
         shards_mgr.reintroduce_transactions(shard_id, &transactions);
@@ -46,7 +46,7 @@ use near_primitives::types::BlockHeight;
 use near_primitives::unwrap_or_return;
 use near_primitives::utils::{from_timestamp, MaybeValidated};
 use near_primitives::validator_signer::ValidatorSigner;
-use near_primitives::version::PROTOCOL_VERSION;
+use near_primitives::version::{enabled_features, PROTOCOL_VERSION};
 use near_primitives::views::{
     DebugBlockStatus, DebugChunkStatus, DetailedDebugStatus, EpochInfoView, ValidatorInfo,
 };
@@ -909,6 +909,7 @@ impl Handler<Status> for ClientActor {
                     .min_block_production_delay
                     .as_millis() as u64,
                 chunk_info: self.client.detailed_upcoming_blocks_info_as_web(),
+                protocol_features: enabled_features(protocol_version),
             })
         } else {
             None
@@ -1429,6 +1429,7 @@ mod test {
                     id: PeerId::new(PublicKey::empty(KeyType::ED25519)),
                     addr: None,
                     account_id: None,
+                    relay: None,
                 },
                 chain_info: Default::default(),
                 partial_edge_info: Default::default(),
@@ -1592,6 +1593,7 @@ mod test {
                     id: PeerId::new(PublicKey::empty(KeyType::ED25519)),
                     addr: None,
                     account_id: None,
+                    relay: None,
                 },
                 chain_info: Default::default(),
                 partial_edge_info: Default::default(),
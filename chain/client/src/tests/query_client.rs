@@ -67,7 +67,7 @@ fn query_status_not_crash() {
             InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
         actix::spawn(view_client.send(GetBlockWithMerkleTree::latest()).then(move |res| {
             let (block, mut block_merkle_tree) = res.unwrap().unwrap();
-            let header: BlockHeader = block.header.clone().into();
+            let header = BlockHeader::try_from(block.header.clone()).unwrap();
             block_merkle_tree.insert(*header.hash());
             let mut next_block = Block::produce(
                 PROTOCOL_VERSION,
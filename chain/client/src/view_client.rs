@@ -52,7 +52,7 @@ use near_primitives::views::{
     BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
     FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView,
     LightClientBlockView, QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView,
-    StateChangesView,
+    StateChangesPageView, StateChangesView,
 };
 
 use crate::{
@@ -736,7 +736,7 @@ impl Handler<GetStateChangesInBlock> for ViewClientActor {
 
 /// Returns a list of changes in a store for a given block filtering by the state changes request.
 impl Handler<GetStateChanges> for ViewClientActor {
-    type Result = Result<StateChangesView, GetStateChangesError>;
+    type Result = Result<StateChangesPageView, GetStateChangesError>;
 
     #[perf]
     fn handle(&mut self, msg: GetStateChanges, _: &mut Self::Context) -> Self::Result {
@@ -744,9 +744,7 @@ impl Handler<GetStateChanges> for ViewClientActor {
             .chain
             .store()
             .get_state_changes(&msg.block_hash, &msg.state_changes_request.into())?
-            .into_iter()
-            .map(Into::into)
-            .collect())
+            .into())
     }
 }
 
@@ -973,7 +971,7 @@ impl Handler<GetBlockProof> for ViewClientActor {
         self.chain.check_block_final_and_canonical(&msg.head_block_hash)?;
         let block_header_lite = self.chain.get_block_header(&msg.block_hash)?.clone().into();
         let block_proof = self.chain.get_block_proof(&msg.block_hash, &msg.head_block_hash)?;
-        Ok(GetBlockProofResponse { block_header_lite, proof: block_proof })
+        Ok(GetBlockProofResponse { block_header_lite, proof: block_proof.path })
     }
 }
 
@@ -6,6 +6,7 @@ use near_cache::SyncLruCache;
 use primitive_types::U256;
 use tracing::{debug, warn};
 
+use near_primitives::epoch_id::{next_epoch_height, next_epoch_id, next_next_epoch_id};
 use near_primitives::epoch_manager::block_info::BlockInfo;
 use near_primitives::epoch_manager::epoch_info::{EpochInfo, EpochSummary};
 use near_primitives::epoch_manager::{
@@ -127,7 +128,7 @@ impl EpochManager {
             epoch_info_aggregator_loop_counter: Default::default(),
             largest_final_height: 0,
         };
-        let genesis_epoch_id = EpochId::default();
+        let genesis_epoch_id = EpochId::genesis();
         if !epoch_manager.has_epoch_info(&genesis_epoch_id)? {
             // Missing genesis epoch, means that there is no validator initialize yet.
             let genesis_epoch_config =
@@ -504,18 +505,18 @@ impl EpochManager {
             Err(EpochError::ThresholdError { stake_sum, num_seats }) => {
                 warn!(target: "epoch_manager", "Not enough stake for required number of seats (all validators tried to unstake?): amount = {} for {}", stake_sum, num_seats);
                 let mut epoch_info = EpochInfo::clone(&next_epoch_info);
-                *epoch_info.epoch_height_mut() += 1;
+                *epoch_info.epoch_height_mut() = next_epoch_height(epoch_info.epoch_height());
                 epoch_info
             }
             Err(EpochError::NotEnoughValidators { num_validators, num_shards }) => {
                 warn!(target: "epoch_manager", "Not enough validators for required number of shards (all validators tried to unstake?): num_validators={} num_shards={}", num_validators, num_shards);
                 let mut epoch_info = EpochInfo::clone(&next_epoch_info);
-                *epoch_info.epoch_height_mut() += 1;
+                *epoch_info.epoch_height_mut() = next_epoch_height(epoch_info.epoch_height());
                 epoch_info
             }
             Err(err) => return Err(err),
         };
-        let next_next_epoch_id = EpochId(*last_block_hash);
+        let next_next_epoch_id = next_next_epoch_id(last_block_hash);
         debug!(target: "epoch_manager", "next next epoch height: {}, id: {:?}, protocol version: {} shard layout: {:?}",
                next_next_epoch_info.epoch_height(),
                &next_next_epoch_id,
@@ -539,12 +540,12 @@ impl EpochManager {
             if block_info.prev_hash() == &CryptoHash::default() {
                 // This is genesis block, we special case as new epoch.
                 assert_eq!(block_info.proposals_iter().len(), 0);
-                let pre_genesis_epoch_id = EpochId::default();
+                let pre_genesis_epoch_id = EpochId::genesis();
                 let genesis_epoch_info = self.get_epoch_info(&pre_genesis_epoch_id)?.clone();
                 self.save_block_info(&mut store_update, Arc::new(block_info))?;
                 self.save_epoch_info(
                     &mut store_update,
-                    &EpochId(current_hash),
+                    &next_epoch_id(&current_hash),
                     genesis_epoch_info,
                 )?;
             } else {
@@ -553,7 +554,7 @@ impl EpochManager {
                 let mut is_epoch_start = false;
                 if prev_block_info.prev_hash() == &CryptoHash::default() {
                     // This is first real block, starts the new epoch.
-                    *block_info.epoch_id_mut() = EpochId::default();
+                    *block_info.epoch_id_mut() = EpochId::genesis();
                     *block_info.epoch_first_block_mut() = current_hash;
                     is_epoch_start = true;
                 } else if self.is_next_block_in_next_epoch(&prev_block_info)? {
@@ -875,7 +876,7 @@ impl EpochManager {
     ) -> Result<EpochId, EpochError> {
         if self.is_next_block_epoch_start(parent_hash)? {
             // Because we ID epochs based on the last block of T - 2, this is ID for next next epoch.
-            Ok(EpochId(*parent_hash))
+            Ok(next_next_epoch_id(parent_hash))
         } else {
             self.get_next_epoch_id(parent_hash)
         }
@@ -903,7 +904,7 @@ impl EpochManager {
         (HashMap<AccountId, Balance>, HashMap<AccountId, Balance>, HashMap<AccountId, Balance>),
         EpochError,
     > {
-        let next_next_epoch_id = EpochId(*last_block_hash);
+        let next_next_epoch_id = next_next_epoch_id(last_block_hash);
         let validator_reward = self.get_epoch_info(&next_next_epoch_id)?.validator_reward().clone();
 
         let next_epoch_id = self.get_next_epoch_id(last_block_hash)?;
@@ -1057,7 +1058,7 @@ impl EpochManager {
                     .collect::<Result<Vec<CurrentEpochValidatorInfo>, EpochError>>()?;
                 (
                     cur_validators,
-                    EpochId(epoch_summary.prev_epoch_last_block_hash),
+                    next_epoch_id(&epoch_summary.prev_epoch_last_block_hash),
                     epoch_summary.all_proposals.into_iter().map(Into::into).collect(),
                 )
             }
@@ -1182,9 +1183,7 @@ impl EpochManager {
             let epoch_info = self.get_epoch_info(&next_epoch_id)?;
             (epoch_info.protocol_version(), epoch_info.seat_price())
         };
-        let config = self.config.for_protocol_version(protocol_version);
-        let stake_divisor = { config.minimum_stake_divisor as Balance };
-        Ok(seat_price / stake_divisor)
+        Ok(self.config.for_protocol_version(protocol_version).minimum_stake(seat_price))
     }
 }
 
@@ -1266,7 +1265,7 @@ impl EpochManager {
     /// Returns epoch id for the next epoch (T+1), given an block info in current epoch (T).
     fn get_next_epoch_id_from_info(&self, block_info: &BlockInfo) -> Result<EpochId, EpochError> {
         let first_block_info = self.get_block_info(block_info.epoch_first_block())?;
-        Ok(EpochId(*first_block_info.prev_hash()))
+        Ok(next_epoch_id(first_block_info.prev_hash()))
     }
 
     pub fn get_shard_config(&self, epoch_id: &EpochId) -> Result<ShardConfig, EpochError> {
@@ -78,6 +78,7 @@ mod old_validator_selection {
     use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
     use std::iter;
 
+    use near_primitives::epoch_id::next_epoch_height;
     use near_primitives::epoch_manager::epoch_info::EpochInfo;
     use near_primitives::epoch_manager::EpochConfig;
     use near_primitives::errors::EpochError;
@@ -253,7 +254,7 @@ mod old_validator_selection {
             .collect::<HashMap<_, _>>();
 
         Ok(EpochInfo::new(
-            prev_epoch_info.epoch_height() + 1,
+            next_epoch_height(prev_epoch_info.epoch_height()),
             final_proposals,
             validator_to_index,
             block_producers_settlement,
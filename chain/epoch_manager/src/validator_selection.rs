@@ -1,6 +1,7 @@
 #[cfg(feature = "protocol_feature_chunk_only_producers")]
 use crate::shard_assignment::assign_shards;
 use near_primitives::checked_feature;
+use near_primitives::epoch_id::next_epoch_height;
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::epoch_manager::{EpochConfig, RngSeed};
 use near_primitives::errors::EpochError;
@@ -191,7 +192,7 @@ pub fn proposals_to_epoch_info(
         .collect::<HashMap<_, _>>();
 
     Ok(EpochInfo::new(
-        prev_epoch_info.epoch_height() + 1,
+        next_epoch_height(prev_epoch_info.epoch_height()),
         all_validators,
         validator_to_index,
         block_producers_settlement,
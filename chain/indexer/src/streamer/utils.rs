@@ -39,6 +39,7 @@ pub(crate) async fn convert_transactions_sir_into_local_receipts(
                                 near_primitives::transaction::Action::try_from(action).unwrap()
                             })
                             .collect(),
+                        priority_fee: None,
                     },
                     prev_block_gas_price,
                     true,
@@ -59,6 +60,7 @@ pub(crate) async fn convert_transactions_sir_into_local_receipts(
                         output_data_receivers: vec![],
                         input_data_ids: vec![],
                         actions: tx.transaction.actions.clone(),
+                        congestion: None,
                     },
                 }
             })
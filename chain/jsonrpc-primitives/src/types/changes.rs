@@ -10,6 +10,14 @@ pub struct RpcStateChangesInBlockRequest {
 pub struct RpcStateChangesInBlockResponse {
     pub block_hash: near_primitives::hash::CryptoHash,
     pub changes: near_primitives::views::StateChangesView,
+    /// Set if the request was paginated and there are more changes.
+    #[serde(
+        rename = "next_from_key_base64",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "near_primitives::serialize::option_base64_format"
+    )]
+    pub next_from_key: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,7 +11,7 @@ use near_logger_utils::{init_integration_logger, init_test_logger};
 use near_network::test_utils::WaitOrTimeoutActor;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::serialize::{to_base, to_base64};
-use near_primitives::transaction::SignedTransaction;
+use near_primitives::transaction::{Action, CreateAccountAction, SignedTransaction};
 use near_primitives::types::BlockReference;
 use near_primitives::views::FinalExecutionStatus;
 
@@ -224,3 +224,30 @@ fn test_check_invalid_tx() {
         }
     });
 }
+
+/// Transactions exceeding the protocol limits are rejected by the RPC itself.
+#[test]
+fn test_send_tx_exceeding_limits() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let signer = InMemorySigner::from_seed("test1".parse().unwrap(), KeyType::ED25519, "test1");
+        let tx = SignedTransaction::from_actions(
+            1,
+            "test1".parse().unwrap(),
+            "test2".parse().unwrap(),
+            &signer,
+            vec![Action::CreateAccount(CreateAccountAction {}); 101],
+            hash(&[1]),
+        );
+        let bytes = tx.try_to_vec().unwrap();
+        match client.broadcast_tx_async(to_base64(&bytes)).await {
+            Err(e) => {
+                let s = serde_json::to_string(&e.data.unwrap()).unwrap();
+                assert_eq!(
+                    s,
+                    "{\"TxExecutionError\":{\"InvalidTxError\":{\"ActionsValidation\":{\"TotalNumberOfActionsExceeded\":{\"limit\":100,\"total_number_of_actions\":101}}}}}"
+                );
+            }
+            Ok(_) => panic!("transaction should not succeed"),
+        }
+    });
+}
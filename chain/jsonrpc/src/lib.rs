@@ -29,7 +29,8 @@ use near_network::types::{NetworkClientMessages, NetworkClientResponses};
 use near_primitives::hash::CryptoHash;
 use near_primitives::serialize::BaseEncode;
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, BlockReference};
+use near_primitives::validation::{validate_transaction_well_formed, TransactionLimits};
 use near_primitives::views::FinalExecutionOutcomeViewEnum;
 
 mod metrics;
@@ -396,7 +397,7 @@ impl JsonRpcHandler {
                     near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest::parse(
                         request.params,
                     )?;
-                let transaction_hash = self.send_tx_async(rpc_transaction_request).await;
+                let transaction_hash = self.send_tx_async(rpc_transaction_request).await?;
                 serde_json::to_value((&transaction_hash).to_base())
                     .map_err(|err| RpcError::serialization_error(err.to_string()))
             }
@@ -593,15 +594,40 @@ impl JsonRpcHandler {
     async fn send_tx_async(
         &self,
         request_data: near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest,
-    ) -> CryptoHash {
+    ) -> Result<CryptoHash, near_jsonrpc_primitives::types::transactions::RpcTransactionError> {
         let tx = request_data.signed_transaction;
+        self.validate_tx_well_formed(&tx).await?;
         let hash = tx.get_hash().clone();
         self.client_addr.do_send(NetworkClientMessages::Transaction {
             transaction: tx,
             is_forwarded: false,
             check_only: false, // if we set true here it will not actually send the transaction
         });
-        hash
+        Ok(hash)
+    }
+
+    /// Rejects the transaction if it exceeds the limits of the protocol version
+    /// of the current epoch, before it reaches the client.  Called once by
+    /// each of the methods sending a transaction.
+    async fn validate_tx_well_formed(
+        &self,
+        tx: &SignedTransaction,
+    ) -> Result<(), near_jsonrpc_primitives::types::transactions::RpcTransactionError> {
+        let config = self
+            .view_client_addr
+            .send(GetProtocolConfig(BlockReference::latest()))
+            .await?
+            .map_err(|err| {
+                near_jsonrpc_primitives::types::transactions::RpcTransactionError::InternalError {
+                    debug_info: err.to_string(),
+                }
+            })?;
+        let limits = TransactionLimits::from(&config.runtime_config.wasm_config.limit_config);
+        validate_transaction_well_formed(tx, &limits).map_err(|context| {
+            near_jsonrpc_primitives::types::transactions::RpcTransactionError::InvalidTransaction {
+                context,
+            }
+        })
     }
 
     async fn tx_exists(
@@ -785,6 +811,7 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::transactions::RpcBroadcastTxSyncResponse,
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
     > {
+        self.validate_tx_well_formed(&request_data.signed_transaction).await?;
         match self.send_tx(request_data.clone().signed_transaction, false).await? {
             NetworkClientResponses::ValidTx => {
                 Ok(near_jsonrpc_primitives::types::transactions::RpcBroadcastTxSyncResponse {
@@ -811,6 +838,7 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::transactions::RpcBroadcastTxSyncResponse,
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
     > {
+        self.validate_tx_well_formed(&request_data.signed_transaction).await?;
         match self.send_tx(request_data.clone().signed_transaction, true).await? {
             NetworkClientResponses::ValidTx => {
                 Ok(near_jsonrpc_primitives::types::transactions::RpcBroadcastTxSyncResponse {
@@ -836,6 +864,7 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
     > {
         let tx = request_data.signed_transaction;
+        self.validate_tx_well_formed(&tx).await?;
         match self
             .tx_status_fetch(
                 near_jsonrpc_primitives::types::transactions::TransactionInfo::Transaction(
@@ -1026,7 +1055,7 @@ impl JsonRpcHandler {
         let block = self.view_client_addr.send(GetBlock(request.block_reference.into())).await??;
 
         let block_hash = block.header.hash.clone();
-        let changes = self
+        let page = self
             .view_client_addr
             .send(GetStateChanges {
                 block_hash,
@@ -1036,7 +1065,8 @@ impl JsonRpcHandler {
 
         Ok(near_jsonrpc_primitives::types::changes::RpcStateChangesInBlockResponse {
             block_hash: block.header.hash,
-            changes,
+            changes: page.changes,
+            next_from_key: page.next_from_key,
         })
     }
 
@@ -65,6 +65,23 @@ pub struct NetworkConfig {
     pub outbound_disabled: bool,
     /// Not clear old data, set `true` for archive nodes.
     pub archive: bool,
+    /// Peers which relay messages to this node, for nodes which can't accept
+    /// inbound connections.  The node keeps connections to them open and
+    /// advertises itself as reachable through them.
+    pub relay_peers: Vec<PeerInfo>,
+    /// Maximum number of peers this node relays messages to.  Zero disables
+    /// relaying.
+    pub max_relayed_peers: u32,
+    /// Bytes per second of routed messages forwarded to each relayed peer.
+    pub relayed_peer_bandwidth_limit: u64,
+    /// Chain info of a peer older than this is considered stale: the peer is
+    /// asked for an update and isn't used for sync until it responds.
+    pub peer_chain_info_staleness: Duration,
+    /// Maximum number of routed messages remembered to route their responses
+    /// back.  A single peer can take at most a quarter of them.
+    pub route_back_cache_capacity: usize,
+    /// Responses arriving later than this after the request are dropped.
+    pub route_back_cache_ttl: Duration,
 }
 
 impl NetworkConfig {
@@ -101,6 +118,12 @@ impl NetworkConfig {
             blacklist: vec![],
             outbound_disabled: false,
             archive: false,
+            relay_peers: vec![],
+            max_relayed_peers: 0,
+            relayed_peer_bandwidth_limit: 10_000_000,
+            peer_chain_info_staleness: Duration::from_secs(60),
+            route_back_cache_capacity: 100_000,
+            route_back_cache_ttl: Duration::from_secs(120),
         }
     }
 
@@ -132,6 +155,33 @@ impl NetworkConfig {
             );
         }
 
+        if let Some(peer_info) = self.relay_peers.iter().find(|peer_info| peer_info.addr.is_none())
+        {
+            anyhow::bail!("relay peer {} has no address.", peer_info);
+        }
+
+        if self.max_relayed_peers > 0 && self.relayed_peer_bandwidth_limit == 0 {
+            anyhow::bail!(
+                "relayed_peer_bandwidth_limit must be positive when relaying is enabled."
+            );
+        }
+
+        if self.peer_chain_info_staleness < self.peer_stats_period {
+            anyhow::bail!(
+                "peer_chain_info_staleness({}ms) must not be shorter than peer_stats_period({}ms).",
+                self.peer_chain_info_staleness.as_millis(),
+                self.peer_stats_period.as_millis()
+            );
+        }
+
+        if self.route_back_cache_capacity == 0 {
+            anyhow::bail!("route_back_cache_capacity must be positive.");
+        }
+
+        if self.route_back_cache_ttl.is_zero() {
+            anyhow::bail!("route_back_cache_ttl must be positive.");
+        }
+
         if UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE * 2 > self.peer_recent_time_window {
             anyhow::bail!(
                 "Very short peer_recent_time_window({}). it should be at least twice update_interval_last_time_received_message({}).",
@@ -149,7 +199,8 @@ pub const UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE: Duration = Duration::from_
 
 #[cfg(test)]
 mod test {
-    use crate::types::{NetworkConfig, UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE};
+    use crate::types::{NetworkConfig, PeerInfo, UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE};
+    use std::time::Duration;
 
     #[test]
     fn test_network_config() {
@@ -175,5 +226,31 @@ mod test {
         nc.peer_recent_time_window = UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE;
         let res = nc.verify();
         assert!(res.is_err(), "{:?}", res);
+
+        let mut nc = NetworkConfig::from_seed("123", 213);
+        nc.relay_peers = vec![PeerInfo::random()];
+        let res = nc.verify();
+        assert!(res.is_err(), "{:?}", res);
+
+        let mut nc = NetworkConfig::from_seed("123", 213);
+        nc.max_relayed_peers = 1;
+        nc.relayed_peer_bandwidth_limit = 0;
+        let res = nc.verify();
+        assert!(res.is_err(), "{:?}", res);
+
+        let mut nc = NetworkConfig::from_seed("123", 213);
+        nc.peer_chain_info_staleness = nc.peer_stats_period / 2;
+        let res = nc.verify();
+        assert!(res.is_err(), "{:?}", res);
+
+        let mut nc = NetworkConfig::from_seed("123", 213);
+        nc.route_back_cache_capacity = 0;
+        let res = nc.verify();
+        assert!(res.is_err(), "{:?}", res);
+
+        let mut nc = NetworkConfig::from_seed("123", 213);
+        nc.route_back_cache_ttl = Duration::ZERO;
+        let res = nc.verify();
+        assert!(res.is_err(), "{:?}", res);
     }
 }
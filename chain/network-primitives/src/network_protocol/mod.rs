@@ -31,6 +31,11 @@ pub struct PeerInfo {
     pub id: PeerId,
     pub addr: Option<SocketAddr>,
     pub account_id: Option<AccountId>,
+    /// Peer which relays messages to this peer, for peers without a public
+    /// address.  Not part of the borsh encoding, old peers see such a peer
+    /// as one without an address.
+    #[borsh_skip]
+    pub relay: Option<PeerId>,
 }
 
 #[cfg(feature = "deepsize_feature")]
@@ -43,11 +48,11 @@ impl deepsize::DeepSizeOf for PeerInfo {
 impl PeerInfo {
     /// Creates random peer info.
     pub fn new(id: PeerId, addr: SocketAddr) -> Self {
-        PeerInfo { id, addr: Some(addr), account_id: None }
+        PeerInfo { id, addr: Some(addr), account_id: None, relay: None }
     }
 
     pub fn random() -> Self {
-        PeerInfo { id: PeerId::random(), addr: None, account_id: None }
+        PeerInfo { id: PeerId::random(), addr: None, account_id: None, relay: None }
     }
 
     pub fn addr_port(&self) -> Option<u16> {
@@ -96,7 +101,7 @@ impl FromStr for PeerInfo {
                 format!("Invalid PeerInfo format: {:?}", chunks),
             )));
         }
-        Ok(PeerInfo { id: PeerId::new(chunks[0].parse()?), addr, account_id })
+        Ok(PeerInfo { id: PeerId::new(chunks[0].parse()?), addr, account_id, relay: None })
     }
 }
 
@@ -158,6 +158,19 @@ pub struct KnownPeerState {
     pub status: KnownPeerStatus,
     pub first_seen: u64,
     pub last_seen: u64,
+    /// Set if the peer was a direct connection when the node last saved its
+    /// connections.
+    pub saved_connection: Option<SavedConnection>,
+}
+
+/// A direct connection of the node, saved so that after a restart the node
+/// reconnects to the validators it was connected to first.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SavedConnection {
+    /// Timestamp of the save.
+    pub saved_at: u64,
+    /// Whether the peer had announced the account of a validator.
+    pub validator: bool,
 }
 
 impl KnownPeerState {
@@ -167,6 +180,7 @@ impl KnownPeerState {
             status: KnownPeerStatus::Unknown,
             first_seen: to_timestamp(now),
             last_seen: to_timestamp(now),
+            saved_connection: None,
         }
     }
 
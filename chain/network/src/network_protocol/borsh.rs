@@ -13,7 +13,7 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::syncing::{EpochSyncFinalizationResponse, EpochSyncResponse};
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::EpochId;
+use near_primitives::types::{BlockHeight, EpochId, ShardId};
 use std::fmt;
 use std::fmt::Formatter;
 
@@ -101,6 +101,16 @@ impl RoutingTableUpdate {
     }
 }
 
+/// Response to `PeerMessage::ChainInfoRequest`: the parts of the peer's chain
+/// info which change while connected.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct ChainInfoUpdate {
+    pub(crate) height: BlockHeight,
+    pub(crate) tracked_shards: Vec<ShardId>,
+    pub(crate) archival: bool,
+}
+
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
 pub enum HandshakeFailureReason {
@@ -160,6 +170,10 @@ pub enum PeerMessage {
     EpochSyncFinalizationResponse(Box<EpochSyncFinalizationResponse>),
 
     RoutingTableSyncV2(RoutingSyncV2),
+
+    /// Asks the peer for its latest chain info, sent when ours is stale.
+    ChainInfoRequest,
+    ChainInfoResponse(ChainInfoUpdate),
 }
 #[cfg(target_arch = "x86_64")] // Non-x86_64 doesn't match this requirement yet but it's not bad as it's not production-ready
 const _: () = assert!(std::mem::size_of::<PeerMessage>() <= 1144, "PeerMessage > 1144 bytes");
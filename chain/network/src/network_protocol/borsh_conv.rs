@@ -13,6 +13,7 @@ impl From<&net::Handshake> for mem::Handshake {
             sender_listen_port: x.sender_listen_port,
             sender_chain_info: x.sender_chain_info.clone(),
             partial_edge_info: x.partial_edge_info.clone(),
+            relay_request: false,
         }
     }
 }
@@ -117,6 +118,8 @@ impl TryFrom<&net::PeerMessage> for mem::PeerMessage {
                 mem::PeerMessage::EpochSyncFinalizationResponse(esfr)
             }
             net::PeerMessage::RoutingTableSyncV2(rs) => mem::PeerMessage::RoutingTableSyncV2(rs),
+            net::PeerMessage::ChainInfoRequest => mem::PeerMessage::ChainInfoRequest,
+            net::PeerMessage::ChainInfoResponse(ciu) => mem::PeerMessage::ChainInfoResponse(ciu),
         })
     }
 }
@@ -155,6 +158,8 @@ impl From<&mem::PeerMessage> for net::PeerMessage {
                 net::PeerMessage::EpochSyncFinalizationResponse(esfr)
             }
             mem::PeerMessage::RoutingTableSyncV2(rs) => net::PeerMessage::RoutingTableSyncV2(rs),
+            mem::PeerMessage::ChainInfoRequest => net::PeerMessage::ChainInfoRequest,
+            mem::PeerMessage::ChainInfoResponse(ciu) => net::PeerMessage::ChainInfoResponse(ciu),
         }
     }
 }
@@ -26,7 +26,7 @@ use std::fmt;
 use thiserror::Error;
 
 pub use self::borsh::{
-    PartialSync, RoutingState, RoutingSyncV2, RoutingTableUpdate, RoutingVersion2,
+    ChainInfoUpdate, PartialSync, RoutingState, RoutingSyncV2, RoutingTableUpdate, RoutingVersion2,
 };
 
 /// Structure representing handshake between peers.
@@ -46,6 +46,9 @@ pub struct Handshake {
     pub(crate) sender_chain_info: PeerChainInfoV2,
     /// Represents new `edge`. Contains only `none` and `Signature` from the sender.
     pub(crate) partial_edge_info: PartialEdgeInfo,
+    /// Whether the sender asks the receiver to relay messages to it.
+    /// Only carried by the proto encoding.
+    pub(crate) relay_request: bool,
 }
 
 impl Handshake {
@@ -56,6 +59,7 @@ impl Handshake {
         listen_port: Option<u16>,
         chain_info: PeerChainInfoV2,
         partial_edge_info: PartialEdgeInfo,
+        relay_request: bool,
     ) -> Self {
         Handshake {
             protocol_version: version,
@@ -65,6 +69,7 @@ impl Handshake {
             sender_listen_port: listen_port,
             sender_chain_info: chain_info,
             partial_edge_info,
+            relay_request,
         }
     }
 }
@@ -109,6 +114,10 @@ pub enum PeerMessage {
     EpochSyncFinalizationResponse(Box<EpochSyncFinalizationResponse>),
 
     RoutingTableSyncV2(RoutingSyncV2),
+
+    /// Asks the peer for its latest chain info, sent when ours is stale.
+    ChainInfoRequest,
+    ChainInfoResponse(ChainInfoUpdate),
 }
 
 impl fmt::Display for PeerMessage {
@@ -2,7 +2,8 @@
 use crate::network_protocol::proto;
 use crate::network_protocol::proto::peer_message::Message_type as ProtoMT;
 use crate::network_protocol::{
-    Handshake, HandshakeFailureReason, PeerMessage, RoutingSyncV2, RoutingTableUpdate,
+    ChainInfoUpdate, Handshake, HandshakeFailureReason, PeerMessage, RoutingSyncV2,
+    RoutingTableUpdate,
 };
 use borsh::{BorshDeserialize as _, BorshSerialize as _};
 use near_network_primitives::types::{
@@ -50,6 +51,10 @@ fn try_from_required<'a, X, Y: TryFrom<&'a X>>(
     x.as_ref().ok_or(ParseRequiredError::Missing)?.try_into().map_err(ParseRequiredError::Other)
 }
 
+fn try_from_optional<'a, X, Y: TryFrom<&'a X>>(x: &'a MF<X>) -> Result<Option<Y>, Y::Error> {
+    x.as_ref().map(TryInto::try_into).transpose()
+}
+
 impl From<&CryptoHash> for proto::CryptoHash {
     fn from(x: &CryptoHash) -> Self {
         let mut y = Self::new();
@@ -161,16 +166,28 @@ impl TryFrom<&proto::PartialEdgeInfo> for PartialEdgeInfo {
 
 impl From<&PeerInfo> for proto::PeerInfo {
     fn from(x: &PeerInfo) -> Self {
-        Self { borsh: x.try_to_vec().unwrap(), ..Self::default() }
+        Self {
+            borsh: x.try_to_vec().unwrap(),
+            relay: MF::from_option(x.relay.as_ref().map(Into::into)),
+            ..Self::default()
+        }
     }
 }
 
-pub type ParsePeerInfoError = borsh::maybestd::io::Error;
+#[derive(Error, Debug)]
+pub enum ParsePeerInfoError {
+    #[error("borsh {0}")]
+    Borsh(borsh::maybestd::io::Error),
+    #[error("relay {0}")]
+    Relay(ParsePeerIdError),
+}
 
 impl TryFrom<&proto::PeerInfo> for PeerInfo {
     type Error = ParsePeerInfoError;
     fn try_from(x: &proto::PeerInfo) -> Result<Self, Self::Error> {
-        Self::try_from_slice(&x.borsh)
+        let mut peer_info = Self::try_from_slice(&x.borsh).map_err(Self::Error::Borsh)?;
+        peer_info.relay = try_from_optional(&x.relay).map_err(Self::Error::Relay)?;
+        Ok(peer_info)
     }
 }
 
@@ -186,6 +203,7 @@ impl From<&Handshake> for proto::Handshake {
             sender_listen_port: x.sender_listen_port.unwrap_or(0).into(),
             sender_chain_info: MF::some((&x.sender_chain_info).into()),
             partial_edge_info: MF::some((&x.partial_edge_info).into()),
+            relay_request: x.relay_request,
             ..Self::default()
         }
     }
@@ -228,6 +246,7 @@ impl TryFrom<&proto::Handshake> for Handshake {
                 .map_err(Self::Error::SenderChainInfo)?,
             partial_edge_info: try_from_required(&p.partial_edge_info)
                 .map_err(Self::Error::PartialEdgeInfo)?,
+            relay_request: p.relay_request,
         })
     }
 }
@@ -490,6 +509,17 @@ impl From<&PeerMessage> for proto::PeerMessage {
                         ..Default::default()
                     })
                 }
+                PeerMessage::ChainInfoRequest => {
+                    ProtoMT::ChainInfoRequest(proto::ChainInfoRequest::new())
+                }
+                PeerMessage::ChainInfoResponse(ciu) => {
+                    ProtoMT::ChainInfoResponse(proto::ChainInfoResponse {
+                        height: ciu.height,
+                        tracked_shards: ciu.tracked_shards.clone(),
+                        archival: ciu.archival,
+                        ..Default::default()
+                    })
+                }
             }),
             ..Default::default()
         }
@@ -620,6 +650,12 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
                 RoutingSyncV2::try_from_slice(&rts.borsh)
                     .map_err(Self::Error::RoutingTableSyncV2)?,
             ),
+            ProtoMT::ChainInfoRequest(_) => PeerMessage::ChainInfoRequest,
+            ProtoMT::ChainInfoResponse(cir) => PeerMessage::ChainInfoResponse(ChainInfoUpdate {
+                height: cir.height,
+                tracked_shards: cir.tracked_shards.clone(),
+                archival: cir.archival,
+            }),
         })
     }
 }
@@ -178,6 +178,7 @@ mod test {
                 archival: false,
             },
             partial_edge_info: PartialEdgeInfo::default(),
+            relay_request: false,
         };
         let msg = PeerMessage::Handshake(fake_handshake);
         test_codec(msg);
@@ -1,4 +1,4 @@
-use crate::network_protocol::{Encoding, ParsePeerMessageError};
+use crate::network_protocol::{ChainInfoUpdate, Encoding, ParsePeerMessageError};
 use crate::peer::codec::Codec;
 use crate::peer::tracker::Tracker;
 use crate::private_actix::{
@@ -35,7 +35,6 @@ use near_primitives::version::{
     ProtocolVersion, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 use near_rate_limiter::{ActixMessageWrapper, ThrottleController};
-use std::cmp::max;
 use std::fmt::Debug;
 use std::io;
 use std::net::SocketAddr;
@@ -59,6 +58,9 @@ const MAX_TRANSACTIONS_PER_BLOCK_MESSAGE: usize = 1000;
 const ROUTED_MESSAGE_CACHE_SIZE: usize = 1000;
 /// Duplicated messages will be dropped if routed through the same peer multiple times.
 const DROP_DUPLICATED_MESSAGES_PERIOD: Duration = Duration::from_millis(50);
+/// Chain info requests arriving sooner than this after the previous one are
+/// ignored.
+const CHAIN_INFO_REQUEST_MIN_INTERVAL: Duration = Duration::from_secs(10);
 
 pub(crate) struct PeerActor {
     /// This node's id and address (either listening or socket address).
@@ -93,6 +95,11 @@ pub(crate) struct PeerActor {
     genesis_id: GenesisId,
     /// Latest chain info from the peer.
     chain_info: PeerChainInfoV2,
+    /// When `chain_info` was last confirmed by the peer, by the handshake, a
+    /// chain info response or a block at least as high as `chain_info.height`.
+    chain_info_updated_at: Instant,
+    /// When the peer last asked for our chain info.
+    last_chain_info_request: Option<Instant>,
     /// Edge information needed to build the real edge. This is relevant for handshake.
     partial_edge_info: Option<PartialEdgeInfo>,
     /// Last time an update of received message was sent to PeerManager
@@ -111,6 +118,9 @@ pub(crate) struct PeerActor {
     /// Whether the PeerActor should skip protobuf support detection and use
     /// a given encoding right away.
     force_encoding: Option<Encoding>,
+    /// Whether this node asks the peer to relay messages to it, see
+    /// `NetworkConfig::relay_peers`.
+    relay_request: bool,
 }
 
 impl Debug for PeerActor {
@@ -146,6 +156,7 @@ impl PeerActor {
         peer_counter: Arc<AtomicUsize>,
         throttle_controller: ThrottleController,
         force_encoding: Option<Encoding>,
+        relay_request: bool,
     ) -> Self {
         PeerActor {
             my_node_info,
@@ -163,6 +174,8 @@ impl PeerActor {
             tracker: Default::default(),
             genesis_id: Default::default(),
             chain_info: Default::default(),
+            chain_info_updated_at: Clock::instant(),
+            last_chain_info_request: None,
             partial_edge_info,
             last_time_received_message_update: Clock::instant(),
             txns_since_last_block,
@@ -171,6 +184,7 @@ impl PeerActor {
             throttle_controller,
             protocol_buffers_supported: false,
             force_encoding,
+            relay_request,
         }
     }
 
@@ -285,9 +299,11 @@ impl PeerActor {
                             act.protocol_version,
                             act.my_node_id().clone(),
                             act.other_peer_id().unwrap().clone(),
-                            act.my_node_info.addr_port(),
+                            // A node asking to be relayed doesn't listen for connections.
+                            if act.relay_request { None } else { act.my_node_info.addr_port() },
                             PeerChainInfoV2 { genesis_id, height, tracked_shards, archival },
                             act.partial_edge_info.as_ref().unwrap().clone(),
+                            act.relay_request,
                         )),
                         _ => {
                             error!(target: "network", "Trying to talk with peer with no supported version: {}", act.protocol_version);
@@ -460,7 +476,10 @@ impl PeerActor {
             PeerMessage::Block(block) => {
                 let block_hash = *block.hash();
                 self.tracker.push_received(block_hash);
-                self.chain_info.height = max(self.chain_info.height, block.header().height());
+                if block.header().height() >= self.chain_info.height {
+                    self.chain_info.height = block.header().height();
+                    self.chain_info_updated_at = Clock::instant();
+                }
                 NetworkClientMessages::Block(block, peer_id, self.tracker.has_request(&block_hash))
             }
             PeerMessage::Transaction(transaction) => NetworkClientMessages::Transaction {
@@ -548,7 +567,9 @@ impl PeerActor {
             | PeerMessage::BlockHeadersRequest(_)
             | PeerMessage::EpochSyncRequest(_)
             | PeerMessage::EpochSyncFinalizationRequest(_)
-            | PeerMessage::RoutingTableSyncV2(_) => {
+            | PeerMessage::RoutingTableSyncV2(_)
+            | PeerMessage::ChainInfoRequest
+            | PeerMessage::ChainInfoResponse(_) => {
                 error!(target: "network", "Peer receive_client_message received unexpected type: {:?}", msg);
                 return;
             }
@@ -851,14 +872,23 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for PeerActor {
                     return;
                 }
 
+                // Only the dialing side may ask to be relayed. The peer proved
+                // its id by signing the edge, so it may be advertised as
+                // reachable through this node.
+                let relay_request = (self.peer_type == PeerType::Inbound
+                    && handshake.relay_request)
+                    .then(|| self.peer_addr);
                 let peer_info = PeerInfo {
                     id: handshake.sender_peer_id.clone(),
                     addr: handshake
                         .sender_listen_port
+                        .filter(|_| relay_request.is_none())
                         .map(|port| SocketAddr::new(self.peer_addr.ip(), port)),
                     account_id: None,
+                    relay: None,
                 };
                 self.chain_info = handshake.sender_chain_info.clone();
+                self.chain_info_updated_at = Clock::instant();
                 self.peer_manager_wrapper_addr
                     .send(ActixMessageWrapper::new_without_size(PeerManagerMessageRequest::RegisterPeer(RegisterPeer {
                         actor: ctx.address(),
@@ -869,6 +899,7 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for PeerActor {
                         other_edge_info: handshake.partial_edge_info.clone(),
                         peer_protocol_version: self.protocol_version,
                         throttle_controller: self.throttle_controller.clone(),
+                        relay_request,
                     }), Some(self.throttle_controller.clone())))
                     .into_actor(self)
                     .then(move |res, act, ctx| {
@@ -962,6 +993,41 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for PeerActor {
                         Some(self.throttle_controller.clone()),
                     ));
             }
+            (PeerStatus::Ready, PeerMessage::ChainInfoRequest) => {
+                let now = Clock::instant();
+                if self.last_chain_info_request.map_or(false, |last| {
+                    now.saturating_duration_since(last) < CHAIN_INFO_REQUEST_MIN_INTERVAL
+                }) {
+                    debug!(target: "network", "Ignoring too frequent chain info request from {}", self.peer_info);
+                    return;
+                }
+                self.last_chain_info_request = Some(now);
+                self.view_client_addr
+                    .send(NetworkViewClientMessages::GetChainInfo)
+                    .into_actor(self)
+                    .then(|res, act, _ctx| {
+                        if let Ok(NetworkViewClientResponses::ChainInfo {
+                            height,
+                            tracked_shards,
+                            archival,
+                            ..
+                        }) = res
+                        {
+                            act.send_message_or_log(&PeerMessage::ChainInfoResponse(
+                                ChainInfoUpdate { height, tracked_shards, archival },
+                            ));
+                        }
+                        actix::fut::ready(())
+                    })
+                    .spawn(ctx);
+            }
+            (PeerStatus::Ready, PeerMessage::ChainInfoResponse(update)) => {
+                debug!(target: "network", "Received chain info from {}: height {}", self.peer_info, update.height);
+                self.chain_info.height = update.height;
+                self.chain_info.tracked_shards = update.tracked_shards;
+                self.chain_info.archival = update.archival;
+                self.chain_info_updated_at = Clock::instant();
+            }
             (PeerStatus::Ready, PeerMessage::RequestUpdateNonce(edge_info)) => self
                 .peer_manager_addr
                 .send(PeerManagerMessageRequest::NetworkRequests(
@@ -1106,6 +1172,7 @@ impl Handler<QueryPeerStats> for PeerActor {
 
         PeerStatsResult {
             chain_info: self.chain_info.clone(),
+            chain_info_updated_at: self.chain_info_updated_at,
             received_bytes_per_sec: received.bytes_per_min / 60,
             sent_bytes_per_sec: sent.bytes_per_min / 60,
             is_abusive,
@@ -0,0 +1,145 @@
+use crate::types::FullPeerInfo;
+use std::time::{Duration, Instant};
+
+/// Tracks how old the chain info of a connected peer is.  Once it's older than
+/// `NetworkConfig::peer_chain_info_staleness` the peer is asked for an update,
+/// at most once per staleness period, and isn't used for sync until it
+/// responds.
+pub(crate) struct ChainInfoAge {
+    updated_at: Instant,
+    requested_at: Option<Instant>,
+}
+
+impl ChainInfoAge {
+    pub(crate) fn new(now: Instant) -> Self {
+        Self { updated_at: now, requested_at: None }
+    }
+
+    pub(crate) fn update(&mut self, updated_at: Instant) {
+        self.updated_at = self.updated_at.max(updated_at);
+    }
+
+    pub(crate) fn is_stale(&self, now: Instant, staleness: Duration) -> bool {
+        now.saturating_duration_since(self.updated_at) > staleness
+    }
+
+    /// Returns whether the peer should be asked for its chain info now, and
+    /// if so records the request.
+    pub(crate) fn should_request(&mut self, now: Instant, staleness: Duration) -> bool {
+        if !self.is_stale(now, staleness) {
+            return false;
+        }
+        if let Some(requested_at) = self.requested_at {
+            if requested_at > self.updated_at
+                && now.saturating_duration_since(requested_at) < staleness
+            {
+                return false;
+            }
+        }
+        self.requested_at = Some(now);
+        true
+    }
+}
+
+/// Returns the peers whose height is within `horizon` from the highest one,
+/// ignoring peers with stale chain info.  If all peers are stale, e.g. because
+/// they don't support chain info requests, they are all considered.
+pub(crate) fn highest_height_peers<'a>(
+    peers: impl Iterator<Item = (&'a FullPeerInfo, &'a ChainInfoAge)>,
+    horizon: u64,
+    now: Instant,
+    staleness: Duration,
+) -> Vec<FullPeerInfo> {
+    let (fresh, stale): (Vec<_>, Vec<_>) =
+        peers.partition(|(_, age)| !age.is_stale(now, staleness));
+    let peers = if fresh.is_empty() { stale } else { fresh };
+    let max_height = match peers.iter().map(|(info, _)| info.chain_info.height).max() {
+        Some(height) => height,
+        None => return vec![],
+    };
+    peers
+        .into_iter()
+        .filter(|(info, _)| info.chain_info.height.saturating_add(horizon) >= max_height)
+        .map(|(info, _)| info.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{highest_height_peers, ChainInfoAge};
+    use crate::types::FullPeerInfo;
+    use near_network_primitives::types::{PeerChainInfoV2, PeerInfo};
+    use std::time::{Duration, Instant};
+
+    fn peer(height: u64) -> FullPeerInfo {
+        FullPeerInfo {
+            peer_info: PeerInfo::random(),
+            chain_info: PeerChainInfoV2 { height, ..Default::default() },
+            partial_edge_info: Default::default(),
+        }
+    }
+
+    fn selected(
+        peers: &[(FullPeerInfo, ChainInfoAge)],
+        now: Instant,
+        staleness: Duration,
+    ) -> Vec<u64> {
+        let mut heights: Vec<_> =
+            highest_height_peers(peers.iter().map(|(info, age)| (info, age)), 5, now, staleness)
+                .iter()
+                .map(|info| info.chain_info.height)
+                .collect();
+        heights.sort();
+        heights
+    }
+
+    #[test]
+    fn test_stale_peer_drops_out_until_it_responds() {
+        let staleness = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut peers: Vec<_> =
+            [100, 102, 300].into_iter().map(|h| (peer(h), ChainInfoAge::new(start))).collect();
+        assert_eq!(selected(&peers, start, staleness), vec![300]);
+
+        // The highest peer stops updating its chain info, the others keep
+        // syncing.
+        let mut now = start;
+        for _ in 0..10 {
+            now += Duration::from_secs(10);
+            for (info, age) in &mut peers[..2] {
+                info.chain_info.height += 10;
+                age.update(now);
+            }
+        }
+        assert!(peers[2].1.is_stale(now, staleness));
+        assert_eq!(selected(&peers, now, staleness), vec![200, 202]);
+
+        // The stale peer is asked for an update once per staleness period.
+        assert!(peers[2].1.should_request(now, staleness));
+        assert!(!peers[2].1.should_request(now + Duration::from_secs(30), staleness));
+        assert!(!peers[0].1.should_request(now, staleness));
+
+        // It returns after responding.
+        now += Duration::from_secs(1);
+        peers[2].0.chain_info.height = 203;
+        peers[2].1.update(now);
+        assert_eq!(selected(&peers, now, staleness), vec![200, 202, 203]);
+        assert!(!peers[2].1.should_request(now, staleness));
+
+        // A peer which never responds is asked again after another period.
+        let later = now + staleness + Duration::from_secs(1);
+        assert!(peers[2].1.should_request(later, staleness));
+        assert!(!peers[2].1.should_request(later + staleness / 2, staleness));
+        assert!(peers[2].1.should_request(later + staleness, staleness));
+    }
+
+    #[test]
+    fn test_all_peers_stale() {
+        let staleness = Duration::from_secs(60);
+        let start = Instant::now();
+        let peers: Vec<_> =
+            [100, 110].into_iter().map(|h| (peer(h), ChainInfoAge::new(start))).collect();
+        let now = start + staleness * 2;
+        assert_eq!(selected(&peers, now, staleness), vec![110]);
+    }
+}
@@ -1,2 +1,4 @@
+pub(crate) mod chain_info;
 pub(crate) mod peer_manager_actor;
 pub(crate) mod peer_store;
+pub(crate) mod relay;
@@ -1,7 +1,9 @@
 use crate::network_protocol::Encoding;
 use crate::peer::codec::Codec;
 use crate::peer::peer_actor::PeerActor;
+use crate::peer_manager::chain_info::{self, ChainInfoAge};
 use crate::peer_manager::peer_store::PeerStore;
+use crate::peer_manager::relay::BandwidthLimiter;
 use crate::private_actix::{
     PeerRequestResult, PeersRequest, RegisterPeer, RegisterPeerResponse, SendMessage, StopMsg,
     Unregister, ValidateEdgeList,
@@ -10,7 +12,9 @@ use crate::routing::edge_validator_actor::EdgeValidatorHelper;
 use crate::routing::routing_table_actor::{
     Prune, RoutingTableActor, RoutingTableMessages, RoutingTableMessagesResponse,
 };
-use crate::routing::routing_table_view::{RoutingTableView, DELETE_PEERS_AFTER_TIME};
+use crate::routing::routing_table_view::{
+    FindRouteError, RoutingTableView, DELETE_PEERS_AFTER_TIME,
+};
 use crate::stats::metrics;
 use crate::stats::metrics::{NetworkMetrics, PARTIAL_ENCODED_CHUNK_REQUEST_DELAY};
 use crate::types::{
@@ -19,10 +23,11 @@ use crate::types::{
     PeersResponse, QueryPeerStats, RoutingTableUpdate,
 };
 use actix::{
-    Actor, ActorFutureExt, Addr, Arbiter, AsyncContext, Context, ContextFutureSpawner, Handler,
-    Recipient, Running, StreamHandler, WrapFuture,
+    Actor, ActorFuture, ActorFutureExt, Addr, Arbiter, AsyncContext, Context, ContextFutureSpawner,
+    Handler, Recipient, Running, StreamHandler, WrapFuture,
 };
 use anyhow::bail;
+use borsh::BorshSerialize;
 use futures::FutureExt;
 use near_network_primitives::types::{
     AccountOrPeerIdOrHash, Ban, Edge, InboundTcpConnect, KnownPeerStatus, KnownProducer,
@@ -85,6 +90,17 @@ const WAIT_FOR_SYNC_DELAY: Duration = Duration::from_millis(1_000);
 const UPDATE_ROUTING_TABLE_INTERVAL: Duration = Duration::from_millis(1_000);
 /// How often to report bandwidth stats.
 const REPORT_BANDWIDTH_STATS_TRIGGER_INTERVAL: Duration = Duration::from_millis(60_000);
+/// How often to save the direct connections even if they didn't change, so
+/// that the save of a long running node doesn't expire.
+const SAVE_CONNECTIONS_INTERVAL: Duration = Duration::from_millis(600_000);
+/// Saved connections older than that aren't dialed on startup, about three
+/// epochs on mainnet.
+const WARMUP_PEERS_EXPIRATION: Duration = Duration::from_millis(3 * 12 * 3_600_000);
+/// Maximum number of previously connected validators dialed on startup.
+const MAX_WARMUP_PEERS: usize = 40;
+/// How long general peer discovery waits for the previously connected
+/// validators on startup.
+const WARMUP_TIMEOUT: Duration = Duration::from_millis(10_000);
 
 /// Max number of messages we received from peer, and they are in progress, before we start throttling.
 /// Disabled for now (TODO PUT UNDER FEATURE FLAG)
@@ -117,6 +133,8 @@ struct ConnectedPeer {
     throttle_controller: ThrottleController,
     /// Encoding used for communication.
     encoding: Option<Encoding>,
+    /// How old `full_peer_info.chain_info` is.
+    chain_info_age: ChainInfoAge,
 }
 
 #[derive(Default)]
@@ -215,6 +233,17 @@ pub struct PeerManagerActor {
     /// Whitelisted nodes, which are allowed to connect even if the connection limit has been
     /// reached.
     whitelist_nodes: Vec<WhitelistNode>,
+    /// Connected peers this node relays messages to, with the budget of
+    /// routed messages which may be forwarded to them.
+    relayed_peers: HashMap<PeerId, BandwidthLimiter>,
+    /// Previously connected validators dialed on startup.  General peer
+    /// discovery waits until they are connected or `warmup_deadline` passes.
+    warmup_peers: Vec<PeerId>,
+    warmup_deadline: Instant,
+    /// Direct connections, with whether the peer is a validator, as of the
+    /// last save to the peer store.
+    saved_connections: HashMap<PeerId, bool>,
+    connections_saved_at: Instant,
 }
 
 impl Actor for PeerManagerActor {
@@ -248,6 +277,9 @@ impl Actor for PeerManagerActor {
             });
         }
 
+        // Dial the validators this node was connected to before the restart.
+        self.start_warmup(ctx);
+
         // Periodically push network information to client.
         self.push_network_info_trigger(ctx, self.config.push_info_period);
 
@@ -276,6 +308,8 @@ impl Actor for PeerManagerActor {
 
     /// Try to gracefully disconnect from connected peers.
     fn stopping(&mut self, _ctx: &mut Self::Context) -> Running {
+        self.save_connections();
+
         let msg = SendMessage { message: PeerMessage::Disconnect };
 
         for connected_peer in self.connected_peers.values() {
@@ -306,7 +340,11 @@ impl PeerManagerActor {
         debug!(target: "network", blacklist = ?config.blacklist, "Blacklist");
 
         let my_peer_id: PeerId = PeerId::new(config.public_key.clone());
-        let routing_table = RoutingTableView::new(store);
+        let routing_table = RoutingTableView::with_route_back_cache(
+            store,
+            config.route_back_cache_capacity,
+            config.route_back_cache_ttl,
+        );
 
         let txns_since_last_block = Arc::new(AtomicUsize::new(0));
 
@@ -336,6 +374,11 @@ impl PeerManagerActor {
             peer_counter: Arc::new(AtomicUsize::new(0)),
             adv_helper: AdvHelper::default(),
             whitelist_nodes,
+            relayed_peers: HashMap::new(),
+            warmup_peers: vec![],
+            warmup_deadline: Clock::instant(),
+            saved_connections: HashMap::new(),
+            connections_saved_at: Clock::instant(),
         })
     }
 
@@ -650,6 +693,7 @@ impl PeerManagerActor {
                 peer_type,
                 throttle_controller: throttle_controller.clone(),
                 encoding: None,
+                chain_info_age: ChainInfoAge::new(Clock::instant()),
             },
         );
 
@@ -774,6 +818,7 @@ impl PeerManagerActor {
         // If the last edge we have with this peer represent a connection addition, create the edge
         // update that represents the connection removal.
         self.connected_peers.remove(peer_id);
+        self.relayed_peers.remove(peer_id);
 
         #[cfg(feature = "protocol_feature_routing_exchange_algorithm")]
         self.routing_table_addr.do_send(RoutingTableMessages::RemovePeer(peer_id.clone()));
@@ -859,6 +904,9 @@ impl PeerManagerActor {
         let handshake_timeout = self.config.handshake_timeout;
         let client_addr = self.client_addr.clone();
         let view_client_addr = self.view_client_addr.clone();
+        // Without a public address this node is only reachable through its relays.
+        let relay_request = server_addr.is_none()
+            && peer_info.as_ref().map_or(false, |peer_info| self.is_relay_peer(&peer_info.id));
 
         let server_addr = match server_addr {
             Some(server_addr) => server_addr,
@@ -905,7 +953,7 @@ impl PeerManagerActor {
             );
 
             PeerActor::new(
-                PeerInfo { id: my_peer_id, addr: Some(server_addr), account_id },
+                PeerInfo { id: my_peer_id, addr: Some(server_addr), account_id, relay: None },
                 remote_addr,
                 peer_info,
                 peer_type,
@@ -920,6 +968,7 @@ impl PeerManagerActor {
                 peer_counter,
                 rate_limiter,
                 None,
+                relay_request,
             )
         });
     }
@@ -965,25 +1014,96 @@ impl PeerManagerActor {
         self.whitelist_nodes.iter().any(|wn| wn.addr.ip() == *ip)
     }
 
-    /// Returns single random peer with close to the highest height
-    fn highest_height_peers(&self) -> Vec<FullPeerInfo> {
-        // This finds max height among peers, and returns one peer close to such height.
-        let max_height = match (self.connected_peers.values())
-            .map(|connected_peer| connected_peer.full_peer_info.chain_info.height)
-            .max()
+    /// Whether the peer is one of the configured relays of this node.
+    fn is_relay_peer(&self, peer_id: &PeerId) -> bool {
+        self.config.relay_peers.iter().any(|relay| &relay.id == peer_id)
+    }
+
+    /// Connections to relays and to relayed peers are kept even if there are
+    /// too many active connections, the relayed peers aren't reachable otherwise.
+    fn is_relay_connection(&self, peer_id: &PeerId) -> bool {
+        self.is_relay_peer(peer_id) || self.relayed_peers.contains_key(peer_id)
+    }
+
+    /// Starts connecting to the configured relays this node isn't connected to.
+    fn connect_to_relay_peers(&mut self, ctx: &mut Context<Self>) {
+        for peer_info in &self.config.relay_peers {
+            if self.connected_peers.contains_key(&peer_info.id)
+                || self.outgoing_peers.contains(&peer_info.id)
+                || self.peer_store.is_banned(&peer_info.id)
+            {
+                continue;
+            }
+            debug!(target: "network", relay = ?peer_info, "Connecting to relay");
+            self.outgoing_peers.insert(peer_info.id.clone());
+            ctx.notify(PeerManagerMessageRequest::OutboundTcpConnect(OutboundTcpConnect {
+                peer_info: peer_info.clone(),
+            }));
+        }
+    }
+
+    /// Starts connecting, in parallel, to the validators which were direct
+    /// connections of this node in the latest save of connections.
+    fn start_warmup(&mut self, ctx: &mut Context<Self>) {
+        if self.config.outbound_disabled {
+            return;
+        }
+        let peers =
+            self.peer_store.warmup_peers(Clock::utc(), WARMUP_PEERS_EXPIRATION, MAX_WARMUP_PEERS);
+        for peer_info in peers {
+            if peer_info.id == self.my_peer_id || self.outgoing_peers.contains(&peer_info.id) {
+                continue;
+            }
+            if let Some(addr) = peer_info.addr {
+                debug!(target: "network", peer = ?peer_info, "Connecting to previously connected validator");
+                self.outgoing_peers.insert(peer_info.id.clone());
+                self.warmup_peers.push(peer_info.id.clone());
+                self.outbound_tcp_connect(addr, peer_info).spawn(ctx);
+            }
+        }
+        self.warmup_deadline = Clock::instant() + WARMUP_TIMEOUT;
+    }
+
+    /// Whether the node still waits for previously connected validators
+    /// dialed on startup.
+    fn is_warming_up(&self) -> bool {
+        Clock::instant() < self.warmup_deadline
+            && self.warmup_peers.iter().any(|peer_id| self.outgoing_peers.contains(peer_id))
+    }
+
+    /// Saves the direct connections to the peer store if they changed or if
+    /// `SAVE_CONNECTIONS_INTERVAL` passed since the last save.
+    fn save_connections(&mut self) {
+        let validators: HashSet<&PeerId> = (self.routing_table_view.get_announce_accounts())
+            .map(|announce_account| &announce_account.peer_id)
+            .collect();
+        let connections: HashMap<PeerId, bool> = (self.connected_peers.keys())
+            .map(|peer_id| (peer_id.clone(), validators.contains(peer_id)))
+            .collect();
+        let now = Clock::instant();
+        if connections == self.saved_connections
+            && now.saturating_duration_since(self.connections_saved_at) < SAVE_CONNECTIONS_INTERVAL
         {
-            Some(height) => height,
-            None => return vec![],
-        };
-        // Find all peers whose height is within `highest_peer_horizon` from max height peer(s).
-        self.connected_peers
-            .values()
-            .filter(|cp| {
-                cp.full_peer_info.chain_info.height.saturating_add(self.config.highest_peer_horizon)
-                    >= max_height
-            })
-            .map(|cp| cp.full_peer_info.clone())
-            .collect::<Vec<_>>()
+            return;
+        }
+        if let Err(err) =
+            self.peer_store.save_connections(connections.clone().into_iter(), Clock::utc())
+        {
+            error!(target: "network", ?err, "Failed to save connections");
+        }
+        self.saved_connections = connections;
+        self.connections_saved_at = now;
+    }
+
+    /// Returns peers close to the highest height, skipping peers whose chain
+    /// info is stale.
+    fn highest_height_peers(&self) -> Vec<FullPeerInfo> {
+        chain_info::highest_height_peers(
+            self.connected_peers.values().map(|cp| (&cp.full_peer_info, &cp.chain_info_age)),
+            self.config.highest_peer_horizon,
+            Clock::instant(),
+            self.config.peer_chain_info_staleness,
+        )
     }
 
     /// Query current peers for more peers.
@@ -1136,9 +1256,14 @@ impl PeerManagerActor {
                                 // }
                             } else if let Some(connected_peer) = act.connected_peers.get_mut(&peer_id1) {
                                 connected_peer.full_peer_info.chain_info = res.chain_info;
+                                connected_peer.chain_info_age.update(res.chain_info_updated_at);
                                 connected_peer.sent_bytes_per_sec = res.sent_bytes_per_sec;
                                 connected_peer.received_bytes_per_sec = res.received_bytes_per_sec;
                                 connected_peer.encoding = res.encoding;
+                                if connected_peer.chain_info_age.should_request(Clock::instant(), act.config.peer_chain_info_staleness) {
+                                    debug!(target: "network", peer_id = ?peer_id1, "Requesting stale chain info");
+                                    connected_peer.addr.do_send(SendMessage { message: PeerMessage::ChainInfoRequest });
+                                }
                             }
                         }
                         Err(err) => {
@@ -1154,12 +1279,12 @@ impl PeerManagerActor {
         });
     }
 
-    /// Check if the number of connections (excluding whitelisted ones) exceeds ideal_connections_hi.
+    /// Check if the number of connections (excluding whitelisted and relay ones) exceeds ideal_connections_hi.
     /// If so, constructs a safe set of peers and selects one random peer outside of that set
     /// and sends signal to stop connection to it gracefully.
     ///
     /// Safe set contruction process:
-    /// 1. Add all whitelisted peers to the safe set.
+    /// 1. Add all whitelisted peers and relay connections to the safe set.
     /// 2. If the number of outbound connections is less or equal than minimum_outbound_connections,
     ///    add all outbound connections to the safe set.
     /// 3. Find all peers who sent us a message within the last peer_recent_time_window,
@@ -1182,8 +1307,10 @@ impl PeerManagerActor {
         let mut safe_set = HashSet::new();
 
         // If there is not enough non-whitelisted peers, return without disconnecting anyone.
-        let whitelisted_peers =
-            filter_peers(&|p| self.is_peer_whitelisted(&p.full_peer_info.peer_info));
+        let whitelisted_peers = filter_peers(&|p| {
+            self.is_peer_whitelisted(&p.full_peer_info.peer_info)
+                || self.is_relay_connection(&p.full_peer_info.peer_info.id)
+        });
         if self.connected_peers.len() - whitelisted_peers.len()
             <= self.config.ideal_connections_hi as usize
         {
@@ -1238,6 +1365,7 @@ impl PeerManagerActor {
     /// Periodically monitor list of peers and:
     ///  - request new peers from connected peers,
     ///  - bootstrap outbound connections from known peers,
+    ///  - reconnect to the relays of this node,
     ///  - unban peers that have been banned for awhile,
     ///  - remove expired peers,
     ///
@@ -1271,7 +1399,13 @@ impl PeerManagerActor {
             }
         }
 
-        if self.is_outbound_bootstrap_needed() {
+        // Relays are connected to regardless of the number of connections.
+        self.connect_to_relay_peers(ctx);
+
+        self.save_connections();
+
+        // Previously connected validators go before any other peer.
+        if self.is_outbound_bootstrap_needed() && !self.is_warming_up() {
             if let Some(peer_info) = self.peer_store.unconnected_peer(|peer_state| {
                 // Ignore connecting to ourself
                 self.my_peer_id == peer_state.peer_info.id
@@ -1437,11 +1571,29 @@ impl PeerManagerActor {
                     self.routing_table_view.add_route_back(msg.hash(), self.my_peer_id.clone());
                 }
 
+                // Messages forwarded on behalf of other peers count towards the
+                // bandwidth of the relayed peer.
+                if msg.author != self.my_peer_id {
+                    if let Some(limiter) = self.relayed_peers.get_mut(&peer_id) {
+                        let size = msg.try_to_vec().map_or(0, |bytes| bytes.len()) as u64;
+                        if !limiter.try_consume(size, Clock::instant()) {
+                            metrics::MessageDropped::RelayBandwidthExceeded.inc(&msg.body);
+                            debug!(target: "network", to = ?peer_id, size, msg = ?msg.body, "Drop relayed message, bandwidth limit exceeded");
+                            return false;
+                        }
+                    }
+                }
+
                 Self::send_message(&self.connected_peers, peer_id, PeerMessage::Routed(msg))
             }
             Err(find_route_error) => {
                 // TODO(MarX, #1369): Message is dropped here. Define policy for this case.
-                metrics::MessageDropped::NoRouteFound.inc(&msg.body);
+                match find_route_error {
+                    FindRouteError::RouteBackEvicted => {
+                        metrics::MessageDropped::RouteBackEvicted.inc(&msg.body)
+                    }
+                    _ => metrics::MessageDropped::NoRouteFound.inc(&msg.body),
+                }
 
                 debug!(target: "network",
                       account_id = ?self.config.account_id,
@@ -2045,47 +2197,55 @@ impl PeerManagerActor {
         let _d = delay_detector::DelayDetector::new(|| "outbound tcp connect".into());
         debug!(target: "network", to = ?msg.peer_info, "Trying to connect");
         if let Some(addr) = msg.peer_info.addr {
-            // The `connect` may take several minutes. This happens when the
-            // `SYN` packet for establishing a TCP connection gets silently
-            // dropped, in which case the default TCP timeout is applied. That's
-            // too long for us, so we shorten it to one second.
-            //
-            // Why exactly a second? It was hard-coded in a library we used
-            // before, so we keep it to preserve behavior. Removing the timeout
-            // completely was observed to break stuff for real on the testnet.
-            tokio::time::timeout(Duration::from_secs(1), TcpStream::connect(addr))
-                .into_actor(self)
-                .then(move |res, act, ctx| match res {
-                    Ok(res) => match res {
-                        Ok(stream) => {
-                            debug!(target: "network", peer_info = ?msg.peer_info, "Connecting");
-                            let edge_info = act.propose_edge(&msg.peer_info.id, None);
-
-                            act.try_connect_peer(
-                                ctx.address(),
-                                stream,
-                                PeerType::Outbound,
-                                Some(msg.peer_info),
-                                Some(edge_info),
-                            );
-                            actix::fut::ready(())
-                        }
-                        Err(err) => {
-                            info!(target: "network", ?addr, ?err, "Error connecting to");
-                            act.outgoing_peers.remove(&msg.peer_info.id);
-                            actix::fut::ready(())
-                        }
-                    },
+            self.outbound_tcp_connect(addr, msg.peer_info).wait(ctx);
+        } else {
+            warn!(target: "network", peer_info = ?msg.peer_info, "Trying to connect to peer with no public address");
+        }
+    }
+
+    /// Returns a future connecting to `peer_info` at `addr`.
+    fn outbound_tcp_connect(
+        &self,
+        addr: SocketAddr,
+        peer_info: PeerInfo,
+    ) -> impl ActorFuture<Self, Output = ()> {
+        // The `connect` may take several minutes. This happens when the
+        // `SYN` packet for establishing a TCP connection gets silently
+        // dropped, in which case the default TCP timeout is applied. That's
+        // too long for us, so we shorten it to one second.
+        //
+        // Why exactly a second? It was hard-coded in a library we used
+        // before, so we keep it to preserve behavior. Removing the timeout
+        // completely was observed to break stuff for real on the testnet.
+        tokio::time::timeout(Duration::from_secs(1), TcpStream::connect(addr))
+            .into_actor(self)
+            .then(move |res, act, ctx| match res {
+                Ok(res) => match res {
+                    Ok(stream) => {
+                        debug!(target: "network", peer_info = ?peer_info, "Connecting");
+                        let edge_info = act.propose_edge(&peer_info.id, None);
+
+                        act.try_connect_peer(
+                            ctx.address(),
+                            stream,
+                            PeerType::Outbound,
+                            Some(peer_info),
+                            Some(edge_info),
+                        );
+                        actix::fut::ready(())
+                    }
                     Err(err) => {
                         info!(target: "network", ?addr, ?err, "Error connecting to");
-                        act.outgoing_peers.remove(&msg.peer_info.id);
+                        act.outgoing_peers.remove(&peer_info.id);
                         actix::fut::ready(())
                     }
-                })
-                .wait(ctx);
-        } else {
-            warn!(target: "network", peer_info = ?msg.peer_info, "Trying to connect to peer with no public address");
-        }
+                },
+                Err(err) => {
+                    info!(target: "network", ?addr, ?err, "Error connecting to");
+                    act.outgoing_peers.remove(&peer_info.id);
+                    actix::fut::ready(())
+                }
+            })
     }
 
     #[perf]
@@ -2096,8 +2256,10 @@ impl PeerManagerActor {
     ) -> RegisterPeerResponse {
         let _d = delay_detector::DelayDetector::new(|| "consolidate".into());
 
-        // Check if this is a blacklisted peer.
-        if (msg.peer_info.addr.as_ref()).map_or(true, |addr| self.peer_store.is_blacklisted(addr)) {
+        // Check if this is a blacklisted peer. A peer asking to be relayed has
+        // no public address, so the address of its connection is checked.
+        let addr = msg.peer_info.addr.or(msg.relay_request);
+        if addr.map_or(true, |addr| self.peer_store.is_blacklisted(&addr)) {
             debug!(target: "network", peer_info = ?msg.peer_info, "Dropping connection from blacklisted peer or unknown address");
             return RegisterPeerResponse::Reject;
         }
@@ -2156,18 +2318,34 @@ impl PeerManagerActor {
             return RegisterPeerResponse::Reject;
         }
 
+        let mut peer_info = msg.peer_info;
+        if msg.relay_request.is_some() {
+            if msg.peer_type != PeerType::Inbound
+                || self.relayed_peers.len() >= self.config.max_relayed_peers as usize
+            {
+                debug!(target: "network", id = ?peer_info.id, max_relayed_peers = self.config.max_relayed_peers, "Dropping relay request");
+                return RegisterPeerResponse::Reject;
+            }
+            peer_info.relay = Some(self.my_peer_id.clone());
+            self.relayed_peers.insert(
+                peer_info.id.clone(),
+                BandwidthLimiter::new(self.config.relayed_peer_bandwidth_limit, Clock::instant()),
+            );
+        }
+
         let require_response = msg.this_edge_info.is_none();
 
-        let edge_info = msg.this_edge_info.clone().unwrap_or_else(|| {
-            self.propose_edge(&msg.peer_info.id, Some(msg.other_edge_info.nonce))
-        });
+        let edge_info = msg
+            .this_edge_info
+            .clone()
+            .unwrap_or_else(|| self.propose_edge(&peer_info.id, Some(msg.other_edge_info.nonce)));
 
         let edge_info_response = if require_response { Some(edge_info.clone()) } else { None };
 
         // TODO: double check that address is connectable and add account id.
         self.register_peer(
             FullPeerInfo {
-                peer_info: msg.peer_info,
+                peer_info,
                 chain_info: msg.chain_info,
                 partial_edge_info: msg.other_edge_info,
             },
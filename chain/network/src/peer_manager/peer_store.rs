@@ -1,6 +1,8 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use chrono::DateTime;
 use near_network_primitives::types::{
     Blacklist, KnownPeerState, KnownPeerStatus, NetworkConfig, PeerInfo, ReasonForBan,
+    SavedConnection,
 };
 use near_primitives::network::PeerId;
 use near_primitives::time::{Clock, Utc};
@@ -13,6 +15,7 @@ use std::collections::HashMap;
 use std::error::Error;
 use std::net::SocketAddr;
 use std::ops::Not;
+use std::time::Duration;
 use tracing::{debug, error, info};
 
 /// Level of trust we have about a new (PeerId, Addr) pair.
@@ -112,6 +115,7 @@ impl PeerStore {
                 first_seen: peer_state.first_seen,
                 last_seen,
                 status,
+                saved_connection: peer_state.saved_connection,
             };
 
             let is_blacklisted =
@@ -132,6 +136,7 @@ impl PeerStore {
                         // If it says in database, that peer should be banned, ban the peer.
                         current_peer_state.get_mut().status = peer_state.status;
                     }
+                    current_peer_state.get_mut().saved_connection = peer_state.saved_connection;
                 }
                 // Peer is not a boot node
                 Entry::Vacant(entry) => {
@@ -279,6 +284,55 @@ impl PeerStore {
         self.find_peers(|p| matches!(p.status, KnownPeerStatus::Banned(_, _)).not(), max_count)
     }
 
+    /// Saves the direct connections of the node, each with whether the peer
+    /// is a validator.  Peers missing in the store are skipped.
+    pub(crate) fn save_connections(
+        &mut self,
+        connections: impl Iterator<Item = (PeerId, bool)>,
+        now: DateTime<Utc>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let saved_at = to_timestamp(now);
+        let mut store_update = self.store.store_update();
+        for (peer_id, validator) in connections {
+            if let Some(peer_state) = self.peer_states.get_mut(&peer_id) {
+                peer_state.saved_connection = Some(SavedConnection { saved_at, validator });
+                store_update.set_ser(DBCol::Peers, &peer_id.try_to_vec()?, peer_state)?;
+            }
+        }
+        store_update.commit().map_err(Into::into)
+    }
+
+    /// Returns up to `max_count` validators which were direct connections of
+    /// the node in the latest save of connections, unless that save is older
+    /// than `expiration`.  Banned peers and peers without address are skipped.
+    pub(crate) fn warmup_peers(
+        &self,
+        now: DateTime<Utc>,
+        expiration: Duration,
+        max_count: usize,
+    ) -> Vec<PeerInfo> {
+        let last_saved_at = match (self.peer_states.values())
+            .filter_map(|p| p.saved_connection.map(|saved| saved.saved_at))
+            .max()
+        {
+            Some(saved_at)
+                if to_timestamp(now).saturating_sub(saved_at) <= expiration.as_nanos() as u64 =>
+            {
+                saved_at
+            }
+            _ => return vec![],
+        };
+        let last_connection = SavedConnection { saved_at: last_saved_at, validator: true };
+        self.find_peers(
+            |p| {
+                p.saved_connection == Some(last_connection)
+                    && !p.status.is_banned()
+                    && p.peer_info.addr.is_some()
+            },
+            max_count,
+        )
+    }
+
     /// Return iterator over all known peers.
     pub(crate) fn iter(&self) -> Iter<'_, PeerId, KnownPeerState> {
         self.peer_states.iter()
@@ -395,10 +449,18 @@ impl PeerStore {
             }
         } else {
             // If doesn't have the address attached it is not verified and we add it
-            // only if it is unknown to us.
-            if !self.peer_states.contains_key(&peer_info.id) {
-                self.peer_states
-                    .insert(peer_info.id.clone(), KnownPeerState::new(peer_info, Clock::utc()));
+            // only if it is unknown to us.  The relay of a signed peer is
+            // the one it is connected to us through, so it replaces the known one.
+            match self.peer_states.get_mut(&peer_info.id) {
+                Some(peer_state) => {
+                    if trust_level == TrustLevel::Signed && peer_info.relay.is_some() {
+                        peer_state.peer_info.relay = peer_info.relay;
+                    }
+                }
+                None => {
+                    self.peer_states
+                        .insert(peer_info.id.clone(), KnownPeerState::new(peer_info, Clock::utc()));
+                }
             }
         }
         Ok(())
@@ -490,7 +552,7 @@ mod test {
     }
 
     fn get_peer_info(peer_id: PeerId, addr: Option<SocketAddr>) -> PeerInfo {
-        PeerInfo { id: peer_id, addr, account_id: None }
+        PeerInfo { id: peer_id, addr, account_id: None, relay: None }
     }
 
     fn gen_peer_info(port: u16) -> PeerInfo {
@@ -498,6 +560,7 @@ mod test {
             id: PeerId::new(SecretKey::from_random(KeyType::ED25519).public_key()),
             addr: Some(get_addr(port)),
             account_id: None,
+            relay: None,
         }
     }
 
@@ -841,4 +904,47 @@ mod test {
         }
         assert_peers_in_store(tmp_dir.path(), &[]);
     }
+
+    #[test]
+    fn test_warmup_peers() {
+        let store = create_test_store();
+        let peer_infos = (0..4).map(gen_peer_info).collect::<Vec<_>>();
+        let ids = peer_infos.iter().map(|info| info.id.clone()).collect::<Vec<_>>();
+        let start = Clock::utc();
+        {
+            let mut peer_store = PeerStore::new(store.clone(), &[], Default::default()).unwrap();
+            for peer_info in &peer_infos {
+                peer_store.peer_connected(peer_info).unwrap();
+            }
+            assert!(peer_store.warmup_peers(start, Duration::from_secs(60), 10).is_empty());
+
+            // Only validators of the latest save are returned.
+            peer_store
+                .save_connections(
+                    [(ids[0].clone(), true), (ids[1].clone(), true), (ids[2].clone(), false)]
+                        .into_iter(),
+                    start,
+                )
+                .unwrap();
+            peer_store
+                .save_connections(
+                    [(ids[1].clone(), true), (ids[2].clone(), false), (ids[3].clone(), true)]
+                        .into_iter(),
+                    start + chrono::Duration::seconds(1),
+                )
+                .unwrap();
+        }
+
+        // The saved connections survive a restart.
+        let peer_store = PeerStore::new(store, &[], Default::default()).unwrap();
+        let now = start + chrono::Duration::seconds(10);
+        let warmup_peers: HashSet<PeerId> = peer_store
+            .warmup_peers(now, Duration::from_secs(60), 10)
+            .into_iter()
+            .map(|peer_info| peer_info.id)
+            .collect();
+        assert_eq!(warmup_peers, HashSet::from_iter([ids[1].clone(), ids[3].clone()]));
+        assert_eq!(peer_store.warmup_peers(now, Duration::from_secs(60), 1).len(), 1);
+        assert!(peer_store.warmup_peers(now, Duration::from_secs(5), 10).is_empty());
+    }
 }
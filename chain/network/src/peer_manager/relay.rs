@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+/// Caps the bytes per second forwarded to a peer this node relays messages
+/// to.  The budget refills continuously and can accumulate up to one second
+/// worth of traffic, so short bursts go through.
+pub(crate) struct BandwidthLimiter {
+    /// Bytes per second.
+    limit: u64,
+    /// Bytes which may be sent right now.
+    available: u64,
+    last_refill: Instant,
+}
+
+impl BandwidthLimiter {
+    pub(crate) fn new(limit: u64, now: Instant) -> Self {
+        Self { limit, available: limit, last_refill: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let refill = (elapsed.as_nanos() * self.limit as u128 / Duration::from_secs(1).as_nanos())
+            .min(self.limit as u128) as u64;
+        // Only move `last_refill` once a whole byte is refilled, otherwise
+        // frequent messages would never refill anything.
+        if refill > 0 {
+            self.available = self.available.saturating_add(refill).min(self.limit);
+            self.last_refill = now;
+        }
+    }
+
+    /// Takes `bytes` from the budget, returns false without taking anything
+    /// if the budget doesn't allow it.
+    pub(crate) fn try_consume(&mut self, bytes: u64, now: Instant) -> bool {
+        self.refill(now);
+        if bytes > self.available {
+            return false;
+        }
+        self.available -= bytes;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BandwidthLimiter;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_bandwidth_limiter() {
+        let start = Instant::now();
+        let mut limiter = BandwidthLimiter::new(1000, start);
+        assert!(limiter.try_consume(600, start));
+        assert!(!limiter.try_consume(600, start));
+        assert!(limiter.try_consume(400, start));
+        assert!(!limiter.try_consume(1, start));
+
+        // Half a second refills half of the limit.
+        let now = start + Duration::from_millis(500);
+        assert!(!limiter.try_consume(501, now));
+        assert!(limiter.try_consume(500, now));
+
+        // The budget doesn't grow beyond one second worth of traffic.
+        let now = now + Duration::from_secs(10);
+        assert!(!limiter.try_consume(1001, now));
+        assert!(limiter.try_consume(1000, now));
+    }
+
+    #[test]
+    fn test_bandwidth_limiter_small_refills() {
+        let start = Instant::now();
+        let mut limiter = BandwidthLimiter::new(1000, start);
+        assert!(limiter.try_consume(1000, start));
+        // 100 steps of 0.5ms each refill 50 bytes in total, even though a
+        // single step is worth half a byte.
+        let mut now = start;
+        for _ in 0..100 {
+            now += Duration::from_micros(500);
+            limiter.refill(now);
+        }
+        assert!(limiter.try_consume(50, now));
+        assert!(!limiter.try_consume(1, now));
+    }
+}
@@ -13,6 +13,7 @@ use near_rate_limiter::ThrottleController;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 
 /// Actor message which asks `PeerManagerActor` to register peer.
@@ -35,6 +36,9 @@ pub struct RegisterPeer {
     pub(crate) peer_protocol_version: ProtocolVersion,
     /// A helper data structure for limiting reading, reporting bandwidth stats.
     pub(crate) throttle_controller: ThrottleController,
+    /// Address of the connection if the peer asked this node to relay
+    /// messages to it.  Such a peer has no public address.
+    pub(crate) relay_request: Option<SocketAddr>,
 }
 
 /// Addr<PeerActor> doesn't implement `DeepSizeOf` waiting for `deepsize` > 0.2.0.
@@ -1,15 +1,19 @@
+use crate::stats::metrics;
+use lru::LruCache;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
 use near_primitives::time::Clock;
-use std::collections::{btree_map, BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::time::{Duration, Instant};
 
 /// default value for `capacity`
-const DEFAULT_CAPACITY: usize = 100_000;
+pub(crate) const DEFAULT_CAPACITY: usize = 100_000;
 /// default value for `evict_timeout`
-const DEFAULT_CACHE_EVICT_TIMEOUT: Duration = Duration::from_millis(120_000);
+pub(crate) const DEFAULT_CACHE_EVICT_TIMEOUT: Duration = Duration::from_millis(120_000);
 /// default value for `remove_frequent_min_size`
 const DEFAULT_REMOVE_BATCH_SIZE: usize = 100;
+/// A single peer can allocate at most `capacity / PEER_QUOTA_DIVISOR` records.
+const PEER_QUOTA_DIVISOR: usize = 4;
 
 /// Cache to store route back messages.
 ///
@@ -37,15 +41,23 @@ const DEFAULT_REMOVE_BATCH_SIZE: usize = 100;
 /// 2. For every peer store how many message should be routed to it.
 ///
 /// First are removed messages that have been in the cache more time than
-/// `EVICTED_TIMEOUT`. If no message was removed, it is removed the oldest
+/// `evict_timeout`. If no message was removed, it is removed the oldest
 /// message from the peer with more messages in the cache.
 ///
+/// Besides, messages are removed once they are older than `evict_timeout`
+/// even if the cache is not full, and a peer which already has `max_per_peer`
+/// messages in the cache loses its oldest one for every new one.
+///
 /// Rationale:
 ///
 /// - Old entries in the cache will be eventually removed (no memory leak).
-/// - If the cache is not at full capacity, all new records will be stored.
+/// - If the cache is not at full capacity, all new records from peers within
+///   their quota will be stored.
 /// - If a peer try to abuse the system, it will be able to allocate at most
-///     `capacity / number_of_active_connections` entries.
+///   `min(max_per_peer, capacity / number_of_active_connections)` entries.
+///
+/// Hashes of removed messages are remembered, so that a response arriving
+/// too late can be told apart from one nobody asked for.
 pub struct RouteBackCache {
     /// Maximum number of records allowed in the cache.
     capacity: usize,
@@ -53,9 +65,14 @@ pub struct RouteBackCache {
     evict_timeout: Duration,
     /// Minimum number of records to delete from offending peer when the cache is full.
     remove_frequent_min_size: usize,
+    /// Maximum number of records allowed for a single PeerId.
+    max_per_peer: usize,
     /// Main map from message hash to time where it was created + target peer
     /// Size: O(capacity)
     main: HashMap<CryptoHash, (Instant, PeerId)>,
+    /// All hashes sorted by the time they arrived from older to newer.
+    /// Size: O(capacity)
+    records: BTreeSet<(Instant, CryptoHash)>,
     /// Number of records allocated by each PeerId.
     /// The size is stored with negative sign, to order in PeerId in decreasing order.
     /// To avoid handling with negative number all sizes are added by capacity.
@@ -65,147 +82,154 @@ pub struct RouteBackCache {
     /// are sorted by the time they arrived from older to newer.
     /// Size: O(capacity)
     record_per_target: BTreeMap<PeerId, BTreeSet<(Instant, CryptoHash)>>,
+    /// Hashes of the records removed before their response arrived.
+    /// Size: O(capacity)
+    evicted: LruCache<CryptoHash, ()>,
 }
 
 impl Default for RouteBackCache {
     fn default() -> Self {
-        Self::new(DEFAULT_CAPACITY, DEFAULT_CACHE_EVICT_TIMEOUT, DEFAULT_REMOVE_BATCH_SIZE)
+        Self::with_capacity_and_ttl(DEFAULT_CAPACITY, DEFAULT_CACHE_EVICT_TIMEOUT)
     }
 }
 
+/// Reason for removing a record before its response arrived, used as metric label.
+#[derive(Clone, Copy, strum::AsRefStr)]
+enum Eviction {
+    Expired,
+    Capacity,
+    PeerQuota,
+}
+
 impl RouteBackCache {
-    pub fn new(capacity: usize, evict_timeout: Duration, remove_frequent_min_size: usize) -> Self {
+    pub fn new(
+        capacity: usize,
+        evict_timeout: Duration,
+        remove_frequent_min_size: usize,
+        max_per_peer: usize,
+    ) -> Self {
         assert!(capacity > 0);
+        assert!(max_per_peer > 0);
 
         Self {
             capacity,
             evict_timeout,
             remove_frequent_min_size,
+            max_per_peer,
             main: HashMap::new(),
+            records: BTreeSet::new(),
             size_per_target: BTreeSet::new(),
             record_per_target: BTreeMap::new(),
+            evicted: LruCache::new(capacity),
         }
     }
 
+    pub fn with_capacity_and_ttl(capacity: usize, evict_timeout: Duration) -> Self {
+        Self::new(
+            capacity,
+            evict_timeout,
+            DEFAULT_REMOVE_BATCH_SIZE,
+            std::cmp::max(1, capacity / PEER_QUOTA_DIVISOR),
+        )
+    }
+
     fn is_full(&self) -> bool {
         self.capacity == self.main.len()
     }
 
-    fn remove_frequent(&mut self) {
-        let (mut size, target) = self.size_per_target.iter().next().cloned().unwrap();
-        let mut removed = 0;
-
-        if let btree_map::Entry::Occupied(mut entry) = self.record_per_target.entry(target.clone())
-        {
-            {
-                let records = entry.get_mut();
-
-                match records.iter().nth(self.remove_frequent_min_size).cloned() {
-                    Some(key) => {
-                        let mut to_remove = records.split_off(&key);
-                        std::mem::swap(&mut to_remove, records);
-
-                        for record in to_remove {
-                            self.main.remove(&record.1);
-                            removed += 1;
-                        }
-                    }
-                    None => {
-                        for record in records.iter() {
-                            self.main.remove(&record.1);
-                            removed += 1;
-                        }
-                        records.clear();
-                    }
-                }
-            }
-
-            if entry.get().is_empty() {
-                entry.remove();
-            }
-        }
+    fn target_size(&self, target: &PeerId) -> usize {
+        self.record_per_target.get(target).map_or(0, |x| x.len())
+    }
 
-        self.size_per_target.remove(&(size, target.clone()));
-        // Since self.size is equal to capacity - real_size, adding 1, is equivalent to subtracting 1 from the real size.
-        size += removed;
+    /// Removes the record from all the indices.
+    fn remove_record(&mut self, hash: &CryptoHash) -> Option<PeerId> {
+        let (time, target) = self.main.remove(hash)?;
+        self.records.remove(&(time, *hash));
 
-        if self.capacity - size != 0 {
-            self.size_per_target.insert((size, target));
-        }
-    }
+        // Number of elements associated with this target
+        let mut size = self.target_size(&target);
 
-    fn remove_evicted(&mut self) {
-        if self.is_full() {
-            self.remove_frequent();
+        // Remove from `size_per_target` since value is going to be updated
+        self.size_per_target.remove(&(self.capacity - size, target.clone()));
 
-            let now = Clock::instant();
-            let remove_until = now - self.evict_timeout;
-            let mut remove_empty = vec![];
+        // Remove current hash from the list associated with `record_par_target`
+        if let Some(records) = self.record_per_target.get_mut(&target) {
+            records.remove(&(time, *hash));
+        }
 
-            for (key, value) in self.record_per_target.iter_mut() {
-                let prev_size = value.len();
-                let keep = value.split_off(&(remove_until, CryptoHash::default()));
+        // Calculate new size
+        size -= 1;
 
-                for evicted in value.iter() {
-                    self.main.remove(&evicted.1);
-                }
+        if size == 0 {
+            // If there are no elements remove entry associated with this peer
+            self.record_per_target.remove(&target);
+        } else {
+            // otherwise, add this peer to `size_per_target` with new size
+            self.size_per_target.insert((self.capacity - size, target.clone()));
+        }
 
-                *value = keep;
-                let new_size = value.len();
+        Some(target)
+    }
 
-                if prev_size != new_size {
-                    self.size_per_target.remove(&(self.capacity - prev_size, key.clone()));
+    fn evict(&mut self, hash: CryptoHash, reason: Eviction) {
+        if self.remove_record(&hash).is_some() {
+            self.evicted.put(hash, ());
+            metrics::ROUTE_BACK_CACHE_EVICTIONS.with_label_values(&[reason.as_ref()]).inc();
+        }
+    }
 
-                    if new_size > 0 {
-                        self.size_per_target.insert((self.capacity - new_size, key.clone()));
-                    }
-                }
+    /// Evicts the oldest records of `target`, at least `count` of them.
+    fn evict_oldest_of(&mut self, target: &PeerId, count: usize, reason: Eviction) {
+        let to_remove: Vec<CryptoHash> = match self.record_per_target.get(target) {
+            Some(records) => records.iter().take(count).map(|(_, hash)| *hash).collect(),
+            None => return,
+        };
+        for hash in to_remove {
+            self.evict(hash, reason);
+        }
+    }
 
-                if new_size == 0 {
-                    remove_empty.push(key.clone());
-                }
-            }
+    fn remove_frequent(&mut self) {
+        let target = match self.size_per_target.iter().next() {
+            Some((_, target)) => target.clone(),
+            None => return,
+        };
+        self.evict_oldest_of(&target, self.remove_frequent_min_size, Eviction::Capacity);
+    }
 
-            for key in remove_empty {
-                self.record_per_target.remove(&key);
+    /// Removes records older than `evict_timeout`.
+    fn remove_evicted(&mut self) {
+        let now = Clock::instant();
+        let remove_until = match now.checked_sub(self.evict_timeout) {
+            Some(remove_until) => remove_until,
+            None => return,
+        };
+        while let Some((time, hash)) = self.records.iter().next().cloned() {
+            if time >= remove_until {
+                break;
             }
+            self.evict(hash, Eviction::Expired);
         }
     }
 
+    fn update_size_metric(&self) {
+        metrics::ROUTE_BACK_CACHE_SIZE.set(self.main.len() as i64);
+    }
+
     pub fn get(&self, hash: &CryptoHash) -> Option<&PeerId> {
         self.main.get(hash).map(|(_, target)| target)
     }
 
+    /// Whether the record for `hash` was removed before its response arrived.
+    pub fn is_evicted(&self, hash: &CryptoHash) -> bool {
+        self.evicted.contains(hash)
+    }
+
     pub fn remove(&mut self, hash: &CryptoHash) -> Option<PeerId> {
         self.remove_evicted();
-
-        if let Some((time, target)) = self.main.remove(hash) {
-            // Number of elements associated with this target
-            let mut size = self.record_per_target.get(&target).map(|x| x.len()).unwrap();
-
-            // Remove from `size_per_target` since value is going to be updated
-            self.size_per_target.remove(&(self.capacity - size, target.clone()));
-
-            // Remove current hash from the list associated with `record_par_target`
-            if let Some(records) = self.record_per_target.get_mut(&target) {
-                records.remove(&(time, *hash));
-            }
-
-            // Calculate new size
-            size -= 1;
-
-            if size == 0 {
-                // If there are no elements remove entry associated with this peer
-                self.record_per_target.remove(&target);
-            } else {
-                // otherwise, add this peer to `size_per_target` with new size
-                self.size_per_target.insert((self.capacity - size, target.clone()));
-            }
-
-            Some(target)
-        } else {
-            None
-        }
+        let target = self.remove_record(hash);
+        self.update_size_metric();
+        target
     }
 
     pub fn insert(&mut self, hash: CryptoHash, target: PeerId) {
@@ -215,11 +239,20 @@ impl RouteBackCache {
 
         self.remove_evicted();
 
+        let size = self.target_size(&target);
+        if size >= self.max_per_peer {
+            self.evict_oldest_of(&target, size + 1 - self.max_per_peer, Eviction::PeerQuota);
+        } else if self.is_full() {
+            self.remove_frequent();
+        }
+
         let now = Clock::instant();
 
         self.main.insert(hash, (now, target.clone()));
+        self.records.insert((now, hash));
+        self.evicted.pop(&hash);
 
-        let mut size = self.record_per_target.get(&target).map_or(0, |x| x.len());
+        let mut size = self.target_size(&target);
 
         if size > 0 {
             self.size_per_target.remove(&(self.capacity - size, target.clone()));
@@ -229,12 +262,14 @@ impl RouteBackCache {
 
         size += 1;
         self.size_per_target.insert((self.capacity - size, target));
+        self.update_size_metric();
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use borsh::BorshSerialize;
     use near_primitives::hash::hash;
     use std::thread;
     use std::time::Duration;
@@ -242,6 +277,8 @@ mod test {
     /// Check internal state of the cache is ok
     fn check_consistency(cache: &RouteBackCache) {
         assert!(cache.main.len() <= cache.capacity);
+        assert!(cache.evicted.len() <= cache.capacity);
+        assert_eq!(cache.records.len(), cache.main.len());
         assert_eq!(cache.size_per_target.len(), cache.record_per_target.len());
 
         for (neg_size, target) in cache.size_per_target.iter() {
@@ -254,6 +291,7 @@ mod test {
 
         for (target, records) in cache.record_per_target.iter() {
             total += records.len();
+            assert!(records.len() <= cache.max_per_peer);
 
             for (time, record) in records.iter() {
                 assert_eq!(cache.main.get(record).unwrap(), &(*time, target.clone()));
@@ -269,7 +307,7 @@ mod test {
 
     #[test]
     fn simple() {
-        let mut cache = RouteBackCache::new(100, Duration::from_millis(1000000000), 1);
+        let mut cache = RouteBackCache::new(100, Duration::from_millis(1000000000), 1, 100);
         let (peer0, hash0) = create_message(0);
 
         check_consistency(&cache);
@@ -285,7 +323,7 @@ mod test {
     /// Check record is removed after some timeout.
     #[test]
     fn evicted() {
-        let mut cache = RouteBackCache::new(1, Duration::from_millis(1), 1);
+        let mut cache = RouteBackCache::new(1, Duration::from_millis(1), 1, 1);
         let (peer0, hash0) = create_message(0);
 
         cache.insert(hash0, peer0.clone());
@@ -300,7 +338,7 @@ mod test {
     /// Check element is removed after timeout triggered by insert at max capacity.
     #[test]
     fn insert_evicted() {
-        let mut cache = RouteBackCache::new(1, Duration::from_millis(1), 1);
+        let mut cache = RouteBackCache::new(1, Duration::from_millis(1), 1, 1);
         let (peer0, hash0) = create_message(0);
         let (peer1, hash1) = create_message(1);
 
@@ -317,7 +355,7 @@ mod test {
     /// Check element is removed after insert because cache is at max capacity.
     #[test]
     fn insert_override() {
-        let mut cache = RouteBackCache::new(1, Duration::from_millis(1000000000), 1);
+        let mut cache = RouteBackCache::new(1, Duration::from_millis(1000000000), 1, 1);
         let (peer0, hash0) = create_message(0);
         let (peer1, hash1) = create_message(1);
 
@@ -332,10 +370,11 @@ mod test {
     }
 
     /// Insert three elements. One old element from peer0 and two recent elements from peer1.
-    /// Check that old element from peer0 is removed, even while peer1 has more elements.
+    /// Check that old element from peer0 is removed, and no element from peer1 is removed
+    /// since there is room for them.
     #[test]
     fn prefer_evict() {
-        let mut cache = RouteBackCache::new(3, Duration::from_millis(100), 1);
+        let mut cache = RouteBackCache::new(3, Duration::from_millis(100), 1, 3);
         let (peer0, hash0) = create_message(0);
         let (peer1, hash1) = create_message(1);
         let (_, hash2) = create_message(2);
@@ -349,16 +388,18 @@ mod test {
         check_consistency(&cache);
 
         assert!(cache.get(&hash0).is_none()); // This is removed because it was evicted
-        assert!(cache.get(&hash1).is_none()); // This is removed since frequent are always removed
+        assert!(cache.get(&hash1).is_some()); // Evicting the old element made room for this one
         assert!(cache.get(&hash2).is_some());
         assert!(cache.get(&hash3).is_some());
+        assert!(cache.is_evicted(&hash0));
+        assert!(!cache.is_evicted(&hash1));
     }
 
     /// Insert three elements. One old element from peer0 and two recent elements from peer1.
     /// Check that older element from peer1 is removed, since evict timeout haven't passed yet.
     #[test]
     fn prefer_full() {
-        let mut cache = RouteBackCache::new(3, Duration::from_millis(100000), 1);
+        let mut cache = RouteBackCache::new(3, Duration::from_millis(100000), 1, 3);
         let (peer0, hash0) = create_message(0);
         let (peer1, hash1) = create_message(1);
         let (_, hash2) = create_message(2);
@@ -381,7 +422,7 @@ mod test {
     /// Check that older element from peer1 is removed, since evict timeout haven't passed yet.
     #[test]
     fn remove_all_frequent() {
-        let mut cache = RouteBackCache::new(3, Duration::from_millis(100000), 2);
+        let mut cache = RouteBackCache::new(3, Duration::from_millis(100000), 2, 3);
         let (peer0, hash0) = create_message(0);
         let (peer1, hash1) = create_message(1);
         let (_, hash2) = create_message(2);
@@ -407,7 +448,7 @@ mod test {
     /// initial hashes should be present in the cache after the attack.
     #[test]
     fn poison_attack() {
-        let mut cache = RouteBackCache::new(17, Duration::from_millis(1000000), 1);
+        let mut cache = RouteBackCache::new(17, Duration::from_millis(1000000), 1, 17);
         let mut ix = 0;
 
         let mut peers = vec![];
@@ -446,4 +487,84 @@ mod test {
             }
         }
     }
+
+    /// Check the oldest elements of a peer are removed once it reaches its quota,
+    /// while other peers keep theirs.
+    #[test]
+    fn peer_quota() {
+        let mut cache = RouteBackCache::new(10, Duration::from_millis(1000000), 1, 3);
+        let peer0 = PeerId::random();
+        let peer1 = PeerId::random();
+
+        cache.insert(hash(&[0]), peer1.clone());
+        for ix in 1..6 {
+            cache.insert(hash(&[ix]), peer0.clone());
+            check_consistency(&cache);
+        }
+
+        assert_eq!(cache.get(&hash(&[0])), Some(&peer1));
+        for ix in 1..3 {
+            assert_eq!(cache.get(&hash(&[ix])), None);
+            assert!(cache.is_evicted(&hash(&[ix])));
+        }
+        for ix in 3..6 {
+            assert_eq!(cache.get(&hash(&[ix])), Some(&peer0));
+        }
+    }
+
+    /// Check a response within the timeout is routed back, and a response after the
+    /// timeout is recognized as evicted even though the cache is not full.
+    #[test]
+    fn expire_without_full() {
+        let mut cache = RouteBackCache::with_capacity_and_ttl(100, Duration::from_millis(50));
+        let (peer0, hash0) = create_message(0);
+        let (_, hash1) = create_message(1);
+        let (_, hash2) = create_message(2);
+
+        cache.insert(hash0, peer0.clone());
+        cache.insert(hash1, peer0.clone());
+        assert_eq!(cache.remove(&hash0), Some(peer0));
+        assert!(!cache.is_evicted(&hash0));
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(cache.remove(&hash1), None);
+        assert!(cache.is_evicted(&hash1));
+        assert!(!cache.is_evicted(&hash2));
+        check_consistency(&cache);
+        assert!(cache.main.is_empty());
+    }
+
+    /// Push records for many peers, a few of them sending a lot, and check the cache,
+    /// including the memory of evicted records, stays within its capacity, and no peer
+    /// goes over its quota.
+    #[test]
+    fn soak() {
+        const CAPACITY: usize = 1000;
+        let mut cache = RouteBackCache::with_capacity_and_ttl(CAPACITY, Duration::from_secs(1000));
+        let peers: Vec<_> = (0..500).map(|_| PeerId::random()).collect();
+        let heavy: Vec<_> = (0..3).map(|_| PeerId::random()).collect();
+
+        for round in 0u64..20 {
+            for (ix, peer) in peers.iter().enumerate() {
+                cache.insert(hash(&(round, ix as u64, 0u8).try_to_vec().unwrap()), peer.clone());
+            }
+            for (ix, peer) in heavy.iter().enumerate() {
+                for j in 0u64..500 {
+                    cache.insert(
+                        hash(&(round, ix as u64, j, 1u8).try_to_vec().unwrap()),
+                        peer.clone(),
+                    );
+                }
+            }
+            check_consistency(&cache);
+            assert!(cache.main.len() <= CAPACITY);
+            assert!(cache.evicted.len() <= CAPACITY);
+            for peer in &heavy {
+                assert!(cache.target_size(peer) <= CAPACITY / PEER_QUOTA_DIVISOR);
+            }
+        }
+
+        // The heavy peers don't push out the regular ones.
+        let regular: usize = peers.iter().map(|peer| cache.target_size(peer)).sum();
+        assert!(regular >= CAPACITY / 2, "{}", regular);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::routing::route_back_cache::RouteBackCache;
+use crate::routing::route_back_cache::{self, RouteBackCache};
 use itertools::Itertools;
 use lru::LruCache;
 use near_network_primitives::types::{Edge, PeerIdOrHash, Ping, Pong};
@@ -51,17 +51,31 @@ pub(crate) enum FindRouteError {
     PeerNotFound,
     AccountNotFound,
     RouteBackNotFound,
+    /// The route back expired or was evicted to make room for others.
+    RouteBackEvicted,
 }
 
 impl RoutingTableView {
     pub fn new(store: Store) -> Self {
-        // Find greater nonce on disk and set `component_nonce` to this value.
+        Self::with_route_back_cache(
+            store,
+            route_back_cache::DEFAULT_CAPACITY,
+            route_back_cache::DEFAULT_CACHE_EVICT_TIMEOUT,
+        )
+    }
 
+    /// Routing table keeping at most `route_back_capacity` messages to route
+    /// responses back, each for at most `route_back_ttl`.
+    pub fn with_route_back_cache(
+        store: Store,
+        route_back_capacity: usize,
+        route_back_ttl: Duration,
+    ) -> Self {
         Self {
             account_peers: LruCache::new(ANNOUNCE_ACCOUNT_CACHE_SIZE),
             peer_forwarding: Default::default(),
             local_edges_info: Default::default(),
-            route_back: RouteBackCache::default(),
+            route_back: RouteBackCache::with_capacity_and_ttl(route_back_capacity, route_back_ttl),
             store,
             route_nonce: LruCache::new(ROUND_ROBIN_NONCE_CACHE_SIZE),
             ping_info: LruCache::new(PING_PONG_CACHE_SIZE),
@@ -113,9 +127,13 @@ impl RoutingTableView {
     pub(crate) fn find_route(&mut self, target: &PeerIdOrHash) -> Result<PeerId, FindRouteError> {
         match target {
             PeerIdOrHash::PeerId(peer_id) => self.find_route_from_peer_id(peer_id),
-            PeerIdOrHash::Hash(hash) => {
-                self.fetch_route_back(*hash).ok_or(FindRouteError::RouteBackNotFound)
-            }
+            PeerIdOrHash::Hash(hash) => self.fetch_route_back(*hash).ok_or_else(|| {
+                if self.route_back.is_evicted(hash) {
+                    FindRouteError::RouteBackEvicted
+                } else {
+                    FindRouteError::RouteBackNotFound
+                }
+            }),
         }
     }
 
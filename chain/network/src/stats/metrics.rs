@@ -122,6 +122,22 @@ static DROPPED_MESSAGE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub(crate) static ROUTE_BACK_CACHE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_route_back_cache_size",
+        "Number of routed messages waiting for a response to be routed back",
+    )
+    .unwrap()
+});
+pub(crate) static ROUTE_BACK_CACHE_EVICTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_route_back_cache_evictions_total",
+        "Number of routed messages removed from the route back cache before their response \
+         arrived, by reason",
+        &["reason"],
+    )
+    .unwrap()
+});
 pub static PARTIAL_ENCODED_CHUNK_REQUEST_DELAY: Lazy<Histogram> = Lazy::new(|| {
     try_create_histogram(
         "partial_encoded_chunk_request_delay",
@@ -133,9 +149,12 @@ pub static PARTIAL_ENCODED_CHUNK_REQUEST_DELAY: Lazy<Histogram> = Lazy::new(|| {
 #[derive(Clone, Copy, strum::AsRefStr)]
 pub(crate) enum MessageDropped {
     NoRouteFound,
+    /// Response to a message whose route back was evicted from the cache.
+    RouteBackEvicted,
     UnknownAccount,
     InputTooLong,
     MaxCapacityExceeded,
+    RelayBandwidthExceeded,
 }
 
 impl MessageDropped {
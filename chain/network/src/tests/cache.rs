@@ -1,8 +1,12 @@
-use crate::routing::routing_table_view::RoutingTableView;
+use crate::routing::routing_table_view::{FindRouteError, RoutingTableView};
 use crate::test_utils::{random_epoch_id, random_peer_id};
+use assert_matches::assert_matches;
 use near_crypto::Signature;
+use near_network_primitives::types::PeerIdOrHash;
+use near_primitives::hash::hash;
 use near_primitives::network::AnnounceAccount;
 use near_store::test_utils::create_test_store;
+use std::time::Duration;
 
 #[test]
 fn announcement_same_epoch() {
@@ -102,3 +106,36 @@ fn load_from_disk() {
     // Cache of second routing table should contain account loaded from disk
     assert_eq!(routing_table1.get_announce_accounts().count(), 1);
 }
+
+#[test]
+fn route_back_round_trip() {
+    let mut routing_table =
+        RoutingTableView::with_route_back_cache(create_test_store(), 10, Duration::from_millis(50));
+    let peer_id0 = random_peer_id();
+    let peer_id1 = random_peer_id();
+
+    // Responses within the timeout are routed back to where the request came from.
+    for i in 0..30u8 {
+        let peer_id = if i % 2 == 0 { &peer_id0 } else { &peer_id1 };
+        routing_table.add_route_back(hash(&[i]), peer_id.clone());
+        assert!(routing_table.compare_route_back(hash(&[i]), peer_id));
+        assert_eq!(routing_table.find_route(&PeerIdOrHash::Hash(hash(&[i]))).unwrap(), *peer_id);
+        // Only once.
+        assert_matches!(
+            routing_table.find_route(&PeerIdOrHash::Hash(hash(&[i]))),
+            Err(FindRouteError::RouteBackNotFound)
+        );
+    }
+
+    // Late responses are recognized.
+    routing_table.add_route_back(hash(&[100]), peer_id0);
+    std::thread::sleep(Duration::from_millis(100));
+    assert_matches!(
+        routing_table.find_route(&PeerIdOrHash::Hash(hash(&[100]))),
+        Err(FindRouteError::RouteBackEvicted)
+    );
+    assert_matches!(
+        routing_table.find_route(&PeerIdOrHash::Hash(hash(&[101]))),
+        Err(FindRouteError::RouteBackNotFound)
+    );
+}
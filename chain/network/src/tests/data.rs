@@ -89,6 +89,7 @@ pub fn make_peer_info<R: Rng>(rng: &mut R) -> PeerInfo {
         id: PeerId::new(signer.public_key),
         addr: Some(make_addr(rng)),
         account_id: Some(signer.account_id),
+        relay: None,
     }
 }
 
@@ -272,6 +273,7 @@ pub fn make_handshake<R: Rng>(rng: &mut R, chain: &Chain) -> Handshake {
         Some(rng.gen()),
         chain.get_info(),
         make_partial_edge(rng),
+        false,
     )
 }
 
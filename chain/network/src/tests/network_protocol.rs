@@ -1,4 +1,4 @@
-use crate::network_protocol::Encoding;
+use crate::network_protocol::{ChainInfoUpdate, Encoding};
 use crate::tests::data;
 use crate::tests::util::{make_rng, FakeClock};
 use crate::types::{HandshakeFailureReason, PeerMessage};
@@ -63,6 +63,12 @@ fn serialize_deserialize() -> anyhow::Result<()> {
         PeerMessage::EpochSyncFinalizationRequest(epoch_id.clone()),
         // TODO: EpochSyncFinalizationResponse
         // TODO: RoutingTableSyncV2,
+        PeerMessage::ChainInfoRequest,
+        PeerMessage::ChainInfoResponse(ChainInfoUpdate {
+            height: chain.height(),
+            tracked_shards: vec![0, 3],
+            archival: true,
+        }),
     ];
 
     // Check that serialize;deserialize = 1
@@ -86,7 +92,7 @@ fn serialize_deserialize() -> anyhow::Result<()> {
         let x = m.serialize(Encoding::Proto);
         assert!(x[0] >= 32, "serialize({},PROTO)[0] = {:?}, want >= 32", m, x.get(0));
         let y = m.serialize(Encoding::Borsh);
-        assert!(y[0] <= 21, "serialize({},BORSH)[0] = {:?}, want <= 21", m, y.get(0));
+        assert!(y[0] <= 23, "serialize({},BORSH)[0] = {:?}, want <= 23", m, y.get(0));
     }
 
     // Encodings should never be compatible.
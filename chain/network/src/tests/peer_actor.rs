@@ -271,12 +271,13 @@ impl PeerHandle {
             PeerActor::create(move |ctx| {
                 PeerActor::add_stream(read, ctx);
                 PeerActor::new(
-                    PeerInfo { id: cfg.id(), addr: Some(my_addr), account_id: None },
+                    PeerInfo { id: cfg.id(), addr: Some(my_addr), account_id: None, relay: None },
                     peer_addr.clone(),
                     cfg.start_handshake_with.as_ref().map(|id| PeerInfo {
                         id: id.clone(),
                         addr: Some(peer_addr.clone()),
                         account_id: None,
+                        relay: None,
                     }),
                     cfg.peer_type(),
                     FramedWrite::new(write, Codec::default(), Codec::default(), ctx),
@@ -290,6 +291,7 @@ impl PeerHandle {
                     Arc::new(AtomicUsize::new(0)),
                     rate_limiter,
                     cfg.force_encoding,
+                    false,
                 )
             })
         })
@@ -39,6 +39,8 @@ pub struct QueryPeerStats {}
 pub struct PeerStatsResult {
     /// Chain info.
     pub chain_info: PeerChainInfoV2,
+    /// When the peer last confirmed `chain_info`.
+    pub chain_info_updated_at: Instant,
     /// Number of bytes we've received from the peer.
     pub received_bytes_per_sec: u64,
     /// Number of bytes we've sent to the peer.
@@ -1,12 +1,13 @@
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
-use crate::types::{PoolIterator, PoolKey, TransactionGroup};
+use crate::types::{PoolIterator, PoolKey, PoolOrder, TransactionGroup};
 use borsh::BorshSerialize;
 use near_crypto::PublicKey;
 use near_primitives::epoch_manager::RngSeed;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, Balance};
+use std::cmp::Reverse;
 use std::ops::Bound;
 
 mod metrics;
@@ -72,6 +73,11 @@ impl TransactionPool {
         PoolIteratorWrapper::new(self)
     }
 
+    /// Same as `pool_iterator`, but hands out the transaction groups in the given order.
+    pub fn pool_iterator_with_order(&mut self, order: PoolOrder) -> PoolIteratorWrapper<'_> {
+        PoolIteratorWrapper::with_order(self, order)
+    }
+
     /// Quick reconciliation step - evict all transactions that already in the block
     /// or became invalid after it.
     pub fn remove_transactions(&mut self, transactions: &[SignedTransaction]) {
@@ -113,6 +119,15 @@ impl TransactionPool {
     pub fn len(&self) -> usize {
         self.unique_transactions.len()
     }
+
+    /// Forgets the hashes of transactions taken out of the pool by an iterator.
+    fn remove_unique_transactions(&mut self, hashes: Vec<CryptoHash>) {
+        for hash in hashes {
+            if self.unique_transactions.remove(&hash) {
+                metrics::TRANSACTION_POOL_TOTAL.dec();
+            }
+        }
+    }
 }
 
 /// PoolIterator is a structure to pull transactions from the pool.
@@ -124,12 +139,97 @@ pub struct PoolIteratorWrapper<'a> {
 
     /// Queue of transaction groups. Each group there is sorted by nonce.
     sorted_groups: VecDeque<TransactionGroup>,
+
+    /// With `PoolOrder::PriorityFee`, all groups sorted by nonce and ranked by the priority fee
+    /// of their next transaction, ties broken by the order in which they were ranked.
+    ranked_groups: BTreeMap<(Reverse<Balance>, u64), TransactionGroup>,
+
+    /// Group returned by the last `next()` with `PoolOrder::PriorityFee`.  It's ranked again on
+    /// the following call, once the caller has taken transactions from it.
+    current_group: Option<TransactionGroup>,
+
+    /// Number of groups ranked so far, used to break ties between equal fees.
+    num_ranked: u64,
+
+    /// Order in which the groups are returned.
+    order: PoolOrder,
 }
 
 impl<'a> PoolIteratorWrapper<'a> {
     pub fn new(pool: &'a mut TransactionPool) -> Self {
-        Self { pool, sorted_groups: Default::default() }
+        Self::with_order(pool, PoolOrder::RoundRobin)
+    }
+
+    pub fn with_order(pool: &'a mut TransactionPool, order: PoolOrder) -> Self {
+        let mut wrapper = Self {
+            pool,
+            sorted_groups: Default::default(),
+            ranked_groups: BTreeMap::new(),
+            current_group: None,
+            num_ranked: 0,
+            order,
+        };
+        if order == PoolOrder::PriorityFee {
+            // Ranking needs the next transaction of every group, so all of them are taken out of
+            // the pool at once, in round robin order so that it decides between equal fees.
+            while !wrapper.pool.transactions.is_empty() {
+                let group = wrapper.take_next_group();
+                wrapper.rank_group(group);
+            }
+        }
+        wrapper
+    }
+
+    /// Takes the group after the last used key out of the pool, which must not be empty, and
+    /// sorts it.
+    fn take_next_group(&mut self) -> TransactionGroup {
+        let last_used_key = self.pool.last_used_key;
+        let key = *self
+            .pool
+            .transactions
+            .range((Bound::Excluded(last_used_key), Bound::Unbounded))
+            .chain(self.pool.transactions.range((Bound::Unbounded, Bound::Included(last_used_key))))
+            .next()
+            .expect("the pool is not empty")
+            .0;
+        self.pool.last_used_key = key;
+        let mut transactions = self.pool.transactions.remove(&key).expect("just checked existence");
+        match self.order {
+            PoolOrder::RoundRobin => transactions.sort_by_key(|st| Reverse(st.transaction.nonce)),
+            PoolOrder::PriorityFee => {
+                transactions.sort_by_key(|st| (Reverse(st.transaction.nonce), priority_fee(st)))
+            }
+        }
+        TransactionGroup { key, transactions, removed_transaction_hashes: vec![] }
     }
+
+    /// Ranks `group` by the priority fee of the transaction `TransactionGroup::next` returns, or
+    /// drops it if it has no transactions left.
+    fn rank_group(&mut self, group: TransactionGroup) {
+        match group.transactions.last() {
+            Some(next_transaction) => {
+                let rank = (Reverse(priority_fee(next_transaction)), self.num_ranked);
+                self.num_ranked += 1;
+                self.ranked_groups.insert(rank, group);
+            }
+            None => self.pool.remove_unique_transactions(group.removed_transaction_hashes),
+        }
+    }
+
+    fn next_by_priority_fee(&mut self) -> Option<&mut TransactionGroup> {
+        if let Some(group) = self.current_group.take() {
+            self.rank_group(group);
+        }
+        let rank = *self.ranked_groups.keys().next()?;
+        let group = self.ranked_groups.remove(&rank).expect("just found");
+        self.pool.last_used_key = group.key;
+        self.current_group = Some(group);
+        self.current_group.as_mut()
+    }
+}
+
+fn priority_fee(transaction: &SignedTransaction) -> Balance {
+    transaction.transaction.priority_fee.unwrap_or(0)
 }
 
 /// The iterator works with the following algorithm:
@@ -149,40 +249,22 @@ impl<'a> PoolIteratorWrapper<'a> {
 ///
 /// When the iterator is dropped, `unique_transactions` in the pool is updated for every group.
 /// And all non-empty group from the sorted groups queue are inserted back into the pool.
+///
+/// With `PoolOrder::PriorityFee` all groups are taken from the pool up front and each call returns
+/// the group whose next transaction has the highest priority fee.
 impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
     fn next(&mut self) -> Option<&mut TransactionGroup> {
+        if self.order == PoolOrder::PriorityFee {
+            return self.next_by_priority_fee();
+        }
         if !self.pool.transactions.is_empty() {
-            let key = *self
-                .pool
-                .transactions
-                .range((Bound::Excluded(self.pool.last_used_key), Bound::Unbounded))
-                .next()
-                .map(|(k, _v)| k)
-                .unwrap_or_else(|| {
-                    self.pool
-                        .transactions
-                        .keys()
-                        .next()
-                        .expect("we've just checked that the map is not empty")
-                });
-            self.pool.last_used_key = key;
-            let mut transactions =
-                self.pool.transactions.remove(&key).expect("just checked existence");
-            transactions.sort_by_key(|st| std::cmp::Reverse(st.transaction.nonce));
-            self.sorted_groups.push_back(TransactionGroup {
-                key,
-                transactions,
-                removed_transaction_hashes: vec![],
-            });
+            let group = self.take_next_group();
+            self.sorted_groups.push_back(group);
             Some(self.sorted_groups.back_mut().expect("just pushed"))
         } else {
             while let Some(sorted_group) = self.sorted_groups.pop_front() {
                 if sorted_group.transactions.is_empty() {
-                    for hash in sorted_group.removed_transaction_hashes {
-                        if self.pool.unique_transactions.remove(&hash) {
-                            metrics::TRANSACTION_POOL_TOTAL.dec();
-                        }
-                    }
+                    self.pool.remove_unique_transactions(sorted_group.removed_transaction_hashes);
                 } else {
                     self.sorted_groups.push_back(sorted_group);
                     return Some(self.sorted_groups.back_mut().expect("just pushed"));
@@ -198,12 +280,15 @@ impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
 /// removed from the pool's unique_transactions.
 impl<'a> Drop for PoolIteratorWrapper<'a> {
     fn drop(&mut self) {
-        for group in self.sorted_groups.drain(..) {
-            for hash in group.removed_transaction_hashes {
-                if self.pool.unique_transactions.remove(&hash) {
-                    metrics::TRANSACTION_POOL_TOTAL.dec();
-                }
-            }
+        let ranked_groups = std::mem::take(&mut self.ranked_groups);
+        let groups = self
+            .sorted_groups
+            .drain(..)
+            .chain(ranked_groups.into_values())
+            .chain(self.current_group.take())
+            .collect::<Vec<_>>();
+        for group in groups {
+            self.pool.remove_unique_transactions(group.removed_transaction_hashes);
             if !group.transactions.is_empty() {
                 self.pool.transactions.insert(group.key, group.transactions);
             }
@@ -219,10 +304,10 @@ mod tests {
     use rand::seq::SliceRandom;
     use rand::thread_rng;
 
-    use near_crypto::{InMemorySigner, KeyType};
+    use near_crypto::{InMemorySigner, KeyType, Signer};
 
     use near_primitives::hash::CryptoHash;
-    use near_primitives::types::Balance;
+    use near_primitives::transaction::Transaction;
 
     const TEST_SEED: RngSeed = [3; 32];
 
@@ -465,4 +550,129 @@ mod tests {
         new_nonces.sort();
         assert_ne!(nonces, new_nonces);
     }
+
+    fn priority_fee_transaction(
+        signer_id: &str,
+        nonce: u64,
+        priority_fee: Balance,
+    ) -> SignedTransaction {
+        let signer_id: AccountId = signer_id.parse().unwrap();
+        let signer =
+            InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, signer_id.as_ref());
+        Transaction {
+            signer_id,
+            public_key: signer.public_key(),
+            nonce,
+            receiver_id: "bob.near".parse().unwrap(),
+            block_hash: CryptoHash::default(),
+            actions: vec![],
+            priority_fee: Some(priority_fee),
+        }
+        .sign(&signer)
+    }
+
+    fn drain(pool: &mut TransactionPool, order: PoolOrder) -> Vec<SignedTransaction> {
+        let mut res = vec![];
+        let mut pool_iter = pool.pool_iterator_with_order(order);
+        while let Some(group) = pool_iter.next() {
+            if let Some(tx) = group.next() {
+                res.push(tx);
+            }
+        }
+        res
+    }
+
+    /// Groups are handed out by decreasing priority fee, whatever their key.
+    #[test]
+    fn test_priority_fee_order_between_groups() {
+        let mut transactions: Vec<_> = (0..10)
+            .map(|i| priority_fee_transaction(&format!("user_{}", i), 1, i * 10))
+            .chain((0..10).map(|i| priority_fee_transaction(&format!("user_{}", i), 2, i)))
+            .collect();
+        transactions.shuffle(&mut thread_rng());
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for tx in transactions {
+            pool.insert_transaction(tx);
+        }
+        let fees: Vec<_> =
+            drain(&mut pool, PoolOrder::PriorityFee).iter().map(priority_fee).collect();
+        // Once a group's first transaction is taken, it's ranked by its second one.
+        assert_eq!(fees, vec![90, 80, 70, 60, 50, 40, 30, 20, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0]);
+    }
+
+    /// A group is ranked by the fee of the transaction it hands out next, not by the highest fee
+    /// among its transactions.
+    #[test]
+    fn test_priority_fee_of_next_transaction() {
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for (signer_id, nonce, fee) in
+            [("alice.near", 1, 1), ("alice.near", 2, 1000), ("bob.near", 1, 100)]
+        {
+            pool.insert_transaction(priority_fee_transaction(signer_id, nonce, fee));
+        }
+        let order: Vec<_> = drain(&mut pool, PoolOrder::PriorityFee)
+            .iter()
+            .map(|tx| (tx.transaction.signer_id.to_string(), priority_fee(tx)))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("bob.near".to_string(), 100),
+                ("alice.near".to_string(), 1),
+                ("alice.near".to_string(), 1000)
+            ]
+        );
+        assert_eq!(pool.len(), 0);
+    }
+
+    /// Groups left in the iterator go back to the pool.
+    #[test]
+    fn test_priority_fee_iterator_drop() {
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for i in 0..5 {
+            pool.insert_transaction(priority_fee_transaction(&format!("user_{}", i), 1, i));
+            pool.insert_transaction(priority_fee_transaction(&format!("user_{}", i), 2, i));
+        }
+        {
+            let mut pool_iter = pool.pool_iterator_with_order(PoolOrder::PriorityFee);
+            let group = pool_iter.next().unwrap();
+            assert_eq!(priority_fee(&group.next().unwrap()), 4);
+            assert_eq!(priority_fee(&group.next().unwrap()), 4);
+            assert_eq!(priority_fee(&pool_iter.next().unwrap().next().unwrap()), 3);
+        }
+        assert_eq!(pool.len(), 7);
+        assert_eq!(drain(&mut pool, PoolOrder::PriorityFee).len(), 7);
+    }
+
+    /// Among transactions with the same nonce and key, the highest fee comes first, while nonce
+    /// order is still respected.
+    #[test]
+    fn test_priority_fee_order_within_group() {
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for (nonce, fee) in [(1, 1), (2, 1000), (1, 100), (1, 10)] {
+            pool.insert_transaction(priority_fee_transaction("alice.near", nonce, fee));
+        }
+        let mut pool_iter = pool.pool_iterator_with_order(PoolOrder::PriorityFee);
+        let group = pool_iter.next().unwrap();
+        let order: Vec<_> = std::iter::from_fn(|| group.next())
+            .map(|tx| (tx.transaction.nonce, priority_fee(&tx)))
+            .collect();
+        assert_eq!(order, vec![(1, 100), (1, 10), (1, 1), (2, 1000)]);
+    }
+
+    /// Without the priority fee order, the fee has no influence.
+    #[test]
+    fn test_round_robin_ignores_priority_fee() {
+        let expensive = priority_fee_transaction("alice.near", 1, 100);
+        let cheap = priority_fee_transaction("alice.near", 1, 1);
+        for (order, expected) in [
+            (PoolOrder::RoundRobin, vec![cheap.clone(), expensive.clone()]),
+            (PoolOrder::PriorityFee, vec![expensive.clone(), cheap.clone()]),
+        ] {
+            let mut pool = TransactionPool::new(TEST_SEED);
+            pool.insert_transaction(expensive.clone());
+            pool.insert_transaction(cheap.clone());
+            assert_eq!(drain(&mut pool, order), expected);
+        }
+    }
 }
@@ -9,6 +9,17 @@ pub trait PoolIterator {
     fn next(&mut self) -> Option<&mut TransactionGroup>;
 }
 
+/// Order in which a pool iterator hands out transaction groups.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolOrder {
+    /// Round robin over the groups, starting after the last used key.
+    RoundRobin,
+    /// The group whose next transaction has the highest priority fee first,
+    /// ties broken in round robin order.  Within a group, transactions with
+    /// the same nonce are ordered by decreasing priority fee.
+    PriorityFee,
+}
+
 /// A hash of (an AccountId, a PublicKey and a seed).
 /// Used to randomize the order of the keys.
 pub(crate) type PoolKey = CryptoHash;
@@ -154,7 +154,8 @@ pub(crate) async fn convert_block_to_transactions(
                     account_ids: touched_account_ids,
                 },
         })
-        .await??;
+        .await??
+        .changes;
 
     let runtime_config = crate::utils::query_protocol_config(block.header.hash, &view_client_addr)
         .await?
@@ -200,9 +201,12 @@ pub struct NearActions {
     pub actions: Vec<near_primitives::transaction::Action>,
 }
 
-impl From<NearActions> for Vec<crate::models::Operation> {
-    /// Convert NEAR Actions to Rosetta Operations. It never fails.
-    fn from(near_actions: NearActions) -> Self {
+impl TryFrom<NearActions> for Vec<crate::models::Operation> {
+    type Error = crate::errors::ErrorKind;
+
+    /// Convert NEAR Actions to Rosetta Operations.  It only fails for public
+    /// keys of an unknown key type, which Rosetta has no curve type for.
+    fn try_from(near_actions: NearActions) -> Result<Self, Self::Error> {
         let NearActions { sender_account_id, receiver_account_id, actions } = near_actions;
         let sender_account_identifier: crate::models::AccountIdentifier = sender_account_id.into();
         let receiver_account_identifier: crate::models::AccountIdentifier =
@@ -278,7 +282,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                     operations.push(
                         validated_operations::AddKeyOperation {
                             account: receiver_account_identifier.clone(),
-                            public_key: (&action.public_key).into(),
+                            public_key: (&action.public_key).try_into()?,
                         }
                         .into_related_operation(
                             add_key_operation_id,
@@ -300,7 +304,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                     operations.push(
                         validated_operations::DeleteKeyOperation {
                             account: receiver_account_identifier.clone(),
-                            public_key: (&action.public_key).into(),
+                            public_key: (&action.public_key).try_into()?,
                         }
                         .into_related_operation(
                             crate::models::OperationIdentifier::new(&operations),
@@ -339,7 +343,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                         validated_operations::StakeOperation {
                             account: receiver_account_identifier.clone(),
                             amount: action.stake,
-                            public_key: (&action.public_key).into(),
+                            public_key: (&action.public_key).try_into()?,
                         }
                         .into_operation(crate::models::OperationIdentifier::new(&operations)),
                     );
@@ -351,7 +355,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                         validated_operations::StakeOperation {
                             account: receiver_account_identifier.clone(),
                             amount: action.stake,
-                            public_key: (&action.public_key).into(),
+                            public_key: (&action.public_key).try_into()?,
                         }
                         .into_operation(crate::models::OperationIdentifier::new(&operations)),
                     );
@@ -421,7 +425,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                 }
             }
         }
-        operations
+        Ok(operations)
     }
 }
 
@@ -430,7 +434,7 @@ impl TryFrom<Vec<crate::models::Operation>> for NearActions {
 
     /// Convert Rosetta Operations to NEAR Actions.
     ///
-    /// See the inverted implementation of From<NearActions> for Vec<Operations>
+    /// See the inverted implementation of TryFrom<NearActions> for Vec<Operations>
     /// above to understand how a single NEAR Action is represented with Rosetta
     /// Operations. The implementations are bijective (there is a test below).
     fn try_from(operations: Vec<crate::models::Operation>) -> Result<Self, Self::Error> {
@@ -906,7 +910,8 @@ mod tests {
                 actions,
             };
             println!("NEAR Actions: {:#?}", near_actions);
-            let operations: Vec<crate::models::Operation> = near_actions.clone().into();
+            let operations: Vec<crate::models::Operation> =
+                near_actions.clone().try_into().unwrap();
             println!("Operations: {:#?}", operations);
 
             let near_actions_recreated = NearActions::try_from(operations).unwrap();
@@ -927,7 +932,8 @@ mod tests {
                 actions,
             };
             println!("NEAR Actions: {:#?}", near_actions);
-            let operations: Vec<crate::models::Operation> = near_actions.clone().into();
+            let operations: Vec<crate::models::Operation> =
+                near_actions.clone().try_into().unwrap();
             println!("Operations: {:#?}", operations);
 
             let near_actions_recreated = NearActions::try_from(operations).unwrap();
@@ -448,7 +448,7 @@ async fn construction_derive(
     let public_key: near_crypto::PublicKey = (&public_key)
         .try_into()
         .map_err(|_| errors::ErrorKind::InvalidInput("Invalid PublicKey".to_string()))?;
-    let address = if let near_crypto::KeyType::ED25519 = public_key.key_type() {
+    let address = if let Some(near_crypto::KeyType::ED25519) = public_key.try_key_type() {
         hex::encode(public_key.key_data())
     } else {
         return Err(errors::ErrorKind::InvalidInput(
@@ -599,6 +599,7 @@ async fn construction_payloads(
         nonce: signer_public_access_key_nonce,
         receiver_id: receiver_account_id,
         actions,
+        priority_fee: None,
     };
 
     let (transaction_hash, _) = unsigned_transaction.get_hash_and_size().clone();
@@ -607,7 +608,7 @@ async fn construction_payloads(
         unsigned_transaction: unsigned_transaction.into(),
         payloads: vec![models::SigningPayload {
             account_identifier: signer_account_id.into(),
-            signature_type: Some(signer_public_access_key.key_type().into()),
+            signature_type: signer_public_access_key.try_key_type().map(Into::into),
             hex_bytes: transaction_hash.as_ref().to_owned().into(),
         }],
     }))
@@ -697,7 +698,7 @@ async fn construction_parse(
 
     Ok(Json(models::ConstructionParseResponse {
         account_identifier_signers,
-        operations: near_actions.into(),
+        operations: near_actions.try_into()?,
     }))
 }
 
@@ -1084,10 +1084,19 @@ pub(crate) struct PublicKey {
     pub curve_type: CurveType,
 }
 
-impl From<&near_crypto::PublicKey> for PublicKey {
-    fn from(public_key: &near_crypto::PublicKey) -> Self {
+impl TryFrom<&near_crypto::PublicKey> for PublicKey {
+    type Error = crate::errors::ErrorKind;
+
+    /// Fails for keys of an unknown key type, which have no `CurveType`.
+    fn try_from(public_key: &near_crypto::PublicKey) -> Result<Self, Self::Error> {
         let hex_bytes = public_key.key_data().to_owned().into();
-        Self { hex_bytes, curve_type: public_key.key_type().into() }
+        let key_type = public_key.try_key_type().ok_or_else(|| {
+            crate::errors::ErrorKind::InvalidInput(format!(
+                "Public key {} is of an unknown key type",
+                public_key
+            ))
+        })?;
+        Ok(Self { hex_bytes, curve_type: key_type.into() })
     }
 }
 
@@ -1,8 +1,10 @@
 use super::AccountId;
 
+use std::collections::HashMap;
 use std::io::{Error, Write};
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::schema::{Declaration, Definition, Fields};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 
 impl BorshSerialize for AccountId {
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
@@ -23,6 +25,22 @@ impl BorshDeserialize for AccountId {
     }
 }
 
+/// Serialized as a string, which is described as a struct with a single
+/// string field to keep the `AccountId` name in the schema.
+impl BorshSchema for AccountId {
+    fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+        Self::add_definition(
+            Self::declaration(),
+            Definition::Struct { fields: Fields::UnnamedFields(vec![String::declaration()]) },
+            definitions,
+        );
+    }
+
+    fn declaration() -> Declaration {
+        "AccountId".to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
@@ -62,4 +80,18 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_borsh_schema() {
+        for account_id in OK_ACCOUNT_IDS.iter().cloned() {
+            let account_id = account_id.parse::<AccountId>().unwrap();
+            let bytes = borsh::try_to_vec_with_schema(&account_id).unwrap();
+            assert_eq!(borsh::try_from_slice_with_schema::<AccountId>(&bytes).unwrap(), account_id);
+            // Serialized like the string the schema describes.
+            assert_eq!(
+                account_id.try_to_vec().unwrap(),
+                account_id.to_string().try_to_vec().unwrap()
+            );
+        }
+    }
 }
@@ -7,6 +7,6 @@ pub use client_config::{
     MIN_GC_NUM_EPOCHS_TO_KEEP, TEST_STATE_SYNC_TIMEOUT,
 };
 pub use genesis_config::{
-    get_initial_supply, Genesis, GenesisConfig, GenesisRecords, GenesisValidationMode,
-    ProtocolConfig, ProtocolConfigView,
+    get_initial_supply, Genesis, GenesisConfig, GenesisJsonHasher, GenesisRecords,
+    GenesisValidationMode, ProtocolConfig, ProtocolConfigView,
 };
@@ -6,19 +6,23 @@ pub enum ParseKeyTypeError {
 
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum ParseKeyError {
-    #[error("unknown key type '{unknown_key_type}'")]
-    UnknownKeyType { unknown_key_type: String },
+    #[error("unknown curve '{unknown_curve}'")]
+    UnknownCurve { unknown_curve: String },
     #[error("invalid key length: expected the input of {expected_length} bytes, but {received_length} was given")]
     InvalidLength { expected_length: usize, received_length: usize },
+    #[error("invalid key encoding: {error_message}")]
+    InvalidEncoding { error_message: String },
     #[error("invalid key data: {error_message}")]
     InvalidData { error_message: String },
+    #[error("public key is not a point on the {key_type} curve")]
+    NotOnCurve { key_type: String },
 }
 
 impl From<ParseKeyTypeError> for ParseKeyError {
     fn from(err: ParseKeyTypeError) -> Self {
         match err {
             ParseKeyTypeError::UnknownKeyType { unknown_key_type } => {
-                Self::UnknownKeyType { unknown_key_type }
+                Self::UnknownCurve { unknown_curve: unknown_key_type }
             }
         }
     }
@@ -43,3 +47,11 @@ impl From<ParseKeyTypeError> for ParseSignatureError {
         }
     }
 }
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ConvertKeyError {
+    #[error("{key_type} keys can't be converted to VRF keys")]
+    UnsupportedKeyType { key_type: String },
+    #[error("public key doesn't correspond to a valid VRF public key")]
+    InvalidVrfPublicKey,
+}
@@ -1,4 +1,4 @@
-use crate::{signature, vrf, PublicKey};
+use crate::{signature, vrf, ConvertKeyError, PublicKey, SecretKey};
 use arrayref::array_ref;
 use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
 use curve25519_dalek::ristretto::RistrettoPoint;
@@ -9,7 +9,7 @@ pub fn is_valid_staking_key(public_key: &PublicKey) -> bool {
     // The valid staking key is ED25519, and can be converted to ristretto.
     match public_key {
         PublicKey::ED25519(key) => convert_public_key(key).is_some(),
-        PublicKey::SECP256K1(_) => false,
+        _ => false,
     }
 }
 
@@ -32,6 +32,37 @@ pub fn convert_secret_key(key: &signature::ED25519SecretKey) -> vrf::SecretKey {
     vrf::SecretKey::from_scalar(Scalar::from_bytes_mod_order(*array_ref!(&b, 0, 32)))
 }
 
+/// Converts an ED25519 public key into the VRF public key of the same
+/// validator.
+///
+/// There is no conversion in the other direction: the ristretto encoding
+/// doesn't preserve which of the equivalent edwards points the key came from.
+impl TryFrom<&PublicKey> for vrf::PublicKey {
+    type Error = ConvertKeyError;
+
+    fn try_from(public_key: &PublicKey) -> Result<Self, Self::Error> {
+        match public_key {
+            PublicKey::ED25519(key) => {
+                convert_public_key(key).ok_or(ConvertKeyError::InvalidVrfPublicKey)
+            }
+            _ => Err(ConvertKeyError::UnsupportedKeyType { key_type: public_key.key_type_name() }),
+        }
+    }
+}
+
+impl TryFrom<&SecretKey> for vrf::SecretKey {
+    type Error = ConvertKeyError;
+
+    fn try_from(secret_key: &SecretKey) -> Result<Self, Self::Error> {
+        match secret_key {
+            SecretKey::ED25519(key) => Ok(convert_secret_key(key)),
+            SecretKey::SECP256K1(_) => Err(ConvertKeyError::UnsupportedKeyType {
+                key_type: secret_key.key_type().to_string(),
+            }),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -54,4 +85,32 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_try_from() {
+        let sk = SecretKey::from_seed(signature::KeyType::ED25519, "test");
+        let vrf_sk = vrf::SecretKey::try_from(&sk).unwrap();
+        let vrf_pk = vrf::PublicKey::try_from(&sk.public_key()).unwrap();
+        assert_eq!(vrf_sk.public_key(), &vrf_pk);
+        let (value, proof) = vrf_sk.compute_vrf_with_proof(b"Test");
+        assert_eq!(proof.verify(&vrf_pk, b"Test", &value), Some(value));
+
+        let secp = SecretKey::from_seed(signature::KeyType::SECP256K1, "test");
+        assert!(matches!(
+            vrf::SecretKey::try_from(&secp),
+            Err(ConvertKeyError::UnsupportedKeyType { .. })
+        ));
+        assert!(matches!(
+            vrf::PublicKey::try_from(&secp.public_key()),
+            Err(ConvertKeyError::UnsupportedKeyType { .. })
+        ));
+
+        // A y coordinate with no matching x on the curve.
+        let off_curve = PublicKey::ED25519(signature::ED25519PublicKey([2; 32]));
+        assert!(CompressedEdwardsY([2; 32]).decompress().is_none());
+        assert!(matches!(
+            vrf::PublicKey::try_from(&off_curve),
+            Err(ConvertKeyError::InvalidVrfPublicKey)
+        ));
+    }
 }
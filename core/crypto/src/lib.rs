@@ -1,9 +1,10 @@
-pub use errors::{ParseKeyError, ParseKeyTypeError, ParseSignatureError};
+pub use errors::{ConvertKeyError, ParseKeyError, ParseKeyTypeError, ParseSignatureError};
 pub use key_file::KeyFile;
 pub use signature::{
     ED25519PublicKey, ED25519SecretKey, KeyType, PublicKey, Secp256K1PublicKey, Secp256K1Signature,
     SecretKey, Signature,
 };
+pub use signature_set::{SignatureSet, SignatureSetError};
 pub use signer::{EmptySigner, InMemorySigner, Signer};
 
 #[macro_use]
@@ -16,8 +17,11 @@ mod util;
 mod errors;
 pub mod key_conversion;
 mod key_file;
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
 pub mod randomness;
 mod signature;
+mod signature_set;
 mod signer;
 mod test_utils;
 pub mod vrf;
@@ -0,0 +1,353 @@
+//! Signer keeping its ED25519 key on a PKCS#11 token.
+//!
+//! Only the handful of PKCS#11 functions needed to find a key pair by label
+//! and sign with `CKM_EDDSA` are bound; the module is loaded at runtime so
+//! no vendor library is needed at build time.
+//!
+//! [`Pkcs11Signer`] deliberately doesn't implement [`crate::Signer`]: that
+//! trait can't report a failed signature, and validator keys also need VRF
+//! proofs, which can't be computed with a key that never leaves the token.
+use std::ffi::c_void;
+use std::os::raw::c_ulong;
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::signature::ED25519PublicKey;
+use crate::{PublicKey, Signature};
+
+type CkRv = c_ulong;
+type CkSessionHandle = c_ulong;
+type CkObjectHandle = c_ulong;
+
+const CKR_OK: CkRv = 0;
+const CKR_CRYPTOKI_ALREADY_INITIALIZED: CkRv = 0x191;
+const CKF_OS_LOCKING_OK: c_ulong = 0x2;
+const CKF_SERIAL_SESSION: c_ulong = 0x4;
+const CKU_USER: c_ulong = 1;
+const CKA_CLASS: c_ulong = 0x0;
+const CKA_LABEL: c_ulong = 0x3;
+const CKA_EC_POINT: c_ulong = 0x181;
+const CKO_PUBLIC_KEY: c_ulong = 2;
+const CKO_PRIVATE_KEY: c_ulong = 3;
+const CKM_EDDSA: c_ulong = 0x1057;
+
+#[repr(C)]
+struct CkAttribute {
+    type_: c_ulong,
+    value: *mut c_void,
+    value_len: c_ulong,
+}
+
+#[repr(C)]
+struct CkCInitializeArgs {
+    create_mutex: *mut c_void,
+    destroy_mutex: *mut c_void,
+    lock_mutex: *mut c_void,
+    unlock_mutex: *mut c_void,
+    flags: c_ulong,
+    reserved: *mut c_void,
+}
+
+#[repr(C)]
+struct CkMechanism {
+    mechanism: c_ulong,
+    parameter: *mut c_void,
+    parameter_len: c_ulong,
+}
+
+type InitializeFn = unsafe extern "C" fn(*mut CkCInitializeArgs) -> CkRv;
+type OpenSessionFn = unsafe extern "C" fn(
+    c_ulong,
+    c_ulong,
+    *mut c_void,
+    Option<unsafe extern "C" fn()>,
+    *mut CkSessionHandle,
+) -> CkRv;
+type CloseSessionFn = unsafe extern "C" fn(CkSessionHandle) -> CkRv;
+type LoginFn = unsafe extern "C" fn(CkSessionHandle, c_ulong, *const u8, c_ulong) -> CkRv;
+type FindObjectsInitFn = unsafe extern "C" fn(CkSessionHandle, *const CkAttribute, c_ulong) -> CkRv;
+type FindObjectsFn =
+    unsafe extern "C" fn(CkSessionHandle, *mut CkObjectHandle, c_ulong, *mut c_ulong) -> CkRv;
+type FindObjectsFinalFn = unsafe extern "C" fn(CkSessionHandle) -> CkRv;
+type GetAttributeValueFn =
+    unsafe extern "C" fn(CkSessionHandle, CkObjectHandle, *mut CkAttribute, c_ulong) -> CkRv;
+type SignInitFn = unsafe extern "C" fn(CkSessionHandle, *const CkMechanism, CkObjectHandle) -> CkRv;
+type SignFn =
+    unsafe extern "C" fn(CkSessionHandle, *const u8, c_ulong, *mut u8, *mut c_ulong) -> CkRv;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Pkcs11Error {
+    #[error("failed to load PKCS#11 module {path}: {error}")]
+    Load { path: PathBuf, error: String },
+    #[error("{function} failed with return value {rv:#x}")]
+    Call { function: &'static str, rv: c_ulong },
+    #[error("no {class} key with label {label:?} on the token")]
+    KeyNotFound { class: &'static str, label: String },
+    #[error("token returned an unexpected {what} of {len} bytes")]
+    UnexpectedLength { what: &'static str, len: usize },
+}
+
+/// Where to find the key on the token.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pkcs11Config {
+    /// Path to the vendor PKCS#11 shared library.
+    pub module_path: PathBuf,
+    pub slot: u64,
+    /// `CKA_LABEL` of both the private and the public key object.
+    pub key_label: String,
+    /// User PIN of the token.
+    pub pin: String,
+}
+
+/// Signer whose ED25519 private key never leaves a PKCS#11 token.
+///
+/// Every signature is a round trip to the token and may fail, so signing is
+/// only available through the fallible [`Pkcs11Signer::try_sign`].
+pub struct Pkcs11Signer {
+    library: libloading::Library,
+    session: Mutex<CkSessionHandle>,
+    private_key: CkObjectHandle,
+    public_key: PublicKey,
+}
+
+impl Pkcs11Signer {
+    /// Loads the module, logs into `config.slot` and looks up the key pair.
+    pub fn new(config: &Pkcs11Config) -> Result<Self, Pkcs11Error> {
+        let library = unsafe { libloading::Library::new(&config.module_path) }.map_err(|err| {
+            Pkcs11Error::Load { path: config.module_path.clone(), error: err.to_string() }
+        })?;
+        let mut signer = Self {
+            library,
+            session: Mutex::new(0),
+            private_key: 0,
+            public_key: PublicKey::empty(crate::KeyType::ED25519),
+        };
+        let session = signer.open_session(config)?;
+        *signer.session.get_mut().unwrap() = session;
+        signer.private_key = signer.find_key(session, CKO_PRIVATE_KEY, &config.key_label)?;
+        let public_key = signer.find_key(session, CKO_PUBLIC_KEY, &config.key_label)?;
+        signer.public_key = signer.read_public_key(session, public_key)?;
+        Ok(signer)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    /// Signs `data` on the token.
+    pub fn try_sign(&self, data: &[u8]) -> Result<Signature, Pkcs11Error> {
+        // A session runs one signing operation at a time, so the lock is held
+        // from `C_SignInit` until `C_Sign` has finished it.
+        let session = self.session.lock().unwrap();
+        let mechanism =
+            CkMechanism { mechanism: CKM_EDDSA, parameter: ptr::null_mut(), parameter_len: 0 };
+        let mut signature = [0u8; ed25519_dalek::SIGNATURE_LENGTH];
+        let mut signature_len = signature.len() as c_ulong;
+        unsafe {
+            let sign_init = self.function::<SignInitFn>(b"C_SignInit\0")?;
+            check("C_SignInit", sign_init(*session, &mechanism, self.private_key))?;
+            let sign = self.function::<SignFn>(b"C_Sign\0")?;
+            check(
+                "C_Sign",
+                sign(
+                    *session,
+                    data.as_ptr(),
+                    data.len() as c_ulong,
+                    signature.as_mut_ptr(),
+                    &mut signature_len,
+                ),
+            )?;
+        }
+        if signature_len as usize != ed25519_dalek::SIGNATURE_LENGTH {
+            return Err(Pkcs11Error::UnexpectedLength {
+                what: "signature",
+                len: signature_len as usize,
+            });
+        }
+        Ok(Signature::ED25519(ed25519_dalek::Signature::from_bytes(&signature).map_err(|_| {
+            Pkcs11Error::UnexpectedLength { what: "signature", len: signature.len() }
+        })?))
+    }
+
+    unsafe fn function<T>(&self, name: &[u8]) -> Result<libloading::Symbol<'_, T>, Pkcs11Error> {
+        self.library.get(name).map_err(|err| Pkcs11Error::Load {
+            path: PathBuf::from(String::from_utf8_lossy(&name[..name.len() - 1]).into_owned()),
+            error: err.to_string(),
+        })
+    }
+
+    fn open_session(&self, config: &Pkcs11Config) -> Result<CkSessionHandle, Pkcs11Error> {
+        let mut session = 0;
+        unsafe {
+            // The signer is shared between threads, so let the module use the
+            // OS primitives for locking.  Another signer in this process may
+            // have initialized it already.
+            let mut args = CkCInitializeArgs {
+                create_mutex: ptr::null_mut(),
+                destroy_mutex: ptr::null_mut(),
+                lock_mutex: ptr::null_mut(),
+                unlock_mutex: ptr::null_mut(),
+                flags: CKF_OS_LOCKING_OK,
+                reserved: ptr::null_mut(),
+            };
+            let initialize = self.function::<InitializeFn>(b"C_Initialize\0")?;
+            match initialize(&mut args) {
+                CKR_CRYPTOKI_ALREADY_INITIALIZED => {}
+                rv => check("C_Initialize", rv)?,
+            }
+            let open_session = self.function::<OpenSessionFn>(b"C_OpenSession\0")?;
+            check(
+                "C_OpenSession",
+                open_session(
+                    config.slot as c_ulong,
+                    CKF_SERIAL_SESSION,
+                    ptr::null_mut(),
+                    None,
+                    &mut session,
+                ),
+            )?;
+            let login = self.function::<LoginFn>(b"C_Login\0")?;
+            check(
+                "C_Login",
+                login(session, CKU_USER, config.pin.as_ptr(), config.pin.len() as c_ulong),
+            )?;
+        }
+        Ok(session)
+    }
+
+    fn find_key(
+        &self,
+        session: CkSessionHandle,
+        class: c_ulong,
+        label: &str,
+    ) -> Result<CkObjectHandle, Pkcs11Error> {
+        let mut class_value = class;
+        let template = [
+            CkAttribute {
+                type_: CKA_CLASS,
+                value: &mut class_value as *mut c_ulong as *mut c_void,
+                value_len: std::mem::size_of::<c_ulong>() as c_ulong,
+            },
+            CkAttribute {
+                type_: CKA_LABEL,
+                value: label.as_ptr() as *mut c_void,
+                value_len: label.len() as c_ulong,
+            },
+        ];
+        let mut object = 0;
+        let mut count = 0;
+        unsafe {
+            let find_init = self.function::<FindObjectsInitFn>(b"C_FindObjectsInit\0")?;
+            check(
+                "C_FindObjectsInit",
+                find_init(session, template.as_ptr(), template.len() as c_ulong),
+            )?;
+            let find = self.function::<FindObjectsFn>(b"C_FindObjects\0")?;
+            let found = check("C_FindObjects", find(session, &mut object, 1, &mut count));
+            let find_final = self.function::<FindObjectsFinalFn>(b"C_FindObjectsFinal\0")?;
+            check("C_FindObjectsFinal", find_final(session))?;
+            found?;
+        }
+        if count == 0 {
+            let class = if class == CKO_PRIVATE_KEY { "private" } else { "public" };
+            return Err(Pkcs11Error::KeyNotFound { class, label: label.to_string() });
+        }
+        Ok(object)
+    }
+
+    fn read_public_key(
+        &self,
+        session: CkSessionHandle,
+        object: CkObjectHandle,
+    ) -> Result<PublicKey, Pkcs11Error> {
+        // DER encoded OCTET STRING with the 32 byte key, i.e. `04 20 <key>`.
+        let mut ec_point = [0u8; ed25519_dalek::PUBLIC_KEY_LENGTH + 2];
+        let mut attribute = CkAttribute {
+            type_: CKA_EC_POINT,
+            value: ec_point.as_mut_ptr() as *mut c_void,
+            value_len: ec_point.len() as c_ulong,
+        };
+        unsafe {
+            let get_attribute = self.function::<GetAttributeValueFn>(b"C_GetAttributeValue\0")?;
+            check("C_GetAttributeValue", get_attribute(session, object, &mut attribute, 1))?;
+        }
+        let len = attribute.value_len as usize;
+        let key = match &ec_point[..len.min(ec_point.len())] {
+            [0x04, 0x20, key @ ..] if key.len() == ed25519_dalek::PUBLIC_KEY_LENGTH => key,
+            key if key.len() == ed25519_dalek::PUBLIC_KEY_LENGTH => key,
+            _ => return Err(Pkcs11Error::UnexpectedLength { what: "public key", len }),
+        };
+        let mut bytes = [0u8; ed25519_dalek::PUBLIC_KEY_LENGTH];
+        bytes.copy_from_slice(key);
+        Ok(PublicKey::ED25519(ED25519PublicKey(bytes)))
+    }
+}
+
+impl Drop for Pkcs11Signer {
+    fn drop(&mut self) {
+        let session = *self.session.get_mut().unwrap();
+        unsafe {
+            if let Ok(close_session) = self.function::<CloseSessionFn>(b"C_CloseSession\0") {
+                close_session(session);
+            }
+        }
+    }
+}
+
+fn check(function: &'static str, rv: CkRv) -> Result<(), Pkcs11Error> {
+    if rv == CKR_OK {
+        Ok(())
+    } else {
+        Err(Pkcs11Error::Call { function, rv })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs against SoftHSM when `NEAR_PKCS11_TEST_MODULE` points at
+    /// `libsofthsm2.so` and the token in `NEAR_PKCS11_TEST_SLOT` holds an
+    /// ED25519 key pair labelled `near-node-key` with user PIN `1234`, e.g.
+    ///
+    /// ```text
+    /// softhsm2-util --init-token --free --label near --pin 1234 --so-pin 1234
+    /// pkcs11-tool --module libsofthsm2.so --login --pin 1234 \
+    ///     --keypairgen --key-type EC:edwards25519 --label near-node-key
+    /// ```
+    #[test]
+    #[ignore = "needs SoftHSM, see NEAR_PKCS11_TEST_MODULE"]
+    fn test_softhsm_sign() {
+        let module_path = PathBuf::from(
+            std::env::var_os("NEAR_PKCS11_TEST_MODULE").expect("NEAR_PKCS11_TEST_MODULE not set"),
+        );
+        let slot = std::env::var("NEAR_PKCS11_TEST_SLOT").map_or(0, |slot| slot.parse().unwrap());
+        let config = Pkcs11Config {
+            module_path,
+            slot,
+            key_label: "near-node-key".to_string(),
+            pin: "1234".to_string(),
+        };
+        let signer = Pkcs11Signer::new(&config).unwrap();
+        let signature = signer.try_sign(b"hello").unwrap();
+        assert!(signature.verify(b"hello", &signer.public_key()));
+        assert!(!signature.verify(b"world", &signer.public_key()));
+
+        let wrong_label = Pkcs11Config { key_label: "missing".to_string(), ..config };
+        assert!(matches!(Pkcs11Signer::new(&wrong_label), Err(Pkcs11Error::KeyNotFound { .. })));
+    }
+
+    #[test]
+    fn test_missing_module() {
+        let config = Pkcs11Config {
+            module_path: PathBuf::from("/nonexistent/libpkcs11.so"),
+            slot: 0,
+            key_label: "near-node-key".to_string(),
+            pin: "1234".to_string(),
+        };
+        assert!(matches!(Pkcs11Signer::new(&config), Err(Pkcs11Error::Load { .. })));
+    }
+}
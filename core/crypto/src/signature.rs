@@ -1,17 +1,20 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::convert::AsRef;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::io::{Error, ErrorKind, Write};
 use std::str::FromStr;
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::schema::{Declaration, Definition, Fields};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use ed25519_dalek::ed25519::signature::{Signer, Verifier};
 use once_cell::sync::Lazy;
 use primitive_types::U256;
 use rand_core::OsRng;
 use secp256k1::Message;
 use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
 
 pub static SECP256K1: Lazy<secp256k1::Secp256k1> = Lazy::new(secp256k1::Secp256k1::new);
 
@@ -71,6 +74,15 @@ fn split_key_type_data(value: &str) -> Result<(KeyType, &str), crate::errors::Pa
     }
 }
 
+/// String prefix for the given borsh key type byte.  Unknown types are shown
+/// as `unknown<byte>`.
+fn key_type_name(key_type: u8) -> String {
+    match KeyType::try_from(key_type) {
+        Ok(key_type) => key_type.to_string(),
+        Err(_) => format!("unknown{}", key_type),
+    }
+}
+
 #[derive(Clone)]
 pub struct Secp256K1PublicKey([u8; 64]);
 
@@ -109,6 +121,15 @@ impl TryFrom<&[u8]> for Secp256K1PublicKey {
     }
 }
 
+impl Secp256K1PublicKey {
+    /// Whether the key is a valid uncompressed point on the curve.
+    pub fn is_on_curve(&self) -> bool {
+        let mut data = [4u8; 65];
+        data[1..].copy_from_slice(&self.0);
+        secp256k1::key::PublicKey::from_slice(&SECP256K1, &data).is_ok()
+    }
+}
+
 impl AsRef<[u8]> for Secp256K1PublicKey {
     fn as_ref(&self) -> &[u8] {
         &self.0
@@ -127,9 +148,10 @@ impl From<Secp256K1PublicKey> for [u8; 64] {
     }
 }
 
+/// Compares in constant time, keys being compared against attacker-influenced ones.
 impl PartialEq for Secp256K1PublicKey {
     fn eq(&self, other: &Self) -> bool {
-        self.0[..] == other.0[..]
+        self.0[..].ct_eq(&other.0[..]).into()
     }
 }
 
@@ -175,9 +197,10 @@ impl std::fmt::Debug for ED25519PublicKey {
     }
 }
 
+/// Compares in constant time, keys being compared against attacker-influenced ones.
 impl PartialEq for ED25519PublicKey {
     fn eq(&self, other: &Self) -> bool {
-        self.0[..] == other.0[..]
+        self.0[..].ct_eq(&other.0[..]).into()
     }
 }
 
@@ -197,12 +220,20 @@ impl Ord for ED25519PublicKey {
 
 /// Public key container supporting different curves.
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(Clone, PartialEq, PartialOrd, Ord, Eq)]
+#[derive(Clone, PartialOrd, Ord, Eq)]
 pub enum PublicKey {
     /// 256 bit elliptic curve based public-key.
     ED25519(ED25519PublicKey),
     /// 512 bit elliptic curve based public-key used in Bitcoin's public-key cryptography.
     SECP256K1(Secp256K1PublicKey),
+    /// Key of a curve this version doesn't know, kept as raw bytes so that
+    /// data produced by newer nodes can still be decoded and displayed.
+    /// Never verifies any signature.
+    ///
+    /// Its borsh payload is length-prefixed like a `Vec<u8>`, so that it can
+    /// be delimited inside an enclosing value.
+    #[cfg(feature = "unknown_key_types")]
+    Unknown { key_type: u8, data: Vec<u8> },
 }
 
 impl PublicKey {
@@ -210,6 +241,8 @@ impl PublicKey {
         match self {
             Self::ED25519(_) => ed25519_dalek::PUBLIC_KEY_LENGTH + 1,
             Self::SECP256K1(_) => 65,
+            #[cfg(feature = "unknown_key_types")]
+            Self::Unknown { data, .. } => data.len() + 5,
         }
     }
 
@@ -222,10 +255,21 @@ impl PublicKey {
         }
     }
 
+    /// # Panics
+    ///
+    /// Panics for `Unknown` keys, which have no `KeyType`, see
+    /// [`PublicKey::try_key_type`].
     pub fn key_type(&self) -> KeyType {
+        self.try_key_type().unwrap_or_else(|| panic!("unknown key type {}", self.key_type_byte()))
+    }
+
+    /// Returns `None` for `Unknown` keys.
+    pub fn try_key_type(&self) -> Option<KeyType> {
         match self {
-            Self::ED25519(_) => KeyType::ED25519,
-            Self::SECP256K1(_) => KeyType::SECP256K1,
+            Self::ED25519(_) => Some(KeyType::ED25519),
+            Self::SECP256K1(_) => Some(KeyType::SECP256K1),
+            #[cfg(feature = "unknown_key_types")]
+            Self::Unknown { .. } => None,
         }
     }
 
@@ -233,15 +277,56 @@ impl PublicKey {
         match self {
             Self::ED25519(key) => key.as_ref(),
             Self::SECP256K1(key) => key.as_ref(),
+            #[cfg(feature = "unknown_key_types")]
+            Self::Unknown { data, .. } => data,
         }
     }
 
     pub fn unwrap_as_ed25519(&self) -> &ED25519PublicKey {
         match self {
             Self::ED25519(key) => key,
-            Self::SECP256K1(_) => panic!(),
+            _ => panic!(),
         }
     }
+
+    /// Key type as encoded in borsh, also defined for `Unknown` keys.
+    fn key_type_byte(&self) -> u8 {
+        match self {
+            Self::ED25519(_) => KeyType::ED25519 as u8,
+            Self::SECP256K1(_) => KeyType::SECP256K1 as u8,
+            #[cfg(feature = "unknown_key_types")]
+            Self::Unknown { key_type, .. } => *key_type,
+        }
+    }
+
+    /// Prefix of the string representation.
+    pub(crate) fn key_type_name(&self) -> String {
+        key_type_name(self.key_type_byte())
+    }
+
+    /// Whether the key is a valid curve point.  Only SECP256K1 keys are
+    /// checked, ED25519 keys are always considered valid here.
+    pub fn is_on_curve(&self) -> bool {
+        match self {
+            Self::ED25519(_) => true,
+            Self::SECP256K1(key) => key.is_on_curve(),
+            #[cfg(feature = "unknown_key_types")]
+            Self::Unknown { .. } => false,
+        }
+    }
+
+    /// Compares in constant time, for comparing against attacker-influenced
+    /// keys.  Same as `==`, spelled out where it matters.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        self.key_type_byte() == other.key_type_byte()
+            && bool::from(self.key_data().ct_eq(other.key_data()))
+    }
+}
+
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
 }
 
 // This `Hash` implementation is safe since it retains the property
@@ -249,16 +334,8 @@ impl PublicKey {
 #[allow(clippy::derive_hash_xor_eq)]
 impl Hash for PublicKey {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        match self {
-            PublicKey::ED25519(public_key) => {
-                state.write_u8(0u8);
-                state.write(&public_key.0);
-            }
-            PublicKey::SECP256K1(public_key) => {
-                state.write_u8(1u8);
-                state.write(&public_key.0);
-            }
-        }
+        state.write_u8(self.key_type_byte());
+        state.write(self.key_data());
     }
 }
 
@@ -276,24 +353,28 @@ impl Debug for PublicKey {
 
 impl BorshSerialize for PublicKey {
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        BorshSerialize::serialize(&self.key_type_byte(), writer)?;
         match self {
-            PublicKey::ED25519(public_key) => {
-                BorshSerialize::serialize(&0u8, writer)?;
-                writer.write_all(&public_key.0)?;
-            }
-            PublicKey::SECP256K1(public_key) => {
-                BorshSerialize::serialize(&1u8, writer)?;
-                writer.write_all(&public_key.0)?;
-            }
+            #[cfg(feature = "unknown_key_types")]
+            Self::Unknown { data, .. } => BorshSerialize::serialize(data, writer),
+            _ => writer.write_all(self.key_data()),
         }
-        Ok(())
     }
 }
 
 impl BorshDeserialize for PublicKey {
     fn deserialize(buf: &mut &[u8]) -> Result<Self, Error> {
-        let key_type = KeyType::try_from(<u8 as BorshDeserialize>::deserialize(buf)?)
-            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        let raw_key_type = <u8 as BorshDeserialize>::deserialize(buf)?;
+        let key_type = match KeyType::try_from(raw_key_type) {
+            Ok(key_type) => key_type,
+            #[cfg(feature = "unknown_key_types")]
+            Err(_) => {
+                let data = BorshDeserialize::deserialize(buf)?;
+                return Ok(PublicKey::Unknown { key_type: raw_key_type, data });
+            }
+            #[cfg(not(feature = "unknown_key_types"))]
+            Err(err) => return Err(Error::new(ErrorKind::InvalidData, err.to_string())),
+        };
         match key_type {
             KeyType::ED25519 => {
                 Ok(PublicKey::ED25519(ED25519PublicKey(BorshDeserialize::deserialize(buf)?)))
@@ -305,6 +386,56 @@ impl BorshDeserialize for PublicKey {
     }
 }
 
+/// Keys of unknown types aren't part of the schema.
+impl BorshSchema for PublicKey {
+    fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+        add_key_type_definitions::<Self, [u8; 32], [u8; 64]>(
+            "ED25519PublicKey",
+            "Secp256K1PublicKey",
+            definitions,
+        );
+    }
+
+    fn declaration() -> Declaration {
+        "PublicKey".to_string()
+    }
+}
+
+/// Adds the definitions of a key or a signature: an enum with a variant per key
+/// type, each holding the raw bytes of the given length.
+fn add_key_type_definitions<
+    T: BorshSchema,
+    Ed25519Data: BorshSchema,
+    Secp256K1Data: BorshSchema,
+>(
+    ed25519_declaration: &str,
+    secp256k1_declaration: &str,
+    definitions: &mut HashMap<Declaration, Definition>,
+) {
+    Ed25519Data::add_definitions_recursively(definitions);
+    Secp256K1Data::add_definitions_recursively(definitions);
+    T::add_definition(
+        ed25519_declaration.to_string(),
+        Definition::Struct { fields: Fields::UnnamedFields(vec![Ed25519Data::declaration()]) },
+        definitions,
+    );
+    T::add_definition(
+        secp256k1_declaration.to_string(),
+        Definition::Struct { fields: Fields::UnnamedFields(vec![Secp256K1Data::declaration()]) },
+        definitions,
+    );
+    T::add_definition(
+        T::declaration(),
+        Definition::Enum {
+            variants: vec![
+                ("ED25519".to_string(), ed25519_declaration.to_string()),
+                ("SECP256K1".to_string(), secp256k1_declaration.to_string()),
+            ],
+        },
+        definitions,
+    );
+}
+
 impl serde::Serialize for PublicKey {
     fn serialize<S>(
         &self,
@@ -330,50 +461,32 @@ impl<'de> serde::Deserialize<'de> for PublicKey {
 
 impl From<&PublicKey> for String {
     fn from(public_key: &PublicKey) -> Self {
-        match public_key {
-            PublicKey::ED25519(public_key) => {
-                format!("{}:{}", KeyType::ED25519, bs58::encode(&public_key.0).into_string())
-            }
-            PublicKey::SECP256K1(public_key) => format!(
-                "{}:{}",
-                KeyType::SECP256K1,
-                bs58::encode(&public_key.0.to_vec()).into_string()
-            ),
-        }
+        format!(
+            "{}:{}",
+            public_key.key_type_name(),
+            bs58::encode(public_key.key_data()).into_string()
+        )
     }
 }
 
 impl FromStr for PublicKey {
     type Err = crate::errors::ParseKeyError;
 
+    /// Parses `<key type>:<base58 data>`.  Unknown curves, encoding errors and
+    /// wrong lengths are reported as distinct errors.
+    ///
+    /// SECP256K1 keys which are not points on the curve are accepted, since
+    /// such keys may already be stored in access keys.  The runtime rejects
+    /// new ones, see [`PublicKey::is_on_curve`].
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         let (key_type, key_data) = split_key_type_data(value)?;
+        let data = bs58::decode(key_data)
+            .into_vec()
+            .map_err(|err| Self::Err::InvalidEncoding { error_message: err.to_string() })?;
         match key_type {
-            KeyType::ED25519 => {
-                let mut array = [0; ed25519_dalek::PUBLIC_KEY_LENGTH];
-                let length = bs58::decode(key_data)
-                    .into(&mut array)
-                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
-                if length != ed25519_dalek::PUBLIC_KEY_LENGTH {
-                    return Err(Self::Err::InvalidLength {
-                        expected_length: ed25519_dalek::PUBLIC_KEY_LENGTH,
-                        received_length: length,
-                    });
-                }
-                Ok(PublicKey::ED25519(ED25519PublicKey(array)))
-            }
+            KeyType::ED25519 => Ok(PublicKey::ED25519(ED25519PublicKey::try_from(&data[..])?)),
             KeyType::SECP256K1 => {
-                let mut array = [0; 64];
-                let length = bs58::decode(key_data)
-                    .into(&mut array[..])
-                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
-                if length != 64 {
-                    return Err(Self::Err::InvalidLength {
-                        expected_length: 64,
-                        received_length: length,
-                    });
-                }
-                Ok(PublicKey::SECP256K1(Secp256K1PublicKey(array)))
+                Ok(PublicKey::SECP256K1(Secp256K1PublicKey::try_from(&data[..])?))
             }
         }
     }
@@ -397,9 +510,13 @@ impl From<Secp256K1PublicKey> for PublicKey {
 // The last PUBLIC_KEY_LENGTH of bytes is the public key, in total it's KEYPAIR_LENGTH
 pub struct ED25519SecretKey(pub [u8; ed25519_dalek::KEYPAIR_LENGTH]);
 
-impl PartialEq for ED25519SecretKey {
-    fn eq(&self, other: &Self) -> bool {
-        self.0[..ed25519_dalek::SECRET_KEY_LENGTH] == other.0[..ed25519_dalek::SECRET_KEY_LENGTH]
+impl ED25519SecretKey {
+    /// Compares in constant time to avoid leaking secret material.  Same as
+    /// `==`, spelled out where it matters.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        self.0[..ed25519_dalek::SECRET_KEY_LENGTH]
+            .ct_eq(&other.0[..ed25519_dalek::SECRET_KEY_LENGTH])
+            .into()
     }
 }
 
@@ -413,16 +530,40 @@ impl std::fmt::Debug for ED25519SecretKey {
     }
 }
 
-impl Eq for ED25519SecretKey {}
-
 /// Secret key container supporting different curves.
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Debug)]
 pub enum SecretKey {
     ED25519(ED25519SecretKey),
     SECP256K1(secp256k1::key::SecretKey),
 }
 
+impl PartialEq for ED25519SecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
+impl Eq for ED25519SecretKey {}
+
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
+impl Eq for SecretKey {}
+
 impl SecretKey {
+    /// Compares in constant time to avoid leaking secret material.  Same as
+    /// `==`, spelled out where it matters.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SecretKey::ED25519(a), SecretKey::ED25519(b)) => a.ct_eq(b),
+            (SecretKey::SECP256K1(a), SecretKey::SECP256K1(b)) => a[..].ct_eq(&b[..]).into(),
+            _ => false,
+        }
+    }
+
     pub fn key_type(&self) -> KeyType {
         match self {
             SecretKey::ED25519(_) => KeyType::ED25519,
@@ -509,7 +650,7 @@ impl FromStr for SecretKey {
                 let mut array = [0; ed25519_dalek::KEYPAIR_LENGTH];
                 let length = bs58::decode(key_data)
                     .into(&mut array[..])
-                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
+                    .map_err(|err| Self::Err::InvalidEncoding { error_message: err.to_string() })?;
                 if length != ed25519_dalek::KEYPAIR_LENGTH {
                     return Err(Self::Err::InvalidLength {
                         expected_length: ed25519_dalek::KEYPAIR_LENGTH,
@@ -522,7 +663,7 @@ impl FromStr for SecretKey {
                 let mut array = [0; secp256k1::constants::SECRET_KEY_SIZE];
                 let length = bs58::decode(key_data)
                     .into(&mut array[..])
-                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
+                    .map_err(|err| Self::Err::InvalidEncoding { error_message: err.to_string() })?;
                 if length != secp256k1::constants::SECRET_KEY_SIZE {
                     return Err(Self::Err::InvalidLength {
                         expected_length: secp256k1::constants::SECRET_KEY_SIZE,
@@ -571,12 +712,16 @@ const SECP256K1_N: U256 =
 const SECP256K1_N_HALF_ONE: U256 =
     U256([0xdfe92f46681b20a1, 0x5d576e7357a4501d, 0xffffffffffffffff, 0x7fffffffffffffff]);
 
-const SECP256K1_SIGNATURE_LENGTH: usize = 65;
+pub(crate) const SECP256K1_SIGNATURE_LENGTH: usize = 65;
 
 #[derive(Clone, Hash)]
 pub struct Secp256K1Signature([u8; SECP256K1_SIGNATURE_LENGTH]);
 
 impl Secp256K1Signature {
+    fn ct_eq(&self, other: &Self) -> bool {
+        self.0[..].ct_eq(&other.0[..]).into()
+    }
+
     pub fn check_signature_values(&self, reject_upper: bool) -> bool {
         let mut r_bytes = [0u8; 32];
         r_bytes.copy_from_slice(&self.0[0..32]);
@@ -656,7 +801,7 @@ impl Eq for Secp256K1Signature {}
 
 impl PartialEq for Secp256K1Signature {
     fn eq(&self, other: &Self) -> bool {
-        self.0[..].eq(&other.0[..])
+        self.ct_eq(other)
     }
 }
 
@@ -673,10 +818,16 @@ impl From<Secp256K1Signature> for [u8; 65] {
 }
 
 /// Signature container supporting different curves.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, Eq)]
 pub enum Signature {
     ED25519(ed25519_dalek::Signature),
     SECP256K1(Secp256K1Signature),
+    /// Signature of a curve this version doesn't know, see `PublicKey::Unknown`.
+    #[cfg(feature = "unknown_key_types")]
+    Unknown {
+        key_type: u8,
+        data: Vec<u8>,
+    },
 }
 
 #[cfg(feature = "deepsize_feature")]
@@ -685,6 +836,8 @@ impl deepsize::DeepSizeOf for Signature {
         match self {
             Signature::ED25519(_) => ed25519_dalek::SIGNATURE_LENGTH,
             Signature::SECP256K1(_) => SECP256K1_SIGNATURE_LENGTH,
+            #[cfg(feature = "unknown_key_types")]
+            Signature::Unknown { data, .. } => data.len(),
         }
     }
 }
@@ -694,6 +847,8 @@ impl Hash for Signature {
         match self {
             Signature::ED25519(sig) => sig.to_bytes().hash(state),
             Signature::SECP256K1(sig) => sig.hash(state),
+            #[cfg(feature = "unknown_key_types")]
+            Signature::Unknown { key_type, data } => (key_type, data).hash(state),
         };
     }
 }
@@ -758,12 +913,67 @@ impl Signature {
         }
     }
 
+    /// # Panics
+    ///
+    /// Panics for `Unknown` signatures, which have no `KeyType`, see
+    /// [`Signature::try_key_type`].
     pub fn key_type(&self) -> KeyType {
+        self.try_key_type().unwrap_or_else(|| panic!("unknown key type {}", self.key_type_byte()))
+    }
+
+    /// Returns `None` for `Unknown` signatures.
+    pub fn try_key_type(&self) -> Option<KeyType> {
         match self {
-            Signature::ED25519(_) => KeyType::ED25519,
-            Signature::SECP256K1(_) => KeyType::SECP256K1,
+            Signature::ED25519(_) => Some(KeyType::ED25519),
+            Signature::SECP256K1(_) => Some(KeyType::SECP256K1),
+            #[cfg(feature = "unknown_key_types")]
+            Signature::Unknown { .. } => None,
         }
     }
+
+    /// Key type as encoded in borsh, also defined for `Unknown` signatures.
+    fn key_type_byte(&self) -> u8 {
+        match self {
+            Signature::ED25519(_) => KeyType::ED25519 as u8,
+            Signature::SECP256K1(_) => KeyType::SECP256K1 as u8,
+            #[cfg(feature = "unknown_key_types")]
+            Signature::Unknown { key_type, .. } => *key_type,
+        }
+    }
+
+    fn data(&self) -> Vec<u8> {
+        match self {
+            Signature::ED25519(signature) => signature.to_bytes().to_vec(),
+            Signature::SECP256K1(signature) => signature.0.to_vec(),
+            #[cfg(feature = "unknown_key_types")]
+            Signature::Unknown { data, .. } => data.clone(),
+        }
+    }
+
+    /// Compares in constant time, for comparing against attacker-influenced
+    /// signatures.  Same as `==`, spelled out where it matters.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Signature::ED25519(a), Signature::ED25519(b)) => {
+                a.to_bytes()[..].ct_eq(&b.to_bytes()[..]).into()
+            }
+            (Signature::SECP256K1(a), Signature::SECP256K1(b)) => a.ct_eq(b),
+            #[cfg(feature = "unknown_key_types")]
+            (
+                Signature::Unknown { key_type: a_type, data: a },
+                Signature::Unknown { key_type: b_type, data: b },
+            ) => a_type == b_type && bool::from(a[..].ct_eq(&b[..])),
+            _ => false,
+        }
+    }
+}
+
+/// Compares in constant time, which also covers the signatures used as keys
+/// of hash maps, such as the routed message cache of the network.
+impl PartialEq for Signature {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
 }
 
 impl Default for Signature {
@@ -774,24 +984,28 @@ impl Default for Signature {
 
 impl BorshSerialize for Signature {
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        BorshSerialize::serialize(&self.key_type_byte(), writer)?;
         match self {
-            Signature::ED25519(signature) => {
-                BorshSerialize::serialize(&0u8, writer)?;
-                writer.write_all(&signature.to_bytes())?;
-            }
-            Signature::SECP256K1(signature) => {
-                BorshSerialize::serialize(&1u8, writer)?;
-                writer.write_all(&signature.0)?;
-            }
+            #[cfg(feature = "unknown_key_types")]
+            Signature::Unknown { data, .. } => BorshSerialize::serialize(data, writer),
+            _ => writer.write_all(&self.data()),
         }
-        Ok(())
     }
 }
 
 impl BorshDeserialize for Signature {
     fn deserialize(buf: &mut &[u8]) -> Result<Self, Error> {
-        let key_type = KeyType::try_from(<u8 as BorshDeserialize>::deserialize(buf)?)
-            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        let raw_key_type = <u8 as BorshDeserialize>::deserialize(buf)?;
+        let key_type = match KeyType::try_from(raw_key_type) {
+            Ok(key_type) => key_type,
+            #[cfg(feature = "unknown_key_types")]
+            Err(_) => {
+                let data = BorshDeserialize::deserialize(buf)?;
+                return Ok(Signature::Unknown { key_type: raw_key_type, data });
+            }
+            #[cfg(not(feature = "unknown_key_types"))]
+            Err(err) => return Err(Error::new(ErrorKind::InvalidData, err.to_string())),
+        };
         match key_type {
             KeyType::ED25519 => {
                 let array: [u8; ed25519_dalek::SIGNATURE_LENGTH] =
@@ -809,15 +1023,25 @@ impl BorshDeserialize for Signature {
     }
 }
 
+/// Signatures of unknown key types aren't part of the schema.
+impl BorshSchema for Signature {
+    fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+        add_key_type_definitions::<Self, [u8; 64], [u8; 65]>(
+            "ED25519Signature",
+            "Secp256K1Signature",
+            definitions,
+        );
+    }
+
+    fn declaration() -> Declaration {
+        "Signature".to_string()
+    }
+}
+
 impl Display for Signature {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        let data = match self {
-            Signature::ED25519(signature) => {
-                bs58::encode(&signature.to_bytes().to_vec()).into_string()
-            }
-            Signature::SECP256K1(signature) => bs58::encode(&signature.0[..]).into_string(),
-        };
-        write!(f, "{}", format!("{}:{}", self.key_type(), data))
+        let key_type = key_type_name(self.key_type_byte());
+        write!(f, "{}:{}", key_type, bs58::encode(self.data()).into_string())
     }
 }
 
@@ -922,7 +1146,7 @@ mod tests {
 
         let expected = "\"ed25519:3KyUuch8pYP47krBq4DosFEVBMR5wDTMQ8AThzM8kAEcBQEpsPdYTZ2FPX5ZnSoLrerjwg66hwwJaW1wHzprd5k3\"";
         assert_eq!(serde_json::to_string(&sk).unwrap(), expected);
-        assert_eq!(sk, serde_json::from_str(expected).unwrap());
+        assert!(sk.ct_eq(&serde_json::from_str(expected).unwrap()));
 
         let signature = sk.sign(b"123");
         let expected = "\"ed25519:3s1dvZdQtcAjBksMHFrysqvF63wnyMHPA4owNQmCJZ2EBakZEKdtMsLqrHdKWQjJbSRN6kRknN2WdwSBLWGCokXj\"";
@@ -948,7 +1172,7 @@ mod tests {
 
         let expected = "\"secp256k1:9ZNzLxNff6ohoFFGkbfMBAFpZgD7EPoWeiuTpPAeeMRV\"";
         assert_eq!(serde_json::to_string(&sk).unwrap(), expected);
-        assert_eq!(sk, serde_json::from_str(expected).unwrap());
+        assert!(sk.ct_eq(&serde_json::from_str(expected).unwrap()));
 
         let signature = sk.sign(&data);
         let expected = "\"secp256k1:7iA75xRmHw17MbUkSpHxBHFVTuJW6jngzbuJPJutwb3EAwVw21wrjpMHU7fFTAqH7D3YEma8utCdvdtsqcAWqnC7r\"";
@@ -978,6 +1202,69 @@ mod tests {
         }
     }
 
+    /// Length of the bytes held by the variant for `key_type` in the schema.
+    fn schema_key_len<T: BorshSchema>(key_type: KeyType) -> u32 {
+        let container = T::schema_container();
+        let variant = match &container.definitions[&container.declaration] {
+            Definition::Enum { variants } => &variants[key_type as usize].1,
+            definition => panic!("unexpected definition {:?}", definition),
+        };
+        let array = match &container.definitions[variant] {
+            Definition::Struct { fields: Fields::UnnamedFields(fields) } => &fields[0],
+            definition => panic!("unexpected definition {:?}", definition),
+        };
+        match &container.definitions[array] {
+            Definition::Array { length, elements } if elements == "u8" => *length,
+            definition => panic!("unexpected definition {:?}", definition),
+        }
+    }
+
+    #[test]
+    fn test_borsh_schema() {
+        use sha2::Digest;
+        let data = sha2::Sha256::digest(b"123").to_vec();
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+            let sk = SecretKey::from_seed(key_type, "test");
+            let pk = sk.public_key();
+            let bytes = borsh::try_to_vec_with_schema(&pk).unwrap();
+            assert_eq!(borsh::try_from_slice_with_schema::<PublicKey>(&bytes).unwrap(), pk);
+            assert_eq!(schema_key_len::<PublicKey>(key_type) as usize, pk.key_data().len());
+
+            let signature = sk.sign(&data);
+            let bytes = borsh::try_to_vec_with_schema(&signature).unwrap();
+            assert_eq!(borsh::try_from_slice_with_schema::<Signature>(&bytes).unwrap(), signature);
+            assert_eq!(
+                schema_key_len::<Signature>(key_type) as usize,
+                signature.try_to_vec().unwrap().len() - 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_ct_eq_matches_eq() {
+        use sha2::Digest;
+        let data = sha2::Sha256::digest(b"123").to_vec();
+        let mut secret_keys = vec![];
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+            for seed in ["test", "test1"] {
+                secret_keys.push(SecretKey::from_seed(key_type, seed));
+            }
+            secret_keys.push(SecretKey::from_random(key_type));
+        }
+        for a in &secret_keys {
+            for b in &secret_keys {
+                let same = a.to_string() == b.to_string();
+                assert_eq!(a.ct_eq(b), same);
+                assert_eq!(a == b, same);
+                assert_eq!(a.public_key() == b.public_key(), same);
+                assert_eq!(a.public_key().ct_eq(&b.public_key()), same);
+                let (sig_a, sig_b) = (a.sign(&data), b.sign(&data));
+                assert_eq!(sig_a == sig_b, sig_a.ct_eq(&sig_b));
+                assert_eq!(sig_a.ct_eq(&sig_b), same);
+            }
+        }
+    }
+
     #[test]
     fn test_invalid_data() {
         let invalid = "\"secp256k1:2xVqteU8PWhadHTv99TGh3bSf\"";
@@ -985,4 +1272,128 @@ mod tests {
         assert!(serde_json::from_str::<SecretKey>(invalid).is_err());
         assert!(serde_json::from_str::<Signature>(invalid).is_err());
     }
+
+    #[test]
+    fn test_public_key_display_from_str_round_trip() {
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+            for seed in ["test", "alice.near", ""] {
+                let public_key = PublicKey::from_seed(key_type, seed);
+                let public_key_str = public_key.to_string();
+                assert!(public_key_str.starts_with(&format!("{}:", key_type)));
+                assert_eq!(public_key_str.parse::<PublicKey>().unwrap(), public_key);
+
+                let json = serde_json::to_string(&public_key).unwrap();
+                assert_eq!(json, format!("\"{}\"", public_key_str));
+                assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), public_key);
+            }
+            let random = SecretKey::from_random(key_type).public_key();
+            assert_eq!(random.to_string().parse::<PublicKey>().unwrap(), random);
+        }
+    }
+
+    #[test]
+    fn test_public_key_parse_errors() {
+        use crate::errors::ParseKeyError;
+
+        assert!(matches!(
+            "foo:DcA2MzgpJbrUATQLLceocVckhhAqrkingax4oJ9kZ847".parse::<PublicKey>(),
+            Err(ParseKeyError::UnknownCurve { .. })
+        ));
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+            let public_key = PublicKey::from_seed(key_type, "test");
+            let expected_length = public_key.key_data().len();
+
+            // Alphabet error ('0', 'O', 'I' and 'l' are not part of base58).
+            let invalid = format!("{}:0OIl", key_type);
+            assert!(matches!(
+                invalid.parse::<PublicKey>(),
+                Err(ParseKeyError::InvalidEncoding { .. })
+            ));
+
+            for length in [expected_length - 1, expected_length + 1] {
+                let data = bs58::encode(vec![1u8; length]).into_string();
+                match format!("{}:{}", key_type, data).parse::<PublicKey>() {
+                    Err(ParseKeyError::InvalidLength {
+                        expected_length: e,
+                        received_length: r,
+                    }) => {
+                        assert_eq!((e, r), (expected_length, length))
+                    }
+                    other => panic!("unexpected result {:?}", other),
+                }
+            }
+        }
+
+        // Right length, but not a point on the curve: parsed anyway, for the
+        // runtime to decide.
+        let off_curve = format!("secp256k1:{}", bs58::encode([1u8; 64]).into_string());
+        let public_key = off_curve.parse::<PublicKey>().unwrap();
+        assert!(!public_key.is_on_curve());
+        assert_eq!(public_key.to_string(), off_curve);
+        let json = format!("\"{}\"", off_curve);
+        assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), public_key);
+        assert!(!PublicKey::empty(KeyType::SECP256K1).is_on_curve());
+        assert!(PublicKey::from_seed(KeyType::SECP256K1, "test").is_on_curve());
+    }
+
+    #[test]
+    #[cfg(not(feature = "unknown_key_types"))]
+    fn test_unknown_key_type_rejected() {
+        let mut bytes = PublicKey::from_seed(KeyType::ED25519, "test").try_to_vec().unwrap();
+        bytes[0] = 7;
+        assert!(PublicKey::try_from_slice(&bytes).is_err());
+        let mut bytes = Signature::empty(KeyType::ED25519).try_to_vec().unwrap();
+        bytes[0] = 7;
+        assert!(Signature::try_from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "unknown_key_types")]
+    fn test_unknown_key_type_round_trip() {
+        let data = vec![42u8; 48];
+        let bytes = [&[7u8][..], &(data.len() as u32).to_le_bytes(), &data].concat();
+
+        let public_key = PublicKey::try_from_slice(&bytes).unwrap();
+        assert_eq!(public_key, PublicKey::Unknown { key_type: 7, data: data.clone() });
+        assert_eq!(public_key.try_to_vec().unwrap(), bytes);
+        assert_eq!(public_key.key_data(), &data[..]);
+        assert!(!public_key.is_on_curve());
+        assert!(public_key.try_key_type().is_none());
+        assert_eq!(
+            public_key.to_string(),
+            format!("unknown7:{}", bs58::encode(&data).into_string())
+        );
+
+        let signature = Signature::try_from_slice(&bytes).unwrap();
+        assert_eq!(signature, Signature::Unknown { key_type: 7, data });
+        assert_eq!(signature.try_to_vec().unwrap(), bytes);
+        assert!(signature.ct_eq(&signature.clone()));
+        assert!(signature.to_string().starts_with("unknown7:"));
+
+        assert!(signature.try_key_type().is_none());
+
+        // The payload is delimited, so whatever follows the key still decodes.
+        let pair =
+            <(PublicKey, u64)>::try_from_slice(&[&bytes[..], &[1, 0, 0, 0, 0, 0, 0, 0]].concat())
+                .unwrap();
+        assert_eq!(pair, (public_key, 1));
+
+        // Known key types still decode as before.
+        let known = PublicKey::from_seed(KeyType::ED25519, "test");
+        assert_eq!(PublicKey::try_from_slice(&known.try_to_vec().unwrap()).unwrap(), known);
+    }
+
+    #[test]
+    #[cfg(feature = "unknown_key_types")]
+    fn test_unknown_key_type_refused() {
+        let unknown_key = PublicKey::Unknown { key_type: 7, data: vec![1; 32] };
+        let unknown_signature = Signature::Unknown { key_type: 7, data: vec![1; 64] };
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "test");
+        let signature = secret_key.sign(b"hello");
+        assert!(!signature.verify(b"hello", &unknown_key));
+        assert!(!unknown_signature.verify(b"hello", &secret_key.public_key()));
+        assert!(!unknown_signature.verify(b"hello", &unknown_key));
+        assert!(crate::vrf::PublicKey::try_from(&unknown_key).is_err());
+        assert!(!crate::key_conversion::is_valid_staking_key(&unknown_key));
+    }
 }
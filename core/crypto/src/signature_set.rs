@@ -0,0 +1,344 @@
+use std::io::{Error, ErrorKind};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+
+use crate::{PublicKey, Signature};
+
+/// Signatures of a subset of an ordered validator set.
+///
+/// Stores a bitmap of participating validators plus their signatures in
+/// validator order.  This is a more compact equivalent of the
+/// `Vec<Option<Signature>>` approvals carried by block headers: absent
+/// approvals cost one bit instead of one byte, and the conversion in both
+/// directions is lossless.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, Serialize, Deserialize)]
+#[serde(try_from = "RawSignatureSet")]
+pub struct SignatureSet {
+    /// Number of validators in the set (participating or not).
+    num_validators: u64,
+    /// Bit `i` (little-endian within each byte) is set iff validator `i` signed.
+    bitmap: Vec<u8>,
+    /// Signatures of participating validators, ordered by validator index.
+    signatures: Vec<Signature>,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SignatureSetError {
+    #[error("{0} validators don't fit in memory")]
+    TooManyValidators(u64),
+    #[error("bitmap has {got} bytes but {expected} are needed for {num_validators} validators")]
+    InvalidBitmapLength { num_validators: u64, expected: usize, got: usize },
+    #[error("bitmap has bits set past the last validator")]
+    TrailingBits,
+    #[error("bitmap marks {participants} participants but {signatures} signatures are present")]
+    SignatureCountMismatch { participants: usize, signatures: usize },
+}
+
+impl SignatureSet {
+    /// Converts from the block header approvals representation, where entry
+    /// `i` is the signature of validator `i` if it approved.
+    pub fn from_approvals(approvals: &[Option<Signature>]) -> Self {
+        // A slice's length always has room for its bitmap.
+        let mut bitmap = vec![0u8; bitmap_len(approvals.len() as u64).unwrap()];
+        let mut signatures = Vec::new();
+        for (i, approval) in approvals.iter().enumerate() {
+            if let Some(signature) = approval {
+                bitmap[i / 8] |= 1 << (i % 8);
+                signatures.push(signature.clone());
+            }
+        }
+        Self { num_validators: approvals.len() as u64, bitmap, signatures }
+    }
+
+    /// Converts back to the block header approvals representation.
+    pub fn to_approvals(&self) -> Vec<Option<Signature>> {
+        let mut signatures = self.signatures.iter();
+        (0..self.num_validators as usize)
+            .map(|i| if self.contains(i) { signatures.next().cloned() } else { None })
+            .collect()
+    }
+
+    /// Number of validators in the set.
+    pub fn num_validators(&self) -> usize {
+        self.num_validators as usize
+    }
+
+    /// Number of validators which signed.
+    pub fn num_signatures(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Whether validator with the given index signed.
+    pub fn contains(&self, validator_index: usize) -> bool {
+        validator_index < self.num_validators as usize
+            && self.bitmap[validator_index / 8] & (1 << (validator_index % 8)) != 0
+    }
+
+    /// Iterates over `(validator_index, signature)` of participating validators.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Signature)> + '_ {
+        (0..self.num_validators as usize).filter(|&i| self.contains(i)).zip(self.signatures.iter())
+    }
+
+    /// Verifies every signature in the set against the public key of the
+    /// validator at the same index in `ordered_public_keys`.
+    ///
+    /// ED25519 signatures are verified in one batch, the others one by one.
+    /// Like `ed25519_dalek::verify_batch`, the batch check is cofactored, so
+    /// unlike `Signature::verify` it accepts signatures which only fail by a
+    /// small order component.
+    ///
+    /// Returns `false` if the number of keys doesn't match the size of the
+    /// set or if any single signature is invalid.
+    pub fn verify_against(&self, ordered_public_keys: &[PublicKey], msg: &[u8]) -> bool {
+        if ordered_public_keys.len() != self.num_validators as usize {
+            return false;
+        }
+        let mut batch = Vec::with_capacity(self.signatures.len());
+        for (i, signature) in self.iter() {
+            match BatchEntry::new(signature, &ordered_public_keys[i], msg) {
+                Some(entry) => batch.push(entry),
+                // Anything the batch can't take is checked on its own, so that
+                // e.g. non-canonical encodings are rejected as usual.
+                None => {
+                    if !signature.verify(msg, &ordered_public_keys[i]) {
+                        return false;
+                    }
+                }
+            }
+        }
+        verify_batch(&batch)
+    }
+
+    fn validate(&self) -> Result<(), SignatureSetError> {
+        let expected = bitmap_len(self.num_validators)
+            .ok_or(SignatureSetError::TooManyValidators(self.num_validators))?;
+        if self.bitmap.len() != expected {
+            return Err(SignatureSetError::InvalidBitmapLength {
+                num_validators: self.num_validators,
+                expected,
+                got: self.bitmap.len(),
+            });
+        }
+        let used_bits = self.num_validators % 8;
+        if used_bits != 0 && self.bitmap[expected - 1] >> used_bits != 0 {
+            return Err(SignatureSetError::TrailingBits);
+        }
+        let participants = self.bitmap.iter().map(|byte| byte.count_ones() as usize).sum();
+        if participants != self.signatures.len() {
+            return Err(SignatureSetError::SignatureCountMismatch {
+                participants,
+                signatures: self.signatures.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Returns `None` if the bitmap for `num_validators` doesn't fit in memory.
+fn bitmap_len(num_validators: u64) -> Option<usize> {
+    usize::try_from(num_validators / 8 + (num_validators % 8 != 0) as u64).ok()
+}
+
+/// Decoded ED25519 signature with its public key, for batch verification.
+struct BatchEntry {
+    r: EdwardsPoint,
+    s: Scalar,
+    a: EdwardsPoint,
+    /// `H(R || A || msg)`.
+    k: Scalar,
+}
+
+impl BatchEntry {
+    /// Returns `None` unless both are ED25519 and canonically encoded.
+    fn new(signature: &Signature, public_key: &PublicKey, msg: &[u8]) -> Option<Self> {
+        let (signature, public_key) = match (signature, public_key) {
+            (Signature::ED25519(signature), PublicKey::ED25519(public_key)) => {
+                (signature.to_bytes(), public_key.0)
+            }
+            _ => return None,
+        };
+        let (r_bytes, s_bytes) = signature.split_at(32);
+        let r_bytes = CompressedEdwardsY::from_slice(r_bytes);
+        let r = r_bytes.decompress().filter(|r| r.compress() == r_bytes)?;
+        let s = Scalar::from_canonical_bytes(s_bytes.try_into().ok()?)?;
+        let a = CompressedEdwardsY(public_key).decompress()?;
+        let mut hasher = Sha512::new();
+        hasher.update(r_bytes.as_bytes());
+        hasher.update(public_key);
+        hasher.update(msg);
+        let mut hash = [0; 64];
+        hash.copy_from_slice(&hasher.finalize());
+        Some(Self { r, s, a, k: Scalar::from_bytes_mod_order_wide(&hash) })
+    }
+}
+
+/// Checks `[8](Σ z_i ([s_i]B - R_i - [k_i]A_i)) = 0` for random 128-bit `z_i`,
+/// which holds for valid signatures and fails for an invalid one with
+/// overwhelming probability.
+fn verify_batch(batch: &[BatchEntry]) -> bool {
+    if batch.is_empty() {
+        return true;
+    }
+    let mut rng = thread_rng();
+    let z: Vec<Scalar> = batch.iter().map(|_| Scalar::from(rng.gen::<u128>())).collect();
+    let b_coefficient: Scalar = -batch.iter().zip(&z).map(|(entry, z)| z * entry.s).sum::<Scalar>();
+    let scalars = std::iter::once(b_coefficient)
+        .chain(z.iter().copied())
+        .chain(batch.iter().zip(&z).map(|(entry, z)| z * entry.k));
+    let points = std::iter::once(ED25519_BASEPOINT_POINT)
+        .chain(batch.iter().map(|entry| entry.r))
+        .chain(batch.iter().map(|entry| entry.a));
+    EdwardsPoint::vartime_multiscalar_mul(scalars, points).mul_by_cofactor().is_identity()
+}
+
+impl From<&[Option<Signature>]> for SignatureSet {
+    fn from(approvals: &[Option<Signature>]) -> Self {
+        Self::from_approvals(approvals)
+    }
+}
+
+impl From<&SignatureSet> for Vec<Option<Signature>> {
+    fn from(set: &SignatureSet) -> Self {
+        set.to_approvals()
+    }
+}
+
+/// Unchecked form used to validate the invariants on deserialization.
+#[derive(BorshDeserialize, Deserialize)]
+struct RawSignatureSet {
+    num_validators: u64,
+    bitmap: Vec<u8>,
+    signatures: Vec<Signature>,
+}
+
+impl TryFrom<RawSignatureSet> for SignatureSet {
+    type Error = SignatureSetError;
+
+    fn try_from(raw: RawSignatureSet) -> Result<Self, Self::Error> {
+        let set = Self {
+            num_validators: raw.num_validators,
+            bitmap: raw.bitmap,
+            signatures: raw.signatures,
+        };
+        set.validate()?;
+        Ok(set)
+    }
+}
+
+impl BorshDeserialize for SignatureSet {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, Error> {
+        let raw = <RawSignatureSet as BorshDeserialize>::deserialize(buf)?;
+        Self::try_from(raw).map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeyType, SecretKey};
+    use rand::{Rng, SeedableRng};
+
+    fn keys(n: usize) -> Vec<SecretKey> {
+        (0..n).map(|i| SecretKey::from_seed(KeyType::ED25519, &format!("test{}", i))).collect()
+    }
+
+    fn approvals(keys: &[SecretKey], participation: &[bool], msg: &[u8]) -> Vec<Option<Signature>> {
+        keys.iter()
+            .zip(participation)
+            .map(|(key, &signed)| if signed { Some(key.sign(msg)) } else { None })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_partial_participation() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let msg = b"block hash";
+        for n in [0, 1, 7, 8, 9, 16, 33] {
+            let keys = keys(n);
+            let public_keys: Vec<_> = keys.iter().map(|key| key.public_key()).collect();
+            for _ in 0..10 {
+                let participation: Vec<bool> = (0..n).map(|_| rng.gen()).collect();
+                let approvals = approvals(&keys, &participation, msg);
+                let set = SignatureSet::from_approvals(&approvals);
+
+                assert_eq!(set.num_validators(), n);
+                assert_eq!(set.num_signatures(), participation.iter().filter(|&&p| p).count());
+                for (i, &signed) in participation.iter().enumerate() {
+                    assert_eq!(set.contains(i), signed);
+                }
+                assert_eq!(set.to_approvals(), approvals);
+                assert!(set.verify_against(&public_keys, msg));
+                if n > 0 {
+                    assert!(!set.verify_against(&public_keys[..n - 1], msg));
+                }
+
+                let bytes = set.try_to_vec().unwrap();
+                assert_eq!(SignatureSet::try_from_slice(&bytes).unwrap(), set);
+                let json = serde_json::to_string(&set).unwrap();
+                assert_eq!(serde_json::from_str::<SignatureSet>(&json).unwrap(), set);
+            }
+        }
+    }
+
+    #[test]
+    fn test_corrupted_member_signature() {
+        let msg = b"block hash";
+        let keys = keys(5);
+        let public_keys: Vec<_> = keys.iter().map(|key| key.public_key()).collect();
+        let mut approvals = approvals(&keys, &[true, false, true, true, false], msg);
+        assert!(SignatureSet::from_approvals(&approvals).verify_against(&public_keys, msg));
+        assert!(!SignatureSet::from_approvals(&approvals).verify_against(&public_keys, b"other"));
+
+        // Validator 2's approval is signed by validator 3.
+        approvals[2] = Some(keys[3].sign(msg));
+        assert!(!SignatureSet::from_approvals(&approvals).verify_against(&public_keys, msg));
+    }
+
+    #[test]
+    fn test_mixed_key_types() {
+        // SECP256K1 signs 32 byte messages.
+        let msg = [7; 32];
+        let keys: Vec<SecretKey> = (0..4)
+            .map(|i| {
+                let key_type = if i % 2 == 0 { KeyType::ED25519 } else { KeyType::SECP256K1 };
+                SecretKey::from_seed(key_type, &format!("test{}", i))
+            })
+            .collect();
+        let public_keys: Vec<_> = keys.iter().map(|key| key.public_key()).collect();
+        let mut approvals = approvals(&keys, &[true, true, true, true], &msg);
+        assert!(SignatureSet::from_approvals(&approvals).verify_against(&public_keys, &msg));
+
+        approvals[1] = Some(keys[3].sign(&msg));
+        assert!(!SignatureSet::from_approvals(&approvals).verify_against(&public_keys, &msg));
+    }
+
+    #[test]
+    fn test_invalid_encoding() {
+        let signature = SecretKey::from_seed(KeyType::ED25519, "test").sign(b"msg");
+        let encode = |num_validators: u64, bitmap: Vec<u8>, signatures: Vec<Signature>| {
+            (num_validators, bitmap, signatures).try_to_vec().unwrap()
+        };
+        let valid = encode(3, vec![0b010], vec![signature.clone()]);
+        assert!(SignatureSet::try_from_slice(&valid).is_ok());
+
+        let wrong_length = encode(3, vec![0b010, 0], vec![signature.clone()]);
+        assert!(SignatureSet::try_from_slice(&wrong_length).is_err());
+        let trailing_bits = encode(3, vec![0b1010], vec![signature.clone(), signature.clone()]);
+        assert!(SignatureSet::try_from_slice(&trailing_bits).is_err());
+        let missing_signature = encode(3, vec![0b110], vec![signature]);
+        assert!(SignatureSet::try_from_slice(&missing_signature).is_err());
+
+        let too_many_validators = encode(u64::MAX, vec![0b010], vec![signature.clone()]);
+        assert!(SignatureSet::try_from_slice(&too_many_validators).is_err());
+
+        let json = r#"{"num_validators":1,"bitmap":[1],"signatures":[]}"#;
+        assert!(serde_json::from_str::<SignatureSet>(json).is_err());
+    }
+}
@@ -44,7 +44,9 @@ impl Signer for EmptySigner {
 }
 
 /// Signer that keeps secret key in memory.
-#[derive(Clone, Serialize, Deserialize, PartialEq)]
+///
+/// Compared in constant time as far as the secret key goes, see `SecretKey`.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InMemorySigner {
     pub account_id: AccountId,
     pub public_key: PublicKey,
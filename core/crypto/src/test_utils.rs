@@ -1,7 +1,8 @@
 use rand::rngs::StdRng;
 
 use crate::signature::{
-    ED25519PublicKey, ED25519SecretKey, KeyType, PublicKey, SecretKey, SECP256K1,
+    ED25519PublicKey, ED25519SecretKey, KeyType, PublicKey, Secp256K1Signature, SecretKey,
+    SECP256K1, SECP256K1_SIGNATURE_LENGTH,
 };
 use crate::{InMemorySigner, Signature};
 use near_account_id::AccountId;
@@ -26,25 +27,37 @@ fn secp256k1_secret_key_from_seed(seed: &str) -> secp256k1::key::SecretKey {
 }
 
 impl PublicKey {
+    /// Public key matching `SecretKey::from_seed(key_type, seed)`.
     pub fn from_seed(key_type: KeyType, seed: &str) -> Self {
         match key_type {
             KeyType::ED25519 => {
                 let keypair = ed25519_key_pair_from_seed(seed);
                 PublicKey::ED25519(ED25519PublicKey(keypair.public.to_bytes()))
             }
-            _ => unimplemented!(),
+            KeyType::SECP256K1 => SecretKey::from_seed(key_type, seed).public_key(),
         }
     }
 }
 
 impl SecretKey {
+    /// Deterministically derives a secret key from a string seed.  Meant for
+    /// tests and fixtures only, the keys are trivially guessable.
+    ///
+    /// The seed bytes are truncated or right-padded with spaces to 32 bytes.
+    /// For ED25519 the result is used directly as the secret scalar seed (the
+    /// usual ed25519 hashing and clamping is applied by the library).  For
+    /// SECP256K1 it seeds a `StdRng` from which a secret key in the valid
+    /// scalar range is sampled.
+    ///
+    /// Many fixtures across the workspace depend on the exact keys produced
+    /// here, so the derivation must never change; see the golden tests below.
     pub fn from_seed(key_type: KeyType, seed: &str) -> Self {
         match key_type {
             KeyType::ED25519 => {
                 let keypair = ed25519_key_pair_from_seed(seed);
                 SecretKey::ED25519(ED25519SecretKey(keypair.to_bytes()))
             }
-            _ => SecretKey::SECP256K1(secp256k1_secret_key_from_seed(seed)),
+            KeyType::SECP256K1 => SecretKey::SECP256K1(secp256k1_secret_key_from_seed(seed)),
         }
     }
 }
@@ -58,7 +71,9 @@ impl Signature {
             KeyType::ED25519 => {
                 Signature::ED25519(ed25519_dalek::Signature::from_bytes(&SIG).unwrap())
             }
-            _ => unimplemented!(),
+            KeyType::SECP256K1 => {
+                Signature::SECP256K1(Secp256K1Signature::from([0u8; SECP256K1_SIGNATURE_LENGTH]))
+            }
         }
     }
 }
@@ -69,3 +84,68 @@ impl InMemorySigner {
         Self { account_id, public_key: secret_key.public_key(), secret_key }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Golden public keys for a few seeds.  If this test fails, the seed
+    /// derivation has changed and every fixture built on `from_seed` with it.
+    #[test]
+    fn test_from_seed_golden() {
+        let cases = [
+            (KeyType::ED25519, "test", "ed25519:DcA2MzgpJbrUATQLLceocVckhhAqrkingax4oJ9kZ847"),
+            (KeyType::ED25519, "alice.near", "ed25519:22skMptHjFWNyuEWY22ftn2AbLPSYpmYwGJRGwpNHbTV"),
+            (KeyType::ED25519, "", "ed25519:6JhaGdekBjU2RfiYWSjYdQAibx4LfSfTNFEeMUHnUVz7"),
+            (
+                KeyType::SECP256K1,
+                "test",
+                "secp256k1:BtJtBjukUQbcipnS78adSwUKE38sdHnk7pTNZH7miGXfodzUunaAcvY43y37nm7AKbcTQycvdgUzFNWsd7dgPZZ",
+            ),
+            (
+                KeyType::SECP256K1,
+                "alice.near",
+                "secp256k1:HLt8mp35BpoNhBS1U6aEJZs8cSVVpoe94QQYhU16g8CPfz77xGHM7aLNU4uE8fV55kLKoLugNApnX4TYDTKFYEy",
+            ),
+            (
+                KeyType::SECP256K1,
+                "",
+                "secp256k1:3sgsieLNnViezKBfU188su6D2aqYDUnRULUTUmWaYT4xnGxibAgNvmdcSy5h9LQmPSqpBxkkRbQJ42828mEgtHAg",
+            ),
+        ];
+        for (key_type, seed, expected) in cases {
+            assert_eq!(PublicKey::from_seed(key_type, seed).to_string(), expected);
+            assert_eq!(SecretKey::from_seed(key_type, seed).public_key().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_from_seed_across_key_types() {
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+            let secret_key = SecretKey::from_seed(key_type, "test.near");
+            assert!(secret_key.ct_eq(&SecretKey::from_seed(key_type, "test.near")));
+            assert!(!secret_key.ct_eq(&SecretKey::from_seed(key_type, "test2.near")));
+            assert_eq!(secret_key.key_type() as u8, key_type as u8);
+
+            let public_key = PublicKey::from_seed(key_type, "test.near");
+            assert_eq!(public_key, secret_key.public_key());
+            assert_eq!(public_key.key_type() as u8, key_type as u8);
+
+            let signer =
+                InMemorySigner::from_seed("test.near".parse().unwrap(), key_type, "test.near");
+            assert_eq!(signer.public_key, public_key);
+        }
+        assert_ne!(
+            PublicKey::from_seed(KeyType::ED25519, "test").key_data(),
+            &PublicKey::from_seed(KeyType::SECP256K1, "test").key_data()[..32]
+        );
+    }
+
+    #[test]
+    fn test_empty_signature() {
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+            let signature = Signature::empty(key_type);
+            assert_eq!(signature.key_type() as u8, key_type as u8);
+        }
+    }
+}
@@ -28,6 +28,35 @@ impl deepsize::DeepSizeOf for Proof {
     }
 }
 
+impl Proof {
+    /// Verifies that `value` is the VRF output of `input` under `public_key`
+    /// and returns it on success.
+    ///
+    /// The proof doesn't commit to the output in a recoverable form, so the
+    /// claimed value has to be passed in alongside it.
+    pub fn verify(&self, public_key: &PublicKey, input: &[u8], value: &Value) -> Option<Value> {
+        if public_key.is_valid(input, value, self) {
+            Some(*value)
+        } else {
+            None
+        }
+    }
+}
+
+/// Verifies a batch of `(public_key, input, value, proof)` tuples.
+///
+/// Returns the index of the first invalid entry, if any.
+pub fn verify_batch<'a>(
+    items: impl IntoIterator<Item = (&'a PublicKey, &'a [u8], &'a Value, &'a Proof)>,
+) -> Result<(), usize> {
+    for (i, (public_key, input, value, proof)) in items.into_iter().enumerate() {
+        if proof.verify(public_key, input, value).is_none() {
+            return Err(i);
+        }
+    }
+    Ok(())
+}
+
 impl PublicKey {
     fn from_bytes(bytes: &[u8; 32]) -> Option<Self> {
         Some(PublicKey(*bytes, unpack(bytes)?))
@@ -169,6 +198,65 @@ mod tests {
         assert!(!pk2.is_vrf_valid(b"Test", &val, &proof2));
     }
 
+    #[test]
+    fn test_proof_verify() {
+        let sk = SecretKey::random();
+        let pk = sk.public_key();
+        let (val, proof) = sk.compute_vrf_with_proof(b"Test");
+        assert_eq!(proof.verify(pk, b"Test", &val), Some(val));
+        assert_eq!(proof.verify(pk, b"Tent", &val), None);
+        assert_eq!(proof.verify(SecretKey::random().public_key(), b"Test", &val), None);
+        let other_val = sk.compute_vrf(b"Tent");
+        assert_eq!(proof.verify(pk, b"Test", &other_val), None);
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let keys: Vec<SecretKey> = (0..4).map(|_| SecretKey::random()).collect();
+        let inputs: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 8]).collect();
+        let outputs: Vec<(Value, Proof)> =
+            keys.iter().zip(&inputs).map(|(sk, input)| sk.compute_vrf_with_proof(input)).collect();
+        let batch = |outputs: &[(Value, Proof)]| {
+            verify_batch(
+                keys.iter()
+                    .zip(&inputs)
+                    .zip(outputs)
+                    .map(|((sk, input), (val, proof))| (sk.public_key(), &input[..], val, proof)),
+            )
+        };
+        assert_eq!(batch(&outputs), Ok(()));
+        assert_eq!(verify_batch(std::iter::empty()), Ok(()));
+
+        let mut swapped = outputs.clone();
+        swapped.swap(1, 2);
+        assert_eq!(batch(&swapped), Err(1));
+    }
+
+    #[test]
+    fn test_golden_vectors() {
+        // Changing any of these means existing block headers no longer verify.
+        let sk = SecretKey::from_bytes(&[7; 32]).unwrap();
+        let pk = sk.public_key();
+        assert_eq!(bs58::encode(pk).into_string(), "CWPmQBcZoLjCbV2yCGAaFYu19PNEbvVzPNBGvUhuvrQX");
+        for (input, value, proof) in [
+            (
+                &b""[..],
+                "8HKtZjjY4pD1WU7KX4hka27UUAyZpEK9WewvwzVwdDv9",
+                "uw2xKtw4V3WX54Pmo3hhS1BU4MQh7FVpnAnttX1B5AjsbBpR1vaLVcQU6ns5bF937RiASwDpFpksm2CW68DuBad",
+            ),
+            (
+                &b"Test"[..],
+                "8HnUYgbnknz9thoujxjeGanqd6xKtUfDbrd1wQj48PNK",
+                "4VRsrSVKivb3cD4Us4FxdGrZjr7A2RByFikAtuudECcjpSzDkhWmbs4jnJYvbDwUvgyaovYAFcX6fytfYwbLkwro",
+            ),
+        ] {
+            let (val, prf) = sk.compute_with_proof(input);
+            assert_eq!(bs58::encode(val.0).into_string(), value);
+            assert_eq!(bs58::encode(&prf.0[..]).into_string(), proof);
+            assert_eq!(prf.verify(pk, input, &val), Some(val));
+        }
+    }
+
     fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
         from_str(to_string(value).unwrap().as_str()).unwrap()
     }
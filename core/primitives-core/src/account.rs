@@ -1,4 +1,4 @@
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use std::io;
 
@@ -149,7 +149,16 @@ impl BorshSerialize for Account {
 /// `account_id,public_key` is a key in the state
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(
-    BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Debug,
 )]
 pub struct AccessKey {
     /// Nonce for this access key, used for tx nonce generation. When access key is created, nonce
@@ -172,7 +181,16 @@ impl AccessKey {
 /// Defines permissions for AccessKey
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(
-    BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Debug,
 )]
 pub enum AccessKeyPermission {
     FunctionCall(FunctionCallPermission),
@@ -188,7 +206,16 @@ pub enum AccessKeyPermission {
 /// It also can restrict the method name for the allowed function calls.
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(
-    BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Debug,
 )]
 pub struct FunctionCallPermission {
     /// Allowance is a balance limit to use by this access key to pay for function call gas and
@@ -212,6 +239,83 @@ pub struct FunctionCallPermission {
     pub method_names: Vec<String>,
 }
 
+impl AccessKeyPermission {
+    /// Whether every transaction allowed by `self` is also allowed by `other`.
+    pub fn is_subset_of(&self, other: &AccessKeyPermission) -> bool {
+        match (self, other) {
+            (_, AccessKeyPermission::FullAccess) => true,
+            (AccessKeyPermission::FullAccess, AccessKeyPermission::FunctionCall(_)) => false,
+            (AccessKeyPermission::FunctionCall(this), AccessKeyPermission::FunctionCall(other)) => {
+                this.is_subset_of(other)
+            }
+        }
+    }
+
+    /// Permission allowing exactly the transactions allowed by both `self` and
+    /// `other`, or `None` if there are no such transactions.
+    pub fn intersect(&self, other: &AccessKeyPermission) -> Option<AccessKeyPermission> {
+        match (self, other) {
+            (permission, AccessKeyPermission::FullAccess)
+            | (AccessKeyPermission::FullAccess, permission) => Some(permission.clone()),
+            (AccessKeyPermission::FunctionCall(this), AccessKeyPermission::FunctionCall(other)) => {
+                this.intersect(other).map(AccessKeyPermission::FunctionCall)
+            }
+        }
+    }
+}
+
+impl FunctionCallPermission {
+    /// Whether the permission allows calling `method_name`.  An empty
+    /// `method_names` list allows any method.
+    pub fn allows_method(&self, method_name: &str) -> bool {
+        self.method_names.is_empty() || self.method_names.iter().any(|name| name == method_name)
+    }
+
+    fn is_subset_of(&self, other: &FunctionCallPermission) -> bool {
+        let allowance_is_subset = match (self.allowance, other.allowance) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(this), Some(other)) => this <= other,
+        };
+        let method_names_are_subset = if self.method_names.is_empty() {
+            other.method_names.is_empty()
+        } else {
+            self.method_names.iter().all(|method_name| other.allows_method(method_name))
+        };
+        self.receiver_id == other.receiver_id && allowance_is_subset && method_names_are_subset
+    }
+
+    fn intersect(&self, other: &FunctionCallPermission) -> Option<FunctionCallPermission> {
+        if self.receiver_id != other.receiver_id {
+            return None;
+        }
+        let allowance = match (self.allowance, other.allowance) {
+            (None, allowance) | (allowance, None) => allowance,
+            (Some(this), Some(other)) => Some(this.min(other)),
+        };
+        let method_names = if self.method_names.is_empty() {
+            other.method_names.clone()
+        } else {
+            let method_names: Vec<String> = self
+                .method_names
+                .iter()
+                .filter(|method_name| other.allows_method(method_name))
+                .cloned()
+                .collect();
+            // An empty list would allow any method, not none of them.
+            if method_names.is_empty() {
+                return None;
+            }
+            method_names
+        };
+        Some(FunctionCallPermission {
+            allowance,
+            receiver_id: self.receiver_id.clone(),
+            method_names,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use borsh::BorshSerialize;
@@ -248,4 +352,139 @@ mod tests {
             <Account as BorshDeserialize>::deserialize(&mut new_bytes).unwrap();
         assert_eq!(deserialized_account, new_account);
     }
+
+    fn function_call(
+        allowance: Option<Balance>,
+        receiver_id: &str,
+        method_names: &[&str],
+    ) -> AccessKeyPermission {
+        AccessKeyPermission::FunctionCall(FunctionCallPermission {
+            allowance,
+            receiver_id: receiver_id.to_string(),
+            method_names: method_names.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+
+    /// All combinations of the interesting allowances, receivers and method lists.
+    fn all_permissions() -> Vec<AccessKeyPermission> {
+        let mut permissions = vec![AccessKeyPermission::FullAccess];
+        for allowance in [None, Some(0), Some(10), Some(20)] {
+            for receiver_id in ["alice", "bob"] {
+                for method_names in [&[][..], &["x"], &["y"], &["x", "y"], &["y", "x"]] {
+                    permissions.push(function_call(allowance, receiver_id, method_names));
+                }
+            }
+        }
+        permissions
+    }
+
+    /// A transaction an access key may be asked to sign.
+    enum Request {
+        FullAccess,
+        FunctionCall { receiver_id: &'static str, method_name: &'static str, cost: Balance },
+    }
+
+    fn all_requests() -> Vec<Request> {
+        let mut requests = vec![Request::FullAccess];
+        for receiver_id in ["alice", "bob", "carol"] {
+            for method_name in ["x", "y", "z"] {
+                for cost in [0, 10, 20, 30] {
+                    requests.push(Request::FunctionCall { receiver_id, method_name, cost });
+                }
+            }
+        }
+        requests
+    }
+
+    /// Reference semantics of a permission, written independently of the
+    /// helpers under test.
+    fn allows(permission: &AccessKeyPermission, request: &Request) -> bool {
+        match (permission, request) {
+            (AccessKeyPermission::FullAccess, _) => true,
+            (AccessKeyPermission::FunctionCall(_), Request::FullAccess) => false,
+            (
+                AccessKeyPermission::FunctionCall(permission),
+                Request::FunctionCall { receiver_id, method_name, cost },
+            ) => {
+                permission.receiver_id == *receiver_id
+                    && (permission.method_names.is_empty()
+                        || permission.method_names.contains(&method_name.to_string()))
+                    && permission.allowance.map_or(true, |allowance| *cost <= allowance)
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_subset_of_matches_semantics() {
+        let requests = all_requests();
+        for a in all_permissions() {
+            for b in all_permissions() {
+                let expected =
+                    requests.iter().all(|request| !allows(&a, request) || allows(&b, request));
+                assert_eq!(a.is_subset_of(&b), expected, "{:?} subset of {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_intersect_matches_semantics() {
+        let requests = all_requests();
+        for a in all_permissions() {
+            for b in all_permissions() {
+                let both = |request: &Request| allows(&a, request) && allows(&b, request);
+                match a.intersect(&b) {
+                    Some(intersection) => {
+                        for request in &requests {
+                            assert_eq!(
+                                allows(&intersection, request),
+                                both(request),
+                                "{:?} intersect {:?}",
+                                a,
+                                b
+                            );
+                        }
+                        assert!(intersection.is_subset_of(&a) && intersection.is_subset_of(&b));
+                        assert_eq!(
+                            b.intersect(&a).map(|p| p.is_subset_of(&intersection)),
+                            Some(true)
+                        );
+                    }
+                    None => {
+                        assert!(
+                            !requests.iter().any(both),
+                            "{:?} intersect {:?} is not empty",
+                            a,
+                            b
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_permission_helpers_examples() {
+        let full = AccessKeyPermission::FullAccess;
+        let any_method = function_call(None, "alice", &[]);
+        let limited = function_call(Some(10), "alice", &["x"]);
+
+        assert!(limited.is_subset_of(&any_method));
+        assert!(!any_method.is_subset_of(&limited));
+        assert!(any_method.is_subset_of(&full));
+        assert!(!full.is_subset_of(&any_method));
+        assert!(!function_call(None, "alice", &["x"]).is_subset_of(&limited));
+
+        assert_eq!(full.intersect(&limited), Some(limited.clone()));
+        assert_eq!(any_method.intersect(&limited), Some(limited.clone()));
+        assert_eq!(
+            function_call(Some(20), "alice", &["x", "y"]).intersect(&function_call(
+                Some(10),
+                "alice",
+                &["y", "z"]
+            )),
+            Some(function_call(Some(10), "alice", &["y"]))
+        );
+        assert_eq!(limited.intersect(&function_call(None, "alice", &["y"])), None);
+        assert_eq!(limited.intersect(&function_call(None, "bob", &[])), None);
+    }
 }
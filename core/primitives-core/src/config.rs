@@ -181,6 +181,50 @@ impl VMConfig {
             limit_config: VMLimitConfig { max_gas_burnt: u64::MAX, ..VMLimitConfig::test() },
         }
     }
+
+    /// Parses a config which must spell out every field.
+    ///
+    /// Unlike plain deserialization, fields which would otherwise be filled
+    /// with defaults and unknown fields are errors.  Optional limits may be
+    /// omitted, as `to_json_pretty` does when they are unset.
+    pub fn from_json_strict(json: &str) -> Result<VMConfig, serde_json::Error> {
+        let given: serde_json::Value = serde_json::from_str(json)?;
+        let config: VMConfig = serde_json::from_value(given.clone())?;
+        check_same_fields("", &given, &serde_json::to_value(&config)?)?;
+        Ok(config)
+    }
+
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).expect("serializing VMConfig can't fail")
+    }
+}
+
+/// Checks that `given` JSON sets the same fields as its re-serialized form
+/// `expected`, recursing into objects.
+fn check_same_fields(
+    path: &str,
+    given: &serde_json::Value,
+    expected: &serde_json::Value,
+) -> Result<(), serde_json::Error> {
+    use serde::de::Error;
+    use serde_json::Value;
+
+    if let (Value::Object(given), Value::Object(expected)) = (given, expected) {
+        for (key, value) in given {
+            if !expected.contains_key(key) && !value.is_null() {
+                return Err(Error::custom(format!("unknown field `{path}{key}`")));
+            }
+        }
+        for (key, value) in expected {
+            match given.get(key) {
+                Some(given_value) => {
+                    check_same_fields(&format!("{path}{key}."), given_value, value)?
+                }
+                None => return Err(Error::custom(format!("missing field `{path}{key}`"))),
+            }
+        }
+    }
+    Ok(())
 }
 
 impl VMLimitConfig {
@@ -735,3 +779,36 @@ impl ExtCosts {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        for config in [VMConfig::test(), VMConfig::free()] {
+            let json = config.to_json_pretty();
+            assert_eq!(VMConfig::from_json_strict(&json).unwrap(), config);
+        }
+    }
+
+    #[test]
+    fn test_from_json_strict_rejects_incomplete_config() {
+        let mut json = serde_json::to_value(VMConfig::test()).unwrap();
+        json["ext_costs"].as_object_mut().unwrap().remove("log_byte");
+        let err = VMConfig::from_json_strict(&json.to_string()).unwrap_err();
+        assert!(err.to_string().contains("log_byte"), "{}", err);
+
+        // Plain deserialization would default this one.
+        let mut json = serde_json::to_value(VMConfig::test()).unwrap();
+        json["limit_config"].as_object_mut().unwrap().remove("stack_limiter_version");
+        assert!(serde_json::from_value::<VMConfig>(json.clone()).is_ok());
+        let err = VMConfig::from_json_strict(&json.to_string()).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `limit_config.stack_limiter_version`");
+
+        let mut json = serde_json::to_value(VMConfig::test()).unwrap();
+        json["ext_costs"]["log_bytes"] = 1.into();
+        let err = VMConfig::from_json_strict(&json.to_string()).unwrap_err();
+        assert_eq!(err.to_string(), "unknown field `ext_costs.log_bytes`");
+    }
+}
@@ -9,12 +9,78 @@ use crate::logging::pretty_hash;
 use crate::serialize::{from_base, to_base, BaseDecode};
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, derive_more::AsRef, derive_more::AsMut)]
+#[derive(
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    derive_more::AsRef,
+    derive_more::AsMut,
+    borsh::BorshSchema,
+)]
 #[as_ref(forward)]
 #[as_mut(forward)]
 pub struct CryptoHash(pub [u8; 32]);
 
 impl CryptoHash {
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        CryptoHash(bytes)
+    }
+
+    /// Parses 64 hex digits, with or without a `0x` prefix.
+    pub fn from_hex(s: &str) -> Result<Self, ParseHashError> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        if digits.len() != 64 {
+            return Err(ParseHashError::InvalidHexLength { length: digits.len() });
+        }
+        let mut bytes = [0; 32];
+        hex::decode_to_slice(digits, &mut bytes).map_err(|err| match err {
+            hex::FromHexError::InvalidHexCharacter { c, index } => {
+                ParseHashError::InvalidHexCharacter { c, index: index + s.len() - digits.len() }
+            }
+            // The length is checked above.
+            _ => ParseHashError::InvalidHexLength { length: digits.len() },
+        })?;
+        Ok(CryptoHash(bytes))
+    }
+
+    /// Lowercase hex digits, without a prefix.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Parses base58 in a const context, panicking on invalid input.  Prefer
+    /// the `hash!` macro, which always evaluates it at compile time.
+    pub const fn from_base58_const(s: &str) -> Self {
+        let s = s.as_bytes();
+        let mut bytes = [0u8; 32];
+        let mut leading_ones = 0;
+        while leading_ones < s.len() && s[leading_ones] == b'1' {
+            leading_ones += 1;
+        }
+        let mut i = 0;
+        while i < s.len() {
+            let mut carry = base58_digit(s[i]) as u32;
+            let mut j = 32;
+            while j > 0 {
+                j -= 1;
+                carry += bytes[j] as u32 * 58;
+                bytes[j] = carry as u8;
+                carry >>= 8;
+            }
+            assert!(carry == 0, "base58 string is longer than 32 bytes");
+            i += 1;
+        }
+        let mut leading_zeros = 0;
+        while leading_zeros < 32 && bytes[leading_zeros] == 0 {
+            leading_zeros += 1;
+        }
+        assert!(leading_ones == leading_zeros, "base58 string is not 32 bytes long");
+        CryptoHash(bytes)
+    }
+
     pub fn hash_bytes(bytes: &[u8]) -> CryptoHash {
         CryptoHash(sha2::Sha256::digest(bytes).into())
     }
@@ -26,6 +92,66 @@ impl CryptoHash {
     }
 }
 
+const fn base58_digit(c: u8) -> u8 {
+    const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    let mut digit = 0;
+    while digit < ALPHABET.len() {
+        if ALPHABET[digit] == c {
+            return digit as u8;
+        }
+        digit += 1;
+    }
+    panic!("invalid base58 character")
+}
+
+/// `CryptoHash` from a base58 string literal, evaluated at compile time.
+///
+/// ```
+/// use near_primitives_core::hash::CryptoHash;
+///
+/// const GENESIS: CryptoHash = near_primitives_core::hash!("CjNSmWXTWhC3EhRVtqLhRmWMTkRbU96wUACqxMtV1uGf");
+/// assert_eq!(GENESIS, near_primitives_core::hash::hash(&[0, 1, 2]));
+/// ```
+#[macro_export]
+macro_rules! hash {
+    ($base58:literal) => {{
+        const HASH: $crate::hash::CryptoHash = $crate::hash::CryptoHash::from_base58_const($base58);
+        HASH
+    }};
+}
+
+/// Error parsing a `CryptoHash`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseHashError {
+    /// Hex digits, not counting the `0x` prefix, aren't 64.
+    InvalidHexLength { length: usize },
+    /// Character at `index` of the string isn't a hex digit.
+    InvalidHexCharacter { c: char, index: usize },
+    /// The string isn't valid base58.
+    InvalidBase58(String),
+    /// Base58 decoded to `length` bytes instead of 32.
+    InvalidLength { length: usize },
+}
+
+impl fmt::Display for ParseHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseHashError::InvalidHexLength { length } => {
+                write!(f, "expected 64 hex digits, got {}", length)
+            }
+            ParseHashError::InvalidHexCharacter { c, index } => {
+                write!(f, "invalid hex character {:?} at index {}", c, index)
+            }
+            ParseHashError::InvalidBase58(err) => write!(f, "invalid base58: {}", err),
+            ParseHashError::InvalidLength { length } => {
+                write!(f, "expected 32 bytes, got {}", length)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseHashError {}
+
 impl Default for CryptoHash {
     fn default() -> Self {
         CryptoHash(Default::default())
@@ -73,12 +199,19 @@ impl<'de> Deserialize<'de> for CryptoHash {
     }
 }
 
+/// Parses hex if the string starts with `0x` or is 64 hex digits, and base58
+/// otherwise.  Neither is ambiguous: `0` isn't a base58 digit and 32 bytes are
+/// at most 44 base58 digits.
 impl std::str::FromStr for CryptoHash {
-    type Err = Box<dyn std::error::Error + Send + Sync>;
+    type Err = ParseHashError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let bytes = from_base(s).map_err::<Self::Err, _>(|e| e.to_string().into())?;
-        Self::try_from(bytes.as_slice())
+        if s.starts_with("0x") || (s.len() == 64 && s.bytes().all(|c| c.is_ascii_hexdigit())) {
+            return Self::from_hex(s);
+        }
+        let bytes = from_base(s).map_err(|err| ParseHashError::InvalidBase58(err.to_string()))?;
+        let length = bytes.len();
+        Self::try_from(bytes.as_slice()).map_err(|_| ParseHashError::InvalidLength { length })
     }
 }
 
@@ -202,4 +335,83 @@ mod tests {
             };
         }
     }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = hash(&[0, 1, 2]);
+        let hex = hash.to_hex();
+        assert_eq!(hex.len(), 64);
+        assert_eq!(CryptoHash::from_hex(&hex), Ok(hash));
+        assert_eq!(CryptoHash::from_hex(&format!("0x{}", hex)), Ok(hash));
+        assert_eq!(CryptoHash::from_hex(&hex.to_uppercase()), Ok(hash));
+        assert_eq!(CryptoHash::default().to_hex(), "0".repeat(64));
+    }
+
+    #[test]
+    fn test_from_hex_errors() {
+        assert_eq!(
+            CryptoHash::from_hex(&"0".repeat(63)),
+            Err(ParseHashError::InvalidHexLength { length: 63 })
+        );
+        assert_eq!(
+            CryptoHash::from_hex(&format!("0x{}", "0".repeat(66))),
+            Err(ParseHashError::InvalidHexLength { length: 66 })
+        );
+        assert_eq!(CryptoHash::from_hex("0x"), Err(ParseHashError::InvalidHexLength { length: 0 }));
+        let err = CryptoHash::from_hex(&format!("0x{}g", "0".repeat(63))).unwrap_err();
+        assert_eq!(err, ParseHashError::InvalidHexCharacter { c: 'g', index: 65 });
+        assert_eq!(err.to_string(), "invalid hex character 'g' at index 65");
+    }
+
+    #[test]
+    fn test_from_str() {
+        let hash = hash(&[0, 1, 2]);
+        assert_eq!("CjNSmWXTWhC3EhRVtqLhRmWMTkRbU96wUACqxMtV1uGf".parse(), Ok(hash));
+        assert_eq!(hash.to_string().parse(), Ok(hash));
+        assert_eq!(hash.to_hex().parse(), Ok(hash));
+        assert_eq!(format!("0x{}", hash.to_hex()).parse(), Ok(hash));
+        assert_eq!("11111111111111111111111111111111".parse(), Ok(CryptoHash::default()));
+
+        // `0x` always means hex.
+        assert_eq!(
+            "0x1111".parse::<CryptoHash>(),
+            Err(ParseHashError::InvalidHexLength { length: 4 })
+        );
+        // 63 hex digits are parsed, and rejected, as base58.
+        assert!(matches!(
+            "1".repeat(63).parse::<CryptoHash>(),
+            Err(ParseHashError::InvalidLength { length: 63 })
+        ));
+        assert!(matches!("---".parse::<CryptoHash>(), Err(ParseHashError::InvalidBase58(_))));
+        assert_eq!(
+            "1".repeat(31).parse::<CryptoHash>(),
+            Err(ParseHashError::InvalidLength { length: 31 })
+        );
+    }
+
+    #[test]
+    fn test_const_constructors() {
+        const ZERO: CryptoHash = CryptoHash::new([0; 32]);
+        assert_eq!(ZERO, CryptoHash::default());
+        assert_eq!(crate::hash!("11111111111111111111111111111111"), CryptoHash::default());
+        assert_eq!(crate::hash!("CjNSmWXTWhC3EhRVtqLhRmWMTkRbU96wUACqxMtV1uGf"), hash(&[0, 1, 2]));
+        for data in [&[][..], &[1], &[0xff; 100]] {
+            let hash = hash(data);
+            assert_eq!(CryptoHash::from_base58_const(&hash.to_string()), hash);
+        }
+        let leading_zeros = CryptoHash::new([[0; 16], [0xff; 16]].concat().try_into().unwrap());
+        assert_eq!(CryptoHash::from_base58_const(&leading_zeros.to_string()), leading_zeros);
+    }
+
+    #[test]
+    #[should_panic(expected = "base58 string is not 32 bytes long")]
+    fn test_from_base58_const_short() {
+        CryptoHash::from_base58_const("CjNSmWXTWhC3");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid base58 character")]
+    fn test_from_base58_const_invalid() {
+        CryptoHash::from_base58_const("0jNSmWXTWhC3EhRVtqLhRmWMTkRbU96wUACqxMtV1uGf");
+    }
 }
@@ -1,9 +1,19 @@
 use std::fmt::Debug;
 
+use crate::hash::CryptoHash;
 use crate::serialize::to_base;
+use crate::types::{Balance, Gas};
 
 const VECTOR_MAX_LENGTH: usize = 5;
 const STRING_PRINT_LEN: usize = 128;
+const SHORT_HASH_LEN: usize = 8;
+
+/// Number of decimal digits of yoctoNEAR in a NEAR.
+pub const NEAR_DECIMALS: u32 = 24;
+/// Number of decimal digits of gas in a Tgas.
+pub const TERA_GAS_DECIMALS: u32 = 12;
+pub const ONE_NEAR: Balance = 10u128.pow(NEAR_DECIMALS);
+pub const TERA_GAS: Gas = 10u64.pow(TERA_GAS_DECIMALS);
 
 pub fn pretty_vec<T: Debug>(buf: &[T]) -> String {
     if buf.len() <= VECTOR_MAX_LENGTH {
@@ -57,6 +67,45 @@ pub fn pretty_results(results: &[Option<Vec<u8>>]) -> String {
     format!("{:?}", pretty_vec(&v))
 }
 
+/// First few base58 characters of the hash, e.g. `7tkzFg8R…`.
+pub fn pretty_short_hash(hash: &CryptoHash) -> String {
+    let hash = hash.to_string();
+    format!("{}…", &hash[..SHORT_HASH_LEN.min(hash.len())])
+}
+
+/// Gas amount in Tgas, e.g. `30Tgas` or `2.5Tgas`.  Amounts below a Ggas
+/// are printed in plain gas units.
+pub fn pretty_gas(gas: Gas) -> String {
+    if gas < TERA_GAS / 1000 {
+        format!("{}gas", gas)
+    } else {
+        format!("{}Tgas", pretty_decimal(gas.into(), TERA_GAS_DECIMALS, TERA_GAS_DECIMALS))
+    }
+}
+
+/// Balance in NEAR, e.g. `1NEAR` or `0.25NEAR`.  Amounts below a
+/// milliNEAR are printed in yoctoNEAR, e.g. `1yN`.  The value is exact.
+pub fn pretty_balance(balance: Balance) -> String {
+    if balance < ONE_NEAR / 1000 {
+        format!("{}yN", balance)
+    } else {
+        format!("{}NEAR", pretty_decimal(balance, NEAR_DECIMALS, NEAR_DECIMALS))
+    }
+}
+
+/// Prints `value` divided by `10^decimals` with at most `precision` digits
+/// after the decimal point, truncating the rest, and without trailing zeros.
+pub fn pretty_decimal(value: u128, decimals: u32, precision: u32) -> String {
+    let scale = 10u128.pow(decimals);
+    let fraction = format!("{:0width$}", value % scale, width = decimals as usize);
+    let fraction = fraction[..precision.min(decimals) as usize].trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{}", value / scale)
+    } else {
+        format!("{}.{}", value / scale, fraction)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +129,41 @@ mod tests {
     fn test_non_ut8_no_truncation() {
         assert_eq!(format!("`{}`", HI_NEAR), pretty_str(HI_NEAR, HI_NEAR.len()));
     }
+
+    #[test]
+    fn test_pretty_gas() {
+        assert_eq!(pretty_gas(0), "0gas");
+        assert_eq!(pretty_gas(999_999_999), "999999999gas");
+        assert_eq!(pretty_gas(1_000_000_000), "0.001Tgas");
+        assert_eq!(pretty_gas(2_500_000_000_000), "2.5Tgas");
+        assert_eq!(pretty_gas(30 * TERA_GAS), "30Tgas");
+        assert_eq!(pretty_gas(Gas::MAX), "18446744.073709551615Tgas");
+    }
+
+    #[test]
+    fn test_pretty_balance() {
+        assert_eq!(pretty_balance(0), "0yN");
+        assert_eq!(pretty_balance(1), "1yN");
+        assert_eq!(pretty_balance(ONE_NEAR / 1000), "0.001NEAR");
+        assert_eq!(pretty_balance(ONE_NEAR / 4), "0.25NEAR");
+        assert_eq!(pretty_balance(ONE_NEAR), "1NEAR");
+        assert_eq!(pretty_balance(ONE_NEAR + 1), "1.000000000000000000000001NEAR");
+    }
+
+    #[test]
+    fn test_pretty_decimal() {
+        assert_eq!(pretty_decimal(0, 3, 3), "0");
+        assert_eq!(pretty_decimal(12_345, 3, 3), "12.345");
+        assert_eq!(pretty_decimal(12_340, 3, 3), "12.34");
+        assert_eq!(pretty_decimal(12_345, 3, 1), "12.3");
+        assert_eq!(pretty_decimal(12_045, 3, 1), "12");
+        assert_eq!(pretty_decimal(12_345, 3, 10), "12.345");
+        assert_eq!(pretty_decimal(12_345, 0, 3), "12345");
+    }
+
+    #[test]
+    fn test_pretty_short_hash() {
+        let hash = CryptoHash::default();
+        assert_eq!(pretty_short_hash(&hash), "11111111…");
+    }
 }
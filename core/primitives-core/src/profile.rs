@@ -2,6 +2,7 @@ use std::fmt;
 use std::ops::{Index, IndexMut};
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Serialize, Serializer};
 
 use crate::config::{ActionCosts, ExtCosts};
 
@@ -127,6 +128,65 @@ impl ProfileData {
         }
         action_gas
     }
+
+    /// Costs with non-zero gas, in `Cost::ALL` order.
+    pub fn costs(&self) -> impl Iterator<Item = (Cost, u64)> + '_ {
+        Cost::ALL.iter().map(move |&cost| (cost, self[cost])).filter(|&(_, gas)| gas != 0)
+    }
+
+    /// Names of the costs with non-zero gas, see `Cost::name`.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.costs().map(|(cost, gas)| (cost.name(), gas))
+    }
+
+    /// Per-cost difference `self - other`, `None` if some difference doesn't
+    /// fit into `i64`.
+    pub fn checked_sub(&self, other: &ProfileData) -> Option<ProfileDataDelta> {
+        let mut delta = ProfileDataDelta { data: Box::new([0; DataArray::LEN]) };
+        for i in 0..DataArray::LEN {
+            let diff = i128::from(self.data[i]) - i128::from(other.data[i]);
+            delta.data[i] = i64::try_from(diff).ok()?;
+        }
+        Some(delta)
+    }
+}
+
+/// Serialized as a map from cost name to gas, omitting zero costs.
+impl Serialize for ProfileData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Difference between two profiles, see `ProfileData::checked_sub`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ProfileDataDelta {
+    data: Box<[i64; DataArray::LEN]>,
+}
+
+impl ProfileDataDelta {
+    pub fn get(&self, cost: Cost) -> i64 {
+        self.data[cost.index()]
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.data.iter().all(|&gas| gas == 0)
+    }
+
+    /// Names of the costs which differ, with the difference in gas.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, i64)> + '_ {
+        Cost::ALL
+            .iter()
+            .map(move |&cost| (cost.name(), self.get(cost)))
+            .filter(|&(_, gas)| gas != 0)
+    }
+}
+
+/// Serialized like `ProfileData`, omitting costs which don't differ.
+impl Serialize for ProfileDataDelta {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
 }
 
 impl fmt::Debug for ProfileData {
@@ -340,6 +400,152 @@ impl Cost {
             Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_element } => 69,
         }
     }
+
+    /// Name of the cost in gas profiles, e.g. `STORAGE_READ_BASE`.
+    ///
+    /// The names are part of the RPC output and of serialized profiles, so
+    /// they are spelled out here rather than derived from the enum variants
+    /// and must not change.
+    pub fn name(self) -> &'static str {
+        match self {
+            Cost::ActionCost { action_cost_kind: ActionCosts::create_account } => "CREATE_ACCOUNT",
+            Cost::ActionCost { action_cost_kind: ActionCosts::delete_account } => "DELETE_ACCOUNT",
+            Cost::ActionCost { action_cost_kind: ActionCosts::deploy_contract } => {
+                "DEPLOY_CONTRACT"
+            }
+            Cost::ActionCost { action_cost_kind: ActionCosts::function_call } => "FUNCTION_CALL",
+            Cost::ActionCost { action_cost_kind: ActionCosts::transfer } => "TRANSFER",
+            Cost::ActionCost { action_cost_kind: ActionCosts::stake } => "STAKE",
+            Cost::ActionCost { action_cost_kind: ActionCosts::add_key } => "ADD_KEY",
+            Cost::ActionCost { action_cost_kind: ActionCosts::delete_key } => "DELETE_KEY",
+            Cost::ActionCost { action_cost_kind: ActionCosts::value_return } => "VALUE_RETURN",
+            Cost::ActionCost { action_cost_kind: ActionCosts::new_receipt } => "NEW_RECEIPT",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::base } => "BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_base } => {
+                "CONTRACT_LOADING_BASE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_bytes } => {
+                "CONTRACT_LOADING_BYTES"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::read_memory_base } => "READ_MEMORY_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::read_memory_byte } => "READ_MEMORY_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::write_memory_base } => "WRITE_MEMORY_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::write_memory_byte } => "WRITE_MEMORY_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::read_register_base } => "READ_REGISTER_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::read_register_byte } => "READ_REGISTER_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::write_register_base } => "WRITE_REGISTER_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::write_register_byte } => "WRITE_REGISTER_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::utf8_decoding_base } => "UTF8_DECODING_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::utf8_decoding_byte } => "UTF8_DECODING_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::utf16_decoding_base } => "UTF16_DECODING_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::utf16_decoding_byte } => "UTF16_DECODING_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::sha256_base } => "SHA256_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::sha256_byte } => "SHA256_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::keccak256_base } => "KECCAK256_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::keccak256_byte } => "KECCAK256_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::keccak512_base } => "KECCAK512_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::keccak512_byte } => "KECCAK512_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::ripemd160_base } => "RIPEMD160_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::ripemd160_block } => "RIPEMD160_BLOCK",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::ecrecover_base } => "ECRECOVER_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::log_base } => "LOG_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::log_byte } => "LOG_BYTE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_write_base } => "STORAGE_WRITE_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_write_key_byte } => {
+                "STORAGE_WRITE_KEY_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_write_value_byte } => {
+                "STORAGE_WRITE_VALUE_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_write_evicted_byte } => {
+                "STORAGE_WRITE_EVICTED_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_read_base } => "STORAGE_READ_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_read_key_byte } => {
+                "STORAGE_READ_KEY_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_read_value_byte } => {
+                "STORAGE_READ_VALUE_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_remove_base } => "STORAGE_REMOVE_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_remove_key_byte } => {
+                "STORAGE_REMOVE_KEY_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_remove_ret_value_byte } => {
+                "STORAGE_REMOVE_RET_VALUE_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_has_key_base } => {
+                "STORAGE_HAS_KEY_BASE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_has_key_byte } => {
+                "STORAGE_HAS_KEY_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_iter_create_prefix_base } => {
+                "STORAGE_ITER_CREATE_PREFIX_BASE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_iter_create_prefix_byte } => {
+                "STORAGE_ITER_CREATE_PREFIX_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_iter_create_range_base } => {
+                "STORAGE_ITER_CREATE_RANGE_BASE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_iter_create_from_byte } => {
+                "STORAGE_ITER_CREATE_FROM_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_iter_create_to_byte } => {
+                "STORAGE_ITER_CREATE_TO_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_iter_next_base } => {
+                "STORAGE_ITER_NEXT_BASE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_iter_next_key_byte } => {
+                "STORAGE_ITER_NEXT_KEY_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_iter_next_value_byte } => {
+                "STORAGE_ITER_NEXT_VALUE_BYTE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::touching_trie_node } => "TOUCHING_TRIE_NODE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::promise_and_base } => "PROMISE_AND_BASE",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::promise_and_per_promise } => {
+                "PROMISE_AND_PER_PROMISE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::promise_return } => "PROMISE_RETURN",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::validator_stake_base } => {
+                "VALIDATOR_STAKE_BASE"
+            }
+            Cost::ExtCost { ext_cost_kind: ExtCosts::validator_total_stake_base } => {
+                "VALIDATOR_TOTAL_STAKE_BASE"
+            }
+            Cost::WasmInstruction => "WASM_INSTRUCTION",
+            Cost::ExtCost { ext_cost_kind: ExtCosts::read_cached_trie_node } => {
+                "READ_CACHED_TRIE_NODE"
+            }
+            #[cfg(feature = "protocol_feature_alt_bn128")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_multiexp_base } => {
+                "ALT_BN128_G1_MULTIEXP_BASE"
+            }
+            #[cfg(feature = "protocol_feature_alt_bn128")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_multiexp_element } => {
+                "ALT_BN128_G1_MULTIEXP_ELEMENT"
+            }
+            #[cfg(feature = "protocol_feature_alt_bn128")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_pairing_check_base } => {
+                "ALT_BN128_PAIRING_CHECK_BASE"
+            }
+            #[cfg(feature = "protocol_feature_alt_bn128")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_pairing_check_element } => {
+                "ALT_BN128_PAIRING_CHECK_ELEMENT"
+            }
+            #[cfg(feature = "protocol_feature_alt_bn128")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_base } => {
+                "ALT_BN128_G1_SUM_BASE"
+            }
+            #[cfg(feature = "protocol_feature_alt_bn128")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_element } => {
+                "ALT_BN128_G1_SUM_ELEMENT"
+            }
+        }
+    }
 }
 
 impl Index<Cost> for ProfileData {
@@ -396,4 +602,77 @@ mod test {
         assert_eq!(profile_data.get_action_cost(ActionCosts::function_call), 333);
         assert_eq!(profile_data.get_ext_cost(ExtCosts::storage_read_base), 33);
     }
+
+    #[test]
+    fn test_cost_names() {
+        // Names end up in RPC responses and stored profiles, they must not
+        // depend on the order or the spelling of the enum variants.
+        let golden = [
+            (Cost::ActionCost { action_cost_kind: ActionCosts::create_account }, "CREATE_ACCOUNT"),
+            (Cost::ActionCost { action_cost_kind: ActionCosts::new_receipt }, "NEW_RECEIPT"),
+            (Cost::ExtCost { ext_cost_kind: ExtCosts::base }, "BASE"),
+            (Cost::ExtCost { ext_cost_kind: ExtCosts::storage_read_base }, "STORAGE_READ_BASE"),
+            (Cost::ExtCost { ext_cost_kind: ExtCosts::touching_trie_node }, "TOUCHING_TRIE_NODE"),
+            (
+                Cost::ExtCost { ext_cost_kind: ExtCosts::read_cached_trie_node },
+                "READ_CACHED_TRIE_NODE",
+            ),
+            (Cost::WasmInstruction, "WASM_INSTRUCTION"),
+        ];
+        for (cost, name) in golden {
+            assert_eq!(cost.name(), name);
+        }
+
+        let mut names: Vec<_> = Cost::ALL.iter().map(|cost| cost.name()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), Cost::ALL.len());
+    }
+
+    #[test]
+    fn test_iter_and_serialize() {
+        let mut profile_data = ProfileData::new();
+        assert_eq!(profile_data.iter().count(), 0);
+        assert_eq!(serde_json::to_string(&profile_data).unwrap(), "{}");
+
+        profile_data.add_ext_cost(ExtCosts::storage_read_base, 11);
+        profile_data.add_action_cost(ActionCosts::function_call, 111);
+        assert_eq!(
+            profile_data.iter().collect::<Vec<_>>(),
+            [("FUNCTION_CALL", 111), ("STORAGE_READ_BASE", 11)]
+        );
+        assert_eq!(
+            serde_json::to_string(&profile_data).unwrap(),
+            r#"{"FUNCTION_CALL":111,"STORAGE_READ_BASE":11}"#
+        );
+    }
+
+    #[test]
+    fn test_checked_sub() {
+        let mut old = ProfileData::new();
+        old.add_action_cost(ActionCosts::function_call, 100);
+        old.add_ext_cost(ExtCosts::storage_read_base, 10);
+        let mut new = ProfileData::new();
+        new.add_action_cost(ActionCosts::function_call, 100);
+        new.add_ext_cost(ExtCosts::storage_read_base, 7);
+        new.add_ext_cost(ExtCosts::touching_trie_node, 5);
+
+        assert!(new.checked_sub(&new).unwrap().is_zero());
+        let delta = new.checked_sub(&old).unwrap();
+        assert!(!delta.is_zero());
+        assert_eq!(delta.get(Cost::ActionCost { action_cost_kind: ActionCosts::function_call }), 0);
+        assert_eq!(
+            delta.iter().collect::<Vec<_>>(),
+            [("STORAGE_READ_BASE", -3), ("TOUCHING_TRIE_NODE", 5)]
+        );
+        assert_eq!(
+            serde_json::to_string(&delta).unwrap(),
+            r#"{"STORAGE_READ_BASE":-3,"TOUCHING_TRIE_NODE":5}"#
+        );
+
+        let mut huge = ProfileData::new();
+        huge.add_ext_cost(ExtCosts::base, u64::MAX);
+        assert_eq!(huge.checked_sub(&ProfileData::new()), None);
+        assert_eq!(ProfileData::new().checked_sub(&huge), None);
+    }
 }
@@ -3,6 +3,7 @@
 //! * sir -- sender is receiver. Receipts that are directed by an account to itself are guaranteed
 //!   to not be cross-shard which is cheaper than cross-shard. Conversely, when sender is not a
 //!   receiver it might or might not be a cross-shard communication.
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 use crate::num_rational::Rational;
@@ -12,7 +13,9 @@ use crate::types::Gas;
 /// by the receiver).
 /// NOTE: `send_sir` or `send_not_sir` fees are usually burned when the item is being created.
 /// And `execution` fee is burned when the item is being executed.
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[derive(
+    Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Hash, PartialEq, Eq,
+)]
 pub struct Fee {
     /// Fee for sending an object from the sender to itself, guaranteeing that it does not leave
     /// the shard.
@@ -84,7 +87,9 @@ pub struct DataReceiptCreationConfig {
 }
 
 /// Describes the cost of creating a specific action, `Action`. Includes all variants.
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[derive(
+    Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Hash, PartialEq, Eq,
+)]
 pub struct ActionCreationConfig {
     /// Base cost of creating an account.
     pub create_account_cost: Fee,
@@ -116,7 +121,9 @@ pub struct ActionCreationConfig {
 }
 
 /// Describes the cost of creating an access key.
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[derive(
+    Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Hash, PartialEq, Eq,
+)]
 pub struct AccessKeyCreationConfig {
     /// Base cost of creating a full access access-key.
     pub full_access_cost: Fee,
@@ -127,7 +134,9 @@ pub struct AccessKeyCreationConfig {
 }
 
 /// Describes cost of storage per block
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[derive(
+    Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Hash, PartialEq, Eq,
+)]
 pub struct StorageUsageConfig {
     /// Number of bytes for an account record, including rounding up for account id.
     pub num_bytes_account: u64,
@@ -100,6 +100,98 @@ pub mod option_base64_format {
     }
 }
 
+/// Bytes of actions (function call arguments, contract code) serialized as
+/// base64 strings.
+///
+/// Deserialization also accepts arrays of numbers, which some clients still
+/// send, and rejects data decoding to more than `DEFAULT_MAX_DECODED_LEN` bytes.
+pub mod base64_bytes {
+    use std::fmt;
+
+    use serde::de::{self, SeqAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    use super::to_base64;
+
+    /// Larger than any contract or arguments the protocol accepts.
+    pub const DEFAULT_MAX_DECODED_LEN: usize = 16 * 1024 * 1024;
+
+    pub fn serialize<S>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&to_base64(data))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_with_max_len(deserializer, DEFAULT_MAX_DECODED_LEN)
+    }
+
+    /// Like `deserialize`, but with a limit other than
+    /// `DEFAULT_MAX_DECODED_LEN`.
+    pub fn deserialize_with_max_len<'de, D>(
+        deserializer: D,
+        max_len: usize,
+    ) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(BytesVisitor { max_len })
+    }
+
+    struct BytesVisitor {
+        max_len: usize,
+    }
+
+    impl BytesVisitor {
+        fn too_long<E: de::Error>(&self) -> E {
+            E::custom(format_args!("decoded data is longer than {} bytes", self.max_len))
+        }
+    }
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a base64 string or an array of bytes")
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<Vec<u8>, E> {
+            // Reject before decoding, every 4 characters decode to at most
+            // 3 bytes.
+            if s.len() / 4 * 3 > self.max_len.saturating_add(3) {
+                return Err(self.too_long());
+            }
+            let bytes = base64::decode(s).map_err(|err| E::custom(err.to_string()))?;
+            if bytes.len() > self.max_len {
+                return Err(self.too_long());
+            }
+            Ok(bytes)
+        }
+
+        fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+            if bytes.len() > self.max_len {
+                return Err(self.too_long());
+            }
+            Ok(bytes.to_vec())
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(self.max_len));
+            while let Some(byte) = seq.next_element()? {
+                if bytes.len() == self.max_len {
+                    return Err(self.too_long());
+                }
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+}
+
 pub mod base_bytes_format {
     use serde::de;
     use serde::{Deserialize, Deserializer, Serializer};
@@ -1,4 +1,5 @@
 use crate::hash::CryptoHash;
+use borsh::{BorshDeserialize, BorshSerialize};
 
 /// Account identifier. Provides access to user's state.
 pub use crate::account::id::AccountId;
@@ -27,7 +28,7 @@ pub type Gas = u64;
 
 /// Weight of unused gas to distribute to scheduled function call actions.
 /// Used in `promise_batch_action_function_call_weight` host function.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
 pub struct GasWeight(pub u64);
 
 /// Result from a gas distribution among function calls with ratios.
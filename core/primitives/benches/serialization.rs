@@ -10,8 +10,11 @@ use near_primitives::account::Account;
 use near_primitives::block::{genesis_chunks, Block};
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::combine_hash;
+use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
 use near_primitives::test_utils::account_new;
-use near_primitives::transaction::{Action, SignedTransaction, Transaction, TransferAction};
+use near_primitives::transaction::{
+    Action, FunctionCallAction, SignedTransaction, Transaction, TransferAction,
+};
 use near_primitives::types::{EpochId, StateRoot};
 use near_primitives::validator_signer::InMemoryValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
@@ -32,6 +35,7 @@ fn create_transaction() -> SignedTransaction {
             receiver_id: "1231231232131".parse().unwrap(),
             block_hash: Default::default(),
             actions,
+            priority_fee: None,
         },
     )
 }
@@ -127,6 +131,42 @@ fn deserialize_account(bench: &mut Bencher) {
     });
 }
 
+fn create_receipt() -> Receipt {
+    let mut actions = vec![];
+    for _ in 0..10 {
+        actions.push(Action::FunctionCall(FunctionCallAction {
+            method_name: "method".to_string(),
+            args: vec![0; 1000],
+            gas: 1_000_000,
+            deposit: 1_000_000_000,
+        }));
+    }
+    Receipt {
+        predecessor_id: "123213123123".parse().unwrap(),
+        receiver_id: "1231231232131".parse().unwrap(),
+        receipt_id: CryptoHash::default(),
+        receipt: ReceiptEnum::Action(ActionReceipt {
+            signer_id: "123213123123".parse().unwrap(),
+            signer_public_key: PublicKey::empty(KeyType::ED25519),
+            gas_price: 0,
+            output_data_receivers: vec![],
+            input_data_ids: vec![],
+            actions,
+            congestion: None,
+        }),
+    }
+}
+
+fn receipt_size_serialize(bench: &mut Bencher) {
+    let r = create_receipt();
+    bench.iter(|| black_box(r.try_to_vec().unwrap().len()));
+}
+
+fn receipt_size_bytes(bench: &mut Bencher) {
+    let r = create_receipt();
+    bench.iter(|| black_box(r.size_bytes()));
+}
+
 fn combine_hash_bench(bench: &mut Bencher) {
     let a = MerkleHash::default();
     let b = MerkleHash::default();
@@ -144,6 +184,8 @@ benchmark_group!(
     deserialize_block,
     serialize_account,
     deserialize_account,
+    receipt_size_serialize,
+    receipt_size_bytes,
     combine_hash_bench,
 );
 benchmark_main!(benches);
@@ -1,4 +1,5 @@
-use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::sync::Arc;
 
 use crate::time::{Clock, Utc};
@@ -7,7 +8,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use chrono::DateTime;
 use near_crypto::Signature;
 use num_rational::Rational;
-use primitive_types::U256;
+use serde::{Deserialize, Serialize};
 
 use crate::block::BlockValidityError::{
     InvalidChallengeRoot, InvalidChunkHeaderRoot, InvalidChunkMask, InvalidReceiptRoot,
@@ -15,7 +16,9 @@ use crate::block::BlockValidityError::{
 };
 pub use crate::block_header::*;
 use crate::challenge::{Challenges, ChallengesResult};
+use crate::gas_price::compute_next_gas_price;
 use crate::hash::{hash, CryptoHash};
+use crate::logging;
 use crate::merkle::{merklize, verify_path, MerklePath};
 use crate::sharding::{
     ChunkHashHeight, EncodedShardChunk, ReedSolomonWrapper, ShardChunk, ShardChunkHeader,
@@ -235,13 +238,14 @@ impl Block {
                 chunk_mask.push(false);
             }
         }
-        let new_gas_price = Self::compute_new_gas_price(
+        let new_gas_price = compute_next_gas_price(
             prev.gas_price(),
             gas_used,
             gas_limit,
             gas_price_adjustment_rate,
             min_gas_price,
             max_gas_price,
+            this_epoch_protocol_version,
         );
 
         let new_total_supply = prev.total_supply() + minted_amount.unwrap_or(0) - balance_burnt;
@@ -316,46 +320,22 @@ impl Block {
         min_gas_price: Balance,
         max_gas_price: Balance,
         gas_price_adjustment_rate: Rational,
+        protocol_version: ProtocolVersion,
     ) -> bool {
         let gas_used = Self::compute_gas_used(self.chunks().iter(), self.header().height());
         let gas_limit = Self::compute_gas_limit(self.chunks().iter(), self.header().height());
-        let expected_price = Self::compute_new_gas_price(
+        let expected_price = compute_next_gas_price(
             prev_gas_price,
             gas_used,
             gas_limit,
             gas_price_adjustment_rate,
             min_gas_price,
             max_gas_price,
+            protocol_version,
         );
         self.header().gas_price() == expected_price
     }
 
-    pub fn compute_new_gas_price(
-        prev_gas_price: Balance,
-        gas_used: Gas,
-        gas_limit: Gas,
-        gas_price_adjustment_rate: Rational,
-        min_gas_price: Balance,
-        max_gas_price: Balance,
-    ) -> Balance {
-        if gas_limit == 0 {
-            prev_gas_price
-        } else {
-            let numerator = 2 * *gas_price_adjustment_rate.denom() as u128 * u128::from(gas_limit)
-                - *gas_price_adjustment_rate.numer() as u128 * u128::from(gas_limit)
-                + 2 * *gas_price_adjustment_rate.numer() as u128 * u128::from(gas_used);
-            let denominator =
-                2 * *gas_price_adjustment_rate.denom() as u128 * u128::from(gas_limit);
-            let new_gas_price =
-                U256::from(prev_gas_price) * U256::from(numerator) / U256::from(denominator);
-            if new_gas_price > U256::from(max_gas_price) {
-                max_gas_price
-            } else {
-                max(new_gas_price.as_u128(), min_gas_price)
-            }
-        }
-    }
-
     pub fn compute_state_root<'a, T: IntoIterator<Item = &'a ShardChunkHeader>>(
         chunks: T,
     ) -> CryptoHash {
@@ -602,7 +582,7 @@ impl<'a> ChunksCollection<'a> {
 /// The tip of a fork. A handle to the fork ancestry from its leaf in the
 /// blockchain tree. References the max height and the latest and previous
 /// blocks for convenience
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Tip {
     /// Height of the tip (max height of the fork)
     pub height: BlockHeight,
@@ -628,3 +608,491 @@ impl Tip {
         }
     }
 }
+
+impl fmt::Display for Tip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} epoch {}",
+            self.height,
+            logging::pretty_short_hash(&self.last_block_hash),
+            self.epoch_id.0
+        )
+    }
+}
+
+/// Hashes of the blocks at a height, grouped by epoch.  Value of
+/// `DBCol::BlockPerHeight`, with the borsh layout of the plain map.
+///
+/// Each hash is in one epoch only and there are no empty sets.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlocksPerHeight(HashMap<EpochId, HashSet<CryptoHash>>);
+
+impl BlocksPerHeight {
+    /// Adds a block, returns whether it wasn't known.  A known block stays
+    /// in its epoch.
+    pub fn insert(&mut self, epoch_id: EpochId, hash: CryptoHash) -> bool {
+        if self.contains(&hash) {
+            return false;
+        }
+        self.0.entry(epoch_id).or_default().insert(hash)
+    }
+
+    /// Removes a block, returns whether it was known.
+    pub fn remove(&mut self, hash: &CryptoHash) -> bool {
+        let epoch_id = match self.0.iter().find(|(_, hashes)| hashes.contains(hash)) {
+            Some((epoch_id, _)) => epoch_id.clone(),
+            None => return false,
+        };
+        let hashes = self.0.get_mut(&epoch_id).unwrap();
+        hashes.remove(hash);
+        if hashes.is_empty() {
+            self.0.remove(&epoch_id);
+        }
+        true
+    }
+
+    pub fn contains(&self, hash: &CryptoHash) -> bool {
+        self.0.values().any(|hashes| hashes.contains(hash))
+    }
+
+    /// Blocks of the epoch, if any.
+    pub fn get(&self, epoch_id: &EpochId) -> Option<&HashSet<CryptoHash>> {
+        self.0.get(epoch_id)
+    }
+
+    pub fn all_hashes(&self) -> impl Iterator<Item = &CryptoHash> {
+        self.0.values().flatten()
+    }
+
+    /// Whether there is more than one block at the height.
+    pub fn is_fork(&self) -> bool {
+        self.all_hashes().nth(1).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Options of `validate_header_chain`.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderValidationOptions {
+    /// Largest amount of nanoseconds a header's timestamp may be ahead of
+    /// its predecessor's.  `None` only requires timestamps to increase.
+    pub max_timestamp_drift: Option<u64>,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum HeaderValidationError {
+    #[error("header chain is not anchored at genesis {genesis_hash}: first header {hash} has prev hash {prev_hash}")]
+    NotAnchoredAtGenesis { genesis_hash: CryptoHash, hash: CryptoHash, prev_hash: CryptoHash },
+    #[error("header #{index} has hash {cached} but its contents hash to {computed}")]
+    HashMismatch { index: usize, cached: CryptoHash, computed: CryptoHash },
+    #[error("header #{index} has prev hash {prev_hash}, expected {expected}")]
+    BrokenLink { index: usize, expected: CryptoHash, prev_hash: CryptoHash },
+    #[error("header #{index} has height {height} not above previous height {prev_height}")]
+    NonIncreasingHeight { index: usize, prev_height: BlockHeight, height: BlockHeight },
+    #[error(
+        "header #{index} has timestamp {timestamp} not above previous timestamp {prev_timestamp}"
+    )]
+    NonIncreasingTimestamp { index: usize, prev_timestamp: u64, timestamp: u64 },
+    #[error("header #{index} timestamp is {drift}ns ahead of the previous one, at most {max_drift}ns allowed")]
+    TimestampDrift { index: usize, drift: u64, max_drift: u64 },
+    #[error("header #{index} is of an older version than the header before it")]
+    VersionDowngrade { index: usize },
+    #[error("header #{index} chunks_included does not match its chunk mask")]
+    InvalidChunksIncluded { index: usize },
+    #[error("header #{index} has prev height {prev_height}, expected {expected}")]
+    InvalidPrevHeight { index: usize, expected: BlockHeight, prev_height: BlockHeight },
+}
+
+/// Runs the stateless checks on a chain of headers, ordered from the oldest
+/// one.  The first header must be either the genesis header or its child.
+///
+/// Checked are the hash of every header against its contents, the prev hash
+/// links, strictly increasing heights and timestamps (within
+/// `options.max_timestamp_drift`) and the fields specific to the header
+/// version: `chunks_included` of V1 headers (except genesis, which has no
+/// chunk mask), `prev_height` of V3 headers and that versions never go down
+/// along the chain.
+///
+/// Everything which requires the validator sets is out of scope: block
+/// producer signatures, approvals, finality and epoch transitions have to be
+/// verified separately.
+pub fn validate_header_chain(
+    headers: &[BlockHeader],
+    genesis_hash: &CryptoHash,
+    options: HeaderValidationOptions,
+) -> Result<(), HeaderValidationError> {
+    let first = match headers.first() {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+    if first.hash() != genesis_hash && first.prev_hash() != genesis_hash {
+        return Err(HeaderValidationError::NotAnchoredAtGenesis {
+            genesis_hash: *genesis_hash,
+            hash: *first.hash(),
+            prev_hash: *first.prev_hash(),
+        });
+    }
+    for (index, header) in headers.iter().enumerate() {
+        let computed = BlockHeader::compute_hash(
+            *header.prev_hash(),
+            &header.inner_lite_bytes(),
+            &header.inner_rest_bytes(),
+        );
+        if &computed != header.hash() {
+            return Err(HeaderValidationError::HashMismatch {
+                index,
+                cached: *header.hash(),
+                computed,
+            });
+        }
+        if header.hash() != genesis_hash && !header.verify_chunks_included() {
+            return Err(HeaderValidationError::InvalidChunksIncluded { index });
+        }
+        if index == 0 {
+            continue;
+        }
+        let prev = &headers[index - 1];
+        if header.prev_hash() != prev.hash() {
+            return Err(HeaderValidationError::BrokenLink {
+                index,
+                expected: *prev.hash(),
+                prev_hash: *header.prev_hash(),
+            });
+        }
+        if header.height() <= prev.height() {
+            return Err(HeaderValidationError::NonIncreasingHeight {
+                index,
+                prev_height: prev.height(),
+                height: header.height(),
+            });
+        }
+        if header.raw_timestamp() <= prev.raw_timestamp() {
+            return Err(HeaderValidationError::NonIncreasingTimestamp {
+                index,
+                prev_timestamp: prev.raw_timestamp(),
+                timestamp: header.raw_timestamp(),
+            });
+        }
+        if let Some(max_drift) = options.max_timestamp_drift {
+            let drift = header.raw_timestamp() - prev.raw_timestamp();
+            if drift > max_drift {
+                return Err(HeaderValidationError::TimestampDrift { index, drift, max_drift });
+            }
+        }
+        if header_version(header) < header_version(prev) {
+            return Err(HeaderValidationError::VersionDowngrade { index });
+        }
+        if let Some(prev_height) = header.prev_height() {
+            if prev_height != prev.height() {
+                return Err(HeaderValidationError::InvalidPrevHeight {
+                    index,
+                    expected: prev.height(),
+                    prev_height,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn header_version(header: &BlockHeader) -> u8 {
+    match header {
+        BlockHeader::BlockHeaderV1(_) => 1,
+        BlockHeader::BlockHeaderV2(_) => 2,
+        BlockHeader::BlockHeaderV3(_) => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator_signer::InMemoryValidatorSigner;
+    use crate::version::PROTOCOL_VERSION;
+    use near_crypto::KeyType;
+
+    fn signer() -> InMemoryValidatorSigner {
+        InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test")
+    }
+
+    fn produce_header(protocol_version: ProtocolVersion, prev: &Block) -> Block {
+        Block::produce(
+            protocol_version,
+            protocol_version,
+            prev.header(),
+            prev.header().height() + 1,
+            prev.header().block_ordinal() + 1,
+            prev.chunks().iter().cloned().collect(),
+            prev.header().epoch_id().clone(),
+            prev.header().next_epoch_id().clone(),
+            None,
+            vec![],
+            Rational::from_integer(0),
+            0,
+            0,
+            Some(0),
+            vec![],
+            vec![],
+            &signer(),
+            CryptoHash::default(),
+            CryptoHash::default(),
+            None,
+        )
+    }
+
+    /// Returns the genesis hash and a chain of `len` headers starting at
+    /// genesis.
+    fn header_chain(
+        protocol_version: ProtocolVersion,
+        len: usize,
+    ) -> (CryptoHash, Vec<BlockHeader>) {
+        let chunks = genesis_chunks(vec![StateRoot::default()], 1, 1_000, 0, protocol_version);
+        let mut block = Block::genesis(
+            protocol_version,
+            chunks.into_iter().map(|chunk| chunk.take_header()).collect(),
+            Clock::utc(),
+            0,
+            1_000,
+            1_000,
+            CryptoHash::default(),
+        );
+        let genesis_hash = *block.hash();
+        let mut headers = vec![block.header().clone()];
+        while headers.len() < len {
+            block = produce_header(protocol_version, &block);
+            headers.push(block.header().clone());
+        }
+        (genesis_hash, headers)
+    }
+
+    fn validate(
+        genesis_hash: &CryptoHash,
+        headers: &[BlockHeader],
+    ) -> Result<(), HeaderValidationError> {
+        validate_header_chain(headers, genesis_hash, HeaderValidationOptions::default())
+    }
+
+    /// Applies `corrupt` to the last header and re-signs it, so that only the
+    /// corrupted field is wrong.
+    fn corrupt_last(headers: &mut [BlockHeader], corrupt: impl FnOnce(&mut BlockHeaderV3)) {
+        let header = headers.last_mut().unwrap();
+        corrupt(header.get_mut());
+        header.resign(&signer());
+    }
+
+    #[test]
+    fn test_valid_header_chain() {
+        for protocol_version in [29, PROTOCOL_VERSION] {
+            let (genesis_hash, headers) = header_chain(protocol_version, 5);
+            assert_eq!(validate(&genesis_hash, &headers), Ok(()));
+            assert_eq!(validate(&genesis_hash, &headers[1..]), Ok(()));
+            assert_eq!(validate(&genesis_hash, &[]), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_not_anchored_at_genesis() {
+        let (genesis_hash, headers) = header_chain(PROTOCOL_VERSION, 4);
+        assert!(matches!(
+            validate(&genesis_hash, &headers[2..]),
+            Err(HeaderValidationError::NotAnchoredAtGenesis { .. })
+        ));
+    }
+
+    #[test]
+    fn test_hash_mismatch() {
+        let (genesis_hash, mut headers) = header_chain(PROTOCOL_VERSION, 4);
+        headers[3].get_mut().inner_lite.outcome_root = hash(b"outcome");
+        assert!(matches!(
+            validate(&genesis_hash, &headers),
+            Err(HeaderValidationError::HashMismatch { index: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_broken_link() {
+        let (genesis_hash, mut headers) = header_chain(PROTOCOL_VERSION, 4);
+        headers.remove(2);
+        assert_eq!(
+            validate(&genesis_hash, &headers),
+            Err(HeaderValidationError::BrokenLink {
+                index: 2,
+                expected: *headers[1].hash(),
+                prev_hash: *headers[2].prev_hash(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_non_increasing_height() {
+        let (genesis_hash, mut headers) = header_chain(PROTOCOL_VERSION, 4);
+        corrupt_last(&mut headers, |header| header.inner_lite.height = 2);
+        assert_eq!(
+            validate(&genesis_hash, &headers),
+            Err(HeaderValidationError::NonIncreasingHeight { index: 3, prev_height: 2, height: 2 })
+        );
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let (genesis_hash, mut headers) = header_chain(PROTOCOL_VERSION, 4);
+        let prev_timestamp = headers[2].raw_timestamp();
+        // Make the last drift the largest one in the chain.
+        let drift = headers
+            .windows(2)
+            .map(|pair| pair[1].raw_timestamp() - pair[0].raw_timestamp())
+            .max()
+            .unwrap()
+            + 1000;
+        corrupt_last(&mut headers, |header| header.inner_lite.timestamp = prev_timestamp + drift);
+        let options = |max_drift| HeaderValidationOptions { max_timestamp_drift: Some(max_drift) };
+        assert_eq!(validate_header_chain(&headers, &genesis_hash, options(drift)), Ok(()));
+        assert_eq!(
+            validate_header_chain(&headers, &genesis_hash, options(drift - 1)),
+            Err(HeaderValidationError::TimestampDrift { index: 3, drift, max_drift: drift - 1 })
+        );
+
+        corrupt_last(&mut headers, |header| header.inner_lite.timestamp = prev_timestamp);
+        assert_eq!(
+            validate(&genesis_hash, &headers),
+            Err(HeaderValidationError::NonIncreasingTimestamp {
+                index: 3,
+                prev_timestamp,
+                timestamp: prev_timestamp,
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_prev_height() {
+        let (genesis_hash, mut headers) = header_chain(PROTOCOL_VERSION, 4);
+        corrupt_last(&mut headers, |header| header.inner_rest.prev_height = 1);
+        assert_eq!(
+            validate(&genesis_hash, &headers),
+            Err(HeaderValidationError::InvalidPrevHeight { index: 3, expected: 2, prev_height: 1 })
+        );
+    }
+
+    #[test]
+    fn test_invalid_chunks_included() {
+        let (genesis_hash, mut headers) = header_chain(29, 4);
+        match headers.last_mut().unwrap() {
+            BlockHeader::BlockHeaderV1(header) => {
+                Arc::make_mut(header).inner_rest.chunks_included += 1;
+            }
+            _ => panic!("expected a V1 header"),
+        }
+        headers.last_mut().unwrap().resign(&signer());
+        assert_eq!(
+            validate(&genesis_hash, &headers),
+            Err(HeaderValidationError::InvalidChunksIncluded { index: 3 })
+        );
+    }
+
+    #[test]
+    fn test_version_downgrade() {
+        // Old chunk headers, so that an old block can be produced on top.
+        let chunks = genesis_chunks(vec![StateRoot::default()], 1, 1_000, 0, 29);
+        let genesis = Block::genesis(
+            PROTOCOL_VERSION,
+            chunks.into_iter().map(|chunk| chunk.take_header()).collect(),
+            Clock::utc(),
+            0,
+            1_000,
+            1_000,
+            CryptoHash::default(),
+        );
+        let block = produce_header(29, &genesis);
+        assert!(matches!(block.header(), BlockHeader::BlockHeaderV1(_)));
+        assert_eq!(
+            validate(genesis.hash(), &[genesis.header().clone(), block.header().clone()]),
+            Err(HeaderValidationError::VersionDowngrade { index: 1 })
+        );
+    }
+
+    fn tip() -> Tip {
+        Tip {
+            height: 42,
+            last_block_hash: hash(b"last"),
+            prev_block_hash: hash(b"prev"),
+            epoch_id: EpochId(hash(b"epoch")),
+            next_epoch_id: EpochId(hash(b"next epoch")),
+        }
+    }
+
+    #[test]
+    fn test_tip_json() {
+        let tip = tip();
+        let json = serde_json::to_value(&tip).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "height": 42,
+                "last_block_hash": hash(b"last").to_string(),
+                "prev_block_hash": hash(b"prev").to_string(),
+                "epoch_id": hash(b"epoch").to_string(),
+                "next_epoch_id": hash(b"next epoch").to_string(),
+            })
+        );
+        assert_eq!(serde_json::from_value::<Tip>(json).unwrap(), tip);
+    }
+
+    #[test]
+    fn test_tip_display() {
+        let tip = tip();
+        assert_eq!(
+            tip.to_string(),
+            format!("#42 {}… epoch {}", &hash(b"last").to_string()[..8], hash(b"epoch"))
+        );
+    }
+
+    #[test]
+    fn test_blocks_per_height() {
+        let (epoch1, epoch2) = (EpochId(hash(b"epoch1")), EpochId(hash(b"epoch2")));
+        let mut blocks = BlocksPerHeight::default();
+        assert!(blocks.is_empty() && !blocks.is_fork());
+        assert!(blocks.insert(epoch1.clone(), hash(b"a")));
+        assert!(!blocks.is_fork());
+        assert!(!blocks.insert(epoch1.clone(), hash(b"a")));
+        // A block can't move to another epoch.
+        assert!(!blocks.insert(epoch2.clone(), hash(b"a")));
+        assert_eq!(blocks.get(&epoch2), None);
+        assert!(blocks.insert(epoch2.clone(), hash(b"b")));
+        assert!(blocks.is_fork());
+        let mut all_hashes = blocks.all_hashes().cloned().collect::<Vec<_>>();
+        all_hashes.sort();
+        let mut expected = vec![hash(b"a"), hash(b"b")];
+        expected.sort();
+        assert_eq!(all_hashes, expected);
+
+        assert!(blocks.remove(&hash(b"b")));
+        assert!(!blocks.remove(&hash(b"b")));
+        // The emptied epoch is gone.
+        assert_eq!(blocks.get(&epoch2), None);
+        assert_eq!(blocks.0.len(), 1);
+        assert!(blocks.remove(&hash(b"a")));
+        assert!(blocks.is_empty());
+    }
+
+    #[test]
+    fn test_blocks_per_height_borsh() {
+        let mut map: HashMap<EpochId, HashSet<CryptoHash>> = HashMap::new();
+        map.entry(EpochId(hash(b"epoch1"))).or_default().extend([hash(b"a"), hash(b"b")]);
+        map.entry(EpochId(hash(b"epoch2"))).or_default().insert(hash(b"c"));
+        let old_bytes = map.try_to_vec().unwrap();
+
+        let blocks = BlocksPerHeight::try_from_slice(&old_bytes).unwrap();
+        assert_eq!(blocks, BlocksPerHeight(map));
+        assert_eq!(blocks.try_to_vec().unwrap(), old_bytes);
+
+        // Written by the plain map for one block in the default epoch.
+        let mut blocks = BlocksPerHeight::default();
+        blocks.insert(EpochId::default(), CryptoHash([7; 32]));
+        let golden = [&[1, 0, 0, 0][..], &[0; 32], &[1, 0, 0, 0], &[7; 32]].concat();
+        assert_eq!(blocks.try_to_vec().unwrap(), golden);
+        assert_eq!(BlocksPerHeight::try_from_slice(&golden).unwrap(), blocks);
+    }
+}
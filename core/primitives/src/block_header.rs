@@ -1,24 +1,27 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::time::Utc;
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::schema::{Declaration, Definition};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use chrono::DateTime;
 use serde::Serialize;
 
 use near_crypto::{KeyType, PublicKey, Signature};
 
+use crate::borsh_schema::add_enum_definition;
 use crate::challenge::ChallengesResult;
 use crate::hash::{hash, CryptoHash};
 use crate::merkle::combine_hash;
 use crate::network::PeerId;
 use crate::types::validator_stake::{ValidatorStake, ValidatorStakeIter, ValidatorStakeV1};
 use crate::types::{AccountId, Balance, BlockHeight, EpochId, MerkleHash, NumBlocks};
-use crate::utils::{from_timestamp, to_timestamp};
+use crate::utils::{from_timestamp, to_timestamp, try_from_timestamp, TimestampError};
 use crate::validator_signer::ValidatorSigner;
 use crate::version::{get_protocol_version, ProtocolVersion, PROTOCOL_VERSION};
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, Eq, PartialEq)]
 pub struct BlockHeaderInnerLite {
     /// Height of this block.
     pub height: BlockHeight,
@@ -39,7 +42,7 @@ pub struct BlockHeaderInnerLite {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, Eq, PartialEq)]
 pub struct BlockHeaderInnerRest {
     /// Root hash of the chunk receipts in the given block.
     pub chunk_receipts_root: MerkleHash,
@@ -78,7 +81,7 @@ pub struct BlockHeaderInnerRest {
 
 /// Remove `chunks_included` from V1
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, Eq, PartialEq)]
 pub struct BlockHeaderInnerRestV2 {
     /// Root hash of the chunk receipts in the given block.
     pub chunk_receipts_root: MerkleHash,
@@ -118,7 +121,7 @@ pub struct BlockHeaderInnerRestV2 {
 /// Add `epoch_sync_data_hash`
 /// Use new `ValidatorStake` struct
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, Eq, PartialEq)]
 pub struct BlockHeaderInnerRestV3 {
     /// Root hash of the chunk receipts in the given block.
     pub chunk_receipts_root: MerkleHash,
@@ -231,7 +234,7 @@ impl ApprovalMessage {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, Eq, PartialEq)]
 #[borsh_init(init)]
 pub struct BlockHeaderV1 {
     pub prev_hash: CryptoHash,
@@ -261,7 +264,7 @@ impl BlockHeaderV1 {
 
 /// V1 -> V2: Remove `chunks_included` from `inner_reset`
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, Eq, PartialEq)]
 #[borsh_init(init)]
 pub struct BlockHeaderV2 {
     pub prev_hash: CryptoHash,
@@ -282,7 +285,7 @@ pub struct BlockHeaderV2 {
 /// V2 -> V3: Add `prev_height` to `inner_rest` and use new `ValidatorStake`
 // Add `block_ordinal` to `inner_rest`
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, Eq, PartialEq)]
 #[borsh_init(init)]
 pub struct BlockHeaderV3 {
     pub prev_hash: CryptoHash,
@@ -330,6 +333,27 @@ pub enum BlockHeader {
     BlockHeaderV3(Arc<BlockHeaderV3>),
 }
 
+/// The headers are kept in an `Arc`, which doesn't change the layout.
+impl BorshSchema for BlockHeader {
+    fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+        BlockHeaderV1::add_definitions_recursively(definitions);
+        BlockHeaderV2::add_definitions_recursively(definitions);
+        BlockHeaderV3::add_definitions_recursively(definitions);
+        add_enum_definition::<Self>(
+            vec![
+                ("BlockHeaderV1", vec![BlockHeaderV1::declaration()]),
+                ("BlockHeaderV2", vec![BlockHeaderV2::declaration()]),
+                ("BlockHeaderV3", vec![BlockHeaderV3::declaration()]),
+            ],
+            definitions,
+        );
+    }
+
+    fn declaration() -> Declaration {
+        "BlockHeader".to_string()
+    }
+}
+
 impl BlockHeader {
     pub fn compute_inner_hash(inner_lite: &[u8], inner_rest: &[u8]) -> CryptoHash {
         let hash_lite = hash(inner_lite);
@@ -873,6 +897,12 @@ impl BlockHeader {
         from_timestamp(self.raw_timestamp())
     }
 
+    /// Like `timestamp` but fails, rather than saturates, if the raw timestamp
+    /// is out of range.  Headers received from peers should be checked with it.
+    pub fn try_timestamp(&self) -> Result<DateTime<Utc>, TimestampError> {
+        try_from_timestamp(self.raw_timestamp())
+    }
+
     pub fn num_approvals(&self) -> u64 {
         self.approvals().iter().filter(|x| x.is_some()).count() as u64
     }
@@ -0,0 +1,311 @@
+//! Borsh schema of the types whose layout is part of the protocol.
+//!
+//! The schema is checked in as `res/borsh_schema.json` so that any change of
+//! the wire format shows up in review.  After an intentional change
+//! regenerate it with
+//!
+//! ```text
+//! cargo run -p borsh-schema-gen
+//! ```
+use std::collections::{BTreeMap, HashMap};
+
+use borsh::schema::{Declaration, Definition, Fields};
+use borsh::BorshSchema;
+use serde_json::{json, Value};
+
+use crate::account::AccessKey;
+use crate::block_header::BlockHeader;
+use crate::receipt::Receipt;
+use crate::transaction::{Action, SignedTransaction, Transaction};
+
+/// Defines `T` as an enum the way `#[derive(BorshSchema)]` does, with every
+/// variant wrapping a tuple struct named after the enum and the variant.
+/// The definitions of the wrapped types must be added beforehand.
+///
+/// For the enums serialized by hand, whose variants depend on enabled
+/// features or don't map to a single type.
+pub(crate) fn add_enum_definition<T: BorshSchema + ?Sized>(
+    variants: Vec<(&str, Vec<Declaration>)>,
+    definitions: &mut HashMap<Declaration, Definition>,
+) {
+    let enum_declaration = T::declaration();
+    let variants = variants
+        .into_iter()
+        .map(|(name, elements)| {
+            let declaration = format!("{}{}", enum_declaration, name);
+            // E.g. `ValidatorStake::V1(ValidatorStakeV1)`, a wrapper would
+            // have the name of the type it wraps and the same layout.
+            if elements.len() == 1 && elements[0] == declaration {
+                return (name.to_string(), declaration);
+            }
+            let fields =
+                if elements.is_empty() { Fields::Empty } else { Fields::UnnamedFields(elements) };
+            T::add_definition(declaration.clone(), Definition::Struct { fields }, definitions);
+            (name.to_string(), declaration)
+        })
+        .collect();
+    T::add_definition(enum_declaration, Definition::Enum { variants }, definitions);
+}
+
+fn definitions() -> (Vec<Declaration>, HashMap<Declaration, Definition>) {
+    let mut definitions = HashMap::new();
+    Transaction::add_definitions_recursively(&mut definitions);
+    SignedTransaction::add_definitions_recursively(&mut definitions);
+    Action::add_definitions_recursively(&mut definitions);
+    Receipt::add_definitions_recursively(&mut definitions);
+    BlockHeader::add_definitions_recursively(&mut definitions);
+    AccessKey::add_definitions_recursively(&mut definitions);
+    let roots = vec![
+        Transaction::declaration(),
+        SignedTransaction::declaration(),
+        Action::declaration(),
+        Receipt::declaration(),
+        BlockHeader::declaration(),
+        AccessKey::declaration(),
+    ];
+    (roots, definitions)
+}
+
+fn definition_json(definition: &Definition) -> Value {
+    match definition {
+        Definition::Array { length, elements } => {
+            json!({ "array": { "length": length, "elements": elements } })
+        }
+        Definition::Sequence { elements } => json!({ "sequence": elements }),
+        Definition::Tuple { elements } => json!({ "tuple": elements }),
+        Definition::Enum { variants } => json!({
+            "enum": variants
+                .iter()
+                .map(|(name, declaration)| json!({ "name": name, "type": declaration }))
+                .collect::<Vec<_>>()
+        }),
+        Definition::Struct { fields: Fields::NamedFields(fields) } => json!({
+            "struct": fields
+                .iter()
+                .map(|(name, declaration)| json!({ "name": name, "type": declaration }))
+                .collect::<Vec<_>>()
+        }),
+        Definition::Struct { fields: Fields::UnnamedFields(elements) } => {
+            json!({ "tuple_struct": elements })
+        }
+        Definition::Struct { fields: Fields::Empty } => json!({ "struct": [] }),
+    }
+}
+
+/// Schema of the consensus-critical types as JSON: the root declarations and
+/// the definitions of all declarations reachable from them.  Primitive types
+/// (`u8` to `u128`, `bool`, `string`, `nil`) have no definition.
+pub fn consensus_schema_json() -> Value {
+    let (roots, definitions) = definitions();
+    let definitions: BTreeMap<_, _> = definitions
+        .iter()
+        .map(|(declaration, definition)| (declaration.clone(), definition_json(definition)))
+        .collect();
+    json!({ "roots": roots, "definitions": definitions })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use near_crypto::{KeyType, PublicKey, Signature};
+
+    use super::*;
+    use crate::block::{genesis_chunks, Block};
+    use crate::block_header::{BlockHeaderInnerRest, BlockHeaderV1};
+    use crate::hash::CryptoHash;
+    use crate::receipt::{ActionReceipt, DataReceipt, ReceiptCongestion, ReceiptEnum};
+    use crate::transaction::TransferAction;
+    use crate::types::AccountId;
+    use crate::version::PROTOCOL_VERSION;
+
+    /// The fixture is generated without optional protocol features, which
+    /// add enum variants.
+    #[test]
+    #[cfg_attr(feature = "protocol_feature_chunk_only_producers", ignore)]
+    fn test_schema_matches_fixture() {
+        let expected: Value =
+            serde_json::from_str(include_str!("../res/borsh_schema.json")).unwrap();
+        assert_eq!(
+            consensus_schema_json(),
+            expected,
+            "the borsh layout changed, run `cargo run -p borsh-schema-gen` if intended"
+        );
+    }
+
+    fn take<'a>(buf: &mut &'a [u8], len: usize) -> &'a [u8] {
+        assert!(buf.len() >= len, "unexpected end of data");
+        let (head, tail) = buf.split_at(len);
+        *buf = tail;
+        head
+    }
+
+    fn take_u32(buf: &mut &[u8]) -> u32 {
+        u32::from_le_bytes(take(buf, 4).try_into().unwrap())
+    }
+
+    /// Consumes a value of `declaration` from `buf` following the schema
+    /// alone.
+    fn consume(declaration: &str, definitions: &HashMap<Declaration, Definition>, buf: &mut &[u8]) {
+        let size = match declaration {
+            "nil" => Some(0),
+            "bool" | "u8" | "i8" => Some(1),
+            "u16" | "i16" => Some(2),
+            "u32" | "i32" | "f32" | "char" => Some(4),
+            "u64" | "i64" | "f64" => Some(8),
+            "u128" | "i128" => Some(16),
+            "string" => Some(take_u32(buf) as usize),
+            _ => None,
+        };
+        if let Some(size) = size {
+            take(buf, size);
+            return;
+        }
+        let consume_all = |elements: &[Declaration], buf: &mut &[u8]| {
+            for element in elements {
+                consume(element, definitions, buf);
+            }
+        };
+        match definitions.get(declaration) {
+            Some(Definition::Array { length, elements }) => {
+                (0..*length).for_each(|_| consume(elements, definitions, buf))
+            }
+            Some(Definition::Sequence { elements }) => {
+                (0..take_u32(buf)).for_each(|_| consume(elements, definitions, buf))
+            }
+            Some(Definition::Tuple { elements }) => consume_all(elements, buf),
+            Some(Definition::Enum { variants }) => {
+                let tag = take(buf, 1)[0] as usize;
+                assert!(tag < variants.len(), "invalid tag {} for {}", tag, declaration);
+                consume(&variants[tag].1, definitions, buf);
+            }
+            Some(Definition::Struct { fields: Fields::NamedFields(fields) }) => {
+                fields.iter().for_each(|(_, field)| consume(field, definitions, buf))
+            }
+            Some(Definition::Struct { fields: Fields::UnnamedFields(elements) }) => {
+                consume_all(elements, buf)
+            }
+            Some(Definition::Struct { fields: Fields::Empty }) => {}
+            None => panic!("no definition for {}", declaration),
+        }
+    }
+
+    /// Checks that the schema describes the bytes of `value` exactly and
+    /// that they round-trip.
+    fn check_layout<T>(declaration: &str, value: &T)
+    where
+        T: BorshSerialize + BorshDeserialize + PartialEq + std::fmt::Debug,
+    {
+        let (_, definitions) = definitions();
+        let bytes = value.try_to_vec().unwrap();
+        let mut buf = bytes.as_slice();
+        consume(declaration, &definitions, &mut buf);
+        assert!(buf.is_empty(), "{} bytes left over after {}", buf.len(), declaration);
+        assert_eq!(&T::try_from_slice(&bytes).unwrap(), value);
+    }
+
+    fn account(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+
+    fn transaction(priority_fee: Option<u128>) -> Transaction {
+        Transaction {
+            signer_id: account("alice.near"),
+            public_key: PublicKey::empty(KeyType::SECP256K1),
+            nonce: 1,
+            receiver_id: account("bob.near"),
+            block_hash: CryptoHash::default(),
+            actions: vec![Action::Transfer(TransferAction { deposit: 10 })],
+            priority_fee,
+        }
+    }
+
+    fn action_receipt(congestion: Option<ReceiptCongestion>) -> ActionReceipt {
+        ActionReceipt {
+            signer_id: account("alice.near"),
+            signer_public_key: PublicKey::empty(KeyType::ED25519),
+            gas_price: 100,
+            output_data_receivers: vec![],
+            input_data_ids: vec![CryptoHash::default()],
+            actions: vec![Action::Transfer(TransferAction { deposit: 10 })],
+            congestion,
+        }
+    }
+
+    #[test]
+    fn test_transaction_layout() {
+        // V0 has no discriminant, so it can only be checked on its own.
+        check_layout("TransactionV0", &transaction(None));
+        check_layout("Transaction", &transaction(Some(5)));
+        let signed =
+            SignedTransaction::new(Signature::empty(KeyType::ED25519), transaction(Some(5)));
+        check_layout("SignedTransaction", &signed);
+    }
+
+    #[test]
+    fn test_receipt_enum_layout() {
+        let congestion = Some(ReceiptCongestion::default());
+        check_layout("ReceiptEnum", &ReceiptEnum::Action(action_receipt(congestion)));
+        check_layout(
+            "ReceiptEnum",
+            &ReceiptEnum::Data(DataReceipt { data_id: CryptoHash::default(), data: Some(vec![1]) }),
+        );
+    }
+
+    #[test]
+    fn test_action_receipt_layout() {
+        check_layout("ActionReceiptV0", &action_receipt(None));
+        check_layout("ActionReceipt", &action_receipt(Some(ReceiptCongestion::default())));
+    }
+
+    #[test]
+    fn test_block_header_layout() {
+        let genesis_chunks =
+            genesis_chunks(vec![CryptoHash::default()], 1, 1_000, 0, PROTOCOL_VERSION);
+        let genesis = Block::genesis(
+            PROTOCOL_VERSION,
+            genesis_chunks.into_iter().map(|chunk| chunk.take_header()).collect(),
+            chrono::Utc::now(),
+            0,
+            100,
+            1_000_000_000,
+            CryptoHash::default(),
+        );
+        let header = genesis.header().clone();
+        assert!(matches!(header, BlockHeader::BlockHeaderV3(_)));
+        check_layout("BlockHeader", &header);
+        if let BlockHeader::BlockHeaderV3(header) = header {
+            let mut v1 = BlockHeaderV1 {
+                prev_hash: header.prev_hash,
+                inner_lite: header.inner_lite.clone(),
+                inner_rest: BlockHeaderInnerRest {
+                    chunk_receipts_root: header.inner_rest.chunk_receipts_root,
+                    chunk_headers_root: header.inner_rest.chunk_headers_root,
+                    chunk_tx_root: header.inner_rest.chunk_tx_root,
+                    chunks_included: 1,
+                    challenges_root: header.inner_rest.challenges_root,
+                    random_value: header.inner_rest.random_value,
+                    validator_proposals: vec![],
+                    chunk_mask: header.inner_rest.chunk_mask.clone(),
+                    gas_price: header.inner_rest.gas_price,
+                    total_supply: header.inner_rest.total_supply,
+                    challenges_result: vec![],
+                    last_final_block: header.inner_rest.last_final_block,
+                    last_ds_final_block: header.inner_rest.last_ds_final_block,
+                    approvals: vec![None, Some(Signature::empty(KeyType::ED25519))],
+                    latest_protocol_version: header.inner_rest.latest_protocol_version,
+                },
+                signature: header.signature.clone(),
+                hash: CryptoHash::default(),
+            };
+            v1.init();
+            check_layout("BlockHeader", &BlockHeader::BlockHeaderV1(Arc::new(v1)));
+        }
+    }
+
+    #[test]
+    fn test_access_key_layout() {
+        check_layout("AccessKey", &AccessKey::full_access());
+    }
+}
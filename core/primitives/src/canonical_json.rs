@@ -0,0 +1,585 @@
+//! Canonical JSON form of off-chain payloads which get signed, following
+//! [RFC 8785](https://www.rfc-editor.org/rfc/rfc8785) (JSON Canonicalization
+//! Scheme) so signatures verify across implementations:
+//!
+//! - object keys are sorted by their UTF-16 code units,
+//! - there is no whitespace between tokens,
+//! - strings escape only `"`, `\` and control characters, using the short
+//!   escapes where JSON has them and lowercase `\u00xx` otherwise,
+//! - numbers are integers written in decimal.
+//!
+//! Floats aren't allowed, as their formatting is where implementations
+//! disagree the most, and neither are integers which a JSON number (an IEEE
+//! double) can't hold exactly, i.e. beyond ±(2^53 - 1).  Amounts and other
+//! large numbers should be serialized as strings, like the rest of our JSON
+//! does with `u128_dec_format`.
+use std::fmt::{self, Write};
+
+use serde::ser::{self, Serialize};
+
+use crate::hash::{hash, CryptoHash};
+
+/// Largest integer a JSON number holds exactly, `Number.MAX_SAFE_INTEGER`.
+const MAX_SAFE_INTEGER: u128 = (1 << 53) - 1;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum CanonicalJsonError {
+    #[error("floating point number {0} is not allowed in canonical JSON")]
+    Float(String),
+    #[error("integer {0} can't be represented exactly as a JSON number")]
+    UnsafeInteger(String),
+    #[error("object keys must be strings or integers")]
+    InvalidKey,
+    #[error("duplicate object key {0:?}")]
+    DuplicateKey(String),
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl ser::Error for CanonicalJsonError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+/// Serializes `value` into its canonical JSON form.
+pub fn to_canonical_string<T: Serialize + ?Sized>(value: &T) -> Result<String, CanonicalJsonError> {
+    let value = value.serialize(ValueSerializer)?;
+    let mut out = String::new();
+    value.write(&mut out);
+    Ok(out)
+}
+
+/// Returns the sha256 hash of the canonical JSON form of `value`.
+pub fn hash_canonical<T: Serialize + ?Sized>(value: &T) -> Result<CryptoHash, CanonicalJsonError> {
+    Ok(hash(to_canonical_string(value)?.as_bytes()))
+}
+
+enum Value {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn integer<T: Into<i128> + fmt::Display + Copy>(n: T) -> Result<Value, CanonicalJsonError> {
+        let n128 = n.into();
+        if n128.unsigned_abs() > MAX_SAFE_INTEGER {
+            return Err(CanonicalJsonError::UnsafeInteger(n.to_string()));
+        }
+        Ok(Value::Integer(n128))
+    }
+
+    fn object(mut entries: Vec<(String, Value)>) -> Result<Value, CanonicalJsonError> {
+        entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(CanonicalJsonError::DuplicateKey(pair[0].0.clone()));
+        }
+        Ok(Value::Object(entries))
+    }
+
+    fn write(&self, out: &mut String) {
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Value::Integer(n) => write!(out, "{}", n).unwrap(),
+            Value::String(s) => write_string(s, out),
+            Value::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    value.write(out);
+                }
+                out.push(']');
+            }
+            Value::Object(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_string(key, out);
+                    out.push(':');
+                    value.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Builds a [`Value`] with the same data model as `serde_json`, except that
+/// floats are rejected rather than turned into `null` when not finite.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeVariant<SerializeVec>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeVariant<SerializeMap>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Self::Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Self::Error> {
+        Value::integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, Self::Error> {
+        match i128::try_from(v) {
+            Ok(v) => Value::integer(v),
+            Err(_) => Err(CanonicalJsonError::UnsafeInteger(v.to_string())),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Self::Error> {
+        Err(CanonicalJsonError::Float(v.to_string()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Self::Error> {
+        Err(CanonicalJsonError::Float(v.to_string()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Self::Error> {
+        Ok(Value::Array(v.iter().map(|b| Value::Integer((*b).into())).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Self::Error> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Self::Error> {
+        Ok(Value::Object(vec![(variant.to_string(), value.serialize(self)?)]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec, Self::Error> {
+        Ok(SerializeVec(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(SerializeVariant { variant, inner: self.serialize_seq(Some(len))? })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap, Self::Error> {
+        Ok(SerializeMap { entries: Vec::with_capacity(len.unwrap_or(0)), next_key: None })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(SerializeVariant { variant, inner: self.serialize_map(Some(len))? })
+    }
+}
+
+struct SerializeVec(Vec<Value>);
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(Value::Array(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeMap {
+    entries: Vec<(String, Value)>,
+    next_key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        // Like `serde_json`, integer keys are written as strings.
+        self.next_key = Some(match key.serialize(ValueSerializer)? {
+            Value::String(key) => key,
+            Value::Integer(key) => key.to_string(),
+            _ => return Err(CanonicalJsonError::InvalidKey),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self.next_key.take().expect("serialize_value called before serialize_key");
+        self.entries.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Value::object(self.entries)
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.entries.push((key.to_string(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Value::object(self.entries)
+    }
+}
+
+/// Enum variant with data, serialized as `{"<variant>": <data>}`.
+struct SerializeVariant<T> {
+    variant: &'static str,
+    inner: T,
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeVec> {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        let value = ser::SerializeSeq::end(self.inner)?;
+        Ok(Value::Object(vec![(self.variant.to_string(), value)]))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeMap> {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        let value = ser::SerializeStruct::end(self.inner)?;
+        Ok(Value::Object(vec![(self.variant.to_string(), value)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_canonical, to_canonical_string, CanonicalJsonError};
+    use serde::Serialize;
+    use std::collections::HashMap;
+
+    /// Inputs with their canonical form and its sha256, as produced by the
+    /// RFC 8785 reference implementation in JavaScript:
+    ///
+    ///     function canonicalize(v) {
+    ///       if (v === null || typeof v !== 'object') return JSON.stringify(v);
+    ///       if (Array.isArray(v)) return '[' + v.map(canonicalize).join(',') + ']';
+    ///       return '{' + Object.keys(v).sort().map(
+    ///         k => JSON.stringify(k) + ':' + canonicalize(v[k])).join(',') + '}';
+    ///     }
+    const VECTORS: &[(&str, &str, &str)] = &[
+        (
+            r#"{"b": 2, "a": 1, "c": {"z": [3, 2, 1], "y": null}}"#,
+            r#"{"a":1,"b":2,"c":{"y":null,"z":[3,2,1]}}"#,
+            "3712356706198c68ea811dc7307845d54d357e37663a1a36bffd3bd36032b50d",
+        ),
+        (
+            r#"[true, false, null, 0, 9007199254740991, -9007199254740991]"#,
+            r#"[true,false,null,0,9007199254740991,-9007199254740991]"#,
+            "b0f01b2d3d0b3dbb89f801cd4ee4e1e2c70c331dd8350439805fc83ed3934ed4",
+        ),
+        // Keys are sorted by UTF-16 code units, which puts U+1F600 (a
+        // surrogate pair) before U+E000.
+        (
+            r#"{"\u20ac": "Euro", "\r": "CR", "1": "one", "\u0080": "ctrl", "\ud83d\ude00": "smile", "\ue000": "private", "10": "ten", "": "empty"}"#,
+            "{\"\":\"empty\",\"\\r\":\"CR\",\"1\":\"one\",\"10\":\"ten\",\"\u{80}\":\"ctrl\",\"\u{20ac}\":\"Euro\",\"\u{1f600}\":\"smile\",\"\u{e000}\":\"private\"}",
+            "abff351a5f283bfff028d2d81c14c86518ba6caa937a6ac557f73094be08db12",
+        ),
+        (
+            r#""\u0000\u0008\t\n\u000b\f\r\u001f \"\\/\u007f\u2028\u00e9""#,
+            "\"\\u0000\\b\\t\\n\\u000b\\f\\r\\u001f \\\"\\\\/\u{7f}\u{2028}\u{e9}\"",
+            "1e9dfb479ae42d0ba3b59c5e3ca7ccc1a760d27eeb9a8ea598808ca478081d89",
+        ),
+        (
+            r#"{"receiver_id": "bob.near", "nonce": 42, "message": "Hello,\n\"NEAR\"", "recipient": "app.example", "callback_url": null}"#,
+            r#"{"callback_url":null,"message":"Hello,\n\"NEAR\"","nonce":42,"receiver_id":"bob.near","recipient":"app.example"}"#,
+            "106f535e323412a6b16e01f5ee2c75a9bbeef7e8f9d59983f504560c9b87e301",
+        ),
+        (
+            r#"{"nested": {"b": [{"d": 4, "c": 3}], "a": {}}, "empty": []}"#,
+            r#"{"empty":[],"nested":{"a":{},"b":[{"c":3,"d":4}]}}"#,
+            "49c19e9a8f348f309884a9ee554bbd7812385a20c9343773fb53b8611332a561",
+        ),
+    ];
+
+    #[test]
+    fn test_vectors() {
+        for (input, canonical, sha256) in VECTORS {
+            let value: serde_json::Value = serde_json::from_str(input).unwrap();
+            assert_eq!(&to_canonical_string(&value).unwrap(), canonical, "{}", input);
+            assert_eq!(&hex::encode(hash_canonical(&value).unwrap()), sha256, "{}", input);
+        }
+    }
+
+    #[derive(Serialize)]
+    enum Payload {
+        Unit,
+        Newtype(u64),
+        Tuple(u8, String),
+        Struct { z: bool, a: Option<u32> },
+    }
+
+    #[derive(Serialize)]
+    struct Message {
+        recipient: String,
+        nonce: [u8; 2],
+        payloads: Vec<Payload>,
+        #[serde(with = "crate::serialize::u128_dec_format")]
+        amount: u128,
+    }
+
+    #[test]
+    fn test_serde_data_model() {
+        let message = Message {
+            recipient: "app.example".to_string(),
+            nonce: [1, 2],
+            payloads: vec![
+                Payload::Unit,
+                Payload::Newtype(7),
+                Payload::Tuple(1, "x".to_string()),
+                Payload::Struct { z: true, a: None },
+            ],
+            amount: u128::MAX,
+        };
+        let canonical = to_canonical_string(&message).unwrap();
+        assert_eq!(
+            canonical,
+            r#"{"amount":"340282366920938463463374607431768211455","nonce":[1,2],"payloads":["Unit",{"Newtype":7},{"Tuple":[1,"x"]},{"Struct":{"a":null,"z":true}}],"recipient":"app.example"}"#
+        );
+        // The same data as `serde_json` sees it, just ordered and compact.
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(to_canonical_string(&value).unwrap(), canonical);
+
+        // Map order doesn't matter, integer keys are strings.
+        let map: HashMap<u32, &str> = (0..20).map(|i| (i, "v")).collect();
+        let canonical = to_canonical_string(&map).unwrap();
+        assert!(canonical.starts_with(r#"{"0":"v","1":"v","10":"v","11":"v""#), "{}", canonical);
+    }
+
+    #[test]
+    fn test_rejected() {
+        assert_eq!(to_canonical_string(&1.5f64), Err(CanonicalJsonError::Float("1.5".to_string())));
+        assert_eq!(
+            to_canonical_string(&vec![f64::NAN]),
+            Err(CanonicalJsonError::Float("NaN".to_string()))
+        );
+        assert!(matches!(to_canonical_string(&f32::INFINITY), Err(CanonicalJsonError::Float(_))));
+        assert_eq!(
+            to_canonical_string(&(1u64 << 53)),
+            Err(CanonicalJsonError::UnsafeInteger("9007199254740992".to_string()))
+        );
+        assert_eq!(
+            to_canonical_string(&-(1i64 << 53)),
+            Err(CanonicalJsonError::UnsafeInteger("-9007199254740992".to_string()))
+        );
+        assert!(matches!(
+            to_canonical_string(&u128::MAX),
+            Err(CanonicalJsonError::UnsafeInteger(_))
+        ));
+        assert_eq!(
+            to_canonical_string(&i128::MIN),
+            Err(CanonicalJsonError::UnsafeInteger(i128::MIN.to_string()))
+        );
+
+        let map: HashMap<(u8, u8), u8> = [((1, 2), 3)].into_iter().collect();
+        assert_eq!(to_canonical_string(&map), Err(CanonicalJsonError::InvalidKey));
+        assert_eq!(
+            to_canonical_string(&DuplicateKeys),
+            Err(CanonicalJsonError::DuplicateKey("1".to_string()))
+        );
+    }
+
+    /// A map with both `1` and `"1"` as keys, which are both `"1"` in JSON.
+    struct DuplicateKeys;
+
+    impl Serialize for DuplicateKeys {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeMap;
+            let mut map = serializer.serialize_map(Some(2))?;
+            map.serialize_entry(&1, &1)?;
+            map.serialize_entry("1", &2)?;
+            map.end()
+        }
+    }
+}
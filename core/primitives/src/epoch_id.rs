@@ -0,0 +1,71 @@
+//! Derivation of epoch ids and heights.
+//!
+//! Epochs are identified by the hash of the last block of the epoch two
+//! epochs before them: the id of epoch T is the hash of the last block of
+//! epoch T-2.  This way the id of the next epoch is already known once the
+//! current epoch starts, and the id of the epoch after it once the current
+//! epoch ends.
+//!
+//! The chain starts with a dummy pre-genesis block which is the parent of
+//! the genesis block.  Viewing it as the last block of epoch -1 and the
+//! genesis block as the last block of epoch 0 extends the rule to the first
+//! epochs:
+//! - the first epoch, which contains the genesis block and the blocks up to
+//!   the first epoch boundary, has the default (all zeros) id;
+//! - the second epoch's id is the genesis block hash.
+//!
+//! Epoch heights count epochs, starting from `GENESIS_EPOCH_HEIGHT`.
+use crate::hash::CryptoHash;
+use crate::types::{EpochHeight, EpochId};
+
+/// Height of the first epoch.
+pub const GENESIS_EPOCH_HEIGHT: EpochHeight = 1;
+
+/// Height of the epoch following the epoch at `epoch_height`.
+pub fn next_epoch_height(epoch_height: EpochHeight) -> EpochHeight {
+    epoch_height + 1
+}
+
+/// Id of the epoch following epoch T, given the previous hash of the first
+/// block of epoch T, i.e. the last block of epoch T-1.
+pub fn next_epoch_id(epoch_first_block_prev_hash: &CryptoHash) -> EpochId {
+    EpochId::from_prev_prev_last_block(epoch_first_block_prev_hash)
+}
+
+/// Id of epoch T+2, given the hash of the last block of epoch T.
+pub fn next_next_epoch_id(epoch_last_block_hash: &CryptoHash) -> EpochId {
+    EpochId::from_prev_prev_last_block(epoch_last_block_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash;
+
+    #[test]
+    fn test_genesis_epoch() {
+        assert_eq!(EpochId::genesis(), EpochId::default());
+        assert_eq!(EpochId::genesis().0.to_string(), "11111111111111111111111111111111");
+        assert_eq!(next_epoch_height(GENESIS_EPOCH_HEIGHT), 2);
+    }
+
+    #[test]
+    fn test_epoch_id_golden() {
+        let genesis_hash = hash(b"genesis");
+        let last_block_hash = hash(b"last block");
+        // The genesis block is the prev block of the first block of the
+        // first epoch, so it identifies the second epoch.
+        assert_eq!(
+            next_epoch_id(&genesis_hash).0.to_string(),
+            "CmpNeggWJ4JaWJeJ8YKN1Zypmk7uvQq3PECGUCAEMbky"
+        );
+        assert_eq!(
+            next_next_epoch_id(&last_block_hash).0.to_string(),
+            "EM7ipCmwUfQz9iiZ3ZvCX1mnFjEn5xZ8kTpnTdjMPDHx"
+        );
+        assert_eq!(
+            EpochId::from_prev_prev_last_block(&last_block_hash),
+            next_next_epoch_id(&last_block_hash)
+        );
+    }
+}
@@ -54,6 +54,20 @@ pub struct EpochConfig {
     pub validator_selection_config: ValidatorSelectionConfig,
 }
 
+impl EpochConfig {
+    /// Minimum stake at the given seat price, see `minimum_stake`.
+    pub fn minimum_stake(&self, seat_price: Balance) -> Balance {
+        minimum_stake(seat_price, self.minimum_stake_divisor)
+    }
+}
+
+/// Minimum stake allowed when the next epoch has the given seat price.  Attempts to stake
+/// with a lower stake are rejected.  Depends only on the epoch info and config, so RPC can
+/// compute it without the epoch manager.
+pub fn minimum_stake(seat_price: Balance, minimum_stake_divisor: u64) -> Balance {
+    seat_price / minimum_stake_divisor as Balance
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShardConfig {
     pub num_block_producer_seats_per_shard: Vec<NumSeats>,
@@ -1,5 +1,7 @@
+use crate::safe_math::BalanceDelta;
 use crate::serialize::u128_dec_format;
-use crate::types::{AccountId, Balance, EpochId, Gas, Nonce};
+use crate::sharding::ChunkHash;
+use crate::types::{AccountId, Balance, BlockHeight, EpochId, Gas, Nonce, NumShards, ShardId};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::PublicKey;
 use serde::{Deserialize, Serialize};
@@ -140,6 +142,8 @@ pub enum InvalidTxError {
     ActionsValidation(ActionsValidationError),
     /// The size of serialized transaction exceeded the limit.
     TransactionSizeExceeded { size: u64, limit: u64 },
+    /// The transaction has a priority fee, which the current protocol version doesn't support.
+    PriorityFeeNotSupported,
 }
 
 impl std::error::Error for InvalidTxError {}
@@ -200,6 +204,10 @@ pub enum ActionsValidationError {
     UnsuitableStakingKey { public_key: PublicKey },
     /// The attached amount of gas in a FunctionCall action has to be a positive number.
     FunctionCallZeroAttachedGas,
+    /// The public key of an AddKey action is not a valid point on its curve.
+    AddKeyPublicKeyNotOnCurve { public_key: PublicKey },
+    /// The beneficiary of a DeleteAccount action is the deleted account itself.
+    DeleteActionMustHaveDifferentBeneficiary { account_id: AccountId, beneficiary_id: AccountId },
 }
 
 /// Describes the error for validating a receipt.
@@ -317,6 +325,19 @@ impl Display for ActionsValidationError {
                 f,
                 "The attached amount of gas in a FunctionCall action has to be a positive number",
             ),
+            ActionsValidationError::AddKeyPublicKeyNotOnCurve { public_key } => write!(
+                f,
+                "The public key {} of an AddKey action is not a valid point on its curve",
+                public_key,
+            ),
+            ActionsValidationError::DeleteActionMustHaveDifferentBeneficiary {
+                account_id,
+                beneficiary_id,
+            } => write!(
+                f,
+                "Deleting account {} with beneficiary {} would burn its balance, the beneficiary must be a different account",
+                account_id, beneficiary_id,
+            ),
         }
     }
 }
@@ -441,6 +462,8 @@ pub enum ActionErrorKind {
     OnlyImplicitAccountCreationAllowed { account_id: AccountId },
     /// Delete account whose state is large is temporarily banned.
     DeleteAccountWithLargeState { account_id: AccountId },
+    /// The staking key isn't an ED25519 key which can be converted to ristretto.
+    InvalidStakingKey { account_id: AccountId, public_key: PublicKey },
 }
 
 impl From<ActionErrorKind> for ActionError {
@@ -502,6 +525,12 @@ impl Display for InvalidTxError {
             InvalidTxError::TransactionSizeExceeded { size, limit } => {
                 write!(f, "Size of serialized transaction {} exceeded the limit {}", size, limit)
             }
+            InvalidTxError::PriorityFeeNotSupported => {
+                write!(
+                    f,
+                    "Transaction priority fees are not supported by the current protocol version"
+                )
+            }
         }
     }
 }
@@ -587,24 +616,37 @@ pub struct BalanceMismatchError {
 
 impl Display for BalanceMismatchError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let inputs = [
+            self.incoming_validator_rewards,
+            self.initial_accounts_balance,
+            self.incoming_receipts_balance,
+            self.processed_delayed_receipts_balance,
+            self.initial_postponed_receipts_balance,
+        ];
+        let outputs = [
+            self.final_accounts_balance,
+            self.outgoing_receipts_balance,
+            self.new_delayed_receipts_balance,
+            self.final_postponed_receipts_balance,
+            self.tx_burnt_amount,
+            self.slashed_burnt_amount,
+            self.other_burnt_amount,
+        ];
         // Using saturating add to avoid overflow in display
-        let initial_balance = self
-            .incoming_validator_rewards
-            .saturating_add(self.initial_accounts_balance)
-            .saturating_add(self.incoming_receipts_balance)
-            .saturating_add(self.processed_delayed_receipts_balance)
-            .saturating_add(self.initial_postponed_receipts_balance);
-        let final_balance = self
-            .final_accounts_balance
-            .saturating_add(self.outgoing_receipts_balance)
-            .saturating_add(self.new_delayed_receipts_balance)
-            .saturating_add(self.final_postponed_receipts_balance)
-            .saturating_add(self.tx_burnt_amount)
-            .saturating_add(self.slashed_burnt_amount)
-            .saturating_add(self.other_burnt_amount);
+        let initial_balance = inputs.iter().fold(0, |sum: Balance, x| sum.saturating_add(*x));
+        let final_balance = outputs.iter().fold(0, |sum: Balance, x| sum.saturating_add(*x));
+        let mut difference = BalanceDelta::default();
+        let difference = match inputs
+            .iter()
+            .try_for_each(|input| difference.add(*input))
+            .and_then(|()| outputs.iter().try_for_each(|output| difference.sub(*output)))
+        {
+            Ok(()) => difference.to_string(),
+            Err(_) => "overflow".to_string(),
+        };
         write!(
             f,
-            "Balance Mismatch Error. The input balance {} doesn't match output balance {}\n\
+            "Balance Mismatch Error. The input balance {} doesn't match output balance {} (difference {})\n\
              Inputs:\n\
              \tIncoming validator rewards sum: {}\n\
              \tInitial accounts balance sum: {}\n\
@@ -621,6 +663,7 @@ impl Display for BalanceMismatchError {
              \tOther burnt amount: {}",
             initial_balance,
             final_balance,
+            difference,
             self.incoming_validator_rewards,
             self.initial_accounts_balance,
             self.incoming_receipts_balance,
@@ -639,6 +682,158 @@ impl Display for BalanceMismatchError {
 
 impl std::error::Error for BalanceMismatchError {}
 
+/// Reasons a `StakeAction` can't be applied to an account, see
+/// `StakeAction::validate_against`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StakeValidationError {
+    /// The staking key isn't an ED25519 key which can be converted to ristretto.
+    UnsuitableStakingKey { public_key: PublicKey },
+    /// Account is not yet staked, but tries to unstake
+    TriesToUnstake,
+    /// The account doesn't have enough balance to increase the stake.
+    TriesToStake { stake: Balance, locked: Balance, balance: Balance },
+    /// The stake is non-zero but below the minimum stake.
+    InsufficientStake { stake: Balance, minimum_stake: Balance },
+}
+
+impl Display for StakeValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            StakeValidationError::UnsuitableStakingKey { public_key } => {
+                write!(f, "The staking key must be ristretto compatible ED25519 key. {} is provided instead.", public_key)
+            }
+            StakeValidationError::TriesToUnstake => {
+                write!(f, "Account is not yet staked, but tries to unstake")
+            }
+            StakeValidationError::TriesToStake { stake, locked, balance } => write!(
+                f,
+                "Account tries to stake {}, but has staked {} and only has {}",
+                stake, locked, balance
+            ),
+            StakeValidationError::InsufficientStake { stake, minimum_stake } => write!(
+                f,
+                "Account tries to stake {} but minimum required stake is {}",
+                stake, minimum_stake
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StakeValidationError {}
+
+/// Reasons `TransactionBuilder` can't build a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionBuilderError {
+    MissingNonce,
+    MissingBlockHash,
+    /// Neither a public key nor a signer was given.
+    MissingPublicKey,
+    NoActions,
+    /// The delete account action must be the last one.
+    DeleteActionMustBeFinal,
+    /// The transaction's public key isn't the key of the signer.
+    SignerKeyMismatch {
+        public_key: PublicKey,
+        signer_public_key: PublicKey,
+    },
+}
+
+impl Display for TransactionBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            TransactionBuilderError::MissingNonce => write!(f, "Transaction has no nonce"),
+            TransactionBuilderError::MissingBlockHash => {
+                write!(f, "Transaction has no block hash")
+            }
+            TransactionBuilderError::MissingPublicKey => {
+                write!(f, "Transaction has no public key")
+            }
+            TransactionBuilderError::NoActions => write!(f, "Transaction has no actions"),
+            TransactionBuilderError::DeleteActionMustBeFinal => {
+                write!(f, "The delete action must be the last action in transaction")
+            }
+            TransactionBuilderError::SignerKeyMismatch { public_key, signer_public_key } => {
+                write!(
+                    f,
+                    "Transaction public key {} doesn't match the signer's key {}",
+                    public_key, signer_public_key
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransactionBuilderError {}
+
+/// Reasons a chunk header fails the checks which don't need the chain, see
+/// `validate_chunk_header_basic`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkValidationError {
+    /// The hash of the header doesn't match the one computed from its inner part.
+    InvalidHash {
+        chunk_hash: ChunkHash,
+        computed_hash: ChunkHash,
+    },
+    InvalidShardId {
+        shard_id: ShardId,
+        num_shards: NumShards,
+    },
+    /// The chunk is included in a block below the height it was created at.
+    InvalidHeightIncluded {
+        height_created: BlockHeight,
+        height_included: BlockHeight,
+    },
+    GasLimitOutOfBounds {
+        gas_limit: Gas,
+        min: Gas,
+        max: Gas,
+    },
+    /// The encoded length is too small to hold even an empty chunk body.
+    InvalidEncodedLength {
+        encoded_length: u64,
+        min: u64,
+    },
+    /// `ShardChunkHeaderV3` carrying the inner header of the previous versions.
+    InnerHeaderVersionMismatch,
+}
+
+impl Display for ChunkValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ChunkValidationError::InvalidHash { chunk_hash, computed_hash } => write!(
+                f,
+                "Chunk hash {:?} doesn't match the hash of its inner header {:?}",
+                chunk_hash.0, computed_hash.0
+            ),
+            ChunkValidationError::InvalidShardId { shard_id, num_shards } => {
+                write!(f, "Chunk shard id {} is invalid, there are {} shards", shard_id, num_shards)
+            }
+            ChunkValidationError::InvalidHeightIncluded { height_created, height_included } => {
+                write!(
+                    f,
+                    "Chunk created at height {} is included at height {}",
+                    height_created, height_included
+                )
+            }
+            ChunkValidationError::GasLimitOutOfBounds { gas_limit, min, max } => write!(
+                f,
+                "Chunk gas limit {} is out of the allowed range [{}, {}]",
+                gas_limit, min, max
+            ),
+            ChunkValidationError::InvalidEncodedLength { encoded_length, min } => write!(
+                f,
+                "Chunk encoded length {} is less than the minimum {}",
+                encoded_length, min
+            ),
+            ChunkValidationError::InnerHeaderVersionMismatch => {
+                write!(f, "Chunk header V3 contains an inner header of an older version")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChunkValidationError {}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
 pub struct IntegerOverflowError;
 
@@ -696,16 +891,16 @@ impl Display for ActionErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
             ActionErrorKind::AccountAlreadyExists { account_id } => {
-                write!(f, "Can't create a new account {:?}, because it already exists", account_id)
+                write!(f, "Can't create a new account {}, because it already exists", account_id)
             }
             ActionErrorKind::AccountDoesNotExist { account_id } => write!(
                 f,
-                "Can't complete the action because account {:?} doesn't exist",
+                "Can't complete the action because account {} doesn't exist",
                 account_id
             ),
             ActionErrorKind::ActorNoPermission { actor_id, account_id } => write!(
                 f,
-                "Actor {:?} doesn't have permission to account {:?} to complete the action",
+                "Actor {} doesn't have permission to account {} to complete the action",
                 actor_id, account_id
             ),
             ActionErrorKind::LackBalanceForState { account_id, amount } => write!(
@@ -714,35 +909,35 @@ impl Display for ActionErrorKind {
                 account_id, amount
             ),
             ActionErrorKind::TriesToUnstake { account_id } => {
-                write!(f, "Account {:?} is not yet staked, but tries to unstake", account_id)
+                write!(f, "Account {} is not yet staked, but tries to unstake", account_id)
             }
             ActionErrorKind::TriesToStake { account_id, stake, locked, balance } => write!(
                 f,
-                "Account {:?} tries to stake {}, but has staked {} and only has {}",
+                "Account {} tries to stake {}, but has staked {} and only has {}",
                 account_id, stake, locked, balance
             ),
             ActionErrorKind::CreateAccountOnlyByRegistrar { account_id, registrar_account_id, predecessor_id } => write!(
                 f,
-                "A top-level account ID {:?} can't be created by {:?}, short top-level account IDs can only be created by {:?}",
+                "A top-level account ID {} can't be created by {}, short top-level account IDs can only be created by {}",
                 account_id, predecessor_id, registrar_account_id,
             ),
             ActionErrorKind::CreateAccountNotAllowed { account_id, predecessor_id } => write!(
                 f,
-                "A sub-account ID {:?} can't be created by account {:?}",
+                "A sub-account ID {} can't be created by account {}",
                 account_id, predecessor_id,
             ),
             ActionErrorKind::DeleteKeyDoesNotExist { account_id, .. } => write!(
                 f,
-                "Account {:?} tries to remove an access key that doesn't exist",
+                "Account {} tries to remove an access key that doesn't exist",
                 account_id
             ),
             ActionErrorKind::AddKeyAlreadyExists { public_key, .. } => write!(
                 f,
-                "The public key {:?} is already used for an existing access key",
+                "The public key {} is already used for an existing access key",
                 public_key
             ),
             ActionErrorKind::DeleteAccountStaking { account_id } => {
-                write!(f, "Account {:?} is staking and can not be deleted", account_id)
+                write!(f, "Account {} is staking and can not be deleted", account_id)
             }
             ActionErrorKind::FunctionCallError(s) => write!(f, "{:?}", s),
             ActionErrorKind::NewReceiptValidationError(e) => {
@@ -751,6 +946,7 @@ impl Display for ActionErrorKind {
             ActionErrorKind::InsufficientStake { account_id, stake, minimum_stake } => write!(f, "Account {} tries to stake {} but minimum required stake is {}", account_id, stake, minimum_stake),
             ActionErrorKind::OnlyImplicitAccountCreationAllowed { account_id } => write!(f, "CreateAccount action is called on hex-characters account of length 64 {}", account_id),
             ActionErrorKind::DeleteAccountWithLargeState { account_id } => write!(f, "The state of account {} is too large and therefore cannot be deleted", account_id),
+            ActionErrorKind::InvalidStakingKey { account_id, public_key } => write!(f, "Account {} tries to stake with {}, which is not a ristretto compatible ED25519 key", account_id, public_key),
         }
     }
 }
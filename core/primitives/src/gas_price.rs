@@ -0,0 +1,118 @@
+use crate::types::{Balance, Gas};
+use crate::version::ProtocolVersion;
+use num_rational::Rational;
+use primitive_types::U256;
+use std::cmp::max;
+
+/// Computes the gas price of the next block.  The price goes up if more than
+/// half of `gas_limit` was used in the current block and down otherwise:
+///
+///     next = current * (1 + adjustment_rate * (gas_used / gas_limit - 1 / 2))
+///
+/// bounded by `[min_gas_price, max_gas_price]`.  The price doesn't change if
+/// `gas_limit` is zero, i.e. the block has no new chunks.
+///
+/// The formula is the same in all protocol versions so far, `protocol_version`
+/// is the version of the epoch of the next block.
+pub fn compute_next_gas_price(
+    current_gas_price: Balance,
+    gas_used: Gas,
+    gas_limit: Gas,
+    adjustment_rate: Rational,
+    min_gas_price: Balance,
+    max_gas_price: Balance,
+    _protocol_version: ProtocolVersion,
+) -> Balance {
+    if gas_limit == 0 {
+        return current_gas_price;
+    }
+    let numerator = 2 * *adjustment_rate.denom() as u128 * u128::from(gas_limit)
+        - *adjustment_rate.numer() as u128 * u128::from(gas_limit)
+        + 2 * *adjustment_rate.numer() as u128 * u128::from(gas_used);
+    let denominator = 2 * *adjustment_rate.denom() as u128 * u128::from(gas_limit);
+    let next_gas_price =
+        U256::from(current_gas_price) * U256::from(numerator) / U256::from(denominator);
+    if next_gas_price > U256::from(max_gas_price) {
+        max_gas_price
+    } else {
+        max(next_gas_price.as_u128(), min_gas_price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::{
+        MIN_PROTOCOL_VERSION_NEP_92, MIN_PROTOCOL_VERSION_NEP_92_FIX, PROTOCOL_VERSION,
+    };
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const PROTOCOL_VERSIONS: [ProtocolVersion; 4] =
+        [29, MIN_PROTOCOL_VERSION_NEP_92, MIN_PROTOCOL_VERSION_NEP_92_FIX, PROTOCOL_VERSION];
+
+    #[test]
+    fn test_compute_next_gas_price_golden() {
+        // (current, gas_used, gas_limit, adjustment_rate, min, max, expected)
+        #[rustfmt::skip]
+        let cases: [(Balance, Gas, Gas, (isize, isize), Balance, Balance, Balance); 8] = [
+            (1_000_000_000, 0, 1_000_000, (1, 100), 100_000_000, 10_000_000_000, 995_000_000),
+            (1_000_000_000, 500_000, 1_000_000, (1, 100), 100_000_000, 10_000_000_000, 1_000_000_000),
+            (1_000_000_000, 1_000_000, 1_000_000, (1, 100), 100_000_000, 10_000_000_000, 1_005_000_000),
+            // Bounded by the max.
+            (1_000_000_000, 1_000_000, 1_000_000, (1, 100), 100_000_000, 1_001_000_000, 1_001_000_000),
+            // Bounded by the min.
+            (100_000_000, 0, 1_000_000, (1, 100), 100_000_000, 10_000_000_000, 100_000_000),
+            (1_234_567_891, 333_333, 1_000_000, (1, 100), 100_000_000, 10_000_000_000, 1_232_510_273),
+            (1_234_567_891, 999_999_999_999_999, 1_000_000_000_000_000, (3, 10), 1, Balance::MAX, 1_419_753_074),
+            // No chunks in the block.
+            (1_234_567_891, 123, 0, (1, 100), 100_000_000, 10_000_000_000, 1_234_567_891),
+        ];
+        for protocol_version in PROTOCOL_VERSIONS {
+            for (current, gas_used, gas_limit, (numer, denom), min, max, expected) in cases {
+                assert_eq!(
+                    compute_next_gas_price(
+                        current,
+                        gas_used,
+                        gas_limit,
+                        Rational::new(numer, denom),
+                        min,
+                        max,
+                        protocol_version,
+                    ),
+                    expected,
+                    "current={current} gas_used={gas_used} gas_limit={gas_limit} protocol_version={protocol_version}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_next_gas_price_bounds() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..10_000 {
+            let min = rng.gen_range(0, 1_000_000_000_000u128);
+            let max = rng.gen_range(min, 1_000_000_000_000_000_000u128);
+            let current = rng.gen_range(min, max + 1);
+            let gas_limit = rng.gen_range(0, 1_000_000_000_000_000u64);
+            let gas_used = rng.gen_range(0, gas_limit + 1);
+            let denom = rng.gen_range(1, 1_000isize);
+            let numer = rng.gen_range(0, denom);
+            let protocol_version = PROTOCOL_VERSIONS[rng.gen_range(0, PROTOCOL_VERSIONS.len())];
+            let next = compute_next_gas_price(
+                current,
+                gas_used,
+                gas_limit,
+                Rational::new(numer, denom),
+                min,
+                max,
+                protocol_version,
+            );
+            assert!(
+                min <= next && next <= max,
+                "next={next} current={current} gas_used={gas_used} gas_limit={gas_limit} \
+                 rate={numer}/{denom} min={min} max={max}"
+            );
+        }
+    }
+}
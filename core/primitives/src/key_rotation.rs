@@ -0,0 +1,175 @@
+//! Helpers for replacing an access key with a new one in a single transaction.
+use near_crypto::PublicKey;
+
+use crate::account::id::AccountId;
+use crate::account::{AccessKey, AccessKeyPermission};
+use crate::transaction::{Action, AddKeyAction, DeleteKeyAction};
+use crate::types::{BlockHeight, Nonce};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeyRotationError {
+    #[error("the new public key {0} is the same as the key being rotated")]
+    SameKey(PublicKey),
+    #[error("function call permission has invalid receiver id {receiver_id:?}")]
+    InvalidReceiverId { receiver_id: String },
+    #[error("function call permission contains an empty method name")]
+    EmptyMethodName,
+    #[error("the signing key must be a full access key to add and delete keys")]
+    SignerNotFullAccess,
+}
+
+/// Builds the actions rotating `old_public_key` to `new_public_key`: an
+/// `AddKey` for the new key with the same `permission`, followed by a
+/// `DeleteKey` for the old one.  Adding first means a failed transaction
+/// never leaves the account without the key.
+///
+/// `nonce_hint` is the height of the block the transaction is expected to
+/// land in; the new key's nonce follows the rule the runtime uses for keys
+/// added at that height (see `AccessKeyNonceRange`).
+///
+/// Function call permissions are copied as is, so their receiver id must be
+/// a valid account id and method names must be non-empty, otherwise the new
+/// key would be unusable.
+///
+/// `signer_permission` is the permission of the key signing the transaction.
+/// `AddKey` and `DeleteKey` are only allowed for full access keys, so any
+/// other signer is rejected here rather than by the runtime.
+pub fn build_rotation_actions(
+    old_public_key: &PublicKey,
+    new_public_key: &PublicKey,
+    permission: AccessKeyPermission,
+    signer_permission: &AccessKeyPermission,
+    nonce_hint: BlockHeight,
+) -> Result<Vec<Action>, KeyRotationError> {
+    if old_public_key == new_public_key {
+        return Err(KeyRotationError::SameKey(new_public_key.clone()));
+    }
+    if signer_permission != &AccessKeyPermission::FullAccess {
+        return Err(KeyRotationError::SignerNotFullAccess);
+    }
+    if let AccessKeyPermission::FunctionCall(function_call) = &permission {
+        if function_call.receiver_id.parse::<AccountId>().is_err() {
+            return Err(KeyRotationError::InvalidReceiverId {
+                receiver_id: function_call.receiver_id.clone(),
+            });
+        }
+        if function_call.method_names.iter().any(|method_name| method_name.is_empty()) {
+            return Err(KeyRotationError::EmptyMethodName);
+        }
+    }
+    let access_key = AccessKey { nonce: rotated_key_nonce(nonce_hint), permission };
+    Ok(vec![
+        AddKeyAction { public_key: new_public_key.clone(), access_key }.into(),
+        DeleteKeyAction { public_key: old_public_key.clone() }.into(),
+    ])
+}
+
+/// Nonce the runtime assigns to a key added at `block_height`.
+fn rotated_key_nonce(block_height: BlockHeight) -> Nonce {
+    block_height.saturating_sub(1).saturating_mul(AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::FunctionCallPermission;
+    use near_crypto::KeyType;
+
+    const FULL_ACCESS: AccessKeyPermission = AccessKeyPermission::FullAccess;
+
+    fn keys() -> (PublicKey, PublicKey) {
+        (
+            PublicKey::from_seed(KeyType::ED25519, "old"),
+            PublicKey::from_seed(KeyType::ED25519, "new"),
+        )
+    }
+
+    fn function_call_permission(receiver_id: &str, method_names: &[&str]) -> AccessKeyPermission {
+        AccessKeyPermission::FunctionCall(FunctionCallPermission {
+            allowance: Some(100),
+            receiver_id: receiver_id.to_string(),
+            method_names: method_names.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn test_full_access_rotation() {
+        let (old_key, new_key) = keys();
+        let actions =
+            build_rotation_actions(&old_key, &new_key, FULL_ACCESS, &FULL_ACCESS, 11).unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                Action::AddKey(AddKeyAction {
+                    public_key: new_key,
+                    access_key: AccessKey {
+                        nonce: 10 * AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER,
+                        permission: AccessKeyPermission::FullAccess,
+                    },
+                }),
+                Action::DeleteKey(DeleteKeyAction { public_key: old_key }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_function_call_rotation() {
+        let (old_key, new_key) = keys();
+        for method_names in [&[][..], &["foo", "bar"][..]] {
+            let permission = function_call_permission("contract.near", method_names);
+            let actions =
+                build_rotation_actions(&old_key, &new_key, permission.clone(), &FULL_ACCESS, 0)
+                    .unwrap();
+            match &actions[..] {
+                [Action::AddKey(add_key), Action::DeleteKey(delete_key)] => {
+                    assert_eq!(add_key.public_key, new_key);
+                    assert_eq!(add_key.access_key.nonce, 0);
+                    assert_eq!(add_key.access_key.permission, permission);
+                    assert_eq!(delete_key.public_key, old_key);
+                }
+                _ => panic!("unexpected actions {:?}", actions),
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_rotation() {
+        let (old_key, new_key) = keys();
+        assert_eq!(
+            build_rotation_actions(&old_key, &old_key, FULL_ACCESS, &FULL_ACCESS, 1),
+            Err(KeyRotationError::SameKey(old_key.clone()))
+        );
+        assert_eq!(
+            build_rotation_actions(
+                &old_key,
+                &new_key,
+                function_call_permission("Invalid Receiver", &[]),
+                &FULL_ACCESS,
+                1
+            ),
+            Err(KeyRotationError::InvalidReceiverId {
+                receiver_id: "Invalid Receiver".to_string()
+            })
+        );
+        assert_eq!(
+            build_rotation_actions(
+                &old_key,
+                &new_key,
+                function_call_permission("contract.near", &["foo", ""]),
+                &FULL_ACCESS,
+                1
+            ),
+            Err(KeyRotationError::EmptyMethodName)
+        );
+        assert_eq!(
+            build_rotation_actions(
+                &old_key,
+                &new_key,
+                function_call_permission("contract.near", &[]),
+                &function_call_permission("contract.near", &[]),
+                1
+            ),
+            Err(KeyRotationError::SignerNotFullAccess)
+        );
+    }
+}
@@ -4,11 +4,16 @@ pub use near_primitives_core::num_rational;
 pub use near_primitives_core::account;
 pub mod block;
 pub mod block_header;
+pub mod borsh_schema;
+pub mod canonical_json;
 pub mod challenge;
 pub use near_primitives_core::config;
 pub use near_primitives_core::contract;
+pub mod epoch_id;
 pub mod epoch_manager;
 pub mod errors;
+pub mod gas_price;
+pub mod key_rotation;
 pub use near_primitives_core::hash;
 pub use near_primitives_core::logging;
 pub mod merkle;
@@ -17,6 +22,7 @@ pub use near_primitives_core::profile;
 pub mod rand;
 pub mod receipt;
 pub mod runtime;
+pub mod safe_math;
 pub mod serialize;
 pub mod shard_layout;
 pub mod sharding;
@@ -31,6 +37,7 @@ pub mod trie_key;
 pub mod types;
 mod upgrade_schedule;
 pub mod utils;
+pub mod validation;
 pub mod validator_signer;
 pub mod version;
 pub mod views;
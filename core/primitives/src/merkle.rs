@@ -175,6 +175,65 @@ impl PartialMerkleTree {
     }
 }
 
+/// Proof that a block is in the block merkle tree with the given root, i.e.
+/// that it's an ancestor of the block whose header has `root` as its
+/// `block_merkle_root`.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct BlockMerkleProof {
+    pub path: MerklePath,
+    pub root: MerkleHash,
+}
+
+impl BlockMerkleProof {
+    pub fn verify(&self, block_hash: &CryptoHash) -> bool {
+        verify_hash(self.root, &self.path, *block_hash)
+    }
+
+    /// Builds the proof for the block with ordinal `index` from the hashes of
+    /// all blocks in the tree, ordered by block ordinal, e.g. as read from an
+    /// archival database.  Returns `None` if `index` is out of range.
+    ///
+    /// Leaves are block hashes, and the left subtree of a tree with `n` leaves
+    /// has `n.next_power_of_two() / 2` of them, as in `PartialMerkleTree`.
+    pub fn from_block_hashes(
+        block_hashes: impl IntoIterator<Item = CryptoHash>,
+        index: u64,
+    ) -> Option<Self> {
+        let hashes: Vec<CryptoHash> = block_hashes.into_iter().collect();
+        let index = usize::try_from(index).ok().filter(|&index| index < hashes.len())?;
+        let mut path = vec![];
+        let root = Self::build(&hashes, index, &mut path);
+        path.reverse();
+        Some(BlockMerkleProof { path, root })
+    }
+
+    /// Returns the root of `hashes` and appends the path to `index` in it,
+    /// root first.
+    fn build(hashes: &[CryptoHash], index: usize, path: &mut MerklePath) -> MerkleHash {
+        if hashes.len() == 1 {
+            return hashes[0];
+        }
+        let (left, right) = hashes.split_at(hashes.len().next_power_of_two() / 2);
+        if index < left.len() {
+            let right_root = Self::root(right);
+            path.push(MerklePathItem { hash: right_root, direction: Direction::Right });
+            combine_hash(&Self::build(left, index, path), &right_root)
+        } else {
+            let left_root = Self::root(left);
+            path.push(MerklePathItem { hash: left_root, direction: Direction::Left });
+            combine_hash(&left_root, &Self::build(right, index - left.len(), path))
+        }
+    }
+
+    fn root(hashes: &[CryptoHash]) -> MerkleHash {
+        if hashes.len() == 1 {
+            return hashes[0];
+        }
+        let (left, right) = hashes.split_at(hashes.len().next_power_of_two() / 2);
+        combine_hash(&Self::root(left), &Self::root(right))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rand::rngs::StdRng;
@@ -248,6 +307,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_block_merkle_proof() {
+        let mut rng: StdRng = SeedableRng::seed_from_u64(2);
+        let mut sizes: Vec<u64> = vec![1, 2, 3, 4, 5, 7, 8, 9, 16, 17, 31, 32, 33, 64];
+        sizes.extend((0..20).map(|_| rng.gen_range(1, 200)));
+        for size in sizes {
+            let hashes: Vec<CryptoHash> = (0..size).map(|i| hash(&i.to_le_bytes())).collect();
+            let mut tree = PartialMerkleTree::default();
+            hashes.iter().for_each(|hash| tree.insert(*hash));
+            for index in 0..size {
+                let proof =
+                    BlockMerkleProof::from_block_hashes(hashes.iter().cloned(), index).unwrap();
+                assert_eq!(proof.root, tree.root(), "size {} index {}", size, index);
+                assert!(proof.path.len() <= 64 - (size - 1).leading_zeros() as usize);
+                assert!(proof.verify(&hashes[index as usize]));
+                let other = hashes[((index + 1) % size) as usize];
+                assert_eq!(proof.verify(&other), size == 1);
+            }
+            assert_eq!(BlockMerkleProof::from_block_hashes(hashes.iter().cloned(), size), None);
+        }
+        assert_eq!(BlockMerkleProof::from_block_hashes(vec![], 0), None);
+    }
+
+    #[test]
+    fn test_block_merkle_proof_single_leaf() {
+        let proof = BlockMerkleProof::from_block_hashes(vec![hash(b"genesis")], 0).unwrap();
+        assert_eq!(proof, BlockMerkleProof { path: vec![], root: hash(b"genesis") });
+    }
+
     #[test]
     fn test_combine_hash_stability() {
         let a = MerkleHash::default();
@@ -1,22 +1,37 @@
 use std::borrow::Borrow;
 use std::fmt;
+use std::io::{self, Write};
+use std::mem::size_of;
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::schema::{Declaration, Definition, Fields};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 use near_crypto::{KeyType, PublicKey};
 
 use crate::borsh::maybestd::collections::HashMap;
+use crate::borsh_schema::add_enum_definition;
+use crate::checked_feature;
 use crate::hash::CryptoHash;
 use crate::logging;
-use crate::serialize::{option_base64_format, u128_dec_format_compatible};
-use crate::transaction::{Action, TransferAction};
-use crate::types::{AccountId, Balance, ShardId};
+use crate::serialize::{option_base64_format, u128_dec_format_compatible, u64_dec_format};
+use crate::transaction::{vec_size, Action, TransferAction};
+use crate::types::{AccountId, Balance, Gas, ProtocolVersion, ShardId};
 
 /// Receipts are used for a cross-shard communication.
 /// Receipts could be 2 types (determined by a `ReceiptEnum`): `ReceiptEnum::Action` of `ReceiptEnum::Data`.
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+)]
 pub struct Receipt {
     /// An issuer account_id of a particular receipt.
     /// `predecessor_id` could be either `Transaction` `signer_id` or intermediate contract's `account_id`.
@@ -41,6 +56,44 @@ impl Receipt {
         self.receipt_id
     }
 
+    /// Number of bytes the receipt occupies when borsh-serialized, computed
+    /// without serializing it.  The result is exact: the fixed overhead is
+    /// the receipt id, the enum discriminant and the length prefixes of the
+    /// variable-sized fields, everything else is summed field by field.
+    pub fn size_bytes(&self) -> u64 {
+        let receipt_size = match &self.receipt {
+            ReceiptEnum::Action(action_receipt) => {
+                vec_size(action_receipt.signer_id.len())
+                    + action_receipt.signer_public_key.len() as u64
+                    + size_of::<Balance>() as u64
+                    + size_of::<u32>() as u64
+                    + action_receipt
+                        .output_data_receivers
+                        .iter()
+                        .map(|receiver| {
+                            size_of::<CryptoHash>() as u64 + vec_size(receiver.receiver_id.len())
+                        })
+                        .sum::<u64>()
+                    + vec_size(action_receipt.input_data_ids.len() * size_of::<CryptoHash>())
+                    + size_of::<u32>() as u64
+                    + action_receipt.actions.iter().map(Action::size_bytes).sum::<u64>()
+                    + action_receipt.congestion.map_or(0, |_| {
+                        size_of::<u8>() as u64 + size_of::<Gas>() as u64 + size_of::<u8>() as u64
+                    })
+            }
+            ReceiptEnum::Data(data_receipt) => {
+                size_of::<CryptoHash>() as u64
+                    + 1
+                    + data_receipt.data.as_ref().map_or(0, |data| vec_size(data.len()))
+            }
+        };
+        vec_size(self.predecessor_id.len())
+            + vec_size(self.receiver_id.len())
+            + size_of::<CryptoHash>() as u64
+            + 1
+            + receipt_size
+    }
+
     /// Generates a receipt with a transfer from system for a given balance without a receipt_id.
     /// This should be used for token refunds instead of gas refunds. It doesn't refund the
     /// allowance of the access key. For gas refunds use `new_gas_refund`.
@@ -57,6 +110,7 @@ impl Receipt {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions: vec![Action::Transfer(TransferAction { deposit: refund })],
+                congestion: None,
             }),
         }
     }
@@ -84,22 +138,71 @@ impl Receipt {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions: vec![Action::Transfer(TransferAction { deposit: refund })],
+                congestion: None,
             }),
         }
     }
 }
 
+/// Number of actions `ActionReceipt`'s `Display` spells out before
+/// eliding the rest.
+const DISPLAYED_ACTIONS: usize = 3;
+
+/// Short single-line summary meant for logs, e.g.
+/// `Bg7VUMhH… alice.near→bob.near: 1 action [Transfer(deposit=1NEAR)]`.
+impl fmt::Display for Receipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}→{}: ",
+            logging::pretty_short_hash(&self.receipt_id),
+            self.predecessor_id,
+            self.receiver_id
+        )?;
+        match &self.receipt {
+            ReceiptEnum::Action(action_receipt) => write!(f, "{}", action_receipt),
+            ReceiptEnum::Data(data_receipt) => write!(
+                f,
+                "Data(id={}, data={})",
+                logging::pretty_short_hash(&data_receipt.data_id),
+                logging::pretty_result(&data_receipt.data)
+            ),
+        }
+    }
+}
+
 /// Receipt could be either ActionReceipt or DataReceipt
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+)]
 pub enum ReceiptEnum {
     Action(ActionReceipt),
     Data(DataReceipt),
 }
 
 /// ActionReceipt is derived from an Action from `Transaction or from Receipt`
+///
+/// Action receipts are serialized in one of two layouts:
+///
+/// * V0, used when there is no `congestion`: the fields in declaration order.
+/// * V1, used otherwise: `ACTION_RECEIPT_V1_TAG`, the V0 fields and the
+///   congestion metadata.
+///
+/// As with `Transaction`, V0 starts with the little-endian `u32` length of
+/// `signer_id`, which is never the tag, and nodes which don't know V1 reject
+/// it as malformed.  `congestion` is only set once `ReceiptCongestionMetadata`
+/// is enabled, so V1 doesn't appear on chain before that.
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ActionReceipt {
     /// A signer of the original transaction
     pub signer_id: AccountId,
@@ -118,12 +221,185 @@ pub struct ActionReceipt {
     pub input_data_ids: Vec<CryptoHash>,
     /// A list of actions to process when all input_data_ids are filled
     pub actions: Vec<Action>,
+    /// Set on receipts emitted by contracts once `ReceiptCongestionMetadata`
+    /// is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub congestion: Option<ReceiptCongestion>,
+}
+
+const ACTION_RECEIPT_V1_TAG: u8 = 1;
+
+impl BorshSerialize for ActionReceipt {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.congestion.is_some() {
+            BorshSerialize::serialize(&ACTION_RECEIPT_V1_TAG, writer)?;
+        }
+        BorshSerialize::serialize(&self.signer_id, writer)?;
+        BorshSerialize::serialize(&self.signer_public_key, writer)?;
+        BorshSerialize::serialize(&self.gas_price, writer)?;
+        BorshSerialize::serialize(&self.output_data_receivers, writer)?;
+        BorshSerialize::serialize(&self.input_data_ids, writer)?;
+        BorshSerialize::serialize(&self.actions, writer)?;
+        if let Some(congestion) = &self.congestion {
+            BorshSerialize::serialize(congestion, writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for ActionReceipt {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let is_v1 = buf.first() == Some(&ACTION_RECEIPT_V1_TAG);
+        if is_v1 {
+            *buf = &buf[1..];
+        }
+        Ok(Self {
+            signer_id: BorshDeserialize::deserialize(buf)?,
+            signer_public_key: BorshDeserialize::deserialize(buf)?,
+            gas_price: BorshDeserialize::deserialize(buf)?,
+            output_data_receivers: BorshDeserialize::deserialize(buf)?,
+            input_data_ids: BorshDeserialize::deserialize(buf)?,
+            actions: BorshDeserialize::deserialize(buf)?,
+            congestion: if is_v1 { Some(BorshDeserialize::deserialize(buf)?) } else { None },
+        })
+    }
+}
+
+/// Describes the layouts as an enum of `ActionReceiptV0` and `ActionReceiptV1`,
+/// like the one of `Transaction`.
+impl BorshSchema for ActionReceipt {
+    fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+        AccountId::add_definitions_recursively(definitions);
+        PublicKey::add_definitions_recursively(definitions);
+        Vec::<DataReceiver>::add_definitions_recursively(definitions);
+        Vec::<CryptoHash>::add_definitions_recursively(definitions);
+        Vec::<Action>::add_definitions_recursively(definitions);
+        ReceiptCongestion::add_definitions_recursively(definitions);
+        let v0_fields = vec![
+            ("signer_id".to_string(), AccountId::declaration()),
+            ("signer_public_key".to_string(), PublicKey::declaration()),
+            ("gas_price".to_string(), Balance::declaration()),
+            ("output_data_receivers".to_string(), Vec::<DataReceiver>::declaration()),
+            ("input_data_ids".to_string(), Vec::<CryptoHash>::declaration()),
+            ("actions".to_string(), Vec::<Action>::declaration()),
+        ];
+        let mut v1_fields = v0_fields.clone();
+        v1_fields.push(("congestion".to_string(), ReceiptCongestion::declaration()));
+        for (declaration, fields) in
+            [("ActionReceiptV0", v0_fields), ("ActionReceiptV1", v1_fields)]
+        {
+            Self::add_definition(
+                declaration.to_string(),
+                Definition::Struct { fields: Fields::NamedFields(fields) },
+                definitions,
+            );
+        }
+        add_enum_definition::<Self>(
+            vec![
+                ("V0", vec!["ActionReceiptV0".to_string()]),
+                ("V1", vec!["ActionReceiptV1".to_string()]),
+            ],
+            definitions,
+        );
+    }
+
+    fn declaration() -> Declaration {
+        "ActionReceipt".to_string()
+    }
+}
+
+/// Lets the receiving shard make admission decisions when it's congested.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+)]
+pub struct ReceiptCongestion {
+    /// Gas burnt by the receipts this one descends from, not counting the
+    /// transaction's conversion to a receipt.
+    #[serde(with = "u64_dec_format")]
+    pub upstream_gas_burnt: Gas,
+    /// Receipts with higher priority are admitted first.  Inherited from the
+    /// parent receipt.
+    pub priority: u8,
+}
+
+impl ReceiptCongestion {
+    /// Congestion metadata of a receipt emitted by a receipt with `parent`
+    /// metadata, after `gas_burnt` was burnt executing the latter.  `None`
+    /// before `ReceiptCongestionMetadata`.
+    pub fn child(
+        parent: Option<ReceiptCongestion>,
+        gas_burnt: Gas,
+        protocol_version: ProtocolVersion,
+    ) -> Option<ReceiptCongestion> {
+        if !checked_feature!(
+            "protocol_feature_receipt_congestion_metadata",
+            ReceiptCongestionMetadata,
+            protocol_version
+        ) {
+            return None;
+        }
+        let parent = parent.unwrap_or_default();
+        Some(ReceiptCongestion {
+            upstream_gas_burnt: parent.upstream_gas_burnt.saturating_add(gas_burnt),
+            priority: parent.priority,
+        })
+    }
+}
+
+/// Number of actions followed by the first few of them and the congestion
+/// metadata if any, e.g. `2 actions [CreateAccount, Transfer(deposit=1NEAR)]`
+/// or `1 action [CreateAccount] upstream_gas_burnt=5Tgas priority=0`.
+impl fmt::Display for ActionReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.actions.len();
+        write!(f, "{} action{} [", count, if count == 1 { "" } else { "s" })?;
+        for (index, action) in self.actions.iter().take(DISPLAYED_ACTIONS).enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", action)?;
+        }
+        if count > DISPLAYED_ACTIONS {
+            f.write_str(", …")?;
+        }
+        f.write_str("]")?;
+        if let Some(congestion) = &self.congestion {
+            write!(
+                f,
+                " upstream_gas_burnt={} priority={}",
+                logging::pretty_gas(congestion.upstream_gas_burnt),
+                congestion.priority
+            )?;
+        }
+        Ok(())
+    }
 }
 
 /// An incoming (ingress) `DataReceipt` which is going to a Receipt's `receiver` input_data_ids
 /// Which will be converted to `PromiseResult::Successful(value)` or `PromiseResult::Failed`
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Hash, PartialEq, Eq, Clone)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    Hash,
+    PartialEq,
+    Eq,
+    Clone,
+)]
 pub struct DataReceipt {
     pub data_id: CryptoHash,
     #[serde(with = "option_base64_format")]
@@ -134,7 +410,16 @@ pub struct DataReceipt {
 /// to a `DataReceipt` to be sent to a `receipt.receiver`
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(
-    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Hash, Clone, Debug, PartialEq, Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    Hash,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
 )]
 pub struct DataReceiver {
     pub data_id: CryptoHash,
@@ -178,3 +463,271 @@ pub struct DelayedReceiptIndices {
 
 /// Map of shard to list of receipts to send to it.
 pub type ReceiptResult = HashMap<ShardId, Vec<Receipt>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{AccessKey, AccessKeyPermission, FunctionCallPermission};
+    use crate::transaction::{
+        AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
+        DeployContractAction, FunctionCallAction, StakeAction,
+    };
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_bytes(rng: &mut StdRng, max_len: usize) -> Vec<u8> {
+        let len = rng.gen_range(0, max_len);
+        (0..len).map(|_| rng.gen()).collect()
+    }
+
+    fn random_string(rng: &mut StdRng, max_len: usize) -> String {
+        let len = rng.gen_range(0, max_len);
+        (0..len).map(|_| rng.gen_range(b'a', b'z' + 1) as char).collect()
+    }
+
+    fn random_account_id(rng: &mut StdRng) -> AccountId {
+        format!("a{}.near", random_string(rng, 40)).parse().unwrap()
+    }
+
+    fn random_public_key(rng: &mut StdRng) -> PublicKey {
+        let key_type = if rng.gen() { KeyType::ED25519 } else { KeyType::SECP256K1 };
+        PublicKey::from_seed(key_type, &random_string(rng, 10))
+    }
+
+    fn random_action(rng: &mut StdRng, kind: usize) -> Action {
+        match kind {
+            0 => CreateAccountAction {}.into(),
+            1 => DeployContractAction { code: random_bytes(rng, 1000) }.into(),
+            2 => FunctionCallAction {
+                method_name: random_string(rng, 50),
+                args: random_bytes(rng, 1000),
+                gas: rng.gen(),
+                deposit: rng.gen(),
+            }
+            .into(),
+            3 => TransferAction { deposit: rng.gen() }.into(),
+            4 => StakeAction { stake: rng.gen(), public_key: random_public_key(rng) }.into(),
+            5 => {
+                let permission = if rng.gen() {
+                    AccessKeyPermission::FullAccess
+                } else {
+                    AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                        allowance: if rng.gen() { Some(rng.gen()) } else { None },
+                        receiver_id: random_string(rng, 60),
+                        method_names: (0..rng.gen_range(0, 5))
+                            .map(|_| random_string(rng, 20))
+                            .collect(),
+                    })
+                };
+                AddKeyAction {
+                    public_key: random_public_key(rng),
+                    access_key: AccessKey { nonce: rng.gen(), permission },
+                }
+                .into()
+            }
+            6 => DeleteKeyAction { public_key: random_public_key(rng) }.into(),
+            7 => DeleteAccountAction { beneficiary_id: random_account_id(rng) }.into(),
+            #[cfg(feature = "protocol_feature_chunk_only_producers")]
+            8 => Action::StakeChunkOnly(StakeAction {
+                stake: rng.gen(),
+                public_key: random_public_key(rng),
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    const ACTION_KINDS: usize =
+        if cfg!(feature = "protocol_feature_chunk_only_producers") { 9 } else { 8 };
+
+    fn random_receipt(rng: &mut StdRng) -> Receipt {
+        let receipt = if rng.gen_range(0, 4) == 0 {
+            ReceiptEnum::Data(DataReceipt {
+                data_id: CryptoHash::hash_bytes(&random_bytes(rng, 10)),
+                data: if rng.gen() { Some(random_bytes(rng, 1000)) } else { None },
+            })
+        } else {
+            ReceiptEnum::Action(ActionReceipt {
+                signer_id: random_account_id(rng),
+                signer_public_key: random_public_key(rng),
+                gas_price: rng.gen(),
+                output_data_receivers: (0..rng.gen_range(0, 3))
+                    .map(|_| DataReceiver {
+                        data_id: CryptoHash::hash_bytes(&random_bytes(rng, 10)),
+                        receiver_id: random_account_id(rng),
+                    })
+                    .collect(),
+                input_data_ids: (0..rng.gen_range(0, 3))
+                    .map(|_| CryptoHash::hash_bytes(&random_bytes(rng, 10)))
+                    .collect(),
+                actions: (0..rng.gen_range(0, 4))
+                    .map(|_| {
+                        let kind = rng.gen_range(0, ACTION_KINDS);
+                        random_action(rng, kind)
+                    })
+                    .collect(),
+                congestion: if rng.gen() {
+                    Some(ReceiptCongestion { upstream_gas_burnt: rng.gen(), priority: rng.gen() })
+                } else {
+                    None
+                },
+            })
+        };
+        Receipt {
+            predecessor_id: random_account_id(rng),
+            receiver_id: random_account_id(rng),
+            receipt_id: CryptoHash::hash_bytes(&random_bytes(rng, 10)),
+            receipt,
+        }
+    }
+
+    #[test]
+    fn test_action_size_bytes() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for kind in 0..ACTION_KINDS {
+            for _ in 0..50 {
+                let action = random_action(&mut rng, kind);
+                assert_eq!(action.size_bytes(), action.try_to_vec().unwrap().len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_receipt_size_bytes() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..500 {
+            let receipt = random_receipt(&mut rng);
+            assert_eq!(
+                receipt.size_bytes(),
+                receipt.try_to_vec().unwrap().len() as u64,
+                "{:?}",
+                receipt
+            );
+        }
+    }
+
+    fn action_receipt(actions: Vec<Action>) -> Receipt {
+        Receipt {
+            predecessor_id: "alice.near".parse().unwrap(),
+            receiver_id: "bob.near".parse().unwrap(),
+            receipt_id: CryptoHash::default(),
+            receipt: ReceiptEnum::Action(ActionReceipt {
+                signer_id: "alice.near".parse().unwrap(),
+                signer_public_key: PublicKey::empty(KeyType::ED25519),
+                gas_price: 100_000_000,
+                output_data_receivers: vec![],
+                input_data_ids: vec![],
+                actions,
+                congestion: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_receipt_display() {
+        let transfer = Action::Transfer(TransferAction { deposit: 10u128.pow(24) });
+        assert_eq!(
+            action_receipt(vec![transfer.clone()]).to_string(),
+            "11111111… alice.near→bob.near: 1 action [Transfer(deposit=1NEAR)]"
+        );
+        assert_eq!(
+            action_receipt(vec![]).to_string(),
+            "11111111… alice.near→bob.near: 0 actions []"
+        );
+        assert_eq!(
+            action_receipt(vec![
+                Action::CreateAccount(CreateAccountAction {}),
+                transfer.clone(),
+                Action::DeployContract(DeployContractAction { code: vec![0; 300] }),
+                transfer,
+            ])
+            .to_string(),
+            "11111111… alice.near→bob.near: 4 actions [CreateAccount, Transfer(deposit=1NEAR), DeployContract(code=300B), …]"
+        );
+
+        let mut receipt = action_receipt(vec![Action::CreateAccount(CreateAccountAction {})]);
+        if let ReceiptEnum::Action(action_receipt) = &mut receipt.receipt {
+            action_receipt.congestion =
+                Some(ReceiptCongestion { upstream_gas_burnt: 5 * 10u64.pow(12), priority: 3 });
+        }
+        assert_eq!(
+            receipt.to_string(),
+            "11111111… alice.near→bob.near: 1 action [CreateAccount] upstream_gas_burnt=5Tgas priority=3"
+        );
+
+        let data_receipt = |data| Receipt {
+            predecessor_id: "alice.near".parse().unwrap(),
+            receiver_id: "bob.near".parse().unwrap(),
+            receipt_id: CryptoHash::default(),
+            receipt: ReceiptEnum::Data(DataReceipt { data_id: CryptoHash::default(), data }),
+        };
+        assert_eq!(
+            data_receipt(Some(b"ok".to_vec())).to_string(),
+            "11111111… alice.near→bob.near: Data(id=11111111…, data=`ok`)"
+        );
+        assert_eq!(
+            data_receipt(None).to_string(),
+            "11111111… alice.near→bob.near: Data(id=11111111…, data=None)"
+        );
+    }
+
+    /// `ActionReceipt` as serialized by nodes which predate `ReceiptCongestion`.
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+    struct OldActionReceipt {
+        signer_id: AccountId,
+        signer_public_key: PublicKey,
+        gas_price: Balance,
+        output_data_receivers: Vec<DataReceiver>,
+        input_data_ids: Vec<CryptoHash>,
+        actions: Vec<Action>,
+    }
+
+    #[test]
+    fn test_receipt_congestion_cross_version_serialization() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let receipt = random_receipt(&mut rng);
+            let bytes = receipt.try_to_vec().unwrap();
+            assert_eq!(Receipt::try_from_slice(&bytes).unwrap(), receipt);
+            let action_receipt = match receipt.receipt {
+                ReceiptEnum::Action(action_receipt) => action_receipt,
+                ReceiptEnum::Data(_) => continue,
+            };
+            let bytes = action_receipt.try_to_vec().unwrap();
+            assert_eq!(bytes[0] == ACTION_RECEIPT_V1_TAG, action_receipt.congestion.is_some());
+            let old_receipt = OldActionReceipt::try_from_slice(&bytes);
+            if action_receipt.congestion.is_some() {
+                assert!(old_receipt.is_err());
+                continue;
+            }
+            let old_receipt = OldActionReceipt {
+                signer_id: action_receipt.signer_id,
+                signer_public_key: action_receipt.signer_public_key,
+                gas_price: action_receipt.gas_price,
+                output_data_receivers: action_receipt.output_data_receivers,
+                input_data_ids: action_receipt.input_data_ids,
+                actions: action_receipt.actions,
+            };
+            assert_eq!(old_receipt.try_to_vec().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_child_congestion() {
+        assert_eq!(ReceiptCongestion::child(None, 10, 0), None);
+        #[cfg(feature = "protocol_feature_receipt_congestion_metadata")]
+        {
+            let version =
+                crate::version::ProtocolFeature::ReceiptCongestionMetadata.protocol_version();
+            assert_eq!(ReceiptCongestion::child(None, 10, version - 1), None);
+            assert_eq!(
+                ReceiptCongestion::child(None, 10, version),
+                Some(ReceiptCongestion { upstream_gas_burnt: 10, priority: 0 })
+            );
+            let parent = Some(ReceiptCongestion { upstream_gas_burnt: 5, priority: 2 });
+            assert_eq!(
+                ReceiptCongestion::child(parent, 10, version),
+                Some(ReceiptCongestion { upstream_gas_burnt: 15, priority: 2 })
+            );
+        }
+    }
+}
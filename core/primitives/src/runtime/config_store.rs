@@ -1,3 +1,4 @@
+use crate::config::VMConfig;
 use crate::runtime::config::RuntimeConfig;
 use crate::runtime::parameter_table::{ParameterTable, ParameterTableDiff};
 use crate::types::ProtocolVersion;
@@ -75,6 +76,22 @@ impl RuntimeConfigStore {
         Self { store }
     }
 
+    /// Constructs the store for the given chain id.
+    ///
+    /// For mainnet and other chains except testnet we don't need to override runtime config for
+    /// first protocol versions.
+    /// For testnet, runtime config for genesis block was (incorrectly) different, that's why we
+    /// need to override it specifically to preserve compatibility.
+    pub fn for_chain_id(chain_id: &str) -> Self {
+        match chain_id {
+            "testnet" => {
+                let genesis_runtime_config = RuntimeConfig::initial_testnet_config();
+                Self::new(Some(&genesis_runtime_config))
+            }
+            _ => Self::new(None),
+        }
+    }
+
     /// Constructs test store.
     pub fn with_one_config(runtime_config: RuntimeConfig) -> Self {
         Self { store: BTreeMap::from_iter([(0, Arc::new(runtime_config))].iter().cloned()) }
@@ -102,6 +119,20 @@ impl RuntimeConfigStore {
     }
 }
 
+/// `VMConfig` presets which need the parameter files of this crate.
+pub trait VMConfigExt {
+    /// VM config used by the chain at the given protocol version.
+    ///
+    /// Parses all the parameter files, so it's meant for tests and tools.
+    fn production(protocol_version: ProtocolVersion) -> VMConfig;
+}
+
+impl VMConfigExt for VMConfig {
+    fn production(protocol_version: ProtocolVersion) -> VMConfig {
+        RuntimeConfigStore::new(None).get_config(protocol_version).wasm_config.clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,6 +158,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_production_vm_config_json_round_trip() {
+        let store = RuntimeConfigStore::new(None);
+        for (&protocol_version, config) in store.store.iter() {
+            let vm_config = VMConfig::production(protocol_version);
+            assert_eq!(vm_config, config.wasm_config);
+            let json = vm_config.to_json_pretty();
+            assert_eq!(VMConfig::from_json_strict(&json).unwrap(), vm_config);
+        }
+    }
+
     #[test]
     fn test_lower_storage_cost() {
         let store = RuntimeConfigStore::new(None);
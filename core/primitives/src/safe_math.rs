@@ -0,0 +1,199 @@
+//! Overflow-checked arithmetic on balances and gas.
+use std::fmt;
+
+use crate::errors::IntegerOverflowError;
+use crate::types::{Balance, Gas};
+
+pub fn safe_add_balance(a: Balance, b: Balance) -> Result<Balance, IntegerOverflowError> {
+    a.checked_add(b).ok_or(IntegerOverflowError {})
+}
+
+pub fn safe_sub_balance(a: Balance, b: Balance) -> Result<Balance, IntegerOverflowError> {
+    a.checked_sub(b).ok_or(IntegerOverflowError {})
+}
+
+pub fn safe_add_gas(a: Gas, b: Gas) -> Result<Gas, IntegerOverflowError> {
+    a.checked_add(b).ok_or(IntegerOverflowError {})
+}
+
+pub fn safe_gas_to_balance(gas_price: Balance, gas: Gas) -> Result<Balance, IntegerOverflowError> {
+    gas_price.checked_mul(Balance::from(gas)).ok_or(IntegerOverflowError {})
+}
+
+/// Overflow while summing balances with `safe_sum_balances!`, naming the
+/// operand which didn't fit.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("balance overflow adding `{operand}` = {value} to {sum}")]
+pub struct BalanceOverflowError {
+    /// Source expression of the operand.
+    pub operand: &'static str,
+    pub value: Balance,
+    /// Sum of the operands before it.
+    pub sum: Balance,
+}
+
+impl From<BalanceOverflowError> for IntegerOverflowError {
+    fn from(_: BalanceOverflowError) -> Self {
+        IntegerOverflowError {}
+    }
+}
+
+/// Sums `(name, balance)` pairs, see `safe_sum_balances!`.
+pub fn safe_sum_named_balances(
+    operands: &[(&'static str, Balance)],
+) -> Result<Balance, BalanceOverflowError> {
+    operands.iter().try_fold(0, |sum: Balance, &(operand, value)| {
+        sum.checked_add(value).ok_or(BalanceOverflowError { operand, value, sum })
+    })
+}
+
+/// Sums balances, returning `Result<Balance, BalanceOverflowError>`.  On
+/// overflow the error names the operand's expression, e.g.
+/// `safe_sum_balances!(stats.tx_burnt_amount, final_accounts_balance)`.
+#[macro_export]
+macro_rules! safe_sum_balances {
+    ($($operand:expr),+ $(,)?) => {
+        $crate::safe_math::safe_sum_named_balances(&[$((stringify!($operand), $operand)),+])
+    };
+}
+
+/// Signed balance difference.  Balances can be added and subtracted in any
+/// order; only a difference larger than `Balance::MAX` in absolute value
+/// overflows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BalanceDelta {
+    negative: bool,
+    magnitude: Balance,
+}
+
+impl BalanceDelta {
+    pub fn add(&mut self, amount: Balance) -> Result<(), IntegerOverflowError> {
+        self.accumulate(false, amount)
+    }
+
+    pub fn sub(&mut self, amount: Balance) -> Result<(), IntegerOverflowError> {
+        self.accumulate(true, amount)
+    }
+
+    /// Difference `a - b`.
+    pub fn between(a: Balance, b: Balance) -> Self {
+        if a >= b {
+            BalanceDelta { negative: false, magnitude: a - b }
+        } else {
+            BalanceDelta { negative: true, magnitude: b - a }
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative && self.magnitude > 0
+    }
+
+    /// Absolute value of the difference.
+    pub fn magnitude(&self) -> Balance {
+        self.magnitude
+    }
+
+    fn accumulate(&mut self, negative: bool, amount: Balance) -> Result<(), IntegerOverflowError> {
+        if self.negative == negative {
+            self.magnitude = safe_add_balance(self.magnitude, amount)?;
+        } else if amount > self.magnitude {
+            *self = BalanceDelta { negative, magnitude: amount - self.magnitude };
+        } else {
+            self.magnitude -= amount;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for BalanceDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_negative() {
+            write!(f, "-{}", self.magnitude)
+        } else {
+            write!(f, "+{}", self.magnitude)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitive_types::U256;
+
+    const EXTREMES: [Balance; 7] =
+        [0, 1, 2, Balance::MAX / 2, Balance::MAX / 2 + 1, Balance::MAX - 1, Balance::MAX];
+
+    #[test]
+    fn test_extremes_never_panic() {
+        for &a in &EXTREMES {
+            for &b in &EXTREMES {
+                assert_eq!(safe_add_balance(a, b).ok(), a.checked_add(b));
+                assert_eq!(safe_sub_balance(a, b).ok(), a.checked_sub(b));
+                let (gas_a, gas_b) = (a as Gas, b as Gas);
+                assert_eq!(safe_add_gas(gas_a, gas_b).ok(), gas_a.checked_add(gas_b));
+                assert_eq!(safe_gas_to_balance(a, gas_b).ok(), a.checked_mul(Balance::from(gas_b)));
+                assert_eq!(
+                    safe_sum_balances!(a, b).ok(),
+                    a.checked_add(b),
+                    "safe_sum_balances!({}, {})",
+                    a,
+                    b
+                );
+                for &c in &EXTREMES {
+                    let mut delta = BalanceDelta::default();
+                    let result = delta.add(a).and_then(|_| delta.sub(b)).and_then(|_| delta.add(c));
+                    let (credit, debit) = (U256::from(a) + U256::from(c), U256::from(b));
+                    let (negative, magnitude) = if credit >= debit {
+                        (false, credit - debit)
+                    } else {
+                        (true, debit - credit)
+                    };
+                    if magnitude > U256::from(Balance::MAX) {
+                        assert!(result.is_err());
+                    } else {
+                        result.unwrap();
+                        assert_eq!(delta.magnitude(), magnitude.as_u128());
+                        assert_eq!(delta.is_negative(), negative);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_safe_sum_balances_names_operand() {
+        let small = 5;
+        let large = Balance::MAX;
+        assert_eq!(safe_sum_balances!(small, 10), Ok(15));
+        let err = safe_sum_balances!(small, large, 1).unwrap_err();
+        assert_eq!(err, BalanceOverflowError { operand: "large", value: Balance::MAX, sum: 5 });
+        assert_eq!(
+            err.to_string(),
+            format!("balance overflow adding `large` = {} to 5", Balance::MAX)
+        );
+        assert_eq!(IntegerOverflowError::from(err), IntegerOverflowError {});
+    }
+
+    #[test]
+    fn test_balance_delta() {
+        let mut delta = BalanceDelta::default();
+        assert!(delta.is_zero());
+        assert_eq!(delta.to_string(), "+0");
+        delta.sub(10).unwrap();
+        assert!(delta.is_negative());
+        assert_eq!(delta.to_string(), "-10");
+        delta.add(Balance::MAX).unwrap();
+        assert_eq!(delta.magnitude(), Balance::MAX - 10);
+        delta.add(10).unwrap();
+        assert_eq!(delta.magnitude(), Balance::MAX);
+        assert!(delta.add(1).is_err());
+        delta.sub(Balance::MAX).unwrap();
+        assert!(delta.is_zero() && !delta.is_negative());
+        assert_eq!(BalanceDelta::between(3, 5).to_string(), "-2");
+        assert_eq!(BalanceDelta::between(Balance::MAX, 0).magnitude(), Balance::MAX);
+    }
+}
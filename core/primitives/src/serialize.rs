@@ -20,6 +20,60 @@ mod tests {
         store_key: StoreKey,
     }
 
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    struct BytesStruct {
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct LimitedBytesStruct {
+        #[serde(deserialize_with = "deserialize_at_most_4_bytes")]
+        data: Vec<u8>,
+    }
+
+    fn deserialize_at_most_4_bytes<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        base64_bytes::deserialize_with_max_len(deserializer, 4)
+    }
+
+    #[test]
+    fn test_base64_bytes() {
+        let s = BytesStruct { data: vec![10, 20, 30] };
+        assert_eq!(serde_json::to_string(&s).unwrap(), r#"{"data":"ChQe"}"#);
+        assert_eq!(serde_json::from_str::<BytesStruct>(r#"{"data":"ChQe"}"#).unwrap(), s);
+        // The old representation is still accepted.
+        assert_eq!(serde_json::from_str::<BytesStruct>(r#"{"data":[10,20,30]}"#).unwrap(), s);
+        assert_eq!(
+            serde_json::from_str::<BytesStruct>(r#"{"data":""}"#).unwrap(),
+            BytesStruct { data: vec![] }
+        );
+
+        for invalid in [r#"{"data":"Ch!e"}"#, r#"{"data":[256]}"#, r#"{"data":10}"#] {
+            assert!(serde_json::from_str::<BytesStruct>(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_base64_bytes_max_len() {
+        fn decode(json: &str) -> serde_json::Result<Vec<u8>> {
+            serde_json::from_str::<LimitedBytesStruct>(json).map(|s| s.data)
+        }
+        assert_eq!(decode(r#"{"data":"AQIDBA=="}"#).unwrap(), [1, 2, 3, 4]);
+        assert_eq!(decode(r#"{"data":[1,2,3,4]}"#).unwrap(), [1, 2, 3, 4]);
+
+        let too_long = "decoded data is longer than 4 bytes";
+        for json in [
+            r#"{"data":"AQIDBAU="}"#.to_string(),
+            r#"{"data":[1,2,3,4,5]}"#.to_string(),
+            format!(r#"{{"data":"{}"}}"#, "A".repeat(1 << 20)),
+        ] {
+            let err = decode(&json).unwrap_err().to_string();
+            assert!(err.starts_with(too_long), "{}", err);
+        }
+    }
+
     #[test]
     fn test_serialize_some() {
         let s = OptionBytesStruct { data: Some(vec![10, 20, 30]) };
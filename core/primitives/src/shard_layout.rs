@@ -96,6 +96,22 @@ pub struct ShardLayoutV1 {
     version: ShardVersion,
 }
 
+/// Explains why an account is assigned to a shard, see `ShardLayout::explain`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShardAssignment {
+    pub shard_id: ShardId,
+    /// Set if the account is a fixed account or one of its sub-accounts; the
+    /// boundaries don't apply then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed_account: Option<AccountId>,
+    /// Largest boundary account <= the account, `None` if the shard is the
+    /// first range.
+    pub lower_bound: Option<AccountId>,
+    /// Smallest boundary account > the account, `None` if the shard is the
+    /// last range.
+    pub upper_bound: Option<AccountId>,
+}
+
 #[derive(Debug)]
 pub enum ShardLayoutError {
     InvalidShardIdError { shard_id: ShardId },
@@ -142,6 +158,15 @@ impl ShardLayout {
         })
     }
 
+    /// Returns a hypothetical V1 ShardLayout splitting accounts by `boundary_accounts`, e.g. to
+    /// see where accounts would land when planning a resharding.  Boundaries are sorted and
+    /// deduplicated; the layout has no fixed shards and no parent layout.
+    pub fn preview(mut boundary_accounts: Vec<AccountId>) -> Self {
+        boundary_accounts.sort();
+        boundary_accounts.dedup();
+        Self::v1(vec![], boundary_accounts, None, 0)
+    }
+
     /// Returns a V1 ShardLayout. It is only used in tests
     pub fn v1_test() -> Self {
         ShardLayout::v1(
@@ -208,6 +233,51 @@ impl ShardLayout {
         }
     }
 
+    /// Boundary accounts dividing accounts into ranges, in order.  Empty for V0, which assigns
+    /// accounts by hash.
+    pub fn boundaries(&self) -> &[AccountId] {
+        match self {
+            Self::V0(_) => &[],
+            Self::V1(v1) => &v1.boundary_accounts,
+        }
+    }
+
+    /// Returns the shard of `account_id`, same as `account_id_to_shard_id`, together with the
+    /// rule which put it there.
+    ///
+    /// For V1, accounts are compared as strings byte by byte, so e.g. `near` < `near.a` <
+    /// `nearx`, and the sub-accounts of an account are not necessarily next to it.  An account
+    /// equal to a boundary belongs to the range starting at that boundary.
+    pub fn explain(&self, account_id: &AccountId) -> ShardAssignment {
+        match self {
+            Self::V0(_) => ShardAssignment {
+                shard_id: account_id_to_shard_id(account_id, self),
+                fixed_account: None,
+                lower_bound: None,
+                upper_bound: None,
+            },
+            Self::V1(ShardLayoutV1 { fixed_shards, boundary_accounts, .. }) => {
+                for (shard_id, fixed_account) in fixed_shards.iter().enumerate() {
+                    if is_top_level_account(fixed_account, account_id) {
+                        return ShardAssignment {
+                            shard_id: shard_id as ShardId,
+                            fixed_account: Some(fixed_account.clone()),
+                            lower_bound: None,
+                            upper_bound: None,
+                        };
+                    }
+                }
+                let index = boundary_accounts.partition_point(|boundary| boundary <= account_id);
+                ShardAssignment {
+                    shard_id: (fixed_shards.len() + index) as ShardId,
+                    fixed_account: None,
+                    lower_bound: index.checked_sub(1).map(|i| boundary_accounts[i].clone()),
+                    upper_bound: boundary_accounts.get(index).cloned(),
+                }
+            }
+        }
+    }
+
     /// Returns shard uids for all shards in the shard layout
     pub fn get_shard_uids(&self) -> Vec<ShardUId> {
         (0..self.num_shards()).map(|x| ShardUId::from_shard_id_and_layout(x, self)).collect()
@@ -332,7 +402,8 @@ pub fn get_block_shard_uid_rev(
 
 #[cfg(test)]
 mod tests {
-    use crate::shard_layout::{account_id_to_shard_id, ShardLayout, ShardUId};
+    use crate::shard_layout::{account_id_to_shard_id, ShardAssignment, ShardLayout, ShardUId};
+    use crate::types::AccountId;
     use rand::distributions::Alphanumeric;
     use rand::rngs::StdRng;
     use rand::{Rng, SeedableRng};
@@ -398,4 +469,75 @@ mod tests {
         assert_eq!(account_id_to_shard_id(&"goo".parse().unwrap(), &shard_layout), 6);
         assert_eq!(account_id_to_shard_id(&"zoo".parse().unwrap(), &shard_layout), 7);
     }
+
+    fn assignment(shard_id: u64, lower: Option<&str>, upper: Option<&str>) -> ShardAssignment {
+        ShardAssignment {
+            shard_id,
+            fixed_account: None,
+            lower_bound: lower.map(|s| s.parse().unwrap()),
+            upper_bound: upper.map(|s| s.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_shard_layout_explain() {
+        let shard_layout = ShardLayout::preview(
+            vec!["near", "aurora", "near"].into_iter().map(|s| s.parse().unwrap()).collect(),
+        );
+        let boundaries: Vec<AccountId> = vec!["aurora".parse().unwrap(), "near".parse().unwrap()];
+        assert_eq!(shard_layout.boundaries(), boundaries.as_slice());
+        assert_eq!(shard_layout.num_shards(), 3);
+
+        let min_implicit = "0".repeat(64);
+        let max_implicit = "f".repeat(64);
+        let cases = [
+            // accounts equal to a boundary start the next range
+            ("aurora", assignment(1, Some("aurora"), Some("near"))),
+            ("near", assignment(2, Some("near"), None)),
+            ("auror", assignment(0, None, Some("aurora"))),
+            // sub-accounts go by their full name, not by their parent
+            ("a.aurora", assignment(0, None, Some("aurora"))),
+            ("x.near", assignment(2, Some("near"), None)),
+            ("aurora.near", assignment(1, Some("aurora"), Some("near"))),
+            ("near.aurora", assignment(2, Some("near"), None)),
+            // implicit accounts sort before and after all named accounts here
+            (min_implicit.as_str(), assignment(0, None, Some("aurora"))),
+            (max_implicit.as_str(), assignment(1, Some("aurora"), Some("near"))),
+            ("zzzz", assignment(2, Some("near"), None)),
+        ];
+        for (account_id, expected) in cases {
+            let account_id: AccountId = account_id.parse().unwrap();
+            let assignment = shard_layout.explain(&account_id);
+            assert_eq!(assignment, expected, "{}", account_id);
+            assert_eq!(assignment.shard_id, account_id_to_shard_id(&account_id, &shard_layout));
+        }
+
+        assert_eq!(
+            serde_json::to_value(shard_layout.explain(&"near".parse().unwrap())).unwrap(),
+            serde_json::json!({"shard_id": 2, "lower_bound": "near", "upper_bound": null})
+        );
+    }
+
+    #[test]
+    fn test_shard_layout_explain_fixed_and_v0() {
+        let shard_layout = ShardLayout::v1_test();
+        let account_id: AccountId = "a.test0".parse().unwrap();
+        assert_eq!(
+            shard_layout.explain(&account_id),
+            ShardAssignment {
+                shard_id: 0,
+                fixed_account: Some("test0".parse().unwrap()),
+                lower_bound: None,
+                upper_bound: None
+            }
+        );
+        let implicit: AccountId = "f".repeat(64).parse().unwrap();
+        assert_eq!(shard_layout.explain(&implicit), assignment(2, Some("abc"), Some("foo")));
+
+        let shard_layout = ShardLayout::v0(4, 0);
+        assert!(shard_layout.boundaries().is_empty());
+        let assignment = shard_layout.explain(&implicit);
+        assert_eq!(assignment.shard_id, account_id_to_shard_id(&implicit, &shard_layout));
+        assert_eq!((assignment.lower_bound, assignment.upper_bound), (None, None));
+    }
 }
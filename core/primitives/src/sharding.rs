@@ -231,6 +231,16 @@ impl ShardChunkHeader {
         }
     }
 
+    /// Recomputes the hash of the header from its inner part, the way the
+    /// header's version does it.
+    pub fn compute_hash(&self) -> ChunkHash {
+        match self {
+            Self::V1(header) => ShardChunkHeaderV1::compute_hash(&header.inner),
+            Self::V2(header) => ShardChunkHeaderV2::compute_hash(&header.inner),
+            Self::V3(header) => ShardChunkHeaderV3::compute_hash(&header.inner),
+        }
+    }
+
     pub fn inner_header_hash(&self) -> CryptoHash {
         let inner_bytes = match self {
             Self::V1(header) => header.inner.try_to_vec(),
@@ -6,7 +6,7 @@ use num_rational::Rational;
 use near_crypto::{EmptySigner, PublicKey, Signature, Signer};
 use near_primitives_core::types::ProtocolVersion;
 
-use crate::account::{AccessKey, AccessKeyPermission, Account};
+use crate::account::{AccessKey, Account};
 use crate::block::Block;
 use crate::block_header::{BlockHeader, BlockHeaderV3};
 use crate::errors::{EpochError, TxExecutionError};
@@ -17,7 +17,7 @@ use crate::sharding::ShardChunkHeader;
 use crate::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
     DeployContractAction, FunctionCallAction, SignedTransaction, StakeAction, Transaction,
-    TransferAction,
+    TransactionBuilder, TransferAction,
 };
 use crate::types::{AccountId, Balance, BlockHeight, EpochId, EpochInfoProvider, Gas, Nonce};
 use crate::validator_signer::ValidatorSigner;
@@ -36,7 +36,15 @@ impl Transaction {
         nonce: Nonce,
         block_hash: CryptoHash,
     ) -> Self {
-        Self { signer_id, public_key, nonce, receiver_id, block_hash, actions: vec![] }
+        Self {
+            signer_id,
+            public_key,
+            nonce,
+            receiver_id,
+            block_hash,
+            actions: vec![],
+            priority_fee: None,
+        }
     }
 
     pub fn sign(self, signer: &dyn Signer) -> SignedTransaction {
@@ -111,6 +119,7 @@ impl SignedTransaction {
             receiver_id,
             block_hash,
             actions,
+            priority_fee: None,
         }
         .sign(signer)
     }
@@ -123,14 +132,12 @@ impl SignedTransaction {
         deposit: Balance,
         block_hash: CryptoHash,
     ) -> Self {
-        Self::from_actions(
-            nonce,
-            signer_id,
-            receiver_id,
-            signer,
-            vec![Action::Transfer(TransferAction { deposit })],
-            block_hash,
-        )
+        TransactionBuilder::new(signer_id, receiver_id)
+            .nonce(nonce)
+            .block_hash(block_hash)
+            .transfer(deposit)
+            .sign(signer)
+            .unwrap()
     }
 
     pub fn stake(
@@ -141,14 +148,12 @@ impl SignedTransaction {
         public_key: PublicKey,
         block_hash: CryptoHash,
     ) -> Self {
-        Self::from_actions(
-            nonce,
-            signer_id.clone(),
-            signer_id,
-            signer,
-            vec![Action::Stake(StakeAction { stake, public_key })],
-            block_hash,
-        )
+        TransactionBuilder::new(signer_id.clone(), signer_id)
+            .nonce(nonce)
+            .block_hash(block_hash)
+            .stake(stake, public_key)
+            .sign(signer)
+            .unwrap()
     }
 
     pub fn create_account(
@@ -160,21 +165,14 @@ impl SignedTransaction {
         signer: &dyn Signer,
         block_hash: CryptoHash,
     ) -> Self {
-        Self::from_actions(
-            nonce,
-            originator,
-            new_account_id,
-            signer,
-            vec![
-                Action::CreateAccount(CreateAccountAction {}),
-                Action::AddKey(AddKeyAction {
-                    public_key,
-                    access_key: AccessKey { nonce: 0, permission: AccessKeyPermission::FullAccess },
-                }),
-                Action::Transfer(TransferAction { deposit: amount }),
-            ],
-            block_hash,
-        )
+        TransactionBuilder::new(originator, new_account_id)
+            .nonce(nonce)
+            .block_hash(block_hash)
+            .create_account()
+            .add_full_access_key(public_key)
+            .transfer(amount)
+            .sign(signer)
+            .unwrap()
     }
 
     pub fn create_contract(
@@ -187,22 +185,15 @@ impl SignedTransaction {
         signer: &dyn Signer,
         block_hash: CryptoHash,
     ) -> Self {
-        Self::from_actions(
-            nonce,
-            originator,
-            new_account_id,
-            signer,
-            vec![
-                Action::CreateAccount(CreateAccountAction {}),
-                Action::AddKey(AddKeyAction {
-                    public_key,
-                    access_key: AccessKey { nonce: 0, permission: AccessKeyPermission::FullAccess },
-                }),
-                Action::Transfer(TransferAction { deposit: amount }),
-                Action::DeployContract(DeployContractAction { code }),
-            ],
-            block_hash,
-        )
+        TransactionBuilder::new(originator, new_account_id)
+            .nonce(nonce)
+            .block_hash(block_hash)
+            .create_account()
+            .add_full_access_key(public_key)
+            .transfer(amount)
+            .deploy_contract(code)
+            .sign(signer)
+            .unwrap()
     }
 
     pub fn call(
@@ -216,14 +207,12 @@ impl SignedTransaction {
         gas: Gas,
         block_hash: CryptoHash,
     ) -> Self {
-        Self::from_actions(
-            nonce,
-            signer_id,
-            receiver_id,
-            signer,
-            vec![Action::FunctionCall(FunctionCallAction { args, method_name, gas, deposit })],
-            block_hash,
-        )
+        TransactionBuilder::new(signer_id, receiver_id)
+            .nonce(nonce)
+            .block_hash(block_hash)
+            .function_call(method_name, args, gas, deposit)
+            .sign(signer)
+            .unwrap()
     }
 
     pub fn delete_account(
@@ -234,14 +223,12 @@ impl SignedTransaction {
         signer: &dyn Signer,
         block_hash: CryptoHash,
     ) -> Self {
-        Self::from_actions(
-            nonce,
-            signer_id,
-            receiver_id,
-            signer,
-            vec![Action::DeleteAccount(DeleteAccountAction { beneficiary_id })],
-            block_hash,
-        )
+        TransactionBuilder::new(signer_id, receiver_id)
+            .nonce(nonce)
+            .block_hash(block_hash)
+            .delete_account(beneficiary_id)
+            .sign(signer)
+            .unwrap()
     }
 
     pub fn empty(block_hash: CryptoHash) -> Self {
@@ -1,25 +1,43 @@
 use std::borrow::Borrow;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::mem::size_of;
 
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::schema::{Declaration, Definition, Fields};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
-use near_crypto::{PublicKey, Signature};
+use near_crypto::key_conversion::is_valid_staking_key;
+use near_crypto::{PublicKey, Signature, Signer};
 
-use crate::account::AccessKey;
-use crate::errors::TxExecutionError;
+use crate::account::{AccessKey, AccessKeyPermission, Account};
+use crate::borsh::maybestd::collections::HashMap;
+use crate::borsh_schema::add_enum_definition;
+use crate::checked_feature;
+use crate::errors::{
+    ActionsValidationError, StakeValidationError, TransactionBuilderError, TxExecutionError,
+};
 use crate::hash::{hash, CryptoHash};
 use crate::logging;
 use crate::merkle::MerklePath;
-use crate::serialize::{base64_format, u128_dec_format_compatible};
-use crate::types::{AccountId, Balance, Gas, Nonce};
+use crate::serialize::{base64_bytes, u128_dec_format_compatible, u64_dec_format};
+use crate::types::{AccountId, Balance, Gas, Nonce, ProtocolVersion};
 use near_primitives_core::profile::ProfileData;
 
 pub type LogEntry = String;
 
+/// Transactions are serialized in one of two layouts:
+///
+/// * V0, used when there is no `priority_fee`: the fields in declaration order.
+/// * V1, used otherwise: `TRANSACTION_V1_TAG`, the V0 fields and the fee.
+///
+/// V0 starts with the little-endian `u32` length of `signer_id`, and valid
+/// account ids are 2 to 64 bytes long, so its first byte is never the tag.
+/// Nodes which don't know V1 read the tag as a `signer_id` longer than 256
+/// bytes and reject the transaction as malformed.
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Transaction {
     /// An account on which behalf transaction is signed
     pub signer_id: AccountId,
@@ -35,6 +53,88 @@ pub struct Transaction {
     pub block_hash: CryptoHash,
     /// A list of actions to be applied
     pub actions: Vec<Action>,
+    /// Amount the signer offers on top of the gas cost to get the
+    /// transaction included first.  Only valid with the
+    /// `PriorityFee` protocol feature.
+    pub priority_fee: Option<Balance>,
+}
+
+const TRANSACTION_V1_TAG: u8 = 1;
+
+impl BorshSerialize for Transaction {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.priority_fee.is_some() {
+            BorshSerialize::serialize(&TRANSACTION_V1_TAG, writer)?;
+        }
+        BorshSerialize::serialize(&self.signer_id, writer)?;
+        BorshSerialize::serialize(&self.public_key, writer)?;
+        BorshSerialize::serialize(&self.nonce, writer)?;
+        BorshSerialize::serialize(&self.receiver_id, writer)?;
+        BorshSerialize::serialize(&self.block_hash, writer)?;
+        BorshSerialize::serialize(&self.actions, writer)?;
+        if let Some(priority_fee) = self.priority_fee {
+            BorshSerialize::serialize(&priority_fee, writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for Transaction {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let is_v1 = buf.first() == Some(&TRANSACTION_V1_TAG);
+        if is_v1 {
+            *buf = &buf[1..];
+        }
+        Ok(Self {
+            signer_id: BorshDeserialize::deserialize(buf)?,
+            public_key: BorshDeserialize::deserialize(buf)?,
+            nonce: BorshDeserialize::deserialize(buf)?,
+            receiver_id: BorshDeserialize::deserialize(buf)?,
+            block_hash: BorshDeserialize::deserialize(buf)?,
+            actions: BorshDeserialize::deserialize(buf)?,
+            priority_fee: if is_v1 { Some(BorshDeserialize::deserialize(buf)?) } else { None },
+        })
+    }
+}
+
+/// Describes the layouts as an enum of `TransactionV0` and `TransactionV1`.
+/// Only the discriminant of V1 is written, V0 is told apart by its first byte,
+/// see `Transaction`.
+impl BorshSchema for Transaction {
+    fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+        AccountId::add_definitions_recursively(definitions);
+        PublicKey::add_definitions_recursively(definitions);
+        CryptoHash::add_definitions_recursively(definitions);
+        Vec::<Action>::add_definitions_recursively(definitions);
+        let v0_fields = vec![
+            ("signer_id".to_string(), AccountId::declaration()),
+            ("public_key".to_string(), PublicKey::declaration()),
+            ("nonce".to_string(), Nonce::declaration()),
+            ("receiver_id".to_string(), AccountId::declaration()),
+            ("block_hash".to_string(), CryptoHash::declaration()),
+            ("actions".to_string(), Vec::<Action>::declaration()),
+        ];
+        let mut v1_fields = v0_fields.clone();
+        v1_fields.push(("priority_fee".to_string(), Balance::declaration()));
+        for (declaration, fields) in [("TransactionV0", v0_fields), ("TransactionV1", v1_fields)] {
+            Self::add_definition(
+                declaration.to_string(),
+                Definition::Struct { fields: Fields::NamedFields(fields) },
+                definitions,
+            );
+        }
+        add_enum_definition::<Self>(
+            vec![
+                ("V0", vec!["TransactionV0".to_string()]),
+                ("V1", vec!["TransactionV1".to_string()]),
+            ],
+            definitions,
+        );
+    }
+
+    fn declaration() -> Declaration {
+        "Transaction".to_string()
+    }
 }
 
 impl Transaction {
@@ -45,6 +145,149 @@ impl Transaction {
     }
 }
 
+/// Builds a `Transaction`, checking that it has a nonce, a block hash and actions, with the
+/// delete account action last.  Actions are added in call order.
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    signer_id: AccountId,
+    receiver_id: AccountId,
+    public_key: Option<PublicKey>,
+    nonce: Option<Nonce>,
+    block_hash: Option<CryptoHash>,
+    actions: Vec<Action>,
+    priority_fee: Option<Balance>,
+}
+
+impl TransactionBuilder {
+    pub fn new(signer_id: AccountId, receiver_id: AccountId) -> Self {
+        Self {
+            signer_id,
+            receiver_id,
+            public_key: None,
+            nonce: None,
+            block_hash: None,
+            actions: vec![],
+            priority_fee: None,
+        }
+    }
+
+    /// Key of the signer's access key.  Not needed with `sign`, which uses the signer's key.
+    pub fn public_key(mut self, public_key: PublicKey) -> Self {
+        self.public_key = Some(public_key);
+        self
+    }
+
+    pub fn nonce(mut self, nonce: Nonce) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    pub fn block_hash(mut self, block_hash: CryptoHash) -> Self {
+        self.block_hash = Some(block_hash);
+        self
+    }
+
+    pub fn priority_fee(mut self, priority_fee: Balance) -> Self {
+        self.priority_fee = Some(priority_fee);
+        self
+    }
+
+    pub fn action(mut self, action: impl Into<Action>) -> Self {
+        self.actions.push(action.into());
+        self
+    }
+
+    pub fn actions(mut self, actions: impl IntoIterator<Item = Action>) -> Self {
+        self.actions.extend(actions);
+        self
+    }
+
+    pub fn create_account(self) -> Self {
+        self.action(CreateAccountAction {})
+    }
+
+    pub fn deploy_contract(self, code: Vec<u8>) -> Self {
+        self.action(DeployContractAction { code })
+    }
+
+    pub fn function_call(
+        self,
+        method_name: impl Into<String>,
+        args: Vec<u8>,
+        gas: Gas,
+        deposit: Balance,
+    ) -> Self {
+        self.action(FunctionCallAction { method_name: method_name.into(), args, gas, deposit })
+    }
+
+    pub fn transfer(self, deposit: Balance) -> Self {
+        self.action(TransferAction { deposit })
+    }
+
+    pub fn stake(self, stake: Balance, public_key: PublicKey) -> Self {
+        self.action(StakeAction { stake, public_key })
+    }
+
+    pub fn add_key(self, public_key: PublicKey, access_key: AccessKey) -> Self {
+        self.action(AddKeyAction { public_key, access_key })
+    }
+
+    pub fn add_full_access_key(self, public_key: PublicKey) -> Self {
+        self.add_key(public_key, AccessKey::full_access())
+    }
+
+    pub fn delete_key(self, public_key: PublicKey) -> Self {
+        self.action(DeleteKeyAction { public_key })
+    }
+
+    pub fn delete_account(self, beneficiary_id: AccountId) -> Self {
+        self.action(DeleteAccountAction { beneficiary_id })
+    }
+
+    pub fn build(self) -> Result<Transaction, TransactionBuilderError> {
+        let nonce = self.nonce.ok_or(TransactionBuilderError::MissingNonce)?;
+        let block_hash = self.block_hash.ok_or(TransactionBuilderError::MissingBlockHash)?;
+        let public_key = self.public_key.ok_or(TransactionBuilderError::MissingPublicKey)?;
+        if self.actions.is_empty() {
+            return Err(TransactionBuilderError::NoActions);
+        }
+        let delete_position =
+            self.actions.iter().position(|action| matches!(action, Action::DeleteAccount(_)));
+        if delete_position.map_or(false, |position| position + 1 != self.actions.len()) {
+            return Err(TransactionBuilderError::DeleteActionMustBeFinal);
+        }
+        Ok(Transaction {
+            signer_id: self.signer_id,
+            public_key,
+            nonce,
+            receiver_id: self.receiver_id,
+            block_hash,
+            actions: self.actions,
+            priority_fee: self.priority_fee,
+        })
+    }
+
+    /// Builds the transaction and signs it, with the signer's key unless `public_key` was set.
+    pub fn sign(
+        mut self,
+        signer: &dyn Signer,
+    ) -> Result<SignedTransaction, TransactionBuilderError> {
+        let signer_public_key = signer.public_key();
+        match self.public_key.take() {
+            Some(public_key) if public_key != signer_public_key => {
+                return Err(TransactionBuilderError::SignerKeyMismatch {
+                    public_key,
+                    signer_public_key,
+                });
+            }
+            _ => self.public_key = Some(signer_public_key),
+        }
+        let transaction = self.build()?;
+        let signature = signer.sign(transaction.get_hash_and_size().0.as_ref());
+        Ok(SignedTransaction::new(signature, transaction))
+    }
+}
+
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(
     BorshSerialize,
@@ -74,6 +317,38 @@ pub enum Action {
     StakeChunkOnly(StakeAction),
 }
 
+/// Lists the variants enabled in this build.
+impl BorshSchema for Action {
+    fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+        CreateAccountAction::add_definitions_recursively(definitions);
+        DeployContractAction::add_definitions_recursively(definitions);
+        FunctionCallAction::add_definitions_recursively(definitions);
+        TransferAction::add_definitions_recursively(definitions);
+        StakeAction::add_definitions_recursively(definitions);
+        AddKeyAction::add_definitions_recursively(definitions);
+        DeleteKeyAction::add_definitions_recursively(definitions);
+        DeleteAccountAction::add_definitions_recursively(definitions);
+        #[allow(unused_mut)]
+        let mut variants = vec![
+            ("CreateAccount", vec![CreateAccountAction::declaration()]),
+            ("DeployContract", vec![DeployContractAction::declaration()]),
+            ("FunctionCall", vec![FunctionCallAction::declaration()]),
+            ("Transfer", vec![TransferAction::declaration()]),
+            ("Stake", vec![StakeAction::declaration()]),
+            ("AddKey", vec![AddKeyAction::declaration()]),
+            ("DeleteKey", vec![DeleteKeyAction::declaration()]),
+            ("DeleteAccount", vec![DeleteAccountAction::declaration()]),
+        ];
+        #[cfg(feature = "protocol_feature_chunk_only_producers")]
+        variants.push(("StakeChunkOnly", vec![StakeAction::declaration()]));
+        add_enum_definition::<Self>(variants, definitions);
+    }
+
+    fn declaration() -> Declaration {
+        "Action".to_string()
+    }
+}
+
 impl Action {
     pub fn get_prepaid_gas(&self) -> Gas {
         match self {
@@ -88,11 +363,121 @@ impl Action {
             _ => 0,
         }
     }
+
+    /// Number of bytes the action occupies when borsh-serialized, computed
+    /// without serializing it.
+    pub fn size_bytes(&self) -> u64 {
+        // One byte for the enum discriminant.
+        1 + match self {
+            Action::CreateAccount(_) => 0,
+            Action::DeployContract(a) => vec_size(a.code.len()),
+            Action::FunctionCall(a) => {
+                vec_size(a.method_name.len())
+                    + vec_size(a.args.len())
+                    + size_of::<Gas>() as u64
+                    + size_of::<Balance>() as u64
+            }
+            Action::Transfer(_) => size_of::<Balance>() as u64,
+            Action::Stake(a) => size_of::<Balance>() as u64 + a.public_key.len() as u64,
+            Action::AddKey(a) => a.public_key.len() as u64 + access_key_size(&a.access_key),
+            Action::DeleteKey(a) => a.public_key.len() as u64,
+            Action::DeleteAccount(a) => vec_size(a.beneficiary_id.len()),
+            #[cfg(feature = "protocol_feature_chunk_only_producers")]
+            Action::StakeChunkOnly(a) => size_of::<Balance>() as u64 + a.public_key.len() as u64,
+        }
+    }
+}
+
+/// Borsh size of a string or byte vector of `len` bytes: the bytes prefixed
+/// with a `u32` length.
+pub(crate) fn vec_size(len: usize) -> u64 {
+    size_of::<u32>() as u64 + len as u64
+}
+
+fn access_key_size(access_key: &AccessKey) -> u64 {
+    let permission_size = match &access_key.permission {
+        AccessKeyPermission::FullAccess => 0,
+        AccessKeyPermission::FunctionCall(permission) => {
+            1 + permission.allowance.map_or(0, |_| size_of::<Balance>() as u64)
+                + vec_size(permission.receiver_id.len())
+                + size_of::<u32>() as u64
+                + permission.method_names.iter().map(|name| vec_size(name.len())).sum::<u64>()
+        }
+    };
+    size_of::<Nonce>() as u64 + 1 + permission_size
+}
+
+/// Short single-line summary meant for logs, e.g.
+/// `FunctionCall(method=ft_transfer, args=120B, gas=30Tgas, deposit=1yN)`.
+/// Contract code and call arguments are only described by their size.
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::CreateAccount(_) => f.write_str("CreateAccount"),
+            Action::DeployContract(a) => write!(f, "DeployContract(code={}B)", a.code.len()),
+            Action::FunctionCall(a) => write!(
+                f,
+                "FunctionCall(method={}, args={}B, gas={}, deposit={})",
+                a.method_name,
+                a.args.len(),
+                logging::pretty_gas(a.gas),
+                logging::pretty_balance(a.deposit)
+            ),
+            Action::Transfer(a) => {
+                write!(f, "Transfer(deposit={})", logging::pretty_balance(a.deposit))
+            }
+            Action::Stake(a) => {
+                write!(f, "Stake(stake={}, key={})", logging::pretty_balance(a.stake), a.public_key)
+            }
+            Action::AddKey(a) => {
+                write!(f, "AddKey(key={}, permission=", a.public_key)?;
+                match &a.access_key.permission {
+                    AccessKeyPermission::FullAccess => f.write_str("FullAccess")?,
+                    AccessKeyPermission::FunctionCall(permission) => {
+                        write!(f, "FunctionCall(receiver={}, methods=", permission.receiver_id)?;
+                        if permission.method_names.is_empty() {
+                            f.write_str("any")?;
+                        } else {
+                            write!(f, "[{}]", permission.method_names.join(", "))?;
+                        }
+                        match permission.allowance {
+                            Some(allowance) => {
+                                write!(f, ", allowance={})", logging::pretty_balance(allowance))?
+                            }
+                            None => f.write_str(", allowance=unlimited)")?,
+                        }
+                    }
+                }
+                f.write_str(")")
+            }
+            Action::DeleteKey(a) => write!(f, "DeleteKey(key={})", a.public_key),
+            Action::DeleteAccount(a) => {
+                write!(f, "DeleteAccount(beneficiary={})", a.beneficiary_id)
+            }
+            #[cfg(feature = "protocol_feature_chunk_only_producers")]
+            Action::StakeChunkOnly(a) => write!(
+                f,
+                "StakeChunkOnly(stake={}, key={})",
+                logging::pretty_balance(a.stake),
+                a.public_key
+            ),
+        }
+    }
 }
 
 /// Create account action
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+)]
 pub struct CreateAccountAction {}
 
 impl From<CreateAccountAction> for Action {
@@ -103,10 +488,12 @@ impl From<CreateAccountAction> for Action {
 
 /// Deploy contract action
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[derive(
+    BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Deserialize, PartialEq, Eq, Clone,
+)]
 pub struct DeployContractAction {
     /// WebAssembly binary
-    #[serde(with = "base64_format")]
+    #[serde(with = "base64_bytes")]
     pub code: Vec<u8>,
 }
 
@@ -125,10 +512,12 @@ impl fmt::Debug for DeployContractAction {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[derive(
+    BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Deserialize, PartialEq, Eq, Clone,
+)]
 pub struct FunctionCallAction {
     pub method_name: String,
-    #[serde(with = "base64_format")]
+    #[serde(with = "base64_bytes")]
     pub args: Vec<u8>,
     pub gas: Gas,
     #[serde(with = "u128_dec_format_compatible")]
@@ -153,7 +542,17 @@ impl fmt::Debug for FunctionCallAction {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+)]
 pub struct TransferAction {
     #[serde(with = "u128_dec_format_compatible")]
     pub deposit: Balance,
@@ -167,7 +566,17 @@ impl From<TransferAction> for Action {
 
 /// An action which stakes singer_id tokens and setup's validator public key
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+)]
 pub struct StakeAction {
     /// Amount of tokens to stake.
     #[serde(with = "u128_dec_format_compatible")]
@@ -176,6 +585,62 @@ pub struct StakeAction {
     pub public_key: PublicKey,
 }
 
+impl StakeAction {
+    /// Checks that the staking key is an ED25519 key convertible to ristretto.
+    /// Doesn't depend on the state, so it's done when validating actions.
+    pub fn validate_staking_key(&self) -> Result<(), StakeValidationError> {
+        if !is_valid_staking_key(&self.public_key) {
+            return Err(StakeValidationError::UnsuitableStakingKey {
+                public_key: self.public_key.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the stake can be applied to `account`: the account has
+    /// enough balance to raise its stake to `self.stake`, unstaking is only
+    /// done by staked accounts and a non-zero stake is at least
+    /// `minimum_stake`, see `epoch_manager::minimum_stake`.
+    pub fn validate_against(
+        &self,
+        account: &Account,
+        minimum_stake: Balance,
+    ) -> Result<(), StakeValidationError> {
+        self.validate_balance(account)?;
+        self.validate_minimum_stake(minimum_stake)
+    }
+
+    /// The checks of `validate_against` which don't need the minimum stake.
+    pub fn validate_balance(&self, account: &Account) -> Result<(), StakeValidationError> {
+        let increment = self.stake.saturating_sub(account.locked());
+        if account.amount() < increment {
+            return Err(StakeValidationError::TriesToStake {
+                stake: self.stake,
+                locked: account.locked(),
+                balance: account.amount(),
+            });
+        }
+        if account.locked() == 0 && self.stake == 0 {
+            return Err(StakeValidationError::TriesToUnstake);
+        }
+        Ok(())
+    }
+
+    /// Checks that a non-zero stake is at least `minimum_stake`.
+    pub fn validate_minimum_stake(
+        &self,
+        minimum_stake: Balance,
+    ) -> Result<(), StakeValidationError> {
+        if self.stake > 0 && self.stake < minimum_stake {
+            return Err(StakeValidationError::InsufficientStake {
+                stake: self.stake,
+                minimum_stake,
+            });
+        }
+        Ok(())
+    }
+}
+
 impl From<StakeAction> for Action {
     fn from(stake_action: StakeAction) -> Self {
         Self::Stake(stake_action)
@@ -183,7 +648,17 @@ impl From<StakeAction> for Action {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+)]
 pub struct AddKeyAction {
     /// A public key which will be associated with an access_key
     pub public_key: PublicKey,
@@ -198,7 +673,17 @@ impl From<AddKeyAction> for Action {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+)]
 pub struct DeleteKeyAction {
     /// A public key associated with the access_key to be deleted.
     pub public_key: PublicKey,
@@ -211,11 +696,48 @@ impl From<DeleteKeyAction> for Action {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+)]
 pub struct DeleteAccountAction {
     pub beneficiary_id: AccountId,
 }
 
+impl DeleteAccountAction {
+    /// Checks the beneficiary of deleting `account_id`.
+    ///
+    /// The balance is paid out with a refund receipt after the account is
+    /// gone, so naming the deleted account itself burns it.  The same happens
+    /// with an implicit beneficiary which doesn't exist yet, as refunds don't
+    /// create accounts; that depends on the state and isn't checked here.
+    pub fn validate(
+        &self,
+        account_id: &AccountId,
+        protocol_version: ProtocolVersion,
+    ) -> Result<(), ActionsValidationError> {
+        if checked_feature!(
+            "protocol_feature_reject_self_beneficiary",
+            RejectSelfBeneficiary,
+            protocol_version
+        ) && &self.beneficiary_id == account_id
+        {
+            return Err(ActionsValidationError::DeleteActionMustHaveDifferentBeneficiary {
+                account_id: account_id.clone(),
+                beneficiary_id: self.beneficiary_id.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
 impl From<DeleteAccountAction> for Action {
     fn from(delete_account_action: DeleteAccountAction) -> Self {
         Self::DeleteAccount(delete_account_action)
@@ -223,7 +745,7 @@ impl From<DeleteAccountAction> for Action {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Eq, Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Eq, Debug, Clone)]
 #[borsh_init(init)]
 pub struct SignedTransaction {
     pub transaction: Transaction,
@@ -265,7 +787,7 @@ impl Hash for SignedTransaction {
 
 impl PartialEq for SignedTransaction {
     fn eq(&self, other: &SignedTransaction) -> bool {
-        self.hash == other.hash && self.signature == other.signature
+        self.hash == other.hash && self.signature.ct_eq(&other.signature)
     }
 }
 
@@ -384,6 +906,10 @@ pub enum ExecutionMetadata {
 
     // V2: With ProfileData
     V2(ProfileData),
+
+    // V3: With ProfileData and the gas of each applied action, which nodes may drop before
+    // saving the outcome
+    V3 { profile: ProfileData, action_gas_breakdown: Option<Vec<ActionGasBreakdown>> },
 }
 
 impl Default for ExecutionMetadata {
@@ -392,6 +918,30 @@ impl Default for ExecutionMetadata {
     }
 }
 
+impl ExecutionMetadata {
+    /// Drops the per action gas breakdown, keeping the rest of the metadata.
+    pub fn clear_action_gas_breakdown(&mut self) {
+        if let ExecutionMetadata::V3 { action_gas_breakdown, .. } = self {
+            *action_gas_breakdown = None;
+        }
+    }
+}
+
+/// Gas of a single action of an action receipt.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Eq, Debug)]
+pub struct ActionGasBreakdown {
+    /// Index of the action in the receipt.
+    pub action_index: u64,
+    /// Gas burnt for sending the action, in the outcome which created the receipt.
+    #[serde(with = "u64_dec_format")]
+    pub send_gas: Gas,
+    /// Gas burnt applying the action: its execution fee, the contract execution and sending
+    /// the receipts it created.
+    #[serde(with = "u64_dec_format")]
+    pub exec_gas: Gas,
+}
+
 impl ExecutionOutcome {
     pub fn to_hashes(&self) -> Vec<CryptoHash> {
         let mut result = vec![hash(
@@ -402,6 +952,70 @@ impl ExecutionOutcome {
         }
         result
     }
+
+    /// Follows `SuccessReceiptId` statuses, starting with this outcome, to the outcome which
+    /// determines the result, e.g. the result of a transaction is the result of the receipt it
+    /// was converted to, which may in turn be the result of a promise it returned.
+    ///
+    /// `lookup` returns the outcome of a receipt, or `None` if it's not known (yet).
+    pub fn flatten_receipt_chain(
+        &self,
+        lookup: impl Fn(&CryptoHash) -> Option<ExecutionOutcome>,
+    ) -> FlattenedResult {
+        let mut result = FlattenedResult {
+            status: FlattenedStatus::Unknown,
+            receipt_ids: vec![],
+            gas_burnt: self.gas_burnt,
+            tokens_burnt: self.tokens_burnt,
+        };
+        let mut status = self.status.clone();
+        loop {
+            result.status = match status {
+                ExecutionStatus::Unknown => FlattenedStatus::Unknown,
+                ExecutionStatus::Failure(error) => FlattenedStatus::Failure(error),
+                ExecutionStatus::SuccessValue(value) => FlattenedStatus::SuccessValue(value),
+                ExecutionStatus::SuccessReceiptId(receipt_id) => {
+                    if result.receipt_ids.contains(&receipt_id) {
+                        FlattenedStatus::Cycle(receipt_id)
+                    } else if let Some(outcome) = lookup(&receipt_id) {
+                        result.receipt_ids.push(receipt_id);
+                        result.gas_burnt = result.gas_burnt.saturating_add(outcome.gas_burnt);
+                        result.tokens_burnt =
+                            result.tokens_burnt.saturating_add(outcome.tokens_burnt);
+                        status = outcome.status;
+                        continue;
+                    } else {
+                        FlattenedStatus::MissingOutcome(receipt_id)
+                    }
+                }
+            };
+            return result;
+        }
+    }
+}
+
+/// Result of `ExecutionOutcome::flatten_receipt_chain`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlattenedResult {
+    pub status: FlattenedStatus,
+    /// Receipts followed, in order; their outcomes were found.
+    pub receipt_ids: Vec<CryptoHash>,
+    /// Gas burnt by the starting outcome and the outcomes of `receipt_ids`.
+    pub gas_burnt: Gas,
+    /// Tokens burnt by the starting outcome and the outcomes of `receipt_ids`.
+    pub tokens_burnt: Balance,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlattenedStatus {
+    /// The last outcome has `ExecutionStatus::Unknown`.
+    Unknown,
+    Failure(TxExecutionError),
+    SuccessValue(Vec<u8>),
+    /// The outcome of the receipt isn't known, e.g. it wasn't executed yet.
+    MissingOutcome(CryptoHash),
+    /// The receipt was already followed; well-formed outcomes never have cycles.
+    Cycle(CryptoHash),
 }
 
 impl fmt::Debug for ExecutionOutcome {
@@ -467,6 +1081,7 @@ mod tests {
     use near_crypto::{InMemorySigner, KeyType, Signature, Signer};
 
     use crate::account::{AccessKeyPermission, FunctionCallPermission};
+    use crate::errors::InvalidTxError;
     use crate::serialize::to_base;
 
     use super::*;
@@ -481,6 +1096,7 @@ mod tests {
             receiver_id: "test".parse().unwrap(),
             block_hash: Default::default(),
             actions: vec![],
+            priority_fee: None,
         }
         .sign(&signer);
         let wrong_public_key = PublicKey::from_seed(KeyType::ED25519, "wrong");
@@ -533,6 +1149,7 @@ mod tests {
                     beneficiary_id: "123".parse().unwrap(),
                 }),
             ],
+            priority_fee: None,
         };
         let signed_tx = SignedTransaction::new(Signature::empty(KeyType::ED25519), transaction);
         let new_signed_tx =
@@ -558,4 +1175,452 @@ mod tests {
         let hashes = outcome.to_hashes();
         assert_eq!(hashes.len(), 3);
     }
+
+    fn priority_fee_transaction(priority_fee: Option<Balance>) -> Transaction {
+        Transaction {
+            signer_id: "test.near".parse().unwrap(),
+            public_key: PublicKey::from_seed(KeyType::ED25519, "test.near"),
+            nonce: 1,
+            receiver_id: "123".parse().unwrap(),
+            block_hash: Default::default(),
+            actions: vec![Action::Transfer(TransferAction { deposit: 123 })],
+            priority_fee,
+        }
+    }
+
+    #[test]
+    fn test_serialize_priority_fee_transaction() {
+        for priority_fee in [None, Some(0), Some(10u128.pow(24))] {
+            let transaction = priority_fee_transaction(priority_fee);
+            let bytes = transaction.try_to_vec().unwrap();
+            assert_eq!(bytes[0] == TRANSACTION_V1_TAG, priority_fee.is_some());
+            assert_eq!(Transaction::try_from_slice(&bytes).unwrap(), transaction);
+
+            let signed_tx = SignedTransaction::new(Signature::empty(KeyType::ED25519), transaction);
+            let decoded = SignedTransaction::try_from_slice(&signed_tx.try_to_vec().unwrap());
+            assert_eq!(decoded.unwrap(), signed_tx);
+        }
+        // The fee is signed over.
+        assert_ne!(
+            priority_fee_transaction(None).get_hash_and_size().0,
+            priority_fee_transaction(Some(0)).get_hash_and_size().0
+        );
+    }
+
+    /// Nodes which don't know the V1 layout must fail to decode it rather
+    /// than misinterpret it.
+    #[test]
+    fn test_old_layout_rejects_priority_fee() {
+        #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
+        struct TransactionV0 {
+            signer_id: AccountId,
+            public_key: PublicKey,
+            nonce: Nonce,
+            receiver_id: AccountId,
+            block_hash: CryptoHash,
+            actions: Vec<Action>,
+        }
+
+        let transaction = priority_fee_transaction(None);
+        let legacy = TransactionV0::try_from_slice(&transaction.try_to_vec().unwrap()).unwrap();
+        assert_eq!(legacy.try_to_vec().unwrap(), transaction.try_to_vec().unwrap());
+
+        let bytes = priority_fee_transaction(Some(100)).try_to_vec().unwrap();
+        assert!(TransactionV0::try_from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_action_display() {
+        let public_key = PublicKey::empty(KeyType::ED25519);
+        let cases: Vec<(Action, String)> = vec![
+            (CreateAccountAction {}.into(), "CreateAccount".to_string()),
+            (
+                DeployContractAction { code: vec![0; 1000] }.into(),
+                "DeployContract(code=1000B)".to_string(),
+            ),
+            (
+                FunctionCallAction {
+                    method_name: "ft_transfer".to_string(),
+                    args: vec![0; 120],
+                    gas: 30_000_000_000_000,
+                    deposit: 1,
+                }
+                .into(),
+                "FunctionCall(method=ft_transfer, args=120B, gas=30Tgas, deposit=1yN)".to_string(),
+            ),
+            (
+                TransferAction { deposit: 2_500_000_000_000_000_000_000_000 }.into(),
+                "Transfer(deposit=2.5NEAR)".to_string(),
+            ),
+            (
+                StakeAction { stake: 10u128.pow(29), public_key: public_key.clone() }.into(),
+                format!("Stake(stake=100000NEAR, key={})", public_key),
+            ),
+            (
+                AddKeyAction { public_key: public_key.clone(), access_key: AccessKey::full_access() }
+                    .into(),
+                format!("AddKey(key={}, permission=FullAccess)", public_key),
+            ),
+            (
+                AddKeyAction {
+                    public_key: public_key.clone(),
+                    access_key: AccessKey {
+                        nonce: 0,
+                        permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                            allowance: None,
+                            receiver_id: "token.near".to_string(),
+                            method_names: vec![],
+                        }),
+                    },
+                }
+                .into(),
+                format!(
+                    "AddKey(key={}, permission=FunctionCall(receiver=token.near, methods=any, allowance=unlimited))",
+                    public_key
+                ),
+            ),
+            (
+                AddKeyAction {
+                    public_key: public_key.clone(),
+                    access_key: AccessKey {
+                        nonce: 0,
+                        permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                            allowance: Some(250_000_000_000_000_000_000_000),
+                            receiver_id: "token.near".to_string(),
+                            method_names: vec!["ft_transfer".to_string(), "ft_mint".to_string()],
+                        }),
+                    },
+                }
+                .into(),
+                format!(
+                    "AddKey(key={}, permission=FunctionCall(receiver=token.near, methods=[ft_transfer, ft_mint], allowance=0.25NEAR))",
+                    public_key
+                ),
+            ),
+            (
+                DeleteKeyAction { public_key: public_key.clone() }.into(),
+                format!("DeleteKey(key={})", public_key),
+            ),
+            (
+                DeleteAccountAction { beneficiary_id: "bob.near".parse().unwrap() }.into(),
+                "DeleteAccount(beneficiary=bob.near)".to_string(),
+            ),
+        ];
+        for (action, expected) in cases {
+            assert_eq!(action.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_action_json() {
+        let function_call = Action::FunctionCall(FunctionCallAction {
+            method_name: "addMessage".to_string(),
+            args: br#"{"text":"hello"}"#.to_vec(),
+            gas: 30_000_000_000_000,
+            deposit: 0,
+        });
+        assert_eq!(
+            serde_json::to_string(&function_call).unwrap(),
+            r#"{"FunctionCall":{"method_name":"addMessage","args":"eyJ0ZXh0IjoiaGVsbG8ifQ==","gas":30000000000000,"deposit":"0"}}"#
+        );
+        let deploy_contract =
+            Action::DeployContract(DeployContractAction { code: b"\0asm\x01\0\0\0".to_vec() });
+        assert_eq!(
+            serde_json::to_string(&deploy_contract).unwrap(),
+            r#"{"DeployContract":{"code":"AGFzbQEAAAA="}}"#
+        );
+
+        // Arrays of numbers are still accepted.
+        let action: Action = serde_json::from_str(
+            r#"{"FunctionCall":{"method_name":"addMessage","args":[123,34,116,101,120,116,34,58,34,104,101,108,108,111,34,125],"gas":30000000000000,"deposit":"0"}}"#,
+        )
+        .unwrap();
+        assert_eq!(action, function_call);
+    }
+
+    #[test]
+    fn test_near_api_js_actions_round_trip() {
+        // Actions in the shape near-api-js sends them, with base64 bytes and
+        // string deposits.
+        let payloads = [
+            r#"{"FunctionCall":{"method_name":"ft_transfer","args":"eyJyZWNlaXZlcl9pZCI6ImJvYi5uZWFyIiwiYW1vdW50IjoiMTAwMCJ9","gas":30000000000000,"deposit":"1"}}"#,
+            r#"{"FunctionCall":{"method_name":"get_status","args":"","gas":100000000000000,"deposit":"0"}}"#,
+            r#"{"DeployContract":{"code":"AGFzbQEAAAA="}}"#,
+        ];
+        for payload in payloads {
+            let action: Action = serde_json::from_str(payload).unwrap();
+            assert_eq!(serde_json::to_string(&action).unwrap(), payload);
+        }
+        let action: Action = serde_json::from_str(payloads[0]).unwrap();
+        match action {
+            Action::FunctionCall(call) => {
+                assert_eq!(call.args, br#"{"receiver_id":"bob.near","amount":"1000"}"#)
+            }
+            _ => panic!("unexpected action {:?}", action),
+        }
+    }
+
+    #[test]
+    fn test_execution_metadata_borsh() {
+        // Outcomes saved before V3 keep deserializing.
+        let v2 = ExecutionMetadata::V2(ProfileData::new());
+        let bytes = v2.try_to_vec().unwrap();
+        assert_eq!(bytes[0], 1);
+        assert_eq!(ExecutionMetadata::try_from_slice(&bytes).unwrap(), v2);
+
+        let breakdown = vec![
+            ActionGasBreakdown { action_index: 0, send_gas: 1, exec_gas: 2 },
+            ActionGasBreakdown { action_index: 1, send_gas: 3, exec_gas: 4 },
+        ];
+        let mut v3 = ExecutionMetadata::V3 {
+            profile: ProfileData::new(),
+            action_gas_breakdown: Some(breakdown),
+        };
+        let bytes = v3.try_to_vec().unwrap();
+        assert_eq!(bytes[0], 2);
+        assert_eq!(ExecutionMetadata::try_from_slice(&bytes).unwrap(), v3);
+
+        v3.clear_action_gas_breakdown();
+        assert_eq!(
+            v3,
+            ExecutionMetadata::V3 { profile: ProfileData::new(), action_gas_breakdown: None }
+        );
+        assert_eq!(ExecutionMetadata::try_from_slice(&v3.try_to_vec().unwrap()).unwrap(), v3);
+    }
+
+    #[test]
+    fn test_stake_validate_against() {
+        let public_key = PublicKey::from_seed(KeyType::ED25519, "validator");
+        let stake = |stake| StakeAction { stake, public_key: public_key.clone() };
+        let minimum_stake = 1000;
+        let unstaked = Account::new(5000, 0, CryptoHash::default(), 0);
+        let staked = Account::new(100, 2000, CryptoHash::default(), 0);
+
+        // Exactly at the minimum, one yocto below.
+        assert_eq!(stake(minimum_stake).validate_against(&unstaked, minimum_stake), Ok(()));
+        assert_eq!(
+            stake(minimum_stake - 1).validate_against(&unstaked, minimum_stake),
+            Err(StakeValidationError::InsufficientStake { stake: 999, minimum_stake })
+        );
+        // The whole balance, one yocto more.
+        assert_eq!(stake(5000).validate_against(&unstaked, minimum_stake), Ok(()));
+        assert_eq!(
+            stake(5001).validate_against(&unstaked, minimum_stake),
+            Err(StakeValidationError::TriesToStake { stake: 5001, locked: 0, balance: 5000 })
+        );
+        assert_eq!(stake(2100).validate_against(&staked, minimum_stake), Ok(()));
+        assert_eq!(
+            stake(2101).validate_against(&staked, minimum_stake),
+            Err(StakeValidationError::TriesToStake { stake: 2101, locked: 2000, balance: 100 })
+        );
+        // Lowering the stake doesn't need balance, only the minimum.
+        assert_eq!(stake(1000).validate_against(&staked, minimum_stake), Ok(()));
+        assert_eq!(
+            stake(1).validate_against(&staked, Balance::MAX),
+            Err(StakeValidationError::InsufficientStake { stake: 1, minimum_stake: Balance::MAX })
+        );
+        // Unstake to zero is only allowed for staked accounts, regardless of the minimum.
+        assert_eq!(stake(0).validate_against(&staked, minimum_stake), Ok(()));
+        assert_eq!(
+            stake(0).validate_against(&unstaked, minimum_stake),
+            Err(StakeValidationError::TriesToUnstake)
+        );
+
+        // The balance is checked before the minimum.
+        assert_eq!(
+            stake(5001).validate_against(&unstaked, Balance::MAX),
+            Err(StakeValidationError::TriesToStake { stake: 5001, locked: 0, balance: 5000 })
+        );
+
+        // The staking key is checked on its own.
+        assert_eq!(stake(minimum_stake).validate_staking_key(), Ok(()));
+        let secp_key = PublicKey::from_seed(KeyType::SECP256K1, "validator");
+        let action = StakeAction { stake: minimum_stake, public_key: secp_key.clone() };
+        assert_eq!(action.validate_against(&unstaked, minimum_stake), Ok(()));
+        assert_eq!(
+            action.validate_staking_key(),
+            Err(StakeValidationError::UnsuitableStakingKey { public_key: secp_key })
+        );
+    }
+
+    fn outcome(status: ExecutionStatus, gas_burnt: Gas) -> ExecutionOutcome {
+        ExecutionOutcome {
+            gas_burnt,
+            tokens_burnt: Balance::from(gas_burnt) * 10,
+            status,
+            ..Default::default()
+        }
+    }
+
+    /// Outcome `i` of the chain returns receipt `i + 1`, the last one has `last_status`.
+    fn receipt_chain(
+        len: u64,
+        last_status: ExecutionStatus,
+    ) -> (ExecutionOutcome, std::collections::HashMap<CryptoHash, ExecutionOutcome>) {
+        let receipt_id = |i: u64| hash(&i.to_le_bytes());
+        let outcomes = (1..=len)
+            .map(|i| {
+                let status = if i == len {
+                    last_status.clone()
+                } else {
+                    ExecutionStatus::SuccessReceiptId(receipt_id(i + 1))
+                };
+                (receipt_id(i), outcome(status, i))
+            })
+            .collect();
+        (outcome(ExecutionStatus::SuccessReceiptId(receipt_id(1)), 100), outcomes)
+    }
+
+    #[test]
+    fn test_flatten_receipt_chain() {
+        let value = ExecutionStatus::SuccessValue(b"ok".to_vec());
+        let result = outcome(value, 7).flatten_receipt_chain(|_| unreachable!());
+        assert_eq!(
+            result,
+            FlattenedResult {
+                status: FlattenedStatus::SuccessValue(b"ok".to_vec()),
+                receipt_ids: vec![],
+                gas_burnt: 7,
+                tokens_burnt: 70,
+            }
+        );
+
+        let (transaction, outcomes) =
+            receipt_chain(1000, ExecutionStatus::SuccessValue(b"deep".to_vec()));
+        let result = transaction.flatten_receipt_chain(|id| outcomes.get(id).cloned());
+        assert_eq!(result.status, FlattenedStatus::SuccessValue(b"deep".to_vec()));
+        assert_eq!(result.receipt_ids.len(), 1000);
+        assert_eq!(result.receipt_ids[999], hash(&1000u64.to_le_bytes()));
+        assert_eq!(result.gas_burnt, 100 + 1000 * 1001 / 2);
+        assert_eq!(result.tokens_burnt, Balance::from(result.gas_burnt) * 10);
+
+        let (transaction, outcomes) = receipt_chain(3, ExecutionStatus::Unknown);
+        let result = transaction.flatten_receipt_chain(|id| outcomes.get(id).cloned());
+        assert_eq!(result.status, FlattenedStatus::Unknown);
+        assert_eq!(result.receipt_ids.len(), 3);
+    }
+
+    #[test]
+    fn test_flatten_receipt_chain_failure() {
+        let error = TxExecutionError::InvalidTxError(InvalidTxError::InvalidSignature);
+        let (transaction, outcomes) = receipt_chain(5, ExecutionStatus::Failure(error.clone()));
+        let result = transaction.flatten_receipt_chain(|id| outcomes.get(id).cloned());
+        assert_eq!(result.status, FlattenedStatus::Failure(error.clone()));
+        assert_eq!(result.gas_burnt, 100 + 15);
+
+        // A failure in the middle of the chain ends it.
+        let (transaction, mut outcomes) = receipt_chain(5, ExecutionStatus::Unknown);
+        let third = hash(&3u64.to_le_bytes());
+        outcomes.get_mut(&third).unwrap().status = ExecutionStatus::Failure(error.clone());
+        let result = transaction.flatten_receipt_chain(|id| outcomes.get(id).cloned());
+        assert_eq!(result.status, FlattenedStatus::Failure(error));
+        assert_eq!(result.receipt_ids.last(), Some(&third));
+        assert_eq!(result.gas_burnt, 100 + 6);
+    }
+
+    #[test]
+    fn test_flatten_receipt_chain_missing_and_cycle() {
+        let (transaction, mut outcomes) = receipt_chain(5, ExecutionStatus::Unknown);
+        let third = hash(&3u64.to_le_bytes());
+        let third_outcome = outcomes.remove(&third).unwrap();
+        let result = transaction.flatten_receipt_chain(|id| outcomes.get(id).cloned());
+        assert_eq!(result.status, FlattenedStatus::MissingOutcome(third));
+        assert_eq!(result.receipt_ids.len(), 2);
+        assert_eq!(result.gas_burnt, 100 + 3);
+
+        let fifth = hash(&5u64.to_le_bytes());
+        outcomes.insert(third, third_outcome);
+        outcomes.get_mut(&fifth).unwrap().status = ExecutionStatus::SuccessReceiptId(third);
+        let result = transaction.flatten_receipt_chain(|id| outcomes.get(id).cloned());
+        assert_eq!(result.status, FlattenedStatus::Cycle(third));
+        assert_eq!(result.receipt_ids.len(), 5);
+    }
+
+    #[test]
+    fn test_transaction_builder() {
+        let signer =
+            InMemorySigner::from_seed("alice.near".parse().unwrap(), KeyType::ED25519, "alice");
+        let new_key = PublicKey::from_seed(KeyType::ED25519, "new");
+        let builder = TransactionBuilder::new(
+            "alice.near".parse().unwrap(),
+            "bob.alice.near".parse().unwrap(),
+        )
+        .nonce(7)
+        .block_hash(hash(b"block"))
+        .create_account()
+        .transfer(100)
+        .add_full_access_key(new_key.clone())
+        .function_call("init", b"{}".to_vec(), 1000, 0);
+        let signed_tx = builder.clone().sign(&signer).unwrap();
+        assert!(verify_transaction_signature(&signed_tx, &[signer.public_key()]));
+        let transaction = builder.public_key(signer.public_key()).build().unwrap();
+        assert_eq!(signed_tx.transaction, transaction);
+        assert_eq!(
+            transaction,
+            Transaction {
+                signer_id: "alice.near".parse().unwrap(),
+                public_key: signer.public_key(),
+                nonce: 7,
+                receiver_id: "bob.alice.near".parse().unwrap(),
+                block_hash: hash(b"block"),
+                actions: vec![
+                    Action::CreateAccount(CreateAccountAction {}),
+                    Action::Transfer(TransferAction { deposit: 100 }),
+                    Action::AddKey(AddKeyAction {
+                        public_key: new_key,
+                        access_key: AccessKey::full_access()
+                    }),
+                    Action::FunctionCall(FunctionCallAction {
+                        method_name: "init".to_string(),
+                        args: b"{}".to_vec(),
+                        gas: 1000,
+                        deposit: 0
+                    }),
+                ],
+                priority_fee: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_transaction_builder_validation() {
+        let signer =
+            InMemorySigner::from_seed("alice.near".parse().unwrap(), KeyType::ED25519, "alice");
+        let builder = || {
+            TransactionBuilder::new("alice.near".parse().unwrap(), "alice.near".parse().unwrap())
+        };
+        let complete = || builder().nonce(1).block_hash(hash(b"block"));
+
+        assert_eq!(
+            builder().block_hash(hash(b"block")).transfer(1).sign(&signer).unwrap_err(),
+            TransactionBuilderError::MissingNonce
+        );
+        assert_eq!(
+            builder().nonce(1).transfer(1).sign(&signer).unwrap_err(),
+            TransactionBuilderError::MissingBlockHash
+        );
+        assert_eq!(
+            complete().transfer(1).build().unwrap_err(),
+            TransactionBuilderError::MissingPublicKey
+        );
+        assert_eq!(complete().sign(&signer).unwrap_err(), TransactionBuilderError::NoActions);
+        let beneficiary_id: AccountId = "bob.near".parse().unwrap();
+        assert_eq!(
+            complete()
+                .delete_account(beneficiary_id.clone())
+                .transfer(1)
+                .sign(&signer)
+                .unwrap_err(),
+            TransactionBuilderError::DeleteActionMustBeFinal
+        );
+        assert!(complete().transfer(1).delete_account(beneficiary_id).sign(&signer).is_ok());
+        let other_key = PublicKey::from_seed(KeyType::ED25519, "other");
+        assert_eq!(
+            complete().public_key(other_key.clone()).transfer(1).sign(&signer).unwrap_err(),
+            TransactionBuilderError::SignerKeyMismatch {
+                public_key: other_key,
+                signer_public_key: signer.public_key()
+            }
+        );
+    }
 }
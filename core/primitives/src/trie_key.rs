@@ -405,6 +405,13 @@ pub mod trie_key_parsers {
         res
     }
 
+    /// Prefixes of the account, contract code, access key and contract data keys of all accounts
+    /// whose id starts with `account_id_prefix`, in key order.
+    pub fn get_raw_prefixes_for_account_id_prefix(account_id_prefix: &str) -> [Vec<u8>; 4] {
+        [col::ACCOUNT, col::CONTRACT_CODE, col::ACCESS_KEY, col::CONTRACT_DATA]
+            .map(|column| [column, account_id_prefix.as_bytes()].concat())
+    }
+
     pub fn get_raw_prefix_for_contract_data(account_id: &AccountId, prefix: &[u8]) -> Vec<u8> {
         let mut res = Vec::with_capacity(
             col::CONTRACT_DATA.len()
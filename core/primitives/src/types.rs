@@ -1,4 +1,4 @@
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use derive_more::{AsRef as DeriveAsRef, From as DeriveFrom};
 use serde::{Deserialize, Serialize};
 use std::ops;
@@ -215,11 +215,46 @@ pub type RawStateChanges = std::collections::BTreeMap<Vec<u8>, RawStateChangesWi
 
 #[derive(Debug)]
 pub enum StateChangesRequest {
-    AccountChanges { account_ids: Vec<AccountId> },
-    SingleAccessKeyChanges { keys: Vec<AccountWithPublicKey> },
-    AllAccessKeyChanges { account_ids: Vec<AccountId> },
-    ContractCodeChanges { account_ids: Vec<AccountId> },
-    DataChanges { account_ids: Vec<AccountId>, key_prefix: StoreKey },
+    AccountChanges {
+        account_ids: Vec<AccountId>,
+    },
+    SingleAccessKeyChanges {
+        keys: Vec<AccountWithPublicKey>,
+    },
+    AllAccessKeyChanges {
+        account_ids: Vec<AccountId>,
+    },
+    ContractCodeChanges {
+        account_ids: Vec<AccountId>,
+    },
+    DataChanges {
+        account_ids: Vec<AccountId>,
+        key_prefix: StoreKey,
+    },
+    /// All account, access key, contract code and data changes of the accounts whose id starts
+    /// with `account_id_prefix`, ordered by trie key.  The prefix must not be empty.
+    AccountPrefix {
+        account_id_prefix: String,
+        pagination: Option<StateChangesPagination>,
+    },
+}
+
+/// Limits the changes returned for a `StateChangesRequest::AccountPrefix` request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChangesPagination {
+    /// Trie key to resume from, inclusive.  `StateChangesPage::next_from_key` of the previous
+    /// page.
+    pub from_key: Option<Vec<u8>>,
+    /// Maximum number of changed trie keys, positive; all changes of a key are always in the
+    /// same page.
+    pub limit: usize,
+}
+
+#[derive(Debug)]
+pub struct StateChangesPage {
+    pub changes: StateChanges,
+    /// Set if there are more changes, pass as `StateChangesPagination::from_key` to get them.
+    pub next_from_key: Option<Vec<u8>>,
 }
 
 #[derive(Debug)]
@@ -434,8 +469,8 @@ impl StateRootNode {
 }
 
 /// Epoch identifier -- wrapped hash, to make it easier to distinguish.
-/// EpochId of epoch T is the hash of last block in T-2
-/// EpochId of first two epochs is 0
+/// EpochId of epoch T is the hash of last block in T-2, see `crate::epoch_id`
+/// for the details.
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(
     Debug,
@@ -448,12 +483,26 @@ impl StateRootNode {
     DeriveAsRef,
     BorshSerialize,
     BorshDeserialize,
+    BorshSchema,
     Serialize,
     Deserialize,
 )]
 #[as_ref(forward)]
 pub struct EpochId(pub CryptoHash);
 
+impl EpochId {
+    /// Id of the first epoch, which starts with the genesis block.
+    pub fn genesis() -> Self {
+        EpochId(CryptoHash::default())
+    }
+
+    /// Id of the epoch two epochs after the one whose last block is
+    /// `prev_prev_last_block_hash`.
+    pub fn from_prev_prev_last_block(prev_prev_last_block_hash: &CryptoHash) -> Self {
+        EpochId(*prev_prev_last_block_hash)
+    }
+}
+
 /// Stores validator and its stake for two consecutive epochs.
 /// It is necessary because the blocks on the epoch boundary need to contain approvals from both
 /// epochs.
@@ -470,8 +519,12 @@ pub struct ApprovalStake {
 }
 
 pub mod validator_stake {
+    use std::collections::HashMap;
+
+    use crate::borsh_schema::add_enum_definition;
     use crate::types::ApprovalStake;
-    use borsh::{BorshDeserialize, BorshSerialize};
+    use borsh::schema::{Declaration, Definition};
+    use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
     use near_crypto::PublicKey;
     use near_primitives_core::types::{AccountId, Balance};
     use serde::Serialize;
@@ -488,9 +541,30 @@ pub mod validator_stake {
         V2(ValidatorStakeV2),
     }
 
+    /// Lists the variants enabled in this build.
+    impl BorshSchema for ValidatorStake {
+        fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+            ValidatorStakeV1::add_definitions_recursively(definitions);
+            #[allow(unused_mut)]
+            let mut variants = vec![("V1", vec![ValidatorStakeV1::declaration()])];
+            #[cfg(feature = "protocol_feature_chunk_only_producers")]
+            {
+                ValidatorStakeV2::add_definitions_recursively(definitions);
+                variants.push(("V2", vec![ValidatorStakeV2::declaration()]));
+            }
+            add_enum_definition::<Self>(variants, definitions);
+        }
+
+        fn declaration() -> Declaration {
+            "ValidatorStake".to_string()
+        }
+    }
+
     #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
     #[cfg(feature = "protocol_feature_chunk_only_producers")]
-    #[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+    #[derive(
+        BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, PartialEq, Eq,
+    )]
     pub struct ValidatorStakeV2 {
         /// Account that stakes money.
         pub account_id: AccountId,
@@ -690,7 +764,7 @@ pub mod validator_stake {
 
 /// Stores validator and its stake.
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct ValidatorStakeV1 {
     /// Account that stakes money.
     pub account_id: AccountId,
@@ -1,17 +1,23 @@
+use std::array::TryFromSliceError;
 use std::cmp::max;
 use std::convert::AsRef;
 use std::fmt;
+use std::io;
 
 use chrono;
 use chrono::{DateTime, NaiveDateTime};
 use rand::distributions::Alphanumeric;
-use rand::{thread_rng, Rng};
+use rand::rngs::OsRng;
+use rand::{thread_rng, Rng, RngCore};
 use serde;
 
 use crate::hash::{hash, CryptoHash};
+use crate::logging::{self, NEAR_DECIMALS, TERA_GAS_DECIMALS};
 use crate::receipt::Receipt;
 use crate::transaction::SignedTransaction;
-use crate::types::{CompiledContractCache, NumSeats, NumShards, ShardId};
+use crate::types::{
+    Balance, BlockHeight, CompiledContractCache, Gas, NumSeats, NumShards, ShardId,
+};
 use crate::version::{
     ProtocolVersion, CORRECT_RANDOM_VALUE_PROTOCOL_VERSION, CREATE_HASH_PROTOCOL_VERSION,
     CREATE_RECEIPT_ID_SWITCH_TO_CURRENT_BLOCK_VERSION,
@@ -180,26 +186,52 @@ impl<T: Sized> Deref for MaybeValidated<T> {
     }
 }
 
-pub fn get_block_shard_id(block_hash: &CryptoHash, shard_id: ShardId) -> Vec<u8> {
-    let mut res = Vec::with_capacity(40);
+/// Length of the keys made of a block hash followed by a little-endian `u64`,
+/// e.g. a shard id or a height.
+const BLOCK_HASH_INDEX_KEY_LEN: usize = size_of::<CryptoHash>() + size_of::<u64>();
+
+/// Error decoding a composite store key.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseKeyError {
+    #[error("invalid key length: expected {expected} bytes, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+}
+
+fn get_block_hash_index_key(block_hash: &CryptoHash, index: u64) -> Vec<u8> {
+    let mut res = Vec::with_capacity(BLOCK_HASH_INDEX_KEY_LEN);
     res.extend_from_slice(block_hash.as_ref());
-    res.extend_from_slice(&shard_id.to_le_bytes());
+    res.extend_from_slice(&index_to_bytes(index));
     res
 }
 
-pub fn get_block_shard_id_rev(
-    key: &[u8],
-) -> Result<(CryptoHash, ShardId), Box<dyn std::error::Error + Send + Sync>> {
-    if key.len() != 40 {
-        return Err(
-            std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid key length").into()
-        );
+fn parse_block_hash_index_key(key: &[u8]) -> Result<(CryptoHash, u64), ParseKeyError> {
+    if key.len() != BLOCK_HASH_INDEX_KEY_LEN {
+        return Err(ParseKeyError::InvalidLength {
+            expected: BLOCK_HASH_INDEX_KEY_LEN,
+            actual: key.len(),
+        });
     }
-    let block_hash = CryptoHash::try_from(&key[..32])?;
-    let mut shard_id_arr: [u8; 8] = Default::default();
-    shard_id_arr.copy_from_slice(&key[key.len() - 8..]);
-    let shard_id = ShardId::from_le_bytes(shard_id_arr);
-    Ok((block_hash, shard_id))
+    let (block_hash, index) = key.split_at(size_of::<CryptoHash>());
+    // Neither can fail, the lengths have been checked above.
+    Ok((CryptoHash(block_hash.try_into().unwrap()), bytes_to_index(index).unwrap()))
+}
+
+pub fn get_block_shard_id(block_hash: &CryptoHash, shard_id: ShardId) -> Vec<u8> {
+    get_block_hash_index_key(block_hash, shard_id)
+}
+
+/// Inverse of `get_block_shard_id`.
+pub fn get_block_shard_id_rev(key: &[u8]) -> Result<(CryptoHash, ShardId), ParseKeyError> {
+    parse_block_hash_index_key(key)
+}
+
+pub fn get_block_hash_height(block_hash: &CryptoHash, height: BlockHeight) -> Vec<u8> {
+    get_block_hash_index_key(block_hash, height)
+}
+
+/// Inverse of `get_block_hash_height`.
+pub fn get_block_hash_height_rev(key: &[u8]) -> Result<(CryptoHash, BlockHeight), ParseKeyError> {
+    parse_block_hash_index_key(key)
 }
 
 /// Creates a new Receipt ID from a given signed transaction and a block hash.
@@ -337,6 +369,11 @@ pub fn index_to_bytes(index: u64) -> [u8; 8] {
     index.to_le_bytes()
 }
 
+/// Inverse of `index_to_bytes`, failing if `bytes` aren't exactly 8 bytes long.
+pub fn bytes_to_index(bytes: &[u8]) -> Result<u64, TryFromSliceError> {
+    Ok(u64::from_le_bytes(bytes.try_into()?))
+}
+
 /// A wrapper around Option<T> that provides native Display trait.
 /// Simplifies propagating automatic Display trait on parent structs.
 pub struct DisplayOption<T>(pub Option<T>);
@@ -391,20 +428,54 @@ macro_rules! unwrap_or_return {
     };
 }
 
-/// Converts timestamp in ns into DateTime UTC time.
-pub fn from_timestamp(timestamp: u64) -> DateTime<chrono::Utc> {
-    DateTime::from_utc(
-        NaiveDateTime::from_timestamp(
-            (timestamp / NS_IN_SECOND) as i64,
-            (timestamp % NS_IN_SECOND) as u32,
-        ),
-        chrono::Utc,
+/// Error converting between DateTime UTC time and timestamp in ns.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TimestampError {
+    #[error("{0} is before the Unix epoch")]
+    BeforeEpoch(DateTime<chrono::Utc>),
+    #[error("{0} is too late to be represented in ns as u64")]
+    TooLate(DateTime<chrono::Utc>),
+    #[error("timestamp {0} is out of the DateTime range")]
+    OutOfRange(u64),
+}
+
+/// Converts timestamp in ns into DateTime UTC time, failing if it's out of the
+/// range of DateTime.
+pub fn try_from_timestamp(timestamp: u64) -> Result<DateTime<chrono::Utc>, TimestampError> {
+    NaiveDateTime::from_timestamp_opt(
+        (timestamp / NS_IN_SECOND) as i64,
+        (timestamp % NS_IN_SECOND) as u32,
     )
+    .map(|time| DateTime::from_utc(time, chrono::Utc))
+    .ok_or(TimestampError::OutOfRange(timestamp))
+}
+
+/// Converts timestamp in ns into DateTime UTC time, saturating at the latest
+/// DateTime.  Any u64 timestamp (up to year 2554) is in range though.
+pub fn from_timestamp(timestamp: u64) -> DateTime<chrono::Utc> {
+    try_from_timestamp(timestamp).unwrap_or(chrono::MAX_DATETIME)
+}
+
+/// Converts DateTime UTC time into timestamp in ns, failing for times before
+/// the Unix epoch or after u64::MAX ns.
+pub fn try_to_timestamp(time: DateTime<chrono::Utc>) -> Result<u64, TimestampError> {
+    // In i128, as `timestamp_nanos` overflows i64 (and panics) after year 2262.
+    let nanos = i128::from(time.timestamp()) * i128::from(NS_IN_SECOND)
+        + i128::from(time.timestamp_subsec_nanos());
+    if nanos < 0 {
+        return Err(TimestampError::BeforeEpoch(time));
+    }
+    u64::try_from(nanos).map_err(|_| TimestampError::TooLate(time))
 }
 
-/// Converts DateTime UTC time into timestamp in ns.
+/// Converts DateTime UTC time into timestamp in ns, saturating at 0 for times
+/// before the Unix epoch and at u64::MAX for times too late.
 pub fn to_timestamp(time: DateTime<chrono::Utc>) -> u64 {
-    time.timestamp_nanos() as u64
+    match try_to_timestamp(time) {
+        Ok(timestamp) => timestamp,
+        Err(TimestampError::BeforeEpoch(_)) => 0,
+        Err(_) => u64::MAX,
+    }
 }
 
 /// Compute number of seats per shard for given total number of seats and number of shards.
@@ -422,19 +493,206 @@ pub fn get_num_seats_per_shard(num_shards: NumShards, num_seats: NumSeats) -> Ve
         .collect()
 }
 
-/// Generate random string of given length
+/// Compute number of seats per shard for given total number of seats, split
+/// proportionally to the weights of the shards.  Every shard gets one seat, and
+/// the remaining seats are split using largest remainder rounding, ties going
+/// to the lower shard ids.  Like `get_num_seats_per_shard`, assigns
+/// `max(num_seats, weights.len())` seats in total, and matches it when all
+/// weights are equal.  If all weights are zero they're taken as equal.  With no
+/// shards there are no seats to assign.
+pub fn get_num_seats_per_shard_weighted(weights: &[u64], num_seats: NumSeats) -> Vec<NumSeats> {
+    if weights.is_empty() {
+        return vec![];
+    }
+    let num_shards = weights.len() as NumSeats;
+    let extra_seats = u128::from(num_seats.saturating_sub(num_shards));
+    let total_weight: u128 = weights.iter().map(|&weight| u128::from(weight)).sum();
+    let weight = |shard: usize| {
+        if total_weight == 0 {
+            (1, u128::from(num_shards))
+        } else {
+            (u128::from(weights[shard]), total_weight)
+        }
+    };
+    let mut seats = Vec::with_capacity(weights.len());
+    let mut remainders = Vec::with_capacity(weights.len());
+    for shard in 0..weights.len() {
+        let (weight, total_weight) = weight(shard);
+        // Can't overflow, as both factors fit in u64.
+        let quota = extra_seats * weight;
+        seats.push(1 + (quota / total_weight) as NumSeats);
+        remainders.push((quota % total_weight, shard));
+    }
+    let assigned: NumSeats = seats.iter().sum();
+    let left = (max(num_seats, num_shards) - assigned) as usize;
+    // Stable, so equal remainders keep the shard order.
+    remainders.sort_by(|a, b| b.0.cmp(&a.0));
+    for &(_, shard) in &remainders[..left] {
+        seats[shard] += 1;
+    }
+    seats
+}
+
+/// Generate random alphanumeric string of given length.
+///
+/// Not suitable for secrets, e.g. key material, even in tests and tooling; use
+/// `generate_secure_random_string` or `generate_secure_random_bytes` instead.
 pub fn generate_random_string(len: usize) -> String {
-    thread_rng().sample_iter(&Alphanumeric).take(len).collect::<String>()
+    generate_random_string_from_rng(&mut thread_rng(), len)
 }
 
-pub struct Serializable<'a, T>(&'a T);
+/// Generate random alphanumeric string of given length from `rng`, e.g. a
+/// seeded one so that tests are reproducible.
+pub fn generate_random_string_from_rng<R: Rng + ?Sized>(rng: &mut R, len: usize) -> String {
+    rng.sample_iter(&Alphanumeric).take(len).collect::<String>()
+}
+
+/// Generate random alphanumeric string of given length from the OS's
+/// cryptographically secure source of randomness.
+pub fn generate_secure_random_string(len: usize) -> String {
+    generate_random_string_from_rng(&mut OsRng, len)
+}
+
+/// Generate given number of random bytes from the OS's cryptographically
+/// secure source of randomness.
+pub fn generate_secure_random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Error parsing an amount of NEAR or gas.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseAmountError {
+    #[error("{input:?} is not an amount in {unit}")]
+    InvalidUnit { input: String, unit: &'static str },
+    #[error("{0:?} is not a decimal number")]
+    InvalidNumber(String),
+    #[error("{0:?} is too large")]
+    Overflow(String),
+}
+
+/// Parses a decimal number followed by `unit` into the number times
+/// `10^decimals`.  Digits past the `decimals`-th after the decimal point are
+/// truncated.
+fn parse_decimal(input: &str, decimals: u32, unit: &'static str) -> Result<u128, ParseAmountError> {
+    let number = input
+        .trim()
+        .strip_suffix(unit)
+        .ok_or_else(|| ParseAmountError::InvalidUnit { input: input.to_string(), unit })?
+        .trim_end();
+    let (whole, fraction) = match number.split_once('.') {
+        Some((whole, fraction)) if !fraction.is_empty() => (whole, fraction),
+        Some(_) => return Err(ParseAmountError::InvalidNumber(number.to_string())),
+        None => (number, ""),
+    };
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+        return Err(ParseAmountError::InvalidNumber(number.to_string()));
+    }
+    let overflow = || ParseAmountError::Overflow(input.to_string());
+    // Only digits are left, so parsing can only fail by overflowing.
+    let whole: u128 = whole.parse().map_err(|_| overflow())?;
+    let fraction = &fraction[..fraction.len().min(decimals as usize)];
+    let fraction: u128 =
+        format!("{:0<width$}", fraction, width = decimals as usize).parse().unwrap();
+    whole
+        .checked_mul(10u128.pow(decimals))
+        .and_then(|whole| whole.checked_add(fraction))
+        .ok_or_else(overflow)
+}
+
+/// Balance in NEAR, e.g. `12.345 NEAR`, exact so that `parse_near` gives it
+/// back.
+pub fn format_near(balance: Balance) -> String {
+    format_near_with_precision(balance, NEAR_DECIMALS)
+}
+
+/// Balance in NEAR with at most `precision` digits after the decimal point,
+/// e.g. `12.345 NEAR` for 3.  The rest is truncated.
+pub fn format_near_with_precision(balance: Balance, precision: u32) -> String {
+    format!("{} NEAR", logging::pretty_decimal(balance, NEAR_DECIMALS, precision))
+}
+
+/// Gas amount in Tgas, e.g. `300 Tgas` or `2.5 Tgas`.  The value is exact.
+pub fn format_gas(gas: Gas) -> String {
+    format!("{} Tgas", logging::pretty_decimal(gas.into(), TERA_GAS_DECIMALS, TERA_GAS_DECIMALS))
+}
+
+/// Parses a balance in NEAR, e.g. `1.5 NEAR`, into yoctoNEAR.  Fractions of a
+/// yoctoNEAR are truncated.
+pub fn parse_near(input: &str) -> Result<Balance, ParseAmountError> {
+    parse_decimal(input, NEAR_DECIMALS, "NEAR")
+}
+
+/// Parses a gas amount in Tgas, e.g. `30 Tgas`, into gas.  Fractions of a gas
+/// unit are truncated.
+pub fn parse_gas(input: &str) -> Result<Gas, ParseAmountError> {
+    let gas = parse_decimal(input, TERA_GAS_DECIMALS, "Tgas")?;
+    Gas::try_from(gas).map_err(|_| ParseAmountError::Overflow(input.to_string()))
+}
+
+pub struct Serializable<'a, T> {
+    object: &'a T,
+    pretty: bool,
+    max_len: Option<usize>,
+}
+
+/// Collects what is written to it up to `max_len` bytes, failing the write which
+/// goes past them so that serialization stops there.
+struct TruncatingWriter {
+    buf: Vec<u8>,
+    max_len: usize,
+    truncated: bool,
+}
+
+impl io::Write for TruncatingWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let room = self.max_len - self.buf.len();
+        if data.len() > room {
+            self.buf.extend_from_slice(&data[..room]);
+            self.truncated = true;
+            return Err(io::Error::new(io::ErrorKind::Other, "max length reached"));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
 
 impl<'a, T> fmt::Display for Serializable<'a, T>
 where
     T: serde::Serialize,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", serde_json::to_string(&self.0).unwrap())
+        let mut writer = TruncatingWriter {
+            buf: vec![],
+            max_len: self.max_len.unwrap_or(usize::MAX),
+            truncated: false,
+        };
+        let result = if self.pretty {
+            serde_json::to_writer_pretty(&mut writer, self.object)
+        } else {
+            serde_json::to_writer(&mut writer, self.object)
+        };
+        match result {
+            Err(err) if !writer.truncated => write!(f, "<serialization error: {}>", err),
+            _ => {
+                // The json is valid UTF-8 unless it was cut in the middle of a char.
+                let json = match std::str::from_utf8(&writer.buf) {
+                    Ok(json) => json,
+                    Err(err) => std::str::from_utf8(&writer.buf[..err.valid_up_to()]).unwrap(),
+                };
+                f.write_str(json)?;
+                if writer.truncated {
+                    f.write_str("…")?;
+                }
+                Ok(())
+            }
+        }
     }
 }
 
@@ -447,19 +705,90 @@ impl fmt::Debug for dyn CompiledContractCache {
 /// Wrap an object that implements Serialize into another object
 /// that implements Display. When used display in this object
 /// it shows its json representation. It is used to display complex
-/// objects using tracing.
+/// objects using tracing.  If the object fails to serialize, an error
+/// marker is shown instead.
 ///
 /// tracing::debug!(target: "diagnostic", value=%ser(&object));
 pub fn ser<T>(object: &T) -> Serializable<'_, T>
 where
     T: serde::Serialize,
 {
-    Serializable(object)
+    Serializable { object, pretty: false, max_len: None }
+}
+
+/// Like `ser`, but shows the json representation on multiple lines.
+pub fn ser_pretty<T>(object: &T) -> Serializable<'_, T>
+where
+    T: serde::Serialize,
+{
+    Serializable { object, pretty: true, max_len: None }
+}
+
+/// Like `ser`, but cuts the json representation after `max_len` bytes,
+/// followed by `…`, so that logging large objects doesn't produce huge lines.
+pub fn ser_truncated<T>(object: &T, max_len: usize) -> Serializable<'_, T>
+where
+    T: serde::Serialize,
+{
+    Serializable { object, pretty: false, max_len: Some(max_len) }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logging::{ONE_NEAR, TERA_GAS};
+
+    #[test]
+    fn test_index_to_bytes_round_trip() {
+        for index in [0, 1, 255, 256, u32::MAX as u64, u64::MAX - 1, u64::MAX] {
+            assert_eq!(bytes_to_index(&index_to_bytes(index)).unwrap(), index);
+        }
+        assert_eq!(index_to_bytes(1), [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(bytes_to_index(&[]).is_err());
+        assert!(bytes_to_index(&[0; 7]).is_err());
+        assert!(bytes_to_index(&[0; 9]).is_err());
+    }
+
+    #[test]
+    fn test_block_hash_index_keys() {
+        let block_hash = hash(b"block");
+        for index in [0, 1, 7, u64::MAX] {
+            let key = get_block_shard_id(&block_hash, index);
+            assert_eq!(key.len(), 40);
+            assert_eq!(get_block_shard_id_rev(&key).unwrap(), (block_hash, index));
+            assert_eq!(get_block_hash_height(&block_hash, index), key);
+            assert_eq!(get_block_hash_height_rev(&key).unwrap(), (block_hash, index));
+        }
+        assert_eq!(
+            get_block_shard_id_rev(block_hash.as_ref()),
+            Err(ParseKeyError::InvalidLength { expected: 40, actual: 32 })
+        );
+        assert!(get_block_shard_id_rev(&[]).is_err());
+        assert!(get_block_hash_height_rev(&[0; 41]).is_err());
+    }
+
+    #[test]
+    fn test_timestamps() {
+        for timestamp in [0, 1, NS_IN_SECOND, 1_600_000_000_123_456_789, u64::MAX] {
+            let time = try_from_timestamp(timestamp).unwrap();
+            assert_eq!(time, from_timestamp(timestamp));
+            assert_eq!(try_to_timestamp(time), Ok(timestamp));
+            assert_eq!(to_timestamp(time), timestamp);
+        }
+        assert_eq!(from_timestamp(0).to_rfc3339(), "1970-01-01T00:00:00+00:00");
+        // Past year 2262, where i64 ns overflow.
+        assert_eq!(from_timestamp(u64::MAX).to_rfc3339(), "2554-07-21T23:34:33.709551615+00:00");
+
+        let before_epoch = from_timestamp(0) - chrono::Duration::nanoseconds(1);
+        assert_eq!(try_to_timestamp(before_epoch), Err(TimestampError::BeforeEpoch(before_epoch)));
+        assert_eq!(to_timestamp(before_epoch), 0);
+
+        let too_late = from_timestamp(u64::MAX) + chrono::Duration::nanoseconds(1);
+        assert_eq!(try_to_timestamp(too_late), Err(TimestampError::TooLate(too_late)));
+        assert_eq!(to_timestamp(too_late), u64::MAX);
+        assert_eq!(to_timestamp(chrono::MAX_DATETIME), u64::MAX);
+        assert_eq!(to_timestamp(chrono::MIN_DATETIME), 0);
+    }
 
     #[test]
     fn test_num_chunk_producers() {
@@ -471,6 +800,182 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_num_chunk_producers_weighted() {
+        let weight_sets: Vec<Vec<u64>> = vec![
+            vec![1],
+            vec![0],
+            vec![0, 0, 0],
+            vec![1, 0, 0, 0],
+            vec![0, 5, 0],
+            vec![1, 2, 3, 4],
+            vec![1000, 1, 1],
+            vec![u64::MAX, 1],
+            vec![u64::MAX, u64::MAX, u64::MAX],
+            vec![3, 3, 3, 3, 3, 3, 3],
+        ];
+        for weights in &weight_sets {
+            for num_seats in 0..100 {
+                let assignment = get_num_seats_per_shard_weighted(weights, num_seats);
+                assert_eq!(assignment.len(), weights.len());
+                assert!(assignment.iter().all(|&seats| seats >= 1), "{:?}", assignment);
+                assert_eq!(
+                    assignment.iter().sum::<u64>(),
+                    max(num_seats, weights.len() as u64),
+                    "{:?} {}",
+                    weights,
+                    num_seats
+                );
+                // Shards without weight only get their guaranteed seat.
+                if weights.iter().any(|&weight| weight > 0) {
+                    for (weight, seats) in weights.iter().zip(&assignment) {
+                        if *weight == 0 {
+                            assert_eq!(*seats, 1);
+                        }
+                    }
+                }
+            }
+        }
+        // Equal weights split like the unweighted version.
+        for num_seats in 1..50 {
+            for num_shards in 1..50 {
+                for weight in [0, 1, 7] {
+                    assert_eq!(
+                        get_num_seats_per_shard_weighted(&vec![weight; num_shards], num_seats),
+                        get_num_seats_per_shard(num_shards as NumShards, num_seats)
+                    );
+                }
+            }
+        }
+        assert_eq!(get_num_seats_per_shard_weighted(&[1, 2, 3, 4], 14), vec![2, 3, 4, 5]);
+        assert_eq!(get_num_seats_per_shard_weighted(&[1000, 1, 1], 10), vec![8, 1, 1]);
+        // 3 extra seats split 1:1, the tie goes to the first shard.
+        assert_eq!(get_num_seats_per_shard_weighted(&[1, 1], 5), vec![3, 2]);
+        assert_eq!(get_num_seats_per_shard_weighted(&[], 5), Vec::<NumSeats>::new());
+    }
+
+    #[test]
+    fn test_random_strings() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let is_alphanumeric = |s: &str| s.chars().all(|c| c.is_ascii_alphanumeric());
+        for len in [0, 1, 10, 100] {
+            let s = generate_random_string(len);
+            assert_eq!(s.len(), len);
+            assert!(is_alphanumeric(&s), "{}", s);
+            let s = generate_secure_random_string(len);
+            assert_eq!(s.len(), len);
+            assert!(is_alphanumeric(&s), "{}", s);
+            assert_eq!(generate_secure_random_bytes(len).len(), len);
+        }
+        assert_ne!(generate_secure_random_bytes(32), generate_secure_random_bytes(32));
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let first = generate_random_string_from_rng(&mut rng, 20);
+        let second = generate_random_string_from_rng(&mut rng, 20);
+        assert_ne!(first, second);
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(generate_random_string_from_rng(&mut rng, 20), first);
+        assert_eq!(generate_random_string_from_rng(&mut rng, 20), second);
+        assert!(is_alphanumeric(&first), "{}", first);
+    }
+
+    #[test]
+    fn test_format_near() {
+        assert_eq!(format_near(0), "0 NEAR");
+        assert_eq!(format_near(1), "0.000000000000000000000001 NEAR");
+        assert_eq!(format_near(ONE_NEAR), "1 NEAR");
+        assert_eq!(format_near(12_345 * ONE_NEAR / 1000), "12.345 NEAR");
+        assert_eq!(format_near(Balance::MAX), "340282366920938.463463374607431768211455 NEAR");
+        assert_eq!(format_near_with_precision(12_345_678 * ONE_NEAR / 1_000_000, 3), "12.345 NEAR");
+        assert_eq!(format_near_with_precision(ONE_NEAR + 1, 3), "1 NEAR");
+        assert_eq!(format_near_with_precision(ONE_NEAR / 2, 0), "0 NEAR");
+        assert_eq!(format_near_with_precision(1, 100), "0.000000000000000000000001 NEAR");
+
+        for balance in
+            [0, 1, ONE_NEAR - 1, ONE_NEAR, 3 * ONE_NEAR / 2, Balance::MAX - 1, Balance::MAX]
+        {
+            assert_eq!(parse_near(&format_near(balance)), Ok(balance));
+        }
+        assert_eq!(parse_near("1.5 NEAR"), Ok(3 * ONE_NEAR / 2));
+        assert_eq!(parse_near(" 1.5NEAR "), Ok(3 * ONE_NEAR / 2));
+        assert_eq!(parse_near("0.0000000000000000000000019 NEAR"), Ok(1));
+        assert_eq!(parse_near("0.00000000000000000000000099 NEAR"), Ok(0));
+        assert_eq!(
+            parse_near("340282366920938.463463374607431768211456 NEAR"),
+            Err(ParseAmountError::Overflow(
+                "340282366920938.463463374607431768211456 NEAR".to_string()
+            ))
+        );
+        assert!(matches!(parse_near("1e40 NEAR"), Err(ParseAmountError::InvalidNumber(_))));
+        assert!(matches!(
+            parse_near("999999999999999999999999999999999999999999 NEAR"),
+            Err(ParseAmountError::Overflow(_))
+        ));
+        for invalid in ["NEAR", ".5 NEAR", "1. NEAR", "-1 NEAR", "1.2.3 NEAR", "1 000 NEAR"] {
+            assert!(
+                matches!(parse_near(invalid), Err(ParseAmountError::InvalidNumber(_))),
+                "{}",
+                invalid
+            );
+        }
+        assert!(matches!(parse_near("1.5"), Err(ParseAmountError::InvalidUnit { .. })));
+        assert!(matches!(parse_near("1.5 Tgas"), Err(ParseAmountError::InvalidUnit { .. })));
+    }
+
+    #[test]
+    fn test_format_gas() {
+        assert_eq!(format_gas(0), "0 Tgas");
+        assert_eq!(format_gas(1), "0.000000000001 Tgas");
+        assert_eq!(format_gas(300 * TERA_GAS), "300 Tgas");
+        assert_eq!(format_gas(5 * TERA_GAS / 2), "2.5 Tgas");
+        assert_eq!(format_gas(Gas::MAX), "18446744.073709551615 Tgas");
+
+        for gas in [0, 1, TERA_GAS, 300 * TERA_GAS, Gas::MAX - 1, Gas::MAX] {
+            assert_eq!(parse_gas(&format_gas(gas)), Ok(gas));
+        }
+        assert_eq!(parse_gas("30 Tgas"), Ok(30 * TERA_GAS));
+        assert_eq!(parse_gas("0.0000000000015 Tgas"), Ok(1));
+        assert!(matches!(
+            parse_gas("18446744.073709551616 Tgas"),
+            Err(ParseAmountError::Overflow(_))
+        ));
+        assert!(matches!(parse_gas("thirty Tgas"), Err(ParseAmountError::InvalidNumber(_))));
+        assert!(matches!(parse_gas("30 gas"), Err(ParseAmountError::InvalidUnit { .. })));
+    }
+
+    #[test]
+    fn test_ser() {
+        let object = vec!["a\"b", "c"];
+        assert_eq!(ser(&object).to_string(), r#"["a\"b","c"]"#);
+        assert_eq!(ser_pretty(&object).to_string(), "[\n  \"a\\\"b\",\n  \"c\"\n]");
+        assert_eq!(ser_truncated(&object, 100).to_string(), ser(&object).to_string());
+        assert_eq!(ser_truncated(&object, 12).to_string(), r#"["a\"b","c"]"#);
+        assert_eq!(ser_truncated(&object, 11).to_string(), r#"["a\"b","c"…"#);
+        assert_eq!(ser_truncated(&object, 4).to_string(), r#"["a\…"#);
+        assert_eq!(ser_truncated(&object, 0).to_string(), "…");
+        // Cut at a char boundary, `П` takes bytes 1 and 2.
+        assert_eq!(ser_truncated(&"Привет", 2).to_string(), "\"…");
+        assert_eq!(ser_truncated(&"Привет", 3).to_string(), "\"П…");
+
+        // Maps with non-string keys can't be serialized to json.
+        let map: std::collections::BTreeMap<Vec<u8>, u8> = [(vec![1], 2)].into_iter().collect();
+        assert_eq!(ser(&map).to_string(), "<serialization error: key must be a string>");
+        assert!(ser_truncated(&map, 1).to_string().starts_with("<serialization error"));
+
+        // Serialization stops once the json is too long.
+        struct Counted<'a>(&'a std::cell::Cell<usize>);
+        impl serde::Serialize for Counted<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq((0..1000).inspect(|_| self.0.set(self.0.get() + 1)))
+            }
+        }
+        let count = std::cell::Cell::new(0);
+        assert_eq!(ser_truncated(&Counted(&count), 10).to_string(), "[0,1,2,3,4…");
+        assert!(count.get() < 10, "{}", count.get());
+    }
+
     #[test]
     fn test_create_hash_upgradable() {
         let base = hash(b"atata");
@@ -0,0 +1,645 @@
+//! Stateless checks of transactions, their actions and chunk headers.
+//!
+//! The runtime applies the transaction checks when validating transactions
+//! and receipts, and the RPC before submitting a transaction, so both reject
+//! the same malformed transactions. The chunk header checks are shared by the
+//! chain and the chunks code handling headers received from peers.
+use crate::account::AccessKeyPermission;
+use crate::config::VMLimitConfig;
+use crate::errors::{ActionsValidationError, ChunkValidationError, InvalidTxError};
+use crate::safe_math::safe_add_gas;
+use crate::sharding::{ShardChunkHeader, ShardChunkHeaderInner};
+use crate::transaction::{
+    Action, AddKeyAction, DeployContractAction, FunctionCallAction, SignedTransaction, StakeAction,
+};
+use crate::types::{Gas, NumShards};
+
+/// Limits on the size and complexity of transactions and action receipts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionLimits {
+    /// Max borsh-serialized size of the transaction.
+    pub max_transaction_size: u64,
+    pub max_actions_per_receipt: u64,
+    pub max_total_prepaid_gas: Gas,
+    pub max_contract_size: u64,
+    /// Applies to function calls and to the methods allowed by access keys.
+    pub max_length_method_name: u64,
+    pub max_arguments_length: u64,
+    /// Max total length of the methods allowed by an access key, counting a
+    /// terminating byte for each of them.
+    pub max_number_bytes_method_names: u64,
+}
+
+impl From<&VMLimitConfig> for TransactionLimits {
+    fn from(limit_config: &VMLimitConfig) -> Self {
+        Self {
+            max_transaction_size: limit_config.max_transaction_size,
+            max_actions_per_receipt: limit_config.max_actions_per_receipt,
+            max_total_prepaid_gas: limit_config.max_total_prepaid_gas,
+            max_contract_size: limit_config.max_contract_size,
+            max_length_method_name: limit_config.max_length_method_name,
+            max_arguments_length: limit_config.max_arguments_length,
+            max_number_bytes_method_names: limit_config.max_number_bytes_method_names,
+        }
+    }
+}
+
+/// Checks the size of the transaction and validates its actions, see
+/// `validate_actions`.
+///
+/// Doesn't verify the signature, nor the checks which depend on the protocol
+/// version.
+pub fn validate_transaction_well_formed(
+    signed_transaction: &SignedTransaction,
+    limits: &TransactionLimits,
+) -> Result<(), InvalidTxError> {
+    let transaction_size = signed_transaction.get_size();
+    if transaction_size > limits.max_transaction_size {
+        return Err(InvalidTxError::TransactionSizeExceeded {
+            size: transaction_size,
+            limit: limits.max_transaction_size,
+        });
+    }
+    validate_actions(limits, &signed_transaction.transaction.actions)
+        .map_err(InvalidTxError::ActionsValidation)
+}
+
+/// Validates given actions:
+///
+/// - Checks that the total number of actions doesn't exceed the limit.
+/// - Checks that `DeleteAccount` is the last action.
+/// - Validates each individual action.
+/// - Checks that the total prepaid gas doesn't exceed the limit.
+pub fn validate_actions(
+    limits: &TransactionLimits,
+    actions: &[Action],
+) -> Result<(), ActionsValidationError> {
+    if actions.len() as u64 > limits.max_actions_per_receipt {
+        return Err(ActionsValidationError::TotalNumberOfActionsExceeded {
+            total_number_of_actions: actions.len() as u64,
+            limit: limits.max_actions_per_receipt,
+        });
+    }
+
+    let mut iter = actions.iter().peekable();
+    while let Some(action) = iter.next() {
+        if let Action::DeleteAccount(_) = action {
+            if iter.peek().is_some() {
+                return Err(ActionsValidationError::DeleteActionMustBeFinal);
+            }
+        }
+        validate_action(limits, action)?;
+    }
+
+    let total_prepaid_gas = actions
+        .iter()
+        .try_fold(0, |acc, action| safe_add_gas(acc, action.get_prepaid_gas()))
+        .map_err(|_| ActionsValidationError::IntegerOverflow)?;
+    if total_prepaid_gas > limits.max_total_prepaid_gas {
+        return Err(ActionsValidationError::TotalPrepaidGasExceeded {
+            total_prepaid_gas,
+            limit: limits.max_total_prepaid_gas,
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates a single given action. Checks limits if applicable.
+pub fn validate_action(
+    limits: &TransactionLimits,
+    action: &Action,
+) -> Result<(), ActionsValidationError> {
+    match action {
+        Action::CreateAccount(_) => Ok(()),
+        Action::DeployContract(a) => validate_deploy_contract_action(limits, a),
+        Action::FunctionCall(a) => validate_function_call_action(limits, a),
+        Action::Transfer(_) => Ok(()),
+        Action::Stake(a) => validate_stake_action(a),
+        #[cfg(feature = "protocol_feature_chunk_only_producers")]
+        Action::StakeChunkOnly(a) => validate_stake_action(a),
+        Action::AddKey(a) => validate_add_key_action(limits, a),
+        Action::DeleteKey(_) => Ok(()),
+        Action::DeleteAccount(_) => Ok(()),
+    }
+}
+
+/// Validates `DeployContractAction`. Checks that the given contract size doesn't exceed the limit.
+fn validate_deploy_contract_action(
+    limits: &TransactionLimits,
+    action: &DeployContractAction,
+) -> Result<(), ActionsValidationError> {
+    if action.code.len() as u64 > limits.max_contract_size {
+        return Err(ActionsValidationError::ContractSizeExceeded {
+            size: action.code.len() as u64,
+            limit: limits.max_contract_size,
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates `FunctionCallAction`. Checks that the method name length doesn't exceed the limit and
+/// the length of the arguments doesn't exceed the limit.
+fn validate_function_call_action(
+    limits: &TransactionLimits,
+    action: &FunctionCallAction,
+) -> Result<(), ActionsValidationError> {
+    if action.gas == 0 {
+        return Err(ActionsValidationError::FunctionCallZeroAttachedGas);
+    }
+
+    if action.method_name.len() as u64 > limits.max_length_method_name {
+        return Err(ActionsValidationError::FunctionCallMethodNameLengthExceeded {
+            length: action.method_name.len() as u64,
+            limit: limits.max_length_method_name,
+        });
+    }
+
+    if action.args.len() as u64 > limits.max_arguments_length {
+        return Err(ActionsValidationError::FunctionCallArgumentsLengthExceeded {
+            length: action.args.len() as u64,
+            limit: limits.max_arguments_length,
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates `StakeAction`. Checks that the `public_key` is a valid staking key.
+/// The checks against the staking account are done when applying it, see
+/// `StakeAction::validate_against`.
+fn validate_stake_action(action: &StakeAction) -> Result<(), ActionsValidationError> {
+    action.validate_staking_key().map_err(|_| ActionsValidationError::UnsuitableStakingKey {
+        public_key: action.public_key.clone(),
+    })
+}
+
+/// Validates `AddKeyAction`. If the access key permission is `FunctionCall`, checks that the
+/// total number of bytes of the method names doesn't exceed the limit and
+/// every method name length doesn't exceed the limit.
+fn validate_add_key_action(
+    limits: &TransactionLimits,
+    action: &AddKeyAction,
+) -> Result<(), ActionsValidationError> {
+    if let AccessKeyPermission::FunctionCall(fc) = &action.access_key.permission {
+        // Checking method name length limits
+        let mut total_number_of_bytes = 0;
+        for method_name in &fc.method_names {
+            let length = method_name.len() as u64;
+            if length > limits.max_length_method_name {
+                return Err(ActionsValidationError::AddKeyMethodNameLengthExceeded {
+                    length,
+                    limit: limits.max_length_method_name,
+                });
+            }
+            // Adding terminating character to the total number of bytes
+            total_number_of_bytes += length + 1;
+        }
+        if total_number_of_bytes > limits.max_number_bytes_method_names {
+            return Err(ActionsValidationError::AddKeyMethodNamesNumberOfBytesExceeded {
+                total_number_of_bytes,
+                limit: limits.max_number_bytes_method_names,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Gas limit cannot be adjusted for more than 0.1% at a time.
+const GAS_LIMIT_ADJUSTMENT_FACTOR: u64 = 1000;
+
+/// Length of the borsh-serialized empty transactions and receipts lists,
+/// which is the smallest body a chunk can have.
+const MIN_CHUNK_ENCODED_LENGTH: u64 = 8;
+
+/// Returns the range the gas limit of a chunk has to be in, given the gas
+/// limit of the previous chunk in the shard.
+pub fn gas_limit_bounds(prev_gas_limit: Gas) -> (Gas, Gas) {
+    let max_adjustment = prev_gas_limit / GAS_LIMIT_ADJUSTMENT_FACTOR;
+    (prev_gas_limit - max_adjustment, prev_gas_limit.saturating_add(max_adjustment))
+}
+
+/// Validates the chunk header without looking at the chain:
+///
+/// - Checks that the hash matches the one computed from the inner header.
+/// - Checks that a V3 header carries a V2 inner header.
+/// - Checks that the shard id is less than `expected_shards`.
+/// - Checks that the chunk isn't included below the height it was created at.
+/// - Checks that the encoded length fits at least an empty chunk body.
+/// - Checks that the gas limit is within `gas_limit_bounds` (inclusive).
+///
+/// Doesn't verify the signature, nor anything depending on the previous
+/// block or chunk.
+pub fn validate_chunk_header_basic(
+    header: &ShardChunkHeader,
+    expected_shards: NumShards,
+    gas_limit_bounds: (Gas, Gas),
+) -> Result<(), ChunkValidationError> {
+    let chunk_hash = header.chunk_hash();
+    let computed_hash = header.compute_hash();
+    if chunk_hash != computed_hash {
+        return Err(ChunkValidationError::InvalidHash { chunk_hash, computed_hash });
+    }
+
+    if let ShardChunkHeader::V3(header) = header {
+        if let ShardChunkHeaderInner::V1(_) = header.inner {
+            return Err(ChunkValidationError::InnerHeaderVersionMismatch);
+        }
+    }
+
+    let shard_id = header.shard_id();
+    if shard_id >= expected_shards {
+        return Err(ChunkValidationError::InvalidShardId { shard_id, num_shards: expected_shards });
+    }
+
+    // Headers which aren't included in a block yet have zero height included.
+    let height_created = header.height_created();
+    let height_included = header.height_included();
+    if height_included != 0 && height_included < height_created {
+        return Err(ChunkValidationError::InvalidHeightIncluded {
+            height_created,
+            height_included,
+        });
+    }
+
+    let encoded_length = header.encoded_length();
+    if encoded_length < MIN_CHUNK_ENCODED_LENGTH {
+        return Err(ChunkValidationError::InvalidEncodedLength {
+            encoded_length,
+            min: MIN_CHUNK_ENCODED_LENGTH,
+        });
+    }
+
+    let gas_limit = header.gas_limit();
+    let (min, max) = gas_limit_bounds;
+    if gas_limit < min || gas_limit > max {
+        return Err(ChunkValidationError::GasLimitOutOfBounds { gas_limit, min, max });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use near_crypto::{InMemorySigner, KeyType, PublicKey};
+
+    use super::*;
+    use crate::account::{AccessKey, FunctionCallPermission};
+    use crate::hash::{hash, CryptoHash};
+    use crate::sharding::{ShardChunkHeaderV1, ShardChunkHeaderV2, ShardChunkHeaderV3};
+    use crate::transaction::{CreateAccountAction, DeleteAccountAction, TransactionBuilder};
+    use crate::validator_signer::InMemoryValidatorSigner;
+
+    fn limits() -> TransactionLimits {
+        TransactionLimits {
+            max_transaction_size: 10_000,
+            max_actions_per_receipt: 4,
+            max_total_prepaid_gas: 100,
+            max_contract_size: 1000,
+            max_length_method_name: 10,
+            max_arguments_length: 100,
+            max_number_bytes_method_names: 20,
+        }
+    }
+
+    fn sign(actions: Vec<Action>) -> SignedTransaction {
+        let signer = InMemorySigner::from_seed("alice".parse().unwrap(), KeyType::ED25519, "alice");
+        TransactionBuilder::new("alice".parse().unwrap(), "bob".parse().unwrap())
+            .nonce(1)
+            .block_hash(CryptoHash::default())
+            .actions(actions)
+            .sign(&signer)
+            .unwrap()
+    }
+
+    fn function_call(method_name: &str, args_len: usize, gas: Gas) -> Action {
+        Action::FunctionCall(FunctionCallAction {
+            method_name: method_name.to_string(),
+            args: vec![0; args_len],
+            gas,
+            deposit: 0,
+        })
+    }
+
+    fn add_key(method_names: Vec<String>) -> Action {
+        Action::AddKey(AddKeyAction {
+            public_key: InMemorySigner::from_seed("bob".parse().unwrap(), KeyType::ED25519, "bob")
+                .public_key,
+            access_key: AccessKey {
+                nonce: 0,
+                permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                    allowance: None,
+                    receiver_id: "bob".to_string(),
+                    method_names,
+                }),
+            },
+        })
+    }
+
+    /// Checks that `make(limit)` is accepted while `make(limit + 1)` fails
+    /// with `err(limit + 1)`.
+    fn check_boundary(
+        limit: u64,
+        make: impl Fn(u64) -> Vec<Action>,
+        err: impl Fn(u64) -> ActionsValidationError,
+    ) {
+        for value in [limit - 1, limit] {
+            let tx = sign(make(value));
+            assert_eq!(validate_transaction_well_formed(&tx, &limits()), Ok(()), "{}", value);
+        }
+        let tx = sign(make(limit + 1));
+        assert_eq!(
+            validate_transaction_well_formed(&tx, &limits()),
+            Err(InvalidTxError::ActionsValidation(err(limit + 1)))
+        );
+    }
+
+    #[test]
+    fn test_transaction_size_limit() {
+        let tx = sign(vec![Action::DeployContract(DeployContractAction { code: vec![1; 500] })]);
+        let size = tx.get_size();
+        let mut limits = limits();
+        for limit in [size, size + 1] {
+            limits.max_transaction_size = limit;
+            assert_eq!(validate_transaction_well_formed(&tx, &limits), Ok(()));
+        }
+        limits.max_transaction_size = size - 1;
+        assert_eq!(
+            validate_transaction_well_formed(&tx, &limits),
+            Err(InvalidTxError::TransactionSizeExceeded { size, limit: size - 1 })
+        );
+    }
+
+    #[test]
+    fn test_action_limits() {
+        let limits = limits();
+        check_boundary(
+            limits.max_actions_per_receipt,
+            |n| vec![Action::CreateAccount(CreateAccountAction {}); n as usize],
+            |n| ActionsValidationError::TotalNumberOfActionsExceeded {
+                total_number_of_actions: n,
+                limit: limits.max_actions_per_receipt,
+            },
+        );
+        check_boundary(
+            limits.max_total_prepaid_gas,
+            |gas| vec![function_call("a", 0, 1), function_call("b", 0, gas - 1)],
+            |gas| ActionsValidationError::TotalPrepaidGasExceeded {
+                total_prepaid_gas: gas,
+                limit: limits.max_total_prepaid_gas,
+            },
+        );
+        check_boundary(
+            limits.max_contract_size,
+            |size| {
+                vec![Action::DeployContract(DeployContractAction { code: vec![1; size as usize] })]
+            },
+            |size| ActionsValidationError::ContractSizeExceeded {
+                size,
+                limit: limits.max_contract_size,
+            },
+        );
+        check_boundary(
+            limits.max_length_method_name,
+            |len| vec![function_call(&"a".repeat(len as usize), 0, 1)],
+            |length| ActionsValidationError::FunctionCallMethodNameLengthExceeded {
+                length,
+                limit: limits.max_length_method_name,
+            },
+        );
+        check_boundary(
+            limits.max_arguments_length,
+            |len| vec![function_call("a", len as usize, 1)],
+            |length| ActionsValidationError::FunctionCallArgumentsLengthExceeded {
+                length,
+                limit: limits.max_arguments_length,
+            },
+        );
+        check_boundary(
+            limits.max_length_method_name,
+            |len| vec![add_key(vec!["a".repeat(len as usize)])],
+            |length| ActionsValidationError::AddKeyMethodNameLengthExceeded {
+                length,
+                limit: limits.max_length_method_name,
+            },
+        );
+        // Two names which take `len` bytes with their terminating bytes.
+        check_boundary(
+            limits.max_number_bytes_method_names,
+            |len| {
+                let first = "a".repeat((len / 2 - 1) as usize);
+                let second = "b".repeat((len - len / 2 - 1) as usize);
+                vec![add_key(vec![first, second])]
+            },
+            |total_number_of_bytes| {
+                ActionsValidationError::AddKeyMethodNamesNumberOfBytesExceeded {
+                    total_number_of_bytes,
+                    limit: limits.max_number_bytes_method_names,
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn test_action_validity() {
+        let limits = limits();
+        let delete_account =
+            Action::DeleteAccount(DeleteAccountAction { beneficiary_id: "carol".parse().unwrap() });
+        assert_eq!(
+            validate_actions(&limits, &[delete_account.clone(), function_call("a", 0, 1)]),
+            Err(ActionsValidationError::DeleteActionMustBeFinal)
+        );
+        validate_actions(&limits, &[function_call("a", 0, 1), delete_account]).unwrap();
+        assert_eq!(
+            validate_actions(&limits, &[function_call("a", 0, 0)]),
+            Err(ActionsValidationError::FunctionCallZeroAttachedGas)
+        );
+        assert_eq!(
+            validate_actions(&limits, &[function_call("a", 0, Gas::MAX), function_call("a", 0, 1)]),
+            Err(ActionsValidationError::IntegerOverflow)
+        );
+        let public_key = PublicKey::empty(KeyType::ED25519);
+        let stake = Action::Stake(StakeAction { stake: 1, public_key: public_key.clone() });
+        assert_eq!(
+            validate_actions(&limits, &[stake]),
+            Err(ActionsValidationError::UnsuitableStakingKey { public_key })
+        );
+    }
+
+    const GAS_LIMIT: Gas = 1_000_000_000_000_000;
+
+    /// Creates a header of the given version the way a chunk producer would,
+    /// and passes it through borsh as if it was received from a peer.
+    // TODO: Also test headers captured from mainnet, one per version, e.g. by
+    // converting the `chunk` RPC views to `ShardChunkHeader` and borsh-encoding
+    // them into fixture files.  They couldn't be fetched where these tests were
+    // written.
+    fn chunk_header(version: u8, shard_id: u64, encoded_length: u64) -> ShardChunkHeader {
+        let signer = InMemoryValidatorSigner::from_seed(
+            "test.near".parse().unwrap(),
+            KeyType::ED25519,
+            "test.near",
+        );
+        let prev_block_hash = hash(b"prev_block");
+        let prev_state_root = hash(b"prev_state_root");
+        let outcome_root = hash(b"outcome_root");
+        let encoded_merkle_root = hash(b"encoded_merkle_root");
+        let outgoing_receipts_root = hash(b"outgoing_receipts_root");
+        let tx_root = hash(b"tx_root");
+        let header = match version {
+            1 => ShardChunkHeader::V1(ShardChunkHeaderV1::new(
+                prev_block_hash,
+                prev_state_root,
+                outcome_root,
+                encoded_merkle_root,
+                encoded_length,
+                10,
+                shard_id,
+                1_000,
+                GAS_LIMIT,
+                100,
+                outgoing_receipts_root,
+                tx_root,
+                vec![],
+                &signer,
+            )),
+            2 => ShardChunkHeader::V2(ShardChunkHeaderV2::new(
+                prev_block_hash,
+                prev_state_root,
+                outcome_root,
+                encoded_merkle_root,
+                encoded_length,
+                10,
+                shard_id,
+                1_000,
+                GAS_LIMIT,
+                100,
+                outgoing_receipts_root,
+                tx_root,
+                vec![],
+                &signer,
+            )),
+            _ => ShardChunkHeader::V3(ShardChunkHeaderV3::new(
+                prev_block_hash,
+                prev_state_root,
+                outcome_root,
+                encoded_merkle_root,
+                encoded_length,
+                10,
+                shard_id,
+                1_000,
+                GAS_LIMIT,
+                100,
+                outgoing_receipts_root,
+                tx_root,
+                vec![],
+                &signer,
+            )),
+        };
+        ShardChunkHeader::try_from_slice(&header.try_to_vec().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_gas_limit_bounds() {
+        assert_eq!(
+            gas_limit_bounds(GAS_LIMIT),
+            (GAS_LIMIT - GAS_LIMIT / 1000, GAS_LIMIT + GAS_LIMIT / 1000)
+        );
+        assert_eq!(gas_limit_bounds(999), (999, 999));
+        assert_eq!(gas_limit_bounds(Gas::MAX).1, Gas::MAX);
+    }
+
+    #[test]
+    fn test_valid_chunk_headers() {
+        for version in 1..=3 {
+            let mut header = chunk_header(version, 3, 8);
+            // The bounds the chain checks a chunk included in a block against, the
+            // gas limit of the previous chunk having moved either way.
+            for prev_gas_limit in [GAS_LIMIT, GAS_LIMIT - GAS_LIMIT / 1001, GAS_LIMIT + 999] {
+                validate_chunk_header_basic(&header, 4, gas_limit_bounds(prev_gas_limit)).unwrap();
+            }
+            // The bounds the chunks manager checks a header from a peer against.
+            validate_chunk_header_basic(&header, 4, (0, Gas::MAX)).unwrap();
+            validate_chunk_header_basic(&header, 4, (GAS_LIMIT, GAS_LIMIT)).unwrap();
+            *header.height_included_mut() = 10;
+            validate_chunk_header_basic(&header, 4, gas_limit_bounds(GAS_LIMIT)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_corrupted_chunk_headers() {
+        let bounds = gas_limit_bounds(GAS_LIMIT);
+        for version in 1..=3 {
+            let mut header = chunk_header(version, 0, 100);
+            let chunk_hash = header.chunk_hash();
+            match &mut header {
+                ShardChunkHeader::V1(header) => header.inner.gas_used += 1,
+                ShardChunkHeader::V2(header) => header.inner.gas_used += 1,
+                ShardChunkHeader::V3(header) => match &mut header.inner {
+                    ShardChunkHeaderInner::V1(inner) => inner.gas_used += 1,
+                    ShardChunkHeaderInner::V2(inner) => inner.gas_used += 1,
+                },
+            }
+            assert_eq!(
+                validate_chunk_header_basic(&header, 1, bounds),
+                Err(ChunkValidationError::InvalidHash {
+                    chunk_hash,
+                    computed_hash: header.compute_hash()
+                })
+            );
+
+            assert_eq!(
+                validate_chunk_header_basic(&chunk_header(version, 4, 100), 4, bounds),
+                Err(ChunkValidationError::InvalidShardId { shard_id: 4, num_shards: 4 })
+            );
+
+            let mut header = chunk_header(version, 0, 100);
+            *header.height_included_mut() = 9;
+            assert_eq!(
+                validate_chunk_header_basic(&header, 1, bounds),
+                Err(ChunkValidationError::InvalidHeightIncluded {
+                    height_created: 10,
+                    height_included: 9
+                })
+            );
+
+            assert_eq!(
+                validate_chunk_header_basic(&chunk_header(version, 0, 7), 1, bounds),
+                Err(ChunkValidationError::InvalidEncodedLength { encoded_length: 7, min: 8 })
+            );
+
+            let header = chunk_header(version, 0, 100);
+            for (min, max) in [(GAS_LIMIT + 1, Gas::MAX), (0, GAS_LIMIT - 1)] {
+                assert_eq!(
+                    validate_chunk_header_basic(&header, 1, (min, max)),
+                    Err(ChunkValidationError::GasLimitOutOfBounds {
+                        gas_limit: GAS_LIMIT,
+                        min,
+                        max
+                    })
+                );
+            }
+        }
+
+        // A V3 header has to carry the V2 inner header even though the hash of
+        // the V1 one can be computed the same way.
+        let inner = match chunk_header(1, 0, 100) {
+            ShardChunkHeader::V1(header) => header.inner,
+            _ => unreachable!(),
+        };
+        let mut header = ShardChunkHeaderV3 {
+            inner: ShardChunkHeaderInner::V1(inner),
+            height_included: 0,
+            signature: Default::default(),
+            hash: Default::default(),
+        };
+        header.init();
+        assert_eq!(
+            validate_chunk_header_basic(&ShardChunkHeader::V3(header), 1, bounds),
+            Err(ChunkValidationError::InnerHeaderVersionMismatch)
+        );
+    }
+}
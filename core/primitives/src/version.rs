@@ -17,7 +17,7 @@ pub struct Version {
 pub type DbVersion = u32;
 
 /// Current version of the database.
-pub const DB_VERSION: DbVersion = 31;
+pub const DB_VERSION: DbVersion = 32;
 
 use crate::upgrade_schedule::{get_protocol_version_internal, ProtocolUpgradeVotingSchedule};
 /// Protocol version type.
@@ -25,34 +25,44 @@ pub use near_primitives_core::types::ProtocolVersion;
 
 /// Minimum gas price proposed in NEP 92 and the associated protocol version
 pub const MIN_GAS_PRICE_NEP_92: Balance = 1_000_000_000;
-pub const MIN_PROTOCOL_VERSION_NEP_92: ProtocolVersion = 31;
+pub const MIN_PROTOCOL_VERSION_NEP_92: ProtocolVersion =
+    ProtocolFeature::MinGasPriceNep92.protocol_version();
 
 /// Minimum gas price proposed in NEP 92 (fixed) and the associated protocol version
 pub const MIN_GAS_PRICE_NEP_92_FIX: Balance = 100_000_000;
-pub const MIN_PROTOCOL_VERSION_NEP_92_FIX: ProtocolVersion = 32;
+pub const MIN_PROTOCOL_VERSION_NEP_92_FIX: ProtocolVersion =
+    ProtocolFeature::MinGasPriceNep92Fix.protocol_version();
 
-pub const CORRECT_RANDOM_VALUE_PROTOCOL_VERSION: ProtocolVersion = 33;
+pub const CORRECT_RANDOM_VALUE_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolFeature::CorrectRandomValue.protocol_version();
 
 /// See [NEP 71](https://github.com/nearprotocol/NEPs/pull/71)
-pub const IMPLICIT_ACCOUNT_CREATION_PROTOCOL_VERSION: ProtocolVersion = 35;
+pub const IMPLICIT_ACCOUNT_CREATION_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolFeature::ImplicitAccountCreation.protocol_version();
 
 /// The protocol version that enables reward on mainnet.
-pub const ENABLE_INFLATION_PROTOCOL_VERSION: ProtocolVersion = 36;
+pub const ENABLE_INFLATION_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolFeature::EnableInflation.protocol_version();
 
 /// Fix upgrade to use the latest voted protocol version instead of the current epoch protocol
 /// version when there is no new change in protocol version.
-pub const UPGRADABILITY_FIX_PROTOCOL_VERSION: ProtocolVersion = 37;
+pub const UPGRADABILITY_FIX_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolFeature::UpgradabilityFix.protocol_version();
 
 /// Updates the way receipt ID, data ID and random seeds are constructed.
-pub const CREATE_HASH_PROTOCOL_VERSION: ProtocolVersion = 38;
+pub const CREATE_HASH_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolFeature::CreateHash.protocol_version();
 
 /// Fix the storage usage of the delete key action.
-pub const DELETE_KEY_STORAGE_USAGE_PROTOCOL_VERSION: ProtocolVersion = 40;
+pub const DELETE_KEY_STORAGE_USAGE_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolFeature::DeleteKeyStorageUsage.protocol_version();
 
-pub const SHARD_CHUNK_HEADER_UPGRADE_VERSION: ProtocolVersion = 41;
+pub const SHARD_CHUNK_HEADER_UPGRADE_VERSION: ProtocolVersion =
+    ProtocolFeature::ShardChunkHeaderUpgrade.protocol_version();
 
 /// Updates the way receipt ID is constructed to use current block hash instead of last block hash
-pub const CREATE_RECEIPT_ID_SWITCH_TO_CURRENT_BLOCK_VERSION: ProtocolVersion = 42;
+pub const CREATE_RECEIPT_ID_SWITCH_TO_CURRENT_BLOCK_VERSION: ProtocolVersion =
+    ProtocolFeature::CreateReceiptIdSwitchToCurrentBlock.protocol_version();
 
 pub struct ProtocolVersionRange {
     lower: ProtocolVersion,
@@ -70,7 +80,7 @@ impl ProtocolVersionRange {
 }
 
 pub fn is_implicit_account_creation_enabled(protocol_version: ProtocolVersion) -> bool {
-    protocol_version >= IMPLICIT_ACCOUNT_CREATION_PROTOCOL_VERSION
+    ProtocolFeature::ImplicitAccountCreation.enabled(protocol_version)
 }
 
 /// New Protocol features should go here. Features are guarded by their corresponding feature flag.
@@ -80,8 +90,31 @@ pub fn is_implicit_account_creation_enabled(protocol_version: ProtocolVersion) -
 /// #[cfg(feature = "protocol_feature_evm")]
 /// EVM code
 ///
-#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize, strum::EnumIter)]
 pub enum ProtocolFeature {
+    // features predating the enum, also available as `*_VERSION` constants
+    /// Minimum gas price proposed in NEP 92.
+    MinGasPriceNep92,
+    /// Minimum gas price proposed in NEP 92 (fixed).
+    MinGasPriceNep92Fix,
+    CorrectRandomValue,
+    /// See [NEP 71](https://github.com/nearprotocol/NEPs/pull/71)
+    ImplicitAccountCreation,
+    /// Enables reward on mainnet.
+    EnableInflation,
+    /// Fix upgrade to use the latest voted protocol version instead of the current epoch protocol
+    /// version when there is no new change in protocol version.
+    UpgradabilityFix,
+    /// Updates the way receipt ID, data ID and random seeds are constructed.
+    CreateHash,
+    /// Fix the storage usage of the delete key action.
+    DeleteKeyStorageUsage,
+    ShardChunkHeaderUpgrade,
+    /// Updates the way receipt ID is constructed to use current block hash instead of last block
+    /// hash.
+    CreateReceiptIdSwitchToCurrentBlock,
+
     // stable features
     ForwardChunkParts,
     RectifyInflation,
@@ -162,6 +195,38 @@ pub enum ProtocolFeature {
     /// alpha is min stake ratio
     #[cfg(feature = "protocol_feature_fix_staking_threshold")]
     FixStakingThreshold,
+    /// Reject transactions adding SECP256K1 access keys which are not valid
+    /// curve points, instead of failing later at signature verification.
+    #[cfg(feature = "protocol_feature_reject_off_curve_keys")]
+    RejectOffCurveKeys,
+    /// Allow transactions to carry a priority fee, which is burnt on top of
+    /// the gas cost and makes chunk producers include them first.
+    #[cfg(feature = "protocol_feature_priority_fee")]
+    PriorityFee,
+    /// Reject deleting an account with itself as the beneficiary, which burns
+    /// its balance.
+    #[cfg(feature = "protocol_feature_reject_self_beneficiary")]
+    RejectSelfBeneficiary,
+    /// Record the gas of each action of a receipt in its execution outcome.
+    #[cfg(feature = "protocol_feature_action_gas_breakdown")]
+    ActionGasBreakdown,
+    /// Action receipts emitted by contracts carry the gas burnt upstream and a
+    /// congestion priority, see `ReceiptCongestion`.
+    #[cfg(feature = "protocol_feature_receipt_congestion_metadata")]
+    ReceiptCongestionMetadata,
+    /// Fail the function call appending a `CreateAccount` action to a receipt
+    /// for an implicit account, instead of the receipt failing later.
+    #[cfg(feature = "protocol_feature_reject_create_implicit_account")]
+    RejectCreateImplicitAccount,
+    /// Fail a stake action with an unsuitable staking key with an action
+    /// error, instead of proposing the key.
+    #[cfg(feature = "protocol_feature_staking_key_action_error")]
+    StakingKeyActionError,
+    /// Fail the function call appending an action which breaks one of the
+    /// limits on receipts, instead of the receipts failing validation once
+    /// the contract returns.
+    #[cfg(feature = "protocol_feature_host_receipt_limits")]
+    HostReceiptLimits,
 }
 
 /// Both, outgoing and incoming tcp connections to peers, will be rejected if `peer's`
@@ -178,7 +243,11 @@ const STABLE_PROTOCOL_VERSION: ProtocolVersion = 54;
 pub const PROTOCOL_VERSION: ProtocolVersion = STABLE_PROTOCOL_VERSION;
 /// Current latest nightly version of the protocol.
 #[cfg(feature = "nightly_protocol")]
-pub const PROTOCOL_VERSION: ProtocolVersion = 128;
+pub const PROTOCOL_VERSION: ProtocolVersion = NIGHTLY_PROTOCOL_VERSION;
+
+/// Latest nightly version of the protocol, which enables every feature.
+#[allow(dead_code)]
+const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 134;
 
 /// The points in time after which the voting for the protocol version should start.
 #[allow(dead_code)]
@@ -201,8 +270,91 @@ pub fn get_protocol_version(next_epoch_protocol_version: ProtocolVersion) -> Pro
 }
 
 impl ProtocolFeature {
+    /// All features, ordered by the protocol version enabling them.
+    pub const ALL: &'static [ProtocolFeature] = &[
+        ProtocolFeature::MinGasPriceNep92,
+        ProtocolFeature::MinGasPriceNep92Fix,
+        ProtocolFeature::CorrectRandomValue,
+        ProtocolFeature::ImplicitAccountCreation,
+        ProtocolFeature::EnableInflation,
+        ProtocolFeature::UpgradabilityFix,
+        ProtocolFeature::CreateHash,
+        ProtocolFeature::DeleteKeyStorageUsage,
+        ProtocolFeature::ShardChunkHeaderUpgrade,
+        ProtocolFeature::CreateReceiptIdSwitchToCurrentBlock,
+        ProtocolFeature::LowerStorageCost,
+        ProtocolFeature::DeleteActionRestriction,
+        ProtocolFeature::FixApplyChunks,
+        ProtocolFeature::ForwardChunkParts,
+        ProtocolFeature::RectifyInflation,
+        ProtocolFeature::AccessKeyNonceRange,
+        ProtocolFeature::AccountVersions,
+        ProtocolFeature::TransactionSizeLimit,
+        ProtocolFeature::FixStorageUsage,
+        ProtocolFeature::CapMaxGasPrice,
+        ProtocolFeature::CountRefundReceiptsInGasLimit,
+        ProtocolFeature::MathExtension,
+        ProtocolFeature::RestoreReceiptsAfterFixApplyChunks,
+        ProtocolFeature::Wasmer2,
+        ProtocolFeature::LowerDataReceiptAndEcrecoverBaseCost,
+        ProtocolFeature::LowerRegularOpCost,
+        ProtocolFeature::SimpleNightshade,
+        ProtocolFeature::LowerRegularOpCost2,
+        ProtocolFeature::LimitContractFunctionsNumber,
+        ProtocolFeature::BlockHeaderV3,
+        ProtocolFeature::AliasValidatorSelectionAlgorithm,
+        ProtocolFeature::SynchronizeBlockChunkProduction,
+        ProtocolFeature::CorrectStackLimit,
+        ProtocolFeature::AccessKeyNonceForImplicitAccounts,
+        ProtocolFeature::IncreaseDeploymentCost,
+        ProtocolFeature::FunctionCallWeight,
+        ProtocolFeature::LimitContractLocals,
+        ProtocolFeature::ChunkNodesCache,
+        ProtocolFeature::LowerStorageKeyLimit,
+        #[cfg(feature = "protocol_feature_alt_bn128")]
+        ProtocolFeature::AltBn128,
+        #[cfg(feature = "protocol_feature_routing_exchange_algorithm")]
+        ProtocolFeature::RoutingExchangeAlgorithm,
+        #[cfg(feature = "protocol_feature_chunk_only_producers")]
+        ProtocolFeature::ChunkOnlyProducers,
+        #[cfg(feature = "protocol_feature_fix_staking_threshold")]
+        ProtocolFeature::FixStakingThreshold,
+        #[cfg(feature = "protocol_feature_reject_off_curve_keys")]
+        ProtocolFeature::RejectOffCurveKeys,
+        #[cfg(feature = "protocol_feature_priority_fee")]
+        ProtocolFeature::PriorityFee,
+        #[cfg(feature = "protocol_feature_reject_self_beneficiary")]
+        ProtocolFeature::RejectSelfBeneficiary,
+        #[cfg(feature = "protocol_feature_action_gas_breakdown")]
+        ProtocolFeature::ActionGasBreakdown,
+        #[cfg(feature = "protocol_feature_receipt_congestion_metadata")]
+        ProtocolFeature::ReceiptCongestionMetadata,
+        #[cfg(feature = "protocol_feature_reject_create_implicit_account")]
+        ProtocolFeature::RejectCreateImplicitAccount,
+        #[cfg(feature = "protocol_feature_staking_key_action_error")]
+        ProtocolFeature::StakingKeyActionError,
+        #[cfg(feature = "protocol_feature_host_receipt_limits")]
+        ProtocolFeature::HostReceiptLimits,
+    ];
+
+    pub const fn enabled(self, protocol_version: ProtocolVersion) -> bool {
+        self.protocol_version() <= protocol_version
+    }
+
     pub const fn protocol_version(self) -> ProtocolVersion {
         match self {
+            // Features predating the enum
+            ProtocolFeature::MinGasPriceNep92 => 31,
+            ProtocolFeature::MinGasPriceNep92Fix => 32,
+            ProtocolFeature::CorrectRandomValue => 33,
+            ProtocolFeature::ImplicitAccountCreation => 35,
+            ProtocolFeature::EnableInflation => 36,
+            ProtocolFeature::UpgradabilityFix => 37,
+            ProtocolFeature::CreateHash => 38,
+            ProtocolFeature::DeleteKeyStorageUsage => 40,
+            ProtocolFeature::ShardChunkHeaderUpgrade => 41,
+            ProtocolFeature::CreateReceiptIdSwitchToCurrentBlock => 42,
+
             // Stable features
             ProtocolFeature::LowerStorageCost => 42,
             ProtocolFeature::DeleteActionRestriction => 43,
@@ -243,19 +395,45 @@ impl ProtocolFeature {
             ProtocolFeature::RoutingExchangeAlgorithm => 117,
             #[cfg(feature = "protocol_feature_fix_staking_threshold")]
             ProtocolFeature::FixStakingThreshold => 126,
+            #[cfg(feature = "protocol_feature_reject_off_curve_keys")]
+            ProtocolFeature::RejectOffCurveKeys => 127,
+            #[cfg(feature = "protocol_feature_priority_fee")]
+            ProtocolFeature::PriorityFee => 128,
+            #[cfg(feature = "protocol_feature_reject_self_beneficiary")]
+            ProtocolFeature::RejectSelfBeneficiary => 129,
+            #[cfg(feature = "protocol_feature_action_gas_breakdown")]
+            ProtocolFeature::ActionGasBreakdown => 130,
+            #[cfg(feature = "protocol_feature_receipt_congestion_metadata")]
+            ProtocolFeature::ReceiptCongestionMetadata => 131,
+            #[cfg(feature = "protocol_feature_reject_create_implicit_account")]
+            ProtocolFeature::RejectCreateImplicitAccount => 132,
+            #[cfg(feature = "protocol_feature_staking_key_action_error")]
+            ProtocolFeature::StakingKeyActionError => 133,
+            #[cfg(feature = "protocol_feature_host_receipt_limits")]
+            ProtocolFeature::HostReceiptLimits => 134,
         }
     }
 }
 
+/// Features enabled at the given protocol version, in the order they were
+/// enabled. Features not compiled into this binary are never reported.
+pub fn enabled_features(protocol_version: ProtocolVersion) -> Vec<ProtocolFeature> {
+    ProtocolFeature::ALL
+        .iter()
+        .copied()
+        .filter(|feature| feature.enabled(protocol_version))
+        .collect()
+}
+
 #[macro_export]
 macro_rules! checked_feature {
     ("stable", $feature:ident, $current_protocol_version:expr) => {{
-        $crate::version::ProtocolFeature::$feature.protocol_version() <= $current_protocol_version
+        $crate::version::ProtocolFeature::$feature.enabled($current_protocol_version)
     }};
     ($feature_name:tt, $feature:ident, $current_protocol_version:expr) => {{
         #[cfg(feature = $feature_name)]
-        let is_feature_enabled = $crate::version::ProtocolFeature::$feature.protocol_version()
-            <= $current_protocol_version;
+        let is_feature_enabled =
+            $crate::version::ProtocolFeature::$feature.enabled($current_protocol_version);
         #[cfg(not(feature = $feature_name))]
         let is_feature_enabled = {
             // Workaround unused variable warning
@@ -287,3 +465,58 @@ macro_rules! checked_feature {
         }
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn test_protocol_versions_monotone() {
+        let mut prev = 0;
+        for feature in ProtocolFeature::ALL {
+            let version = feature.protocol_version();
+            assert!(
+                version >= prev,
+                "{:?} is enabled at {} before its predecessor",
+                feature,
+                version
+            );
+            assert!(
+                version <= NIGHTLY_PROTOCOL_VERSION,
+                "{:?} is enabled after the nightly protocol version",
+                feature
+            );
+            prev = version;
+        }
+    }
+
+    /// Every variant compiled into this binary, whichever cargo features
+    /// gate it, has to be listed exactly once.
+    #[test]
+    fn test_all_features_listed() {
+        for feature in ProtocolFeature::iter() {
+            assert_eq!(
+                ProtocolFeature::ALL.iter().filter(|listed| **listed == feature).count(),
+                1,
+                "{:?} must be listed once in ProtocolFeature::ALL",
+                feature
+            );
+        }
+        assert_eq!(ProtocolFeature::iter().count(), ProtocolFeature::ALL.len());
+    }
+
+    #[test]
+    fn test_enabled_features() {
+        assert!(enabled_features(0).is_empty());
+        let features = enabled_features(IMPLICIT_ACCOUNT_CREATION_PROTOCOL_VERSION);
+        assert_eq!(features.last(), Some(&ProtocolFeature::ImplicitAccountCreation));
+        assert!(!features.contains(&ProtocolFeature::EnableInflation));
+        assert!(is_implicit_account_creation_enabled(IMPLICIT_ACCOUNT_CREATION_PROTOCOL_VERSION));
+        assert!(!is_implicit_account_creation_enabled(
+            IMPLICIT_ACCOUNT_CREATION_PROTOCOL_VERSION - 1
+        ));
+        assert_eq!(enabled_features(NIGHTLY_PROTOCOL_VERSION).len(), ProtocolFeature::ALL.len());
+    }
+}
@@ -3,6 +3,7 @@
 //! These types should only change when we cannot avoid this. Thus, when the counterpart internal
 //! type gets changed, the view should preserve the old shape and only re-map the necessary bits
 //! from the source structure in the relevant `From<SourceStruct>` impl.
+use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
 
@@ -25,7 +26,9 @@ use crate::hash::{hash, CryptoHash};
 use crate::logging;
 use crate::merkle::MerklePath;
 use crate::profile::Cost;
-use crate::receipt::{ActionReceipt, DataReceipt, DataReceiver, Receipt, ReceiptEnum};
+use crate::receipt::{
+    ActionReceipt, DataReceipt, DataReceiver, Receipt, ReceiptCongestion, ReceiptEnum,
+};
 use crate::serialize::{
     base64_format, from_base64, option_base64_format, option_u128_dec_format, to_base64,
     u128_dec_format, u64_dec_format,
@@ -35,17 +38,18 @@ use crate::sharding::{
     ShardChunkHeaderV3,
 };
 use crate::transaction::{
-    Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithIdAndProof,
-    ExecutionStatus, FunctionCallAction, SignedTransaction, StakeAction, TransferAction,
+    Action, ActionGasBreakdown, AddKeyAction, CreateAccountAction, DeleteAccountAction,
+    DeleteKeyAction, DeployContractAction, ExecutionMetadata, ExecutionOutcome,
+    ExecutionOutcomeWithIdAndProof, ExecutionStatus, FlattenedResult, FlattenedStatus,
+    FunctionCallAction, SignedTransaction, StakeAction, TransferAction,
 };
 use crate::types::{
     AccountId, AccountWithPublicKey, Balance, BlockHeight, CompiledContractCache, EpochHeight,
     EpochId, FunctionArgs, Gas, Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind,
-    StateChangeValue, StateChangeWithCause, StateChangesRequest, StateRoot, StorageUsage, StoreKey,
-    StoreValue, ValidatorKickoutReason,
+    StateChangeValue, StateChangeWithCause, StateChangesPage, StateChangesPagination,
+    StateChangesRequest, StateRoot, StorageUsage, StoreKey, StoreValue, ValidatorKickoutReason,
 };
-use crate::version::{ProtocolVersion, Version};
+use crate::version::{ProtocolFeature, ProtocolVersion, Version};
 use validator_stake_view::ValidatorStakeView;
 
 /// A view of the account
@@ -439,6 +443,8 @@ pub struct DetailedDebugStatus {
     pub epochs_info: Vec<EpochInfoView>,
     pub block_production_delay_millis: u64,
     pub chunk_info: ChunkInfoView,
+    /// Protocol features enabled in the current epoch.
+    pub protocol_features: Vec<ProtocolFeature>,
 }
 
 // TODO: add more information to status.
@@ -565,115 +571,145 @@ impl From<BlockHeader> for BlockHeaderView {
     }
 }
 
-impl From<BlockHeaderView> for BlockHeader {
-    fn from(view: BlockHeaderView) -> Self {
+/// Error returned when a `BlockHeaderView` can't be turned back into the exact
+/// header it was created from.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlockHeaderFromViewError {
+    #[error("block header view {hash} is missing {} required by header {version}", missing.join(", "))]
+    MissingFields { hash: CryptoHash, version: &'static str, missing: Vec<&'static str> },
+    #[error("block header view {hash} does not match any header version, computed {computed:?}")]
+    HashMismatch { hash: CryptoHash, computed: Vec<(&'static str, CryptoHash)> },
+}
+
+/// Reconstructs the header variant the view was created from.
+///
+/// Header versions are picked by the epoch protocol version of the producer,
+/// which the view doesn't carry, so headers without V3 fields are rebuilt as
+/// both V1 and V2 and the one matching `view.hash` is returned.
+impl TryFrom<BlockHeaderView> for BlockHeader {
+    type Error = BlockHeaderFromViewError;
+
+    fn try_from(view: BlockHeaderView) -> Result<Self, Self::Error> {
         let inner_lite = BlockHeaderInnerLite {
             height: view.height,
             epoch_id: EpochId(view.epoch_id),
             next_epoch_id: EpochId(view.next_epoch_id),
             prev_state_root: view.prev_state_root,
             outcome_root: view.outcome_root,
-            timestamp: view.timestamp,
+            timestamp: view.timestamp_nanosec,
             next_bp_hash: view.next_bp_hash,
             block_merkle_root: view.block_merkle_root,
         };
-        let last_header_v2_version =
-            Some(crate::version::ProtocolFeature::BlockHeaderV3.protocol_version() - 1);
-        if view.latest_protocol_version <= 29 {
-            let validator_proposals = view
-                .validator_proposals
-                .into_iter()
-                .map(|v| v.into_validator_stake().into_v1())
-                .collect();
-            let mut header = BlockHeaderV1 {
-                prev_hash: view.prev_hash,
-                inner_lite,
-                inner_rest: BlockHeaderInnerRest {
-                    chunk_receipts_root: view.chunk_receipts_root,
-                    chunk_headers_root: view.chunk_headers_root,
-                    chunk_tx_root: view.chunk_tx_root,
-                    chunks_included: view.chunks_included,
-                    challenges_root: view.challenges_root,
-                    random_value: view.random_value,
-                    validator_proposals,
-                    chunk_mask: view.chunk_mask,
-                    gas_price: view.gas_price,
-                    total_supply: view.total_supply,
-                    challenges_result: view.challenges_result,
-                    last_final_block: view.last_final_block,
-                    last_ds_final_block: view.last_ds_final_block,
-                    approvals: view.approvals.clone(),
-                    latest_protocol_version: view.latest_protocol_version,
-                },
-                signature: view.signature,
-                hash: CryptoHash::default(),
-            };
-            header.init();
-            BlockHeader::BlockHeaderV1(Arc::new(header))
-        } else if last_header_v2_version.is_none()
-            || view.latest_protocol_version <= last_header_v2_version.unwrap()
-        {
-            let validator_proposals = view
-                .validator_proposals
-                .into_iter()
-                .map(|v| v.into_validator_stake().into_v1())
-                .collect();
-            let mut header = BlockHeaderV2 {
-                prev_hash: view.prev_hash,
-                inner_lite,
-                inner_rest: BlockHeaderInnerRestV2 {
-                    chunk_receipts_root: view.chunk_receipts_root,
-                    chunk_headers_root: view.chunk_headers_root,
-                    chunk_tx_root: view.chunk_tx_root,
-                    challenges_root: view.challenges_root,
-                    random_value: view.random_value,
-                    validator_proposals,
-                    chunk_mask: view.chunk_mask,
-                    gas_price: view.gas_price,
-                    total_supply: view.total_supply,
-                    challenges_result: view.challenges_result,
-                    last_final_block: view.last_final_block,
-                    last_ds_final_block: view.last_ds_final_block,
-                    approvals: view.approvals.clone(),
-                    latest_protocol_version: view.latest_protocol_version,
-                },
-                signature: view.signature,
-                hash: CryptoHash::default(),
-            };
-            header.init();
-            BlockHeader::BlockHeaderV2(Arc::new(header))
-        } else {
-            let mut header = BlockHeaderV3 {
-                prev_hash: view.prev_hash,
-                inner_lite,
-                inner_rest: BlockHeaderInnerRestV3 {
-                    chunk_receipts_root: view.chunk_receipts_root,
-                    chunk_headers_root: view.chunk_headers_root,
-                    chunk_tx_root: view.chunk_tx_root,
-                    challenges_root: view.challenges_root,
-                    random_value: view.random_value,
-                    validator_proposals: view
-                        .validator_proposals
-                        .into_iter()
-                        .map(Into::into)
-                        .collect(),
-                    chunk_mask: view.chunk_mask,
-                    gas_price: view.gas_price,
-                    block_ordinal: view.block_ordinal.unwrap_or(0),
-                    total_supply: view.total_supply,
-                    challenges_result: view.challenges_result,
-                    last_final_block: view.last_final_block,
-                    last_ds_final_block: view.last_ds_final_block,
-                    prev_height: view.prev_height.unwrap_or_default(),
-                    epoch_sync_data_hash: view.epoch_sync_data_hash,
-                    approvals: view.approvals.clone(),
-                    latest_protocol_version: view.latest_protocol_version,
-                },
-                signature: view.signature,
-                hash: CryptoHash::default(),
-            };
-            header.init();
-            BlockHeader::BlockHeaderV3(Arc::new(header))
+        let mut candidates = match view.prev_height {
+            Some(prev_height) => {
+                let mut header = BlockHeaderV3 {
+                    prev_hash: view.prev_hash,
+                    inner_lite,
+                    inner_rest: BlockHeaderInnerRestV3 {
+                        chunk_receipts_root: view.chunk_receipts_root,
+                        chunk_headers_root: view.chunk_headers_root,
+                        chunk_tx_root: view.chunk_tx_root,
+                        challenges_root: view.challenges_root,
+                        random_value: view.random_value,
+                        validator_proposals: view
+                            .validator_proposals
+                            .into_iter()
+                            .map(Into::into)
+                            .collect(),
+                        chunk_mask: view.chunk_mask,
+                        gas_price: view.gas_price,
+                        // The view doesn't distinguish a zero ordinal from a missing one.
+                        block_ordinal: view.block_ordinal.unwrap_or(0),
+                        total_supply: view.total_supply,
+                        challenges_result: view.challenges_result,
+                        last_final_block: view.last_final_block,
+                        last_ds_final_block: view.last_ds_final_block,
+                        prev_height,
+                        epoch_sync_data_hash: view.epoch_sync_data_hash,
+                        approvals: view.approvals,
+                        latest_protocol_version: view.latest_protocol_version,
+                    },
+                    signature: view.signature,
+                    hash: CryptoHash::default(),
+                };
+                header.init();
+                vec![("V3", BlockHeader::BlockHeaderV3(Arc::new(header)))]
+            }
+            None if view.block_ordinal.is_some() || view.epoch_sync_data_hash.is_some() => {
+                return Err(BlockHeaderFromViewError::MissingFields {
+                    hash: view.hash,
+                    version: "V3",
+                    missing: vec!["prev_height"],
+                });
+            }
+            None => {
+                let validator_proposals: Vec<_> = view
+                    .validator_proposals
+                    .into_iter()
+                    .map(|v| v.into_validator_stake().into_v1())
+                    .collect();
+                let mut header_v1 = BlockHeaderV1 {
+                    prev_hash: view.prev_hash,
+                    inner_lite: inner_lite.clone(),
+                    inner_rest: BlockHeaderInnerRest {
+                        chunk_receipts_root: view.chunk_receipts_root,
+                        chunk_headers_root: view.chunk_headers_root,
+                        chunk_tx_root: view.chunk_tx_root,
+                        chunks_included: view.chunks_included,
+                        challenges_root: view.challenges_root,
+                        random_value: view.random_value,
+                        validator_proposals: validator_proposals.clone(),
+                        chunk_mask: view.chunk_mask.clone(),
+                        gas_price: view.gas_price,
+                        total_supply: view.total_supply,
+                        challenges_result: view.challenges_result.clone(),
+                        last_final_block: view.last_final_block,
+                        last_ds_final_block: view.last_ds_final_block,
+                        approvals: view.approvals.clone(),
+                        latest_protocol_version: view.latest_protocol_version,
+                    },
+                    signature: view.signature.clone(),
+                    hash: CryptoHash::default(),
+                };
+                header_v1.init();
+                let mut header_v2 = BlockHeaderV2 {
+                    prev_hash: view.prev_hash,
+                    inner_lite,
+                    inner_rest: BlockHeaderInnerRestV2 {
+                        chunk_receipts_root: view.chunk_receipts_root,
+                        chunk_headers_root: view.chunk_headers_root,
+                        chunk_tx_root: view.chunk_tx_root,
+                        challenges_root: view.challenges_root,
+                        random_value: view.random_value,
+                        validator_proposals,
+                        chunk_mask: view.chunk_mask,
+                        gas_price: view.gas_price,
+                        total_supply: view.total_supply,
+                        challenges_result: view.challenges_result,
+                        last_final_block: view.last_final_block,
+                        last_ds_final_block: view.last_ds_final_block,
+                        approvals: view.approvals,
+                        latest_protocol_version: view.latest_protocol_version,
+                    },
+                    signature: view.signature,
+                    hash: CryptoHash::default(),
+                };
+                header_v2.init();
+                vec![
+                    ("V1", BlockHeader::BlockHeaderV1(Arc::new(header_v1))),
+                    ("V2", BlockHeader::BlockHeaderV2(Arc::new(header_v2))),
+                ]
+            }
+        };
+        match candidates.iter().position(|(_, header)| header.hash() == &view.hash) {
+            Some(index) => Ok(candidates.swap_remove(index).1),
+            None => Err(BlockHeaderFromViewError::HashMismatch {
+                hash: view.hash,
+                computed: candidates
+                    .iter()
+                    .map(|(version, header)| (*version, *header.hash()))
+                    .collect(),
+            }),
         }
     }
 }
@@ -998,6 +1034,8 @@ pub struct SignedTransactionView {
     pub actions: Vec<ActionView>,
     pub signature: Signature,
     pub hash: CryptoHash,
+    #[serde(with = "option_u128_dec_format", default, skip_serializing_if = "Option::is_none")]
+    pub priority_fee: Option<Balance>,
 }
 
 impl From<SignedTransaction> for SignedTransactionView {
@@ -1016,6 +1054,7 @@ impl From<SignedTransaction> for SignedTransactionView {
                 .collect(),
             signature: signed_tx.signature,
             hash,
+            priority_fee: signed_tx.transaction.priority_fee,
         }
     }
 }
@@ -1053,6 +1092,25 @@ impl Default for FinalExecutionStatus {
     }
 }
 
+impl From<FlattenedResult> for FinalExecutionStatus {
+    /// Status of a transaction whose outcome flattened to `result`.  The transaction is still
+    /// going while the outcome of a receipt is missing; it never finishes if there's a cycle.
+    fn from(result: FlattenedResult) -> Self {
+        match result.status {
+            FlattenedStatus::Unknown if result.receipt_ids.is_empty() => {
+                FinalExecutionStatus::NotStarted
+            }
+            FlattenedStatus::Unknown
+            | FlattenedStatus::MissingOutcome(_)
+            | FlattenedStatus::Cycle(_) => FinalExecutionStatus::Started,
+            FlattenedStatus::Failure(error) => FinalExecutionStatus::Failure(error),
+            FlattenedStatus::SuccessValue(value) => {
+                FinalExecutionStatus::SuccessValue(to_base64(&value))
+            }
+        }
+    }
+}
+
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub enum ServerError {
@@ -1118,6 +1176,9 @@ pub struct CostGasUsed {
 pub struct ExecutionMetadataView {
     pub version: u32,
     pub gas_profile: Option<Vec<CostGasUsed>>,
+    /// Gas of each applied action, if the node keeps it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_gas_breakdown: Option<Vec<ActionGasBreakdown>>,
 }
 
 impl Default for ExecutionMetadataView {
@@ -1128,46 +1189,41 @@ impl Default for ExecutionMetadataView {
 
 impl From<ExecutionMetadata> for ExecutionMetadataView {
     fn from(metadata: ExecutionMetadata) -> Self {
-        let gas_profile = match metadata {
-            ExecutionMetadata::V1 => None,
-            ExecutionMetadata::V2(profile_data) => {
-                let mut costs: Vec<_> = Cost::ALL
-                    .iter()
-                    .filter(|&cost| profile_data[*cost] > 0)
-                    .map(|&cost| CostGasUsed {
-                        cost_category: match cost {
-                            Cost::ActionCost { .. } => "ACTION_COST",
-                            Cost::ExtCost { .. } => "WASM_HOST_COST",
-                            Cost::WasmInstruction => "WASM_HOST_COST",
-                        }
-                        .to_string(),
-                        cost: match cost {
-                            Cost::ActionCost { action_cost_kind: action_cost } => {
-                                format!("{:?}", action_cost).to_ascii_uppercase()
-                            }
-                            Cost::ExtCost { ext_cost_kind: ext_cost } => {
-                                format!("{:?}", ext_cost).to_ascii_uppercase()
-                            }
-                            Cost::WasmInstruction => "WASM_INSTRUCTION".to_string(),
-                        },
-                        gas_used: profile_data[cost],
-                    })
-                    .collect();
-
-                // The order doesn't really matter, but the default one is just
-                // historical, which is especially unintuitive, so let's sort
-                // lexicographically.
-                //
-                // Can't `sort_by_key` here because lifetime inference in
-                // closures is limited.
-                costs.sort_by(|lhs, rhs| {
-                    lhs.cost_category.cmp(&rhs.cost_category).then(lhs.cost.cmp(&rhs.cost))
-                });
-
-                Some(costs)
+        let (profile_data, action_gas_breakdown) = match metadata {
+            ExecutionMetadata::V1 => (None, None),
+            ExecutionMetadata::V2(profile_data) => (Some(profile_data), None),
+            ExecutionMetadata::V3 { profile, action_gas_breakdown } => {
+                (Some(profile), action_gas_breakdown)
             }
         };
-        ExecutionMetadataView { version: 1, gas_profile }
+        let gas_profile = profile_data.map(|profile_data| {
+            let mut costs: Vec<_> = profile_data
+                .costs()
+                .map(|(cost, gas_used)| CostGasUsed {
+                    cost_category: match cost {
+                        Cost::ActionCost { .. } => "ACTION_COST",
+                        Cost::ExtCost { .. } => "WASM_HOST_COST",
+                        Cost::WasmInstruction => "WASM_HOST_COST",
+                    }
+                    .to_string(),
+                    cost: cost.name().to_string(),
+                    gas_used,
+                })
+                .collect();
+
+            // The order doesn't really matter, but the default one is just
+            // historical, which is especially unintuitive, so let's sort
+            // lexicographically.
+            //
+            // Can't `sort_by_key` here because lifetime inference in
+            // closures is limited.
+            costs.sort_by(|lhs, rhs| {
+                lhs.cost_category.cmp(&rhs.cost_category).then(lhs.cost.cmp(&rhs.cost))
+            });
+
+            costs
+        });
+        ExecutionMetadataView { version: 1, gas_profile, action_gas_breakdown }
     }
 }
 
@@ -1195,6 +1251,28 @@ pub struct ExecutionOutcomeView {
     pub metadata: ExecutionMetadataView,
 }
 
+impl ExecutionOutcomeView {
+    /// The parts of the outcome `ExecutionOutcome::flatten_receipt_chain` uses.  Malformed
+    /// base64 values, which views produced by nodes never have, are flattened to empty values.
+    fn to_flattenable(&self) -> ExecutionOutcome {
+        ExecutionOutcome {
+            gas_burnt: self.gas_burnt,
+            tokens_burnt: self.tokens_burnt,
+            status: match &self.status {
+                ExecutionStatusView::Unknown => ExecutionStatus::Unknown,
+                ExecutionStatusView::Failure(error) => ExecutionStatus::Failure(error.clone()),
+                ExecutionStatusView::SuccessValue(value) => {
+                    ExecutionStatus::SuccessValue(from_base64(value).unwrap_or_default())
+                }
+                ExecutionStatusView::SuccessReceiptId(receipt_id) => {
+                    ExecutionStatus::SuccessReceiptId(*receipt_id)
+                }
+            },
+            ..Default::default()
+        }
+    }
+}
+
 impl From<ExecutionOutcome> for ExecutionOutcomeView {
     fn from(outcome: ExecutionOutcome) -> Self {
         Self {
@@ -1218,6 +1296,22 @@ pub struct ExecutionOutcomeWithIdView {
     pub outcome: ExecutionOutcomeView,
 }
 
+impl ExecutionOutcomeWithIdView {
+    /// Hashes of the outcome which are merklized into the chunk outcome root, the same as
+    /// `ExecutionOutcomeWithId::to_hashes` of the outcome the view was created from.
+    pub fn to_hashes(&self) -> Vec<CryptoHash> {
+        let outcome = ExecutionOutcome {
+            logs: self.outcome.logs.clone(),
+            receipt_ids: self.outcome.receipt_ids.clone(),
+            executor_id: self.outcome.executor_id.clone(),
+            ..self.outcome.to_flattenable()
+        };
+        let mut result = vec![self.id];
+        result.extend(outcome.to_hashes());
+        result
+    }
+}
+
 impl From<ExecutionOutcomeWithIdAndProof> for ExecutionOutcomeWithIdView {
     fn from(outcome_with_id_and_proof: ExecutionOutcomeWithIdAndProof) -> Self {
         Self {
@@ -1250,6 +1344,24 @@ pub struct FinalExecutionOutcomeView {
     pub receipts_outcome: Vec<ExecutionOutcomeWithIdView>,
 }
 
+impl FinalExecutionOutcomeView {
+    /// Status of the transaction computed from `transaction_outcome` and `receipts_outcome`, see
+    /// `ExecutionOutcome::flatten_receipt_chain`.
+    pub fn final_status(&self) -> FinalExecutionStatus {
+        self.flatten_receipt_chain().into()
+    }
+
+    /// Flattens the `SuccessReceiptId` chain starting at the transaction outcome, looking up
+    /// receipt outcomes in `receipts_outcome`.
+    pub fn flatten_receipt_chain(&self) -> FlattenedResult {
+        let outcomes: HashMap<&CryptoHash, &ExecutionOutcomeView> =
+            self.receipts_outcome.iter().map(|outcome| (&outcome.id, &outcome.outcome)).collect();
+        self.transaction_outcome.outcome.to_flattenable().flatten_receipt_chain(|receipt_id| {
+            outcomes.get(receipt_id).map(|outcome| outcome.to_flattenable())
+        })
+    }
+}
+
 impl fmt::Debug for FinalExecutionOutcomeView {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FinalExecutionOutcome")
@@ -1414,12 +1526,52 @@ pub enum ReceiptEnumView {
         output_data_receivers: Vec<DataReceiverView>,
         input_data_ids: Vec<CryptoHash>,
         actions: Vec<ActionView>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        congestion: Option<ReceiptCongestionView>,
     },
-    Data {
-        data_id: CryptoHash,
-        #[serde(with = "option_base64_format")]
-        data: Option<Vec<u8>>,
-    },
+    Data(DataReceiptView),
+}
+
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReceiptCongestionView {
+    #[serde(with = "u64_dec_format")]
+    pub upstream_gas_burnt: Gas,
+    pub priority: u8,
+}
+
+impl From<ReceiptCongestion> for ReceiptCongestionView {
+    fn from(congestion: ReceiptCongestion) -> Self {
+        Self { upstream_gas_burnt: congestion.upstream_gas_burnt, priority: congestion.priority }
+    }
+}
+
+impl From<ReceiptCongestionView> for ReceiptCongestion {
+    fn from(view: ReceiptCongestionView) -> Self {
+        Self { upstream_gas_burnt: view.upstream_gas_burnt, priority: view.priority }
+    }
+}
+
+/// View of a `DataReceipt`, with `data_id` in base58 and `data` in base64.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DataReceiptView {
+    pub data_id: CryptoHash,
+    /// Value returned by the promise, `None` if the promise failed.
+    #[serde(with = "option_base64_format")]
+    pub data: Option<Vec<u8>>,
+}
+
+impl From<DataReceipt> for DataReceiptView {
+    fn from(data_receipt: DataReceipt) -> Self {
+        DataReceiptView { data_id: data_receipt.data_id, data: data_receipt.data }
+    }
+}
+
+impl From<DataReceiptView> for DataReceipt {
+    fn from(data_receipt_view: DataReceiptView) -> Self {
+        DataReceipt { data_id: data_receipt_view.data_id, data: data_receipt_view.data }
+    }
 }
 
 impl From<Receipt> for ReceiptView {
@@ -1447,10 +1599,9 @@ impl From<Receipt> for ReceiptView {
                         .map(Into::into)
                         .collect(),
                     actions: action_receipt.actions.into_iter().map(Into::into).collect(),
+                    congestion: action_receipt.congestion.map(Into::into),
                 },
-                ReceiptEnum::Data(data_receipt) => {
-                    ReceiptEnumView::Data { data_id: data_receipt.data_id, data: data_receipt.data }
-                }
+                ReceiptEnum::Data(data_receipt) => ReceiptEnumView::Data(data_receipt.into()),
             },
         }
     }
@@ -1472,6 +1623,7 @@ impl TryFrom<ReceiptView> for Receipt {
                     output_data_receivers,
                     input_data_ids,
                     actions,
+                    congestion,
                 } => ReceiptEnum::Action(ActionReceipt {
                     signer_id,
                     signer_public_key,
@@ -1488,9 +1640,10 @@ impl TryFrom<ReceiptView> for Receipt {
                         .into_iter()
                         .map(TryInto::try_into)
                         .collect::<Result<Vec<_>, _>>()?,
+                    congestion: congestion.map(Into::into),
                 }),
-                ReceiptEnumView::Data { data_id, data } => {
-                    ReceiptEnum::Data(DataReceipt { data_id, data })
+                ReceiptEnumView::Data(data_receipt_view) => {
+                    ReceiptEnum::Data(data_receipt_view.into())
                 }
             },
         })
@@ -1611,6 +1764,29 @@ pub enum StateChangesRequestView {
         #[serde(rename = "key_prefix_base64", with = "base64_format")]
         key_prefix: StoreKey,
     },
+    AccountPrefix {
+        account_id_prefix: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pagination: Option<StateChangesPaginationView>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateChangesPaginationView {
+    #[serde(
+        rename = "from_key_base64",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_base64_format"
+    )]
+    pub from_key: Option<Vec<u8>>,
+    pub limit: usize,
+}
+
+impl From<StateChangesPaginationView> for StateChangesPagination {
+    fn from(pagination: StateChangesPaginationView) -> Self {
+        Self { from_key: pagination.from_key, limit: pagination.limit }
+    }
 }
 
 impl From<StateChangesRequestView> for StateChangesRequest {
@@ -1631,6 +1807,9 @@ impl From<StateChangesRequestView> for StateChangesRequest {
             StateChangesRequestView::DataChanges { account_ids, key_prefix } => {
                 Self::DataChanges { account_ids, key_prefix }
             }
+            StateChangesRequestView::AccountPrefix { account_id_prefix, pagination } => {
+                Self::AccountPrefix { account_id_prefix, pagination: pagination.map(Into::into) }
+            }
         }
     }
 }
@@ -1798,3 +1977,281 @@ impl From<StateChangeWithCause> for StateChangeWithCauseView {
 }
 
 pub type StateChangesView = Vec<StateChangeWithCauseView>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateChangesPageView {
+    pub changes: StateChangesView,
+    #[serde(
+        rename = "next_from_key_base64",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_base64_format"
+    )]
+    pub next_from_key: Option<Vec<u8>>,
+}
+
+impl From<StateChangesPage> for StateChangesPageView {
+    fn from(page: StateChangesPage) -> Self {
+        Self {
+            changes: page.changes.into_iter().map(Into::into).collect(),
+            next_from_key: page.next_from_key,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_header::ApprovalInner;
+    use crate::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+    use crate::version::PROTOCOL_VERSION;
+    use near_crypto::KeyType;
+
+    fn data_receipt(data: Option<Vec<u8>>) -> Receipt {
+        Receipt {
+            predecessor_id: "alice.near".parse().unwrap(),
+            receiver_id: "bob.near".parse().unwrap(),
+            receipt_id: hash(b"receipt"),
+            receipt: ReceiptEnum::Data(DataReceipt { data_id: hash(b"data"), data }),
+        }
+    }
+
+    /// Indexers rely on these exact field names, so the JSON is pinned.
+    #[test]
+    fn test_data_receipt_view_json() {
+        let view = ReceiptView::from(data_receipt(Some(b"hello".to_vec())));
+        assert_eq!(
+            serde_json::to_value(&view).unwrap(),
+            serde_json::json!({
+                "predecessor_id": "alice.near",
+                "receiver_id": "bob.near",
+                "receipt_id": hash(b"receipt").to_string(),
+                "receipt": {
+                    "Data": {
+                        "data_id": hash(b"data").to_string(),
+                        "data": "aGVsbG8=",
+                    }
+                }
+            })
+        );
+
+        let failed = ReceiptView::from(data_receipt(None));
+        assert_eq!(
+            serde_json::to_value(&failed.receipt).unwrap(),
+            serde_json::json!({ "Data": { "data_id": hash(b"data").to_string(), "data": null } })
+        );
+    }
+
+    #[test]
+    fn test_data_receipt_view_round_trip() {
+        for data in [Some(b"hello".to_vec()), Some(vec![]), None] {
+            let receipt = data_receipt(data);
+            let view = ReceiptView::from(receipt.clone());
+            let json = serde_json::to_string(&view).unwrap();
+            let parsed: ReceiptView = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, view);
+            assert_eq!(Receipt::try_from(parsed).unwrap(), receipt);
+        }
+    }
+
+    #[test]
+    fn test_action_receipt_view_congestion() {
+        let mut receipt = Receipt::new_balance_refund(&"bob.near".parse().unwrap(), 1);
+        let json = serde_json::to_value(&ReceiptView::from(receipt.clone())).unwrap();
+        assert!(json["receipt"]["Action"].get("congestion").is_none());
+
+        if let ReceiptEnum::Action(action_receipt) = &mut receipt.receipt {
+            action_receipt.congestion =
+                Some(ReceiptCongestion { upstream_gas_burnt: 10u64.pow(13), priority: 1 });
+        }
+        let view = ReceiptView::from(receipt.clone());
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(
+            json["receipt"]["Action"]["congestion"],
+            serde_json::json!({"upstream_gas_burnt": "10000000000000", "priority": 1})
+        );
+        let parsed: ReceiptView = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, view);
+        assert_eq!(Receipt::try_from(parsed).unwrap(), receipt);
+    }
+
+    fn outcome_view(id: &[u8], status: ExecutionStatusView) -> ExecutionOutcomeWithIdView {
+        ExecutionOutcomeWithIdView {
+            proof: vec![],
+            block_hash: CryptoHash::default(),
+            id: hash(id),
+            outcome: ExecutionOutcomeView {
+                logs: vec![],
+                receipt_ids: vec![],
+                gas_burnt: 10,
+                tokens_burnt: 100,
+                executor_id: "alice.near".parse().unwrap(),
+                status,
+                metadata: ExecutionMetadataView::default(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_final_execution_outcome_final_status() {
+        let mut final_outcome = FinalExecutionOutcomeView {
+            status: FinalExecutionStatus::NotStarted,
+            transaction: SignedTransaction::empty(CryptoHash::default()).into(),
+            transaction_outcome: outcome_view(
+                b"tx",
+                ExecutionStatusView::SuccessReceiptId(hash(b"first")),
+            ),
+            receipts_outcome: vec![
+                // Refunds and other receipts don't matter.
+                outcome_view(b"refund", ExecutionStatusView::SuccessValue(String::new())),
+                outcome_view(b"second", ExecutionStatusView::SuccessValue(to_base64(b"ok"))),
+                outcome_view(b"first", ExecutionStatusView::SuccessReceiptId(hash(b"second"))),
+            ],
+        };
+        assert_eq!(
+            final_outcome.final_status(),
+            FinalExecutionStatus::SuccessValue(to_base64(b"ok"))
+        );
+        let flattened = final_outcome.flatten_receipt_chain();
+        assert_eq!(flattened.receipt_ids, vec![hash(b"first"), hash(b"second")]);
+        assert_eq!((flattened.gas_burnt, flattened.tokens_burnt), (30, 300));
+
+        final_outcome.receipts_outcome.remove(1);
+        assert_eq!(final_outcome.final_status(), FinalExecutionStatus::Started);
+
+        final_outcome.transaction_outcome.outcome.status = ExecutionStatusView::Unknown;
+        assert_eq!(final_outcome.final_status(), FinalExecutionStatus::NotStarted);
+    }
+
+    #[test]
+    fn test_execution_outcome_view_hashes() {
+        let outcome = ExecutionOutcomeWithIdAndProof {
+            proof: vec![],
+            block_hash: hash(b"block"),
+            outcome_with_id: crate::transaction::ExecutionOutcomeWithId {
+                id: hash(b"receipt"),
+                outcome: ExecutionOutcome {
+                    logs: vec!["log".to_string()],
+                    receipt_ids: vec![hash(b"next")],
+                    gas_burnt: 10,
+                    tokens_burnt: 100,
+                    executor_id: "alice.near".parse().unwrap(),
+                    status: ExecutionStatus::SuccessValue(b"ok".to_vec()),
+                    metadata: ExecutionMetadata::V1,
+                },
+            },
+        };
+        let view = ExecutionOutcomeWithIdView::from(outcome.clone());
+        assert_eq!(view.to_hashes(), outcome.outcome_with_id.to_hashes());
+    }
+
+    fn block_header(
+        this_epoch_protocol_version: ProtocolVersion,
+        next_epoch_protocol_version: ProtocolVersion,
+    ) -> BlockHeader {
+        let signer =
+            InMemoryValidatorSigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+        BlockHeader::new(
+            this_epoch_protocol_version,
+            next_epoch_protocol_version,
+            12,
+            hash(b"prev"),
+            hash(b"state"),
+            hash(b"receipts"),
+            hash(b"chunk headers"),
+            hash(b"txs"),
+            hash(b"outcomes"),
+            1_650_000_000_123_456_789,
+            hash(b"challenges"),
+            hash(b"random"),
+            vec![crate::types::validator_stake::ValidatorStake::new_v1(
+                "test1".parse().unwrap(),
+                signer.public_key(),
+                100,
+            )],
+            vec![true, false, true],
+            7,
+            EpochId(hash(b"epoch")),
+            EpochId(hash(b"next epoch")),
+            1_000_000_000,
+            1_000_000_000_000,
+            vec![],
+            &signer,
+            hash(b"final"),
+            hash(b"ds final"),
+            Some(hash(b"epoch sync")),
+            vec![Some(signer.sign_approval(&ApprovalInner::Skip(11), 12)), None],
+            hash(b"next bp"),
+            hash(b"merkle root"),
+            10,
+        )
+    }
+
+    fn round_trip(header: &BlockHeader) -> BlockHeader {
+        let view = BlockHeaderView::from(header.clone());
+        let view: BlockHeaderView =
+            serde_json::from_str(&serde_json::to_string(&view).unwrap()).unwrap();
+        BlockHeader::try_from(view).unwrap()
+    }
+
+    #[test]
+    fn test_block_header_view_round_trip() {
+        let last_header_v2_version = ProtocolFeature::BlockHeaderV3.protocol_version() - 1;
+        let headers = [
+            block_header(29, 29),
+            block_header(last_header_v2_version, last_header_v2_version),
+            block_header(PROTOCOL_VERSION, PROTOCOL_VERSION),
+        ];
+        assert!(matches!(headers[0], BlockHeader::BlockHeaderV1(_)));
+        assert!(matches!(headers[1], BlockHeader::BlockHeaderV2(_)));
+        assert!(matches!(headers[2], BlockHeader::BlockHeaderV3(_)));
+        for header in headers {
+            assert_eq!(round_trip(&header), header);
+        }
+
+        for protocol_version in [29, last_header_v2_version, PROTOCOL_VERSION] {
+            let genesis = BlockHeader::genesis(
+                protocol_version,
+                0,
+                hash(b"state"),
+                hash(b"receipts"),
+                hash(b"chunk headers"),
+                hash(b"txs"),
+                4,
+                hash(b"challenges"),
+                chrono::Utc::now(),
+                1_000_000_000,
+                1_000_000_000_000,
+                hash(b"next bp"),
+            );
+            assert_eq!(round_trip(&genesis), genesis);
+        }
+    }
+
+    #[test]
+    fn test_block_header_view_inexact() {
+        let header = block_header(PROTOCOL_VERSION, PROTOCOL_VERSION);
+        let mut view = BlockHeaderView::from(header.clone());
+        view.prev_height = None;
+        assert_eq!(
+            BlockHeader::try_from(view).unwrap_err(),
+            BlockHeaderFromViewError::MissingFields {
+                hash: *header.hash(),
+                version: "V3",
+                missing: vec!["prev_height"],
+            }
+        );
+
+        let mut view = BlockHeaderView::from(header.clone());
+        view.gas_price += 1;
+        assert!(matches!(
+            BlockHeader::try_from(view),
+            Err(BlockHeaderFromViewError::HashMismatch { computed, .. }) if computed.len() == 1
+        ));
+
+        let mut view = BlockHeaderView::from(block_header(29, 29));
+        view.chunks_included = 1;
+        let err = BlockHeader::try_from(view).unwrap_err();
+        assert!(err.to_string().contains("does not match any header version"), "{}", err);
+    }
+}
@@ -8,7 +8,7 @@ use rocksdb::{
     BlockBasedOptions, Cache, ColumnFamily, ColumnFamilyDescriptor, Direction, Env, IteratorMode,
     Options, ReadOptions, WriteBatch, DB,
 };
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::io;
 use std::path::Path;
 use std::sync::atomic::Ordering;
@@ -56,6 +56,9 @@ pub const GENESIS_STATE_ROOTS_KEY: &[u8; 19] = b"GENESIS_STATE_ROOTS";
 /// Boolean stored in DBCol::BlockMisc indicating whether the database is for an
 /// archival node.  The default value (if missing) is false.
 pub const IS_ARCHIVE_KEY: &[u8; 10] = b"IS_ARCHIVE";
+/// The latest read-write opens of the database stored in DBCol::BlockMisc, see
+/// `Store::open_history`.
+pub const OPEN_HISTORY_KEY: &[u8; 12] = b"OPEN_HISTORY";
 
 pub(crate) struct DBTransaction {
     pub(crate) ops: Vec<DBOp>,
@@ -256,7 +259,8 @@ impl RocksDB {
 }
 
 pub struct TestDB {
-    db: RwLock<enum_map::EnumMap<DBCol, HashMap<Vec<u8>, Vec<u8>>>>,
+    // Ordered like RocksDB, so iteration order is the same.
+    db: RwLock<enum_map::EnumMap<DBCol, BTreeMap<Vec<u8>, Vec<u8>>>>,
 }
 
 pub(crate) trait Database: Sync + Send {
@@ -281,6 +285,21 @@ pub(crate) trait Database: Sync + Send {
     fn get_store_statistics(&self) -> Option<StoreStatistics> {
         None
     }
+    /// Returns a read-only view of the database as of now.  Writes made after
+    /// the call aren't visible through the snapshot.
+    fn snapshot<'a>(&'a self) -> Box<dyn DBSnapshot + 'a>;
+}
+
+/// Read-only view of a [`Database`] at the moment [`Database::snapshot`] was
+/// called.
+pub(crate) trait DBSnapshot {
+    fn get(&self, col: DBCol, key: &[u8]) -> Result<Option<Vec<u8>>, DBError>;
+    fn iter<'a>(&'a self, column: DBCol) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
+    fn iter_prefix<'a>(
+        &'a self,
+        col: DBCol,
+        key_prefix: &'a [u8],
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
 }
 
 impl Database for RocksDB {
@@ -398,6 +417,57 @@ impl Database for RocksDB {
         }
         None
     }
+
+    fn snapshot<'a>(&'a self) -> Box<dyn DBSnapshot + 'a> {
+        Box::new(RocksDBSnapshot { db: self, snapshot: self.db.snapshot() })
+    }
+}
+
+struct RocksDBSnapshot<'a> {
+    db: &'a RocksDB,
+    snapshot: rocksdb::Snapshot<'a>,
+}
+
+impl<'a> RocksDBSnapshot<'a> {
+    fn read_options(&self) -> ReadOptions {
+        let mut read_options = rocksdb_read_options();
+        read_options.set_snapshot(&self.snapshot);
+        read_options
+    }
+}
+
+impl<'a> DBSnapshot for RocksDBSnapshot<'a> {
+    fn get(&self, col: DBCol, key: &[u8]) -> Result<Option<Vec<u8>>, DBError> {
+        let result = self.db.db.get_cf_opt(self.db.cf_handle(col), key, &self.read_options())?;
+        Ok(RocksDB::get_with_rc_logic(col, result))
+    }
+
+    fn iter<'b>(&'b self, col: DBCol) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'b> {
+        let cf_handle = self.db.cf_handle(col);
+        let iterator =
+            self.db.db.iterator_cf_opt(cf_handle, self.read_options(), IteratorMode::Start);
+        RocksDB::iter_with_rc_logic(col, iterator)
+    }
+
+    fn iter_prefix<'b>(
+        &'b self,
+        col: DBCol,
+        key_prefix: &'b [u8],
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'b> {
+        let mut read_options = self.read_options();
+        read_options.set_prefix_same_as_start(true);
+        let cf_handle = self.db.cf_handle(col);
+        let iterator = self
+            .db
+            .db
+            .iterator_cf_opt(
+                cf_handle,
+                read_options,
+                IteratorMode::From(key_prefix, Direction::Forward),
+            )
+            .take_while(move |(key, _value)| key.starts_with(key_prefix));
+        RocksDB::iter_with_rc_logic(col, iterator)
+    }
 }
 
 impl Database for TestDB {
@@ -465,6 +535,28 @@ impl Database for TestDB {
         }
         Ok(())
     }
+
+    fn snapshot<'a>(&'a self) -> Box<dyn DBSnapshot + 'a> {
+        Box::new(TestDB { db: RwLock::new(self.db.read().unwrap().clone()) })
+    }
+}
+
+impl DBSnapshot for TestDB {
+    fn get(&self, col: DBCol, key: &[u8]) -> Result<Option<Vec<u8>>, DBError> {
+        Database::get(self, col, key)
+    }
+
+    fn iter<'a>(&'a self, col: DBCol) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        Database::iter(self, col)
+    }
+
+    fn iter_prefix<'a>(
+        &'a self,
+        col: DBCol,
+        key_prefix: &'a [u8],
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        Database::iter_prefix(self, col, key_prefix)
+    }
 }
 
 fn assert_no_ovewrite(col: DBCol, key: &[u8], value: &[u8], old_value: &[u8]) {
@@ -24,18 +24,19 @@ use near_primitives::serialize::to_base;
 pub use near_primitives::shard_layout::ShardUId;
 use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::{AccountId, CompiledContractCache, StateRoot};
+use near_primitives::version::{DbVersion, ProtocolVersion};
 
 pub use crate::db::refcount::decode_value_with_rc;
 use crate::db::refcount::encode_value_with_rc;
 use crate::db::{
     DBOp, DBTransaction, Database, RocksDB, StoreStatistics, GENESIS_JSON_HASH_KEY,
-    GENESIS_STATE_ROOTS_KEY,
+    GENESIS_STATE_ROOTS_KEY, OPEN_HISTORY_KEY,
 };
 pub use crate::trie::iterator::TrieIterator;
 pub use crate::trie::update::{TrieUpdate, TrieUpdateIterator, TrieUpdateValuePtr};
 pub use crate::trie::{
-    estimator, split_state, ApplyStatePartResult, KeyForStateChanges, PartialStorage, ShardTries,
-    Trie, TrieCache, TrieCachingStorage, TrieChanges, TrieStorage, WrappedTrieChanges,
+    estimator, raw_node, split_state, ApplyStatePartResult, KeyForStateChanges, PartialStorage,
+    ShardTries, Trie, TrieCache, TrieCachingStorage, TrieChanges, TrieStorage, WrappedTrieChanges,
 };
 
 mod columns;
@@ -43,10 +44,12 @@ mod config;
 pub mod db;
 mod metrics;
 pub mod migrations;
+mod overlay;
 pub mod test_utils;
 mod trie;
 
 pub use crate::config::StoreConfig;
+pub use crate::overlay::StoreOverlay;
 
 #[derive(Clone)]
 pub struct Store {
@@ -156,6 +159,51 @@ impl Store {
     pub fn get_store_statistics(&self) -> Option<StoreStatistics> {
         self.storage.get_store_statistics()
     }
+
+    /// Returns the latest read-write opens of the database, oldest first.
+    pub fn open_history(&self) -> io::Result<Vec<DbOpen>> {
+        Ok(self.get_ser(DBCol::BlockMisc, OPEN_HISTORY_KEY)?.unwrap_or_default())
+    }
+
+    /// Appends an open to the open history, keeping at most
+    /// `OPEN_HISTORY_SIZE` latest ones.  Fails on a read-only database.
+    pub fn record_open(&self, open: DbOpen) -> io::Result<()> {
+        let mut history = self.open_history()?;
+        history.push(open);
+        if history.len() > OPEN_HISTORY_SIZE {
+            history.drain(..history.len() - OPEN_HISTORY_SIZE);
+        }
+        let mut store_update = self.store_update();
+        store_update.set_ser(DBCol::BlockMisc, OPEN_HISTORY_KEY, &history)?;
+        store_update.commit()
+    }
+}
+
+/// Number of the latest read-write opens kept in the open history.
+pub const OPEN_HISTORY_SIZE: usize = 16;
+
+/// A read-write open of the database by neard, see [`Store::open_history`].
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DbOpen {
+    /// Release and build of neard.
+    pub neard_version: String,
+    pub protocol_version: ProtocolVersion,
+    pub db_version: DbVersion,
+    /// When the database was opened, see `near_primitives::utils::to_timestamp`.
+    pub timestamp: u64,
+}
+
+impl fmt::Display for DbOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} neard {} (protocol {}) (db {})",
+            near_primitives::utils::from_timestamp(self.timestamp).to_rfc3339(),
+            self.neard_version,
+            self.protocol_version,
+            self.db_version
+        )
+    }
 }
 
 /// Keeps track of current changes to the database and can commit all of them to the database.
@@ -337,8 +385,15 @@ pub fn create_store(path: &Path) -> Store {
 }
 
 pub fn create_store_with_config(path: &Path, store_config: &StoreConfig) -> Store {
-    let db = RocksDB::open(path, &store_config).expect("Failed to open the database");
-    Store::new(Arc::new(db))
+    try_create_store_with_config(path, store_config).expect("Failed to open the database")
+}
+
+/// Like [`create_store_with_config`] but returns an error if the database
+/// can't be opened, e.g. for tools which reopen a database a node is writing
+/// to and have to survive its files changing under them.
+pub fn try_create_store_with_config(path: &Path, store_config: &StoreConfig) -> io::Result<Store> {
+    let db = RocksDB::open(path, store_config)?;
+    Ok(Store::new(Arc::new(db)))
 }
 
 /// Reads an object from Trie.
@@ -562,9 +617,47 @@ impl CompiledContractCache for StoreCompiledContractCache {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_no_cache_disabled() {
         #[cfg(feature = "no_cache")]
         panic!("no cache is enabled");
     }
+
+    fn db_open(neard_version: &str, timestamp: u64) -> DbOpen {
+        DbOpen {
+            neard_version: neard_version.to_string(),
+            protocol_version: 1,
+            db_version: 1,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_open_history() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_open_history").tempdir().unwrap();
+        {
+            let store = create_store(tmp_dir.path());
+            assert_eq!(store.open_history().unwrap(), vec![]);
+            store.record_open(db_open("1.0.0", 0)).unwrap();
+        }
+        {
+            let store = create_store(tmp_dir.path());
+            for i in 1..=OPEN_HISTORY_SIZE as u64 {
+                store.record_open(db_open("1.1.0", i)).unwrap();
+            }
+        }
+        let expected: Vec<_> =
+            (1..=OPEN_HISTORY_SIZE as u64).map(|i| db_open("1.1.0", i)).collect();
+        {
+            let store = create_store(tmp_dir.path());
+            assert_eq!(store.open_history().unwrap(), expected);
+        }
+
+        // A read-only database isn't modified.
+        let store = create_store_with_config(tmp_dir.path(), &StoreConfig::read_only());
+        assert!(store.record_open(db_open("1.2.0", 100)).is_err());
+        assert_eq!(store.open_history().unwrap(), expected);
+    }
 }
@@ -0,0 +1,340 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::io;
+use std::iter::Peekable;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::db::DBSnapshot;
+use crate::{DBCol, Store, StoreUpdate};
+
+type KeyValueIter<'a> = Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
+
+/// In-memory write set on top of a snapshot of a [`Store`].
+///
+/// Reads see the changes made through the overlay first and fall back to a
+/// snapshot of the store taken when the overlay was created, so writes to the
+/// store made in the meantime aren't visible.  The store itself is never
+/// modified.  Once done, the changes are either added to a [`StoreUpdate`]
+/// with [`StoreOverlay::commit_to`] or dropped with [`StoreOverlay::discard`],
+/// so an error half way through leaves nothing behind.
+///
+/// Reference-counted columns can be read but not written.
+pub struct StoreOverlay<'a> {
+    snapshot: Box<dyn DBSnapshot + 'a>,
+    /// `None` marks a deleted key.
+    changes: enum_map::EnumMap<DBCol, BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+}
+
+impl<'a> StoreOverlay<'a> {
+    pub fn new(store: &'a Store) -> Self {
+        Self { snapshot: store.storage.snapshot(), changes: Default::default() }
+    }
+
+    pub fn get(&self, column: DBCol, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        match self.changes[column].get(key) {
+            Some(value) => Ok(value.clone()),
+            None => self.snapshot.get(column, key).map_err(io::Error::from),
+        }
+    }
+
+    pub fn get_ser<T: BorshDeserialize>(&self, column: DBCol, key: &[u8]) -> io::Result<Option<T>> {
+        match self.get(column, key)? {
+            Some(bytes) => Ok(Some(T::try_from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn exists(&self, column: DBCol, key: &[u8]) -> io::Result<bool> {
+        self.get(column, key).map(|value| value.is_some())
+    }
+
+    pub fn set(&mut self, column: DBCol, key: &[u8], value: &[u8]) {
+        assert!(!column.is_rc(), "can't set: {column:?}");
+        self.changes[column].insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    pub fn set_ser<T: BorshSerialize>(
+        &mut self,
+        column: DBCol,
+        key: &[u8],
+        value: &T,
+    ) -> io::Result<()> {
+        let data = value.try_to_vec()?;
+        self.set(column, key, &data);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, column: DBCol, key: &[u8]) {
+        assert!(!column.is_rc(), "can't delete: {column:?}");
+        self.changes[column].insert(key.to_vec(), None);
+    }
+
+    /// Iterates over the column in key order, as [`Store::iter`] would after
+    /// committing the overlay.
+    pub fn iter<'b>(&'b self, column: DBCol) -> KeyValueIter<'b> {
+        Box::new(MergeIter {
+            store: self.snapshot.iter(column).peekable(),
+            changes: (Box::new(self.changes[column].iter()) as ChangesIter<'b>).peekable(),
+        })
+    }
+
+    /// Iterates over the keys starting with `key_prefix` in key order, as
+    /// [`Store::iter_prefix`] would after committing the overlay.
+    pub fn iter_prefix<'b>(&'b self, column: DBCol, key_prefix: &'b [u8]) -> KeyValueIter<'b> {
+        let changes = self.changes[column]
+            .range(key_prefix.to_vec()..)
+            .take_while(move |(key, _)| key.starts_with(key_prefix));
+        Box::new(MergeIter {
+            store: self.snapshot.iter_prefix(column, key_prefix).peekable(),
+            changes: (Box::new(changes) as ChangesIter<'b>).peekable(),
+        })
+    }
+
+    /// Adds the changes made through the overlay to `store_update`.
+    pub fn commit_to(self, store_update: &mut StoreUpdate) {
+        for (column, changes) in self.changes {
+            for (key, value) in changes {
+                match value {
+                    Some(value) if column.is_insert_only() => {
+                        store_update.insert(column, &key, &value)
+                    }
+                    Some(value) => store_update.set(column, &key, &value),
+                    None => store_update.delete(column, &key),
+                }
+            }
+        }
+    }
+
+    /// Drops the changes made through the overlay.
+    pub fn discard(self) {}
+}
+
+type ChangesIter<'a> = Box<dyn Iterator<Item = (&'a Vec<u8>, &'a Option<Vec<u8>>)> + 'a>;
+
+/// Merges two iterators ordered by key, with `changes` taking precedence.
+struct MergeIter<'a> {
+    store: Peekable<KeyValueIter<'a>>,
+    changes: Peekable<ChangesIter<'a>>,
+}
+
+impl<'a> Iterator for MergeIter<'a> {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let order = match (self.store.peek(), self.changes.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((store_key, _)), Some((key, _))) => store_key.as_ref().cmp(key.as_slice()),
+            };
+            match order {
+                Ordering::Less => return self.store.next(),
+                Ordering::Equal => {
+                    self.store.next();
+                }
+                Ordering::Greater => {}
+            }
+            if let Some((key, Some(value))) = self.changes.next() {
+                return Some((key.clone().into_boxed_slice(), value.clone().into_boxed_slice()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StoreOverlay;
+    use crate::test_utils::create_test_store;
+    use crate::{create_store, DBCol, Store};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeMap;
+
+    const COL: DBCol = DBCol::BlockMisc;
+
+    fn fill(store: &Store, entries: &[(&[u8], &[u8])]) {
+        let mut store_update = store.store_update();
+        for (key, value) in entries {
+            store_update.set(COL, key, value);
+        }
+        store_update.commit().unwrap();
+    }
+
+    fn collect(
+        iter: Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + '_>,
+    ) -> Vec<(Vec<u8>, Vec<u8>)> {
+        iter.map(|(key, value)| (key.into_vec(), value.into_vec())).collect()
+    }
+
+    fn entries(entries: &[(&[u8], &[u8])]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        entries.iter().map(|(key, value)| (key.to_vec(), value.to_vec())).collect()
+    }
+
+    #[test]
+    fn test_get_commit_discard() {
+        let store = create_test_store();
+        fill(&store, &[(b"a", b"1"), (b"b", b"2")]);
+
+        let mut overlay = StoreOverlay::new(&store);
+        overlay.set(COL, b"a", b"10");
+        overlay.delete(COL, b"b");
+        overlay.set(COL, b"c", b"3");
+        overlay.set_ser(COL, b"d", &7u64).unwrap();
+        assert_eq!(overlay.get(COL, b"a").unwrap(), Some(b"10".to_vec()));
+        assert!(!overlay.exists(COL, b"b").unwrap());
+        assert_eq!(overlay.get(COL, b"c").unwrap(), Some(b"3".to_vec()));
+        assert_eq!(overlay.get_ser::<u64>(COL, b"d").unwrap(), Some(7));
+        // The store is untouched.
+        assert_eq!(collect(store.iter(COL)), entries(&[(b"a", b"1"), (b"b", b"2")]));
+
+        overlay.discard();
+        assert_eq!(collect(store.iter(COL)), entries(&[(b"a", b"1"), (b"b", b"2")]));
+
+        let mut overlay = StoreOverlay::new(&store);
+        overlay.set(COL, b"a", b"10");
+        overlay.delete(COL, b"b");
+        overlay.set(COL, b"c", b"3");
+        overlay.delete(COL, b"c");
+        overlay.set(COL, b"d", b"4");
+        let mut store_update = store.store_update();
+        overlay.commit_to(&mut store_update);
+        store_update.commit().unwrap();
+        assert_eq!(collect(store.iter(COL)), entries(&[(b"a", b"10"), (b"d", b"4")]));
+    }
+
+    #[test]
+    fn test_iter_merges_changes() {
+        let store = create_test_store();
+        fill(&store, &[(b"a", b"1"), (b"c", b"3"), (b"e", b"5"), (b"g", b"7")]);
+
+        let mut overlay = StoreOverlay::new(&store);
+        assert_eq!(
+            collect(overlay.iter(COL)),
+            entries(&[(b"a", b"1"), (b"c", b"3"), (b"e", b"5"), (b"g", b"7")])
+        );
+        overlay.set(COL, b"0", b"0");
+        overlay.set(COL, b"b", b"2");
+        overlay.set(COL, b"c", b"30");
+        overlay.delete(COL, b"e");
+        overlay.delete(COL, b"f");
+        overlay.delete(COL, b"g");
+        overlay.set(COL, b"h", b"8");
+        assert_eq!(
+            collect(overlay.iter(COL)),
+            entries(&[(b"0", b"0"), (b"a", b"1"), (b"b", b"2"), (b"c", b"30"), (b"h", b"8")])
+        );
+        // Other columns aren't affected.
+        assert_eq!(collect(overlay.iter(DBCol::BlockHeader)), vec![]);
+    }
+
+    #[test]
+    fn test_iter_prefix_merges_changes() {
+        let store = create_test_store();
+        fill(&store, &[(b"a", b"1"), (b"ab", b"2"), (b"abc", b"3"), (b"b", b"4")]);
+
+        let mut overlay = StoreOverlay::new(&store);
+        overlay.set(COL, b"aa", b"5");
+        overlay.delete(COL, b"ab");
+        overlay.set(COL, b"abd", b"6");
+        overlay.set(COL, b"ac", b"7");
+        overlay.set(COL, b"", b"8");
+        overlay.set(COL, b"ba", b"9");
+        assert_eq!(
+            collect(overlay.iter_prefix(COL, b"ab")),
+            entries(&[(b"abc", b"3"), (b"abd", b"6")])
+        );
+        assert_eq!(
+            collect(overlay.iter_prefix(COL, b"a")),
+            entries(&[(b"a", b"1"), (b"aa", b"5"), (b"abc", b"3"), (b"abd", b"6"), (b"ac", b"7")])
+        );
+        assert_eq!(collect(overlay.iter_prefix(COL, b"c")), vec![]);
+    }
+
+    /// Writes to the store after the overlay is created aren't visible through
+    /// it, on both the test and the RocksDB backed store.
+    #[test]
+    fn test_reads_from_snapshot() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_overlay").tempdir().unwrap();
+        for store in [create_test_store(), create_store(tmp_dir.path())] {
+            fill(&store, &[(b"a", b"1"), (b"b", b"2")]);
+
+            let mut overlay = StoreOverlay::new(&store);
+            overlay.set(COL, b"c", b"3");
+            let mut store_update = store.store_update();
+            store_update.set(COL, b"a", b"10");
+            store_update.delete(COL, b"b");
+            store_update.set(COL, b"d", b"4");
+            store_update.commit().unwrap();
+
+            assert_eq!(overlay.get(COL, b"a").unwrap(), Some(b"1".to_vec()));
+            assert_eq!(overlay.get(COL, b"b").unwrap(), Some(b"2".to_vec()));
+            assert!(!overlay.exists(COL, b"d").unwrap());
+            assert_eq!(
+                collect(overlay.iter(COL)),
+                entries(&[(b"a", b"1"), (b"b", b"2"), (b"c", b"3")])
+            );
+            assert_eq!(collect(overlay.iter_prefix(COL, b"a")), entries(&[(b"a", b"1")]));
+            overlay.discard();
+
+            let overlay = StoreOverlay::new(&store);
+            assert_eq!(collect(overlay.iter(COL)), entries(&[(b"a", b"10"), (b"d", b"4")]));
+        }
+    }
+
+    /// Compares iteration against a model with random changes, on both the
+    /// test and the RocksDB backed store.
+    #[test]
+    fn test_iter_random() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_overlay").tempdir().unwrap();
+        for store in [create_test_store(), create_store(tmp_dir.path())] {
+            let mut rng = StdRng::seed_from_u64(42);
+            let key = |rng: &mut StdRng| {
+                let len = rng.gen_range(0, 4);
+                (0..len).map(|_| rng.gen_range(0, 3)).collect::<Vec<u8>>()
+            };
+            for _ in 0..20 {
+                let mut model = BTreeMap::new();
+                for _ in 0..rng.gen_range(0, 20) {
+                    model.insert(key(&mut rng), vec![rng.gen::<u8>()]);
+                }
+                let mut store_update = store.store_update();
+                store_update.delete_all(COL);
+                for (k, v) in &model {
+                    store_update.set(COL, k, v);
+                }
+                store_update.commit().unwrap();
+
+                let mut overlay = StoreOverlay::new(&store);
+                for _ in 0..rng.gen_range(0, 20) {
+                    let k = key(&mut rng);
+                    if rng.gen_bool(0.5) {
+                        let v = vec![rng.gen::<u8>()];
+                        overlay.set(COL, &k, &v);
+                        model.insert(k, v);
+                    } else {
+                        overlay.delete(COL, &k);
+                        model.remove(&k);
+                    }
+                }
+
+                let expected: Vec<_> = model.clone().into_iter().collect();
+                assert_eq!(collect(overlay.iter(COL)), expected);
+                for prefix in [vec![], vec![0], vec![1, 2], vec![2, 2, 2]] {
+                    let expected: Vec<_> = model
+                        .iter()
+                        .filter(|(k, _)| k.starts_with(&prefix))
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect();
+                    assert_eq!(collect(overlay.iter_prefix(COL, &prefix)), expected);
+                }
+
+                let mut store_update = store.store_update();
+                overlay.commit_to(&mut store_update);
+                store_update.commit().unwrap();
+                assert_eq!(collect(store.iter(COL)), expected);
+            }
+        }
+    }
+}
@@ -783,6 +783,53 @@ pub mod estimator {
     }
 }
 
+/// Decoding of trie nodes as stored in `DBCol::State`, for tools which walk
+/// the trie in the database node by node, e.g. to check it for corruption.
+pub mod raw_node {
+    use super::nibble_slice::NibbleSlice;
+    use super::RawTrieNode;
+    use super::RawTrieNodeWithSize;
+    use near_primitives::hash::CryptoHash;
+
+    /// Hashes a stored trie node refers to, with the nibbles of the key
+    /// leading from the node to each of them.
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct DecodedTrieNode {
+        pub children: Vec<(Vec<u8>, CryptoHash)>,
+        /// Hash and length of the value.
+        pub value: Option<(Vec<u8>, CryptoHash, u32)>,
+        pub memory_usage: u64,
+    }
+
+    fn key_nibbles(key: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        if key.is_empty() {
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "Empty key"));
+        }
+        Ok(NibbleSlice::from_encoded(key).0.iter().collect())
+    }
+
+    pub fn decode_trie_node(bytes: &[u8]) -> Result<DecodedTrieNode, std::io::Error> {
+        let RawTrieNodeWithSize { node, memory_usage } = RawTrieNodeWithSize::decode(bytes)?;
+        let (children, value) = match node {
+            RawTrieNode::Leaf(key, value_length, value_hash) => {
+                (vec![], Some((key_nibbles(&key)?, value_hash, value_length)))
+            }
+            RawTrieNode::Branch(children, value) => {
+                let children = (0u8..)
+                    .zip(children)
+                    .filter_map(|(nibble, child)| child.map(|child| (vec![nibble], child)))
+                    .collect();
+                (
+                    children,
+                    value.map(|(value_length, value_hash)| (vec![], value_hash, value_length)),
+                )
+            }
+            RawTrieNode::Extension(key, child) => (vec![(key_nibbles(&key)?, child)], None),
+        };
+        Ok(DecodedTrieNode { children, value, memory_usage })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rand::Rng;
@@ -840,6 +887,36 @@ mod tests {
         assert_eq!(node, new_node);
     }
 
+    #[test]
+    fn test_decode_trie_node() {
+        let value_hash = hash(b"value");
+        let child = hash(b"child");
+        let decode = |node| {
+            let node = RawTrieNodeWithSize { node, memory_usage: 7 };
+            raw_node::decode_trie_node(&node.encode().unwrap()).unwrap()
+        };
+
+        // Odd number of nibbles 1, 2, 3 with the leaf flag.
+        let node = decode(RawTrieNode::Leaf(vec![0x31, 0x23], 5, value_hash));
+        assert_eq!(node.children, vec![]);
+        assert_eq!(node.value, Some((vec![1, 2, 3], value_hash, 5)));
+        assert_eq!(node.memory_usage, 7);
+
+        let mut children: [Option<CryptoHash>; 16] = Default::default();
+        children[3] = Some(child);
+        children[10] = Some(value_hash);
+        let node = decode(RawTrieNode::Branch(children, Some((5, value_hash))));
+        assert_eq!(node.children, vec![(vec![3], child), (vec![10], value_hash)]);
+        assert_eq!(node.value, Some((vec![], value_hash, 5)));
+
+        // Even number of nibbles 4, 5.
+        let node = decode(RawTrieNode::Extension(vec![0x00, 0x45], child));
+        assert_eq!(node.children, vec![(vec![4, 5], child)]);
+        assert_eq!(node.value, None);
+
+        assert!(raw_node::decode_trie_node(b"garbage").is_err());
+    }
+
     #[test]
     fn test_basic_trie() {
         // test trie version > 0
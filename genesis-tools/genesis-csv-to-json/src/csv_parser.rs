@@ -267,6 +267,7 @@ fn account_records(row: &Row, gas_price: Balance) -> Vec<StateRecord> {
                     gas: INIT_GAS,
                     deposit: 0,
                 })],
+                congestion: None,
             }),
         };
         res.push(StateRecord::PostponedReceipt(Box::new(receipt.into())));
@@ -313,6 +314,7 @@ mod tests {
                     id: PeerId::new(PublicKey::empty(KeyType::ED25519)),
                     addr: Some("127.0.0.1:8080".parse().unwrap()),
                     account_id: None,
+                    relay: None,
                 }),
                 is_treasury: false,
                 smart_contract: None,
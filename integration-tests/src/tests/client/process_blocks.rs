@@ -32,12 +32,14 @@ use near_network::types::{
 use near_network::types::{NetworkInfo, PeerManagerMessageRequest, PeerManagerMessageResponse};
 use near_network_primitives::types::{PeerChainInfoV2, PeerInfo, ReasonForBan};
 use near_primitives::block::{Approval, ApprovalInner};
-use near_primitives::block_header::BlockHeader;
+use near_primitives::block_header::{
+    BlockHeader, BlockHeaderInnerLite, BlockHeaderInnerRest, BlockHeaderV1,
+};
 use near_primitives::epoch_manager::RngSeed;
 use near_primitives::errors::TxExecutionError;
 use near_primitives::errors::{ActionErrorKind, InvalidTxError};
 use near_primitives::hash::{hash, CryptoHash};
-use near_primitives::merkle::verify_hash;
+use near_primitives::merkle::BlockMerkleProof;
 use near_primitives::receipt::DelayedReceiptIndices;
 use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
@@ -59,9 +61,7 @@ use near_primitives::utils::to_timestamp;
 use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
 use near_primitives::version::ProtocolFeature;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{
-    BlockHeaderView, FinalExecutionStatus, QueryRequest, QueryResponseKind,
-};
+use near_primitives::views::{FinalExecutionStatus, QueryRequest, QueryResponseKind};
 use near_store::test_utils::create_test_store;
 use near_store::{get, DBCol};
 use near_vm_errors::{CompilationError, FunctionCallErrorSer, PrepareError};
@@ -357,7 +357,7 @@ fn receive_network_block() {
             let block = Block::produce(
                 PROTOCOL_VERSION,
                 PROTOCOL_VERSION,
-                &last_block.header.clone().into(),
+                &BlockHeader::try_from(last_block.header.clone()).unwrap(),
                 last_block.header.height + 1,
                 next_block_ordinal,
                 last_block.chunks.into_iter().map(Into::into).collect(),
@@ -434,7 +434,7 @@ fn produce_block_with_approvals() {
             let block = Block::produce(
                 PROTOCOL_VERSION,
                 PROTOCOL_VERSION,
-                &last_block.header.clone().into(),
+                &BlockHeader::try_from(last_block.header.clone()).unwrap(),
                 last_block.header.height + 1,
                 next_block_ordinal,
                 last_block.chunks.into_iter().map(Into::into).collect(),
@@ -624,7 +624,7 @@ fn invalid_blocks_common(is_requested: bool) {
             let valid_block = Block::produce(
                 PROTOCOL_VERSION,
                 PROTOCOL_VERSION,
-                &last_block.header.clone().into(),
+                &BlockHeader::try_from(last_block.header.clone()).unwrap(),
                 last_block.header.height + 1,
                 next_block_ordinal,
                 last_block.chunks.iter().cloned().map(Into::into).collect(),
@@ -1028,6 +1028,7 @@ fn test_process_invalid_tx() {
             receiver_id: "test".parse().unwrap(),
             block_hash: *client.chain.genesis().hash(),
             actions: vec![],
+            priority_fee: None,
         },
     );
     produce_blocks(&mut client, 12);
@@ -1044,6 +1045,7 @@ fn test_process_invalid_tx() {
             receiver_id: "test".parse().unwrap(),
             block_hash: hash(&[1]),
             actions: vec![],
+            priority_fee: None,
         },
     );
     assert_eq!(
@@ -1987,9 +1989,21 @@ fn test_block_merkle_proof_with_len(n: NumBlocks, rng: &mut StdRng) {
             assert_eq!(block_hash, block_hash1);
         }
     }
+    let store = env.clients[0].chain.mut_store();
+    let tree_size = store.get_block_merkle_tree(head.hash()).unwrap().size();
+    let ordered_hashes: Vec<_> = (0..tree_size)
+        .map(|ordinal| *store.get_block_hash_from_ordinal(ordinal).unwrap())
+        .collect();
     for block in blocks {
         let proof = env.clients[0].chain.get_block_proof(block.hash(), head.hash()).unwrap();
-        assert!(verify_hash(*root, &proof, *block.hash()));
+        assert_eq!(proof.root, *root);
+        assert!(proof.verify(block.hash()));
+        let ordinal =
+            env.clients[0].chain.mut_store().get_block_merkle_tree(block.hash()).unwrap().size();
+        assert_eq!(
+            BlockMerkleProof::from_block_hashes(ordered_hashes.iter().cloned(), ordinal),
+            Some(proof)
+        );
     }
 }
 
@@ -2007,7 +2021,8 @@ fn test_block_merkle_proof_same_hash() {
     let genesis_block = env.clients[0].chain.get_block_by_height(0).unwrap().clone();
     let proof =
         env.clients[0].chain.get_block_proof(genesis_block.hash(), genesis_block.hash()).unwrap();
-    assert!(proof.is_empty());
+    assert!(proof.path.is_empty());
+    assert!(proof.verify(genesis_block.hash()));
 }
 
 #[test]
@@ -2649,6 +2664,7 @@ fn test_wasmer2_upgrade() {
 
         nonce: 0,
         block_hash: CryptoHash::default(),
+        priority_fee: None,
     };
 
     // Run the transaction & collect the logs.
@@ -2694,6 +2710,46 @@ fn test_wasmer2_upgrade() {
     assert!(logs_at_new_version.iter().any(|l| l.contains(&"run_wasmer2")));
 }
 
+/// Two transactions with the same nonce from the same key conflict: only the one with the higher
+/// priority fee makes it into the chunk, even though the pool would otherwise pick the other one.
+#[test]
+#[cfg(feature = "protocol_feature_priority_fee")]
+fn test_conflicting_transactions_ordered_by_priority_fee() {
+    use near_primitives::transaction::TransferAction;
+
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.protocol_version = ProtocolFeature::PriorityFee.protocol_version();
+    let chain_genesis = ChainGenesis::from(&genesis);
+    let mut env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let transfer = |deposit, priority_fee| {
+        Transaction {
+            signer_id: "test0".parse().unwrap(),
+            public_key: signer.public_key(),
+            nonce: 1,
+            receiver_id: "test1".parse().unwrap(),
+            block_hash: genesis_hash,
+            actions: vec![Action::Transfer(TransferAction { deposit })],
+            priority_fee: Some(priority_fee),
+        }
+        .sign(&signer)
+    };
+    let cheap = transfer(1, 1);
+    let expensive = transfer(2, 100);
+    for tx in [expensive.clone(), cheap.clone()] {
+        assert_eq!(env.clients[0].process_tx(tx, false, false), NetworkClientResponses::ValidTx);
+    }
+    for height in 1..5 {
+        env.produce_block(0, height);
+    }
+    let outcome = env.clients[0].chain.get_final_transaction_result(&expensive.get_hash()).unwrap();
+    assert_matches!(outcome.status, FinalExecutionStatus::SuccessValue(_));
+    assert!(env.clients[0].chain.get_final_transaction_result(&cheap.get_hash()).is_err());
+}
+
 #[test]
 fn test_execution_metadata() {
     // Prepare TestEnv with a very simple WASM contract.
@@ -2740,6 +2796,7 @@ fn test_execution_metadata() {
 
             nonce: 10,
             block_hash: tip.last_block_hash,
+            priority_fee: None,
         }
         .sign(&signer);
         let tx_hash = tx.get_hash();
@@ -3131,28 +3188,48 @@ fn test_header_version_downgrade() {
     }
     let block = {
         let mut block = env.clients[0].produce_block(10).unwrap().unwrap();
-        // Convert header to BlockHeaderV1
-        let mut header_view: BlockHeaderView = block.header().clone().into();
-        header_view.latest_protocol_version = 1;
-        let mut header = header_view.into();
-
-        // BlockHeaderV1, but protocol version is newest
-        match &mut header {
-            BlockHeader::BlockHeaderV1(header) => {
-                let header = Arc::make_mut(header);
-                header.inner_rest.latest_protocol_version = PROTOCOL_VERSION;
-                let (hash, signature) = validator_signer.sign_block_header_parts(
-                    header.prev_hash,
-                    &header.inner_lite.try_to_vec().expect("Failed to serialize"),
-                    &header.inner_rest.try_to_vec().expect("Failed to serialize"),
-                );
-                header.hash = hash;
-                header.signature = signature;
-            }
-            _ => {
-                unreachable!();
-            }
-        }
+        // Convert header to BlockHeaderV1, but protocol version is newest
+        let header = block.header();
+        let mut header_v1 = BlockHeaderV1 {
+            prev_hash: *header.prev_hash(),
+            inner_lite: BlockHeaderInnerLite {
+                height: header.height(),
+                epoch_id: header.epoch_id().clone(),
+                next_epoch_id: header.next_epoch_id().clone(),
+                prev_state_root: *header.prev_state_root(),
+                outcome_root: *header.outcome_root(),
+                timestamp: header.raw_timestamp(),
+                next_bp_hash: *header.next_bp_hash(),
+                block_merkle_root: *header.block_merkle_root(),
+            },
+            inner_rest: BlockHeaderInnerRest {
+                chunk_receipts_root: *header.chunk_receipts_root(),
+                chunk_headers_root: *header.chunk_headers_root(),
+                chunk_tx_root: *header.chunk_tx_root(),
+                chunks_included: header.chunks_included(),
+                challenges_root: *header.challenges_root(),
+                random_value: *header.random_value(),
+                validator_proposals: header.validator_proposals().map(|v| v.into_v1()).collect(),
+                chunk_mask: header.chunk_mask().to_vec(),
+                gas_price: header.gas_price(),
+                total_supply: header.total_supply(),
+                challenges_result: header.challenges_result().clone(),
+                last_final_block: *header.last_final_block(),
+                last_ds_final_block: *header.last_ds_final_block(),
+                approvals: header.approvals().to_vec(),
+                latest_protocol_version: PROTOCOL_VERSION,
+            },
+            signature: header.signature().clone(),
+            hash: *header.hash(),
+        };
+        let (hash, signature) = validator_signer.sign_block_header_parts(
+            header_v1.prev_hash,
+            &header_v1.inner_lite.try_to_vec().expect("Failed to serialize"),
+            &header_v1.inner_rest.try_to_vec().expect("Failed to serialize"),
+        );
+        header_v1.hash = hash;
+        header_v1.signature = signature;
+        let header = BlockHeader::BlockHeaderV1(Arc::new(header_v1));
         *block.mut_header() = header;
         block
     };
@@ -3394,6 +3471,7 @@ fn verify_contract_limits_upgrade(
 
         nonce: 0,
         block_hash: CryptoHash::default(),
+        priority_fee: None,
     };
 
     // Run the transaction & get tx outcome.
@@ -3541,6 +3619,7 @@ fn test_deploy_cost_increased() {
         actions: vec![Action::DeployContract(DeployContractAction { code: test_contract })],
         nonce: 0,
         block_hash: CryptoHash::default(),
+        priority_fee: None,
     };
 
     // Run the transaction & get tx outcome in a closure.
@@ -4813,7 +4892,8 @@ mod chunk_nodes_cache_test {
                 let metadata = receipt_execution_outcome.outcome_with_id.outcome.metadata.clone();
                 match metadata {
                     ExecutionMetadata::V1 => panic!("ExecutionMetadata cannot be empty"),
-                    ExecutionMetadata::V2(profile_data) => TrieNodesCount {
+                    ExecutionMetadata::V2(profile_data)
+                    | ExecutionMetadata::V3 { profile: profile_data, .. } => TrieNodesCount {
                         db_reads: {
                             let cost = profile_data.get_ext_cost(ExtCosts::touching_trie_node);
                             assert_eq!(cost % touching_trie_node_cost, 0);
@@ -4894,6 +4974,7 @@ mod lower_storage_key_limit_test {
 
             nonce: 0,
             block_hash: CryptoHash::default(),
+            priority_fee: None,
         };
 
         // Run transaction writing storage key exceeding the limit. Check that execution succeeds.
@@ -4977,6 +5058,7 @@ mod lower_storage_key_limit_test {
 
                 nonce: 0,
                 block_hash: CryptoHash::default(),
+                priority_fee: None,
             };
             let tip = env.clients[0].chain.head().unwrap();
             let signed_tx = Transaction {
@@ -4,6 +4,8 @@ mod full_network;
 mod infinite_loop;
 mod peer_handshake;
 mod peer_manager;
+mod relay;
 mod routing;
 mod runner;
 mod stress_network;
+mod warmup;
@@ -0,0 +1,35 @@
+use crate::tests::network::runner::*;
+
+/// Node 2 doesn't listen for connections and is reachable only through its
+/// relay, node 1.
+#[test]
+fn hidden_node_behind_relay() -> anyhow::Result<()> {
+    let mut runner = Runner::new(3, 3).relay_through(2, 1);
+
+    runner.push(Action::AddEdge { from: 0, to: 1, force: true });
+    runner.push_action(check_relayed_peer(1, 2));
+    runner.push_action(assert_expected_peers(2, vec![1]));
+    runner.push(Action::CheckRoutingTable(0, vec![(1, vec![1]), (2, vec![1])]));
+    runner.push(Action::CheckRoutingTable(2, vec![(1, vec![1]), (0, vec![1])]));
+    // Messages to the account of the hidden node, e.g. approvals, are routed
+    // to its peer id.
+    runner.push(Action::CheckAccountId(0, vec![0, 1, 2]));
+    runner.push(Action::PingTo(0, 0, 2));
+    runner.push(Action::CheckPingPong(2, vec![(0, 0, None)], vec![]));
+    runner.push(Action::CheckPingPong(0, vec![], vec![(0, 2, None)]));
+
+    start_test(runner)
+}
+
+/// The relay accepts no more relayed peers than configured.
+#[test]
+fn relay_capacity() -> anyhow::Result<()> {
+    let mut runner =
+        Runner::new(3, 3).relay_through(1, 0).relay_through(2, 0).max_relayed_peers(0, 1);
+
+    runner.push_action(check_expected_connections(0, Some(1), Some(1)));
+    runner.push(Action::Wait(tokio::time::Duration::from_millis(500)));
+    runner.push_action(check_expected_connections(0, Some(1), Some(1)));
+
+    start_test(runner)
+}
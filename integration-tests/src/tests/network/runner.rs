@@ -33,6 +33,7 @@ use near_primitives::network::PeerId;
 use near_primitives::types::{AccountId, ValidatorId};
 use near_primitives::validator_signer::InMemoryValidatorSigner;
 use near_store::test_utils::create_test_store;
+use near_store::Store;
 use near_telemetry::{TelemetryActor, TelemetryConfig};
 use std::pin::Pin;
 
@@ -41,12 +42,14 @@ pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
 pub type ActionFn =
     Box<dyn for<'a> Fn(&'a mut RunningInfo) -> BoxFuture<'a, anyhow::Result<ControlFlow>>>;
 
-/// Sets up a node with a valid Client, Peer
+/// Sets up a node with a valid Client, Peer.  The chain starts from scratch,
+/// while the network keeps what is in `network_store`.
 pub fn setup_network_node(
     account_id: AccountId,
     validators: Vec<AccountId>,
     chain_genesis: ChainGenesis,
     config: NetworkConfig,
+    network_store: Store,
 ) -> Addr<PeerManagerActor> {
     let store = create_test_store();
 
@@ -91,11 +94,13 @@ pub fn setup_network_node(
             adv,
         );
 
-        let routing_table_addr =
-            start_routing_table_actor(PeerId::new(config.public_key.clone()), store.clone());
+        let routing_table_addr = start_routing_table_actor(
+            PeerId::new(config.public_key.clone()),
+            network_store.clone(),
+        );
 
         PeerManagerActor::new(
-            store.clone(),
+            network_store,
             config,
             client_actor.recipient(),
             view_client_actor.recipient(),
@@ -353,6 +358,11 @@ struct TestConfig {
     minimum_outbound_peers: Option<u32>,
     safe_set_size: Option<u32>,
     archive: bool,
+    /// Relay of the node, which then doesn't listen for connections.
+    relay: Option<usize>,
+    max_relayed_peers: u32,
+    /// Store of the network, i.e. known peers, which is kept across restarts.
+    network_store: Store,
 
     account_id: AccountId,
     port: u16,
@@ -372,6 +382,9 @@ impl TestConfig {
             minimum_outbound_peers: None,
             safe_set_size: None,
             archive: false,
+            relay: None,
+            max_relayed_peers: 0,
+            network_store: create_test_store(),
 
             account_id: format!("test{}", id).parse().unwrap(),
             port: open_port(),
@@ -457,6 +470,20 @@ impl Runner {
         self
     }
 
+    /// Hide node `u` behind node `v`: `u` doesn't listen for connections and
+    /// keeps a connection to its relay `v`, which forwards messages to it.
+    pub fn relay_through(mut self, u: usize, v: usize) -> Self {
+        self.test_config[u].relay = Some(v);
+        self.test_config[v].max_relayed_peers += 1;
+        self
+    }
+
+    /// Set the maximum number of peers node `u` relays messages to.
+    pub fn max_relayed_peers(mut self, u: usize, max_relayed_peers: u32) -> Self {
+        self.test_config[u].max_relayed_peers = max_relayed_peers;
+        self
+    }
+
     /// Specify boot nodes. By default there are no boot nodes.
     pub fn use_boot_nodes(mut self, boot_nodes: Vec<usize>) -> Self {
         self.apply_all(move |test_config| {
@@ -519,6 +546,12 @@ impl Runner {
         self
     }
 
+    /// Allow node `u` to connect to other nodes without triggering connections.
+    pub fn enable_outbound_for(mut self, u: usize) -> Self {
+        self.test_config[u].outbound_disabled = false;
+        self
+    }
+
     /// Add an action to be executed by the Runner. Actions are executed sequentially.
     /// Each action is executed after the previous action succeed.
     pub fn push(&mut self, action: Action) {
@@ -569,6 +602,11 @@ impl Runner {
         network_config.outbound_disabled = config.outbound_disabled;
         network_config.boot_nodes = boot_nodes;
         network_config.archive = config.archive;
+        network_config.max_relayed_peers = config.max_relayed_peers;
+        if let Some(relay) = config.relay {
+            network_config.addr = None;
+            network_config.relay_peers = vec![self.test_config[relay].peer_info()];
+        }
 
         config.ideal_connections.map(|(lo, hi)| {
             network_config.ideal_connections_lo = lo;
@@ -587,6 +625,7 @@ impl Runner {
             let account_id = config.account_id.clone();
             let validators = self.validators.clone();
             let chain_genesis = self.chain_genesis.clone();
+            let network_store = config.network_store.clone();
             move || {
                 actix::System::new().block_on(async move {
                     send_pm
@@ -595,6 +634,7 @@ impl Runner {
                             validators,
                             chain_genesis,
                             network_config,
+                            network_store,
                         ))
                         .map_err(|_| anyhow!("send failed"))?;
                     // recv_stop is expected to get closed.
@@ -694,6 +734,53 @@ pub fn assert_expected_peers(node_id: usize, peers: Vec<usize>) -> ActionFn {
     })
 }
 
+/// Check that `relay` relays messages to `node_id`, i.e. it advertises
+/// `node_id` as reachable through itself.
+pub fn check_relayed_peer(relay: usize, node_id: usize) -> ActionFn {
+    Box::new(move |info: &mut RunningInfo| {
+        Box::pin(async move {
+            let pm = &info.get_node(relay)?.addr;
+            let network_info = pm.send(GetInfo {}).await?;
+            let peer_id = info.runner.test_config[node_id].peer_id();
+            let relay_id = info.runner.test_config[relay].peer_id();
+            let peer_info = match network_info
+                .connected_peers
+                .into_iter()
+                .find(|peer| peer.peer_info.id == peer_id)
+            {
+                Some(peer) => peer.peer_info,
+                None => return Ok(ControlFlow::Continue(())),
+            };
+            if peer_info.addr.is_some() || peer_info.relay != Some(relay_id) {
+                bail!("node {node_id} isn't relayed by node {relay}: {peer_info:?}");
+            }
+            Ok(ControlFlow::Break(()))
+        })
+    })
+}
+
+/// Check that `node_id` connects to all of `first` before any other peer.
+pub fn check_connected_first(node_id: usize, first: Vec<usize>) -> ActionFn {
+    Box::new(move |info: &mut RunningInfo| {
+        let first = first.clone();
+        Box::pin(async move {
+            let pm = &info.get_node(node_id)?.addr;
+            let network_info = pm.send(GetInfo {}).await?;
+            let got: HashSet<_> =
+                network_info.connected_peers.into_iter().map(|i| i.peer_info.id).collect();
+            let first: HashSet<_> =
+                first.iter().map(|i| info.runner.test_config[*i].peer_id()).collect();
+            if got.is_superset(&first) {
+                return Ok(ControlFlow::Break(()));
+            }
+            if !got.is_subset(&first) {
+                bail!("node {node_id} has peers {got:?} before connecting to {first:?}");
+            }
+            Ok(ControlFlow::Continue(()))
+        })
+    })
+}
+
 /// Check that the number of connections of `node_id` is in the range:
 /// [expected_connections_lo, expected_connections_hi]
 /// Use None to denote semi-open interval
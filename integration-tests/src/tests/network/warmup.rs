@@ -0,0 +1,22 @@
+use crate::tests::network::runner::*;
+use std::time::Duration;
+
+/// After a restart node 4 connects to the validators it was connected to,
+/// nodes 0 and 1, before the other peers it knows.
+#[test]
+fn reconnect_to_validators_first() -> anyhow::Result<()> {
+    let mut runner = Runner::new(5, 2).enable_outbound_for(4);
+
+    for peer in 0..4 {
+        runner.push(Action::AddEdge { from: 4, to: peer, force: true });
+    }
+    runner.push(Action::CheckAccountId(4, vec![0, 1]));
+    // Give node 4 time to save its connections.
+    runner.push(Action::Wait(Duration::from_millis(500)));
+    runner.push(Action::Stop(4));
+    runner.push_action(restart(4));
+    runner.push_action(check_connected_first(4, vec![0, 1]));
+    runner.push_action(check_expected_connections(4, Some(4), None));
+
+    start_test(runner)
+}
@@ -685,7 +685,7 @@ pub fn test_create_account_failure_already_exists(node: impl Node) {
 
 pub fn test_swap_key(node: impl Node) {
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     let node_user = node.user();
     let root = node_user.get_state_root();
     let money_used = TESTING_INIT_BALANCE / 2;
@@ -721,7 +721,7 @@ pub fn test_swap_key(node: impl Node) {
 
 pub fn test_add_key(node: impl Node) {
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     let node_user = node.user();
 
     add_access_key(&node, node_user.as_ref(), &AccessKey::full_access(), &signer2);
@@ -759,7 +759,7 @@ pub fn test_add_existing_key(node: impl Node) {
 
 pub fn test_delete_key(node: impl Node) {
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     let node_user = node.user();
     add_access_key(&node, node_user.as_ref(), &AccessKey::full_access(), &signer2);
 
@@ -780,7 +780,7 @@ pub fn test_delete_key(node: impl Node) {
 
 pub fn test_delete_key_not_owned(node: impl Node) {
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     let node_user = node.user();
 
     assert!(node_user.get_access_key(account_id, &node.signer().public_key()).is_ok());
@@ -876,7 +876,7 @@ pub fn test_add_access_key_function_call(node: impl Node) {
             method_names: vec![],
         }),
     };
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     let result = add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
 
     assert!(node_user.get_access_key(account_id, &node.signer().public_key()).is_ok());
@@ -896,7 +896,7 @@ pub fn test_delete_access_key(node: impl Node) {
             method_names: vec![],
         }),
     };
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
 
     assert!(node_user.get_access_key(account_id, &node.signer().public_key()).is_ok());
@@ -925,7 +925,7 @@ pub fn test_add_access_key_with_allowance(node: impl Node) {
         }),
     };
     let node_user = node.user();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     let account = node_user.view_account(account_id).unwrap();
     let initial_balance = account.amount;
     let fee_helper = fee_helper(&node);
@@ -951,7 +951,7 @@ pub fn test_delete_access_key_with_allowance(node: impl Node) {
         }),
     };
     let node_user = node.user();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     let account = node_user.view_account(account_id).unwrap();
     let initial_balance = account.amount;
     let fee_helper = fee_helper(&node);
@@ -988,7 +988,8 @@ pub fn test_access_key_smart_contract(node: impl Node) {
     };
     let mut node_user = node.user();
     let account_id = &node.account_id().unwrap();
-    let signer2 = Arc::new(InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519));
+    let signer2 =
+        Arc::new(InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test"));
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
     node_user.set_signer(signer2.clone());
 
@@ -1040,7 +1041,7 @@ pub fn test_access_key_smart_contract_reject_method_name(node: impl Node) {
     };
     let mut node_user = node.user();
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
     node_user.set_signer(Arc::new(signer2));
 
@@ -1068,7 +1069,7 @@ pub fn test_access_key_smart_contract_reject_contract_id(node: impl Node) {
     };
     let mut node_user = node.user();
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
     node_user.set_signer(Arc::new(signer2));
 
@@ -1104,7 +1105,7 @@ pub fn test_access_key_reject_non_function_call(node: impl Node) {
         }),
     };
     let mut node_user = node.user();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
     node_user.set_signer(Arc::new(signer2));
 
@@ -1422,6 +1423,7 @@ fn make_receipt(node: &impl Node, actions: Vec<Action>, receiver_id: AccountId)
         output_data_receivers: vec![],
         input_data_ids: vec![],
         actions,
+        congestion: None,
     });
     Receipt {
         predecessor_id: alice_account(),
@@ -41,6 +41,7 @@ use near_rosetta_rpc::RosettaRpcConfig;
 use near_telemetry::TelemetryConfig;
 
 use crate::download_file::{run_download_file, FileDownloadError};
+use crate::instrumented_signer::InstrumentedValidatorSigner;
 
 /// Initial balance used in tests.
 pub const TESTING_INIT_BALANCE: Balance = 1_000_000_000 * NEAR_BASE;
@@ -196,6 +197,22 @@ fn default_peer_stats_period() -> Duration {
     Duration::from_secs(5)
 }
 
+fn default_relayed_peer_bandwidth_limit() -> u64 {
+    10_000_000
+}
+
+fn default_peer_chain_info_staleness() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_route_back_cache_capacity() -> usize {
+    100_000
+}
+
+fn default_route_back_cache_ttl() -> Duration {
+    Duration::from_secs(120)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Network {
     /// Address to listen for incoming connections.
@@ -253,6 +270,29 @@ pub struct Network {
     /// Period to check on peer status
     #[serde(default = "default_peer_stats_period")]
     pub peer_stats_period: Duration,
+    /// Comma separated list of nodes relaying messages to this node, for nodes
+    /// which can't accept inbound connections, e.g. behind a NAT.  For each
+    /// relay node specifying both PeerId and IP:port is required.
+    #[serde(default)]
+    pub relay_nodes: String,
+    /// Maximum number of nodes this node relays messages to.  Zero disables
+    /// relaying.
+    #[serde(default)]
+    pub max_relayed_peers: u32,
+    /// Bytes per second of messages forwarded to each relayed node.
+    #[serde(default = "default_relayed_peer_bandwidth_limit")]
+    pub relayed_peer_bandwidth_limit: u64,
+    /// Peers whose chain info wasn't updated for this long are asked for it
+    /// again and not used for sync until they respond.
+    #[serde(default = "default_peer_chain_info_staleness")]
+    pub peer_chain_info_staleness: Duration,
+    /// Maximum number of routed messages remembered to route their responses
+    /// back.
+    #[serde(default = "default_route_back_cache_capacity")]
+    pub route_back_cache_capacity: usize,
+    /// Responses to routed messages arriving later than this are dropped.
+    #[serde(default = "default_route_back_cache_ttl")]
+    pub route_back_cache_ttl: Duration,
 }
 
 impl Default for Network {
@@ -276,6 +316,12 @@ impl Default for Network {
             blacklist: vec![],
             ttl_account_id_router: default_ttl_account_id_router(),
             peer_stats_period: default_peer_stats_period(),
+            relay_nodes: "".to_string(),
+            max_relayed_peers: 0,
+            relayed_peer_bandwidth_limit: default_relayed_peer_bandwidth_limit(),
+            peer_chain_info_staleness: default_peer_chain_info_staleness(),
+            route_back_cache_capacity: default_route_back_cache_capacity(),
+            route_back_cache_ttl: default_route_back_cache_ttl(),
         }
     }
 }
@@ -458,6 +504,10 @@ pub struct Config {
     pub db_migration_snapshot_path: Option<PathBuf>,
     /// Different parameters to configure/optimize underlying storage.
     pub store: near_store::StoreConfig,
+    /// Whether to save the gas of each action in execution outcomes. Defaults to `archive`, as
+    /// validators which don't serve RPC have little use for it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_action_gas_breakdown: Option<bool>,
 }
 
 impl Default for Config {
@@ -487,6 +537,7 @@ impl Default for Config {
             db_migration_snapshot_path: None,
             use_db_migration_snapshot: true,
             store: near_store::StoreConfig::read_write(),
+            save_action_gas_breakdown: None,
         }
     }
 }
@@ -515,6 +566,10 @@ impl Config {
         file.write_all(str.as_bytes())
     }
 
+    pub fn save_action_gas_breakdown(&self) -> bool {
+        self.save_action_gas_breakdown.unwrap_or(self.archive)
+    }
+
     pub fn rpc_addr(&self) -> Option<&str> {
         #[cfg(feature = "json_rpc")]
         if let Some(rpc) = &self.rpc {
@@ -762,6 +817,21 @@ impl NearConfig {
                 blacklist: config.network.blacklist,
                 outbound_disabled: false,
                 archive: config.archive,
+                relay_peers: if config.network.relay_nodes.is_empty() {
+                    vec![]
+                } else {
+                    config
+                        .network
+                        .relay_nodes
+                        .split(',')
+                        .map(|chunk| chunk.try_into().expect("Failed to parse PeerInfo"))
+                        .collect()
+                },
+                max_relayed_peers: config.network.max_relayed_peers,
+                relayed_peer_bandwidth_limit: config.network.relayed_peer_bandwidth_limit,
+                peer_chain_info_staleness: config.network.peer_chain_info_staleness,
+                route_back_cache_capacity: config.network.route_back_cache_capacity,
+                route_back_cache_ttl: config.network.route_back_cache_ttl,
             },
             telemetry_config: config.telemetry,
             #[cfg(feature = "json_rpc")]
@@ -1393,7 +1463,7 @@ pub fn load_config(
         let signer = InMemoryValidatorSigner::from_file(&validator_file).with_context(|| {
             format!("Failed initializing validator signer from {}", validator_file.display())
         })?;
-        Some(Arc::new(signer) as Arc<dyn ValidatorSigner>)
+        Some(Arc::new(InstrumentedValidatorSigner::new(signer)) as Arc<dyn ValidatorSigner>)
     } else {
         None
     };
@@ -0,0 +1,206 @@
+//! Validator signer decorator exporting signing latency metrics.
+use std::path::Path;
+use std::time::Instant;
+
+use near_crypto::{PublicKey, Signature};
+use near_metrics::Histogram;
+use near_primitives::block::ApprovalInner;
+use near_primitives::challenge::ChallengeBody;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::telemetry::TelemetryInfo;
+use near_primitives::types::{AccountId, BlockHeight, EpochId};
+use near_primitives::validator_signer::ValidatorSigner;
+
+use crate::metrics::VALIDATOR_SIGNING_LATENCY;
+
+/// Metric handles of one message class, resolved once so that signing
+/// doesn't have to look up labels.
+struct ClassMetrics {
+    latency: Histogram,
+}
+
+impl ClassMetrics {
+    fn new(class: &str) -> Self {
+        Self { latency: VALIDATOR_SIGNING_LATENCY.with_label_values(&[class]) }
+    }
+
+    fn measure<T>(&self, sign: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = sign();
+        self.latency.observe(start.elapsed().as_secs_f64());
+        result
+    }
+}
+
+/// `ValidatorSigner` which records how long each kind of signature takes.
+pub struct InstrumentedValidatorSigner<S> {
+    inner: S,
+    block: ClassMetrics,
+    chunk: ClassMetrics,
+    approval: ClassMetrics,
+    telemetry: ClassMetrics,
+    challenge: ClassMetrics,
+    account_announce: ClassMetrics,
+    vrf: ClassMetrics,
+}
+
+impl<S: ValidatorSigner> InstrumentedValidatorSigner<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            block: ClassMetrics::new("block"),
+            chunk: ClassMetrics::new("chunk"),
+            approval: ClassMetrics::new("approval"),
+            telemetry: ClassMetrics::new("telemetry"),
+            challenge: ClassMetrics::new("challenge"),
+            account_announce: ClassMetrics::new("account_announce"),
+            vrf: ClassMetrics::new("vrf"),
+        }
+    }
+}
+
+impl<S: ValidatorSigner> ValidatorSigner for InstrumentedValidatorSigner<S> {
+    fn validator_id(&self) -> &AccountId {
+        self.inner.validator_id()
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.inner.public_key()
+    }
+
+    fn sign_telemetry(&self, info: &TelemetryInfo) -> serde_json::Value {
+        self.telemetry.measure(|| self.inner.sign_telemetry(info))
+    }
+
+    fn sign_block_header_parts(
+        &self,
+        prev_hash: CryptoHash,
+        inner_lite: &[u8],
+        inner_rest: &[u8],
+    ) -> (CryptoHash, Signature) {
+        self.block.measure(|| self.inner.sign_block_header_parts(prev_hash, inner_lite, inner_rest))
+    }
+
+    fn sign_chunk_hash(&self, chunk_hash: &ChunkHash) -> Signature {
+        self.chunk.measure(|| self.inner.sign_chunk_hash(chunk_hash))
+    }
+
+    fn sign_approval(&self, inner: &ApprovalInner, target_height: BlockHeight) -> Signature {
+        self.approval.measure(|| self.inner.sign_approval(inner, target_height))
+    }
+
+    fn sign_challenge(&self, challenge_body: &ChallengeBody) -> (CryptoHash, Signature) {
+        self.challenge.measure(|| self.inner.sign_challenge(challenge_body))
+    }
+
+    fn sign_account_announce(
+        &self,
+        account_id: &AccountId,
+        peer_id: &PeerId,
+        epoch_id: &EpochId,
+    ) -> Signature {
+        self.account_announce
+            .measure(|| self.inner.sign_account_announce(account_id, peer_id, epoch_id))
+    }
+
+    fn compute_vrf_with_proof(
+        &self,
+        data: &[u8],
+    ) -> (near_crypto::vrf::Value, near_crypto::vrf::Proof) {
+        self.vrf.measure(|| self.inner.compute_vrf_with_proof(data))
+    }
+
+    fn write_to_file(&self, path: &Path) -> std::io::Result<()> {
+        self.inner.write_to_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::KeyType;
+    use near_primitives::validator_signer::InMemoryValidatorSigner;
+    use std::time::Duration;
+
+    /// Signer which takes `delay` to sign approvals.
+    struct SlowSigner {
+        inner: InMemoryValidatorSigner,
+        delay: Duration,
+    }
+
+    impl ValidatorSigner for SlowSigner {
+        fn validator_id(&self) -> &AccountId {
+            self.inner.validator_id()
+        }
+
+        fn public_key(&self) -> PublicKey {
+            self.inner.public_key()
+        }
+
+        fn sign_telemetry(&self, info: &TelemetryInfo) -> serde_json::Value {
+            self.inner.sign_telemetry(info)
+        }
+
+        fn sign_block_header_parts(
+            &self,
+            prev_hash: CryptoHash,
+            inner_lite: &[u8],
+            inner_rest: &[u8],
+        ) -> (CryptoHash, Signature) {
+            self.inner.sign_block_header_parts(prev_hash, inner_lite, inner_rest)
+        }
+
+        fn sign_chunk_hash(&self, chunk_hash: &ChunkHash) -> Signature {
+            self.inner.sign_chunk_hash(chunk_hash)
+        }
+
+        fn sign_approval(&self, inner: &ApprovalInner, target_height: BlockHeight) -> Signature {
+            std::thread::sleep(self.delay);
+            self.inner.sign_approval(inner, target_height)
+        }
+
+        fn sign_challenge(&self, challenge_body: &ChallengeBody) -> (CryptoHash, Signature) {
+            self.inner.sign_challenge(challenge_body)
+        }
+
+        fn sign_account_announce(
+            &self,
+            account_id: &AccountId,
+            peer_id: &PeerId,
+            epoch_id: &EpochId,
+        ) -> Signature {
+            self.inner.sign_account_announce(account_id, peer_id, epoch_id)
+        }
+
+        fn compute_vrf_with_proof(
+            &self,
+            data: &[u8],
+        ) -> (near_crypto::vrf::Value, near_crypto::vrf::Proof) {
+            self.inner.compute_vrf_with_proof(data)
+        }
+
+        fn write_to_file(&self, path: &Path) -> std::io::Result<()> {
+            self.inner.write_to_file(path)
+        }
+    }
+
+    #[test]
+    fn test_records_signing_latency() {
+        let inner =
+            InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let expected = inner.sign_approval(&ApprovalInner::Skip(1), 2);
+        let signer = InstrumentedValidatorSigner::new(SlowSigner {
+            inner,
+            delay: Duration::from_millis(50),
+        });
+        let approval = VALIDATOR_SIGNING_LATENCY.with_label_values(&["approval"]);
+        let (count, sum) = (approval.get_sample_count(), approval.get_sample_sum());
+
+        // Transparent to callers.
+        assert_eq!(signer.sign_approval(&ApprovalInner::Skip(1), 2), expected);
+        assert_eq!(approval.get_sample_count(), count + 1);
+        assert!(approval.get_sample_sum() - sum >= 0.05);
+    }
+}
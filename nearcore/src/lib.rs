@@ -1,5 +1,5 @@
 pub use crate::config::{init_configs, load_config, load_test_config, NearConfig, NEAR_BASE};
-use crate::migrations::migrate_30_to_31;
+use crate::migrations::{migrate_30_to_31, migrate_31_to_32};
 pub use crate::runtime::NightshadeRuntime;
 pub use crate::shard_tracker::TrackedConfig;
 use actix::{Actor, Addr, Arbiter};
@@ -12,6 +12,8 @@ use near_network::routing::start_routing_table_actor;
 use near_network::test_utils::NetworkRecipient;
 use near_network::PeerManagerActor;
 use near_primitives::network::PeerId;
+use near_primitives::time::Clock;
+use near_primitives::utils::to_timestamp;
 use near_primitives::version::DbVersion;
 #[cfg(feature = "rosetta_rpc")]
 use near_rosetta_rpc::start_rosetta_rpc;
@@ -21,7 +23,7 @@ use near_store::db::RocksDB;
 use near_store::migrations::{
     get_store_version, migrate_28_to_29, migrate_29_to_30, set_store_version,
 };
-use near_store::{create_store, create_store_with_config, DBCol, Store};
+use near_store::{create_store, create_store_with_config, DBCol, DbOpen, Store};
 use near_telemetry::TelemetryActor;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -32,6 +34,7 @@ use tracing::{error, info, trace};
 pub mod append_only_map;
 pub mod config;
 mod download_file;
+pub mod instrumented_signer;
 mod metrics;
 pub mod migrations;
 mod runtime;
@@ -177,6 +180,11 @@ fn apply_store_migrations(path: &Path, near_config: &NearConfig) -> anyhow::Resu
         info!(target: "near", "Migrate DB from version 30 to 31");
         migrate_30_to_31(path, &near_config);
     }
+    if db_version <= 31 {
+        // version 31 => 32: add saved direct connections to known peers
+        info!(target: "near", "Migrate DB from version 31 to 32");
+        migrate_31_to_32(path);
+    }
 
     if cfg!(feature = "nightly") || cfg!(feature = "nightly_protocol") {
         let store = create_store(&path);
@@ -238,6 +246,19 @@ fn init_and_migrate_store(home_dir: &Path, near_config: &NearConfig) -> anyhow::
         update.commit()?;
     }
 
+    // Keep track of the binaries which opened the database, e.g. to tell
+    // whether a node was rolled back.
+    let version = &near_config.client_config.version;
+    store.record_open(DbOpen {
+        neard_version: format!("{} (build {})", version.version, version.build),
+        protocol_version: near_primitives::version::PROTOCOL_VERSION,
+        db_version: near_primitives::version::DB_VERSION,
+        timestamp: to_timestamp(Clock::utc()),
+    })?;
+    for open in store.open_history()? {
+        info!(target: "near", "Database opened at {}", open);
+    }
+
     Ok(store)
 }
 
@@ -24,3 +24,13 @@ pub static SECONDS_PER_PETAGAS: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static VALIDATOR_SIGNING_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    try_create_histogram_vec(
+        "near_validator_signing_latency_seconds",
+        "Time taken by the validator signer, by kind of signed message",
+        &["class"],
+        Some(prometheus::exponential_buckets(0.00001, 2.0, 20).unwrap()),
+    )
+    .unwrap()
+});
@@ -1,9 +1,11 @@
+use borsh::BorshDeserialize;
 use near_chain::{ChainStore, ChainStoreAccess};
+use near_network_primitives::types::{KnownPeerState, KnownPeerStatus, PeerInfo};
 use near_primitives::receipt::ReceiptResult;
 use near_primitives::runtime::migration_data::MigrationData;
 use near_primitives::types::Gas;
 use near_primitives::utils::index_to_bytes;
-use near_store::migrations::{set_store_version, BatchedStoreUpdate};
+use near_store::migrations::{map_col, set_store_version, BatchedStoreUpdate};
 use near_store::{create_store, DBCol};
 use std::path::Path;
 
@@ -45,6 +47,28 @@ pub fn migrate_30_to_31(path: &Path, near_config: &crate::NearConfig) {
     set_store_version(&store, 31);
 }
 
+/// Add saved direct connections to known peers
+pub fn migrate_31_to_32(path: &Path) {
+    #[derive(BorshDeserialize)]
+    struct OldKnownPeerState {
+        peer_info: PeerInfo,
+        status: KnownPeerStatus,
+        first_seen: u64,
+        last_seen: u64,
+    }
+
+    let store = create_store(path);
+    map_col(&store, DBCol::Peers, |state: OldKnownPeerState| KnownPeerState {
+        peer_info: state.peer_info,
+        status: state.status,
+        first_seen: state.first_seen,
+        last_seen: state.last_seen,
+        saved_connection: None,
+    })
+    .expect("Failed to migrate");
+    set_store_version(&store, 32);
+}
+
 lazy_static_include::lazy_static_include_bytes! {
     /// File with account ids and deltas that need to be applied in order to fix storage usage
     /// difference between actual and stored usage, introduced due to bug in access key deletion,
@@ -55,7 +55,6 @@ use near_store::{
 };
 use near_vm_runner::precompile_contract;
 use node_runtime::adapter::ViewRuntimeAdapter;
-use node_runtime::config::RuntimeConfig;
 use node_runtime::near_primitives::shard_layout::ShardLayoutError;
 use node_runtime::state_viewer::TrieViewer;
 use node_runtime::{
@@ -141,6 +140,8 @@ pub struct NightshadeRuntime {
     genesis_state_roots: Vec<StateRoot>,
     migration_data: Arc<MigrationData>,
     gc_num_epochs_to_keep: u64,
+    /// Whether to keep the per action gas breakdown in execution outcomes.
+    save_action_gas_breakdown: bool,
 }
 
 impl NightshadeRuntime {
@@ -151,7 +152,7 @@ impl NightshadeRuntime {
         trie_viewer_state_size_limit: Option<u64>,
         max_gas_burnt_view: Option<Gas>,
     ) -> Self {
-        Self::new(
+        let mut runtime = Self::new(
             home_dir,
             store,
             &config.genesis,
@@ -160,7 +161,9 @@ impl NightshadeRuntime {
             max_gas_burnt_view,
             None,
             config.config.gc.gc_num_epochs_to_keep(),
-        )
+        );
+        runtime.save_action_gas_breakdown = config.config.save_action_gas_breakdown();
+        runtime
     }
 
     pub fn new(
@@ -175,7 +178,7 @@ impl NightshadeRuntime {
     ) -> Self {
         let runtime_config_store = match runtime_config_store {
             Some(store) => store,
-            None => NightshadeRuntime::create_runtime_config_store(&genesis.config.chain_id),
+            None => RuntimeConfigStore::for_chain_id(&genesis.config.chain_id),
         };
 
         let runtime = Runtime::new();
@@ -212,6 +215,7 @@ impl NightshadeRuntime {
             genesis_state_roots: state_roots,
             migration_data: Arc::new(load_migration_data(&genesis.config.chain_id)),
             gc_num_epochs_to_keep: gc_num_epochs_to_keep.max(MIN_GC_NUM_EPOCHS_TO_KEEP),
+            save_action_gas_breakdown: true,
         }
     }
 
@@ -251,22 +255,6 @@ impl NightshadeRuntime {
         epoch_manager.get_epoch_id(hash).map_err(Error::from)
     }
 
-    /// Create store of runtime configs for the given chain id.
-    ///
-    /// For mainnet and other chains except testnet we don't need to override runtime config for
-    /// first protocol versions.
-    /// For testnet, runtime config for genesis block was (incorrectly) different, that's why we
-    /// need to override it specifically to preserve compatibility.
-    fn create_runtime_config_store(chain_id: &str) -> RuntimeConfigStore {
-        match chain_id {
-            "testnet" => {
-                let genesis_runtime_config = RuntimeConfig::initial_testnet_config();
-                RuntimeConfigStore::new(Some(&genesis_runtime_config))
-            }
-            _ => RuntimeConfigStore::new(None),
-        }
-    }
-
     fn genesis_state_from_dump(store: Store, home_dir: &Path) -> Vec<StateRoot> {
         error!(target: "near", "Loading genesis from a state dump file. Do not use this outside of genesis-tools");
         let mut state_file = home_dir.to_path_buf();
@@ -307,8 +295,7 @@ impl NightshadeRuntime {
         assert!(has_protocol_account, "Genesis spec doesn't have protocol treasury account");
         let tries = ShardTries::new(store, genesis.config.shard_layout.version(), num_shards);
         let runtime = Runtime::new();
-        let runtime_config_store =
-            NightshadeRuntime::create_runtime_config_store(&genesis.config.chain_id);
+        let runtime_config_store = RuntimeConfigStore::for_chain_id(&genesis.config.chain_id);
         let runtime_config = runtime_config_store.get_config(genesis.config.protocol_version);
 
         for shard_id in 0..num_shards {
@@ -598,6 +585,13 @@ impl NightshadeRuntime {
 
         let shard_uid = self.get_shard_uid_from_prev_hash(shard_id, prev_block_hash)?;
 
+        let mut outcomes = apply_result.outcomes;
+        if !self.save_action_gas_breakdown {
+            for outcome_with_id in outcomes.iter_mut() {
+                outcome_with_id.outcome.metadata.clear_action_gas_breakdown();
+            }
+        }
+
         let result = ApplyTransactionResult {
             trie_changes: WrappedTrieChanges::new(
                 self.get_tries(),
@@ -607,7 +601,7 @@ impl NightshadeRuntime {
                 *block_hash,
             ),
             new_root: apply_result.state_root,
-            outcomes: apply_result.outcomes,
+            outcomes,
             outgoing_receipts: apply_result.outgoing_receipts,
             validator_proposals: apply_result.validator_proposals,
             total_gas_burnt,
@@ -738,15 +732,13 @@ impl RuntimeAdapter for NightshadeRuntime {
     ) -> Result<(), Error> {
         let epoch_manager = self.epoch_manager.read();
         let validator = epoch_manager.get_block_producer_info(epoch_id, block_height)?;
-        let public_key = near_crypto::key_conversion::convert_public_key(
-            validator.public_key().unwrap_as_ed25519(),
-        )
-        .unwrap();
+        let public_key = near_crypto::vrf::PublicKey::try_from(validator.public_key())
+            .map_err(|_| Error::InvalidRandomnessBeaconOutput)?;
 
-        if !public_key.is_vrf_valid(&prev_random_value.as_ref(), vrf_value, vrf_proof) {
-            return Err(Error::InvalidRandomnessBeaconOutput.into());
+        match vrf_proof.verify(&public_key, prev_random_value.as_ref(), vrf_value) {
+            Some(_) => Ok(()),
+            None => Err(Error::InvalidRandomnessBeaconOutput),
         }
-        Ok(())
     }
 
     fn validate_tx(
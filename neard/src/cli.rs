@@ -6,7 +6,7 @@ use near_o11y::{
     default_subscriber, BuildEnvFilterError, ColorOutput, DefaultSubcriberGuard, EnvFilterBuilder,
 };
 use near_primitives::types::{Gas, NumSeats, NumShards};
-use near_state_viewer::StateViewerSubCommand;
+use near_state_viewer::{OutputFormat, StateViewerSubCommand};
 use near_store::db::RocksDB;
 use nearcore::get_store_path;
 use std::cell::Cell;
@@ -89,7 +89,7 @@ impl NeardCmd {
             }
 
             NeardSubCommand::StateViewer(cmd) => {
-                cmd.subcmd.run(&home_dir, genesis_validation, cmd.readwrite);
+                cmd.subcmd.run(&home_dir, genesis_validation, cmd.readwrite, cmd.format);
             }
 
             NeardSubCommand::RecompressStorage(cmd) => {
@@ -132,6 +132,10 @@ pub(super) struct StateViewerCommand {
     /// In case an operation needs to write to caches, a read-write mode may be needed.
     #[clap(long, short = 'w')]
     readwrite: bool,
+    /// Format of the output.  Subcommands which print reports or lookups also
+    /// support `json`, one object per line, and most of them `csv`.
+    #[clap(long, arg_enum, default_value = "human")]
+    format: OutputFormat,
     #[clap(subcommand)]
     subcmd: StateViewerSubCommand,
 }
@@ -225,6 +225,26 @@ pub enum HostError {
     /// on the curve).
     #[cfg(feature = "protocol_feature_alt_bn128")]
     AltBn128InvalidInput { msg: String },
+    // New variants go last: borsh encodes the variant index, so inserting one anywhere else
+    // would change the encoding of the variants after it.
+    /// An action receipt created by the contract would exceed the `limit_name` limit.
+    ReceiptLimitExceeded { limit_name: String, value: u64, limit: u64 },
+    /// Receipt `receipt_index` can't be cancelled or replaced by its output while its output
+    /// is sent to other receipts or returned by the contract.
+    ReceiptHasDependents { receipt_index: u64 },
+    /// `CreateAccount` action for implicit account `account_id`, which can only be created by a
+    /// transfer.
+    CreateImplicitAccount { account_id: String },
+    /// Receipt `receipt_index` adds the access key `public_key` twice without deleting it in
+    /// between.
+    DuplicateAddKey { receipt_index: u64, public_key: String },
+    /// Receipt `receipt_index` deletes the access key `public_key` twice without adding it in
+    /// between.
+    DuplicateDeleteKey { receipt_index: u64, public_key: String },
+    /// The method name at `index` of the methods allowed by an access key isn't valid UTF-8.
+    InvalidAccessKeyMethodName { index: u64 },
+    /// Receipt `receipt_index` can't be replaced by its output since it has actions.
+    ReceiptHasActions { receipt_index: u64 },
 }
 
 #[derive(Debug, PartialEq)]
@@ -423,6 +443,13 @@ impl std::fmt::Display for HostError {
             #[cfg(feature = "protocol_feature_alt_bn128")]
             AltBn128InvalidInput { msg } => write!(f, "AltBn128 invalid input: {}", msg),
             ECRecoverError { msg } => write!(f, "ECDSA recover error: {}", msg),
+            ReceiptLimitExceeded { limit_name, value, limit } => write!(f, "Action receipt {} {} exceeds the limit {} by {}", limit_name, value, limit, value - limit),
+            ReceiptHasDependents { receipt_index } => write!(f, "Receipt {} can't be cancelled or replaced while its output is used", receipt_index),
+            CreateImplicitAccount { account_id } => write!(f, "Implicit account {} can only be created by a transfer, not by a CreateAccount action", account_id),
+            DuplicateAddKey { receipt_index, public_key } => write!(f, "Receipt {} already adds the access key {}", receipt_index, public_key),
+            DuplicateDeleteKey { receipt_index, public_key } => write!(f, "Receipt {} already deletes the access key {}", receipt_index, public_key),
+            InvalidAccessKeyMethodName { index } => write!(f, "Method name {} allowed by the access key is not valid UTF-8", index),
+            ReceiptHasActions { receipt_index } => write!(f, "Receipt {} can't be replaced by its output since it has actions", receipt_index),
         }
     }
 }
@@ -485,7 +512,39 @@ impl<T: Any + Eq + Sized + Send + Sync> AnyEq for T {
 
 #[cfg(test)]
 mod tests {
-    use crate::{CompilationError, FunctionCallError, MethodResolveError, PrepareError, VMError};
+    use crate::{
+        CompilationError, FunctionCallError, HostError, MethodResolveError, PrepareError, VMError,
+    };
+    use borsh::BorshSerialize;
+
+    /// The borsh discriminant of `error`, which is part of the outcome of function calls.
+    fn discriminant(error: HostError) -> u8 {
+        error.try_to_vec().unwrap()[0]
+    }
+
+    #[test]
+    fn test_host_error_discriminants() {
+        assert_eq!(discriminant(HostError::BadUTF16), 0);
+        assert_eq!(discriminant(HostError::InvalidMethodName), 18);
+        assert_eq!(discriminant(HostError::InvalidPublicKey), 19);
+        assert_eq!(discriminant(HostError::ECRecoverError { msg: String::new() }), 30);
+        #[cfg(feature = "protocol_feature_alt_bn128")]
+        assert_eq!(discriminant(HostError::AltBn128InvalidInput { msg: String::new() }), 31);
+
+        let first_new = if cfg!(feature = "protocol_feature_alt_bn128") { 32 } else { 31 };
+        let new_variants = [
+            HostError::ReceiptLimitExceeded { limit_name: String::new(), value: 0, limit: 0 },
+            HostError::ReceiptHasDependents { receipt_index: 0 },
+            HostError::CreateImplicitAccount { account_id: String::new() },
+            HostError::DuplicateAddKey { receipt_index: 0, public_key: String::new() },
+            HostError::DuplicateDeleteKey { receipt_index: 0, public_key: String::new() },
+            HostError::InvalidAccessKeyMethodName { index: 0 },
+            HostError::ReceiptHasActions { receipt_index: 0 },
+        ];
+        for (offset, error) in new_variants.into_iter().enumerate() {
+            assert_eq!(discriminant(error), first_new + offset as u8);
+        }
+    }
 
     #[test]
     fn test_display() {
@@ -1,4 +1,5 @@
 use crate::types::PublicKey;
+use near_primitives::receipt::ReceiptCongestion;
 use near_primitives_core::config::ViewConfig;
 use near_primitives_core::serialize::u64_dec_format;
 use near_primitives_core::types::{
@@ -61,6 +62,10 @@ pub struct VMContext {
     /// How many `DataReceipt`'s should receive this execution result. This should be empty if
     /// this function call is a part of a batch and it is not the last action.
     pub output_data_receivers: Vec<AccountId>,
+    /// Congestion metadata of the receipt being executed, the receipts it
+    /// creates inherit it.
+    #[serde(default)]
+    pub congestion: Option<ReceiptCongestion>,
 }
 
 impl VMContext {
@@ -1,3 +1,4 @@
+use crate::receipt_manager::FeeTracker;
 use crate::{HostError, VMLogicError};
 use near_primitives::types::TrieNodesCount;
 use near_primitives_core::config::ExtCosts::read_cached_trie_node;
@@ -227,13 +228,7 @@ impl GasCounter {
         sir: bool,
         action: ActionCosts,
     ) -> Result<()> {
-        let burn_gas =
-            num_bytes.checked_mul(per_byte_fee.send_fee(sir)).ok_or(HostError::IntegerOverflow)?;
-        let use_gas = burn_gas
-            .checked_add(
-                num_bytes.checked_mul(per_byte_fee.exec_fee()).ok_or(HostError::IntegerOverflow)?,
-            )
-            .ok_or(HostError::IntegerOverflow)?;
+        let (burn_gas, use_gas) = FeeTracker::per_byte_fee(per_byte_fee, num_bytes, sir)?;
         self.update_profile_action(action, burn_gas);
         self.deduct_gas(burn_gas, use_gas)
     }
@@ -249,9 +244,7 @@ impl GasCounter {
         sir: bool,
         action: ActionCosts,
     ) -> Result<()> {
-        let burn_gas = base_fee.send_fee(sir);
-        let use_gas =
-            burn_gas.checked_add(base_fee.exec_fee()).ok_or(HostError::IntegerOverflow)?;
+        let (burn_gas, use_gas) = FeeTracker::base_fee(base_fee, sir)?;
         self.update_profile_action(action, burn_gas);
         self.deduct_gas(burn_gas, use_gas)
     }
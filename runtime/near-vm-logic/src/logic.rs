@@ -1,19 +1,20 @@
 use crate::context::VMContext;
 use crate::dependencies::{External, MemoryLike};
 use crate::gas_counter::{FastGasCounter, GasCounter};
-use crate::receipt_manager::ReceiptManager;
+use crate::receipt_manager::{DuplicateAddKey, FeeTracker, ReceiptLimits, ReceiptManager};
 use crate::types::{PromiseIndex, PromiseResult, ReceiptIndex, ReturnData};
 use crate::utils::split_method_names;
 use crate::{ReceiptMetadata, ValuePtr};
 use byteorder::ByteOrder;
 use near_crypto::Secp256K1Signature;
+use near_primitives::checked_feature;
+use near_primitives::errors::IntegerOverflowError;
+use near_primitives::receipt::{DataReceipt, ReceiptCongestion};
 use near_primitives::version::is_implicit_account_creation_enabled;
 use near_primitives_core::config::ExtCosts::*;
 use near_primitives_core::config::{ActionCosts, ExtCosts, VMConfig, ViewConfig};
 use near_primitives_core::profile::ProfileData;
-use near_primitives_core::runtime::fees::{
-    transfer_exec_fee, transfer_send_fee, RuntimeFeesConfig,
-};
+use near_primitives_core::runtime::fees::RuntimeFeesConfig;
 use near_primitives_core::types::{
     AccountId, Balance, EpochHeight, Gas, ProtocolVersion, StorageUsage,
 };
@@ -126,6 +127,21 @@ impl<'a> VMLogic<'a> {
             context.prepaid_gas,
             context.is_view(),
         );
+        let limits = checked_feature!(
+            "protocol_feature_host_receipt_limits",
+            HostReceiptLimits,
+            current_protocol_version
+        )
+        .then(|| ReceiptLimits::from(&config.limit_config));
+        let receipt_manager = ReceiptManager::new(
+            limits,
+            fees_config.action_creation_config.clone(),
+            fees_config.storage_usage_config.clone(),
+            context.current_account_id.clone(),
+            current_protocol_version,
+            // Leaving out or rejecting duplicate keys would change the outcome of receipts.
+            DuplicateAddKey::Allow,
+        );
         Self {
             ext,
             context,
@@ -143,7 +159,7 @@ impl<'a> VMLogic<'a> {
             promises: vec![],
             total_log_length: 0,
             current_protocol_version,
-            receipt_manager: ReceiptManager::default(),
+            receipt_manager,
         }
     }
 
@@ -152,11 +168,90 @@ impl<'a> VMLogic<'a> {
         &self.logs
     }
 
-    /// Returns receipt metadata for created receipts
+    /// Returns receipt metadata for created receipts, the cancelled ones included so that
+    /// receipt indices can be used to look them up.
     pub fn action_receipts(&self) -> &[(AccountId, ReceiptMetadata)] {
         &self.receipt_manager.action_receipts
     }
 
+    /// Returns the gas burnt for the send fees of the actions appended to the created receipts.
+    pub fn actions_burnt_gas(&self) -> Gas {
+        self.receipt_manager.burnt_gas()
+    }
+
+    /// Returns the gas used for the send and exec fees of the actions appended to the created
+    /// receipts.
+    pub fn actions_used_gas(&self) -> Gas {
+        self.receipt_manager.used_gas()
+    }
+
+    /// Returns the profile of the gas burnt for the actions appended to the created receipts.
+    pub fn actions_profile(&self) -> &ProfileData {
+        self.receipt_manager.profile()
+    }
+
+    /// Replaces a created receipt by its already known output, see
+    /// [`ReceiptManager::return_data`].
+    ///
+    /// Fails with [`HostError::ReceiptHasDependents`] if the receipt is the one returned by
+    /// the contract.
+    pub fn return_receipt_data(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        data: Option<Vec<u8>>,
+    ) -> Result<()> {
+        self.check_not_returned(receipt_index)?;
+        self.receipt_manager.return_data(receipt_index, data)
+    }
+
+    /// The runtime sends the output of the current receipt to the receipt the contract
+    /// returns, which therefore has to be sent.
+    fn check_not_returned(&self, receipt_index: ReceiptIndex) -> Result<()> {
+        if self.return_data == ReturnData::ReceiptIndex(receipt_index) {
+            return Err(HostError::ReceiptHasDependents { receipt_index }.into());
+        }
+        Ok(())
+    }
+
+    /// Checks the order of the access key actions of a created receipt, see
+    /// [`ReceiptManager::validate_key_action_ordering`].
+    pub fn validate_key_action_ordering(&self, receipt_index: ReceiptIndex) -> Result<()> {
+        self.receipt_manager.validate_key_action_ordering(receipt_index)
+    }
+
+    /// Returns the sum of the deposits attached to the created receipts.
+    pub fn total_attached_deposit(&self) -> std::result::Result<Balance, IntegerOverflowError> {
+        self.receipt_manager.total_attached_deposit()
+    }
+
+    /// Returns the sum of the gas prepaid for the function calls of the created receipts.
+    pub fn total_prepaid_gas(&self) -> std::result::Result<Gas, IntegerOverflowError> {
+        self.receipt_manager.total_prepaid_gas()
+    }
+
+    /// Returns the number of bytes of storage the receivers of the created receipts gain from
+    /// their actions.
+    pub fn total_storage_delta(&self) -> std::result::Result<StorageUsage, IntegerOverflowError> {
+        self.receipt_manager.total_storage_delta()
+    }
+
+    /// Returns the number of bytes of storage the receiver of the receipt at `receipt_index`
+    /// gains from its actions, see [`ReceiptManager::storage_delta`].
+    pub fn storage_delta(&self, receipt_index: ReceiptIndex) -> Result<StorageUsage> {
+        self.receipt_manager.storage_delta(receipt_index)
+    }
+
+    /// Returns the sum of the deposits attached to the receipt at `receipt_index`.
+    pub fn receipt_attached_deposit(&self, receipt_index: ReceiptIndex) -> Result<Balance> {
+        self.receipt_manager.receipt_attached_deposit(receipt_index)
+    }
+
+    /// Returns the sum of the gas prepaid for the function calls of the receipt at
+    /// `receipt_index`.
+    pub fn receipt_prepaid_gas(&self, receipt_index: ReceiptIndex) -> Result<Gas> {
+        self.receipt_manager.receipt_prepaid_gas(receipt_index)
+    }
+
     #[allow(dead_code)]
     #[cfg(test)]
     pub(crate) fn receipt_manager(&self) -> &ReceiptManager {
@@ -1370,7 +1465,8 @@ impl<'a> VMLogic<'a> {
         let account_id = self.read_and_parse_account_id(account_id_ptr, account_id_len)?;
         let sir = account_id == self.context.current_account_id;
         self.pay_gas_for_new_receipt(sir, &[])?;
-        let new_receipt_idx = self.receipt_manager.create_receipt(self.ext, vec![], account_id)?;
+        let new_receipt_idx =
+            self.receipt_manager.build_receipt(self.ext, account_id).receipt_index();
 
         self.checked_push_promise(Promise::Receipt(new_receipt_idx))
     }
@@ -1427,8 +1523,10 @@ impl<'a> VMLogic<'a> {
             .collect();
         self.pay_gas_for_new_receipt(sir, &deps)?;
 
-        let new_receipt_idx =
-            self.receipt_manager.create_receipt(self.ext, receipt_dependencies, account_id)?;
+        let new_receipt_idx = self
+            .receipt_manager
+            .build_receipt_after(self.ext, receipt_dependencies, account_id)?
+            .receipt_index();
 
         self.checked_push_promise(Promise::Receipt(new_receipt_idx))
     }
@@ -1489,7 +1587,7 @@ impl<'a> VMLogic<'a> {
             ActionCosts::create_account,
         )?;
 
-        self.receipt_manager.append_action_create_account(receipt_idx)?;
+        self.receipt_manager.receipt_builder(self.ext, receipt_idx).create_account()?;
         Ok(())
     }
 
@@ -1547,7 +1645,7 @@ impl<'a> VMLogic<'a> {
             ActionCosts::deploy_contract,
         )?;
 
-        self.receipt_manager.append_action_deploy_contract(receipt_idx, code)?;
+        self.receipt_manager.receipt_builder(self.ext, receipt_idx).deploy_contract(code)?;
         Ok(())
     }
 
@@ -1672,14 +1770,14 @@ impl<'a> VMLogic<'a> {
 
         self.deduct_balance(amount)?;
 
-        self.receipt_manager.append_action_function_call_weight(
-            receipt_idx,
+        self.receipt_manager.receipt_builder(self.ext, receipt_idx).function_call_weight(
             method_name,
             arguments,
             amount,
             gas,
             GasWeight(gas_weight),
-        )
+        )?;
+        Ok(())
     }
 
     /// Appends `Transfer` action to the batch of actions for the given promise pointed by
@@ -1718,17 +1816,16 @@ impl<'a> VMLogic<'a> {
             is_implicit_account_creation_enabled(self.current_protocol_version)
                 && receiver_id.is_implicit();
 
-        let send_fee =
-            transfer_send_fee(&self.fees_config.action_creation_config, sir, is_receiver_implicit);
-        let exec_fee =
-            transfer_exec_fee(&self.fees_config.action_creation_config, is_receiver_implicit);
-        let burn_gas = send_fee;
-        let use_gas = burn_gas.checked_add(exec_fee).ok_or(HostError::IntegerOverflow)?;
+        let (burn_gas, use_gas) = FeeTracker::transfer_fee(
+            &self.fees_config.action_creation_config,
+            sir,
+            is_receiver_implicit,
+        )?;
         self.gas_counter.pay_action_accumulated(burn_gas, use_gas, ActionCosts::transfer)?;
 
         self.deduct_balance(amount)?;
 
-        self.receipt_manager.append_action_transfer(receipt_idx, amount)?;
+        self.receipt_manager.receipt_builder(self.ext, receipt_idx).transfer(amount)?;
         Ok(())
     }
 
@@ -1774,7 +1871,7 @@ impl<'a> VMLogic<'a> {
             ActionCosts::stake,
         )?;
 
-        self.receipt_manager.append_action_stake(receipt_idx, amount, public_key)?;
+        self.receipt_manager.receipt_builder(self.ext, receipt_idx).stake(amount, public_key)?;
         Ok(())
     }
 
@@ -1819,11 +1916,9 @@ impl<'a> VMLogic<'a> {
             ActionCosts::add_key,
         )?;
 
-        self.receipt_manager.append_action_add_key_with_full_access(
-            receipt_idx,
-            public_key,
-            nonce,
-        )?;
+        self.receipt_manager
+            .receipt_builder(self.ext, receipt_idx)
+            .add_full_access_key(public_key, nonce)?;
         Ok(())
     }
 
@@ -1889,8 +1984,7 @@ impl<'a> VMLogic<'a> {
             ActionCosts::add_key,
         )?;
 
-        self.receipt_manager.append_action_add_key_with_function_call(
-            receipt_idx,
+        self.receipt_manager.receipt_builder(self.ext, receipt_idx).add_function_call_key(
             public_key,
             nonce,
             allowance,
@@ -1940,7 +2034,7 @@ impl<'a> VMLogic<'a> {
             ActionCosts::delete_key,
         )?;
 
-        self.receipt_manager.append_action_delete_key(receipt_idx, public_key)?;
+        self.receipt_manager.receipt_builder(self.ext, receipt_idx).delete_key(public_key)?;
         Ok(())
     }
 
@@ -1983,7 +2077,9 @@ impl<'a> VMLogic<'a> {
             ActionCosts::delete_account,
         )?;
 
-        self.receipt_manager.append_action_delete_account(receipt_idx, beneficiary_id)?;
+        self.receipt_manager
+            .receipt_builder(self.ext, receipt_idx)
+            .delete_account(beneficiary_id)?;
         Ok(())
     }
 
@@ -2080,6 +2176,10 @@ impl<'a> VMLogic<'a> {
             .ok_or(HostError::InvalidPromiseIndex { promise_idx })?
         {
             Promise::Receipt(receipt_idx) => {
+                // Cancelled receipts and receipts replaced by their output aren't sent.
+                self.receipt_manager
+                    .sent_receipt_index(*receipt_idx)
+                    .map_err(|_| HostError::InvalidPromiseIndex { promise_idx })?;
                 self.return_data = ReturnData::ReceiptIndex(*receipt_idx);
                 Ok(())
             }
@@ -2661,15 +2761,32 @@ impl<'a> VMLogic<'a> {
         let mut profile = self.gas_counter.profile_data();
         profile.compute_wasm_instruction_cost(burnt_gas);
 
+        let congestion = ReceiptCongestion::child(
+            self.context.congestion,
+            burnt_gas,
+            self.current_protocol_version,
+        );
+
+        // The index of the returned receipt among the receipts which are sent.
+        let return_data = match self.return_data {
+            ReturnData::ReceiptIndex(receipt_index) => ReturnData::ReceiptIndex(
+                self.receipt_manager
+                    .sent_receipt_index(receipt_index)
+                    .expect("the returned receipt can't be cancelled or replaced"),
+            ),
+            return_data => return_data,
+        };
+
         VMOutcome {
             balance: self.current_account_balance,
             storage_usage: self.current_storage_usage,
-            return_data: self.return_data,
+            return_data,
             burnt_gas,
             used_gas,
             logs: self.logs,
             profile,
-            action_receipts: self.receipt_manager.action_receipts,
+            data_receipts: self.receipt_manager.data_receipts(),
+            action_receipts: self.receipt_manager.into_action_receipts(congestion),
         }
     }
 
@@ -2702,6 +2819,9 @@ pub struct VMOutcome {
     /// Data collected from making a contract call
     pub profile: ProfileData,
     pub action_receipts: Vec<(AccountId, ReceiptMetadata)>,
+    /// Data receipts sending the output of the receipts which were replaced by it to the
+    /// receipts depending on them.
+    pub data_receipts: Vec<(AccountId, DataReceipt)>,
 }
 
 impl std::fmt::Debug for VMOutcome {
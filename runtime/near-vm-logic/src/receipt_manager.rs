@@ -1,23 +1,36 @@
 use crate::logic;
 use crate::types::ReceiptIndex;
 use crate::External;
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::PublicKey;
-use near_primitives::receipt::DataReceiver;
+use near_primitives::checked_feature;
+use near_primitives::errors::IntegerOverflowError;
+use near_primitives::receipt::{DataReceipt, DataReceiver, ReceiptCongestion};
+use near_primitives::safe_math::{safe_add_balance, safe_add_gas};
 use near_primitives::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
     DeployContractAction, FunctionCallAction, StakeAction, TransferAction,
 };
-use near_primitives::types::{Balance, Nonce};
+use near_primitives::types::{Balance, Nonce, StorageUsage};
+use near_primitives::version::is_implicit_account_creation_enabled;
 use near_primitives_core::account::{AccessKey, AccessKeyPermission, FunctionCallPermission};
+use near_primitives_core::config::{ActionCosts, VMLimitConfig};
 use near_primitives_core::hash::CryptoHash;
-use near_primitives_core::types::{AccountId, Gas};
+use near_primitives_core::profile::ProfileData;
+use near_primitives_core::runtime::fees::{
+    transfer_exec_fee, transfer_send_fee, ActionCreationConfig, Fee, StorageUsageConfig,
+};
+use near_primitives_core::types::{AccountId, Gas, ProtocolVersion};
 use near_primitives_core::types::{GasDistribution, GasWeight};
 use near_vm_errors::HostError;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
 
 type ActionReceipts = Vec<(AccountId, ReceiptMetadata)>;
+type DataReceipts = Vec<(AccountId, DataReceipt)>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
 pub struct ReceiptMetadata {
     /// If present, where to route the output data
     pub output_data_receivers: Vec<DataReceiver>,
@@ -29,16 +42,340 @@ pub struct ReceiptMetadata {
     pub input_data_ids: Vec<CryptoHash>,
     /// A list of actions to process when all input_data_ids are filled
     pub actions: Vec<Action>,
+    /// Congestion metadata, set once the execution finishes, see
+    /// [`ReceiptManager::into_action_receipts`].
+    pub congestion: Option<ReceiptCongestion>,
+}
+
+impl ReceiptMetadata {
+    pub fn output_data_receivers(&self) -> &[DataReceiver] {
+        &self.output_data_receivers
+    }
+
+    pub fn input_data_ids(&self) -> &[CryptoHash] {
+        &self.input_data_ids
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    pub fn congestion(&self) -> Option<ReceiptCongestion> {
+        self.congestion
+    }
+}
+
+/// Limits on the actions of the receipts created by a contract, as set by the
+/// [`VMLimitConfig`].
+///
+/// The runtime rejects receipts over the limits once the contract returns, checking them
+/// as the actions are appended fails the call at the host function which broke the limit.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct ReceiptLimits {
+    pub max_actions_per_receipt: u64,
+    /// Applies to function calls and to the methods allowed by access keys.
+    pub max_method_name_len: u64,
+    /// Max total length of the methods allowed by an access key, counting a terminating byte
+    /// for each, which also bounds their number.
+    pub max_number_bytes_method_names: u64,
+    pub max_arguments_len: u64,
+    pub max_contract_size: u64,
+}
+
+impl From<&VMLimitConfig> for ReceiptLimits {
+    fn from(limit_config: &VMLimitConfig) -> Self {
+        Self {
+            max_actions_per_receipt: limit_config.max_actions_per_receipt,
+            max_method_name_len: limit_config.max_length_method_name,
+            max_number_bytes_method_names: limit_config.max_number_bytes_method_names,
+            max_arguments_len: limit_config.max_arguments_length,
+            max_contract_size: limit_config.max_contract_size,
+        }
+    }
+}
+
+/// Fails with [`HostError::ReceiptLimitExceeded`] if `value` is over `limit`.
+fn check_limit(limit_name: &str, value: u64, limit: u64) -> logic::Result<()> {
+    if value > limit {
+        return Err(HostError::ReceiptLimitExceeded {
+            limit_name: limit_name.to_string(),
+            value,
+            limit,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Counts the bytes written to it, failing once the count overflows.
+#[derive(Default)]
+struct ByteCounter(u64);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 = self.0.checked_add(buf.len() as u64).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "borsh-serialized size overflows u64")
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Number of bytes `value` takes borsh-serialized, without allocating them.
+fn borsh_size(value: &impl BorshSerialize) -> Result<StorageUsage, IntegerOverflowError> {
+    let mut counter = ByteCounter::default();
+    // The counter is the only thing which can fail.
+    value.serialize(&mut counter).map_err(|_| IntegerOverflowError)?;
+    Ok(counter.0)
+}
+
+/// Gas for the fees of the actions appended to the receipts, the send fees being burnt and the
+/// send and exec fees used, as charged by `VMLogic` when appending them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct FeeTracker {
+    pub burnt_gas: Gas,
+    pub used_gas: Gas,
+}
+
+impl FeeTracker {
+    /// Gas burnt and used for the base fee `fee` of an action, the send fee being burnt and
+    /// the send and exec fees used.
+    pub(crate) fn base_fee(fee: &Fee, sir: bool) -> logic::Result<(Gas, Gas)> {
+        let burnt_gas = fee.send_fee(sir);
+        let used_gas = burnt_gas.checked_add(fee.exec_fee()).ok_or(HostError::IntegerOverflow)?;
+        Ok((burnt_gas, used_gas))
+    }
+
+    /// Gas burnt and used for the per byte fee `fee` of an action of `num_bytes` bytes, the
+    /// send fee being burnt and the send and exec fees used.
+    pub(crate) fn per_byte_fee(fee: &Fee, num_bytes: u64, sir: bool) -> logic::Result<(Gas, Gas)> {
+        let burnt_gas =
+            num_bytes.checked_mul(fee.send_fee(sir)).ok_or(HostError::IntegerOverflow)?;
+        let used_gas = num_bytes
+            .checked_mul(fee.exec_fee())
+            .and_then(|exec_gas| burnt_gas.checked_add(exec_gas))
+            .ok_or(HostError::IntegerOverflow)?;
+        Ok((burnt_gas, used_gas))
+    }
+
+    /// Gas burnt and used for the fee of a transfer, which also pays for creating the receiver
+    /// if `is_receiver_implicit`.
+    pub(crate) fn transfer_fee(
+        fees: &ActionCreationConfig,
+        sir: bool,
+        is_receiver_implicit: bool,
+    ) -> logic::Result<(Gas, Gas)> {
+        let burnt_gas = transfer_send_fee(fees, sir, is_receiver_implicit);
+        let used_gas = burnt_gas
+            .checked_add(transfer_exec_fee(fees, is_receiver_implicit))
+            .ok_or(HostError::IntegerOverflow)?;
+        Ok((burnt_gas, used_gas))
+    }
+
+    /// Returns the tracker with the fees of `action` added.
+    ///
+    /// # Arguments
+    ///
+    /// * `fees` - the fees of the actions
+    /// * `action` - the appended action
+    /// * `sir` - whether the receiver of the receipt is the current account
+    /// * `is_receiver_implicit` - whether a transfer to the receiver would create an implicit
+    ///   account
+    fn with_action(
+        self,
+        fees: &ActionCreationConfig,
+        action: &Action,
+        sir: bool,
+        is_receiver_implicit: bool,
+    ) -> logic::Result<Self> {
+        let base = |fee: &Fee| Self::base_fee(fee, sir);
+        let per_byte = |fee: &Fee, num_bytes: usize| Self::per_byte_fee(fee, num_bytes as u64, sir);
+        let ((base_burnt, base_used), (per_byte_burnt, per_byte_used)) = match action {
+            Action::CreateAccount(_) => (base(&fees.create_account_cost)?, (0, 0)),
+            Action::DeployContract(a) => (
+                base(&fees.deploy_contract_cost)?,
+                per_byte(&fees.deploy_contract_cost_per_byte, a.code.len())?,
+            ),
+            Action::FunctionCall(a) => (
+                base(&fees.function_call_cost)?,
+                per_byte(&fees.function_call_cost_per_byte, a.method_name.len() + a.args.len())?,
+            ),
+            Action::Transfer(_) => (Self::transfer_fee(fees, sir, is_receiver_implicit)?, (0, 0)),
+            Action::Stake(_) => (base(&fees.stake_cost)?, (0, 0)),
+            Action::AddKey(a) => match &a.access_key.permission {
+                AccessKeyPermission::FullAccess => {
+                    (base(&fees.add_key_cost.full_access_cost)?, (0, 0))
+                }
+                AccessKeyPermission::FunctionCall(permission) => (
+                    base(&fees.add_key_cost.function_call_cost)?,
+                    // +1 is to account for null-terminating characters.
+                    per_byte(
+                        &fees.add_key_cost.function_call_cost_per_byte,
+                        permission.method_names.iter().map(|name| name.len() + 1).sum(),
+                    )?,
+                ),
+            },
+            Action::DeleteKey(_) => (base(&fees.delete_key_cost)?, (0, 0)),
+            Action::DeleteAccount(_) => (base(&fees.delete_account_cost)?, (0, 0)),
+            #[cfg(feature = "protocol_feature_chunk_only_producers")]
+            Action::StakeChunkOnly(_) => (base(&fees.stake_cost)?, (0, 0)),
+        };
+        let add = |a: Gas, b: Gas| a.checked_add(b).ok_or(HostError::IntegerOverflow);
+        Ok(Self {
+            burnt_gas: add(self.burnt_gas, add(base_burnt, per_byte_burnt)?)?,
+            used_gas: add(self.used_gas, add(base_used, per_byte_used)?)?,
+        })
+    }
+}
+
+/// Number of bytes of storage the receiver of `action` gains when it's applied, the same way
+/// as the runtime adds them to the storage usage of the account.
+///
+/// Actions which free storage count as zero, since what they free depends on the state of the
+/// receiver.  A contract is counted in full, as if the receiver had no code before.
+fn action_storage_delta(
+    config: &StorageUsageConfig,
+    action: &Action,
+) -> Result<StorageUsage, IntegerOverflowError> {
+    match action {
+        Action::CreateAccount(_) => Ok(config.num_bytes_account),
+        Action::DeployContract(a) => Ok(a.code.len() as StorageUsage),
+        Action::AddKey(a) => borsh_size(&a.public_key)?
+            .checked_add(borsh_size(&a.access_key)?)
+            .and_then(|num_bytes| num_bytes.checked_add(config.num_extra_bytes_record))
+            .ok_or(IntegerOverflowError),
+        Action::FunctionCall(_)
+        | Action::Transfer(_)
+        | Action::Stake(_)
+        | Action::DeleteKey(_)
+        | Action::DeleteAccount(_) => Ok(0),
+        #[cfg(feature = "protocol_feature_chunk_only_producers")]
+        Action::StakeChunkOnly(_) => Ok(0),
+    }
 }
 
-#[derive(Default, Clone, PartialEq)]
+/// The cost `action` is profiled under, the same as when `VMLogic` charges for it.
+fn action_cost_kind(action: &Action) -> ActionCosts {
+    match action {
+        Action::CreateAccount(_) => ActionCosts::create_account,
+        Action::DeployContract(_) => ActionCosts::deploy_contract,
+        Action::FunctionCall(_) => ActionCosts::function_call,
+        Action::Transfer(_) => ActionCosts::transfer,
+        Action::Stake(_) => ActionCosts::stake,
+        #[cfg(feature = "protocol_feature_chunk_only_producers")]
+        Action::StakeChunkOnly(_) => ActionCosts::stake,
+        Action::AddKey(_) => ActionCosts::add_key,
+        Action::DeleteKey(_) => ActionCosts::delete_key,
+        Action::DeleteAccount(_) => ActionCosts::delete_account,
+    }
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize)]
 pub(crate) struct ReceiptManager {
+    /// `None` until the `HostReceiptLimits` protocol feature, the receipts being checked by
+    /// the runtime only.
+    limits: Option<ReceiptLimits>,
+    fees: ActionCreationConfig,
+    storage_usage_config: StorageUsageConfig,
+    current_account_id: AccountId,
+    current_protocol_version: ProtocolVersion,
+    duplicate_add_key: DuplicateAddKey,
+    fee_tracker: FeeTracker,
+    /// Burnt gas of the appended actions by the kind of action.
+    profile: ProfileData,
     pub(crate) action_receipts: ActionReceipts,
+    /// What becomes of each of the action receipts.  Receipts which aren't sent stay in
+    /// `action_receipts` so that the indices of the others don't change.
+    statuses: Vec<ReceiptStatus>,
     gas_weights: Vec<(FunctionCallActionIndex, GasWeight)>,
+    /// Changes made to the receipts and gas weights, oldest first, for
+    /// [`ReceiptManager::rollback`] to undo.
+    changes: Vec<Change>,
+}
+
+/// A change to the receipts and gas weights of a [`ReceiptManager`], with what it takes to undo
+/// it.
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+enum Change {
+    /// A receipt was pushed to `action_receipts` and `statuses`.
+    ReceiptCreated,
+    /// An action was pushed to the actions of the receipt at the index.
+    ActionAppended(usize),
+    /// A data receiver was pushed to the output data receivers of the receipt at the index.
+    DataReceiverAdded(usize),
+    /// A gas weight was pushed to `gas_weights`.
+    GasWeightAdded,
+    /// The pending receipt at `index` was cancelled or replaced by its data, removing the data
+    /// receivers and gas weights that were at the given positions.
+    StatusChanged {
+        index: usize,
+        removed_data_receivers: Vec<(usize, Vec<(usize, DataReceiver)>)>,
+        removed_gas_weights: Vec<(usize, (FunctionCallActionIndex, GasWeight))>,
+    },
+}
+
+/// State of a [`ReceiptManager`] to go back to with [`ReceiptManager::rollback`].
+///
+/// Only the number of changes made so far is recorded, along with the fees of the actions, so
+/// taking a checkpoint doesn't depend on the number of receipts and actions.
+#[derive(Debug, Clone)]
+pub(crate) struct ReceiptManagerCheckpoint {
+    num_changes: usize,
+    fee_tracker: FeeTracker,
+    profile: ProfileData,
+}
+
+/// Removes the items of `items` for which `remove` returns true, and returns them along with the
+/// positions they had, for [`restore_removed`] to put them back.
+fn remove_where<T>(items: &mut Vec<T>, mut remove: impl FnMut(&T) -> bool) -> Vec<(usize, T)> {
+    let mut removed = vec![];
+    for (position, item) in std::mem::take(items).into_iter().enumerate() {
+        if remove(&item) {
+            removed.push((position, item));
+        } else {
+            items.push(item);
+        }
+    }
+    removed
+}
+
+/// Puts back the items taken out by [`remove_where`].
+fn restore_removed<T>(items: &mut Vec<T>, removed: Vec<(usize, T)>) {
+    for (position, item) in removed {
+        items.insert(position, item);
+    }
+}
+
+/// What becomes of an action receipt of a [`ReceiptManager`].
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+enum ReceiptStatus {
+    /// Sent as an action receipt.
+    Pending,
+    /// Not sent, see [`ReceiptManager::cancel_receipt`].
+    Cancelled,
+    /// Not sent, its output being sent as data receipts instead, see
+    /// [`ReceiptManager::return_data`].
+    DataReturned(Option<Vec<u8>>),
+}
+
+/// What [`ReceiptManager`] does with an `AddKey` action for a public key which an earlier
+/// `AddKey` action of the same receipt already adds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum DuplicateAddKey {
+    /// Append the action anyway, so that the receipt fails with `AddKeyAlreadyExists` once it's
+    /// executed.
+    Allow,
+    /// Leave the action out of the receipt.
+    Skip,
+    /// Fail with [`HostError::DuplicateAddKey`].
+    Reject,
 }
 
 /// Indexes the [`ReceiptManager`]'s action receipts and actions.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize)]
 struct FunctionCallActionIndex {
     /// Index of [`ReceiptMetadata`] in the action receipts of [`ReceiptManager`].
     receipt_index: usize,
@@ -64,7 +401,115 @@ fn get_fuction_call_action_mut(
     }
 }
 
+/// Shows the receivers and number of actions of the receipts rather than the actions, which
+/// can hold whole contracts.
+impl fmt::Debug for ReceiptManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[derive(Debug)]
+        #[allow(dead_code)] // Only read through `Debug`.
+        struct Receipt<'a> {
+            receiver_id: &'a AccountId,
+            num_actions: usize,
+            cancelled: bool,
+            data_returned: bool,
+        }
+        let receipts: Vec<_> = self
+            .action_receipts
+            .iter()
+            .zip(&self.statuses)
+            .map(|((receiver_id, receipt), status)| Receipt {
+                receiver_id,
+                num_actions: receipt.actions.len(),
+                cancelled: *status == ReceiptStatus::Cancelled,
+                data_returned: matches!(status, ReceiptStatus::DataReturned(_)),
+            })
+            .collect();
+        f.debug_struct("ReceiptManager")
+            .field("num_receipts", &receipts.len())
+            .field("receipts", &receipts)
+            .field("num_gas_weights", &self.gas_weights.len())
+            .field("num_changes", &self.changes.len())
+            .field("duplicate_add_key", &self.duplicate_add_key)
+            .field("fee_tracker", &self.fee_tracker)
+            .finish()
+    }
+}
+
 impl ReceiptManager {
+    /// Creates a manager for the receipts created by `current_account_id`.
+    ///
+    /// `limits` are checked as actions are appended, if given.  `duplicate_add_key` decides
+    /// what happens to `AddKey` actions for a key that the receipt already adds.
+    pub(crate) fn new(
+        limits: Option<ReceiptLimits>,
+        fees: ActionCreationConfig,
+        storage_usage_config: StorageUsageConfig,
+        current_account_id: AccountId,
+        current_protocol_version: ProtocolVersion,
+        duplicate_add_key: DuplicateAddKey,
+    ) -> Self {
+        Self {
+            limits,
+            fees,
+            storage_usage_config,
+            current_account_id,
+            current_protocol_version,
+            duplicate_add_key,
+            fee_tracker: FeeTracker::default(),
+            profile: ProfileData::new(),
+            action_receipts: vec![],
+            statuses: vec![],
+            gas_weights: vec![],
+            changes: vec![],
+        }
+    }
+
+    /// Returns the current state, to roll back to if what comes next has to be undone.
+    pub(crate) fn checkpoint(&self) -> ReceiptManagerCheckpoint {
+        ReceiptManagerCheckpoint {
+            num_changes: self.changes.len(),
+            fee_tracker: self.fee_tracker,
+            profile: self.profile.clone(),
+        }
+    }
+
+    /// Restores the state at the time of the `checkpoint`, dropping the receipts, actions, data
+    /// receivers and gas weights added since, undoing cancellations and returned data, and
+    /// taking back the fees of the dropped actions.
+    ///
+    /// The `checkpoint` must have been taken from this manager, with no rollback to an earlier
+    /// checkpoint and no [`ReceiptManager::distribute_unused_gas`] in between.
+    pub(crate) fn rollback(&mut self, checkpoint: ReceiptManagerCheckpoint) {
+        debug_assert!(checkpoint.num_changes <= self.changes.len());
+        while self.changes.len() > checkpoint.num_changes {
+            match self.changes.pop().unwrap() {
+                Change::ReceiptCreated => {
+                    self.action_receipts.pop();
+                    self.statuses.pop();
+                }
+                Change::ActionAppended(index) => {
+                    self.action_receipts[index].1.actions.pop();
+                }
+                Change::DataReceiverAdded(index) => {
+                    self.action_receipts[index].1.output_data_receivers.pop();
+                }
+                Change::GasWeightAdded => {
+                    self.gas_weights.pop();
+                }
+                Change::StatusChanged { index, removed_data_receivers, removed_gas_weights } => {
+                    for (other, removed) in removed_data_receivers {
+                        let receivers = &mut self.action_receipts[other].1.output_data_receivers;
+                        restore_removed(receivers, removed);
+                    }
+                    restore_removed(&mut self.gas_weights, removed_gas_weights);
+                    self.statuses[index] = ReceiptStatus::Pending;
+                }
+            }
+        }
+        self.fee_tracker = checkpoint.fee_tracker;
+        self.profile = checkpoint.profile;
+    }
+
     pub(crate) fn get_receipt_receiver(&self, receipt_index: ReceiptIndex) -> &AccountId {
         self.action_receipts
             .get(receipt_index as usize)
@@ -72,26 +517,249 @@ impl ReceiptManager {
             .expect("receipt index should be valid for getting receiver")
     }
 
-    /// Appends an action and returns the index the action was inserted in the receipt
-    fn append_action(&mut self, receipt_index: ReceiptIndex, action: Action) -> usize {
-        let actions = &mut self
-            .action_receipts
-            .get_mut(receipt_index as usize)
-            .expect("receipt index should be present")
-            .1
-            .actions;
+    /// Returns `receipt_index` as an index into `action_receipts` if it refers to a receipt
+    /// which is still pending, i.e. neither cancelled nor replaced by its data.
+    fn check_receipt_index(&self, receipt_index: ReceiptIndex) -> logic::Result<usize> {
+        match self.statuses.get(receipt_index as usize) {
+            Some(ReceiptStatus::Pending) => Ok(receipt_index as usize),
+            _ => Err(HostError::InvalidReceiptIndex { receipt_index }.into()),
+        }
+    }
+
+    /// Returns the receivers and actions of the pending receipts, in the order they were
+    /// created.
+    pub(crate) fn receipts(&self) -> impl Iterator<Item = (&AccountId, &[Action])> {
+        self.action_receipts
+            .iter()
+            .zip(&self.statuses)
+            .filter(|(_, status)| **status == ReceiptStatus::Pending)
+            .map(|((receiver_id, receipt), _)| (receiver_id, receipt.actions()))
+    }
+
+    /// Returns a data receipt for each receiver of the output of the receipts replaced by their
+    /// data with [`ReceiptManager::return_data`].
+    pub(crate) fn data_receipts(&self) -> DataReceipts {
+        self.action_receipts
+            .iter()
+            .zip(&self.statuses)
+            .filter_map(|((_, receipt), status)| match status {
+                ReceiptStatus::DataReturned(data) => Some((receipt, data)),
+                _ => None,
+            })
+            .flat_map(|(receipt, data)| {
+                receipt.output_data_receivers.iter().map(move |data_receiver| {
+                    let data_receipt =
+                        DataReceipt { data_id: data_receiver.data_id, data: data.clone() };
+                    (data_receiver.receiver_id.clone(), data_receipt)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the actions of the receipt at `receipt_index`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// pending receipt.
+    pub(crate) fn actions(&self, receipt_index: ReceiptIndex) -> logic::Result<&[Action]> {
+        Ok(self.action_receipts[self.check_receipt_index(receipt_index)?].1.actions())
+    }
+
+    /// Returns the index the pending receipt at `receipt_index` has among the receipts
+    /// returned by [`ReceiptManager::into_action_receipts`], which leaves out the receipts
+    /// that aren't sent.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// pending receipt.
+    pub(crate) fn sent_receipt_index(
+        &self,
+        receipt_index: ReceiptIndex,
+    ) -> logic::Result<ReceiptIndex> {
+        let index = self.check_receipt_index(receipt_index)?;
+        let num_sent_before =
+            self.statuses[..index].iter().filter(|status| **status == ReceiptStatus::Pending);
+        Ok(num_sent_before.count() as ReceiptIndex)
+    }
+
+    /// Consumes the manager and returns the pending receipts, each carrying `congestion`.
+    ///
+    /// The receipts which aren't sent are left out, so that the index of a receipt among them
+    /// is given by [`ReceiptManager::sent_receipt_index`] rather than its `ReceiptIndex`.
+    pub(crate) fn into_action_receipts(
+        self,
+        congestion: Option<ReceiptCongestion>,
+    ) -> ActionReceipts {
+        self.action_receipts
+            .into_iter()
+            .zip(self.statuses)
+            .filter(|(_, status)| *status == ReceiptStatus::Pending)
+            .map(|((receiver_id, receipt), _)| {
+                (receiver_id, ReceiptMetadata { congestion, ..receipt })
+            })
+            .collect()
+    }
+
+    /// Sum of the deposits attached by the actions of all the pending receipts, see
+    /// [`Action::get_deposit_balance`].
+    pub(crate) fn total_attached_deposit(&self) -> Result<Balance, IntegerOverflowError> {
+        self.receipts()
+            .flat_map(|(_, actions)| actions)
+            .try_fold(0, |total, action| safe_add_balance(total, action.get_deposit_balance()))
+    }
+
+    /// Sum of the gas prepaid for the function calls of all the pending receipts.
+    pub(crate) fn total_prepaid_gas(&self) -> Result<Gas, IntegerOverflowError> {
+        self.receipts()
+            .flat_map(|(_, actions)| actions)
+            .try_fold(0, |total, action| safe_add_gas(total, action.get_prepaid_gas()))
+    }
+
+    /// Number of bytes of storage the receivers of all the pending receipts gain from their
+    /// actions, see [`ReceiptManager::storage_delta`].
+    pub(crate) fn total_storage_delta(&self) -> Result<StorageUsage, IntegerOverflowError> {
+        self.receipts().flat_map(|(_, actions)| actions).try_fold(0, |total, action| {
+            let delta = action_storage_delta(&self.storage_usage_config, action)?;
+            StorageUsage::checked_add(total, delta).ok_or(IntegerOverflowError)
+        })
+    }
+
+    /// Number of bytes of storage the receiver of the receipt at `receipt_index` gains from its
+    /// actions: the code of deployed contracts, the records of added access keys and the record
+    /// of a created account.  Freed storage isn't subtracted.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt and with [`HostError::IntegerOverflow`] if the sum overflows.
+    pub(crate) fn storage_delta(&self, receipt_index: ReceiptIndex) -> logic::Result<StorageUsage> {
+        self.actions(receipt_index)?
+            .iter()
+            .try_fold(0, |total, action| {
+                let delta = action_storage_delta(&self.storage_usage_config, action)?;
+                StorageUsage::checked_add(total, delta).ok_or(IntegerOverflowError)
+            })
+            .map_err(|_| HostError::IntegerOverflow.into())
+    }
+
+    /// Sum of the deposits attached by the actions of the receipt at `receipt_index`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt and with [`HostError::IntegerOverflow`] if the sum overflows.
+    pub(crate) fn receipt_attached_deposit(
+        &self,
+        receipt_index: ReceiptIndex,
+    ) -> logic::Result<Balance> {
+        self.actions(receipt_index)?
+            .iter()
+            .try_fold(0, |total, action| safe_add_balance(total, action.get_deposit_balance()))
+            .map_err(|_| HostError::IntegerOverflow.into())
+    }
 
+    /// Sum of the gas prepaid for the function calls of the receipt at `receipt_index`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt and with [`HostError::IntegerOverflow`] if the sum overflows.
+    pub(crate) fn receipt_prepaid_gas(&self, receipt_index: ReceiptIndex) -> logic::Result<Gas> {
+        self.actions(receipt_index)?
+            .iter()
+            .try_fold(0, |total, action| safe_add_gas(total, action.get_prepaid_gas()))
+            .map_err(|_| HostError::IntegerOverflow.into())
+    }
+
+    /// Gas burnt for the send fees of the appended actions.
+    pub(crate) fn burnt_gas(&self) -> Gas {
+        self.fee_tracker.burnt_gas
+    }
+
+    /// Gas used for the send and exec fees of the appended actions.
+    pub(crate) fn used_gas(&self) -> Gas {
+        self.fee_tracker.used_gas
+    }
+
+    /// Profile of the gas burnt for the send fees of the appended actions, by the kind of
+    /// action.  Per-byte fees are included in the cost of their action.
+    pub(crate) fn profile(&self) -> &ProfileData {
+        &self.profile
+    }
+
+    /// Appends an action, adds its fees to the tracked ones and returns the index the action
+    /// was inserted in the receipt
+    ///
+    /// Fails if the receipt would have more actions than the limits allow.
+    fn append_action(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        action: Action,
+    ) -> logic::Result<usize> {
+        let index = self.check_receipt_index(receipt_index)?;
+        let num_actions = self.action_receipts[index].1.actions.len() as u64 + 1;
+        self.check_limit("max_actions_per_receipt", num_actions, |limits| {
+            limits.max_actions_per_receipt
+        })?;
+        let (receiver_id, receipt) = &mut self.action_receipts[index];
+        let sir = *receiver_id == self.current_account_id;
+        let is_receiver_implicit =
+            is_implicit_account_creation_enabled(self.current_protocol_version)
+                && receiver_id.is_implicit();
+        let actions = &mut receipt.actions;
+
+        let fee_tracker =
+            self.fee_tracker.with_action(&self.fees, &action, sir, is_receiver_implicit)?;
+
+        self.profile.add_action_cost(
+            action_cost_kind(&action),
+            fee_tracker.burnt_gas - self.fee_tracker.burnt_gas,
+        );
+        self.fee_tracker = fee_tracker;
         actions.push(action);
+        self.changes.push(Change::ActionAppended(index));
 
         // Return index that action was inserted at
-        actions.len() - 1
+        Ok(actions.len() - 1)
+    }
+
+    /// Appends an `AddKey` action for `public_key`, unless the receipt already adds that key and
+    /// duplicates are skipped.
+    ///
+    /// Keys are compared after parsing, so the same key is caught however it was encoded.
+    fn append_action_add_key(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        access_key: AccessKey,
+    ) -> logic::Result<()> {
+        if self.duplicate_add_key != DuplicateAddKey::Allow {
+            let is_duplicate = self.actions(receipt_index)?.iter().any(|action| {
+                matches!(action, Action::AddKey(add_key) if add_key.public_key == public_key)
+            });
+            if is_duplicate {
+                return match self.duplicate_add_key {
+                    DuplicateAddKey::Allow => unreachable!(),
+                    DuplicateAddKey::Skip => Ok(()),
+                    DuplicateAddKey::Reject => Err(HostError::DuplicateAddKey {
+                        receipt_index,
+                        public_key: public_key.to_string(),
+                    }
+                    .into()),
+                };
+            }
+        }
+        self.append_action(receipt_index, Action::AddKey(AddKeyAction { public_key, access_key }))?;
+        Ok(())
     }
 
     /// Create a receipt which will be executed after all the receipts identified by
     /// `receipt_indices` are complete.
     ///
     /// If any of the [`RecepitIndex`]es do not refer to a known receipt, this function will fail
-    /// with an error.
+    /// with an error, and no data receivers are added.
     ///
     /// # Arguments
     ///
@@ -104,23 +772,242 @@ impl ReceiptManager {
         receipt_indices: Vec<ReceiptIndex>,
         receiver_id: AccountId,
     ) -> logic::Result<ReceiptIndex> {
+        let checkpoint = self.checkpoint();
         let mut input_data_ids = vec![];
         for receipt_index in receipt_indices {
             let data_id = ext.generate_data_id();
-            self.action_receipts
-                .get_mut(receipt_index as usize)
-                .ok_or_else(|| HostError::InvalidReceiptIndex { receipt_index })?
-                .1
-                .output_data_receivers
-                .push(DataReceiver { data_id, receiver_id: receiver_id.clone() });
+            if let Err(err) =
+                self.add_output_data_receiver(receipt_index, data_id, receiver_id.clone())
+            {
+                self.rollback(checkpoint);
+                return Err(err);
+            }
             input_data_ids.push(data_id);
         }
+        Ok(self.create_receipt_dependent_on_data(input_data_ids, receiver_id))
+    }
+
+    /// Create a receipt for `receiver_id` which doesn't depend on other receipts, and return a
+    /// [`ReceiptBuilder`] to append actions to it and to create receipts depending on it.
+    pub(crate) fn build_receipt<'a>(
+        &'a mut self,
+        ext: &'a mut dyn External,
+        receiver_id: AccountId,
+    ) -> ReceiptBuilder<'a> {
+        let receipt_index = self.create_receipt_dependent_on_data(vec![], receiver_id);
+        ReceiptBuilder { manager: self, ext, receipt_index }
+    }
+
+    /// Same as [`ReceiptManager::create_receipt`], but returns a [`ReceiptBuilder`] for the new
+    /// receipt.
+    pub(crate) fn build_receipt_after<'a>(
+        &'a mut self,
+        ext: &'a mut dyn External,
+        receipt_indices: Vec<ReceiptIndex>,
+        receiver_id: AccountId,
+    ) -> logic::Result<ReceiptBuilder<'a>> {
+        let receipt_index = self.create_receipt(ext, receipt_indices, receiver_id)?;
+        Ok(ReceiptBuilder { manager: self, ext, receipt_index })
+    }
+
+    /// Return a [`ReceiptBuilder`] appending actions to the existing receipt at
+    /// `receipt_index`.  The index is checked when an action is appended.
+    pub(crate) fn receipt_builder<'a>(
+        &'a mut self,
+        ext: &'a mut dyn External,
+        receipt_index: ReceiptIndex,
+    ) -> ReceiptBuilder<'a> {
+        ReceiptBuilder { manager: self, ext, receipt_index }
+    }
 
-        let new_receipt =
-            ReceiptMetadata { output_data_receivers: vec![], input_data_ids, actions: vec![] };
+    /// Create a receipt which will be executed once the data identified by each of the
+    /// `data_ids` is received, whether it's the output of a receipt or supplied by other means.
+    ///
+    /// Repeated data ids are only waited for once.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_ids` - ids of the data the new receipt depends on
+    /// * `receiver_id` - account id of the receiver of the receipt created
+    pub(crate) fn create_receipt_dependent_on_data(
+        &mut self,
+        data_ids: Vec<CryptoHash>,
+        receiver_id: AccountId,
+    ) -> ReceiptIndex {
+        let mut input_data_ids = Vec::with_capacity(data_ids.len());
+        for data_id in data_ids {
+            if !input_data_ids.contains(&data_id) {
+                input_data_ids.push(data_id);
+            }
+        }
+        let new_receipt = ReceiptMetadata {
+            output_data_receivers: vec![],
+            input_data_ids,
+            actions: vec![],
+            congestion: None,
+        };
         let new_receipt_index = self.action_receipts.len() as ReceiptIndex;
         self.action_receipts.push((receiver_id, new_receipt));
-        Ok(new_receipt_index)
+        self.statuses.push(ReceiptStatus::Pending);
+        self.changes.push(Change::ReceiptCreated);
+        new_receipt_index
+    }
+
+    /// Send the output of an existing receipt to `receiver_id` as the data `data_id`, on top of
+    /// any receivers it already has.  Adding the same receiver twice is a no-op.
+    ///
+    /// # Arguments
+    ///
+    /// * `receipt_index` - an index of Receipt whose output to send
+    /// * `data_id` - id of the data the output is sent as
+    /// * `receiver_id` - account id of the receiver of the data
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt or the receipt was cancelled.  The output of a receipt replaced by its
+    /// data can still be sent to more receivers.
+    pub(crate) fn add_output_data_receiver(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        data_id: CryptoHash,
+        receiver_id: AccountId,
+    ) -> logic::Result<()> {
+        let index = match self.statuses.get(receipt_index as usize) {
+            Some(ReceiptStatus::Pending | ReceiptStatus::DataReturned(_)) => receipt_index as usize,
+            _ => return Err(HostError::InvalidReceiptIndex { receipt_index }.into()),
+        };
+        let output_data_receivers = &mut self.action_receipts[index].1.output_data_receivers;
+        let data_receiver = DataReceiver { data_id, receiver_id };
+        if !output_data_receivers.contains(&data_receiver) {
+            output_data_receivers.push(data_receiver);
+            self.changes.push(Change::DataReceiverAdded(index));
+        }
+        Ok(())
+    }
+
+    /// Cancel a receipt, so that it isn't part of the outcome of the execution.
+    ///
+    /// The receipts it depends on no longer send it their output and its function calls no
+    /// longer get a share of the unused gas.  The gas already paid for its actions isn't
+    /// refunded.  The index of the receipt is never reused.
+    ///
+    /// # Arguments
+    ///
+    /// * `receipt_index` - an index of Receipt to cancel
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt or the receipt is already cancelled, and with
+    /// [`HostError::ReceiptHasDependents`] if the output of the receipt is still sent somewhere,
+    /// in which case the receipts depending on it have to be cancelled first.
+    // Not exposed by `VMLogic`: the gas paid for the receipt stays in its used gas without
+    // being attached to any receipt, which the runtime's balance checker would reject.
+    #[allow(dead_code)]
+    pub(crate) fn cancel_receipt(&mut self, receipt_index: ReceiptIndex) -> logic::Result<()> {
+        let index = self.check_receipt_index(receipt_index)?;
+        let receipt = &self.action_receipts[index].1;
+        if !receipt.output_data_receivers.is_empty() {
+            return Err(HostError::ReceiptHasDependents { receipt_index }.into());
+        }
+        self.change_status(index, ReceiptStatus::Cancelled);
+        Ok(())
+    }
+
+    /// Sets the status of the pending receipt at `index`, which then no longer gets the output
+    /// of other receipts nor a share of the unused gas.
+    fn change_status(&mut self, index: usize, status: ReceiptStatus) {
+        let input_data_ids = self.action_receipts[index].1.input_data_ids.clone();
+        let mut removed_data_receivers = vec![];
+        for (other, (_, receipt)) in self.action_receipts.iter_mut().enumerate() {
+            let removed = remove_where(&mut receipt.output_data_receivers, |receiver| {
+                input_data_ids.contains(&receiver.data_id)
+            });
+            if !removed.is_empty() {
+                removed_data_receivers.push((other, removed));
+            }
+        }
+        let removed_gas_weights = remove_where(&mut self.gas_weights, |(action_index, _)| {
+            action_index.receipt_index == index
+        });
+        self.statuses[index] = status;
+        self.changes.push(Change::StatusChanged {
+            index,
+            removed_data_receivers,
+            removed_gas_weights,
+        });
+    }
+
+    /// Replace a receipt whose output is already known by that output: the receipt isn't sent
+    /// and the receipts depending on it are sent `data` in data receipts instead, see
+    /// [`ReceiptManager::data_receipts`].
+    ///
+    /// Like for a cancelled receipt, the receipts it depends on no longer send it their
+    /// output, and no actions can be appended to it.  Receipts can still be made to depend on
+    /// it.
+    ///
+    /// # Arguments
+    ///
+    /// * `receipt_index` - an index of Receipt to replace
+    /// * `data` - the output of the receipt, `None` if it failed
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// pending receipt, and with [`HostError::ReceiptHasActions`] if actions were appended to
+    /// the receipt, since its output would then depend on them.
+    pub(crate) fn return_data(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        data: Option<Vec<u8>>,
+    ) -> logic::Result<()> {
+        let index = self.check_receipt_index(receipt_index)?;
+        if !self.action_receipts[index].1.actions.is_empty() {
+            return Err(HostError::ReceiptHasActions { receipt_index }.into());
+        }
+        self.change_status(index, ReceiptStatus::DataReturned(data));
+        Ok(())
+    }
+
+    /// Checks that the access key actions of a receipt can all succeed when they're applied in
+    /// order: a key isn't added again unless it was deleted in between, and isn't deleted again
+    /// unless it was added in between.
+    ///
+    /// Only the actions of the receipt are looked at, not the keys the receiver already has.
+    ///
+    /// # Arguments
+    ///
+    /// * `receipt_index` - an index of Receipt to validate
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt, with [`HostError::DuplicateAddKey`] if a key is added twice and with
+    /// [`HostError::DuplicateDeleteKey`] if a key is deleted twice.
+    pub(crate) fn validate_key_action_ordering(
+        &self,
+        receipt_index: ReceiptIndex,
+    ) -> logic::Result<()> {
+        // Whether the last action of the receipt for each key added it rather than deleted it.
+        let mut is_added = HashMap::new();
+        for action in self.actions(receipt_index)? {
+            let (public_key, adds) = match action {
+                Action::AddKey(action) => (&action.public_key, true),
+                Action::DeleteKey(action) => (&action.public_key, false),
+                _ => continue,
+            };
+            if is_added.insert(public_key, adds) == Some(adds) {
+                let public_key = public_key.to_string();
+                return Err(if adds {
+                    HostError::DuplicateAddKey { receipt_index, public_key }
+                } else {
+                    HostError::DuplicateDeleteKey { receipt_index, public_key }
+                }
+                .into());
+            }
+        }
+        Ok(())
     }
 
     /// Attach the [`CreateAccountAction`] action to an existing receipt.
@@ -129,17 +1016,62 @@ impl ReceiptManager {
     ///
     /// * `receipt_index` - an index of Receipt to append an action
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// [`ReceiptLimits`].
+    /// Fails with [`HostError::CreateImplicitAccount`] if the receiver is an implicit account,
+    /// once the `RejectCreateImplicitAccount` protocol feature is enabled.  Such accounts can
+    /// only be created by a receipt with a single transfer, so the receipt would fail anyway.
     pub(crate) fn append_action_create_account(
         &mut self,
         receipt_index: ReceiptIndex,
     ) -> logic::Result<()> {
-        self.append_action(receipt_index, Action::CreateAccount(CreateAccountAction {}));
+        if checked_feature!(
+            "protocol_feature_reject_create_implicit_account",
+            RejectCreateImplicitAccount,
+            self.current_protocol_version
+        ) {
+            let receiver_id = &self.action_receipts[self.check_receipt_index(receipt_index)?].0;
+            if receiver_id.is_implicit() {
+                return Err(HostError::CreateImplicitAccount {
+                    account_id: receiver_id.to_string(),
+                }
+                .into());
+            }
+        }
+        self.append_action(receipt_index, Action::CreateAccount(CreateAccountAction {}))?;
         Ok(())
     }
 
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if `value` is over one of the limits,
+    /// when they are checked.
+    fn check_limit(
+        &self,
+        limit_name: &str,
+        value: u64,
+        limit: impl FnOnce(&ReceiptLimits) -> u64,
+    ) -> logic::Result<()> {
+        match &self.limits {
+            Some(limits) => check_limit(limit_name, value, limit(limits)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with [`HostError::ContractSizeExceeded`] if a contract of `size` bytes can't be
+    /// deployed, when the limits are checked.
+    fn check_contract_size(&self, size: u64) -> logic::Result<()> {
+        match &self.limits {
+            Some(limits) if size > limits.max_contract_size => {
+                Err(HostError::ContractSizeExceeded { size, limit: limits.max_contract_size }
+                    .into())
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Attach the [`DeployContractAction`] action to an existing receipt.
     ///
     /// # Arguments
@@ -147,15 +1079,21 @@ impl ReceiptManager {
     /// * `receipt_index` - an index of Receipt to append an action
     /// * `code` - a Wasm code to attach
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ContractSizeExceeded`] if the code is bigger than
+    /// `max_contract_size`.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// other [`ReceiptLimits`].
     pub(crate) fn append_action_deploy_contract(
         &mut self,
         receipt_index: ReceiptIndex,
         code: Vec<u8>,
     ) -> logic::Result<()> {
-        self.append_action(receipt_index, Action::DeployContract(DeployContractAction { code }));
+        self.check_contract_size(code.len() as u64)?;
+        self.append_action(receipt_index, Action::DeployContract(DeployContractAction { code }))?;
         Ok(())
     }
 
@@ -178,9 +1116,12 @@ impl ReceiptManager {
     /// * `prepaid_gas` - amount of prepaid gas to attach to the call
     /// * `gas_weight` - relative weight of unused gas to distribute to the function call action
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// [`ReceiptLimits`].
     pub(crate) fn append_action_function_call_weight(
         &mut self,
         receipt_index: ReceiptIndex,
@@ -190,6 +1131,12 @@ impl ReceiptManager {
         prepaid_gas: Gas,
         gas_weight: GasWeight,
     ) -> logic::Result<()> {
+        self.check_limit("max_method_name_len", method_name.len() as u64, |limits| {
+            limits.max_method_name_len
+        })?;
+        self.check_limit("max_arguments_len", args.len() as u64, |limits| {
+            limits.max_arguments_len
+        })?;
         let action_index = self.append_action(
             receipt_index,
             Action::FunctionCall(FunctionCallAction {
@@ -199,13 +1146,14 @@ impl ReceiptManager {
                 gas: prepaid_gas,
                 deposit: attached_deposit,
             }),
-        );
+        )?;
 
         if gas_weight.0 > 0 {
             self.gas_weights.push((
                 FunctionCallActionIndex { receipt_index: receipt_index as usize, action_index },
                 gas_weight,
             ));
+            self.changes.push(Change::GasWeightAdded);
         }
 
         Ok(())
@@ -218,15 +1166,18 @@ impl ReceiptManager {
     /// * `receipt_index` - an index of Receipt to append an action
     /// * `amount` - amount of tokens to transfer
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// [`ReceiptLimits`].
     pub(crate) fn append_action_transfer(
         &mut self,
         receipt_index: ReceiptIndex,
         deposit: Balance,
     ) -> logic::Result<()> {
-        self.append_action(receipt_index, Action::Transfer(TransferAction { deposit }));
+        self.append_action(receipt_index, Action::Transfer(TransferAction { deposit }))?;
         Ok(())
     }
 
@@ -238,9 +1189,12 @@ impl ReceiptManager {
     /// * `stake` - amount of tokens to stake
     /// * `public_key` - a validator public key
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// [`ReceiptLimits`].
     pub(crate) fn append_action_stake(
         &mut self,
         receipt_index: ReceiptIndex,
@@ -254,7 +1208,7 @@ impl ReceiptManager {
                 public_key: PublicKey::try_from_slice(&public_key)
                     .map_err(|_| HostError::InvalidPublicKey)?,
             }),
-        );
+        )?;
         Ok(())
     }
 
@@ -266,24 +1220,25 @@ impl ReceiptManager {
     /// * `public_key` - a public key for an access key
     /// * `nonce` - a nonce
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// [`ReceiptLimits`].
     pub(crate) fn append_action_add_key_with_full_access(
         &mut self,
         receipt_index: ReceiptIndex,
         public_key: Vec<u8>,
         nonce: Nonce,
     ) -> logic::Result<()> {
-        self.append_action(
+        let public_key =
+            PublicKey::try_from_slice(&public_key).map_err(|_| HostError::InvalidPublicKey)?;
+        self.append_action_add_key(
             receipt_index,
-            Action::AddKey(AddKeyAction {
-                public_key: PublicKey::try_from_slice(&public_key)
-                    .map_err(|_| HostError::InvalidPublicKey)?,
-                access_key: AccessKey { nonce, permission: AccessKeyPermission::FullAccess },
-            }),
-        );
-        Ok(())
+            public_key,
+            AccessKey { nonce, permission: AccessKeyPermission::FullAccess },
+        )
     }
 
     /// Attach the [`AddKeyAction`] action an existing receipt.
@@ -300,9 +1255,20 @@ impl ReceiptManager {
     /// * `receiver_id` - a contract witch will be allowed to call with this access key
     /// * `method_names` - a list of method names is allowed to call with this access key (empty = any method)
     ///
-    /// # Panics
+    /// # Errors
+    ///
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// [`ReceiptLimits`].
+    /// Fails with [`HostError::InvalidAccessKeyMethodName`] if one of the `method_names` isn't
+    /// valid UTF-8, in which case the method names are all checked before the public key.
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Without [`ReceiptLimits`], i.e. before the `HostReceiptLimits` protocol feature, the
+    /// public key is parsed first and a method name which isn't valid UTF-8 fails with
+    /// [`HostError::InvalidMethodName`] instead, as it always did.
+    ///
+    /// Nothing is appended if any of the checks fails.
     pub(crate) fn append_action_add_key_with_function_call(
         &mut self,
         receipt_index: ReceiptIndex,
@@ -312,28 +1278,63 @@ impl ReceiptManager {
         receiver_id: AccountId,
         method_names: Vec<Vec<u8>>,
     ) -> logic::Result<()> {
-        self.append_action(
+        let (public_key, method_names) = if self.limits.is_some() {
+            let method_names = self.validate_access_key_method_names(method_names)?;
+            let public_key =
+                PublicKey::try_from_slice(&public_key).map_err(|_| HostError::InvalidPublicKey)?;
+            (public_key, method_names)
+        } else {
+            let public_key =
+                PublicKey::try_from_slice(&public_key).map_err(|_| HostError::InvalidPublicKey)?;
+            let method_names = method_names
+                .into_iter()
+                .map(|method_name| {
+                    String::from_utf8(method_name).map_err(|_| HostError::InvalidMethodName)
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            (public_key, method_names)
+        };
+        self.append_action_add_key(
             receipt_index,
-            Action::AddKey(AddKeyAction {
-                public_key: PublicKey::try_from_slice(&public_key)
-                    .map_err(|_| HostError::InvalidPublicKey)?,
-                access_key: AccessKey {
-                    nonce,
-                    permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
-                        allowance,
-                        receiver_id: receiver_id.into(),
-                        method_names: method_names
-                            .into_iter()
-                            .map(|method_name| {
-                                String::from_utf8(method_name)
-                                    .map_err(|_| HostError::InvalidMethodName)
-                            })
-                            .collect::<std::result::Result<Vec<_>, _>>()?,
-                    }),
-                },
-            }),
-        );
-        Ok(())
+            public_key,
+            AccessKey {
+                nonce,
+                permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                    allowance,
+                    receiver_id: receiver_id.into(),
+                    method_names,
+                }),
+            },
+        )
+    }
+
+    /// Checks the length of each of the methods allowed by an access key and their total
+    /// length, and converts them to strings.
+    fn validate_access_key_method_names(
+        &self,
+        method_names: Vec<Vec<u8>>,
+    ) -> logic::Result<Vec<String>> {
+        let mut total_number_of_bytes = 0u64;
+        for method_name in &method_names {
+            self.check_limit("max_method_name_len", method_name.len() as u64, |limits| {
+                limits.max_method_name_len
+            })?;
+            // Adding terminating character to the total number of bytes, like the runtime does.
+            total_number_of_bytes =
+                total_number_of_bytes.saturating_add(method_name.len() as u64 + 1);
+        }
+        self.check_limit("max_number_bytes_method_names", total_number_of_bytes, |limits| {
+            limits.max_number_bytes_method_names
+        })?;
+        method_names
+            .into_iter()
+            .enumerate()
+            .map(|(index, method_name)| {
+                String::from_utf8(method_name).map_err(|_| {
+                    HostError::InvalidAccessKeyMethodName { index: index as u64 }.into()
+                })
+            })
+            .collect()
     }
 
     /// Attach the [`DeleteKeyAction`] action to an existing receipt.
@@ -343,9 +1344,12 @@ impl ReceiptManager {
     /// * `receipt_index` - an index of Receipt to append an action
     /// * `public_key` - a public key for an access key to delete
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// [`ReceiptLimits`].
     pub(crate) fn append_action_delete_key(
         &mut self,
         receipt_index: ReceiptIndex,
@@ -357,7 +1361,7 @@ impl ReceiptManager {
                 public_key: PublicKey::try_from_slice(&public_key)
                     .map_err(|_| HostError::InvalidPublicKey)?,
             }),
-        );
+        )?;
         Ok(())
     }
 
@@ -368,9 +1372,12 @@ impl ReceiptManager {
     /// * `receipt_index` - an index of Receipt to append an action
     /// * `beneficiary_id` - an account id to which the rest of the funds of the removed account will be transferred
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the `receipt_index` does not refer to a known receipt.
+    /// Fails with [`HostError::InvalidReceiptIndex`] if the `receipt_index` does not refer to a
+    /// known receipt.
+    /// Fails with [`HostError::ReceiptLimitExceeded`] if the action would break one of the
+    /// [`ReceiptLimits`].
     pub(crate) fn append_action_delete_account(
         &mut self,
         receipt_index: ReceiptIndex,
@@ -379,7 +1386,7 @@ impl ReceiptManager {
         self.append_action(
             receipt_index,
             Action::DeleteAccount(DeleteAccountAction { beneficiary_id }),
-        );
+        )?;
         Ok(())
     }
 
@@ -433,3 +1440,128 @@ impl ReceiptManager {
         GasDistribution::All
     }
 }
+
+/// Appends actions to a receipt of a [`ReceiptManager`] without passing its index around, see
+/// [`ReceiptManager::build_receipt`].
+///
+/// The methods call the corresponding `append_action_*` method of the manager and fail the
+/// same way.
+pub(crate) struct ReceiptBuilder<'a> {
+    manager: &'a mut ReceiptManager,
+    ext: &'a mut dyn External,
+    receipt_index: ReceiptIndex,
+}
+
+impl ReceiptBuilder<'_> {
+    /// Index of the receipt in the [`ReceiptManager`].
+    pub(crate) fn receipt_index(&self) -> ReceiptIndex {
+        self.receipt_index
+    }
+
+    /// Create a receipt for `receiver_id` which will be executed after this one, and return a
+    /// builder for it.
+    // `VMLogic` creates dependent receipts with `build_receipt_after`, as a promise can depend
+    // on several receipts.
+    #[allow(dead_code)]
+    pub(crate) fn then(&mut self, receiver_id: AccountId) -> logic::Result<ReceiptBuilder<'_>> {
+        self.manager.build_receipt_after(self.ext, vec![self.receipt_index], receiver_id)
+    }
+
+    pub(crate) fn create_account(&mut self) -> logic::Result<&mut Self> {
+        self.manager.append_action_create_account(self.receipt_index)?;
+        Ok(self)
+    }
+
+    pub(crate) fn deploy_contract(&mut self, code: Vec<u8>) -> logic::Result<&mut Self> {
+        self.manager.append_action_deploy_contract(self.receipt_index, code)?;
+        Ok(self)
+    }
+
+    /// Appends a function call which doesn't get a share of the unused gas.
+    // `VMLogic` always passes the weight given by the contract.
+    #[allow(dead_code)]
+    pub(crate) fn function_call(
+        &mut self,
+        method_name: Vec<u8>,
+        args: Vec<u8>,
+        attached_deposit: Balance,
+        prepaid_gas: Gas,
+    ) -> logic::Result<&mut Self> {
+        self.function_call_weight(method_name, args, attached_deposit, prepaid_gas, GasWeight(0))
+    }
+
+    pub(crate) fn function_call_weight(
+        &mut self,
+        method_name: Vec<u8>,
+        args: Vec<u8>,
+        attached_deposit: Balance,
+        prepaid_gas: Gas,
+        gas_weight: GasWeight,
+    ) -> logic::Result<&mut Self> {
+        self.manager.append_action_function_call_weight(
+            self.receipt_index,
+            method_name,
+            args,
+            attached_deposit,
+            prepaid_gas,
+            gas_weight,
+        )?;
+        Ok(self)
+    }
+
+    pub(crate) fn transfer(&mut self, deposit: Balance) -> logic::Result<&mut Self> {
+        self.manager.append_action_transfer(self.receipt_index, deposit)?;
+        Ok(self)
+    }
+
+    pub(crate) fn stake(
+        &mut self,
+        stake: Balance,
+        public_key: Vec<u8>,
+    ) -> logic::Result<&mut Self> {
+        self.manager.append_action_stake(self.receipt_index, stake, public_key)?;
+        Ok(self)
+    }
+
+    pub(crate) fn add_full_access_key(
+        &mut self,
+        public_key: Vec<u8>,
+        nonce: Nonce,
+    ) -> logic::Result<&mut Self> {
+        self.manager.append_action_add_key_with_full_access(
+            self.receipt_index,
+            public_key,
+            nonce,
+        )?;
+        Ok(self)
+    }
+
+    pub(crate) fn add_function_call_key(
+        &mut self,
+        public_key: Vec<u8>,
+        nonce: Nonce,
+        allowance: Option<Balance>,
+        receiver_id: AccountId,
+        method_names: Vec<Vec<u8>>,
+    ) -> logic::Result<&mut Self> {
+        self.manager.append_action_add_key_with_function_call(
+            self.receipt_index,
+            public_key,
+            nonce,
+            allowance,
+            receiver_id,
+            method_names,
+        )?;
+        Ok(self)
+    }
+
+    pub(crate) fn delete_key(&mut self, public_key: Vec<u8>) -> logic::Result<&mut Self> {
+        self.manager.append_action_delete_key(self.receipt_index, public_key)?;
+        Ok(self)
+    }
+
+    pub(crate) fn delete_account(&mut self, beneficiary_id: AccountId) -> logic::Result<&mut Self> {
+        self.manager.append_action_delete_account(self.receipt_index, beneficiary_id)?;
+        Ok(self)
+    }
+}
@@ -19,6 +19,7 @@ pub fn create_context() -> VMContext {
         random_seed: vec![0, 1, 2],
         view_config: None,
         output_data_receivers: vec![],
+        congestion: None,
     }
 }
 
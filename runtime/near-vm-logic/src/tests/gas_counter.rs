@@ -1,10 +1,13 @@
-use crate::receipt_manager::ReceiptMetadata;
+use crate::mocks::mock_external::MockedExternal;
+use crate::receipt_manager::{ReceiptLimits, ReceiptMetadata};
 use crate::tests::fixtures::get_context;
 use crate::tests::helpers::*;
-use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::tests::receipt_manager::test_manager;
+use crate::tests::vm_logic_builder::{VMLogicBuilder, LATEST_PROTOCOL_VERSION};
 use crate::types::Gas;
-use crate::{VMConfig, VMLogic};
+use crate::{VMConfig, VMLimitConfig, VMLogic};
 use near_primitives::transaction::{Action, FunctionCallAction};
+use near_primitives_core::types::{GasDistribution, GasWeight};
 
 #[test]
 fn test_dont_burn_gas_when_exceeding_attached_gas_limit() {
@@ -219,6 +222,88 @@ fn function_call_no_weight_refund() {
     assert!(outcome.used_gas < gas_limit);
 }
 
+/// Schedules a function call with the given static gas and weight per pair and
+/// distributes `unused_gas` among them.  Returns the distribution and the gas
+/// each call got on top of its static gas.
+fn distribute(calls: &[(Gas, u64)], unused_gas: Gas) -> (GasDistribution, Vec<Gas>) {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    for &(static_gas, weight) in calls {
+        let index = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+        manager
+            .append_action_function_call_weight(
+                index,
+                b"f".to_vec(),
+                vec![],
+                0,
+                static_gas,
+                GasWeight(weight),
+            )
+            .unwrap();
+    }
+    let distribution = manager.distribute_unused_gas(unused_gas);
+    // The weights are used up, so distributing again changes nothing.
+    assert_eq!(manager.distribute_unused_gas(unused_gas), GasDistribution::NoRatios);
+    let assigned = manager
+        .action_receipts
+        .iter()
+        .zip(calls)
+        .map(|((_, receipt), &(static_gas, _))| match &receipt.actions[..] {
+            [Action::FunctionCall(FunctionCallAction { gas, .. })] => gas - static_gas,
+            actions => panic!("expected a function call, got {:?}", actions),
+        })
+        .collect();
+    (distribution, assigned)
+}
+
+#[test]
+fn distribute_unused_gas_without_weights() {
+    assert_eq!(distribute(&[], 1000), (GasDistribution::NoRatios, vec![]));
+    assert_eq!(distribute(&[(10, 0), (20, 0)], 1000), (GasDistribution::NoRatios, vec![0, 0]));
+}
+
+#[test]
+fn distribute_unused_gas_sums_to_unused_gas() {
+    // A fixed linear congruential generator, so that failures are reproducible.
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        state >> 11
+    };
+    for _ in 0..1000 {
+        let calls: Vec<(Gas, u64)> = (0..next() % 8)
+            .map(|_| {
+                let weight = match next() % 4 {
+                    0 => 0,
+                    1 => u64::MAX - next() % 10,
+                    _ => next() % 1000,
+                };
+                (next() % 1_000_000, weight)
+            })
+            .collect();
+        let unused_gas = match next() % 3 {
+            0 => 0,
+            1 => u64::MAX - next(),
+            _ => next() % 10u64.pow(15),
+        };
+        let (distribution, assigned) = distribute(&calls, unused_gas);
+        if calls.iter().all(|&(_, weight)| weight == 0) {
+            assert_eq!(distribution, GasDistribution::NoRatios, "{:?}", calls);
+            assert!(assigned.iter().all(|&gas| gas == 0), "{:?}", calls);
+            continue;
+        }
+        assert_eq!(distribution, GasDistribution::All, "{:?}", calls);
+        let total: u128 = assigned.iter().map(|&gas| gas as u128).sum();
+        assert_eq!(total, unused_gas as u128, "{:?} with {}", calls, unused_gas);
+        for (&(_, weight), &gas) in calls.iter().zip(&assigned) {
+            if weight == 0 {
+                assert_eq!(gas, 0, "{:?} with {}", calls, unused_gas);
+            }
+        }
+    }
+}
+
 impl VMLogicBuilder {
     fn max_gas_burnt(mut self, max_gas_burnt: Gas) -> Self {
         self.config.limit_config.max_gas_burnt = max_gas_burnt;
@@ -7,6 +7,7 @@ mod helpers;
 mod iterators;
 mod miscs;
 mod promises;
+mod receipt_manager;
 mod registers;
 mod storage_read_write;
 mod storage_usage;
@@ -6,9 +6,11 @@ use crate::VMLogic;
 use borsh::BorshSerialize;
 use near_account_id::AccountId;
 use near_crypto::PublicKey;
+use near_primitives::receipt::DataReceiver;
 use near_primitives::transaction::Action;
 use serde::Serialize;
 use serde_json;
+use std::collections::HashSet;
 
 #[derive(Serialize)]
 struct ReceiptView<'a> {
@@ -421,3 +423,64 @@ fn test_promise_batch_then() {
     ]);
     assert_eq!(&serde_json::to_string(&vm_receipts(&logic)).unwrap(), &expected.to_string());
 }
+
+#[test]
+fn test_promise_batch_then_data_ids() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let first = promise_batch_create(&mut logic, "rick.test").expect("should create a promise");
+    let second = promise_batch_create(&mut logic, "morty.test").expect("should create a promise");
+    let promises = [first, second];
+    let both = logic
+        .promise_and(promises.as_ptr() as _, promises.len() as _)
+        .expect("should create a non-receipt promise");
+    let account_id = b"alice.test";
+    logic
+        .promise_batch_then(both, account_id.len() as u64, account_id.as_ptr() as _)
+        .expect("should create a receipt depending on both promises");
+    logic
+        .promise_batch_then(first, account_id.len() as u64, account_id.as_ptr() as _)
+        .expect("should create a receipt depending on the first promise");
+
+    let receipts: Vec<_> =
+        logic.receipt_manager().action_receipts.iter().map(|(_, receipt)| receipt).collect();
+    assert_eq!(receipts.len(), 4);
+    let (after_both, after_first) = (receipts[2], receipts[3]);
+    assert_eq!(after_both.input_data_ids.len(), 2);
+    assert_eq!(after_first.input_data_ids.len(), 1);
+    let receiver = |data_id| DataReceiver { data_id, receiver_id: "alice.test".parse().unwrap() };
+    // Every dependency sends its output to exactly the input data ids of the
+    // receipts depending on it.
+    assert_eq!(
+        receipts[0].output_data_receivers,
+        vec![receiver(after_both.input_data_ids[0]), receiver(after_first.input_data_ids[0])]
+    );
+    assert_eq!(receipts[1].output_data_receivers, vec![receiver(after_both.input_data_ids[1])]);
+    assert!(after_both.output_data_receivers.is_empty());
+    assert!(after_first.output_data_receivers.is_empty());
+
+    let data_ids: HashSet<_> =
+        after_both.input_data_ids.iter().chain(&after_first.input_data_ids).collect();
+    assert_eq!(data_ids.len(), 3, "data ids must not collide");
+}
+
+#[test]
+fn test_outcome_receipt_congestion() {
+    use near_primitives::receipt::ReceiptCongestion;
+
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut context = get_context(vec![], false);
+    let parent = ReceiptCongestion { upstream_gas_burnt: 5, priority: 2 };
+    context.congestion = Some(parent);
+    let mut logic = logic_builder.build(context);
+    promise_create(&mut logic, b"rick.test", 0, 0).expect("should create a promise");
+    let outcome = logic.compute_outcome_and_distribute_gas();
+
+    let expected = if cfg!(feature = "protocol_feature_receipt_congestion_metadata") {
+        Some(ReceiptCongestion { upstream_gas_burnt: 5 + outcome.burnt_gas, priority: 2 })
+    } else {
+        None
+    };
+    assert_eq!(outcome.action_receipts.len(), 1);
+    assert_eq!(outcome.action_receipts[0].1.congestion(), expected);
+}
@@ -0,0 +1,1428 @@
+use crate::mocks::mock_external::MockedExternal;
+use crate::receipt_manager::{DuplicateAddKey, ReceiptLimits, ReceiptManager};
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::{
+    promise_batch_action_add_key_with_function_call, promise_batch_action_function_call,
+    promise_batch_create,
+};
+use crate::tests::vm_logic_builder::{VMLogicBuilder, LATEST_PROTOCOL_VERSION};
+use crate::types::{PromiseResult, ReceiptIndex, ReturnData};
+use crate::{ActionCosts, VMLimitConfig, VMLogic};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::{KeyType, PublicKey};
+use near_primitives::errors::IntegerOverflowError;
+use near_primitives::hash::hash;
+use near_primitives::receipt::{DataReceipt, DataReceiver};
+use near_primitives::transaction::{Action, TransferAction};
+use near_primitives::types::{AccountId, Balance, Gas, ProtocolVersion};
+use near_primitives::version::ProtocolFeature;
+use near_primitives_core::runtime::fees::{
+    AccessKeyCreationConfig, ActionCreationConfig, Fee, RuntimeFeesConfig,
+};
+use near_primitives_core::types::{GasDistribution, GasWeight};
+use near_vm_errors::{HostError, VMLogicError};
+
+/// Returns a manager of the receipts created by `alice.test`, with the test fees, checking
+/// the `limits`.
+pub(super) fn test_manager(
+    limits: ReceiptLimits,
+    protocol_version: ProtocolVersion,
+) -> ReceiptManager {
+    ReceiptManager::new(
+        Some(limits),
+        RuntimeFeesConfig::test().action_creation_config,
+        RuntimeFeesConfig::test().storage_usage_config,
+        "alice.test".parse().unwrap(),
+        protocol_version,
+        DuplicateAddKey::Allow,
+    )
+}
+
+#[test]
+fn append_action_with_invalid_receipt_index() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let valid = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+
+    for receipt_index in [valid + 1, ReceiptIndex::MAX] {
+        let expected =
+            Err(VMLogicError::HostError(HostError::InvalidReceiptIndex { receipt_index }));
+        assert_eq!(manager.append_action_create_account(receipt_index), expected);
+        assert_eq!(manager.append_action_deploy_contract(receipt_index, vec![]), expected);
+        assert_eq!(
+            manager.append_action_function_call_weight(
+                receipt_index,
+                b"f".to_vec(),
+                vec![],
+                0,
+                0,
+                GasWeight(1)
+            ),
+            expected
+        );
+        assert_eq!(manager.append_action_transfer(receipt_index, 1), expected);
+        assert_eq!(manager.append_action_stake(receipt_index, 1, public_key.clone()), expected);
+        assert_eq!(
+            manager.append_action_add_key_with_full_access(receipt_index, public_key.clone(), 0),
+            expected
+        );
+        assert_eq!(
+            manager.append_action_add_key_with_function_call(
+                receipt_index,
+                public_key.clone(),
+                0,
+                None,
+                "rick.test".parse().unwrap(),
+                vec![],
+            ),
+            expected
+        );
+        assert_eq!(manager.append_action_delete_key(receipt_index, public_key.clone()), expected);
+        assert_eq!(
+            manager.append_action_delete_account(receipt_index, "rick.test".parse().unwrap()),
+            expected
+        );
+    }
+
+    // Nothing was appended anywhere, and the weight of the failed function call
+    // wasn't recorded.
+    assert!(manager.action_receipts[0].1.actions.is_empty());
+    assert_eq!(manager.distribute_unused_gas(100), GasDistribution::NoRatios);
+    manager.append_action_transfer(valid, 1).unwrap();
+    assert_eq!(manager.action_receipts[0].1.actions.len(), 1);
+}
+
+fn limit_exceeded(limit_name: &str, value: u64, limit: u64) -> Result<(), VMLogicError> {
+    Err(HostError::ReceiptLimitExceeded { limit_name: limit_name.to_string(), value, limit }.into())
+}
+
+/// Returns a manager with the given limits and an empty receipt at index 0.
+fn manager_with_limits(limits: ReceiptLimits) -> ReceiptManager {
+    let mut manager = test_manager(limits, LATEST_PROTOCOL_VERSION);
+    manager
+        .create_receipt(&mut MockedExternal::default(), vec![], "rick.test".parse().unwrap())
+        .unwrap();
+    manager
+}
+
+fn large_limits() -> ReceiptLimits {
+    ReceiptLimits {
+        max_actions_per_receipt: u64::MAX,
+        max_method_name_len: u64::MAX,
+        max_number_bytes_method_names: u64::MAX,
+        max_arguments_len: u64::MAX,
+        max_contract_size: u64::MAX,
+    }
+}
+
+#[test]
+fn receipt_limit_max_actions_per_receipt() {
+    let mut manager =
+        manager_with_limits(ReceiptLimits { max_actions_per_receipt: 2, ..large_limits() });
+    manager.append_action_transfer(0, 1).unwrap();
+    manager.append_action_transfer(0, 1).unwrap();
+    assert_eq!(
+        manager.append_action_transfer(0, 1),
+        limit_exceeded("max_actions_per_receipt", 3, 2)
+    );
+    assert_eq!(
+        manager.append_action_create_account(0),
+        limit_exceeded("max_actions_per_receipt", 3, 2)
+    );
+    assert_eq!(manager.action_receipts[0].1.actions.len(), 2);
+
+    // The limit applies to every receipt on its own.
+    let other = manager
+        .create_receipt(&mut MockedExternal::default(), vec![], "rick.test".parse().unwrap())
+        .unwrap();
+    manager.append_action_transfer(other, 1).unwrap();
+}
+
+#[test]
+fn receipt_limit_max_method_name_len() {
+    let mut manager =
+        manager_with_limits(ReceiptLimits { max_method_name_len: 3, ..large_limits() });
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+    manager
+        .append_action_function_call_weight(0, b"abc".to_vec(), vec![], 0, 0, GasWeight(0))
+        .unwrap();
+    assert_eq!(
+        manager.append_action_function_call_weight(0, b"abcd".to_vec(), vec![], 0, 0, GasWeight(1)),
+        limit_exceeded("max_method_name_len", 4, 3)
+    );
+    manager
+        .append_action_add_key_with_function_call(
+            0,
+            public_key.clone(),
+            0,
+            None,
+            "rick.test".parse().unwrap(),
+            vec![b"a".to_vec(), b"abc".to_vec()],
+        )
+        .unwrap();
+    assert_eq!(
+        manager.append_action_add_key_with_function_call(
+            0,
+            public_key,
+            0,
+            None,
+            "rick.test".parse().unwrap(),
+            vec![b"a".to_vec(), b"abcd".to_vec()],
+        ),
+        limit_exceeded("max_method_name_len", 4, 3)
+    );
+    assert_eq!(manager.action_receipts[0].1.actions.len(), 2);
+    // The weight of the failed call wasn't recorded.
+    assert_eq!(manager.distribute_unused_gas(100), GasDistribution::NoRatios);
+}
+
+/// Appends a function call access key allowing `method_names` to the receipt at index 0.
+fn add_function_call_key(
+    manager: &mut ReceiptManager,
+    method_names: &[&[u8]],
+) -> Result<(), VMLogicError> {
+    manager.append_action_add_key_with_function_call(
+        0,
+        PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap(),
+        0,
+        None,
+        "rick.test".parse().unwrap(),
+        method_names.iter().map(|method_name| method_name.to_vec()).collect(),
+    )
+}
+
+#[test]
+fn receipt_limit_max_number_bytes_method_names() {
+    let mut manager =
+        manager_with_limits(ReceiptLimits { max_number_bytes_method_names: 6, ..large_limits() });
+    add_function_call_key(&mut manager, &[b"ab", b"c", b""]).unwrap();
+    assert_eq!(
+        add_function_call_key(&mut manager, &[b"ab", b"cde"]),
+        limit_exceeded("max_number_bytes_method_names", 7, 6)
+    );
+    // Every name counts, so the limit also bounds the number of names.
+    assert_eq!(
+        add_function_call_key(&mut manager, &[b"a", b"b", b"c", b"d"]),
+        limit_exceeded("max_number_bytes_method_names", 8, 6)
+    );
+    assert_eq!(manager.action_receipts[0].1.actions.len(), 1);
+}
+
+#[test]
+fn add_key_with_invalid_method_name() {
+    let mut manager = manager_with_limits(ReceiptLimits::from(&VMLimitConfig::test()));
+    let invalid: &[u8] = &[b'f', 0xff];
+    for (method_names, index) in [
+        (&[invalid][..], 0),
+        (&[b"a", b"b", invalid, b"d", b"e"][..], 2),
+        (&[b"a", invalid, b"c", invalid][..], 1),
+    ] {
+        assert_eq!(
+            add_function_call_key(&mut manager, method_names),
+            Err(HostError::InvalidAccessKeyMethodName { index }.into())
+        );
+    }
+    // The method names are checked before the public key.
+    assert_eq!(
+        manager.append_action_add_key_with_function_call(
+            0,
+            vec![],
+            0,
+            None,
+            "rick.test".parse().unwrap(),
+            vec![invalid.to_vec()],
+        ),
+        Err(HostError::InvalidAccessKeyMethodName { index: 0 }.into())
+    );
+    assert_eq!(manager.actions(0), Ok(&[][..]));
+    assert_eq!(manager.burnt_gas(), 0);
+
+    add_function_call_key(&mut manager, &[b"a", b"b", b"c", b"d", b"e"]).unwrap();
+    assert_eq!(manager.actions(0).unwrap().len(), 1);
+}
+
+#[test]
+fn add_key_with_invalid_method_name_unchecked() {
+    let mut manager = ReceiptManager::new(
+        None,
+        RuntimeFeesConfig::test().action_creation_config,
+        RuntimeFeesConfig::test().storage_usage_config,
+        "alice.test".parse().unwrap(),
+        LATEST_PROTOCOL_VERSION,
+        DuplicateAddKey::Allow,
+    );
+    manager
+        .create_receipt(&mut MockedExternal::default(), vec![], "rick.test".parse().unwrap())
+        .unwrap();
+    let invalid: &[u8] = &[b'f', 0xff];
+    assert_eq!(
+        add_function_call_key(&mut manager, &[b"a", invalid]),
+        Err(HostError::InvalidMethodName.into())
+    );
+    // The public key is checked before the method names.
+    assert_eq!(
+        manager.append_action_add_key_with_function_call(
+            0,
+            vec![],
+            0,
+            None,
+            "rick.test".parse().unwrap(),
+            vec![invalid.to_vec()],
+        ),
+        Err(HostError::InvalidPublicKey.into())
+    );
+    assert_eq!(manager.actions(0), Ok(&[][..]));
+}
+
+#[test]
+fn receipt_limit_max_contract_size() {
+    let mut manager =
+        manager_with_limits(ReceiptLimits { max_contract_size: 10, ..large_limits() });
+    manager.append_action_deploy_contract(0, vec![0; 10]).unwrap();
+    assert_eq!(
+        manager.append_action_deploy_contract(0, vec![0; 11]),
+        Err(HostError::ContractSizeExceeded { size: 11, limit: 10 }.into())
+    );
+    assert_eq!(manager.action_receipts[0].1.actions.len(), 1);
+    assert_eq!(manager.storage_delta(0), Ok(10));
+}
+
+#[test]
+fn receipt_limit_max_arguments_len() {
+    let mut manager = manager_with_limits(ReceiptLimits { max_arguments_len: 4, ..large_limits() });
+    manager
+        .append_action_function_call_weight(0, b"f".to_vec(), vec![0; 4], 0, 0, GasWeight(0))
+        .unwrap();
+    assert_eq!(
+        manager.append_action_function_call_weight(
+            0,
+            b"f".to_vec(),
+            vec![0; 5],
+            0,
+            0,
+            GasWeight(0)
+        ),
+        limit_exceeded("max_arguments_len", 5, 4)
+    );
+    assert_eq!(manager.action_receipts[0].1.actions.len(), 1);
+}
+
+#[test]
+fn receipt_limits_unchecked() {
+    let mut manager = ReceiptManager::new(
+        None,
+        RuntimeFeesConfig::test().action_creation_config,
+        RuntimeFeesConfig::test().storage_usage_config,
+        "alice.test".parse().unwrap(),
+        LATEST_PROTOCOL_VERSION,
+        DuplicateAddKey::Allow,
+    );
+    let mut ext = MockedExternal::default();
+    let index = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let limits = ReceiptLimits::from(&VMLimitConfig::test());
+    for _ in 0..=limits.max_actions_per_receipt {
+        manager.append_action_transfer(index, 1).unwrap();
+    }
+    manager
+        .append_action_deploy_contract(index, vec![0; limits.max_contract_size as usize + 1])
+        .unwrap();
+    manager
+        .append_action_function_call_weight(
+            index,
+            vec![b'f'; limits.max_method_name_len as usize + 1],
+            vec![0; limits.max_arguments_len as usize + 1],
+            0,
+            0,
+            GasWeight(1),
+        )
+        .unwrap();
+    assert_eq!(
+        manager.action_receipts[0].1.actions.len() as u64,
+        limits.max_actions_per_receipt + 3
+    );
+}
+
+#[cfg(feature = "protocol_feature_host_receipt_limits")]
+#[test]
+fn invalid_method_name_error_from_feature() {
+    let feature_version = ProtocolFeature::HostReceiptLimits.protocol_version();
+    for (protocol_version, expected) in [
+        (feature_version - 1, HostError::InvalidMethodName),
+        (feature_version, HostError::InvalidAccessKeyMethodName { index: 1 }),
+    ] {
+        let logic_builder =
+            VMLogicBuilder { current_protocol_version: protocol_version, ..Default::default() };
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let index = promise_batch_create(&mut logic, "rick.test").unwrap();
+        let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+        assert_eq!(
+            promise_batch_action_add_key_with_function_call(
+                &mut logic,
+                index,
+                &public_key,
+                0,
+                0,
+                b"rick.test",
+                b"a,f\xff",
+            ),
+            Err(expected.into())
+        );
+    }
+}
+
+#[cfg(feature = "protocol_feature_host_receipt_limits")]
+#[test]
+fn receipt_limits_checked_from_feature() {
+    let feature_version = ProtocolFeature::HostReceiptLimits.protocol_version();
+    for (protocol_version, expected) in [
+        (feature_version - 1, Ok(())),
+        (feature_version, limit_exceeded("max_actions_per_receipt", 2, 1)),
+    ] {
+        let mut logic_builder =
+            VMLogicBuilder { current_protocol_version: protocol_version, ..Default::default() };
+        logic_builder.config.limit_config.max_actions_per_receipt = 1;
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let index = promise_batch_create(&mut logic, "rick.test").unwrap();
+        promise_batch_action_function_call(&mut logic, index, 0, 0).unwrap();
+        assert_eq!(promise_batch_action_function_call(&mut logic, index, 0, 0), expected);
+    }
+}
+
+#[test]
+fn totals_of_deposits_and_prepaid_gas() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let second = manager.create_receipt(&mut ext, vec![], "morty.test".parse().unwrap()).unwrap();
+    assert_eq!(manager.total_attached_deposit(), Ok(0));
+    assert_eq!(manager.total_prepaid_gas(), Ok(0));
+    assert_eq!(manager.receipt_attached_deposit(first), Ok(0));
+
+    manager.append_action_transfer(first, 10).unwrap();
+    manager
+        .append_action_function_call_weight(first, b"f".to_vec(), vec![], 1, 100, GasWeight(0))
+        .unwrap();
+    // A stake locks the amount rather than sending it, so it isn't a deposit.
+    manager.append_action_stake(first, 1000, public_key).unwrap();
+    manager
+        .append_action_function_call_weight(second, b"g".to_vec(), vec![], 2, 200, GasWeight(0))
+        .unwrap();
+
+    assert_eq!(manager.total_attached_deposit(), Ok(13));
+    assert_eq!(manager.total_prepaid_gas(), Ok(300));
+    assert_eq!(manager.receipt_attached_deposit(first), Ok(11));
+    assert_eq!(manager.receipt_prepaid_gas(first), Ok(100));
+    assert_eq!(manager.receipt_attached_deposit(second), Ok(2));
+    assert_eq!(manager.receipt_prepaid_gas(second), Ok(200));
+
+    let invalid = second + 1;
+    let expected = HostError::InvalidReceiptIndex { receipt_index: invalid };
+    assert_eq!(manager.receipt_attached_deposit(invalid), Err(expected.clone().into()));
+    assert_eq!(manager.receipt_prepaid_gas(invalid), Err(expected.into()));
+}
+
+#[test]
+fn totals_overflow() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let second = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    for index in [first, second] {
+        manager.append_action_transfer(index, Balance::MAX).unwrap();
+        manager
+            .append_action_function_call_weight(
+                index,
+                b"f".to_vec(),
+                vec![],
+                0,
+                Gas::MAX,
+                GasWeight(0),
+            )
+            .unwrap();
+    }
+    // Each receipt on its own fits, together they don't.
+    assert_eq!(manager.receipt_attached_deposit(first), Ok(Balance::MAX));
+    assert_eq!(manager.receipt_prepaid_gas(first), Ok(Gas::MAX));
+    assert_eq!(manager.total_attached_deposit(), Err(IntegerOverflowError));
+    assert_eq!(manager.total_prepaid_gas(), Err(IntegerOverflowError));
+
+    manager.append_action_transfer(first, 1).unwrap();
+    assert_eq!(manager.receipt_attached_deposit(first), Err(HostError::IntegerOverflow.into()));
+}
+
+#[test]
+fn create_receipt_dependent_on_data() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let [a, b] = [hash(b"a"), hash(b"b")];
+    let index =
+        manager.create_receipt_dependent_on_data(vec![a, b, a], "rick.test".parse().unwrap());
+    manager.append_action_transfer(index, 1).unwrap();
+
+    let (receiver_id, receipt) = &manager.action_receipts[index as usize];
+    assert_eq!(receiver_id.as_ref(), "rick.test");
+    assert_eq!(receipt.input_data_ids, vec![a, b]);
+    assert!(receipt.output_data_receivers.is_empty());
+    assert_eq!(receipt.actions.len(), 1);
+}
+
+#[test]
+fn add_output_data_receiver() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let second =
+        manager.create_receipt(&mut ext, vec![first], "morty.test".parse().unwrap()).unwrap();
+    let data_id = hash(b"yielded");
+    let morty: AccountId = "morty.test".parse().unwrap();
+    let summer: AccountId = "summer.test".parse().unwrap();
+
+    manager.add_output_data_receiver(first, data_id, summer.clone()).unwrap();
+    manager.add_output_data_receiver(first, data_id, summer.clone()).unwrap();
+    let receivers = &manager.action_receipts[first as usize].1.output_data_receivers;
+    // The receiver added by `create_receipt` matches the input of the dependent receipt.
+    assert_eq!(
+        receivers,
+        &vec![
+            DataReceiver {
+                data_id: manager.action_receipts[second as usize].1.input_data_ids[0],
+                receiver_id: morty,
+            },
+            DataReceiver { data_id, receiver_id: summer.clone() },
+        ]
+    );
+
+    let third = manager.create_receipt_dependent_on_data(vec![data_id], summer.clone());
+    assert_eq!(manager.action_receipts[third as usize].1.input_data_ids, vec![data_id]);
+
+    let invalid = third + 1;
+    assert_eq!(
+        manager.add_output_data_receiver(invalid, data_id, summer),
+        Err(HostError::InvalidReceiptIndex { receipt_index: invalid }.into())
+    );
+}
+
+#[test]
+fn cancel_receipt() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let other = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let dependent = manager
+        .create_receipt(&mut ext, vec![first, other], "morty.test".parse().unwrap())
+        .unwrap();
+    let last = manager.create_receipt(&mut ext, vec![], "summer.test".parse().unwrap()).unwrap();
+    manager
+        .append_action_function_call_weight(dependent, b"f".to_vec(), vec![], 1, 10, GasWeight(1))
+        .unwrap();
+    manager.append_action_transfer(last, 2).unwrap();
+
+    assert_eq!(
+        manager.cancel_receipt(first),
+        Err(HostError::ReceiptHasDependents { receipt_index: first }.into())
+    );
+    manager.cancel_receipt(dependent).unwrap();
+    // The receipts it depended on no longer send it their output.
+    assert!(manager.action_receipts[first as usize].1.output_data_receivers.is_empty());
+    assert!(manager.action_receipts[other as usize].1.output_data_receivers.is_empty());
+    manager.cancel_receipt(first).unwrap();
+
+    // Cancelled receipts are gone for good, the others keep their indices.
+    for receipt_index in [first, dependent] {
+        let expected = HostError::InvalidReceiptIndex { receipt_index };
+        assert_eq!(manager.cancel_receipt(receipt_index), Err(expected.clone().into()));
+        assert_eq!(manager.append_action_transfer(receipt_index, 1), Err(expected.clone().into()));
+        assert_eq!(
+            manager.create_receipt(&mut ext, vec![receipt_index], "rick.test".parse().unwrap()),
+            Err(expected.into())
+        );
+    }
+    manager.append_action_transfer(last, 3).unwrap();
+    assert_eq!(manager.receipt_attached_deposit(last), Ok(5));
+    assert_eq!(manager.total_attached_deposit(), Ok(5));
+    assert_eq!(manager.total_prepaid_gas(), Ok(0));
+    // The cancelled function call doesn't get a share of the unused gas.
+    assert_eq!(manager.distribute_unused_gas(100), GasDistribution::NoRatios);
+
+    assert_eq!(manager.sent_receipt_index(other), Ok(0));
+    assert_eq!(manager.sent_receipt_index(last), Ok(1));
+    let receipts = manager.into_action_receipts(None);
+    let receivers: Vec<_> = receipts.iter().map(|(receiver_id, _)| receiver_id.as_ref()).collect();
+    assert_eq!(receivers, ["rick.test", "summer.test"]);
+    assert_eq!(receipts[1].1.actions.len(), 2);
+}
+
+#[test]
+fn return_promise_created_after_replaced() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let replaced = promise_batch_create(&mut logic, "morty.test").unwrap();
+    let returned = promise_batch_create(&mut logic, "summer.test").unwrap();
+    logic.return_receipt_data(replaced, None).unwrap();
+    assert_eq!(
+        logic.promise_return(replaced),
+        Err(HostError::InvalidPromiseIndex { promise_idx: replaced }.into())
+    );
+    logic.promise_return(returned).unwrap();
+    // The runtime sends the output of the current receipt to the returned one.
+    assert_eq!(
+        logic.return_receipt_data(returned, None),
+        Err(HostError::ReceiptHasDependents { receipt_index: returned }.into())
+    );
+
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    assert_eq!(outcome.return_data, ReturnData::ReceiptIndex(0));
+    assert_eq!(outcome.action_receipts.len(), 1);
+    assert_eq!(outcome.action_receipts[0].0.as_ref(), "summer.test");
+}
+
+/// Appends a `CreateAccount` action to a receipt for an implicit account and
+/// for a named one, at the given protocol version.
+fn create_accounts(
+    protocol_version: ProtocolVersion,
+) -> (Result<(), VMLogicError>, ReceiptManager) {
+    let mut manager = test_manager(ReceiptLimits::from(&VMLimitConfig::test()), protocol_version);
+    let mut ext = MockedExternal::default();
+    let implicit = "a".repeat(64).parse().unwrap();
+    let implicit_index = manager.create_receipt(&mut ext, vec![], implicit).unwrap();
+    let named_index =
+        manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    manager.append_action_create_account(named_index).unwrap();
+    // Transfers are how implicit accounts are created.
+    manager.append_action_transfer(implicit_index, 1).unwrap();
+    (manager.append_action_create_account(implicit_index), manager)
+}
+
+#[test]
+fn create_implicit_account_before_feature() {
+    let (result, manager) =
+        create_accounts(ProtocolFeature::ImplicitAccountCreation.protocol_version());
+    assert_eq!(result, Ok(()));
+    assert_eq!(manager.action_receipts[0].1.actions.len(), 2);
+}
+
+#[cfg(feature = "protocol_feature_reject_create_implicit_account")]
+#[test]
+fn create_implicit_account_rejected() {
+    let feature_version = ProtocolFeature::RejectCreateImplicitAccount.protocol_version();
+    let (result, _) = create_accounts(feature_version - 1);
+    assert_eq!(result, Ok(()));
+
+    let (result, manager) = create_accounts(feature_version);
+    assert_eq!(result, Err(HostError::CreateImplicitAccount { account_id: "a".repeat(64) }.into()));
+    assert_eq!(manager.action_receipts[0].1.actions.len(), 1);
+    // Named accounts are still created.
+    assert_eq!(manager.action_receipts[1].1.actions.len(), 1);
+}
+
+#[test]
+fn checkpoint_and_rollback() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    manager.append_action_transfer(first, 1).unwrap();
+    let checkpoint = manager.checkpoint();
+    let expected = manager.clone();
+
+    let second =
+        manager.create_receipt(&mut ext, vec![first], "morty.test".parse().unwrap()).unwrap();
+    manager.append_action_transfer(first, 2).unwrap();
+    manager
+        .append_action_function_call_weight(second, b"f".to_vec(), vec![], 0, 0, GasWeight(1))
+        .unwrap();
+    manager.cancel_receipt(second).unwrap();
+    manager.create_receipt(&mut ext, vec![], "summer.test".parse().unwrap()).unwrap();
+
+    manager.rollback(checkpoint);
+    assert_eq!(manager.burnt_gas(), expected.burnt_gas());
+    assert_eq!(manager.used_gas(), expected.used_gas());
+    assert!(*manager.profile() == *expected.profile());
+    assert_eq!(manager.distribute_unused_gas(100), GasDistribution::NoRatios);
+    assert_eq!(manager.into_action_receipts(None), expected.into_action_receipts(None));
+}
+
+#[test]
+fn rollback_after_append_action() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    manager.append_action_transfer(first, 1).unwrap();
+    let checkpoint = manager.checkpoint();
+
+    manager.append_action_transfer(first, 2).unwrap();
+    manager
+        .append_action_function_call_weight(first, b"f".to_vec(), vec![], 0, 0, GasWeight(1))
+        .unwrap();
+    assert_eq!(manager.actions(first).unwrap().len(), 3);
+
+    manager.rollback(checkpoint);
+    assert_eq!(manager.actions(first).unwrap(), [Action::Transfer(TransferAction { deposit: 1 })]);
+    assert_eq!(manager.total_attached_deposit(), Ok(1));
+    assert_eq!(manager.distribute_unused_gas(100), GasDistribution::NoRatios);
+}
+
+#[test]
+fn rollback_undoes_cancel_and_return_data() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let second =
+        manager.create_receipt(&mut ext, vec![first], "morty.test".parse().unwrap()).unwrap();
+    manager
+        .append_action_function_call_weight(second, b"f".to_vec(), vec![], 0, 0, GasWeight(1))
+        .unwrap();
+    let third =
+        manager.create_receipt(&mut ext, vec![first], "summer.test".parse().unwrap()).unwrap();
+    let checkpoint = manager.checkpoint();
+    let expected = manager.clone();
+
+    manager.cancel_receipt(second).unwrap();
+    manager.return_data(first, None).unwrap();
+    manager.cancel_receipt(third).unwrap();
+    assert_eq!(manager.receipts().count(), 0);
+
+    manager.rollback(checkpoint);
+    assert!(manager == expected);
+    assert_eq!(manager.distribute_unused_gas(100), GasDistribution::All);
+}
+
+#[test]
+fn create_receipt_with_invalid_dependency_adds_no_receivers() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    assert_eq!(
+        manager.create_receipt(&mut ext, vec![first, 5], "morty.test".parse().unwrap()),
+        Err(HostError::InvalidReceiptIndex { receipt_index: 5 }.into())
+    );
+    assert!(manager.action_receipts[first as usize].1.output_data_receivers.is_empty());
+    assert_eq!(manager.receipts().count(), 1);
+}
+
+#[test]
+fn borsh_round_trip() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let second =
+        manager.create_receipt(&mut ext, vec![first], "morty.test".parse().unwrap()).unwrap();
+    manager
+        .append_action_function_call_weight(second, b"f".to_vec(), vec![1], 2, 3, GasWeight(4))
+        .unwrap();
+    let cancelled =
+        manager.create_receipt(&mut ext, vec![], "summer.test".parse().unwrap()).unwrap();
+    manager.cancel_receipt(cancelled).unwrap();
+
+    let mut restored = ReceiptManager::try_from_slice(&manager.try_to_vec().unwrap()).unwrap();
+    assert!(restored == manager);
+    // The gas weights came along.
+    assert_eq!(restored.distribute_unused_gas(100), GasDistribution::All);
+    assert_eq!(restored.total_prepaid_gas(), Ok(103));
+}
+
+/// Fees with distinct digits for every action, the send fee to another account
+/// twice and the exec fee four times the send fee to the current one.
+fn distinct_fees() -> ActionCreationConfig {
+    let fee = |n: Gas| Fee { send_sir: n, send_not_sir: 2 * n, execution: 4 * n };
+    ActionCreationConfig {
+        create_account_cost: fee(1),
+        deploy_contract_cost: fee(10),
+        deploy_contract_cost_per_byte: fee(100),
+        function_call_cost: fee(1_000),
+        function_call_cost_per_byte: fee(10_000),
+        transfer_cost: fee(100_000),
+        stake_cost: fee(1_000_000),
+        add_key_cost: AccessKeyCreationConfig {
+            full_access_cost: fee(10_000_000),
+            function_call_cost: fee(100_000_000),
+            function_call_cost_per_byte: fee(1_000_000_000),
+        },
+        delete_key_cost: fee(10_000_000_000),
+        delete_account_cost: fee(100_000_000_000),
+    }
+}
+
+type AppendAction = fn(&mut ReceiptManager, ReceiptIndex) -> Result<(), VMLogicError>;
+
+#[test]
+fn action_fees() {
+    let implicit = "a".repeat(64);
+    let before_implicit = ProtocolFeature::ImplicitAccountCreation.protocol_version() - 1;
+    // Receiver, protocol version, action, burnt gas and used gas, computed by hand.
+    let cases: Vec<(&str, ProtocolVersion, AppendAction, Gas, Gas)> = vec![
+        ("alice.test", LATEST_PROTOCOL_VERSION, |m, i| m.append_action_create_account(i), 1, 5),
+        ("bob.test", LATEST_PROTOCOL_VERSION, |m, i| m.append_action_create_account(i), 2, 6),
+        (
+            "bob.test",
+            LATEST_PROTOCOL_VERSION,
+            |m, i| m.append_action_deploy_contract(i, vec![0; 3]),
+            20 + 3 * 200,
+            20 + 3 * 200 + 40 + 3 * 400,
+        ),
+        (
+            "alice.test",
+            LATEST_PROTOCOL_VERSION,
+            |m, i| {
+                m.append_action_function_call_weight(
+                    i,
+                    b"ab".to_vec(),
+                    vec![0; 3],
+                    0,
+                    0,
+                    GasWeight(0),
+                )
+            },
+            1_000 + 5 * 10_000,
+            1_000 + 5 * 10_000 + 4_000 + 5 * 40_000,
+        ),
+        (
+            "bob.test",
+            LATEST_PROTOCOL_VERSION,
+            |m, i| m.append_action_transfer(i, 1),
+            200_000,
+            600_000,
+        ),
+        // A transfer to an implicit account also pays for creating it and its key.
+        (
+            &implicit,
+            LATEST_PROTOCOL_VERSION,
+            |m, i| m.append_action_transfer(i, 1),
+            2 + 20_000_000 + 200_000,
+            2 + 20_000_000 + 200_000 + 4 + 40_000_000 + 400_000,
+        ),
+        (&implicit, before_implicit, |m, i| m.append_action_transfer(i, 1), 200_000, 600_000),
+        (
+            "alice.test",
+            LATEST_PROTOCOL_VERSION,
+            |m, i| {
+                let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+                m.append_action_stake(i, 1, public_key)
+            },
+            1_000_000,
+            5_000_000,
+        ),
+        (
+            "bob.test",
+            LATEST_PROTOCOL_VERSION,
+            |m, i| {
+                let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+                m.append_action_add_key_with_full_access(i, public_key, 0)
+            },
+            20_000_000,
+            60_000_000,
+        ),
+        // Method names are charged with a terminating byte each.
+        (
+            "alice.test",
+            LATEST_PROTOCOL_VERSION,
+            |m, i| {
+                let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+                m.append_action_add_key_with_function_call(
+                    i,
+                    public_key,
+                    0,
+                    None,
+                    "bob.test".parse().unwrap(),
+                    vec![b"a".to_vec(), b"bc".to_vec()],
+                )
+            },
+            100_000_000 + 5 * 1_000_000_000,
+            100_000_000 + 5 * 1_000_000_000 + 400_000_000 + 5 * 4_000_000_000,
+        ),
+        (
+            "bob.test",
+            LATEST_PROTOCOL_VERSION,
+            |m, i| {
+                let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+                m.append_action_delete_key(i, public_key)
+            },
+            20_000_000_000,
+            60_000_000_000,
+        ),
+        (
+            "alice.test",
+            LATEST_PROTOCOL_VERSION,
+            |m, i| m.append_action_delete_account(i, "bob.test".parse().unwrap()),
+            100_000_000_000,
+            500_000_000_000,
+        ),
+    ];
+
+    let mut ext = MockedExternal::default();
+    for (case, (receiver_id, protocol_version, append, burnt_gas, used_gas)) in
+        cases.into_iter().enumerate()
+    {
+        let mut manager = ReceiptManager::new(
+            Some(ReceiptLimits::from(&VMLimitConfig::test())),
+            distinct_fees(),
+            RuntimeFeesConfig::test().storage_usage_config,
+            "alice.test".parse().unwrap(),
+            protocol_version,
+            DuplicateAddKey::Allow,
+        );
+        let index = manager.create_receipt(&mut ext, vec![], receiver_id.parse().unwrap()).unwrap();
+        append(&mut manager, index).unwrap();
+        assert_eq!(
+            (manager.burnt_gas(), manager.used_gas()),
+            (burnt_gas, used_gas),
+            "case {}",
+            case
+        );
+
+        // Fees add up and failed appends don't pay any.
+        append(&mut manager, index).unwrap();
+        assert!(append(&mut manager, index + 1).is_err());
+        assert_eq!((manager.burnt_gas(), manager.used_gas()), (2 * burnt_gas, 2 * used_gas));
+    }
+}
+
+#[test]
+fn action_fees_match_gas_counter() {
+    let mut context = get_context(vec![], false);
+    context.account_balance = 100;
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(context);
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+    let index = promise_batch_create(&mut logic, "rick.test").unwrap();
+    // Host functions burn all the gas they use, so the gas used but not burnt is what is
+    // reserved for executing the receipt.
+    let exec_gas =
+        |logic: &VMLogic| logic.gas_counter().used_gas() - logic.gas_counter().burnt_gas();
+    let exec_gas_before = exec_gas(&logic);
+
+    promise_batch_action_function_call(&mut logic, index, 0, 0).unwrap();
+    logic.promise_batch_action_transfer(index, 1u128.to_le_bytes().as_ptr() as _).unwrap();
+    promise_batch_action_add_key_with_function_call(
+        &mut logic,
+        index,
+        &public_key,
+        0,
+        0,
+        b"rick.test",
+        b"f,g",
+    )
+    .unwrap();
+    logic
+        .promise_batch_action_delete_key(index, public_key.len() as _, public_key.as_ptr() as _)
+        .unwrap();
+
+    assert_ne!(logic.actions_burnt_gas(), 0);
+    assert_eq!(
+        exec_gas(&logic) - exec_gas_before,
+        logic.actions_used_gas() - logic.actions_burnt_gas()
+    );
+}
+
+#[test]
+fn receipts_and_actions() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let cancelled =
+        manager.create_receipt(&mut ext, vec![], "morty.test".parse().unwrap()).unwrap();
+    let last = manager.create_receipt(&mut ext, vec![], "summer.test".parse().unwrap()).unwrap();
+    manager.append_action_deploy_contract(first, vec![0; 10_000]).unwrap();
+    manager.append_action_transfer(first, 1).unwrap();
+    manager.cancel_receipt(cancelled).unwrap();
+
+    let receipts: Vec<_> = manager
+        .receipts()
+        .map(|(receiver_id, actions)| (receiver_id.as_ref(), actions.len()))
+        .collect();
+    assert_eq!(receipts, [("rick.test", 2), ("summer.test", 0)]);
+    assert_eq!(manager.actions(first).unwrap()[1], Action::Transfer(TransferAction { deposit: 1 }));
+    assert_eq!(manager.actions(last), Ok(&[][..]));
+    for receipt_index in [cancelled, last + 1] {
+        assert_eq!(
+            manager.actions(receipt_index),
+            Err(HostError::InvalidReceiptIndex { receipt_index }.into())
+        );
+    }
+
+    let debug = format!("{:?}", manager);
+    assert!(debug.len() < 1000, "{}", debug);
+    assert!(debug.contains("num_receipts: 3"), "{}", debug);
+    assert!(
+        debug.contains("receiver_id: AccountId(\"rick.test\"), num_actions: 2, cancelled: false"),
+        "{}",
+        debug
+    );
+    assert!(
+        debug.contains("receiver_id: AccountId(\"morty.test\"), num_actions: 0, cancelled: true"),
+        "{}",
+        debug
+    );
+}
+
+#[test]
+fn duplicate_add_key() {
+    let public_key = PublicKey::empty(KeyType::ED25519);
+    let other_key = PublicKey::empty(KeyType::SECP256K1);
+    for duplicate_add_key in
+        [DuplicateAddKey::Allow, DuplicateAddKey::Skip, DuplicateAddKey::Reject]
+    {
+        let mut manager = ReceiptManager::new(
+            Some(ReceiptLimits::from(&VMLimitConfig::test())),
+            RuntimeFeesConfig::test().action_creation_config,
+            RuntimeFeesConfig::test().storage_usage_config,
+            "alice.test".parse().unwrap(),
+            LATEST_PROTOCOL_VERSION,
+            duplicate_add_key,
+        );
+        let mut ext = MockedExternal::default();
+        let receipt_index =
+            manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+        let other_receipt_index =
+            manager.create_receipt(&mut ext, vec![], "morty.test".parse().unwrap()).unwrap();
+        let add_full_access = |manager: &mut ReceiptManager, receipt_index, key: &PublicKey| {
+            manager.append_action_add_key_with_full_access(
+                receipt_index,
+                key.try_to_vec().unwrap(),
+                0,
+            )
+        };
+
+        add_full_access(&mut manager, receipt_index, &public_key).unwrap();
+        add_full_access(&mut manager, receipt_index, &other_key).unwrap();
+        add_full_access(&mut manager, other_receipt_index, &public_key).unwrap();
+        let duplicates = [
+            add_full_access(&mut manager, receipt_index, &public_key),
+            manager.append_action_add_key_with_function_call(
+                receipt_index,
+                public_key.try_to_vec().unwrap(),
+                1,
+                None,
+                "rick.test".parse().unwrap(),
+                vec![b"f".to_vec()],
+            ),
+        ];
+
+        let expected_error =
+            HostError::DuplicateAddKey { receipt_index, public_key: public_key.to_string() };
+        let (expected_result, expected_num_actions) = match duplicate_add_key {
+            DuplicateAddKey::Allow => (Ok(()), 4),
+            DuplicateAddKey::Skip => (Ok(()), 2),
+            DuplicateAddKey::Reject => (Err(expected_error), 2),
+        };
+        for result in duplicates {
+            assert_eq!(result, expected_result.clone().map_err(Into::into));
+        }
+        assert_eq!(manager.actions(receipt_index).unwrap().len(), expected_num_actions);
+        assert_eq!(manager.actions(other_receipt_index).unwrap().len(), 1);
+    }
+}
+
+#[test]
+fn key_action_ordering() {
+    let key = PublicKey::empty(KeyType::ED25519);
+    let other_key = PublicKey::empty(KeyType::SECP256K1);
+    let add = |manager: &mut ReceiptManager, receipt_index, key: &PublicKey| {
+        manager.append_action_add_key_with_full_access(receipt_index, key.try_to_vec().unwrap(), 0)
+    };
+    let delete = |manager: &mut ReceiptManager, receipt_index, key: &PublicKey| {
+        manager.append_action_delete_key(receipt_index, key.try_to_vec().unwrap())
+    };
+    type KeyAction = fn(&mut ReceiptManager, ReceiptIndex, &PublicKey) -> Result<(), VMLogicError>;
+    type KeyActions<'a> = &'a [(KeyAction, &'a PublicKey)];
+    let cases: [(KeyActions, Option<HostError>); 6] = [
+        (&[], None),
+        (&[(add, &key), (delete, &key), (add, &key)], None),
+        (&[(delete, &key), (add, &key), (delete, &key)], None),
+        (&[(add, &key), (add, &other_key), (delete, &other_key)], None),
+        (
+            &[(add, &key), (add, &other_key), (add, &key)],
+            Some(HostError::DuplicateAddKey { receipt_index: 0, public_key: key.to_string() }),
+        ),
+        (
+            &[(delete, &other_key), (add, &key), (delete, &other_key)],
+            Some(HostError::DuplicateDeleteKey {
+                receipt_index: 0,
+                public_key: other_key.to_string(),
+            }),
+        ),
+    ];
+    for (actions, expected) in cases {
+        let mut manager =
+            test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+        let mut ext = MockedExternal::default();
+        let receipt_index =
+            manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+        for (append, key) in actions {
+            append(&mut manager, receipt_index, key).unwrap();
+        }
+        assert_eq!(
+            manager.validate_key_action_ordering(receipt_index),
+            expected.map_or(Ok(()), |error| Err(error.into()))
+        );
+    }
+
+    let manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    assert_eq!(
+        manager.validate_key_action_ordering(0),
+        Err(HostError::InvalidReceiptIndex { receipt_index: 0 }.into())
+    );
+}
+
+#[test]
+fn storage_delta() {
+    // 100 bytes per account and 40 extra bytes per record in the test config.
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+    let new_account =
+        manager.create_receipt(&mut ext, vec![], "rick.alice.test".parse().unwrap()).unwrap();
+    let existing_account =
+        manager.create_receipt(&mut ext, vec![], "morty.test".parse().unwrap()).unwrap();
+    let cancelled =
+        manager.create_receipt(&mut ext, vec![], "summer.test".parse().unwrap()).unwrap();
+
+    manager.append_action_create_account(new_account).unwrap();
+    manager.append_action_transfer(new_account, 10).unwrap();
+    manager.append_action_deploy_contract(new_account, vec![0; 1000]).unwrap();
+    // 33 bytes of key and 9 bytes of nonce and permission.
+    manager.append_action_add_key_with_full_access(new_account, public_key.clone(), 0).unwrap();
+    manager.append_action_delete_key(existing_account, public_key.clone()).unwrap();
+    // 33 bytes of key and 8 + 1 + 17 + 13 + 9 bytes of nonce, permission, allowance, receiver
+    // and method names.
+    manager
+        .append_action_add_key_with_function_call(
+            existing_account,
+            public_key,
+            0,
+            Some(1),
+            "rick.test".parse().unwrap(),
+            vec![b"f".to_vec()],
+        )
+        .unwrap();
+    manager.append_action_deploy_contract(cancelled, vec![0; 500]).unwrap();
+    manager.cancel_receipt(cancelled).unwrap();
+
+    assert_eq!(manager.storage_delta(new_account), Ok(100 + 1000 + 33 + 9 + 40));
+    assert_eq!(manager.storage_delta(existing_account), Ok(33 + 48 + 40));
+    assert_eq!(manager.total_storage_delta(), Ok(1182 + 121));
+    assert_eq!(
+        manager.storage_delta(cancelled),
+        Err(HostError::InvalidReceiptIndex { receipt_index: cancelled }.into())
+    );
+}
+
+#[test]
+fn builder_matches_receipt_indices() {
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+    let new_manager =
+        || test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+
+    let mut manager = new_manager();
+    let mut ext = MockedExternal::default();
+    let first =
+        manager.create_receipt(&mut ext, vec![], "rick.alice.test".parse().unwrap()).unwrap();
+    manager.append_action_create_account(first).unwrap();
+    manager.append_action_transfer(first, 10).unwrap();
+    manager.append_action_deploy_contract(first, vec![1, 2, 3]).unwrap();
+    manager.append_action_add_key_with_full_access(first, public_key.clone(), 1).unwrap();
+    manager.append_action_stake(first, 5, public_key.clone()).unwrap();
+    let second =
+        manager.create_receipt(&mut ext, vec![first], "morty.test".parse().unwrap()).unwrap();
+    manager
+        .append_action_function_call_weight(second, b"f".to_vec(), vec![1], 2, 100, GasWeight(0))
+        .unwrap();
+    manager
+        .append_action_function_call_weight(second, b"g".to_vec(), vec![], 0, 200, GasWeight(3))
+        .unwrap();
+    manager
+        .append_action_add_key_with_function_call(
+            second,
+            public_key.clone(),
+            2,
+            None,
+            "rick.test".parse().unwrap(),
+            vec![b"f".to_vec()],
+        )
+        .unwrap();
+    manager.append_action_delete_key(second, public_key.clone()).unwrap();
+    manager.append_action_delete_account(second, "alice.test".parse().unwrap()).unwrap();
+
+    let mut built = new_manager();
+    let mut ext = MockedExternal::default();
+    built
+        .build_receipt(&mut ext, "rick.alice.test".parse().unwrap())
+        .create_account()
+        .unwrap()
+        .transfer(10)
+        .unwrap()
+        .deploy_contract(vec![1, 2, 3])
+        .unwrap()
+        .add_full_access_key(public_key.clone(), 1)
+        .unwrap()
+        .stake(5, public_key.clone())
+        .unwrap()
+        .then("morty.test".parse().unwrap())
+        .unwrap()
+        .function_call(b"f".to_vec(), vec![1], 2, 100)
+        .unwrap()
+        .function_call_weight(b"g".to_vec(), vec![], 0, 200, GasWeight(3))
+        .unwrap()
+        .add_function_call_key(
+            public_key.clone(),
+            2,
+            None,
+            "rick.test".parse().unwrap(),
+            vec![b"f".to_vec()],
+        )
+        .unwrap()
+        .delete_key(public_key)
+        .unwrap()
+        .delete_account("alice.test".parse().unwrap())
+        .unwrap();
+
+    assert!(built == manager);
+}
+
+#[test]
+fn builder_errors() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let mut builder = manager.build_receipt(&mut ext, "rick.test".parse().unwrap());
+    let receipt_index = builder.receipt_index();
+
+    assert_eq!(builder.stake(1, vec![]).err(), Some(HostError::InvalidPublicKey.into()));
+    assert_eq!(
+        builder.function_call(vec![0; 257], vec![], 0, 0).err(),
+        Some(
+            HostError::ReceiptLimitExceeded {
+                limit_name: "max_method_name_len".to_string(),
+                value: 257,
+                limit: 256,
+            }
+            .into()
+        )
+    );
+    let dependent_index = builder.then("morty.test".parse().unwrap()).unwrap().receipt_index();
+    assert_eq!(dependent_index, receipt_index + 1);
+    assert_eq!(manager.actions(receipt_index), Ok(&[][..]));
+    assert_eq!(manager.action_receipts[0].1.output_data_receivers.len(), 1);
+    assert_eq!(manager.action_receipts[1].1.input_data_ids.len(), 1);
+}
+
+#[test]
+fn storage_delta_with_builder() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+    let mut builder = manager.build_receipt(&mut ext, "rick.alice.test".parse().unwrap());
+    builder
+        .create_account()
+        .unwrap()
+        .transfer(10)
+        .unwrap()
+        .deploy_contract(vec![0; 1000])
+        .unwrap()
+        .add_full_access_key(public_key.clone(), 0)
+        .unwrap();
+    let new_account = builder.receipt_index();
+    let existing_account = builder
+        .then("morty.test".parse().unwrap())
+        .unwrap()
+        .delete_key(public_key.clone())
+        .unwrap()
+        .add_function_call_key(
+            public_key,
+            0,
+            Some(1),
+            "rick.test".parse().unwrap(),
+            vec![b"f".to_vec()],
+        )
+        .unwrap()
+        .receipt_index();
+
+    assert_eq!(manager.storage_delta(new_account), Ok(100 + 1000 + 33 + 9 + 40));
+    assert_eq!(manager.storage_delta(existing_account), Ok(33 + 48 + 40));
+    assert_eq!(manager.total_storage_delta(), Ok(1182 + 121));
+}
+
+#[test]
+fn return_data_round_trip() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let first = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let second =
+        manager.create_receipt(&mut ext, vec![first], "morty.test".parse().unwrap()).unwrap();
+    manager
+        .append_action_function_call_weight(second, b"f".to_vec(), vec![], 0, 100, GasWeight(0))
+        .unwrap();
+    manager.return_data(first, Some(b"42".to_vec())).unwrap();
+
+    let data_receipts = manager.data_receipts();
+    let action_receipts = manager.into_action_receipts(None);
+    assert_eq!(action_receipts.len(), 1);
+    let (receiver_id, receipt) = &action_receipts[0];
+    assert_eq!(receiver_id.as_ref(), "morty.test");
+    assert_eq!(
+        data_receipts,
+        [(
+            receiver_id.clone(),
+            DataReceipt { data_id: receipt.input_data_ids[0], data: Some(b"42".to_vec()) }
+        )]
+    );
+
+    // What the runtime gives to the second receipt once the data receipts arrive.
+    let promise_results = receipt
+        .input_data_ids
+        .iter()
+        .map(|data_id| {
+            let (_, data_receipt) = data_receipts
+                .iter()
+                .find(|(_, data_receipt)| data_receipt.data_id == *data_id)
+                .unwrap();
+            match &data_receipt.data {
+                Some(data) => PromiseResult::Successful(data.clone()),
+                None => PromiseResult::Failed,
+            }
+        })
+        .collect();
+    let mut logic_builder = VMLogicBuilder { promise_results, ..VMLogicBuilder::default() };
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(logic.promise_results_count(), Ok(1));
+    assert_eq!(logic.promise_result(0, 0), Ok(1));
+    let buffer = [0u8; 2];
+    logic.read_register(0, buffer.as_ptr() as u64).unwrap();
+    assert_eq!(&buffer, b"42");
+}
+
+#[test]
+fn return_data() {
+    let mut manager =
+        test_manager(ReceiptLimits::from(&VMLimitConfig::test()), LATEST_PROTOCOL_VERSION);
+    let mut ext = MockedExternal::default();
+    let input = manager.create_receipt(&mut ext, vec![], "rick.test".parse().unwrap()).unwrap();
+    let returned =
+        manager.create_receipt(&mut ext, vec![input], "morty.test".parse().unwrap()).unwrap();
+    let with_actions =
+        manager.create_receipt(&mut ext, vec![], "summer.test".parse().unwrap()).unwrap();
+    manager.append_action_transfer(with_actions, 1).unwrap();
+
+    assert_eq!(
+        manager.return_data(with_actions, None),
+        Err(HostError::ReceiptHasActions { receipt_index: with_actions }.into())
+    );
+    manager.return_data(returned, None).unwrap();
+    // The receipt it depended on no longer sends it its output.
+    assert_eq!(manager.action_receipts[input as usize].1.output_data_receivers, []);
+    for receipt_index in [returned, with_actions + 1] {
+        let expected = Err(HostError::InvalidReceiptIndex { receipt_index }.into());
+        assert_eq!(manager.return_data(receipt_index, None), expected);
+        assert_eq!(manager.append_action_transfer(receipt_index, 1), expected);
+    }
+
+    // Receipts can depend on it after it was replaced, and get its output.
+    let dependent =
+        manager.create_receipt(&mut ext, vec![returned], "beth.test".parse().unwrap()).unwrap();
+    let data_id = manager.action_receipts[dependent as usize].1.input_data_ids[0];
+    assert_eq!(
+        manager.data_receipts(),
+        [("beth.test".parse().unwrap(), DataReceipt { data_id, data: None })]
+    );
+    let receivers: Vec<_> =
+        manager.receipts().map(|(receiver_id, _)| receiver_id.to_string()).collect();
+    assert_eq!(receivers, ["rick.test", "summer.test", "beth.test"]);
+
+    let debug = format!("{:?}", manager);
+    assert!(debug.contains("num_actions: 0, cancelled: false, data_returned: true"), "{}", debug);
+}
+
+#[test]
+fn profile() {
+    let mut manager = ReceiptManager::new(
+        Some(ReceiptLimits::from(&VMLimitConfig::test())),
+        distinct_fees(),
+        RuntimeFeesConfig::test().storage_usage_config,
+        "alice.test".parse().unwrap(),
+        LATEST_PROTOCOL_VERSION,
+        DuplicateAddKey::Allow,
+    );
+    let mut ext = MockedExternal::default();
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+    let own = manager.create_receipt(&mut ext, vec![], "alice.test".parse().unwrap()).unwrap();
+    let other = manager.create_receipt(&mut ext, vec![], "bob.test".parse().unwrap()).unwrap();
+    manager.append_action_create_account(own).unwrap();
+    manager.append_action_transfer(own, 1).unwrap();
+    manager
+        .append_action_function_call_weight(own, b"ab".to_vec(), vec![0; 3], 0, 0, GasWeight(0))
+        .unwrap();
+    manager.append_action_deploy_contract(other, vec![0; 3]).unwrap();
+    manager.append_action_transfer(other, 1).unwrap();
+    manager.append_action_add_key_with_full_access(other, public_key.clone(), 0).unwrap();
+    manager.append_action_delete_key(other, public_key).unwrap();
+    // Failed actions aren't profiled.
+    manager.append_action_delete_key(other, vec![]).unwrap_err();
+    manager.append_action_stake(own, 1, vec![]).unwrap_err();
+
+    let expected = [
+        (ActionCosts::create_account, 1),
+        (ActionCosts::deploy_contract, 20 + 3 * 200),
+        (ActionCosts::function_call, 1_000 + 5 * 10_000),
+        (ActionCosts::transfer, 100_000 + 200_000),
+        (ActionCosts::stake, 0),
+        (ActionCosts::add_key, 20_000_000),
+        (ActionCosts::delete_key, 20_000_000_000),
+        (ActionCosts::delete_account, 0),
+        (ActionCosts::value_return, 0),
+        (ActionCosts::new_receipt, 0),
+    ];
+    for (action_cost, gas) in expected {
+        assert_eq!(manager.profile().get_action_cost(action_cost), gas, "{:?}", action_cost);
+    }
+    assert_eq!(manager.profile().action_gas(), manager.burnt_gas());
+}
+
+#[test]
+fn profile_matches_gas_counter() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let public_key = PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap();
+    let index = promise_batch_create(&mut logic, "rick.test").unwrap();
+    promise_batch_action_function_call(&mut logic, index, 0, 0).unwrap();
+    promise_batch_action_add_key_with_function_call(
+        &mut logic,
+        index,
+        &public_key,
+        0,
+        0,
+        b"rick.test",
+        b"f,g",
+    )
+    .unwrap();
+    logic
+        .promise_batch_action_delete_key(index, public_key.len() as _, public_key.as_ptr() as _)
+        .unwrap();
+
+    let actions_profile = logic.actions_profile().clone();
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    for action_cost in [ActionCosts::function_call, ActionCosts::add_key, ActionCosts::delete_key] {
+        assert_ne!(actions_profile.get_action_cost(action_cost), 0);
+        assert_eq!(
+            actions_profile.get_action_cost(action_cost),
+            outcome.profile.get_action_cost(action_cost),
+            "{:?}",
+            action_cost
+        );
+    }
+}
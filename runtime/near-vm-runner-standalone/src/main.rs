@@ -5,7 +5,7 @@ mod script;
 use crate::script::Script;
 use clap::Parser;
 use near_vm_logic::ProtocolVersion;
-use near_vm_logic::VMOutcome;
+use near_vm_logic::{VMConfig, VMOutcome};
 use near_vm_runner::internal::VMKind;
 use near_vm_runner::VMResult;
 use serde::{
@@ -101,6 +101,9 @@ struct CliArgs {
     /// Protocol version.
     #[clap(long)]
     protocol_version: Option<ProtocolVersion>,
+    /// Prints the gas profile as JSON, keyed by cost name.
+    #[clap(long)]
+    profile_json: bool,
 }
 
 #[allow(unused)]
@@ -132,7 +135,7 @@ fn main() {
         _ => (),
     };
     if let Some(config) = &cli_args.config {
-        script.vm_config(serde_json::from_str(config).unwrap());
+        script.vm_config(VMConfig::from_json_strict(config).unwrap());
     }
     if let Some(path) = &cli_args.config_file {
         script.vm_config_from_file(path);
@@ -187,7 +190,11 @@ fn main() {
             for log in &outcome.logs {
                 println!("{}\n", log);
             }
-            println!("{:#?}", outcome.profile);
+            if cli_args.profile_json {
+                println!("{}", serde_json::to_string_pretty(&outcome.profile).unwrap());
+            } else {
+                println!("{:#?}", outcome.profile);
+            }
         }
     }
 }
@@ -2,7 +2,7 @@ use std::fs;
 use std::path::Path;
 
 use near_primitives::contract::ContractCode;
-use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::runtime::config_store::{RuntimeConfigStore, VMConfigExt};
 use near_primitives::types::CompiledContractCache;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_vm_logic::mocks::mock_external::MockedExternal;
@@ -44,12 +44,10 @@ pub struct ScriptResults {
 impl Default for Script {
     fn default() -> Self {
         let protocol_version = PROTOCOL_VERSION;
-        let config_store = RuntimeConfigStore::new(None);
-        let runtime_config = config_store.get_config(protocol_version).as_ref();
         Script {
             contracts: Vec::new(),
             vm_kind: VMKind::for_protocol_version(protocol_version),
-            vm_config: runtime_config.wasm_config.clone(),
+            vm_config: VMConfig::production(protocol_version),
             protocol_version,
             contract_cache: None,
             initial_state: None,
@@ -80,8 +78,8 @@ impl Script {
     }
 
     pub(crate) fn vm_config_from_file(&mut self, path: &Path) {
-        let data = fs::read(path).unwrap();
-        let vm_config = serde_json::from_slice(&data).unwrap();
+        let data = fs::read_to_string(path).unwrap();
+        let vm_config = VMConfig::from_json_strict(&data).unwrap();
         self.vm_config(vm_config)
     }
 
@@ -194,6 +192,7 @@ fn default_vm_context() -> VMContext {
         random_seed: vec![0, 1, 2],
         view_config: None,
         output_data_receivers: vec![],
+        congestion: None,
         epoch_height: 1,
     }
 }
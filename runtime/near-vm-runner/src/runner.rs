@@ -149,6 +149,7 @@ impl VMResult {
             logs: Vec::new(),
             profile: ProfileData::default(),
             action_receipts: Vec::new(),
+            data_receipts: Vec::new(),
         };
         VMResult::Aborted(outcome, error)
     }
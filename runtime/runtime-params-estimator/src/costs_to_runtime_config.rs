@@ -1,5 +1,5 @@
 use near_primitives::runtime::config::AccountCreationConfig;
-use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::runtime::config_store::{RuntimeConfigStore, VMConfigExt};
 use near_primitives::runtime::fees::{
     AccessKeyCreationConfig, ActionCreationConfig, DataReceiptCreationConfig, Fee,
     RuntimeFeesConfig,
@@ -25,13 +25,6 @@ pub fn costs_to_runtime_config(cost_table: &CostTable) -> anyhow::Result<Runtime
         .get(Cost::WasmInstruction)
         .with_context(|| format!("undefined cost: {}", Cost::WasmInstruction))?;
 
-    // Take latest VM limit config, because estimation doesn't affect it.
-    // Note that if you run estimation against stable version, it doesn't catch updates of nightly
-    // version.
-    let config_store = RuntimeConfigStore::new(None);
-    let latest_runtime_config = config_store.get_config(PROTOCOL_VERSION);
-    let vm_limit_config = latest_runtime_config.wasm_config.limit_config.clone();
-
     let res = RuntimeConfig {
         // See https://nomicon.io/Economics/README.html#general-variables for how it was calculated.
         storage_amount_per_byte: 909 * 100_000_000_000_000_000,
@@ -40,7 +33,10 @@ pub fn costs_to_runtime_config(cost_table: &CostTable) -> anyhow::Result<Runtime
             ext_costs: ext_costs_config(cost_table)?,
             grow_mem_cost: 1,
             regular_op_cost: u32::try_from(regular_op_cost).unwrap(),
-            limit_config: vm_limit_config,
+            // Take latest VM limit config, because estimation doesn't affect it.
+            // Note that if you run estimation against stable version, it doesn't catch updates of
+            // nightly version.
+            ..VMConfig::production(PROTOCOL_VERSION)
         },
         account_creation_config: AccountCreationConfig::default(),
     };
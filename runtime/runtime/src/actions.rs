@@ -4,7 +4,9 @@ use near_crypto::PublicKey;
 use near_primitives::account::{AccessKey, AccessKeyPermission, Account};
 use near_primitives::checked_feature;
 use near_primitives::contract::ContractCode;
-use near_primitives::errors::{ActionError, ActionErrorKind, ContractCallError, RuntimeError};
+use near_primitives::errors::{
+    ActionError, ActionErrorKind, ContractCallError, RuntimeError, StakeValidationError,
+};
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
 use near_primitives::runtime::config::AccountCreationConfig;
@@ -96,6 +98,7 @@ pub(crate) fn execute_function_call(
         random_seed,
         view_config,
         output_data_receivers,
+        congestion: action_receipt.congestion,
     };
 
     // Enable caching chunk mode for the function call. This allows to charge for nodes touched in a chunk only once for
@@ -242,6 +245,18 @@ pub(crate) fn action_function_call(
     result.logs.extend(outcome.logs);
     result.profile.merge(&outcome.profile);
     if execution_succeeded {
+        // Data receipts come after the action receipts, so that `ReturnData::ReceiptIndex`
+        // still refers to the right receipt.
+        let data_receipts = outcome.data_receipts.into_iter().map(|(receiver_id, data_receipt)| {
+            Receipt {
+                predecessor_id: account_id.clone(),
+                receiver_id,
+                // Actual receipt ID is set in the Runtime.apply_action_receipt(...) in the
+                // "Generating receipt IDs" section
+                receipt_id: CryptoHash::default(),
+                receipt: ReceiptEnum::Data(data_receipt),
+            }
+        });
         let new_receipts: Vec<_> = outcome
             .action_receipts
             .into_iter()
@@ -258,8 +273,10 @@ pub(crate) fn action_function_call(
                     output_data_receivers: receipt.output_data_receivers,
                     input_data_ids: receipt.input_data_ids,
                     actions: receipt.actions,
+                    congestion: receipt.congestion,
                 }),
             })
+            .chain(data_receipts)
             .collect();
 
         account.set_amount(outcome.balance);
@@ -278,51 +295,51 @@ pub(crate) fn action_stake(
     stake: &StakeAction,
     last_block_hash: &CryptoHash,
     epoch_info_provider: &dyn EpochInfoProvider,
+    current_protocol_version: ProtocolVersion,
     #[cfg(feature = "protocol_feature_chunk_only_producers")] is_chunk_only: bool,
 ) -> Result<(), RuntimeError> {
-    let increment = stake.stake.saturating_sub(account.locked());
-
-    if account.amount() >= increment {
-        if account.locked() == 0 && stake.stake == 0 {
-            // if the account hasn't staked, it cannot unstake
-            result.result =
-                Err(ActionErrorKind::TriesToUnstake { account_id: account_id.clone() }.into());
-            return Ok(());
-        }
-
-        if stake.stake > 0 {
-            let minimum_stake = epoch_info_provider.minimum_stake(last_block_hash)?;
-            if stake.stake < minimum_stake {
-                result.result = Err(ActionErrorKind::InsufficientStake {
-                    account_id: account_id.clone(),
-                    stake: stake.stake,
-                    minimum_stake,
-                }
-                .into());
-                return Ok(());
+    let mut validation = Ok(());
+    if checked_feature!(
+        "protocol_feature_staking_key_action_error",
+        StakingKeyActionError,
+        current_protocol_version
+    ) {
+        validation = stake.validate_staking_key();
+    }
+    validation = validation.and_then(|()| stake.validate_balance(account));
+    if validation.is_ok() && stake.stake > 0 {
+        let minimum_stake = epoch_info_provider.minimum_stake(last_block_hash)?;
+        validation = stake.validate_minimum_stake(minimum_stake);
+    }
+    if let Err(err) = validation {
+        let account_id = account_id.clone();
+        result.result = Err(match err {
+            StakeValidationError::UnsuitableStakingKey { public_key } => {
+                ActionErrorKind::InvalidStakingKey { account_id, public_key }
+            }
+            StakeValidationError::TriesToUnstake => ActionErrorKind::TriesToUnstake { account_id },
+            StakeValidationError::TriesToStake { stake, locked, balance } => {
+                ActionErrorKind::TriesToStake { account_id, stake, locked, balance }
+            }
+            StakeValidationError::InsufficientStake { stake, minimum_stake } => {
+                ActionErrorKind::InsufficientStake { account_id, stake, minimum_stake }
             }
-        }
-
-        result.validator_proposals.push(ValidatorStake::new(
-            account_id.clone(),
-            stake.public_key.clone(),
-            stake.stake,
-            #[cfg(feature = "protocol_feature_chunk_only_producers")]
-            is_chunk_only,
-        ));
-        if stake.stake > account.locked() {
-            // We've checked above `account.amount >= increment`
-            account.set_amount(account.amount() - increment);
-            account.set_locked(stake.stake);
-        }
-    } else {
-        result.result = Err(ActionErrorKind::TriesToStake {
-            account_id: account_id.clone(),
-            stake: stake.stake,
-            locked: account.locked(),
-            balance: account.amount(),
         }
         .into());
+        return Ok(());
+    }
+
+    result.validator_proposals.push(ValidatorStake::new(
+        account_id.clone(),
+        stake.public_key.clone(),
+        stake.stake,
+        #[cfg(feature = "protocol_feature_chunk_only_producers")]
+        is_chunk_only,
+    ));
+    if stake.stake > account.locked() {
+        // `validate_against` checked that the account has the balance to cover the increment.
+        account.set_amount(account.amount() - (stake.stake - account.locked()));
+        account.set_locked(stake.stake);
     }
     Ok(())
 }
@@ -932,4 +949,46 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_stake_with_unsuitable_key() {
+        use near_crypto::KeyType;
+        use near_primitives::test_utils::MockEpochInfoProvider;
+        use near_primitives::version::PROTOCOL_VERSION;
+
+        let account_id: AccountId = "alice".parse().unwrap();
+        let stake = StakeAction {
+            stake: 100,
+            public_key: PublicKey::from_seed(KeyType::SECP256K1, "alice"),
+        };
+        let mut account = Account::new(1000, 0, CryptoHash::default(), 0);
+        let mut result = ActionResult::default();
+        action_stake(
+            &mut account,
+            &mut result,
+            &account_id,
+            &stake,
+            &CryptoHash::default(),
+            &MockEpochInfoProvider::default(),
+            PROTOCOL_VERSION,
+            #[cfg(feature = "protocol_feature_chunk_only_producers")]
+            false,
+        )
+        .unwrap();
+        if cfg!(feature = "protocol_feature_staking_key_action_error") {
+            assert_eq!(
+                result.result,
+                Err(ActionErrorKind::InvalidStakingKey {
+                    account_id,
+                    public_key: stake.public_key
+                }
+                .into())
+            );
+            assert!(result.validator_proposals.is_empty());
+            assert_eq!(account.locked(), 0);
+        } else {
+            assert!(result.result.is_ok());
+            assert_eq!(account.locked(), 100);
+        }
+    }
 }
@@ -1,5 +1,3 @@
-use crate::safe_add_balance_apply;
-
 use crate::config::{
     safe_add_balance, safe_add_gas, safe_gas_to_balance, total_deposit, total_prepaid_exec_fees,
     total_prepaid_gas,
@@ -10,6 +8,8 @@ use near_primitives::errors::{
 };
 use near_primitives::receipt::{Receipt, ReceiptEnum};
 use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::safe_math::BalanceOverflowError;
+use near_primitives::safe_sum_balances;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{AccountId, Balance};
@@ -17,6 +17,8 @@ use near_primitives::version::ProtocolVersion;
 use near_store::{get, get_account, get_postponed_receipt, TrieUpdate};
 use std::collections::HashSet;
 
+// All arithmetic here must be checked, see `near_primitives::safe_math`.
+#[deny(clippy::integer_arithmetic)]
 pub(crate) fn check_balance(
     transaction_costs: &RuntimeFeesConfig,
     initial_state: &TrieUpdate,
@@ -179,14 +181,19 @@ pub(crate) fn check_balance(
     let final_postponed_receipts_balance = total_postponed_receipts_cost(final_state)?;
     // Sum it up
 
-    let initial_balance = safe_add_balance_apply!(
+    let overflow = |err: BalanceOverflowError| {
+        tracing::error!(target: "runtime", "balance checker: {}", err);
+        RuntimeError::UnexpectedIntegerOverflow
+    };
+    let initial_balance = safe_sum_balances!(
         incoming_validator_rewards,
         initial_accounts_balance,
         incoming_receipts_balance,
         processed_delayed_receipts_balance,
         initial_postponed_receipts_balance
-    );
-    let final_balance = safe_add_balance_apply!(
+    )
+    .map_err(overflow)?;
+    let final_balance = safe_sum_balances!(
         final_accounts_balance,
         outgoing_receipts_balance,
         new_delayed_receipts_balance,
@@ -194,7 +201,8 @@ pub(crate) fn check_balance(
         stats.tx_burnt_amount,
         stats.slashed_burnt_amount,
         stats.other_burnt_amount
-    );
+    )
+    .map_err(overflow)?;
     if initial_balance != final_balance {
         Err(BalanceMismatchError {
             // Inputs
@@ -372,6 +380,7 @@ mod tests {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions: vec![Action::Transfer(TransferAction { deposit })],
+                congestion: None,
             }),
         };
 
@@ -428,6 +437,7 @@ mod tests {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions: vec![Action::Transfer(TransferAction { deposit })],
+                congestion: None,
             }),
         };
 
@@ -5,11 +5,13 @@ use num_traits::cast::ToPrimitive;
 use num_traits::pow::Pow;
 
 use near_primitives::account::AccessKeyPermission;
+use near_primitives::checked_feature;
 use near_primitives::errors::IntegerOverflowError;
 // Just re-exporting RuntimeConfig for backwards compatibility.
 pub use near_primitives::num_rational::Rational;
 pub use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::runtime::fees::{transfer_exec_fee, transfer_send_fee, RuntimeFeesConfig};
+pub use near_primitives::safe_math::{safe_add_balance, safe_add_gas, safe_gas_to_balance};
 use near_primitives::transaction::{
     Action, AddKeyAction, DeployContractAction, FunctionCallAction, Transaction,
 };
@@ -46,26 +48,6 @@ pub fn safe_gas_price_inflated(
     inflated_gas_price.to_u128().ok_or_else(|| IntegerOverflowError {})
 }
 
-pub fn safe_gas_to_balance(gas_price: Balance, gas: Gas) -> Result<Balance, IntegerOverflowError> {
-    gas_price.checked_mul(Balance::from(gas)).ok_or_else(|| IntegerOverflowError {})
-}
-
-pub fn safe_add_gas(a: Gas, b: Gas) -> Result<Gas, IntegerOverflowError> {
-    a.checked_add(b).ok_or_else(|| IntegerOverflowError {})
-}
-
-pub fn safe_add_balance(a: Balance, b: Balance) -> Result<Balance, IntegerOverflowError> {
-    a.checked_add(b).ok_or_else(|| IntegerOverflowError {})
-}
-
-#[macro_export]
-macro_rules! safe_add_balance_apply {
-    ($x: expr) => {$x};
-    ($x: expr, $($rest: expr),+) => {
-        safe_add_balance($x, safe_add_balance_apply!($($rest),+))?
-    }
-}
-
 /// Total sum of gas that needs to be burnt to send these actions.
 pub fn total_send_fees(
     config: &RuntimeFeesConfig,
@@ -74,60 +56,70 @@ pub fn total_send_fees(
     receiver_id: &AccountId,
     current_protocol_version: ProtocolVersion,
 ) -> Result<Gas, IntegerOverflowError> {
-    let cfg = &config.action_creation_config;
     let mut result = 0;
 
     for action in actions {
-        use Action::*;
-        let delta = match action {
-            CreateAccount(_) => cfg.create_account_cost.send_fee(sender_is_receiver),
-            DeployContract(DeployContractAction { code }) => {
-                let num_bytes = code.len() as u64;
-                cfg.deploy_contract_cost.send_fee(sender_is_receiver)
-                    + cfg.deploy_contract_cost_per_byte.send_fee(sender_is_receiver) * num_bytes
-            }
-            FunctionCall(FunctionCallAction { method_name, args, .. }) => {
-                let num_bytes = method_name.as_bytes().len() as u64 + args.len() as u64;
-                cfg.function_call_cost.send_fee(sender_is_receiver)
-                    + cfg.function_call_cost_per_byte.send_fee(sender_is_receiver) * num_bytes
-            }
-            Transfer(_) => {
-                // Account for implicit account creation
-                let is_receiver_implicit =
-                    is_implicit_account_creation_enabled(current_protocol_version)
-                        && receiver_id.is_implicit();
-                transfer_send_fee(cfg, sender_is_receiver, is_receiver_implicit)
-            }
-            Stake(_) => cfg.stake_cost.send_fee(sender_is_receiver),
-            #[cfg(feature = "protocol_feature_chunk_only_producers")]
-            StakeChunkOnly(_) => cfg.stake_cost.send_fee(sender_is_receiver),
-            AddKey(AddKeyAction { access_key, .. }) => match &access_key.permission {
-                AccessKeyPermission::FunctionCall(call_perm) => {
-                    let num_bytes = call_perm
-                        .method_names
-                        .iter()
-                        // Account for null-terminating characters.
-                        .map(|name| name.as_bytes().len() as u64 + 1)
-                        .sum::<u64>();
-                    cfg.add_key_cost.function_call_cost.send_fee(sender_is_receiver)
-                        + num_bytes
-                            * cfg
-                                .add_key_cost
-                                .function_call_cost_per_byte
-                                .send_fee(sender_is_receiver)
-                }
-                AccessKeyPermission::FullAccess => {
-                    cfg.add_key_cost.full_access_cost.send_fee(sender_is_receiver)
-                }
-            },
-            DeleteKey(_) => cfg.delete_key_cost.send_fee(sender_is_receiver),
-            DeleteAccount(_) => cfg.delete_account_cost.send_fee(sender_is_receiver),
-        };
+        let delta =
+            send_fee(config, sender_is_receiver, action, receiver_id, current_protocol_version);
         result = safe_add_gas(result, delta)?;
     }
     Ok(result)
 }
 
+/// Gas that needs to be burnt to send the action.
+pub fn send_fee(
+    config: &RuntimeFeesConfig,
+    sender_is_receiver: bool,
+    action: &Action,
+    receiver_id: &AccountId,
+    current_protocol_version: ProtocolVersion,
+) -> Gas {
+    let cfg = &config.action_creation_config;
+    use Action::*;
+
+    match action {
+        CreateAccount(_) => cfg.create_account_cost.send_fee(sender_is_receiver),
+        DeployContract(DeployContractAction { code }) => {
+            let num_bytes = code.len() as u64;
+            cfg.deploy_contract_cost.send_fee(sender_is_receiver)
+                + cfg.deploy_contract_cost_per_byte.send_fee(sender_is_receiver) * num_bytes
+        }
+        FunctionCall(FunctionCallAction { method_name, args, .. }) => {
+            let num_bytes = method_name.as_bytes().len() as u64 + args.len() as u64;
+            cfg.function_call_cost.send_fee(sender_is_receiver)
+                + cfg.function_call_cost_per_byte.send_fee(sender_is_receiver) * num_bytes
+        }
+        Transfer(_) => {
+            // Account for implicit account creation
+            let is_receiver_implicit =
+                is_implicit_account_creation_enabled(current_protocol_version)
+                    && receiver_id.is_implicit();
+            transfer_send_fee(cfg, sender_is_receiver, is_receiver_implicit)
+        }
+        Stake(_) => cfg.stake_cost.send_fee(sender_is_receiver),
+        #[cfg(feature = "protocol_feature_chunk_only_producers")]
+        StakeChunkOnly(_) => cfg.stake_cost.send_fee(sender_is_receiver),
+        AddKey(AddKeyAction { access_key, .. }) => match &access_key.permission {
+            AccessKeyPermission::FunctionCall(call_perm) => {
+                let num_bytes = call_perm
+                    .method_names
+                    .iter()
+                    // Account for null-terminating characters.
+                    .map(|name| name.as_bytes().len() as u64 + 1)
+                    .sum::<u64>();
+                cfg.add_key_cost.function_call_cost.send_fee(sender_is_receiver)
+                    + num_bytes
+                        * cfg.add_key_cost.function_call_cost_per_byte.send_fee(sender_is_receiver)
+            }
+            AccessKeyPermission::FullAccess => {
+                cfg.add_key_cost.full_access_cost.send_fee(sender_is_receiver)
+            }
+        },
+        DeleteKey(_) => cfg.delete_key_cost.send_fee(sender_is_receiver),
+        DeleteAccount(_) => cfg.delete_account_cost.send_fee(sender_is_receiver),
+    }
+}
+
 pub fn exec_fee(
     config: &RuntimeFeesConfig,
     action: &Action,
@@ -178,6 +170,8 @@ pub fn exec_fee(
 }
 
 /// Returns transaction costs for a given transaction.
+// All arithmetic here must be checked, see `near_primitives::safe_math`.
+#[deny(clippy::integer_arithmetic)]
 pub fn tx_cost(
     config: &RuntimeFeesConfig,
     transaction: &Transaction,
@@ -205,9 +199,8 @@ pub fn tx_cost(
     let receipt_gas_price = if gas_price == 0 {
         0
     } else {
-        let maximum_depth =
-            if minimum_new_receipt_gas > 0 { prepaid_gas / minimum_new_receipt_gas } else { 0 };
-        let inflation_exponent = u8::try_from(initial_receipt_hop + maximum_depth)
+        let maximum_depth = prepaid_gas.checked_div(minimum_new_receipt_gas).unwrap_or(0);
+        let inflation_exponent = u8::try_from(safe_add_gas(initial_receipt_hop, maximum_depth)?)
             .map_err(|_| IntegerOverflowError {})?;
         safe_gas_price_inflated(
             gas_price,
@@ -227,7 +220,12 @@ pub fn tx_cost(
             current_protocol_version,
         )?,
     )?;
-    let burnt_amount = safe_gas_to_balance(gas_price, gas_burnt)?;
+    let mut burnt_amount = safe_gas_to_balance(gas_price, gas_burnt)?;
+    // Until a distribution rule is agreed on, the priority fee is burnt
+    // together with the gas.
+    if checked_feature!("protocol_feature_priority_fee", PriorityFee, current_protocol_version) {
+        burnt_amount = safe_add_balance(burnt_amount, transaction.priority_fee.unwrap_or(0))?;
+    }
     let remaining_gas_amount = safe_gas_to_balance(receipt_gas_price, gas_remaining)?;
     let mut total_cost = safe_add_balance(burnt_amount, remaining_gas_amount)?;
     total_cost = safe_add_balance(total_cost, total_deposit(&transaction.actions)?)?;
@@ -16,7 +16,7 @@ pub use near_primitives::runtime::apply_state::ApplyState;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::runtime::get_insufficient_storage_stake;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
-use near_primitives::transaction::ExecutionMetadata;
+use near_primitives::transaction::{ActionGasBreakdown, ExecutionMetadata};
 use near_primitives::version::{
     is_implicit_account_creation_enabled, ProtocolFeature, ProtocolVersion,
 };
@@ -56,11 +56,11 @@ pub use near_vm_runner::with_ext_cost_counter;
 use crate::actions::*;
 use crate::balance_checker::check_balance;
 use crate::config::{
-    exec_fee, safe_add_balance, safe_add_gas, safe_gas_to_balance, total_deposit,
+    exec_fee, safe_add_balance, safe_add_gas, safe_gas_to_balance, send_fee, total_deposit,
     total_prepaid_exec_fees, total_prepaid_gas, RuntimeConfig,
 };
 use crate::genesis::{GenesisStateApplier, StorageComputer};
-use crate::verifier::validate_receipt;
+use crate::verifier::{validate_new_receipt, validate_receipt};
 pub use crate::verifier::{validate_transaction, verify_and_charge_transaction};
 
 mod actions;
@@ -257,6 +257,7 @@ impl Runtime {
                         output_data_receivers: vec![],
                         input_data_ids: vec![],
                         actions: transaction.actions.clone(),
+                        congestion: None,
                     }),
                 };
                 stats.tx_burnt_amount =
@@ -408,6 +409,7 @@ impl Runtime {
                     stake,
                     &apply_state.prev_block_hash,
                     epoch_info_provider,
+                    apply_state.current_protocol_version,
                     #[cfg(feature = "protocol_feature_chunk_only_producers")]
                     false,
                 )?;
@@ -454,6 +456,7 @@ impl Runtime {
                     stake,
                     &apply_state.prev_block_hash,
                     epoch_info_provider,
+                    apply_state.current_protocol_version,
                     true,
                 )?;
             }
@@ -512,6 +515,7 @@ impl Runtime {
             apply_state.config.transaction_costs.action_receipt_creation_config.exec_fee();
         result.gas_used = exec_fee;
         result.gas_burnt = exec_fee;
+        let mut action_gas_breakdown = Vec::new();
         // Executing actions one by one
         for (action_index, action) in action_receipt.actions.iter().enumerate() {
             let action_hash = create_action_hash(
@@ -537,15 +541,31 @@ impl Runtime {
             )?;
             if new_result.result.is_ok() {
                 if let Err(e) = new_result.new_receipts.iter().try_for_each(|receipt| {
-                    validate_receipt(&apply_state.config.wasm_config.limit_config, receipt)
+                    validate_new_receipt(
+                        &apply_state.config.wasm_config.limit_config,
+                        receipt,
+                        apply_state.current_protocol_version,
+                    )
                 }) {
                     new_result.result = Err(ActionErrorKind::NewReceiptValidationError(e).into());
                 }
             }
+            action_gas_breakdown.push(ActionGasBreakdown {
+                action_index: action_index as u64,
+                send_gas: send_fee(
+                    &apply_state.config.transaction_costs,
+                    receipt.predecessor_id == receipt.receiver_id,
+                    action,
+                    &receipt.receiver_id,
+                    apply_state.current_protocol_version,
+                ),
+                exec_gas: new_result.gas_burnt,
+            });
             result.merge(new_result)?;
             // TODO storage error
             if let Err(ref mut res) = result.result {
                 res.index = Some(action_index as u64);
+                debug!(target: "runtime", receipt_id = %receipt.receipt_id, "{} failed: {}", action, res);
                 break;
             }
         }
@@ -747,7 +767,18 @@ impl Runtime {
                 gas_burnt: result.gas_burnt,
                 tokens_burnt,
                 executor_id: account_id.clone(),
-                metadata: ExecutionMetadata::V2(result.profile),
+                metadata: if checked_feature!(
+                    "protocol_feature_action_gas_breakdown",
+                    ActionGasBreakdown,
+                    apply_state.current_protocol_version
+                ) {
+                    ExecutionMetadata::V3 {
+                        profile: result.profile,
+                        action_gas_breakdown: Some(action_gas_breakdown),
+                    }
+                } else {
+                    ExecutionMetadata::V2(result.profile)
+                },
             },
         })
     }
@@ -1267,6 +1298,7 @@ impl Runtime {
                 target: "runtime",
                 "process_receipt",
                 receipt_id = %receipt.receipt_id,
+                receipt = %receipt,
                 node_counter = ?state_update.trie.get_trie_nodes_count())
             .entered();
             let result = self.process_receipt(
@@ -1519,6 +1551,7 @@ mod tests {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions,
+                congestion: None,
             }),
         }]
     }
@@ -1877,6 +1910,7 @@ mod tests {
                         actions: vec![Action::Transfer(TransferAction {
                             deposit: small_transfer + Balance::from(i),
                         })],
+                        congestion: None,
                     }),
                 }
             })
@@ -2196,6 +2230,7 @@ mod tests {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions,
+                congestion: None,
             }),
         }];
         let total_receipt_cost = Balance::from(gas + expected_gas_burnt) * gas_price;
@@ -2266,6 +2301,7 @@ mod tests {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions,
+                congestion: None,
             }),
         }];
         let total_receipt_cost = Balance::from(gas + expected_gas_burnt) * gas_price;
@@ -2413,4 +2449,101 @@ mod tests {
             .expect("Compiled contract should be cached")
             .expect("Compilation result should be non-empty");
     }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_action_gas_breakdown")]
+    fn test_action_gas_breakdown() {
+        let (runtime, tries, root, mut apply_state, signer, epoch_info_provider) =
+            setup_runtime(to_yocto(1_000_000), 0, 10u64.pow(15));
+        apply_state.current_protocol_version =
+            ProtocolFeature::ActionGasBreakdown.protocol_version();
+
+        let actions = vec![Action::DeployContract(DeployContractAction {
+            code: near_test_contracts::trivial_contract().to_vec(),
+        })];
+        let receipts = create_receipts_with_actions(alice_account(), signer.clone(), actions);
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(ShardUId::single_shard()),
+                root,
+                &None,
+                &apply_state,
+                &receipts,
+                &[],
+                &epoch_info_provider,
+                None,
+            )
+            .unwrap();
+        let (store_update, root) =
+            tries.apply_all(&apply_result.trie_changes, ShardUId::single_shard());
+        store_update.commit().unwrap();
+
+        let actions = vec![
+            Action::Transfer(TransferAction { deposit: 1 }),
+            Action::FunctionCall(FunctionCallAction {
+                method_name: "main".to_string(),
+                args: vec![],
+                gas: 10u64.pow(13),
+                deposit: 0,
+            }),
+            Action::AddKey(AddKeyAction {
+                public_key: PublicKey::from_seed(KeyType::ED25519, "new_key"),
+                access_key: AccessKey::full_access(),
+            }),
+        ];
+        let transaction = SignedTransaction::from_actions(
+            1,
+            alice_account(),
+            alice_account(),
+            &*signer,
+            actions.clone(),
+            CryptoHash::default(),
+        );
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(ShardUId::single_shard()),
+                root,
+                &None,
+                &apply_state,
+                &[],
+                &[transaction],
+                &epoch_info_provider,
+                None,
+            )
+            .unwrap();
+        assert_eq!(apply_result.outcomes.len(), 2);
+        let tx_outcome = &apply_result.outcomes[0].outcome;
+        let receipt_outcome = &apply_result.outcomes[1].outcome;
+        assert!(matches!(receipt_outcome.status, ExecutionStatus::SuccessValue(_)));
+        let breakdown = match &receipt_outcome.metadata {
+            ExecutionMetadata::V3 { action_gas_breakdown: Some(breakdown), .. } => breakdown,
+            metadata => panic!("unexpected metadata {:?}", metadata),
+        };
+        assert_eq!(
+            breakdown.iter().map(|action_gas| action_gas.action_index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+
+        let fees = &apply_state.config.transaction_costs;
+        // The function call burnt gas running the contract on top of its execution fee.
+        assert!(
+            breakdown[1].exec_gas
+                > exec_fee(
+                    fees,
+                    &actions[1],
+                    &alice_account(),
+                    apply_state.current_protocol_version
+                )
+        );
+        let total_exec_gas: Gas = breakdown.iter().map(|action_gas| action_gas.exec_gas).sum();
+        assert_eq!(
+            fees.action_receipt_creation_config.exec_fee() + total_exec_gas,
+            receipt_outcome.gas_burnt
+        );
+        let total_send_gas: Gas = breakdown.iter().map(|action_gas| action_gas.send_gas).sum();
+        assert_eq!(
+            fees.action_receipt_creation_config.send_fee(true) + total_send_gas,
+            tx_outcome.gas_burnt
+        );
+    }
 }
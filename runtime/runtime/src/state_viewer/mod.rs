@@ -218,6 +218,7 @@ impl TrieViewer {
             output_data_receivers: vec![],
             input_data_ids: vec![],
             actions: vec![],
+            congestion: None,
         };
         let function_call = FunctionCallAction {
             method_name: method_name.to_string(),
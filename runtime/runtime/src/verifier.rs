@@ -1,4 +1,3 @@
-use near_crypto::key_conversion::is_valid_staking_key;
 use near_primitives::runtime::get_insufficient_storage_stake;
 use near_primitives::{
     account::AccessKeyPermission,
@@ -8,18 +7,16 @@ use near_primitives::{
         RuntimeError,
     },
     receipt::{ActionReceipt, DataReceipt, Receipt, ReceiptEnum},
-    transaction::{
-        Action, AddKeyAction, DeployContractAction, FunctionCallAction, SignedTransaction,
-        StakeAction,
-    },
+    transaction::{Action, SignedTransaction},
     types::{AccountId, Balance},
+    validation::{self, validate_transaction_well_formed, TransactionLimits},
     version::ProtocolVersion,
 };
 use near_store::{
     get_access_key, get_account, set_access_key, set_account, StorageError, TrieUpdate,
 };
 
-use crate::config::{total_prepaid_gas, tx_cost, TransactionCost};
+use crate::config::{tx_cost, TransactionCost};
 use crate::VerificationResult;
 use near_primitives::checked_feature;
 use near_primitives::runtime::config::RuntimeConfig;
@@ -45,18 +42,32 @@ pub fn validate_transaction(
         return Err(InvalidTxError::InvalidSignature.into());
     }
 
-    let transaction_size = signed_transaction.get_size();
-    let max_transaction_size = config.wasm_config.limit_config.max_transaction_size;
-    if transaction_size > max_transaction_size {
-        return Err(InvalidTxError::TransactionSizeExceeded {
-            size: transaction_size,
-            limit: max_transaction_size,
-        }
-        .into());
+    validate_transaction_well_formed(
+        signed_transaction,
+        &TransactionLimits::from(&config.wasm_config.limit_config),
+    )?;
+
+    if transaction.priority_fee.is_some()
+        && !checked_feature!("protocol_feature_priority_fee", PriorityFee, current_protocol_version)
+    {
+        return Err(InvalidTxError::PriorityFeeNotSupported.into());
     }
 
-    validate_actions(&config.wasm_config.limit_config, &transaction.actions)
-        .map_err(InvalidTxError::ActionsValidation)?;
+    if checked_feature!(
+        "protocol_feature_reject_off_curve_keys",
+        RejectOffCurveKeys,
+        current_protocol_version
+    ) {
+        validate_add_key_public_keys(&transaction.actions)
+            .map_err(InvalidTxError::ActionsValidation)?;
+    }
+
+    validate_delete_account_actions(
+        &transaction.receiver_id,
+        &transaction.actions,
+        current_protocol_version,
+    )
+    .map_err(InvalidTxError::ActionsValidation)?;
 
     let sender_is_receiver = &transaction.receiver_id == signer_id;
 
@@ -194,12 +205,7 @@ pub fn verify_and_charge_transaction(
                 )
                 .into());
             }
-            if !function_call_permission.method_names.is_empty()
-                && function_call_permission
-                    .method_names
-                    .iter()
-                    .all(|method_name| &function_call.method_name != method_name)
-            {
+            if !function_call_permission.allows_method(&function_call.method_name) {
                 return Err(InvalidTxError::InvalidAccessKeyError(
                     InvalidAccessKeyError::MethodNameMismatch {
                         method_name: function_call.method_name.clone(),
@@ -246,6 +252,28 @@ pub(crate) fn validate_receipt(
     }
 }
 
+/// Validates a receipt created while executing a receipt.
+///
+/// On top of `validate_receipt`, applies the checks which only apply to new
+/// receipts: incoming and delayed receipts may have been created before the
+/// checks were introduced.
+pub(crate) fn validate_new_receipt(
+    limit_config: &VMLimitConfig,
+    receipt: &Receipt,
+    current_protocol_version: ProtocolVersion,
+) -> Result<(), ReceiptValidationError> {
+    validate_receipt(limit_config, receipt)?;
+    if let ReceiptEnum::Action(action_receipt) = &receipt.receipt {
+        validate_delete_account_actions(
+            &receipt.receiver_id,
+            &action_receipt.actions,
+            current_protocol_version,
+        )
+        .map_err(ReceiptValidationError::ActionsValidation)?;
+    }
+    Ok(())
+}
+
 /// Validates given ActionReceipt. Checks validity of the number of input data dependencies and all actions.
 fn validate_action_receipt(
     limit_config: &VMLimitConfig,
@@ -276,43 +304,12 @@ fn validate_data_receipt(
     Ok(())
 }
 
-/// Validates given actions:
-///
-/// - Checks limits if applicable.
-/// - Checks that the total number of actions doesn't exceed the limit.
-/// - Validates each individual action.
-/// - Checks that the total prepaid gas doesn't exceed the limit.
+/// Validates given actions, see `near_primitives::validation::validate_actions`.
 pub(crate) fn validate_actions(
     limit_config: &VMLimitConfig,
     actions: &[Action],
 ) -> Result<(), ActionsValidationError> {
-    if actions.len() as u64 > limit_config.max_actions_per_receipt {
-        return Err(ActionsValidationError::TotalNumberOfActionsExceeded {
-            total_number_of_actions: actions.len() as u64,
-            limit: limit_config.max_actions_per_receipt,
-        });
-    }
-
-    let mut iter = actions.iter().peekable();
-    while let Some(action) = iter.next() {
-        if let Action::DeleteAccount(_) = action {
-            if iter.peek().is_some() {
-                return Err(ActionsValidationError::DeleteActionMustBeFinal);
-            }
-        }
-        validate_action(limit_config, action)?;
-    }
-
-    let total_prepaid_gas =
-        total_prepaid_gas(actions).map_err(|_| ActionsValidationError::IntegerOverflow)?;
-    if total_prepaid_gas > limit_config.max_total_prepaid_gas {
-        return Err(ActionsValidationError::TotalPrepaidGasExceeded {
-            total_prepaid_gas,
-            limit: limit_config.max_total_prepaid_gas,
-        });
-    }
-
-    Ok(())
+    validation::validate_actions(&TransactionLimits::from(limit_config), actions)
 }
 
 /// Validates a single given action. Checks limits if applicable.
@@ -320,102 +317,37 @@ pub fn validate_action(
     limit_config: &VMLimitConfig,
     action: &Action,
 ) -> Result<(), ActionsValidationError> {
-    match action {
-        Action::CreateAccount(_) => Ok(()),
-        Action::DeployContract(a) => validate_deploy_contract_action(limit_config, a),
-        Action::FunctionCall(a) => validate_function_call_action(limit_config, a),
-        Action::Transfer(_) => Ok(()),
-        Action::Stake(a) => validate_stake_action(a),
-        #[cfg(feature = "protocol_feature_chunk_only_producers")]
-        Action::StakeChunkOnly(a) => validate_stake_action(a),
-        Action::AddKey(a) => validate_add_key_action(limit_config, a),
-        Action::DeleteKey(_) => Ok(()),
-        Action::DeleteAccount(_) => Ok(()),
-    }
-}
-
-/// Validates `DeployContractAction`. Checks that the given contract size doesn't exceed the limit.
-fn validate_deploy_contract_action(
-    limit_config: &VMLimitConfig,
-    action: &DeployContractAction,
-) -> Result<(), ActionsValidationError> {
-    if action.code.len() as u64 > limit_config.max_contract_size {
-        return Err(ActionsValidationError::ContractSizeExceeded {
-            size: action.code.len() as u64,
-            limit: limit_config.max_contract_size,
-        });
-    }
-
-    Ok(())
+    validation::validate_action(&TransactionLimits::from(limit_config), action)
 }
 
-/// Validates `FunctionCallAction`. Checks that the method name length doesn't exceed the limit and
-/// the length of the arguments doesn't exceed the limit.
-fn validate_function_call_action(
-    limit_config: &VMLimitConfig,
-    action: &FunctionCallAction,
+/// Validates the `DeleteAccountAction`s of actions applied to `account_id`.
+fn validate_delete_account_actions(
+    account_id: &AccountId,
+    actions: &[Action],
+    current_protocol_version: ProtocolVersion,
 ) -> Result<(), ActionsValidationError> {
-    if action.gas == 0 {
-        return Err(ActionsValidationError::FunctionCallZeroAttachedGas);
-    }
-
-    if action.method_name.len() as u64 > limit_config.max_length_method_name {
-        return Err(ActionsValidationError::FunctionCallMethodNameLengthExceeded {
-            length: action.method_name.len() as u64,
-            limit: limit_config.max_length_method_name,
-        });
-    }
-
-    if action.args.len() as u64 > limit_config.max_arguments_length {
-        return Err(ActionsValidationError::FunctionCallArgumentsLengthExceeded {
-            length: action.args.len() as u64,
-            limit: limit_config.max_arguments_length,
-        });
-    }
-
-    Ok(())
-}
-
-/// Validates `StakeAction`. Checks that the `public_key` is a valid staking key.
-fn validate_stake_action(action: &StakeAction) -> Result<(), ActionsValidationError> {
-    if !is_valid_staking_key(&action.public_key) {
-        return Err(ActionsValidationError::UnsuitableStakingKey {
-            public_key: action.public_key.clone(),
-        });
+    for action in actions {
+        if let Action::DeleteAccount(delete_account) = action {
+            delete_account.validate(account_id, current_protocol_version)?;
+        }
     }
-
     Ok(())
 }
 
-/// Validates `AddKeyAction`. If the access key permission is `FunctionCall`, checks that the
-/// total number of bytes of the method names doesn't exceed the limit and
-/// every method name length doesn't exceed the limit.
-fn validate_add_key_action(
-    limit_config: &VMLimitConfig,
-    action: &AddKeyAction,
-) -> Result<(), ActionsValidationError> {
-    if let AccessKeyPermission::FunctionCall(fc) = &action.access_key.permission {
-        // Checking method name length limits
-        let mut total_number_of_bytes = 0;
-        for method_name in &fc.method_names {
-            let length = method_name.len() as u64;
-            if length > limit_config.max_length_method_name {
-                return Err(ActionsValidationError::AddKeyMethodNameLengthExceeded {
-                    length,
-                    limit: limit_config.max_length_method_name,
+/// Checks that the public keys added by `AddKey` actions are valid curve points.
+///
+/// Only applied to transactions: receipts carrying such keys may have been
+/// created before the check was introduced.
+fn validate_add_key_public_keys(actions: &[Action]) -> Result<(), ActionsValidationError> {
+    for action in actions {
+        if let Action::AddKey(add_key) = action {
+            if !add_key.public_key.is_on_curve() {
+                return Err(ActionsValidationError::AddKeyPublicKeyNotOnCurve {
+                    public_key: add_key.public_key.clone(),
                 });
             }
-            // Adding terminating character to the total number of bytes
-            total_number_of_bytes += length + 1;
-        }
-        if total_number_of_bytes > limit_config.max_number_bytes_method_names {
-            return Err(ActionsValidationError::AddKeyMethodNamesNumberOfBytesExceeded {
-                total_number_of_bytes,
-                limit: limit_config.max_number_bytes_method_names,
-            });
         }
     }
-
     Ok(())
 }
 
@@ -428,7 +360,8 @@ mod tests {
     use near_primitives::hash::{hash, CryptoHash};
     use near_primitives::test_utils::account_new;
     use near_primitives::transaction::{
-        CreateAccountAction, DeleteAccountAction, DeleteKeyAction, StakeAction, TransferAction,
+        AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
+        DeployContractAction, FunctionCallAction, StakeAction, Transaction, TransferAction,
     };
     use near_primitives::types::{AccountId, Balance, MerkleHash, StateChangeCause};
     use near_primitives::version::PROTOCOL_VERSION;
@@ -658,6 +591,221 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "protocol_feature_reject_off_curve_keys")]
+    fn test_validate_transaction_off_curve_add_key() {
+        use near_crypto::Secp256K1PublicKey;
+        use near_primitives::version::ProtocolFeature;
+
+        let config = RuntimeConfig::test();
+        let (signer, mut state_update, gas_price) =
+            setup_common(TESTING_INIT_BALANCE, 0, Some(AccessKey::full_access()));
+        let off_curve_key = PublicKey::SECP256K1(Secp256K1PublicKey::from([1u8; 64]));
+        let tx = SignedTransaction::from_actions(
+            1,
+            alice_account(),
+            alice_account(),
+            &*signer,
+            vec![Action::AddKey(AddKeyAction {
+                public_key: off_curve_key.clone(),
+                access_key: AccessKey::full_access(),
+            })],
+            CryptoHash::default(),
+        );
+
+        let feature_version = ProtocolFeature::RejectOffCurveKeys.protocol_version();
+        let expected_err = RuntimeError::InvalidTxError(InvalidTxError::ActionsValidation(
+            ActionsValidationError::AddKeyPublicKeyNotOnCurve { public_key: off_curve_key },
+        ));
+        assert_eq!(
+            validate_transaction(&config, gas_price, &tx, true, feature_version)
+                .expect_err("expected an error"),
+            expected_err,
+        );
+        assert_eq!(
+            verify_and_charge_transaction(
+                &config,
+                &mut state_update,
+                gas_price,
+                &tx,
+                true,
+                None,
+                feature_version,
+            )
+            .expect_err("expected an error"),
+            expected_err,
+        );
+        validate_transaction(&config, gas_price, &tx, true, feature_version - 1)
+            .expect("off-curve keys are accepted before the feature");
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_reject_self_beneficiary")]
+    fn test_validate_transaction_delete_account_beneficiary() {
+        use near_primitives::version::ProtocolFeature;
+
+        let config = RuntimeConfig::test();
+        let (signer, _, gas_price) =
+            setup_common(TESTING_INIT_BALANCE, 0, Some(AccessKey::full_access()));
+        let feature_version = ProtocolFeature::RejectSelfBeneficiary.protocol_version();
+        let validate = |actions: Vec<Action>, protocol_version| {
+            let tx = SignedTransaction::from_actions(
+                1,
+                alice_account(),
+                alice_account(),
+                &*signer,
+                actions,
+                CryptoHash::default(),
+            );
+            validate_transaction(&config, gas_price, &tx, true, protocol_version).map(drop)
+        };
+        let delete_account =
+            |beneficiary_id| Action::DeleteAccount(DeleteAccountAction { beneficiary_id });
+        let transfer = Action::Transfer(TransferAction { deposit: 100 });
+
+        let self_beneficiary_err = RuntimeError::InvalidTxError(InvalidTxError::ActionsValidation(
+            ActionsValidationError::DeleteActionMustHaveDifferentBeneficiary {
+                account_id: alice_account(),
+                beneficiary_id: alice_account(),
+            },
+        ));
+        assert_eq!(
+            validate(vec![delete_account(alice_account())], feature_version),
+            Err(self_beneficiary_err.clone())
+        );
+        assert_eq!(
+            validate(vec![transfer.clone(), delete_account(alice_account())], feature_version),
+            Err(self_beneficiary_err)
+        );
+        validate(vec![delete_account(alice_account())], feature_version - 1)
+            .expect("self beneficiary is accepted before the feature");
+        validate(vec![transfer.clone(), delete_account(bob_account())], feature_version)
+            .expect("different beneficiary");
+        assert_eq!(
+            validate(vec![delete_account(bob_account()), transfer], feature_version),
+            Err(RuntimeError::InvalidTxError(InvalidTxError::ActionsValidation(
+                ActionsValidationError::DeleteActionMustBeFinal
+            )))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_reject_self_beneficiary")]
+    fn test_validate_new_receipt_delete_account_beneficiary() {
+        use near_primitives::version::ProtocolFeature;
+
+        let limit_config = VMLimitConfig::test();
+        let feature_version = ProtocolFeature::RejectSelfBeneficiary.protocol_version();
+        let receipt = Receipt::new_balance_refund(&alice_account(), 1);
+        let receipt = Receipt {
+            receipt: match receipt.receipt {
+                ReceiptEnum::Action(action_receipt) => ReceiptEnum::Action(ActionReceipt {
+                    actions: vec![Action::DeleteAccount(DeleteAccountAction {
+                        beneficiary_id: alice_account(),
+                    })],
+                    ..action_receipt
+                }),
+                ReceiptEnum::Data(_) => unreachable!(),
+            },
+            ..receipt
+        };
+        assert_eq!(
+            validate_new_receipt(&limit_config, &receipt, feature_version),
+            Err(ReceiptValidationError::ActionsValidation(
+                ActionsValidationError::DeleteActionMustHaveDifferentBeneficiary {
+                    account_id: alice_account(),
+                    beneficiary_id: alice_account(),
+                }
+            ))
+        );
+        // Receipts already in flight are still accepted.
+        validate_receipt(&limit_config, &receipt).expect("valid receipt");
+        validate_new_receipt(&limit_config, &receipt, feature_version - 1)
+            .expect("self beneficiary is accepted before the feature");
+    }
+
+    fn priority_fee_transaction(
+        signer: &InMemorySigner,
+        priority_fee: Option<Balance>,
+    ) -> SignedTransaction {
+        Transaction {
+            signer_id: alice_account(),
+            public_key: signer.public_key(),
+            nonce: 1,
+            receiver_id: bob_account(),
+            block_hash: CryptoHash::default(),
+            actions: vec![Action::Transfer(TransferAction { deposit: 100 })],
+            priority_fee,
+        }
+        .sign(signer)
+    }
+
+    #[test]
+    fn test_validate_transaction_priority_fee_not_supported() {
+        let config = RuntimeConfig::test();
+        let (signer, mut state_update, gas_price) =
+            setup_common(TESTING_INIT_BALANCE, 0, Some(AccessKey::full_access()));
+
+        #[cfg(feature = "protocol_feature_priority_fee")]
+        let protocol_version =
+            near_primitives::version::ProtocolFeature::PriorityFee.protocol_version() - 1;
+        #[cfg(not(feature = "protocol_feature_priority_fee"))]
+        let protocol_version = PROTOCOL_VERSION;
+        let tx = priority_fee_transaction(&signer, Some(10));
+        let expected_err = RuntimeError::InvalidTxError(InvalidTxError::PriorityFeeNotSupported);
+        assert_eq!(
+            validate_transaction(&config, gas_price, &tx, true, protocol_version)
+                .expect_err("expected an error"),
+            expected_err,
+        );
+        assert_eq!(
+            verify_and_charge_transaction(
+                &config,
+                &mut state_update,
+                gas_price,
+                &tx,
+                true,
+                None,
+                protocol_version,
+            )
+            .expect_err("expected an error"),
+            expected_err,
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_priority_fee")]
+    fn test_validate_transaction_priority_fee_charged_upfront() {
+        use near_primitives::version::ProtocolFeature;
+
+        let config = RuntimeConfig::test();
+        let feature_version = ProtocolFeature::PriorityFee.protocol_version();
+        let priority_fee = 10 * NEAR_BASE;
+        let mut results = vec![];
+        for fee in [None, Some(priority_fee)] {
+            let (signer, mut state_update, gas_price) =
+                setup_common(TESTING_INIT_BALANCE, 0, Some(AccessKey::full_access()));
+            let tx = priority_fee_transaction(&signer, fee);
+            let verification_result = verify_and_charge_transaction(
+                &config,
+                &mut state_update,
+                gas_price,
+                &tx,
+                true,
+                None,
+                feature_version,
+            )
+            .expect("valid transaction");
+            let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+            results.push((verification_result, account.amount()));
+        }
+        let (without_fee, balance_without_fee) = &results[0];
+        let (with_fee, balance_with_fee) = &results[1];
+        assert_eq!(with_fee.gas_burnt, without_fee.gas_burnt);
+        assert_eq!(with_fee.burnt_amount, without_fee.burnt_amount + priority_fee);
+        assert_eq!(*balance_with_fee, balance_without_fee - priority_fee);
+    }
+
     #[test]
     fn test_validate_transaction_invalid_bad_signer() {
         let config = RuntimeConfig::test();
@@ -1170,7 +1318,8 @@ mod tests {
                     gas_price: 100,
                     output_data_receivers: vec![],
                     input_data_ids: vec![CryptoHash::default(), CryptoHash::default()],
-                    actions: vec![]
+                    actions: vec![],
+                    congestion: None,
                 }
             )
             .expect_err("expected an error"),
@@ -0,0 +1,17 @@
+use std::path::PathBuf;
+
+/// Writes the borsh schema of the consensus-critical types to the given path,
+/// `core/primitives/res/borsh_schema.json` by default.  Run from the
+/// repository root after an intentional change of the wire format.
+fn main() -> std::io::Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("core/primitives/res/borsh_schema.json"));
+    let schema = near_primitives::borsh_schema::consensus_schema_json();
+    let mut json = serde_json::to_string_pretty(&schema)?;
+    json.push('\n');
+    std::fs::write(&path, json)?;
+    println!("Wrote the borsh schema to {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,338 @@
+use crate::output::Record;
+use borsh::BorshDeserialize;
+use near_chain::RuntimeAdapter;
+use near_crypto::PublicKey;
+use near_primitives::account::id::AccountId;
+use near_primitives::account::AccessKey;
+use near_primitives::block::BlockHeader;
+use near_primitives::trie_key::trie_key_parsers;
+use near_primitives::types::{Nonce, StateRoot};
+use near_primitives::views::{AccessKeyInfoView, AccessKeyPermissionView, AccountView};
+use near_store::{Trie, TrieIterator};
+use nearcore::NightshadeRuntime;
+use node_runtime::adapter::ViewRuntimeAdapter;
+use serde::Serialize;
+use serde_json::json;
+use std::io::Write;
+
+/// Writes the state of `account_id` as JSON lines: first the account, which
+/// has the hash of the contract code, then an `access_key` line per access
+/// key and a `data` line per contract storage entry, with the key in hex and
+/// the value both in hex and as lossy UTF-8.
+///
+/// The storage is read from the trie directly rather than through the view
+/// runtime, so that it isn't subject to the state size limit of
+/// `view_state` and is never held in memory all at once.
+pub(crate) fn dump_account(
+    runtime: &NightshadeRuntime,
+    header: &BlockHeader,
+    state_roots: &[StateRoot],
+    account_id: &AccountId,
+    output: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let epoch_id = header.epoch_id().clone();
+    let shard_id = runtime.account_id_to_shard_id(account_id, &epoch_id)?;
+    let shard_uid = runtime.shard_id_to_uid(shard_id, &epoch_id)?;
+    let state_root = state_roots[shard_id as usize];
+
+    let account = runtime.view_account(&shard_uid, state_root, account_id)?;
+    serde_json::to_writer(&mut *output, &json!({ "account": AccountView::from(account) }))?;
+    writeln!(output)?;
+
+    for (public_key, access_key) in runtime.view_access_keys(&shard_uid, state_root, account_id)? {
+        let access_key = AccessKeyInfoView { public_key, access_key: access_key.into() };
+        serde_json::to_writer(&mut *output, &json!({ "access_key": access_key }))?;
+        writeln!(output)?;
+    }
+
+    let trie = runtime.get_trie_for_shard(shard_id, header.prev_hash())?;
+    let prefix = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, &[]);
+    let mut iter = TrieIterator::new(&trie, &state_root)?;
+    iter.seek(&prefix)?;
+    for item in iter {
+        let (key, value) = item?;
+        if !key.starts_with(&prefix) {
+            break;
+        }
+        let data = json!({
+            "key": hex::encode(&key[prefix.len()..]),
+            "value_hex": hex::encode(&value),
+            "value_utf8": String::from_utf8_lossy(&value),
+        });
+        serde_json::to_writer(&mut *output, &json!({ "data": data }))?;
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+/// An access key of an account as stored in the trie.
+#[derive(Serialize, Debug)]
+pub(crate) struct AccessKeyRecord {
+    pub account_id: AccountId,
+    pub public_key: PublicKey,
+    pub nonce: Nonce,
+    pub permission: AccessKeyPermissionView,
+}
+
+impl Record for AccessKeyRecord {
+    fn human(&self) -> String {
+        let permission = match &self.permission {
+            AccessKeyPermissionView::FullAccess => "full access".to_string(),
+            AccessKeyPermissionView::FunctionCall { allowance, receiver_id, method_names } => {
+                format!(
+                    "function call to {}, methods [{}], allowance {}",
+                    receiver_id,
+                    method_names.join(", "),
+                    allowance.map_or("unlimited".to_string(), |allowance| allowance.to_string())
+                )
+            }
+        };
+        format!("{} {} nonce {}: {}", self.account_id, self.public_key, self.nonce, permission)
+    }
+}
+
+/// Calls `on_key` for every access key whose raw trie key starts with
+/// `raw_prefix` and whose account `filter` accepts, in key order.
+fn iter_access_keys(
+    trie: &Trie,
+    state_root: &StateRoot,
+    raw_prefix: &[u8],
+    filter: &dyn Fn(&AccountId) -> bool,
+    on_key: &mut dyn FnMut(AccessKeyRecord) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut iter = TrieIterator::new(trie, state_root)?;
+    iter.seek(raw_prefix)?;
+    for item in iter {
+        let (key, value) = item?;
+        if !key.starts_with(raw_prefix) {
+            break;
+        }
+        let account_id = trie_key_parsers::parse_account_id_from_access_key_key(&key)?;
+        if !filter(&account_id) {
+            continue;
+        }
+        let public_key = trie_key_parsers::parse_public_key_from_access_key_key(&key, &account_id)?;
+        let access_key = AccessKey::try_from_slice(&value)?;
+        on_key(AccessKeyRecord {
+            account_id,
+            public_key,
+            nonce: access_key.nonce,
+            permission: access_key.permission.into(),
+        })?;
+    }
+    Ok(())
+}
+
+/// Calls `on_key` for every access key of `account_id`, read from the trie
+/// of its shard rather than through the view runtime.  Fails if the account
+/// doesn't exist.
+pub(crate) fn account_access_keys(
+    runtime: &NightshadeRuntime,
+    header: &BlockHeader,
+    state_roots: &[StateRoot],
+    account_id: &AccountId,
+    on_key: &mut dyn FnMut(AccessKeyRecord) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let epoch_id = header.epoch_id().clone();
+    let shard_id = runtime.account_id_to_shard_id(account_id, &epoch_id)?;
+    let shard_uid = runtime.shard_id_to_uid(shard_id, &epoch_id)?;
+    let state_root = state_roots[shard_id as usize];
+    runtime.view_account(&shard_uid, state_root, account_id)?;
+
+    let trie = runtime.get_trie_for_shard(shard_id, header.prev_hash())?;
+    let prefix = trie_key_parsers::get_raw_prefix_for_access_keys(account_id);
+    iter_access_keys(&trie, &state_root, &prefix, &|_| true, on_key)
+}
+
+/// Calls `on_key` for every access key of the implicit accounts whose id
+/// starts with `hex_prefix`, going through the tries of all shards.  An
+/// empty prefix lists the keys of all implicit accounts.
+pub(crate) fn implicit_access_keys(
+    runtime: &NightshadeRuntime,
+    header: &BlockHeader,
+    state_roots: &[StateRoot],
+    hex_prefix: &str,
+    on_key: &mut dyn FnMut(AccessKeyRecord) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        hex_prefix.len() <= 64
+            && hex_prefix.bytes().all(|b| matches!(b, b'a'..=b'f' | b'0'..=b'9')),
+        "{} is not a prefix of an implicit account, which are 64 lowercase hex digits",
+        hex_prefix
+    );
+    let prefix = &trie_key_parsers::get_raw_prefixes_for_account_id_prefix(hex_prefix)[2];
+    for (shard_id, state_root) in state_roots.iter().enumerate() {
+        let trie = runtime.get_trie_for_shard(shard_id as u64, header.prev_hash())?;
+        iter_access_keys(&trie, state_root, prefix, &AccountId::is_implicit, on_key)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{account_access_keys, dump_account, implicit_access_keys, AccessKeyRecord};
+    use crate::output::Record;
+    use crate::test_utils::setup_env_with_genesis;
+    use near_chain::ChainGenesis;
+    use near_chain_configs::Genesis;
+    use near_crypto::{KeyType, PublicKey};
+    use near_primitives::account::id::AccountId;
+    use near_primitives::account::{
+        AccessKey, AccessKeyPermission, Account, FunctionCallPermission,
+    };
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::state_record::StateRecord;
+    use near_primitives::views::AccessKeyPermissionView;
+    use nearcore::config::GenesisExt;
+    use serde_json::Value;
+
+    #[test]
+    fn test_dump_account() {
+        let mut genesis =
+            Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+        for (data_key, value) in [(b"b".to_vec(), vec![0xff, b'x']), (b"a".to_vec(), b"1".to_vec())]
+        {
+            genesis.records.0.push(StateRecord::Data {
+                account_id: "test1".parse().unwrap(),
+                data_key,
+                value,
+            });
+        }
+        let mut setup = setup_env_with_genesis(genesis, ChainGenesis::test(), 2);
+        let runtime = setup.runtime;
+        let head = setup.env.clients[0].chain.head().unwrap();
+        let block = setup.env.clients[0].chain.get_block(&head.last_block_hash).unwrap().clone();
+        let state_roots: Vec<_> =
+            block.chunks().iter().map(|chunk| chunk.prev_state_root()).collect();
+
+        let mut output = vec![];
+        dump_account(
+            &runtime,
+            block.header(),
+            &state_roots,
+            &"test1".parse().unwrap(),
+            &mut output,
+        )
+        .unwrap();
+        let lines: Vec<Value> = std::str::from_utf8(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4, "{:?}", lines);
+        assert!(lines[0]["account"]["code_hash"].is_string());
+        assert!(lines[1]["access_key"]["public_key"].is_string());
+        assert_eq!(lines[2]["data"]["key"], "61");
+        assert_eq!(lines[2]["data"]["value_utf8"], "1");
+        assert_eq!(lines[3]["data"]["key"], "62");
+        assert_eq!(lines[3]["data"]["value_hex"], "ff78");
+        assert_eq!(lines[3]["data"]["value_utf8"], "\u{fffd}x");
+
+        let missing = dump_account(
+            &runtime,
+            block.header(),
+            &state_roots,
+            &"test2".parse().unwrap(),
+            &mut vec![],
+        );
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_access_keys() {
+        let mut genesis =
+            Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+        let implicit_key = PublicKey::from_seed(KeyType::ED25519, "implicit");
+        let implicit_id: AccountId = hex::encode(implicit_key.key_data()).parse().unwrap();
+        let function_call_key = PublicKey::from_seed(KeyType::ED25519, "function call");
+        genesis.records.0.extend([
+            StateRecord::Account {
+                account_id: implicit_id.clone(),
+                account: Account::new(1, 0, CryptoHash::default(), 0),
+            },
+            StateRecord::AccessKey {
+                account_id: implicit_id.clone(),
+                public_key: implicit_key.clone(),
+                access_key: AccessKey::full_access(),
+            },
+            StateRecord::AccessKey {
+                account_id: "test1".parse().unwrap(),
+                public_key: function_call_key.clone(),
+                access_key: AccessKey {
+                    nonce: 7,
+                    permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                        allowance: Some(100),
+                        receiver_id: "test0".to_string(),
+                        method_names: vec!["a".to_string(), "b".to_string()],
+                    }),
+                },
+            },
+        ]);
+        let mut setup = setup_env_with_genesis(genesis, ChainGenesis::test(), 0);
+        let block = setup.produce_blocks([1]).pop().unwrap();
+        let runtime = setup.runtime;
+        let state_roots: Vec<_> =
+            block.chunks().iter().map(|chunk| chunk.prev_state_root()).collect();
+
+        let account_keys = |account_id: &str| {
+            let mut keys: Vec<AccessKeyRecord> = vec![];
+            account_access_keys(
+                &runtime,
+                block.header(),
+                &state_roots,
+                &account_id.parse().unwrap(),
+                &mut |key| {
+                    keys.push(key);
+                    Ok(())
+                },
+            )
+            .map(|()| keys)
+        };
+        let keys = account_keys("test1").unwrap();
+        assert_eq!(keys.len(), 2, "{:?}", keys);
+        let function_call = keys.iter().find(|key| key.public_key == function_call_key).unwrap();
+        assert_eq!(function_call.nonce, 7);
+        assert_eq!(
+            function_call.permission,
+            AccessKeyPermissionView::FunctionCall {
+                allowance: Some(100),
+                receiver_id: "test0".to_string(),
+                method_names: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+        assert_eq!(
+            function_call.human(),
+            format!(
+                "test1 {} nonce 7: function call to test0, methods [a, b], allowance 100",
+                function_call_key
+            )
+        );
+        let json = serde_json::to_value(function_call).unwrap();
+        assert_eq!(json["permission"]["FunctionCall"]["allowance"], "100");
+        assert!(keys.iter().all(|key| key.account_id.as_ref() == "test1"));
+        assert!(account_keys("test2").is_err());
+
+        let implicit_keys = |hex_prefix: &str| {
+            let mut keys: Vec<AccessKeyRecord> = vec![];
+            implicit_access_keys(&runtime, block.header(), &state_roots, hex_prefix, &mut |key| {
+                keys.push(key);
+                Ok(())
+            })
+            .map(|()| keys)
+        };
+        let keys = implicit_keys("").unwrap();
+        assert_eq!(keys.len(), 1, "{:?}", keys);
+        assert_eq!(keys[0].account_id, implicit_id);
+        assert_eq!(keys[0].public_key, implicit_key);
+        assert_eq!(keys[0].permission, AccessKeyPermissionView::FullAccess);
+        assert_eq!(
+            keys[0].human(),
+            format!("{} {} nonce 0: full access", implicit_id, implicit_key)
+        );
+        assert_eq!(implicit_keys(&implicit_id.as_ref()[..3]).unwrap().len(), 1);
+        let other_prefix = if implicit_id.as_ref().starts_with('0') { "1" } else { "0" };
+        assert_eq!(implicit_keys(other_prefix).unwrap().len(), 0);
+        // Implicit account ids are hex.
+        assert!(implicit_keys("te").is_err());
+    }
+}
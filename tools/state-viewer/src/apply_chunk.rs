@@ -11,6 +11,7 @@ use near_primitives::shard_layout;
 use near_primitives::sharding::{ChunkHash, ReceiptProof};
 use near_primitives::syncing::ReceiptProofResponse;
 use near_primitives::types::{BlockHeight, ShardId};
+use near_primitives::utils::bytes_to_index;
 use near_primitives_core::hash::hash;
 use near_primitives_core::types::Gas;
 use near_store::DBCol;
@@ -217,7 +218,7 @@ fn apply_tx_in_chunk(
     let mut chunk_hashes = vec![];
 
     for (k, v) in store.iter(DBCol::ChunkHashesByHeight) {
-        let height = BlockHeight::from_le_bytes(k[..].try_into().unwrap());
+        let height = bytes_to_index(&k).unwrap();
         if height > head {
             let hashes = HashSet::<ChunkHash>::try_from_slice(&v).unwrap();
             for chunk_hash in hashes {
@@ -323,7 +324,7 @@ fn apply_receipt_in_chunk(
     let mut non_applied_chunks = HashMap::new();
 
     for (k, v) in store.iter(DBCol::ChunkHashesByHeight) {
-        let height = BlockHeight::from_le_bytes(k[..].try_into().unwrap());
+        let height = bytes_to_index(&k).unwrap();
         if height > head {
             let hashes = HashSet::<ChunkHash>::try_from_slice(&v).unwrap();
             for chunk_hash in hashes {
@@ -403,45 +404,17 @@ pub(crate) fn apply_receipt(
 
 #[cfg(test)]
 mod test {
-    use near_chain::{ChainGenesis, ChainStore, ChainStoreAccess, Provenance, RuntimeAdapter};
+    use crate::test_utils::{setup_env_with_genesis, TestSetup};
+    use near_chain::{ChainGenesis, ChainStore, ChainStoreAccess, RuntimeAdapter};
     use near_chain_configs::Genesis;
-    use near_client::test_utils::TestEnv;
-    use near_crypto::{InMemorySigner, KeyType};
-    use near_network::types::NetworkClientResponses;
-    use near_primitives::hash::CryptoHash;
-    use near_primitives::runtime::config_store::RuntimeConfigStore;
     use near_primitives::shard_layout;
-    use near_primitives::transaction::SignedTransaction;
     use near_primitives::utils::get_num_seats_per_shard;
-    use near_store::test_utils::create_test_store;
     use nearcore::config::GenesisExt;
-    use nearcore::NightshadeRuntime;
-    use nearcore::TrackedConfig;
     use rand::rngs::StdRng;
     use rand::SeedableRng;
-    use std::path::Path;
-    use std::sync::Arc;
 
-    fn send_txs(env: &mut TestEnv, signers: &[InMemorySigner], height: u64, hash: CryptoHash) {
-        for (i, signer) in signers.iter().enumerate() {
-            let from = format!("test{}", i);
-            let to = format!("test{}", (i + 1) % signers.len());
-            let tx = SignedTransaction::send_money(
-                height,
-                from.parse().unwrap(),
-                to.parse().unwrap(),
-                signer,
-                100,
-                hash,
-            );
-            let response = env.clients[0].process_tx(tx, false, false);
-            assert_eq!(response, NetworkClientResponses::ValidTx);
-        }
-    }
-
-    #[test]
-    fn test_apply_chunk() {
-        let genesis = Genesis::test_sharded(
+    fn sharded_genesis() -> Genesis {
+        Genesis::test_sharded(
             vec![
                 "test0".parse().unwrap(),
                 "test1".parse().unwrap(),
@@ -450,42 +423,34 @@ mod test {
             ],
             1,
             get_num_seats_per_shard(4, 1),
-        );
-
-        let store = create_test_store();
-        let mut chain_store = ChainStore::new(store.clone(), genesis.config.genesis_height, false);
-        let runtime = Arc::new(NightshadeRuntime::test_with_runtime_config_store(
-            Path::new("."),
-            store,
-            &genesis,
-            TrackedConfig::AllShards,
-            RuntimeConfigStore::test(),
-            None,
-        ));
-        let chain_genesis = ChainGenesis::test();
+        )
+    }
 
-        let signers = (0..4)
-            .map(|i| {
-                let acc = format!("test{}", i);
-                InMemorySigner::from_seed(acc.parse().unwrap(), KeyType::ED25519, &acc)
-            })
-            .collect::<Vec<_>>();
+    /// Sends money from each test account to the next one.
+    fn send_txs(setup: &mut TestSetup, nonce: u64) {
+        for i in 0..4 {
+            let from = format!("test{}", i);
+            let to = format!("test{}", (i + 1) % 4);
+            setup.send_money(nonce, &from, &to);
+        }
+    }
 
-        let mut env =
-            TestEnv::builder(chain_genesis).runtime_adapters(vec![runtime.clone()]).build();
-        let genesis_hash = *env.clients[0].chain.genesis().hash();
+    #[test]
+    fn test_apply_chunk() {
+        let mut setup = setup_env_with_genesis(sharded_genesis(), ChainGenesis::test(), 0);
+        let runtime = setup.runtime.clone();
+        let mut chain_store =
+            ChainStore::new(setup.store.clone(), setup.genesis.config.genesis_height, false);
 
         for height in 1..10 {
-            send_txs(&mut env, &signers, height, genesis_hash);
+            send_txs(&mut setup, height);
 
-            let block = env.clients[0].produce_block(height).unwrap().unwrap();
+            let block = setup.produce_blocks([height]).pop().unwrap();
 
             let hash = *block.hash();
             let chunk_hashes = block.chunks().iter().map(|c| c.chunk_hash()).collect::<Vec<_>>();
             let epoch_id = block.header().epoch_id().clone();
 
-            env.process_block(0, block, Provenance::PRODUCED);
-
             let new_roots = (0..4)
                 .map(|i| {
                     let shard_uid = runtime.shard_id_to_uid(i, &epoch_id).unwrap();
@@ -519,56 +484,28 @@ mod test {
 
     #[test]
     fn test_apply_tx_apply_receipt() {
-        let genesis = Genesis::test_sharded(
-            vec![
-                "test0".parse().unwrap(),
-                "test1".parse().unwrap(),
-                "test2".parse().unwrap(),
-                "test3".parse().unwrap(),
-            ],
-            1,
-            get_num_seats_per_shard(4, 1),
-        );
-
-        let store = create_test_store();
-        let mut chain_store = ChainStore::new(store.clone(), genesis.config.genesis_height, false);
-        let runtime = Arc::new(NightshadeRuntime::test_with_runtime_config_store(
-            Path::new("."),
-            store.clone(),
-            &genesis,
-            TrackedConfig::AllShards,
-            RuntimeConfigStore::test(),
-            None,
-        ));
+        let genesis = sharded_genesis();
         let mut chain_genesis = ChainGenesis::test();
         // receipts get delayed with the small ChainGenesis::test() limit
         chain_genesis.gas_limit = genesis.config.gas_limit;
-
-        let signers = (0..4)
-            .map(|i| {
-                let acc = format!("test{}", i);
-                InMemorySigner::from_seed(acc.parse().unwrap(), KeyType::ED25519, &acc)
-            })
-            .collect::<Vec<_>>();
-
-        let mut env =
-            TestEnv::builder(chain_genesis).runtime_adapters(vec![runtime.clone()]).build();
-        let genesis_hash = *env.clients[0].chain.genesis().hash();
+        let mut setup = setup_env_with_genesis(genesis, chain_genesis, 0);
+        let genesis_height = setup.genesis.config.genesis_height;
+        let runtime = setup.runtime.clone();
+        let store = setup.store.clone();
+        let mut chain_store = ChainStore::new(store.clone(), genesis_height, false);
 
         // first check that applying txs and receipts works when the block exists
 
         for height in 1..5 {
-            send_txs(&mut env, &signers, height, genesis_hash);
+            send_txs(&mut setup, height);
 
-            let block = env.clients[0].produce_block(height).unwrap().unwrap();
+            let block = setup.produce_blocks([height]).pop().unwrap();
 
             let hash = *block.hash();
             let prev_hash = *block.header().prev_hash();
             let chunk_hashes = block.chunks().iter().map(|c| c.chunk_hash()).collect::<Vec<_>>();
             let epoch_id = block.header().epoch_id().clone();
 
-            env.process_block(0, block, Provenance::PRODUCED);
-
             let new_roots = (0..4)
                 .map(|i| {
                     let shard_uid = runtime.shard_id_to_uid(i, &epoch_id).unwrap();
@@ -583,7 +520,7 @@ mod test {
 
                     for tx in chunk.transactions() {
                         let results = crate::apply_chunk::apply_tx(
-                            genesis.config.genesis_height,
+                            genesis_height,
                             runtime.as_ref(),
                             store.clone(),
                             tx.get_hash(),
@@ -600,7 +537,7 @@ mod test {
                         );
 
                         let results = crate::apply_chunk::apply_receipt(
-                            genesis.config.genesis_height,
+                            genesis_height,
                             runtime.as_ref(),
                             store.clone(),
                             receipt.get_hash(),
@@ -628,7 +565,7 @@ mod test {
 
             for tx in chunk.transactions() {
                 let results = crate::apply_chunk::apply_tx(
-                    genesis.config.genesis_height,
+                    genesis_height,
                     runtime.as_ref(),
                     store.clone(),
                     tx.get_hash(),
@@ -647,7 +584,7 @@ mod test {
             }
             for receipt in chunk.receipts() {
                 let results = crate::apply_chunk::apply_receipt(
-                    genesis.config.genesis_height,
+                    genesis_height,
                     runtime.as_ref(),
                     store.clone(),
                     receipt.get_hash(),
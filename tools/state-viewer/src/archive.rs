@@ -0,0 +1,252 @@
+use crate::block::sorted_hashes;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::block::{Block, BlockHeader, BlocksPerHeight};
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ShardChunk;
+use near_primitives::types::BlockHeight;
+use near_primitives::utils::index_to_bytes;
+use near_store::{DBCol, Store};
+use std::collections::HashSet;
+use std::io::Write;
+use std::ops::RangeInclusive;
+
+const MAGIC: &[u8; 8] = b"NEARARCH";
+const VERSION: u32 = 1;
+
+/// Start of an archive, followed by borsh serialized [`ArchiveEntry`]s until
+/// the end of the file.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+struct ArchiveHeader {
+    magic: [u8; 8],
+    version: u32,
+    start_height: BlockHeight,
+    end_height: BlockHeight,
+}
+
+/// An entry of the database.  Values of rc columns are stored without the
+/// refcount.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ArchiveEntry {
+    col: DBCol,
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+/// Writes every entry at most once, as blocks share receipts, outcomes and
+/// epochs.
+struct Exporter<'a, W: Write> {
+    store: &'a Store,
+    output: W,
+    written: HashSet<(DBCol, Vec<u8>)>,
+}
+
+impl<W: Write> Exporter<'_, W> {
+    /// Copies the entry to the archive and returns its value, if it exists.
+    fn copy(&mut self, col: DBCol, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let value = match self.store.get(col, key)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        if self.written.insert((col, key.to_vec())) {
+            ArchiveEntry { col, key: key.to_vec(), value: value.clone() }
+                .serialize(&mut self.output)?;
+        }
+        Ok(Some(value))
+    }
+
+    fn copy_ser<T: BorshDeserialize>(
+        &mut self,
+        col: DBCol,
+        key: &[u8],
+    ) -> anyhow::Result<Option<T>> {
+        match self.copy(col, key)? {
+            Some(value) => Ok(Some(T::try_from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Copies the entries of a column keyed by block hash and shard.
+    fn copy_prefix(&mut self, col: DBCol, block_hash: &CryptoHash) -> anyhow::Result<()> {
+        let keys: Vec<_> =
+            self.store.iter_prefix(col, block_hash.as_ref()).map(|(k, _)| k).collect();
+        for key in keys {
+            if col == DBCol::OutcomeIds {
+                let ids: Vec<CryptoHash> = self.copy_ser(col, &key)?.unwrap_or_default();
+                for id in ids {
+                    self.copy(DBCol::TransactionResult, id.as_ref())?;
+                }
+            } else {
+                self.copy(col, &key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_block(&mut self, hash: &CryptoHash) -> anyhow::Result<()> {
+        let header: BlockHeader = self
+            .copy_ser(DBCol::BlockHeader, hash.as_ref())?
+            .ok_or_else(|| anyhow::anyhow!("no header of block {}", hash))?;
+        for col in
+            [DBCol::BlockInfo, DBCol::BlockExtra, DBCol::BlockMerkleTree, DBCol::NextBlockHashes]
+        {
+            self.copy(col, hash.as_ref())?;
+        }
+        for col in
+            [DBCol::ChunkExtra, DBCol::OutgoingReceipts, DBCol::IncomingReceipts, DBCol::OutcomeIds]
+        {
+            self.copy_prefix(col, hash)?;
+        }
+        for epoch_id in [header.epoch_id(), header.next_epoch_id()] {
+            self.copy(DBCol::EpochInfo, epoch_id.as_ref())?;
+            self.copy(DBCol::EpochStart, epoch_id.as_ref())?;
+        }
+        let block: Block = match self.copy_ser(DBCol::Block, hash.as_ref())? {
+            Some(block) => block,
+            None => return Ok(()),
+        };
+        for chunk_header in block.chunks().iter() {
+            if chunk_header.height_included() != header.height() {
+                continue;
+            }
+            let chunk_hash = chunk_header.chunk_hash();
+            self.copy(DBCol::PartialChunks, chunk_hash.as_ref())?;
+            let chunk: ShardChunk = match self.copy_ser(DBCol::Chunks, chunk_hash.as_ref())? {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            for tx in chunk.transactions() {
+                self.copy(DBCol::Transactions, tx.get_hash().as_ref())?;
+            }
+            for receipt in chunk.receipts() {
+                self.copy(DBCol::Receipts, receipt.get_hash().as_ref())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the blocks at `heights` with their headers, chunks, transactions,
+/// receipts, outcomes and epochs to `output`, and returns the number of
+/// entries written.
+///
+/// Missing entries are skipped, e.g. chunks of garbage collected blocks only
+/// have their headers exported.
+pub(crate) fn export_archive(
+    store: &Store,
+    heights: RangeInclusive<BlockHeight>,
+    mut output: impl Write,
+) -> anyhow::Result<usize> {
+    ArchiveHeader {
+        magic: *MAGIC,
+        version: VERSION,
+        start_height: *heights.start(),
+        end_height: *heights.end(),
+    }
+    .serialize(&mut output)?;
+    let mut exporter = Exporter { store, output, written: HashSet::new() };
+    for height in heights {
+        let key = index_to_bytes(height);
+        exporter.copy(DBCol::BlockHeight, &key)?;
+        let blocks: BlocksPerHeight = match exporter.copy_ser(DBCol::BlockPerHeight, &key)? {
+            Some(blocks) => blocks,
+            None => continue,
+        };
+        for hash in sorted_hashes(&blocks) {
+            exporter.copy_block(&hash)?;
+        }
+    }
+    exporter.output.flush()?;
+    Ok(exporter.written.len())
+}
+
+/// Writes the entries of an archive to `store` in a single transaction, and
+/// returns the exported height range and the number of entries.
+///
+/// Nothing is written if any of the entries is already in the store, so the
+/// archive should be imported into a fresh database.
+pub(crate) fn import_archive(
+    store: &Store,
+    archive: &[u8],
+) -> anyhow::Result<(RangeInclusive<BlockHeight>, usize)> {
+    let mut archive = archive;
+    let header = ArchiveHeader::deserialize(&mut archive)
+        .map_err(|err| anyhow::anyhow!("not an archive: {}", err))?;
+    anyhow::ensure!(&header.magic == MAGIC, "not an archive");
+    anyhow::ensure!(
+        header.version == VERSION,
+        "archive version {} is not supported, expected {}",
+        header.version,
+        VERSION
+    );
+    let mut update = store.store_update();
+    let mut num_entries = 0;
+    while !archive.is_empty() {
+        let ArchiveEntry { col, key, value } = ArchiveEntry::deserialize(&mut archive)?;
+        anyhow::ensure!(
+            !store.exists(col, &key)?,
+            "{} {} is already in the database",
+            <&str>::from(col),
+            hex::encode(&key)
+        );
+        if col.is_rc() {
+            update.update_refcount(col, &key, &value, 1);
+        } else if col.is_insert_only() {
+            update.insert(col, &key, &value);
+        } else {
+            update.set(col, &key, &value);
+        }
+        num_entries += 1;
+    }
+    update.commit()?;
+    Ok((header.start_height..=header.end_height, num_entries))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{export_archive, import_archive};
+    use crate::test_utils::setup_env;
+    use near_primitives::utils::index_to_bytes;
+    use near_store::test_utils::create_test_store;
+    use near_store::DBCol;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_export_import() {
+        let mut setup = setup_env(&["test0", "test1"], 0);
+        let tx = setup.send_money(1, "test0", "test1");
+        setup.produce_blocks(1..6);
+        let store = setup.store;
+
+        let mut archive = vec![];
+        let num_exported = export_archive(&store, 1..=4, &mut archive).unwrap();
+        let imported = create_test_store();
+        let (heights, num_imported) = import_archive(&imported, &archive).unwrap();
+        assert_eq!(heights, 1..=4);
+        assert_eq!(num_imported, num_exported);
+
+        // Everything imported is what the source has.
+        let mut num_entries = 0;
+        for col in DBCol::iter() {
+            for (key, value) in imported.iter_raw_bytes(col) {
+                assert_eq!(store.get(col, &key).unwrap(), imported.get(col, &key).unwrap());
+                if col.is_rc() {
+                    assert!(value.ends_with(&1i64.to_le_bytes()), "{:?}", col);
+                }
+                num_entries += 1;
+            }
+        }
+        assert_eq!(num_entries, num_exported);
+        let height_key = index_to_bytes(2);
+        let hash = imported.get(DBCol::BlockHeight, &height_key).unwrap().unwrap();
+        assert!(imported.exists(DBCol::Block, &hash).unwrap());
+        assert!(imported.exists(DBCol::Transactions, tx.get_hash().as_ref()).unwrap());
+        assert!(imported.exists(DBCol::TransactionResult, tx.get_hash().as_ref()).unwrap());
+        assert!(!imported.exists(DBCol::BlockHeight, &index_to_bytes(5)).unwrap());
+
+        // Importing again would double the refcounts, so nothing is written.
+        let err = import_archive(&imported, &archive).unwrap_err();
+        assert!(err.to_string().contains("is already in the database"), "{}", err);
+        let err = import_archive(&create_test_store(), b"not an archive at all").unwrap_err();
+        assert!(err.to_string().contains("not an archive"), "{}", err);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::output::Record;
+use near_store::{DBCol, Store};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Read throughput and point read latencies of a column.
+#[derive(Serialize, Debug)]
+pub(crate) struct ColumnBench {
+    pub column: &'static str,
+    /// Keys read by the scan, all keys of the column if `complete`.
+    pub num_keys: u64,
+    /// Bytes of the keys and values read by the scan.
+    pub bytes: u64,
+    /// Whether the scan got to the end of the column within the duration.
+    pub complete: bool,
+    pub keys_per_sec: f64,
+    pub mb_per_sec: f64,
+    /// Number of point reads, the sampled keys.
+    pub num_reads: usize,
+    /// Percentiles of the point read latencies in microseconds, empty if
+    /// there were no reads.
+    pub p50_us: Option<u64>,
+    pub p90_us: Option<u64>,
+    pub p99_us: Option<u64>,
+    pub max_us: Option<u64>,
+}
+
+impl Record for ColumnBench {
+    fn human(&self) -> String {
+        let mut line = format!(
+            "{}: {} keys, {} bytes{} at {:.0} keys/s, {:.1} MB/s",
+            self.column,
+            self.num_keys,
+            self.bytes,
+            if self.complete { "" } else { " (stopped early)" },
+            self.keys_per_sec,
+            self.mb_per_sec
+        );
+        if let (Some(p50), Some(p90), Some(p99), Some(max)) =
+            (self.p50_us, self.p90_us, self.p99_us, self.max_us)
+        {
+            line += &format!(
+                "; {} point reads p50 {}us, p90 {}us, p99 {}us, max {}us",
+                self.num_reads, p50, p90, p99, max
+            );
+        }
+        line
+    }
+}
+
+/// Returns the `p`th percentile of the sorted latencies.
+fn percentile(sorted: &[Duration], p: usize) -> Option<u64> {
+    let index = (sorted.len() * p / 100).min(sorted.len().checked_sub(1)?);
+    Some(sorted[index].as_micros() as u64)
+}
+
+/// Scans `col` from the start for at most `duration`, keeping a uniform
+/// sample of up to `sample` of the keys read, then reads the sampled keys in
+/// random order one by one and measures the latency of each read.
+pub(crate) fn bench_column(
+    store: &Store,
+    col: DBCol,
+    duration: Duration,
+    sample: usize,
+) -> anyhow::Result<ColumnBench> {
+    let mut rng = rand::thread_rng();
+    let mut keys: Vec<Box<[u8]>> = Vec::with_capacity(sample);
+    let mut num_keys = 0;
+    let mut bytes = 0;
+    let mut complete = true;
+    let start = Instant::now();
+    for (key, value) in store.iter(col) {
+        if start.elapsed() >= duration {
+            complete = false;
+            break;
+        }
+        num_keys += 1;
+        bytes += (key.len() + value.len()) as u64;
+        // Reservoir sampling, so that every key read is equally likely to be
+        // in the sample without knowing the number of keys in advance.
+        if keys.len() < sample {
+            keys.push(key);
+        } else {
+            let index = rng.gen_range(0, num_keys) as usize;
+            if index < sample {
+                keys[index] = key;
+            }
+        }
+    }
+    let seconds = start.elapsed().as_secs_f64();
+
+    keys.shuffle(&mut rng);
+    let mut latencies = Vec::with_capacity(keys.len());
+    for key in &keys {
+        let start = Instant::now();
+        store.get(col, key)?;
+        latencies.push(start.elapsed());
+    }
+    latencies.sort();
+
+    Ok(ColumnBench {
+        column: col.into(),
+        num_keys,
+        bytes,
+        complete,
+        keys_per_sec: num_keys as f64 / seconds,
+        mb_per_sec: bytes as f64 / 1e6 / seconds,
+        num_reads: latencies.len(),
+        p50_us: percentile(&latencies, 50),
+        p90_us: percentile(&latencies, 90),
+        p99_us: percentile(&latencies, 99),
+        max_us: percentile(&latencies, 100),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bench_column, percentile};
+    use near_store::test_utils::create_test_store;
+    use near_store::DBCol;
+    use std::time::Duration;
+
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<_> = (1..=10).map(Duration::from_micros).collect();
+        assert_eq!(percentile(&latencies, 50), Some(6));
+        assert_eq!(percentile(&latencies, 90), Some(10));
+        assert_eq!(percentile(&latencies, 100), Some(10));
+        assert_eq!(percentile(&latencies[..1], 99), Some(1));
+        assert_eq!(percentile(&[], 50), None);
+    }
+
+    #[test]
+    fn test_bench_column() {
+        let store = create_test_store();
+        let mut update = store.store_update();
+        for i in 0..100u32 {
+            update.set(DBCol::BlockMisc, &i.to_be_bytes(), b"value");
+        }
+        update.commit().unwrap();
+
+        let bench = bench_column(&store, DBCol::BlockMisc, Duration::from_secs(60), 10).unwrap();
+        assert_eq!(bench.column, "BlockMisc");
+        assert_eq!(bench.num_keys, 100);
+        assert_eq!(bench.bytes, 100 * 9);
+        assert!(bench.complete);
+        assert_eq!(bench.num_reads, 10);
+        assert!(bench.p50_us <= bench.p99_us && bench.p99_us <= bench.max_us);
+
+        let stopped = bench_column(&store, DBCol::BlockMisc, Duration::ZERO, 10).unwrap();
+        assert_eq!(stopped.num_keys, 0);
+        assert!(!stopped.complete);
+        assert_eq!((stopped.num_reads, stopped.p50_us), (0, None));
+
+        let empty = bench_column(&store, DBCol::Block, Duration::from_secs(60), 10).unwrap();
+        assert!(empty.complete);
+        assert_eq!(empty.num_keys, 0);
+    }
+}
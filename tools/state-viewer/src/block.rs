@@ -0,0 +1,148 @@
+use crate::output::Record;
+use near_primitives::block::{Block, BlockHeader, BlocksPerHeight};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_primitives::utils::index_to_bytes;
+use near_primitives::views::{BlockHeaderView, ChunkHeaderView};
+use near_store::{DBCol, Store};
+use serde::Serialize;
+use std::io::Write;
+
+/// A block as stored in the database.
+#[derive(Serialize, Debug)]
+pub(crate) struct BlockDump {
+    pub hash: CryptoHash,
+    /// The header, with the approvals of the previous block.
+    pub header: BlockHeaderView,
+    /// Headers of the chunks, `None` if only the header of the block is
+    /// stored, e.g. after garbage collection or header sync.
+    pub chunks: Option<Vec<ChunkHeaderView>>,
+}
+
+impl Record for BlockDump {
+    fn human(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Reads the block with the given hash, falling back to its header alone if
+/// the block itself isn't stored.
+pub(crate) fn read_block(store: &Store, hash: &CryptoHash) -> anyhow::Result<BlockDump> {
+    if let Some(block) = store.get_ser::<Block>(DBCol::Block, hash.as_ref())? {
+        let chunks = block.chunks().iter().cloned().map(ChunkHeaderView::from).collect();
+        return Ok(BlockDump {
+            hash: *hash,
+            header: block.header().clone().into(),
+            chunks: Some(chunks),
+        });
+    }
+    match store.get_ser::<BlockHeader>(DBCol::BlockHeader, hash.as_ref())? {
+        Some(header) => Ok(BlockDump { hash: *hash, header: header.into(), chunks: None }),
+        None => anyhow::bail!("block {} is not in the database", hash),
+    }
+}
+
+/// Returns hashes of all blocks of `blocks`, including forks, sorted.
+pub(crate) fn sorted_hashes(blocks: &BlocksPerHeight) -> Vec<CryptoHash> {
+    let mut hashes: Vec<_> = blocks.all_hashes().copied().collect();
+    hashes.sort();
+    hashes
+}
+
+/// Returns hashes of all blocks at `height`, including forks, sorted.  Empty
+/// if `BlockPerHeight` has no entry for the height.
+pub(crate) fn block_hashes_at_height(
+    store: &Store,
+    height: BlockHeight,
+) -> anyhow::Result<Vec<CryptoHash>> {
+    let blocks = store
+        .get_ser::<BlocksPerHeight>(DBCol::BlockPerHeight, &index_to_bytes(height))?
+        .unwrap_or_default();
+    Ok(sorted_hashes(&blocks))
+}
+
+/// Writes the block given by `hash`, or all blocks at `height`, as a pretty
+/// JSON array.
+pub(crate) fn print_blocks(
+    hash: Option<CryptoHash>,
+    height: Option<BlockHeight>,
+    store: &Store,
+    output: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let hashes = match (hash, height) {
+        (Some(hash), None) => vec![hash],
+        (None, Some(height)) => {
+            let hashes = block_hashes_at_height(store, height)?;
+            anyhow::ensure!(!hashes.is_empty(), "no blocks at height {}", height);
+            hashes
+        }
+        _ => anyhow::bail!("exactly one of hash and height must be given"),
+    };
+    let blocks =
+        hashes.iter().map(|hash| read_block(store, hash)).collect::<anyhow::Result<Vec<_>>>()?;
+    serde_json::to_writer_pretty(&mut *output, &blocks)?;
+    writeln!(output)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::print_blocks;
+    use crate::test_utils::setup_env;
+    use near_crypto::KeyType;
+    use near_primitives::block::BlocksPerHeight;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::utils::index_to_bytes;
+    use near_primitives::validator_signer::InMemoryValidatorSigner;
+    use near_store::DBCol;
+    use serde_json::Value;
+
+    #[test]
+    fn test_print_blocks() {
+        let mut setup = setup_env(&["test0", "test1"], 0);
+        let blocks = setup.produce_blocks(1..4);
+        let store = setup.store;
+
+        let print = |hash, height| -> anyhow::Result<Vec<Value>> {
+            let mut output = vec![];
+            print_blocks(hash, height, &store, &mut output)?;
+            Ok(serde_json::from_slice(&output).unwrap())
+        };
+
+        let block = &blocks[1];
+        let by_hash = print(Some(*block.hash()), None).unwrap();
+        assert_eq!(by_hash, print(None, Some(2)).unwrap());
+        assert_eq!(by_hash.len(), 1);
+        assert_eq!(by_hash[0]["hash"], block.hash().to_string());
+        assert_eq!(by_hash[0]["header"]["height"], 2);
+        assert_eq!(by_hash[0]["header"]["approvals"].as_array().unwrap().len(), 1);
+        assert_eq!(by_hash[0]["chunks"].as_array().unwrap().len(), block.chunks().len());
+        assert!(print(None, Some(10)).is_err());
+        assert!(print(Some(CryptoHash::hash_bytes(b"foo")), None).is_err());
+
+        // A fork at height 3 of which only the header is stored.
+        let mut fork = blocks[2].clone();
+        let signer =
+            InMemoryValidatorSigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+        fork.mut_header().get_mut().inner_lite.timestamp += 1;
+        fork.mut_header().resign(&signer);
+        let mut update = store.store_update();
+        update.set_ser(DBCol::BlockHeader, fork.hash().as_ref(), fork.header()).unwrap();
+        let mut per_height = BlocksPerHeight::default();
+        per_height.insert(fork.header().epoch_id().clone(), *blocks[2].hash());
+        per_height.insert(fork.header().epoch_id().clone(), *fork.hash());
+        update.set_ser(DBCol::BlockPerHeight, &index_to_bytes(3), &per_height).unwrap();
+        update.commit().unwrap();
+
+        let at_height = print(None, Some(3)).unwrap();
+        assert_eq!(at_height.len(), 2);
+        for dump in at_height {
+            if dump["hash"] == fork.hash().to_string() {
+                assert!(dump["chunks"].is_null());
+            } else {
+                assert_eq!(dump["hash"], blocks[2].hash().to_string());
+                assert!(dump["chunks"].is_array());
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Which blocks the entries of a column belong to and when garbage
+//! collection removes them.
+//!
+//! Commands which need to know how to get from a column entry to its block
+//! (e.g. to tell whether the entry survived the garbage collection of its
+//! block) should use [`COLUMN_BLOCK_REFS`] rather than hard-coding key layouts.
+
+use crate::gc_info::Tails;
+use borsh::BorshDeserialize;
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::{PartialEncodedChunk, ShardChunk};
+use near_primitives::transaction::ExecutionOutcomeWithIdAndProof;
+use near_primitives::types::BlockHeight;
+use near_primitives::utils::bytes_to_index;
+use near_store::DBCol;
+use std::io;
+
+/// How to find the blocks an entry of a column refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BlockRef {
+    /// The key contains the block hash at the given offset.
+    Key(usize),
+    /// The value is a list of execution outcomes, each with its block hash.
+    Outcomes,
+    /// The key is the height of the blocks.
+    Height,
+    /// The key is the height the chunks were created at.
+    ChunkHeight,
+    /// The value is a `ShardChunk`, referring to the height it was created at.
+    Chunk,
+    /// The value is a `PartialEncodedChunk`, referring to the height it was
+    /// created at.
+    PartialChunk,
+}
+
+/// What an entry of a column refers to.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Referenced {
+    Blocks(Vec<CryptoHash>),
+    Height(BlockHeight),
+}
+
+/// Which heights garbage collection removes the entries of a column at,
+/// compared to the tails it stores in `BlockMisc`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GcBoundary {
+    /// Collecting a height removes the data of the block before it, so the
+    /// entries below the tail are removed and those of the tail block stay.
+    BelowTail,
+    /// Removed together with the block at the height being collected, i.e. at
+    /// or below the tail.  The genesis block's entries are never removed.
+    AtTail,
+    /// Removed below the chunk tail, independently of the blocks.
+    BelowChunkTail,
+}
+
+impl GcBoundary {
+    /// Whether garbage collection has removed the entries of `height`.
+    pub(crate) fn collects(
+        self,
+        height: BlockHeight,
+        tails: &Tails,
+        genesis_height: BlockHeight,
+    ) -> bool {
+        match self {
+            Self::BelowTail => height < tails.tail,
+            Self::AtTail => height > genesis_height && height <= tails.tail,
+            Self::BelowChunkTail => height < tails.chunk_tail,
+        }
+    }
+}
+
+/// Columns whose entries are garbage collected by the height of the block or
+/// chunk they refer to.
+pub(crate) const COLUMN_BLOCK_REFS: &[(DBCol, BlockRef, GcBoundary)] = &[
+    (DBCol::Block, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::ChunkExtra, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::TransactionResult, BlockRef::Outcomes, GcBoundary::BelowTail),
+    (DBCol::OutgoingReceipts, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::IncomingReceipts, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::BlockInfo, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::Chunks, BlockRef::Chunk, GcBoundary::BelowChunkTail),
+    (DBCol::PartialChunks, BlockRef::PartialChunk, GcBoundary::BelowChunkTail),
+    (DBCol::BlocksToCatchup, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::StateDlInfos, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::ChallengedBlocks, BlockRef::Key(0), GcBoundary::BelowTail),
+    // `StateHeaderKey` is the shard id followed by the block hash.
+    (DBCol::StateHeaders, BlockRef::Key(8), GcBoundary::BelowTail),
+    (DBCol::BlockExtra, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::StateParts, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::NextBlockHashes, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::StateChanges, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::BlockRefCount, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::TrieChanges, BlockRef::Key(0), GcBoundary::AtTail),
+    (DBCol::OutcomeIds, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::StateChangesForSplitStates, BlockRef::Key(0), GcBoundary::BelowTail),
+    (DBCol::BlockPerHeight, BlockRef::Height, GcBoundary::BelowTail),
+    (DBCol::ChunkHashesByHeight, BlockRef::ChunkHeight, GcBoundary::BelowChunkTail),
+    (DBCol::HeaderHashesByHeight, BlockRef::ChunkHeight, GcBoundary::BelowChunkTail),
+];
+
+/// Returns how entries of `col` refer to blocks, `None` if they don't.
+pub(crate) fn block_ref(col: DBCol) -> Option<(BlockRef, GcBoundary)> {
+    COLUMN_BLOCK_REFS.iter().find(|(c, _, _)| *c == col).map(|(_, block_ref, gc)| (*block_ref, *gc))
+}
+
+impl BlockRef {
+    /// Returns the blocks or the height the entry refers to.
+    pub(crate) fn referenced(&self, key: &[u8], value: &[u8]) -> io::Result<Referenced> {
+        Ok(match self {
+            Self::Key(offset) => {
+                let bytes = key.get(*offset..*offset + 32).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "key too short for a block hash")
+                })?;
+                Referenced::Blocks(vec![CryptoHash::try_from(bytes)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?])
+            }
+            Self::Outcomes => Referenced::Blocks(
+                Vec::<ExecutionOutcomeWithIdAndProof>::try_from_slice(value)?
+                    .into_iter()
+                    .map(|outcome| outcome.block_hash)
+                    .collect(),
+            ),
+            Self::Height | Self::ChunkHeight => Referenced::Height(
+                bytes_to_index(key)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?,
+            ),
+            Self::Chunk => Referenced::Height(ShardChunk::try_from_slice(value)?.height_created()),
+            Self::PartialChunk => {
+                Referenced::Height(PartialEncodedChunk::try_from_slice(value)?.height_created())
+            }
+        })
+    }
+}
@@ -0,0 +1,174 @@
+use crate::block::block_hashes_at_height;
+use near_primitives::block::{BlockHeader, Tip};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_store::{DBCol, Store, HEAD_KEY, TAIL_KEY};
+use std::fmt;
+
+/// An inconsistency found on the canonical chain.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ChainIssue {
+    /// The header of a block on the chain isn't stored, so the chain can't be
+    /// walked any further.  `child_height` is the height of the block
+    /// referring to it, `None` for the head.
+    MissingHeader { hash: CryptoHash, child_height: Option<BlockHeight> },
+    /// The block above the tail has a header but no body.
+    MissingBlock { height: BlockHeight, hash: CryptoHash },
+    /// `BlockPerHeight` doesn't list the block at its height.
+    MissingBlockPerHeight { height: BlockHeight, hash: CryptoHash },
+    /// The previous block isn't below the block referring to it.
+    HeightNotDecreasing { height: BlockHeight, hash: CryptoHash, prev_height: BlockHeight },
+}
+
+impl fmt::Display for ChainIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainIssue::MissingHeader { hash, child_height: Some(child_height) } => write!(
+                f,
+                "header {} of the previous block of height {} is missing",
+                hash, child_height
+            ),
+            ChainIssue::MissingHeader { hash, child_height: None } => {
+                write!(f, "header {} of the head is missing", hash)
+            }
+            ChainIssue::MissingBlock { height, hash } => {
+                write!(f, "block {} at height {} is missing", hash, height)
+            }
+            ChainIssue::MissingBlockPerHeight { height, hash } => {
+                write!(f, "block {} at height {} is missing from BlockPerHeight", hash, height)
+            }
+            ChainIssue::HeightNotDecreasing { height, hash, prev_height } => write!(
+                f,
+                "block {} at height {} has its previous block at height {}",
+                hash, height, prev_height
+            ),
+        }
+    }
+}
+
+/// Result of walking the canonical chain.
+#[derive(Debug, Default)]
+pub(crate) struct ChainCheck {
+    /// Number of blocks above the tail which were checked.
+    pub num_blocks: u64,
+    pub issues: Vec<ChainIssue>,
+}
+
+/// Walks the canonical chain backwards from the head down to the tail, and
+/// checks that every previous block is stored, that heights strictly decrease
+/// and that `BlockPerHeight` lists each block.  Blocks at and below the tail
+/// are garbage collected, so only their headers are expected.
+///
+/// Stops at the first issue unless `keep_going` is set.  The walk can't go on
+/// past a missing header either way.
+pub(crate) fn check_chain(
+    store: &Store,
+    genesis_height: BlockHeight,
+    keep_going: bool,
+) -> anyhow::Result<ChainCheck> {
+    let head = store
+        .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+        .ok_or_else(|| anyhow::anyhow!("the database has no head"))?;
+    let tail = store.get_ser::<BlockHeight>(DBCol::BlockMisc, TAIL_KEY)?.unwrap_or(genesis_height);
+
+    let mut check = ChainCheck::default();
+    let mut hash = head.last_block_hash;
+    let mut child_height = None;
+    loop {
+        let header = match store.get_ser::<BlockHeader>(DBCol::BlockHeader, hash.as_ref())? {
+            Some(header) => header,
+            None => {
+                check.issues.push(ChainIssue::MissingHeader { hash, child_height });
+                break;
+            }
+        };
+        let height = header.height();
+        if height <= tail {
+            break;
+        }
+        check.num_blocks += 1;
+
+        if !store.exists(DBCol::Block, hash.as_ref())? {
+            check.issues.push(ChainIssue::MissingBlock { height, hash });
+        }
+        if !block_hashes_at_height(store, height)?.contains(&hash) {
+            check.issues.push(ChainIssue::MissingBlockPerHeight { height, hash });
+        }
+        if let Some(prev_header) =
+            store.get_ser::<BlockHeader>(DBCol::BlockHeader, header.prev_hash().as_ref())?
+        {
+            if prev_header.height() >= height {
+                check.issues.push(ChainIssue::HeightNotDecreasing {
+                    height,
+                    hash,
+                    prev_height: prev_header.height(),
+                });
+                // Following the previous block could loop forever.
+                break;
+            }
+        }
+        if !keep_going && !check.issues.is_empty() {
+            break;
+        }
+        child_height = Some(height);
+        hash = *header.prev_hash();
+    }
+    Ok(check)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_chain, ChainIssue};
+    use crate::test_utils::setup_env;
+    use near_primitives::utils::index_to_bytes;
+    use near_store::{DBCol, TAIL_KEY};
+
+    #[test]
+    fn test_check_chain() {
+        let mut setup = setup_env(&["test0", "test1"], 0);
+        let genesis_height = setup.env.clients[0].chain.genesis().height();
+        let blocks = setup.produce_blocks(1..6);
+        let store = setup.store;
+
+        let check = check_chain(&store, genesis_height, false).unwrap();
+        assert_eq!(check.num_blocks, 5);
+        assert_eq!(check.issues, vec![]);
+
+        let per_height_4 = store.get(DBCol::BlockPerHeight, &index_to_bytes(4)).unwrap().unwrap();
+        let mut update = store.store_update();
+        update.delete(DBCol::Block, blocks[1].hash().as_ref());
+        update.delete(DBCol::BlockPerHeight, &index_to_bytes(4));
+        update.commit().unwrap();
+        let missing_block = ChainIssue::MissingBlock { height: 2, hash: *blocks[1].hash() };
+        let missing_per_height =
+            ChainIssue::MissingBlockPerHeight { height: 4, hash: *blocks[3].hash() };
+
+        // Stops at the first issue from the head.
+        let check = check_chain(&store, genesis_height, false).unwrap();
+        assert_eq!(check.num_blocks, 2);
+        assert_eq!(check.issues, vec![missing_per_height]);
+        let check = check_chain(&store, genesis_height, true).unwrap();
+        assert_eq!(check.num_blocks, 5);
+        assert_eq!(check.issues.len(), 2);
+        assert_eq!(check.issues[1], missing_block);
+
+        // Blocks at and below the tail are garbage collected.
+        let mut update = store.store_update();
+        update.set_ser(DBCol::BlockMisc, TAIL_KEY, &2u64).unwrap();
+        update.set(DBCol::BlockPerHeight, &index_to_bytes(4), &per_height_4);
+        update.commit().unwrap();
+        let check = check_chain(&store, genesis_height, true).unwrap();
+        assert_eq!(check.num_blocks, 3);
+        assert_eq!(check.issues, vec![]);
+
+        // Headers are never garbage collected.
+        let mut update = store.store_update();
+        update.delete(DBCol::BlockHeader, blocks[1].hash().as_ref());
+        update.commit().unwrap();
+        let check = check_chain(&store, genesis_height, true).unwrap();
+        assert_eq!(
+            check.issues,
+            vec![ChainIssue::MissingHeader { hash: *blocks[1].hash(), child_height: Some(3) }]
+        );
+    }
+}
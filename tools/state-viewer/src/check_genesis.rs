@@ -0,0 +1,170 @@
+use ansi_term::Color::{Green, Red};
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_chain_configs::{Genesis, GenesisConfig, GenesisJsonHasher, GenesisRecords};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, ProtocolVersion};
+use near_primitives::utils::{from_timestamp, to_timestamp};
+use near_store::{get_genesis_hash, Store};
+use std::path::Path;
+
+/// Top-level sections of a genesis, see `GenesisSummary::differences`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GenesisSummary {
+    /// Same as `Genesis::json_hash`.
+    pub json_hash: CryptoHash,
+    pub chain_id: String,
+    /// Genesis time in nanoseconds, like block timestamps.
+    pub genesis_timestamp: u64,
+    pub genesis_height: BlockHeight,
+    pub protocol_version: ProtocolVersion,
+    pub num_records: u64,
+}
+
+impl GenesisSummary {
+    /// Goes over the records one by one, so `genesis` may have them in a
+    /// records file of any size.
+    pub fn new(genesis: &Genesis) -> Self {
+        let mut hasher = GenesisJsonHasher::new();
+        hasher.process_config(&genesis.config);
+        let mut num_records = 0;
+        genesis.for_each_record(|record| {
+            hasher.process_record(record);
+            num_records += 1;
+        });
+        let config = &genesis.config;
+        Self {
+            json_hash: hasher.finalize(),
+            chain_id: config.chain_id.clone(),
+            genesis_timestamp: to_timestamp(config.genesis_time),
+            genesis_height: config.genesis_height,
+            protocol_version: config.protocol_version,
+            num_records,
+        }
+    }
+
+    /// Reads a genesis file without loading its records into memory.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let config = GenesisConfig::from_file(path)?;
+        // Records are streamed from the `records` field of the same file.
+        let genesis =
+            Genesis { config, records: GenesisRecords(vec![]), records_file: path.to_path_buf() };
+        Ok(Self::new(&genesis))
+    }
+
+    /// Names of the sections which differ, with the values of `self` and
+    /// `other`.
+    pub fn differences(&self, other: &Self) -> Vec<(&'static str, String, String)> {
+        let mut differences = vec![];
+        let mut check = |name, this: String, other: String| {
+            if this != other {
+                differences.push((name, this, other));
+            }
+        };
+        check("chain_id", self.chain_id.clone(), other.chain_id.clone());
+        check(
+            "genesis_time",
+            from_timestamp(self.genesis_timestamp).to_rfc3339(),
+            from_timestamp(other.genesis_timestamp).to_rfc3339(),
+        );
+        check("genesis_height", self.genesis_height.to_string(), other.genesis_height.to_string());
+        check(
+            "protocol_version",
+            self.protocol_version.to_string(),
+            other.protocol_version.to_string(),
+        );
+        check("records", self.num_records.to_string(), other.num_records.to_string());
+        differences
+    }
+}
+
+fn print_differences(differences: &[(&str, String, String)], this: &str, other: &str) {
+    for (name, this_value, other_value) in differences {
+        println!("  {}: {} in {}, {} in {}", name, this_value, this, other_value, other);
+    }
+}
+
+/// Compares the genesis in `genesis_path` with the one the database was
+/// initialized with.  The database only keeps the genesis hash, so on a
+/// mismatch the sections are compared with the genesis block and the genesis
+/// in the node's config.
+pub(crate) fn check_genesis(
+    genesis_path: &Path,
+    node_genesis: &Genesis,
+    store: Store,
+    chain_store: &mut ChainStore,
+) {
+    let file = GenesisSummary::from_file(genesis_path)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {:#}", genesis_path.display(), err));
+    let stored_hash = get_genesis_hash(&store).unwrap();
+    println!("Genesis hash of {}: {}", genesis_path.display(), file.json_hash);
+    match stored_hash {
+        Some(stored_hash) => println!("Genesis hash in the database: {}", stored_hash),
+        None => println!("Genesis hash in the database: {}", Red.bold().paint("MISSING")),
+    }
+    if stored_hash == Some(file.json_hash) {
+        println!("{}", Green.paint("Genesis matches the database"));
+        return;
+    }
+    println!("{}", Red.bold().paint("Genesis doesn't match the database"));
+
+    match chain_store.get_block_hash_by_height(file.genesis_height) {
+        Ok(block_hash) => {
+            let header = chain_store.get_block_header(&block_hash).unwrap();
+            let block = GenesisSummary {
+                genesis_timestamp: header.raw_timestamp(),
+                protocol_version: header.latest_protocol_version(),
+                ..file.clone()
+            };
+            let differences = file.differences(&block);
+            if differences.is_empty() {
+                println!("Genesis block {} agrees with the file", block_hash);
+            } else {
+                println!("Genesis block {} differs from the file:", block_hash);
+                print_differences(&differences, "the file", "the block");
+            }
+        }
+        Err(_) => println!("No block at genesis height {} in the database", file.genesis_height),
+    }
+
+    let node = GenesisSummary::new(node_genesis);
+    if node.json_hash == file.json_hash {
+        println!("The node's config uses the same genesis as the file");
+    } else if stored_hash == Some(node.json_hash) {
+        println!(
+            "The node's config uses the genesis of the database, which differs from the file:"
+        );
+        print_differences(&file.differences(&node), "the file", "the config");
+    } else {
+        println!("The node's config uses yet another genesis, which differs from the file:");
+        print_differences(&file.differences(&node), "the file", "the config");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GenesisSummary;
+    use near_chain_configs::Genesis;
+    use nearcore::config::GenesisExt;
+
+    #[test]
+    fn test_summary_from_file() {
+        let genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+        let file = tempfile::NamedTempFile::new().unwrap();
+        genesis.to_file(file.path());
+
+        let summary = GenesisSummary::from_file(file.path()).unwrap();
+        assert_eq!(summary, GenesisSummary::new(&genesis));
+        assert_eq!(summary.json_hash, genesis.json_hash());
+        assert_eq!(summary.num_records, genesis.records.0.len() as u64);
+        assert!(summary.differences(&GenesisSummary::new(&genesis)).is_empty());
+
+        let mut other = genesis.clone();
+        other.config.chain_id = "other".to_string();
+        other.records.0.pop();
+        let other = GenesisSummary::new(&other);
+        assert_ne!(summary.json_hash, other.json_hash);
+        let names: Vec<_> =
+            summary.differences(&other).into_iter().map(|(name, _, _)| name).collect();
+        assert_eq!(names, vec!["chain_id", "records"]);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::block::block_hashes_at_height;
+use crate::output::Record;
+use near_primitives::block::Block;
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::{ChunkHash, ReceiptProof, ShardChunk, ShardChunkHeader};
+use near_primitives::types::{BlockHeight, ShardId};
+use near_primitives::utils::get_block_shard_id;
+use near_primitives::views::{ChunkHeaderView, ReceiptView, SignedTransactionView};
+use near_store::{DBCol, Store};
+use serde::Serialize;
+use std::ops::RangeInclusive;
+
+/// A chunk as stored in the database.  The outgoing receipts root is part of
+/// the header.  `height_included` isn't set in the header stored with the
+/// chunk, only in the header from a block.
+#[derive(Serialize, Debug)]
+pub(crate) struct ChunkDump {
+    pub hash: ChunkHash,
+    pub header: ChunkHeaderView,
+    pub transactions: Vec<SignedTransactionView>,
+    /// Receipts produced by the previous chunk of the shard, which the
+    /// outgoing receipts root in the header commits to.
+    pub outgoing_receipts: Vec<ReceiptView>,
+    /// Receipts applied in the chunk, only known when the chunk is read
+    /// through the block including it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incoming_receipts: Option<Vec<ReceiptView>>,
+}
+
+impl Record for ChunkDump {
+    fn human(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+pub(crate) fn read_chunk(store: &Store, chunk_hash: &ChunkHash) -> anyhow::Result<ChunkDump> {
+    let chunk = store
+        .get_ser::<ShardChunk>(DBCol::Chunks, chunk_hash.as_ref())?
+        .ok_or_else(|| anyhow::anyhow!("chunk {} is not in the database", chunk_hash.0))?;
+    Ok(ChunkDump {
+        hash: chunk_hash.clone(),
+        header: chunk.cloned_header().into(),
+        transactions: chunk.transactions().iter().cloned().map(Into::into).collect(),
+        outgoing_receipts: chunk.receipts().iter().cloned().map(Into::into).collect(),
+        incoming_receipts: None,
+    })
+}
+
+/// Reads the receipts applied to the chunk of `shard_id` in the block, i.e.
+/// the incoming receipts of the shard, `None` if they aren't stored.
+pub(crate) fn read_incoming_receipts(
+    store: &Store,
+    block_hash: &CryptoHash,
+    shard_id: ShardId,
+) -> anyhow::Result<Option<Vec<ReceiptView>>> {
+    let proofs = store.get_ser::<Vec<ReceiptProof>>(
+        DBCol::IncomingReceipts,
+        &get_block_shard_id(block_hash, shard_id),
+    )?;
+    Ok(proofs.map(|proofs| proofs.into_iter().flat_map(|proof| proof.0).map(Into::into).collect()))
+}
+
+/// Returns the header of the chunk of `shard_id` in the block, which is the
+/// last chunk of the shard if the block doesn't include a new one.
+pub(crate) fn chunk_header_in_block(
+    store: &Store,
+    block_hash: &CryptoHash,
+    shard_id: ShardId,
+) -> anyhow::Result<ShardChunkHeader> {
+    let block = store
+        .get_ser::<Block>(DBCol::Block, block_hash.as_ref())?
+        .ok_or_else(|| anyhow::anyhow!("block {} is not in the database", block_hash))?;
+    let chunks = block.chunks();
+    let chunk_header = chunks.get(shard_id as usize).ok_or_else(|| {
+        anyhow::anyhow!("block {} has {} shards, no shard {}", block_hash, chunks.len(), shard_id)
+    })?;
+    Ok(chunk_header.clone())
+}
+
+/// A chunk which a block includes but whose body isn't stored.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct MissingChunk {
+    pub height: BlockHeight,
+    pub block_hash: CryptoHash,
+    pub shard_id: ShardId,
+    pub chunk_hash: ChunkHash,
+    /// Whether the parts of the chunk the node owns are stored, as they are
+    /// on nodes which don't track the shard.
+    pub has_partial_chunk: bool,
+}
+
+impl Record for MissingChunk {
+    fn human(&self) -> String {
+        format!(
+            "Block {} at height {} has chunk {} of shard {} but it is not stored{}",
+            self.block_hash,
+            self.height,
+            self.chunk_hash.0,
+            self.shard_id,
+            if self.has_partial_chunk { ", its partial chunk is" } else { "" }
+        )
+    }
+}
+
+/// Finds the chunks which blocks at `heights` have set in their chunk mask but
+/// which aren't stored in `Chunks`.  Heights without blocks and blocks of
+/// which only the header is stored are skipped.
+pub(crate) fn find_missing_chunks(
+    store: &Store,
+    heights: RangeInclusive<BlockHeight>,
+) -> anyhow::Result<Vec<MissingChunk>> {
+    let mut missing = vec![];
+    for height in heights {
+        for block_hash in block_hashes_at_height(store, height)? {
+            let block = match store.get_ser::<Block>(DBCol::Block, block_hash.as_ref())? {
+                Some(block) => block,
+                None => continue,
+            };
+            let chunk_mask = block.header().chunk_mask();
+            for (shard_id, chunk_header) in block.chunks().iter().enumerate() {
+                if !chunk_mask.get(shard_id).copied().unwrap_or(false) {
+                    continue;
+                }
+                let chunk_hash = chunk_header.chunk_hash();
+                if store.exists(DBCol::Chunks, chunk_hash.as_ref())? {
+                    continue;
+                }
+                missing.push(MissingChunk {
+                    height,
+                    block_hash,
+                    shard_id: shard_id as ShardId,
+                    has_partial_chunk: store.exists(DBCol::PartialChunks, chunk_hash.as_ref())?,
+                    chunk_hash,
+                });
+            }
+        }
+    }
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{chunk_header_in_block, find_missing_chunks, read_chunk, read_incoming_receipts};
+    use crate::test_utils::setup_env;
+    use near_store::DBCol;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_chunk() {
+        let mut setup = setup_env(&["test0", "test1"], 0);
+        let tx = setup.send_money(1, "test0", "test1");
+        let blocks: HashMap<u64, _> = (1..5).zip(setup.produce_blocks(1..5)).collect();
+        let store = setup.store;
+
+        // The transaction is included in the chunk of height 2 and its
+        // receipt is applied in the next one.
+        let chunk_header = chunk_header_in_block(&store, blocks[&2].hash(), 0).unwrap();
+        assert_eq!(chunk_header.height_included(), 2);
+        let chunk = read_chunk(&store, &chunk_header.chunk_hash()).unwrap();
+        assert_eq!(chunk.header.height_included, 0);
+        assert_eq!(chunk.header.height_created, 2);
+        assert_eq!(chunk.transactions.len(), 1);
+        assert_eq!(chunk.transactions[0].hash, tx.get_hash());
+        // The chunk of height 3 carries the receipt as its outgoing receipt
+        // and, as there is a single shard, also applies it.
+        let chunk = read_chunk(&store, &blocks[&3].chunks()[0].chunk_hash()).unwrap();
+        assert_eq!(chunk.outgoing_receipts.len(), 1);
+        assert_eq!(chunk.outgoing_receipts[0].predecessor_id.as_ref(), "test0");
+        assert_eq!(chunk.incoming_receipts, None);
+        let incoming = read_incoming_receipts(&store, blocks[&3].hash(), 0).unwrap().unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].receipt_id, chunk.outgoing_receipts[0].receipt_id);
+        assert_eq!(read_incoming_receipts(&store, blocks[&3].hash(), 1).unwrap(), None);
+        let err = chunk_header_in_block(&store, blocks[&2].hash(), 1).unwrap_err();
+        assert!(err.to_string().contains("has 1 shards, no shard 1"), "{}", err);
+
+        assert_eq!(find_missing_chunks(&store, 0..=10).unwrap(), vec![]);
+        let missing_hash = blocks[&3].chunks()[0].chunk_hash();
+        let mut update = store.store_update();
+        update.delete(DBCol::Chunks, missing_hash.as_ref());
+        update.commit().unwrap();
+        let missing = find_missing_chunks(&store, 0..=10).unwrap();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].height, 3);
+        assert_eq!(missing[0].chunk_hash, missing_hash);
+        assert!(missing[0].has_partial_chunk);
+    }
+}
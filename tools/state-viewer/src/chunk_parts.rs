@@ -0,0 +1,212 @@
+use ansi_term::Color::{Green, Red};
+use near_chain::{ChainStore, ChainStoreAccess, Error, RuntimeAdapter};
+use near_primitives::account::id::AccountId;
+use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
+use near_primitives::types::BlockHeight;
+
+/// Parts of an encoded chunk stored by the node.
+pub(crate) struct ChunkParts {
+    pub header: ShardChunkHeader,
+    /// Size of each part by ordinal, `None` if the part isn't stored.
+    pub part_sizes: Vec<Option<usize>>,
+    /// Owner of each part by ordinal.
+    pub owners: Vec<AccountId>,
+    /// Number of parts needed to reconstruct the chunk.
+    pub num_data_parts: usize,
+    /// Whether the whole chunk is stored, from which all parts can be encoded.
+    pub has_full_chunk: bool,
+}
+
+impl ChunkParts {
+    pub fn num_stored(&self) -> usize {
+        self.part_sizes.iter().filter(|size| size.is_some()).count()
+    }
+
+    pub fn num_missing(&self) -> usize {
+        self.part_sizes.len() - self.num_stored()
+    }
+
+    pub fn can_reconstruct(&self) -> bool {
+        self.has_full_chunk || self.num_stored() >= self.num_data_parts
+    }
+}
+
+/// Checks whether the whole chunk is stored and reads its parts from the
+/// partial chunk storage.  Chunks the node only has whole are reported with no
+/// parts, but as reconstructable.
+pub(crate) fn get_chunk_parts(
+    chain_store: &mut ChainStore,
+    runtime_adapter: &dyn RuntimeAdapter,
+    chunk_hash: &ChunkHash,
+) -> Result<ChunkParts, Error> {
+    let num_total_parts = runtime_adapter.num_total_parts();
+    let mut part_sizes = vec![None; num_total_parts];
+    let full_chunk_header = chain_store.get_chunk(chunk_hash).map(|chunk| chunk.cloned_header());
+    let has_full_chunk = full_chunk_header.is_ok();
+    let header = match chain_store.get_partial_chunk(chunk_hash) {
+        Ok(partial_chunk) => {
+            for part in partial_chunk.parts() {
+                if let Some(size) = part_sizes.get_mut(part.part_ord as usize) {
+                    *size = Some(part.part.len());
+                }
+            }
+            partial_chunk.cloned_header()
+        }
+        Err(_) => full_chunk_header?,
+    };
+    let owners = (0..num_total_parts as u64)
+        .map(|part_ord| runtime_adapter.get_part_owner(header.prev_block_hash_ref(), part_ord))
+        .collect::<Result<_, _>>()?;
+    Ok(ChunkParts {
+        header,
+        part_sizes,
+        owners,
+        num_data_parts: runtime_adapter.num_data_parts(),
+        has_full_chunk,
+    })
+}
+
+fn reconstruct_status(parts: &ChunkParts) -> String {
+    if parts.has_full_chunk {
+        Green.paint("stored whole").to_string()
+    } else if parts.can_reconstruct() {
+        Green.paint("can reconstruct").to_string()
+    } else {
+        Red.bold().paint("cannot reconstruct").to_string()
+    }
+}
+
+pub(crate) fn print_chunk_parts(
+    chunk_hash: ChunkHash,
+    chain_store: &mut ChainStore,
+    runtime_adapter: &dyn RuntimeAdapter,
+) {
+    let parts = get_chunk_parts(chain_store, runtime_adapter, &chunk_hash).unwrap();
+    println!(
+        "Chunk {:?} of shard {} created at height {}, prev block {}",
+        chunk_hash.0,
+        parts.header.shard_id(),
+        parts.header.height_created(),
+        parts.header.prev_block_hash()
+    );
+    println!(
+        "Stored {} of {} parts, {} needed: {}",
+        parts.num_stored(),
+        parts.part_sizes.len(),
+        parts.num_data_parts,
+        reconstruct_status(&parts)
+    );
+    println!("{: >5} {: >10} owner", "part", "size");
+    for (part_ord, (size, owner)) in parts.part_sizes.iter().zip(&parts.owners).enumerate() {
+        match size {
+            Some(size) => println!("{: >5} {: >10} {}", part_ord, size, owner),
+            None => println!("{: >5} {: >10} {}", part_ord, Red.bold().paint("MISSING"), owner),
+        }
+    }
+}
+
+/// Prints the number of missing parts of every chunk created at a height in
+/// the range, including chunks which never made it into a block.
+pub(crate) fn print_missing_parts_by_height(
+    start_height: BlockHeight,
+    end_height: BlockHeight,
+    chain_store: &mut ChainStore,
+    runtime_adapter: &dyn RuntimeAdapter,
+) {
+    let mut total_chunks = 0;
+    let mut total_missing = 0;
+    let mut unreconstructable = 0;
+    for height in start_height..=end_height {
+        let mut chunk_hashes: Vec<_> =
+            chain_store.get_all_chunk_hashes_by_height(height).unwrap().into_iter().collect();
+        chunk_hashes.sort_by_key(|chunk_hash| chunk_hash.0);
+        let mut height_missing = 0;
+        for chunk_hash in &chunk_hashes {
+            let parts = match get_chunk_parts(chain_store, runtime_adapter, chunk_hash) {
+                Ok(parts) => parts,
+                Err(err) => {
+                    println!(
+                        "{: >10} {:?}: {}",
+                        height,
+                        chunk_hash.0,
+                        Red.bold().paint(err.to_string())
+                    );
+                    continue;
+                }
+            };
+            if parts.num_missing() > 0 {
+                println!(
+                    "{: >10} {:?} shard {}: missing {} of {} parts, {}",
+                    height,
+                    chunk_hash.0,
+                    parts.header.shard_id(),
+                    parts.num_missing(),
+                    parts.part_sizes.len(),
+                    reconstruct_status(&parts)
+                );
+            }
+            height_missing += parts.num_missing();
+            if !parts.can_reconstruct() {
+                unreconstructable += 1;
+            }
+        }
+        if !chunk_hashes.is_empty() {
+            println!(
+                "{: >10} {} chunks, {} missing parts",
+                height,
+                chunk_hashes.len(),
+                height_missing
+            );
+        }
+        total_chunks += chunk_hashes.len();
+        total_missing += height_missing;
+    }
+    println!(
+        "Heights {}..={}: {} chunks, {} missing parts, {} chunks cannot be reconstructed",
+        start_height, end_height, total_chunks, total_missing, unreconstructable
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::setup_env;
+    use near_chain::{ChainStore, RuntimeAdapter};
+    use near_store::DBCol;
+
+    #[test]
+    fn test_get_chunk_parts() {
+        let mut setup = setup_env(&["test0"], 3);
+        let runtime = setup.runtime.clone();
+        let genesis_height = setup.genesis.config.genesis_height;
+        let mut chain_store = ChainStore::new(setup.store.clone(), genesis_height, false);
+
+        let block = setup.env.clients[0].chain.get_block_by_height(3).unwrap().clone();
+        let chunk_hash = block.chunks()[0].chunk_hash();
+        let parts =
+            super::get_chunk_parts(&mut chain_store, runtime.as_ref(), &chunk_hash).unwrap();
+        assert_eq!(parts.header.chunk_hash(), chunk_hash);
+        assert_eq!(parts.part_sizes.len(), runtime.num_total_parts());
+        assert_eq!(parts.num_missing(), 0);
+        assert!(parts.can_reconstruct());
+        assert!(parts.owners.iter().all(|owner| owner.as_ref() == "test0"));
+        assert!(parts.has_full_chunk);
+
+        // Without the parts the node can still encode them from the chunk.
+        let store = setup.store;
+        let mut update = store.store_update();
+        update.delete(DBCol::PartialChunks, chunk_hash.as_ref());
+        update.commit().unwrap();
+        let mut chain_store = ChainStore::new(store.clone(), genesis_height, false);
+        let parts =
+            super::get_chunk_parts(&mut chain_store, runtime.as_ref(), &chunk_hash).unwrap();
+        assert_eq!(parts.header.chunk_hash(), chunk_hash);
+        assert_eq!(parts.num_stored(), 0);
+        assert!(parts.can_reconstruct());
+
+        let mut update = store.store_update();
+        update.delete(DBCol::Chunks, chunk_hash.as_ref());
+        update.commit().unwrap();
+        let mut chain_store = ChainStore::new(store, genesis_height, false);
+        assert!(super::get_chunk_parts(&mut chain_store, runtime.as_ref(), &chunk_hash).is_err());
+    }
+}
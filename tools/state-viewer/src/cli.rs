@@ -1,16 +1,49 @@
+use crate::archive::{export_archive, import_archive};
+use crate::bench::bench_column;
+use crate::block::{block_hashes_at_height, print_blocks, read_block};
+use crate::block_refs::block_ref;
+use crate::check_chain::check_chain;
+use crate::chunk::{
+    chunk_header_in_block, find_missing_chunks, read_chunk, read_incoming_receipts,
+};
+use crate::column_stats::{column_stats, print_table};
+use crate::column_values::decode_key;
 use crate::commands::*;
+use crate::db_diff::{diff_column, Difference};
+use crate::dump_column::{dump_column, entry_to_json};
 use crate::epoch_info;
+use crate::find::{find_keys, parse_pattern, FoundKey};
+use crate::follow::follow_head;
+use crate::gc_info::{count_epochs, find_leaks, read_tails, GcInfo};
+use crate::height_gaps::find_gaps;
+use crate::light_proof::print_light_proof;
+use crate::orphans::print_orphans;
+use crate::output::{Output, OutputFormat};
+use crate::raw::{ensure_unlocked, raw_set};
+use crate::repl::run_repl;
 use crate::rocksdb_stats::get_rocksdb_stats;
+use crate::tx_lookup::{lookup_receipt, lookup_transaction, trace_transaction};
+use crate::validators::{epoch_line, epoch_performance, last_epochs, ValidatorRecord};
+use crate::verify_headers::verify_headers;
+use crate::verify_state::verify_trie;
 use clap::{Args, Parser, Subcommand};
+use near_chain::RuntimeAdapter;
 use near_chain_configs::GenesisValidationMode;
+use near_crypto::PublicKey;
+use near_epoch_manager::EpochManager;
 use near_primitives::account::id::AccountId;
+use near_primitives::block::{BlockHeader, HeaderValidationOptions, Tip};
 use near_primitives::hash::CryptoHash;
 use near_primitives::sharding::ChunkHash;
-use near_primitives::types::{BlockHeight, ShardId};
-use near_store::{create_store_with_config, Store};
-use nearcore::{get_store_path, load_config, NearConfig};
+use near_primitives::types::{BlockHeight, EpochId, ShardId};
+use near_store::{create_store_with_config, try_create_store_with_config, DBCol, Store, HEAD_KEY};
+use nearcore::{get_store_path, load_config, NearConfig, NightshadeRuntime};
+use std::fs::File;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
+use strum::IntoEnumIterator;
 
 #[derive(Subcommand)]
 #[clap(subcommand_required = true, arg_required_else_help = true)]
@@ -37,7 +70,11 @@ pub enum StateViewerSubCommand {
     /// Check whether the node has all the blocks up to its head.
     #[clap(alias = "check_block")]
     CheckBlock,
-    /// Dump deployed contract code of given account to wasm file.
+    /// Check whether a genesis file is the one the database was created with.
+    #[clap(alias = "check_genesis")]
+    CheckGenesis(CheckGenesisCmd),
+    /// Dump deployed contract code of given account or with given hash to wasm
+    /// file.
     #[clap(alias = "dump_code")]
     DumpCode(DumpCodeCmd),
     /// Dump contract data in storage of given account to binary file.
@@ -53,6 +90,10 @@ pub enum StateViewerSubCommand {
     Chunks(ChunksCmd),
     #[clap(alias = "partial_chunks")]
     PartialChunks(PartialChunksCmd),
+    /// Print which parts of a chunk are stored and who owns them, or with
+    /// `--start-height` and `--end-height` the missing parts per height.
+    #[clap(alias = "chunk_parts")]
+    ChunkParts(ChunkPartsCmd),
     /// Apply a chunk, even if it's not included in any block on disk
     #[clap(alias = "apply_chunk")]
     ApplyChunk(ApplyChunkCmd),
@@ -64,10 +105,114 @@ pub enum StateViewerSubCommand {
     /// even if it's not included in any block on disk
     #[clap(alias = "apply_receipt")]
     ApplyReceipt(ApplyReceiptCmd),
+    /// Print an unsigned transaction replacing an access key with a new one,
+    /// for signing offline.
+    #[clap(alias = "rotate_key")]
+    RotateKey(RotateKeyCmd),
+    /// Print the latest read-write opens of the database by neard.
+    #[clap(alias = "open_history")]
+    OpenHistory,
+    /// Report entries which refer to blocks that are no longer in the
+    /// database, e.g. left behind by garbage collection.
+    Orphans(OrphansCmd),
+    /// Build a light client proof of a transaction or receipt outcome from
+    /// the local database.
+    #[clap(alias = "light_proof")]
+    LightProof(LightProofCmd),
+    /// Print the entries of a column as JSON lines, with the values decoded
+    /// where the column has a single value type.
+    #[clap(alias = "dump_column")]
+    DumpColumn(DumpColumnCmd),
+    /// Print the header, chunk headers and approvals of the block given by
+    /// `--hash`, or of all blocks at `--height`, as pretty JSON.
+    Block(BlockCmd),
+    /// Walk the canonical chain from the head down to the tail and report
+    /// missing blocks and inconsistent entries.
+    #[clap(alias = "check_chain")]
+    CheckChain(CheckChainCmd),
+    /// Run the stateless header checks (hashes, prev hash links, heights,
+    /// timestamps and version specific fields) on the canonical headers in a
+    /// range of heights.
+    #[clap(alias = "verify_headers")]
+    VerifyHeaders(VerifyHeadersCmd),
+    /// Print the number of keys and the sizes of keys and values per column.
+    #[clap(alias = "column_stats")]
+    ColumnStats(ColumnStatsCmd),
+    /// Print the account, access keys and contract storage of an account as
+    /// JSON lines.
+    Account(AccountCmd),
+    /// Compare two databases column by column and print the keys which are
+    /// only in one of them or have different values.
+    Diff(DiffCmd),
+    /// Print a transaction and its outcomes as pretty JSON.
+    Tx(TxCmd),
+    /// Print a receipt and its outcomes as pretty JSON.
+    Receipt(ReceiptCmd),
+    /// Report runs of heights without blocks, and heights without blocks
+    /// which stored blocks build on.
+    Gaps(GapsCmd),
+    /// Write the blocks of a height range with their chunks, transactions,
+    /// receipts and outcomes to an archive file.
+    Export(ExportCmd),
+    /// Load an archive written by `export` into a fresh database.
+    Import(ImportCmd),
+    /// Open the database once and read commands such as `get`, `iter`,
+    /// `height` and `block` from stdin.
+    Repl,
+    /// Print a chunk with its transactions and receipts as pretty JSON, or
+    /// with `--missing` the chunks included in blocks but not stored.
+    Chunk(ChunkCmd),
+    /// Walk the whole trie a chunk is applied to and check that every node
+    /// and value is stored and hashes correctly.
+    #[clap(alias = "verify_state")]
+    VerifyState(VerifyStateCmd),
+    /// Print the keys of all or some columns which contain a byte pattern,
+    /// e.g. a block hash.
+    Find(FindCmd),
+    /// Print the produced and expected blocks and chunks of every validator
+    /// and whether they were kicked out, for the epoch of the head, a given
+    /// epoch or the last epochs.
+    Validators(ValidatorsCmd),
+    /// Print the GC tails and stop height, the number of epochs of history
+    /// above the tail and the entries GC should have removed but didn't.
+    #[clap(alias = "gc_info")]
+    GcInfo(GcInfoCmd),
+    /// Print the access keys of an account, or of the implicit accounts
+    /// matching a hex prefix, as stored in the trie.
+    #[clap(alias = "access_keys")]
+    AccessKeys(AccessKeysCmd),
+    /// Print the accounts of a shard with the most bytes of account records,
+    /// contract code, access keys and contract data in the trie.
+    #[clap(alias = "state_size")]
+    StateSize(StateSizeCmd),
+    /// Print the value of a key of a column, decoded if the column has values
+    /// of a single type.
+    #[clap(alias = "raw_get")]
+    RawGet(RawGetCmd),
+    /// Overwrite the value of a key of a column, e.g. to reset `HEAD` to an
+    /// earlier tip.  Opens the database read-write and refuses to run while
+    /// a node uses it.
+    #[clap(alias = "raw_set")]
+    RawSet(RawSetCmd),
+    /// Print the tree of receipts created by a transaction with their status,
+    /// gas burnt and logs.
+    Trace(TraceCmd),
+    /// Measure the sequential read throughput and the point read latencies
+    /// of every column or of one column.
+    Bench(BenchCmd),
 }
 
 impl StateViewerSubCommand {
-    pub fn run(self, home_dir: &Path, genesis_validation: GenesisValidationMode, readwrite: bool) {
+    pub fn run(
+        self,
+        home_dir: &Path,
+        genesis_validation: GenesisValidationMode,
+        readwrite: bool,
+        format: OutputFormat,
+    ) {
+        if format != OutputFormat::Human && !self.supports_format() {
+            panic!("--format {:?} is not supported by this subcommand", format);
+        }
         let near_config = load_config(home_dir, genesis_validation)
             .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
         let store_path = get_store_path(home_dir);
@@ -82,8 +227,9 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::Replay(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::ApplyRange(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::Apply(cmd) => cmd.run(home_dir, near_config, store),
-            StateViewerSubCommand::ViewChain(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::ViewChain(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::CheckBlock => check_block_chunk_existence(store, near_config),
+            StateViewerSubCommand::CheckGenesis(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::DumpCode(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::DumpAccountStorage(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::EpochInfo(cmd) => cmd.run(home_dir, near_config, store),
@@ -91,11 +237,75 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::Receipts(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::Chunks(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::PartialChunks(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::ChunkParts(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::ApplyChunk(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::ApplyTx(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::ApplyReceipt(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::RotateKey(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::OpenHistory => open_history(store),
+            StateViewerSubCommand::Orphans(cmd) => cmd.run(near_config, store, format),
+            StateViewerSubCommand::LightProof(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::DumpColumn(cmd) => cmd.run(store),
+            StateViewerSubCommand::Block(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::CheckChain(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::VerifyHeaders(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::ColumnStats(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::Account(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::Diff(cmd) => cmd.run(near_config),
+            StateViewerSubCommand::Tx(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::Receipt(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::Gaps(cmd) => cmd.run(near_config, store, format),
+            StateViewerSubCommand::Export(cmd) => cmd.run(store),
+            StateViewerSubCommand::Import(cmd) => cmd.run(near_config),
+            StateViewerSubCommand::Chunk(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::VerifyState(cmd) => {
+                cmd.run(home_dir, near_config, store, format)
+            }
+            StateViewerSubCommand::Find(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::Validators(cmd) => cmd.run(near_config, store, format),
+            StateViewerSubCommand::GcInfo(cmd) => cmd.run(home_dir, near_config, store, format),
+            StateViewerSubCommand::AccessKeys(cmd) => cmd.run(home_dir, near_config, store, format),
+            StateViewerSubCommand::StateSize(cmd) => cmd.run(home_dir, near_config, store, format),
+            StateViewerSubCommand::RawGet(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::Trace(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::Bench(cmd) => cmd.run(store, format),
+            StateViewerSubCommand::RawSet(cmd) => {
+                // The database is opened again read-write.
+                drop(store);
+                cmd.run(&store_path, near_config)
+            }
+            StateViewerSubCommand::Repl => {
+                let stdin = std::io::stdin();
+                let stdout = std::io::stdout();
+                run_repl(&store, &mut stdin.lock(), &mut stdout.lock())
+                    .unwrap_or_else(|err| panic!("Failed to run the REPL: {:#}", err));
+            }
         }
     }
+
+    /// Whether the subcommand writes its output through [`Output`], the other
+    /// ones only print human readable text.
+    fn supports_format(&self) -> bool {
+        matches!(
+            self,
+            StateViewerSubCommand::Orphans(_)
+                | StateViewerSubCommand::Block(_)
+                | StateViewerSubCommand::ColumnStats(_)
+                | StateViewerSubCommand::Tx(_)
+                | StateViewerSubCommand::Receipt(_)
+                | StateViewerSubCommand::Gaps(_)
+                | StateViewerSubCommand::Chunk(_)
+                | StateViewerSubCommand::VerifyState(_)
+                | StateViewerSubCommand::Find(_)
+                | StateViewerSubCommand::Validators(_)
+                | StateViewerSubCommand::GcInfo(_)
+                | StateViewerSubCommand::AccessKeys(_)
+                | StateViewerSubCommand::StateSize(_)
+                | StateViewerSubCommand::RawGet(_)
+                | StateViewerSubCommand::Trace(_)
+                | StateViewerSubCommand::Bench(_)
+        )
+    }
 }
 
 #[derive(Parser)]
@@ -220,25 +430,126 @@ pub struct ViewChainCmd {
     block: bool,
     #[clap(long)]
     chunk: bool,
+    /// Keep printing a line per new block as the head moves.
+    #[clap(long, conflicts_with_all = &["height", "block", "chunk"])]
+    follow: bool,
+    /// How often to check for new blocks with `--follow`, in milliseconds.
+    #[clap(long, default_value = "1000")]
+    interval_ms: u64,
 }
 
 impl ViewChainCmd {
-    pub fn run(self, near_config: NearConfig, store: Store) {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        if self.follow {
+            let store_config = near_config.config.store.clone().with_read_only(true);
+            follow_head(
+                &get_store_path(home_dir),
+                &store_config,
+                Duration::from_millis(self.interval_ms),
+            );
+        }
         view_chain(self.height, self.block, self.chunk, near_config, store);
     }
 }
 
+#[derive(Parser)]
+pub struct CheckGenesisCmd {
+    /// Genesis file to check, with the records in it.
+    #[clap(long, parse(from_os_str))]
+    genesis: PathBuf,
+}
+
+impl CheckGenesisCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        check_genesis(&self.genesis, near_config, store);
+    }
+}
+
 #[derive(Parser)]
 pub struct DumpCodeCmd {
+    /// Account whose contract to dump.
+    #[clap(long, required_unless_present = "code-hash", conflicts_with = "code-hash")]
+    account_id: Option<AccountId>,
+    /// Hash of the contract code to dump instead of the code of an account.
     #[clap(long)]
-    account_id: String,
+    code_hash: Option<CryptoHash>,
     #[clap(long, parse(from_os_str))]
     output: PathBuf,
 }
 
 impl DumpCodeCmd {
     pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
-        dump_code(self.account_id, &self.output, home_dir, near_config, store);
+        dump_code(self.account_id, self.code_hash, &self.output, home_dir, near_config, store);
+    }
+}
+
+#[derive(Parser)]
+pub struct AccountCmd {
+    #[clap(long)]
+    account_id: AccountId,
+    /// Block whose prev state to read, the head by default.
+    #[clap(long)]
+    block_hash: Option<CryptoHash>,
+}
+
+impl AccountCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        print_account(self.account_id, self.block_hash, home_dir, near_config, store);
+    }
+}
+
+#[derive(Parser)]
+pub struct AccessKeysCmd {
+    #[clap(long, required_unless_present = "all-accounts", conflicts_with = "all-accounts")]
+    account_id: Option<AccountId>,
+    /// List the keys of all implicit accounts instead, or with `--prefix` of
+    /// those whose id starts with the given hex digits.
+    #[clap(long)]
+    all_accounts: bool,
+    #[clap(long, requires = "all-accounts")]
+    prefix: Option<String>,
+    /// Block whose prev state to read, the head by default.
+    #[clap(long)]
+    block_hash: Option<CryptoHash>,
+}
+
+impl AccessKeysCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store, format: OutputFormat) {
+        print_access_keys(
+            self.account_id,
+            self.prefix.as_deref().unwrap_or_default(),
+            self.block_hash,
+            home_dir,
+            near_config,
+            store,
+            format,
+        );
+    }
+}
+
+#[derive(Parser)]
+pub struct StateSizeCmd {
+    #[clap(long, default_value = "0")]
+    shard_id: ShardId,
+    /// Number of accounts to print, largest first.
+    #[clap(long, default_value = "10")]
+    top: usize,
+    /// Block whose prev state to read, the head by default.
+    #[clap(long)]
+    block_hash: Option<CryptoHash>,
+}
+
+impl StateSizeCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store, format: OutputFormat) {
+        print_state_size(
+            self.shard_id,
+            self.top,
+            self.block_hash,
+            home_dir,
+            near_config,
+            store,
+            format,
+        );
     }
 }
 
@@ -274,6 +585,9 @@ pub struct EpochInfoCmd {
     /// Displays kickouts of the given validator and expected and missed blocks and chunks produced.
     #[clap(long)]
     validator_account_id: Option<String>,
+    /// Only list the epochs with their start heights.
+    #[clap(long)]
+    list: bool,
 }
 
 impl EpochInfoCmd {
@@ -281,6 +595,7 @@ impl EpochInfoCmd {
         print_epoch_info(
             self.epoch_selection,
             self.validator_account_id.map(|s| AccountId::from_str(&s).unwrap()),
+            self.list,
             home_dir,
             near_config,
             store,
@@ -313,6 +628,48 @@ impl ReceiptsCmd {
     }
 }
 
+#[derive(Parser)]
+pub struct TxCmd {
+    /// Hash of the transaction.
+    hash: CryptoHash,
+}
+
+impl TxCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        let tx = lookup_transaction(&store, &self.hash)
+            .unwrap_or_else(|err| panic!("Failed to look up the transaction: {:#}", err));
+        Output::stdout(format).record(&tx).unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct ReceiptCmd {
+    /// Id of the receipt.
+    id: CryptoHash,
+}
+
+impl ReceiptCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        let receipt = lookup_receipt(&store, &self.id)
+            .unwrap_or_else(|err| panic!("Failed to look up the receipt: {:#}", err));
+        Output::stdout(format).record(&receipt).unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct TraceCmd {
+    /// Hash of the transaction.
+    hash: CryptoHash,
+}
+
+impl TraceCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        let mut output = Output::stdout(format);
+        trace_transaction(&store, &self.hash, &mut |node| output.record(&node))
+            .unwrap_or_else(|err| panic!("Failed to trace the transaction: {:#}", err));
+    }
+}
+
 #[derive(Parser)]
 pub struct ChunksCmd {
     #[clap(long)]
@@ -339,6 +696,701 @@ impl PartialChunksCmd {
     }
 }
 
+#[derive(Parser)]
+pub struct ChunkPartsCmd {
+    #[clap(long, conflicts_with_all = &["start-height", "end-height"])]
+    chunk: Option<String>,
+    #[clap(long, requires = "end-height")]
+    start_height: Option<BlockHeight>,
+    #[clap(long, requires = "start-height")]
+    end_height: Option<BlockHeight>,
+}
+
+impl ChunkPartsCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let selection = match (self.chunk, self.start_height, self.end_height) {
+            (Some(chunk_hash), _, _) => ChunkPartsSelection::Chunk(ChunkHash::from(
+                CryptoHash::from_str(&chunk_hash).unwrap(),
+            )),
+            (None, Some(start_height), Some(end_height)) => {
+                ChunkPartsSelection::Heights(start_height, end_height)
+            }
+            _ => panic!("Either --chunk or --start-height and --end-height must be given"),
+        };
+        print_chunk_parts(selection, home_dir, near_config, store);
+    }
+}
+
+#[derive(Parser)]
+pub struct OrphansCmd {
+    /// Column to check, e.g. `TrieChanges`.
+    #[clap(long, required_unless_present = "all", parse(try_from_str = parse_column))]
+    column: Option<DBCol>,
+    /// Check all columns which refer to blocks.
+    #[clap(long, conflicts_with = "column")]
+    all: bool,
+    /// File to write `<column> <key>` lines to for every orphaned entry,
+    /// with the key in base58.
+    #[clap(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl OrphansCmd {
+    pub fn run(self, near_config: NearConfig, store: Store, format: OutputFormat) {
+        if let Some(col) = self.column {
+            if block_ref(col).is_none() {
+                panic!("{} is not a column referring to blocks", <&str>::from(col));
+            }
+        }
+        let mut output = self.output.map(|path| File::create(path).unwrap());
+        print_orphans(
+            &store,
+            near_config.genesis.config.genesis_height,
+            self.column,
+            output.as_mut().map(|file| file as &mut dyn Write),
+            &mut Output::stdout(format),
+        )
+        .unwrap();
+    }
+}
+
+/// Parses a column name, also accepting the old `Col` prefixed names such as
+/// `ColBlockMisc`.
+pub(crate) fn parse_column(name: &str) -> Result<DBCol, String> {
+    let stripped = name.strip_prefix("Col").unwrap_or(name);
+    DBCol::iter()
+        .find(|col| <&str>::from(col) == name || <&str>::from(col) == stripped)
+        .ok_or(format!("{} is not a column", name))
+}
+
+#[derive(Parser)]
+pub struct GapsCmd {
+    /// First height to check, genesis by default.
+    #[clap(long)]
+    start_height: Option<BlockHeight>,
+    /// Last height to check, the head by default.
+    #[clap(long)]
+    end_height: Option<BlockHeight>,
+}
+
+impl GapsCmd {
+    pub fn run(self, near_config: NearConfig, store: Store, format: OutputFormat) {
+        let start_height = self.start_height.unwrap_or(near_config.genesis.config.genesis_height);
+        let end_height = self.end_height.unwrap_or_else(|| {
+            let head: Tip = store.get_ser(DBCol::BlockMisc, HEAD_KEY).unwrap().unwrap();
+            head.height
+        });
+        let gaps = find_gaps(&store, start_height..=end_height).unwrap();
+        let mut output = Output::stdout(format);
+        for gap in gaps.records() {
+            output.record(&gap).unwrap();
+        }
+        output
+            .line(&format!(
+                "{} heights with blocks, {} of them with forks, {} gaps",
+                gaps.num_stored,
+                gaps.num_forked,
+                gaps.gaps.len()
+            ))
+            .unwrap();
+        if let Some(gap) = gaps.longest_gap() {
+            output
+                .line(&format!(
+                    "Longest gap is {}..={} of {} heights",
+                    gap.start(),
+                    gap.end(),
+                    gap.end() - gap.start() + 1
+                ))
+                .unwrap();
+        }
+        output
+            .line(&format!(
+                "{} missing heights are referenced by stored blocks",
+                gaps.referenced.len()
+            ))
+            .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct ExportCmd {
+    #[clap(long)]
+    start_height: BlockHeight,
+    #[clap(long)]
+    end_height: BlockHeight,
+    /// File to write the archive to.
+    #[clap(long, parse(from_os_str))]
+    output: PathBuf,
+}
+
+impl ExportCmd {
+    pub fn run(self, store: Store) {
+        let file = File::create(&self.output)
+            .unwrap_or_else(|err| panic!("Failed to create {}: {}", self.output.display(), err));
+        let num_entries = export_archive(
+            &store,
+            self.start_height..=self.end_height,
+            std::io::BufWriter::new(file),
+        )
+        .unwrap_or_else(|err| panic!("Failed to export: {:#}", err));
+        println!("Exported {} entries to {}", num_entries, self.output.display());
+    }
+}
+
+#[derive(Parser)]
+pub struct ImportCmd {
+    /// Archive written by `export`.
+    #[clap(long, parse(from_os_str))]
+    input: PathBuf,
+    /// Database to import into, created if it doesn't exist.
+    #[clap(long, parse(from_os_str))]
+    db_path: PathBuf,
+}
+
+impl ImportCmd {
+    pub fn run(self, near_config: NearConfig) {
+        let archive = std::fs::read(&self.input)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {}", self.input.display(), err));
+        let store_config = near_config.config.store.clone().with_read_only(false);
+        let store = try_create_store_with_config(&self.db_path, &store_config)
+            .unwrap_or_else(|err| panic!("Failed to open {}: {}", self.db_path.display(), err));
+        let (heights, num_entries) = import_archive(&store, &archive)
+            .unwrap_or_else(|err| panic!("Failed to import: {:#}", err));
+        println!(
+            "Imported {} entries of heights {}..={} into {}",
+            num_entries,
+            heights.start(),
+            heights.end(),
+            self.db_path.display()
+        );
+    }
+}
+
+#[derive(Parser)]
+pub struct ChunkCmd {
+    /// Hash of the chunk.
+    #[clap(
+        required_unless_present_any = &["block", "missing"],
+        conflicts_with_all = &["block", "missing"]
+    )]
+    chunk_hash: Option<CryptoHash>,
+    /// Block whose chunk of `--shard` to print instead.
+    #[clap(long, requires = "shard", conflicts_with = "missing")]
+    block: Option<CryptoHash>,
+    #[clap(long)]
+    shard: Option<ShardId>,
+    /// Report the blocks from `--start-height` to `--end-height` whose chunk
+    /// mask has a chunk whose body isn't stored.
+    #[clap(long, requires_all = &["start-height", "end-height"])]
+    missing: bool,
+    #[clap(long)]
+    start_height: Option<BlockHeight>,
+    #[clap(long)]
+    end_height: Option<BlockHeight>,
+}
+
+impl ChunkCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        let mut output = Output::stdout(format);
+        if self.missing {
+            let missing =
+                find_missing_chunks(&store, self.start_height.unwrap()..=self.end_height.unwrap())
+                    .unwrap_or_else(|err| panic!("Failed to find missing chunks: {:#}", err));
+            for chunk in &missing {
+                output.record(chunk).unwrap();
+            }
+            output.line(&format!("{} chunks are missing", missing.len())).unwrap();
+            return;
+        }
+        let (chunk_hash, header) = match (self.chunk_hash, self.block) {
+            (Some(chunk_hash), _) => (ChunkHash(chunk_hash), None),
+            (None, Some(block)) => {
+                let header = chunk_header_in_block(&store, &block, self.shard.unwrap())
+                    .unwrap_or_else(|err| panic!("Failed to find the chunk: {:#}", err));
+                (header.chunk_hash(), Some(header))
+            }
+            (None, None) => unreachable!(),
+        };
+        let mut chunk = read_chunk(&store, &chunk_hash)
+            .unwrap_or_else(|err| panic!("Failed to read the chunk: {:#}", err));
+        // The header from the block has `height_included` set.
+        if let (Some(header), Some(block)) = (header, self.block) {
+            chunk.header = header.into();
+            chunk.incoming_receipts = read_incoming_receipts(&store, &block, self.shard.unwrap())
+                .unwrap_or_else(|err| panic!("Failed to read the incoming receipts: {:#}", err));
+        }
+        output.record(&chunk).unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct VerifyStateCmd {
+    /// Block whose chunk's prev state root to verify.
+    #[clap(long)]
+    block_hash: CryptoHash,
+    #[clap(long)]
+    shard_id: ShardId,
+}
+
+impl VerifyStateCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store, format: OutputFormat) {
+        let chunk_header = chunk_header_in_block(&store, &self.block_hash, self.shard_id)
+            .unwrap_or_else(|err| panic!("Failed to find the chunk: {:#}", err));
+        let header: BlockHeader =
+            store.get_ser(DBCol::BlockHeader, self.block_hash.as_ref()).unwrap().unwrap();
+        let runtime = NightshadeRuntime::with_config(
+            home_dir,
+            store.clone(),
+            &near_config,
+            None,
+            near_config.client_config.max_gas_burnt_view,
+        );
+        let shard_uid = runtime.shard_id_to_uid(self.shard_id, header.epoch_id()).unwrap();
+        let root = chunk_header.prev_state_root();
+        let mut output = Output::stdout(format);
+        output.line(&format!("Verifying state root {} of shard {:?}", root, shard_uid)).unwrap();
+        let (stats, broken) = verify_trie(&store, shard_uid, &root, &mut |stats| {
+            eprintln!(
+                "Visited {} nodes, {} values, {} bytes",
+                stats.num_nodes, stats.num_values, stats.bytes
+            )
+        })
+        .unwrap_or_else(|err| panic!("Failed to verify the state: {:#}", err));
+        for node in &broken {
+            output.record(node).unwrap();
+        }
+        output
+            .line(&format!(
+                "Visited {} nodes and {} values of {} bytes, {} are broken",
+                stats.num_nodes,
+                stats.num_values,
+                stats.bytes,
+                broken.len()
+            ))
+            .unwrap();
+        if !broken.is_empty() {
+            std::process::exit(1);
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct FindCmd {
+    /// Bytes to look for, in hex with a `0x` prefix or in base58.
+    pattern: String,
+    /// Take the pattern as text, e.g. an account id.
+    #[clap(long)]
+    text: bool,
+    /// Only search this column, can be given more than once.
+    #[clap(
+        long,
+        parse(try_from_str = parse_column),
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    column: Vec<DBCol>,
+    /// Only match keys starting with the pattern, which doesn't need to read
+    /// every key.
+    #[clap(long)]
+    prefix_only: bool,
+    /// Stop after this many matches.
+    #[clap(long)]
+    max_results: Option<usize>,
+}
+
+impl FindCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        let pattern = parse_pattern(&self.pattern, self.text)
+            .unwrap_or_else(|err| panic!("Failed to parse the pattern: {:#}", err));
+        let cols = if self.column.is_empty() { DBCol::iter().collect() } else { self.column };
+        let max_results = self.max_results.unwrap_or(usize::MAX);
+        let mut output = Output::stdout(format);
+        let num_matches = find_keys(
+            &store,
+            &cols,
+            &pattern,
+            self.prefix_only,
+            max_results,
+            &mut |col, key, value_size| {
+                let found = FoundKey {
+                    column: col.into(),
+                    key: hex::encode(key),
+                    decoded_key: decode_key(col, key),
+                    value_size,
+                };
+                output.record(&found).unwrap();
+            },
+        );
+        output.line(&format!("Found {} keys", num_matches)).unwrap();
+        if num_matches == max_results {
+            output.line(&format!("Stopped after {} matches", max_results)).unwrap();
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct ValidatorsCmd {
+    /// Epoch to report.
+    #[clap(long, conflicts_with = "last")]
+    epoch_id: Option<CryptoHash>,
+    /// Report the last N epochs, from the epoch of the head back.
+    #[clap(long)]
+    last: Option<usize>,
+}
+
+impl ValidatorsCmd {
+    pub fn run(self, near_config: NearConfig, store: Store, format: OutputFormat) {
+        let epoch_manager =
+            EpochManager::new_from_genesis_config(store.clone(), &near_config.genesis.config)
+                .expect("Failed to start Epoch Manager");
+        let head: Tip = store.get_ser(DBCol::BlockMisc, HEAD_KEY).unwrap().unwrap();
+        let epoch_ids = match self.epoch_id {
+            Some(epoch_id) => vec![EpochId(epoch_id)],
+            None => last_epochs(&epoch_manager, &head.last_block_hash, self.last.unwrap_or(1))
+                .unwrap_or_else(|err| panic!("Failed to find the epochs: {:#}", err)),
+        };
+        let mut output = Output::stdout(format);
+        for epoch_id in epoch_ids {
+            let epoch = epoch_performance(&epoch_manager, &epoch_id, &head.last_block_hash)
+                .unwrap_or_else(|err| panic!("Failed to read epoch {:?}: {:#}", epoch_id, err));
+            output.line(&epoch_line(&epoch)).unwrap();
+            for validator in &epoch.validators {
+                let record = ValidatorRecord {
+                    epoch_id: &epoch.epoch_id,
+                    epoch_height: epoch.epoch_height,
+                    validator,
+                };
+                output.record(&record).unwrap();
+            }
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct GcInfoCmd {
+    /// Number of example keys to print for every column with leaked entries.
+    #[clap(long, default_value = "5")]
+    max_examples: usize,
+}
+
+impl GcInfoCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store, format: OutputFormat) {
+        let runtime = NightshadeRuntime::with_config(
+            home_dir,
+            store.clone(),
+            &near_config,
+            None,
+            near_config.client_config.max_gas_burnt_view,
+        );
+        let genesis_height = near_config.genesis.config.genesis_height;
+        let head: Tip = store.get_ser(DBCol::BlockMisc, HEAD_KEY).unwrap().unwrap();
+        let tails = read_tails(&store, genesis_height)
+            .unwrap_or_else(|err| panic!("Failed to read the tails: {:#}", err));
+        let num_epochs = count_epochs(&store, &runtime, &head.last_block_hash, tails.tail)
+            .unwrap_or_else(|err| panic!("Failed to count the epochs: {:#}", err));
+        let leaks = find_leaks(&store, genesis_height, &tails, self.max_examples)
+            .unwrap_or_else(|err| panic!("Failed to look for leaked entries: {:#}", err));
+        let gc_stop_height = runtime.get_gc_stop_height(&head.last_block_hash);
+        let info = GcInfo { head, gc_stop_height, tails, num_epochs, leaks };
+        Output::stdout(format).record(&info).unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct ColumnStatsCmd {
+    /// Only show this column, e.g. `State`.
+    #[clap(long, parse(try_from_str = parse_column))]
+    column: Option<DBCol>,
+}
+
+impl ColumnStatsCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        let cols = match self.column {
+            Some(col) => vec![col],
+            None => DBCol::iter().collect(),
+        };
+        let stats: Vec<_> = cols.into_iter().map(|col| column_stats(&store, col)).collect();
+        print_table(&stats, &mut Output::stdout(format)).unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct BenchCmd {
+    /// Only benchmark this column, e.g. `State`.
+    #[clap(long, parse(try_from_str = parse_column))]
+    column: Option<DBCol>,
+    /// Maximum time to scan each column for, in seconds.
+    #[clap(long, default_value = "10")]
+    duration: u64,
+    /// Number of keys of each column to read one by one.
+    #[clap(long, default_value = "1000")]
+    sample: usize,
+}
+
+impl BenchCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        let cols = match self.column {
+            Some(col) => vec![col],
+            None => DBCol::iter().collect(),
+        };
+        let mut output = Output::stdout(format);
+        for col in cols {
+            let bench = bench_column(&store, col, Duration::from_secs(self.duration), self.sample)
+                .unwrap_or_else(|err| panic!("Failed to benchmark {:?}: {:#}", col, err));
+            output.record(&bench).unwrap();
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct DiffCmd {
+    /// Paths of the two databases to compare, given twice.
+    #[clap(long, parse(from_os_str), multiple_occurrences = true, number_of_values = 1)]
+    db_path: Vec<PathBuf>,
+    /// Only compare this column, e.g. `BlockPerHeight`.
+    #[clap(long, parse(try_from_str = parse_column))]
+    column: Option<DBCol>,
+    /// Stop after this many differences.
+    #[clap(long)]
+    max_diffs: Option<u64>,
+}
+
+impl DiffCmd {
+    pub fn run(self, near_config: NearConfig) {
+        let (first_path, second_path) = match self.db_path.as_slice() {
+            [first, second] => (first, second),
+            _ => panic!("--db-path must be given exactly twice"),
+        };
+        let store_config = near_config.config.store.clone().with_read_only(true);
+        let first = create_store_with_config(first_path, &store_config);
+        let second = create_store_with_config(second_path, &store_config);
+        let cols = match self.column {
+            Some(col) => vec![col],
+            None => DBCol::iter().collect(),
+        };
+        let mut remaining = self.max_diffs.unwrap_or(u64::MAX);
+        for col in cols {
+            let summary = diff_column(&first, &second, col, remaining, &mut |key, difference| {
+                let difference = match difference {
+                    Difference::OnlyInFirst => format!("only in {}", first_path.display()),
+                    Difference::OnlyInSecond => format!("only in {}", second_path.display()),
+                    Difference::DifferentValues => "different values".to_string(),
+                };
+                println!("{} {}: {}", <&str>::from(col), hex::encode(key), difference);
+            });
+            if summary.total() > 0 {
+                println!(
+                    "{}: {} only in {}, {} only in {}, {} with different values",
+                    <&str>::from(col),
+                    summary.only_in_first,
+                    first_path.display(),
+                    summary.only_in_second,
+                    second_path.display(),
+                    summary.different_values
+                );
+            }
+            remaining -= summary.total();
+            if remaining == 0 {
+                println!("Stopped after {} differences", self.max_diffs.unwrap());
+                break;
+            }
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct DumpColumnCmd {
+    /// Column to dump, e.g. `BlockHeader`.
+    #[clap(long, parse(try_from_str = parse_column))]
+    column: DBCol,
+    /// Only dump entries whose keys start with this prefix, in hex.
+    #[clap(long)]
+    prefix: Option<String>,
+    /// Maximum number of entries to dump.
+    #[clap(long)]
+    limit: Option<usize>,
+}
+
+impl DumpColumnCmd {
+    pub fn run(self, store: Store) {
+        let prefix = self
+            .prefix
+            .map(|prefix| hex::decode(prefix).expect("prefix must be hex"))
+            .unwrap_or_default();
+        dump_column(&store, self.column, &prefix, self.limit, &mut std::io::stdout().lock())
+            .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct RawGetCmd {
+    /// Column of the key, e.g. `BlockMisc`.
+    #[clap(long, parse(try_from_str = parse_column))]
+    column: DBCol,
+    /// The key in hex.
+    #[clap(long)]
+    key: String,
+}
+
+impl RawGetCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        let key = hex::decode(&self.key).expect("key must be hex");
+        let value = store
+            .get(self.column, &key)
+            .unwrap()
+            .unwrap_or_else(|| panic!("{} {} not found", <&str>::from(self.column), self.key));
+        Output::stdout(format).record(&entry_to_json(self.column, &key, &value)).unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct RawSetCmd {
+    /// Column of the key, e.g. `BlockMisc`.
+    #[clap(long, parse(try_from_str = parse_column))]
+    column: DBCol,
+    /// The key in hex.
+    #[clap(long)]
+    key: String,
+    /// The new value in hex.
+    #[clap(long)]
+    value: String,
+    /// Confirms that the database is meant to be modified.
+    #[clap(long = "i-know-what-i-am-doing")]
+    confirmed: bool,
+}
+
+impl RawSetCmd {
+    pub fn run(self, store_path: &Path, near_config: NearConfig) {
+        if !self.confirmed {
+            panic!("raw-set modifies the database, pass --i-know-what-i-am-doing to run it");
+        }
+        let key = hex::decode(&self.key).expect("key must be hex");
+        let value = hex::decode(&self.value).expect("value must be hex");
+        if let Err(err) = ensure_unlocked(store_path) {
+            panic!("Refusing to write: {:#}", err);
+        }
+        let store_config = near_config.config.store.clone().with_read_only(false);
+        let store = try_create_store_with_config(store_path, &store_config)
+            .unwrap_or_else(|err| panic!("Failed to open {}: {}", store_path.display(), err));
+        let previous = raw_set(&store, self.column, &key, &value)
+            .unwrap_or_else(|err| panic!("Failed to set the value: {:#}", err));
+        match previous {
+            Some(previous) => println!(
+                "Set {} {}, the previous value was {}",
+                <&str>::from(self.column),
+                self.key,
+                hex::encode(previous)
+            ),
+            None => println!("Set {} {}, it had no value", <&str>::from(self.column), self.key),
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct BlockCmd {
+    /// Hash of the block.
+    #[clap(long, required_unless_present = "height", conflicts_with = "height")]
+    hash: Option<CryptoHash>,
+    /// Height of the blocks, all forks at it are printed.
+    #[clap(long)]
+    height: Option<BlockHeight>,
+}
+
+impl BlockCmd {
+    pub fn run(self, store: Store, format: OutputFormat) {
+        if format == OutputFormat::Human {
+            print_blocks(self.hash, self.height, &store, &mut std::io::stdout().lock())
+                .unwrap_or_else(|err| panic!("Failed to print blocks: {:#}", err));
+            return;
+        }
+        let hashes = match (self.hash, self.height) {
+            (Some(hash), _) => vec![hash],
+            (None, Some(height)) => {
+                let hashes = block_hashes_at_height(&store, height)
+                    .unwrap_or_else(|err| panic!("Failed to find blocks: {:#}", err));
+                if hashes.is_empty() {
+                    panic!("No blocks at height {}", height);
+                }
+                hashes
+            }
+            (None, None) => unreachable!(),
+        };
+        let mut output = Output::stdout(format);
+        for hash in hashes {
+            let block = read_block(&store, &hash)
+                .unwrap_or_else(|err| panic!("Failed to read block {}: {:#}", hash, err));
+            output.record(&block).unwrap();
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct CheckChainCmd {
+    /// Report all issues instead of stopping at the first one.
+    #[clap(long)]
+    keep_going: bool,
+}
+
+impl CheckChainCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        let check = check_chain(&store, near_config.genesis.config.genesis_height, self.keep_going)
+            .unwrap_or_else(|err| panic!("Failed to check the chain: {:#}", err));
+        for issue in &check.issues {
+            println!("{}", issue);
+        }
+        println!("Checked {} blocks, found {} issues", check.num_blocks, check.issues.len());
+        if !check.issues.is_empty() {
+            std::process::exit(1);
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct VerifyHeadersCmd {
+    /// First height to check, genesis by default.
+    #[clap(long)]
+    start_height: Option<BlockHeight>,
+    /// Last height to check, the header head by default.
+    #[clap(long)]
+    end_height: Option<BlockHeight>,
+    /// Largest allowed difference between the timestamps of consecutive
+    /// headers, in nanoseconds.
+    #[clap(long)]
+    max_timestamp_drift: Option<u64>,
+}
+
+impl VerifyHeadersCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        let start_height = self.start_height.unwrap_or(near_config.genesis.config.genesis_height);
+        let end_height = self.end_height.unwrap_or(BlockHeight::MAX);
+        let options = HeaderValidationOptions { max_timestamp_drift: self.max_timestamp_drift };
+        match verify_headers(&store, start_height..=end_height, options) {
+            Ok(num_headers) => println!("Checked {} headers, found no issues", num_headers),
+            Err(err) => {
+                println!("{:#}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct LightProofCmd {
+    /// Hash of the transaction or id of the receipt whose outcome to prove.
+    #[clap(long)]
+    tx: CryptoHash,
+    /// Hash of the final block the light client trusts.
+    #[clap(long)]
+    light_client_head: CryptoHash,
+}
+
+impl LightProofCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        print_light_proof(&self.tx, &self.light_client_head, home_dir, near_config, store)
+            .unwrap_or_else(|err| panic!("Failed to build the light client proof: {:#}", err));
+    }
+}
+
 #[derive(Parser)]
 pub struct ApplyChunkCmd {
     #[clap(long)]
@@ -379,3 +1431,32 @@ impl ApplyReceiptCmd {
         apply_receipt(home_dir, near_config, store, hash).unwrap();
     }
 }
+
+#[derive(Parser)]
+pub struct RotateKeyCmd {
+    #[clap(long)]
+    account_id: AccountId,
+    /// Access key to replace.  Its permission is copied to the new key.
+    #[clap(long)]
+    old_public_key: PublicKey,
+    #[clap(long)]
+    new_public_key: PublicKey,
+    /// Full access key which will sign the transaction.  Defaults to the key
+    /// being replaced.
+    #[clap(long)]
+    signer_public_key: Option<PublicKey>,
+}
+
+impl RotateKeyCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        rotate_key(
+            self.account_id,
+            self.old_public_key,
+            self.new_public_key,
+            self.signer_public_key,
+            home_dir,
+            near_config,
+            store,
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use crate::output::{Output, Record};
+use near_store::{DBCol, Store};
+use serde::Serialize;
+use std::io::Write;
+
+/// Sizes of the entries of a column as stored, i.e. with the reference counts
+/// of refcounted columns.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub(crate) struct ColumnStats {
+    pub column: &'static str,
+    pub num_keys: u64,
+    pub key_bytes: u64,
+    pub value_bytes: u64,
+    /// Zero for an empty column, as are the other value sizes.
+    pub min_value_size: u64,
+    pub max_value_size: u64,
+    pub avg_value_size: u64,
+}
+
+impl ColumnStats {
+    fn add(&mut self, key_size: u64, value_size: u64) {
+        self.min_value_size =
+            if self.num_keys == 0 { value_size } else { self.min_value_size.min(value_size) };
+        self.max_value_size = self.max_value_size.max(value_size);
+        self.num_keys += 1;
+        self.key_bytes += key_size;
+        self.value_bytes += value_size;
+        self.avg_value_size = self.value_bytes / self.num_keys;
+    }
+}
+
+/// Iterates over `col` and sums up the sizes of its entries.  Only one entry
+/// is held in memory at a time.
+pub(crate) fn column_stats(store: &Store, col: DBCol) -> ColumnStats {
+    let mut stats = ColumnStats { column: col.into(), ..Default::default() };
+    for (key, value) in store.iter_raw_bytes(col) {
+        stats.add(key.len() as u64, value.len() as u64);
+    }
+    stats
+}
+
+impl Record for ColumnStats {
+    fn human(&self) -> String {
+        format!(
+            "{:<28} {:>12} {:>16} {:>16} {:>10} {:>10} {:>10}",
+            self.column,
+            self.num_keys,
+            self.key_bytes,
+            self.value_bytes,
+            self.min_value_size,
+            self.max_value_size,
+            self.avg_value_size
+        )
+    }
+}
+
+/// Writes the stats as a table with a row per column, followed by the totals
+/// in the human readable format.
+pub(crate) fn print_table<W: Write>(
+    stats: &[ColumnStats],
+    output: &mut Output<W>,
+) -> anyhow::Result<()> {
+    output.line(&format!(
+        "{:<28} {:>12} {:>16} {:>16} {:>10} {:>10} {:>10}",
+        "column", "keys", "key bytes", "value bytes", "min value", "max value", "avg value"
+    ))?;
+    let mut total = ColumnStats { column: "total", ..Default::default() };
+    for stats in stats {
+        output.record(stats)?;
+        total.num_keys += stats.num_keys;
+        total.key_bytes += stats.key_bytes;
+        total.value_bytes += stats.value_bytes;
+    }
+    if stats.len() > 1 {
+        output.line(&total.human())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{column_stats, print_table, ColumnStats};
+    use crate::output::{Output, OutputFormat};
+    use near_store::test_utils::create_test_store;
+    use near_store::DBCol;
+
+    #[test]
+    fn test_column_stats() {
+        let store = create_test_store();
+        let mut update = store.store_update();
+        update.set(DBCol::BlockMisc, b"a", &[0; 10]);
+        update.set(DBCol::BlockMisc, b"bb", &[0; 20]);
+        update.set(DBCol::BlockMisc, b"ccc", &[0; 3]);
+        update.commit().unwrap();
+
+        let stats = column_stats(&store, DBCol::BlockMisc);
+        assert_eq!(
+            stats,
+            ColumnStats {
+                column: "BlockMisc",
+                num_keys: 3,
+                key_bytes: 6,
+                value_bytes: 33,
+                min_value_size: 3,
+                max_value_size: 20,
+                avg_value_size: 11,
+            }
+        );
+        let empty = column_stats(&store, DBCol::Peers);
+        assert_eq!(empty, ColumnStats { column: "Peers", ..Default::default() });
+
+        let print = |format| {
+            let mut output = Output::new(format, vec![]);
+            print_table(&[stats.clone(), empty.clone()], &mut output).unwrap();
+            String::from_utf8(output.into_inner()).unwrap()
+        };
+        let table = print(OutputFormat::Human);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("BlockMisc"));
+        assert!(lines[3].starts_with("total"));
+        assert!(lines[3].contains(" 33 "));
+        // The totals are left out of the machine readable formats.
+        let json = print(OutputFormat::Json);
+        assert_eq!(json.lines().count(), 2);
+        assert!(json.starts_with("{\"column\":\"BlockMisc\",\"num_keys\":3,"), "{}", json);
+    }
+}
@@ -0,0 +1,160 @@
+//! How to decode the keys and values of a column.
+//!
+//! Commands which show column values should use [`COLUMN_VALUES`] rather than
+//! hard-coding value types, so that new columns only need to be added here.
+//! The same goes for [`decode_key`] and key layouts.
+
+use crate::block::sorted_hashes;
+use borsh::BorshDeserialize;
+use near_network_primitives::types::{Edge, KnownPeerState};
+use near_primitives::block::{Block, BlockHeader, BlocksPerHeight};
+use near_primitives::epoch_manager::block_info::BlockInfo;
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::PartialMerkleTree;
+use near_primitives::network::AnnounceAccount;
+use near_primitives::receipt::Receipt;
+use near_primitives::sharding::{
+    ChunkHash, EncodedShardChunk, PartialEncodedChunk, ReceiptProof, ShardChunk, StateSyncInfo,
+};
+use near_primitives::syncing::ShardStateSyncResponseHeader;
+use near_primitives::transaction::{ExecutionOutcomeWithIdAndProof, SignedTransaction};
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{
+    BlockExtra, BlockHeight, RawStateChangesWithTrieKey, ShardId, StateChangesForSplitStates,
+};
+use near_primitives::utils::get_block_shard_id_rev;
+use near_primitives::views::{
+    BlockHeaderView, ExecutionOutcomeWithIdView, LightClientBlockView, ReceiptView,
+    SignedTransactionView,
+};
+use near_store::{DBCol, TrieChanges};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::io;
+
+/// Decodes a value of a column into JSON.
+pub(crate) type Decoder = fn(&[u8]) -> io::Result<Value>;
+
+/// Borsh value with a JSON representation.
+fn json<T: BorshDeserialize + Serialize>(value: &[u8]) -> io::Result<Value> {
+    Ok(serde_json::to_value(T::try_from_slice(value)?)?)
+}
+
+/// Borsh value shown as its view.
+fn view<T: BorshDeserialize, V: From<T> + Serialize>(value: &[u8]) -> io::Result<Value> {
+    Ok(serde_json::to_value(V::from(T::try_from_slice(value)?))?)
+}
+
+/// Borsh vector of values shown as their views.
+fn views<T: BorshDeserialize, V: From<T> + Serialize>(value: &[u8]) -> io::Result<Value> {
+    let values = Vec::<T>::try_from_slice(value)?;
+    Ok(serde_json::to_value(values.into_iter().map(V::from).collect::<Vec<_>>())?)
+}
+
+/// Borsh value without a JSON representation, shown as its debug string.
+fn debug<T: BorshDeserialize + Debug>(value: &[u8]) -> io::Result<Value> {
+    Ok(Value::String(format!("{:?}", T::try_from_slice(value)?)))
+}
+
+/// Hashes of the blocks at a height, sorted as by `block_hashes_at_height`.
+fn blocks_per_height(value: &[u8]) -> io::Result<Value> {
+    Ok(serde_json::to_value(sorted_hashes(&BlocksPerHeight::try_from_slice(value)?))?)
+}
+
+/// Value stored as JSON rather than borsh.
+fn raw_json(value: &[u8]) -> io::Result<Value> {
+    Ok(serde_json::from_slice(value)?)
+}
+
+/// Decoders of the columns with a single value type.  Columns which store
+/// different types under different keys, e.g. `BlockMisc`, or raw bytes, e.g.
+/// `State`, aren't listed.
+pub(crate) const COLUMN_VALUES: &[(DBCol, Decoder)] = &[
+    (DBCol::DbVersion, raw_json),
+    (DBCol::Block, debug::<Block>),
+    (DBCol::BlockHeader, view::<BlockHeader, BlockHeaderView>),
+    (DBCol::BlockHeight, json::<CryptoHash>),
+    (DBCol::ChunkExtra, debug::<ChunkExtra>),
+    (DBCol::TransactionResult, views::<ExecutionOutcomeWithIdAndProof, ExecutionOutcomeWithIdView>),
+    (DBCol::OutgoingReceipts, views::<Receipt, ReceiptView>),
+    (DBCol::IncomingReceipts, debug::<Vec<ReceiptProof>>),
+    (DBCol::Peers, debug::<KnownPeerState>),
+    // Besides `EpochInfo`s, the column has the epoch info aggregator under
+    // `AGGREGATOR_KEY`, which fails to decode as one.
+    (DBCol::EpochInfo, debug::<EpochInfo>),
+    (DBCol::BlockInfo, debug::<BlockInfo>),
+    (DBCol::Chunks, debug::<ShardChunk>),
+    (DBCol::PartialChunks, debug::<PartialEncodedChunk>),
+    (DBCol::BlocksToCatchup, json::<Vec<CryptoHash>>),
+    (DBCol::StateDlInfos, debug::<StateSyncInfo>),
+    (DBCol::ChallengedBlocks, json::<bool>),
+    (DBCol::StateHeaders, debug::<ShardStateSyncResponseHeader>),
+    (DBCol::InvalidChunks, debug::<EncodedShardChunk>),
+    (DBCol::BlockExtra, debug::<BlockExtra>),
+    (DBCol::BlockPerHeight, blocks_per_height),
+    (DBCol::EpochStart, json::<BlockHeight>),
+    (DBCol::AccountAnnouncements, debug::<AnnounceAccount>),
+    (DBCol::NextBlockHashes, json::<CryptoHash>),
+    (DBCol::EpochLightClientBlocks, json::<LightClientBlockView>),
+    (DBCol::ReceiptIdToShardId, json::<ShardId>),
+    (DBCol::PeerComponent, json::<u64>),
+    (DBCol::ComponentEdges, debug::<Vec<Edge>>),
+    (DBCol::LastComponentNonce, json::<u64>),
+    (DBCol::Transactions, view::<SignedTransaction, SignedTransactionView>),
+    (DBCol::ChunkPerHeightShard, json::<ChunkHash>),
+    (DBCol::StateChanges, debug::<RawStateChangesWithTrieKey>),
+    (DBCol::BlockRefCount, json::<u64>),
+    (DBCol::TrieChanges, debug::<TrieChanges>),
+    (DBCol::BlockMerkleTree, debug::<PartialMerkleTree>),
+    (DBCol::ChunkHashesByHeight, json::<HashSet<ChunkHash>>),
+    (DBCol::BlockOrdinal, json::<CryptoHash>),
+    (DBCol::GCCount, json::<u64>),
+    (DBCol::OutcomeIds, json::<Vec<CryptoHash>>),
+    (DBCol::ProcessedBlockHeights, json::<()>),
+    (DBCol::Receipts, view::<Receipt, ReceiptView>),
+    (DBCol::HeaderHashesByHeight, json::<HashSet<CryptoHash>>),
+    (DBCol::StateChangesForSplitStates, debug::<StateChangesForSplitStates>),
+];
+
+/// Returns how to decode values of `col`, `None` if it has no single type.
+pub(crate) fn decoder(col: DBCol) -> Option<Decoder> {
+    COLUMN_VALUES.iter().find(|(c, _)| *c == col).map(|(_, decoder)| *decoder)
+}
+
+/// Columns keyed by `get_block_shard_id`.
+const BLOCK_SHARD_ID_KEYS: &[DBCol] = &[
+    DBCol::OutgoingReceipts,
+    DBCol::IncomingReceipts,
+    DBCol::OutcomeIds,
+    DBCol::StateChangesForSplitStates,
+];
+
+/// Decodes a key of `col` into a readable form, `None` if the keys of `col`
+/// have no known layout or `key` doesn't match it.
+pub(crate) fn decode_key(col: DBCol, key: &[u8]) -> Option<String> {
+    if BLOCK_SHARD_ID_KEYS.contains(&col) {
+        let (block_hash, shard_id) = get_block_shard_id_rev(key).ok()?;
+        return Some(format!("{} shard {}", block_hash, shard_id));
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode_key;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::utils::get_block_shard_id;
+    use near_store::DBCol;
+
+    #[test]
+    fn test_decode_key() {
+        let hash = CryptoHash::hash_bytes(b"block");
+        let key = get_block_shard_id(&hash, 3);
+        assert_eq!(decode_key(DBCol::OutcomeIds, &key), Some(format!("{} shard 3", hash)));
+        assert_eq!(decode_key(DBCol::OutcomeIds, hash.as_ref()), None);
+        assert_eq!(decode_key(DBCol::BlockHeader, &key), None);
+    }
+}
@@ -1,32 +1,39 @@
+use crate::account::AccessKeyRecord;
 use crate::apply_chain_range::apply_chain_range;
+use crate::output::{Output, OutputFormat};
 use crate::state_dump::state_dump;
 use crate::state_dump::state_dump_redis;
-use crate::{apply_chunk, epoch_info};
+use crate::state_size::state_size;
+use crate::{account, apply_chunk, check_genesis, chunk_parts, contract_code, epoch_info};
 use ansi_term::Color::Red;
+use borsh::BorshSerialize;
 use near_chain::chain::collect_receipts_from_response;
 use near_chain::migrations::check_if_block_is_first_with_chunk_of_version;
 use near_chain::types::{ApplyTransactionResult, BlockHeaderInfo};
 use near_chain::{ChainStore, ChainStoreAccess, ChainStoreUpdate, RuntimeAdapter};
+use near_crypto::PublicKey;
 use near_epoch_manager::EpochManager;
 use near_network::iter_peers_from_store;
 use near_primitives::account::id::AccountId;
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::hash::CryptoHash;
-use near_primitives::serialize::to_base;
+use near_primitives::key_rotation::build_rotation_actions;
+use near_primitives::serialize::{to_base, to_base64};
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::sharding::ChunkHash;
 use near_primitives::state_record::StateRecord;
+use near_primitives::transaction::Transaction;
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{BlockHeight, ShardId, StateRoot};
+use near_primitives::views::ActionView;
 use near_primitives_core::types::Gas;
 use near_store::test_utils::create_test_store;
 use near_store::{Store, TrieIterator};
 use nearcore::{NearConfig, NightshadeRuntime};
 use node_runtime::adapter::ViewRuntimeAdapter;
 use std::collections::HashMap;
-use std::fs::{self, File};
-use std::io::Write;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -36,6 +43,12 @@ pub(crate) fn peers(store: Store) {
     })
 }
 
+pub(crate) fn open_history(store: Store) {
+    for open in store.open_history().unwrap() {
+        println!("{}", open);
+    }
+}
+
 pub(crate) fn state(home_dir: &Path, near_config: NearConfig, store: Store) {
     let (runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
     println!("Storage roots are {:?}, block height is {}", state_roots, header.height());
@@ -136,31 +149,181 @@ pub(crate) fn apply_range(
 }
 
 pub(crate) fn dump_code(
-    account_id: String,
+    account_id: Option<AccountId>,
+    code_hash: Option<CryptoHash>,
     output: &Path,
     home_dir: &Path,
     near_config: NearConfig,
     store: Store,
+) {
+    let (runtime, state_roots, header) = load_trie(store.clone(), home_dir, &near_config);
+    let code = match (account_id, code_hash) {
+        (Some(account_id), None) => {
+            contract_code::code_of_account(&runtime, &header, &state_roots, &account_id)
+        }
+        (None, Some(code_hash)) => {
+            let shard_uids = runtime.get_shard_layout(header.epoch_id()).unwrap().get_shard_uids();
+            contract_code::code_by_hash(&store, &shard_uids, &code_hash)
+        }
+        _ => panic!("exactly one of account_id and code_hash must be given"),
+    }
+    .unwrap_or_else(|err| panic!("Failed to extract the contract code: {:#}", err));
+    fs::write(output, &code).unwrap();
+    println!(
+        "Dumped contract code of {} bytes with hash {} into file {}",
+        code.len(),
+        CryptoHash::hash_bytes(&code),
+        output.display()
+    );
+}
+
+pub(crate) fn rotate_key(
+    account_id: AccountId,
+    old_public_key: PublicKey,
+    new_public_key: PublicKey,
+    signer_public_key: Option<PublicKey>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
 ) {
     let (runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
     let epoch_id = &runtime.get_epoch_id(header.hash()).unwrap();
+    let shard_id = runtime.account_id_to_shard_id(&account_id, epoch_id).unwrap();
+    let shard_uid = runtime.shard_id_to_uid(shard_id, epoch_id).unwrap();
+    let state_root = state_roots[shard_id as usize];
+    let view_access_key = |public_key: &PublicKey| {
+        runtime
+            .view_access_key(&shard_uid, state_root, &account_id, public_key)
+            .unwrap_or_else(|err| panic!("Can't find access key {}: {}", public_key, err))
+    };
 
-    for (shard_id, state_root) in state_roots.iter().enumerate() {
-        let shard_uid = runtime.shard_id_to_uid(shard_id as u64, epoch_id).unwrap();
-        if let Ok(contract_code) =
-            runtime.view_contract_code(&shard_uid, *state_root, &account_id.parse().unwrap())
-        {
-            let mut file = File::create(output).unwrap();
-            file.write_all(contract_code.code()).unwrap();
-            println!("Dump contract of account {} into file {}", account_id, output.display());
-
-            std::process::exit(0);
+    let old_access_key = view_access_key(&old_public_key);
+    let signer_public_key = signer_public_key.unwrap_or_else(|| old_public_key.clone());
+    let signer_access_key = view_access_key(&signer_public_key);
+    let actions = build_rotation_actions(
+        &old_public_key,
+        &new_public_key,
+        old_access_key.permission,
+        &signer_access_key.permission,
+        header.height() + 1,
+    )
+    .unwrap_or_else(|err| panic!("Can't rotate key {}: {}", old_public_key, err));
+    let transaction = Transaction {
+        signer_id: account_id.clone(),
+        public_key: signer_public_key,
+        nonce: signer_access_key.nonce + 1,
+        receiver_id: account_id,
+        block_hash: *header.hash(),
+        actions,
+        priority_fee: None,
+    };
+
+    let json = serde_json::json!({
+        "signer_id": transaction.signer_id,
+        "public_key": transaction.public_key,
+        "nonce": transaction.nonce,
+        "receiver_id": transaction.receiver_id,
+        "block_hash": transaction.block_hash,
+        "actions": transaction.actions.iter().cloned().map(ActionView::from).collect::<Vec<_>>(),
+    });
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    println!("{}", to_base64(&transaction.try_to_vec().unwrap()));
+}
+
+pub(crate) fn print_account(
+    account_id: AccountId,
+    block_hash: Option<CryptoHash>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) {
+    let mode = block_hash.map_or(LoadTrieMode::Latest, LoadTrieMode::Hash);
+    let (runtime, state_roots, header) =
+        load_trie_stop_at_height(store, home_dir, &near_config, mode);
+    account::dump_account(
+        &runtime,
+        &header,
+        &state_roots,
+        &account_id,
+        &mut std::io::stdout().lock(),
+    )
+    .unwrap_or_else(|err| panic!("Failed to dump account {}: {:#}", account_id, err));
+}
+
+/// Prints the access keys of `account_id`, or of the implicit accounts
+/// starting with `implicit_prefix` if no account is given.
+pub(crate) fn print_access_keys(
+    account_id: Option<AccountId>,
+    implicit_prefix: &str,
+    block_hash: Option<CryptoHash>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+    format: OutputFormat,
+) {
+    let mode = block_hash.map_or(LoadTrieMode::Latest, LoadTrieMode::Hash);
+    let (runtime, state_roots, header) =
+        load_trie_stop_at_height(store, home_dir, &near_config, mode);
+    let mut output = Output::stdout(format);
+    output
+        .line(&format!("Access keys before block {} at height {}", header.hash(), header.height()))
+        .unwrap();
+    let mut on_key = |key: AccessKeyRecord| output.record(&key);
+    match account_id {
+        Some(account_id) => {
+            account::account_access_keys(&runtime, &header, &state_roots, &account_id, &mut on_key)
+                .unwrap_or_else(|err| {
+                    panic!("Failed to list the access keys of {}: {:#}", account_id, err)
+                })
         }
+        None => account::implicit_access_keys(
+            &runtime,
+            &header,
+            &state_roots,
+            implicit_prefix,
+            &mut on_key,
+        )
+        .unwrap_or_else(|err| panic!("Failed to list the access keys: {:#}", err)),
+    }
+}
+
+/// Prints the `top` accounts of the shard with the most bytes in the trie.
+pub(crate) fn print_state_size(
+    shard_id: ShardId,
+    top: usize,
+    block_hash: Option<CryptoHash>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+    format: OutputFormat,
+) {
+    let mode = block_hash.map_or(LoadTrieMode::Latest, LoadTrieMode::Hash);
+    let (runtime, state_roots, header) =
+        load_trie_stop_at_height(store, home_dir, &near_config, mode);
+    let state_root = state_roots
+        .get(shard_id as usize)
+        .unwrap_or_else(|| panic!("There is no shard {}", shard_id));
+    let trie = runtime.get_trie_for_shard(shard_id, header.prev_hash()).unwrap();
+    let (largest, shard) = state_size(&trie, state_root, top)
+        .unwrap_or_else(|err| panic!("Failed to walk the trie of shard {}: {:#}", shard_id, err));
+    let mut output = Output::stdout(format);
+    output
+        .line(&format!(
+            "Shard {} before block {} at height {} has {} accounts with {} bytes of account \
+             records, {} of contract code, {} of access keys and {} of contract data",
+            shard_id,
+            header.hash(),
+            header.height(),
+            shard.num_accounts,
+            shard.account,
+            shard.contract_code,
+            shard.access_keys,
+            shard.contract_data
+        ))
+        .unwrap();
+    for size in &largest {
+        output.record(size).unwrap();
     }
-    println!(
-        "Account {} does not exist or do not have contract deployed in all shards",
-        account_id
-    );
 }
 
 pub(crate) fn dump_account_storage(
@@ -547,7 +710,7 @@ pub(crate) fn view_chain(
 
     if height.is_none() {
         let head = chain_store.head().unwrap();
-        println!("head: {:#?}", head);
+        println!("head: {}", head);
     } else {
         println!("block height {}, hash {}", block.header().height(), block.hash());
     }
@@ -592,9 +755,19 @@ pub(crate) fn check_block_chunk_existence(store: Store, near_config: NearConfig)
     println!("Block check succeed");
 }
 
+pub(crate) fn check_genesis(genesis_path: &Path, near_config: NearConfig, store: Store) {
+    let mut chain_store = ChainStore::new(
+        store.clone(),
+        near_config.genesis.config.genesis_height,
+        !near_config.client_config.archive,
+    );
+    check_genesis::check_genesis(genesis_path, &near_config.genesis, store, &mut chain_store);
+}
+
 pub(crate) fn print_epoch_info(
     epoch_selection: epoch_info::EpochSelection,
     validator_account_id: Option<AccountId>,
+    list: bool,
     home_dir: &Path,
     near_config: NearConfig,
     store: Store,
@@ -616,6 +789,7 @@ pub(crate) fn print_epoch_info(
     epoch_info::print_epoch_info(
         epoch_selection,
         validator_account_id,
+        list,
         store,
         &mut chain_store,
         &mut epoch_manager,
@@ -657,6 +831,45 @@ pub(crate) fn get_partial_chunk(
     println!("Partial chunk: {:#?}", partial_chunk);
 }
 
+pub(crate) enum ChunkPartsSelection {
+    Chunk(ChunkHash),
+    /// Inclusive range of heights.
+    Heights(BlockHeight, BlockHeight),
+}
+
+pub(crate) fn print_chunk_parts(
+    selection: ChunkPartsSelection,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) {
+    let mut chain_store = ChainStore::new(
+        store.clone(),
+        near_config.genesis.config.genesis_height,
+        !near_config.client_config.archive,
+    );
+    let runtime = NightshadeRuntime::with_config(
+        home_dir,
+        store,
+        &near_config,
+        None,
+        near_config.client_config.max_gas_burnt_view,
+    );
+    match selection {
+        ChunkPartsSelection::Chunk(chunk_hash) => {
+            chunk_parts::print_chunk_parts(chunk_hash, &mut chain_store, &runtime)
+        }
+        ChunkPartsSelection::Heights(start_height, end_height) => {
+            chunk_parts::print_missing_parts_by_height(
+                start_height,
+                end_height,
+                &mut chain_store,
+                &runtime,
+            )
+        }
+    }
+}
+
 #[allow(unused)]
 enum LoadTrieMode {
     /// Load latest state
@@ -665,6 +878,8 @@ enum LoadTrieMode {
     Height(BlockHeight),
     /// Load the prev state of the last final block from some height
     LastFinalFromHeight(BlockHeight),
+    /// Load prev state of the block with the given hash
+    Hash(CryptoHash),
 }
 
 fn load_trie(
@@ -725,6 +940,7 @@ fn load_trie_stop_at_height(
             let block_hash = chain_store.get_block_hash_by_height(height).unwrap();
             chain_store.get_block(&block_hash).unwrap().clone()
         }
+        LoadTrieMode::Hash(block_hash) => chain_store.get_block(&block_hash).unwrap().clone(),
         LoadTrieMode::Latest => chain_store.get_block(&head.last_block_hash).unwrap().clone(),
     };
     let state_roots = last_block.chunks().iter().map(|chunk| chunk.prev_state_root()).collect();
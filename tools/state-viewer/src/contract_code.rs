@@ -0,0 +1,114 @@
+use near_chain::RuntimeAdapter;
+use near_primitives::account::id::AccountId;
+use near_primitives::block::BlockHeader;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::types::StateRoot;
+use near_store::{DBCol, Store};
+use nearcore::NightshadeRuntime;
+use node_runtime::adapter::ViewRuntimeAdapter;
+
+/// Checks that `code` hashes to `expected`, as a corrupted database could
+/// store different bytes.
+fn check_code_hash(code: &[u8], expected: &CryptoHash) -> anyhow::Result<()> {
+    let actual = hash(code);
+    anyhow::ensure!(actual == *expected, "code hashes to {} instead of {}", actual, expected);
+    Ok(())
+}
+
+/// Reads the contract code deployed to `account_id` from the trie.
+pub(crate) fn code_of_account(
+    runtime: &NightshadeRuntime,
+    header: &BlockHeader,
+    state_roots: &[StateRoot],
+    account_id: &AccountId,
+) -> anyhow::Result<Vec<u8>> {
+    let epoch_id = header.epoch_id();
+    let shard_id = runtime.account_id_to_shard_id(account_id, epoch_id)?;
+    let shard_uid = runtime.shard_id_to_uid(shard_id, epoch_id)?;
+    let state_root = state_roots[shard_id as usize];
+    let account = runtime.view_account(&shard_uid, state_root, account_id)?;
+    anyhow::ensure!(
+        account.code_hash() != CryptoHash::default(),
+        "account {} has no contract deployed",
+        account_id
+    );
+    let code = runtime.view_contract_code(&shard_uid, state_root, account_id)?.into_code();
+    check_code_hash(&code, &account.code_hash())?;
+    Ok(code)
+}
+
+/// Reads the contract code with the given hash.  Trie values are stored in
+/// `DBCol::State` under the shard and the hash of the value, so this finds
+/// the code as long as some account in one of the shards has it deployed.
+pub(crate) fn code_by_hash(
+    store: &Store,
+    shard_uids: &[ShardUId],
+    code_hash: &CryptoHash,
+) -> anyhow::Result<Vec<u8>> {
+    for shard_uid in shard_uids {
+        let key = [&shard_uid.to_bytes()[..], code_hash.as_ref()].concat();
+        if let Some(code) = store.get(DBCol::State, &key)? {
+            check_code_hash(&code, code_hash)?;
+            return Ok(code);
+        }
+    }
+    anyhow::bail!("no value with hash {} is stored in shards {:?}", code_hash, shard_uids)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{code_by_hash, code_of_account};
+    use crate::test_utils::setup_env_with_genesis;
+    use near_chain::ChainGenesis;
+    use near_chain_configs::Genesis;
+    use near_primitives::hash::hash;
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::state_record::StateRecord;
+    use near_store::DBCol;
+    use nearcore::config::GenesisExt;
+
+    #[test]
+    fn test_contract_code() {
+        let code = b"not really wasm".to_vec();
+        let mut genesis =
+            Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+        for record in genesis.records.0.iter_mut() {
+            if let StateRecord::Account { account_id, account } = record {
+                if account_id.as_ref() == "test1" {
+                    account.set_code_hash(hash(&code));
+                }
+            }
+        }
+        genesis.records.0.push(StateRecord::Contract {
+            account_id: "test1".parse().unwrap(),
+            code: code.clone(),
+        });
+        let mut setup = setup_env_with_genesis(genesis, ChainGenesis::test(), 2);
+        let (store, runtime) = (setup.store.clone(), setup.runtime.clone());
+        let head = setup.env.clients[0].chain.head().unwrap();
+        let block = setup.env.clients[0].chain.get_block(&head.last_block_hash).unwrap().clone();
+        let state_roots: Vec<_> =
+            block.chunks().iter().map(|chunk| chunk.prev_state_root()).collect();
+
+        let account_code =
+            code_of_account(&runtime, block.header(), &state_roots, &"test1".parse().unwrap());
+        assert_eq!(account_code.unwrap(), code);
+        let no_code =
+            code_of_account(&runtime, block.header(), &state_roots, &"test0".parse().unwrap());
+        assert!(no_code.unwrap_err().to_string().contains("no contract deployed"));
+
+        let shard_uids = [ShardUId::single_shard()];
+        assert_eq!(code_by_hash(&store, &shard_uids, &hash(&code)).unwrap(), code);
+        assert!(code_by_hash(&store, &shard_uids, &hash(b"bar")).is_err());
+
+        // Stored bytes which don't match their hash are rejected.
+        let foo_hash = hash(b"foo");
+        let key = [&ShardUId::single_shard().to_bytes()[..], foo_hash.as_ref()].concat();
+        let mut update = store.store_update();
+        update.update_refcount(DBCol::State, &key, b"tampered", 1);
+        update.commit().unwrap();
+        let err = code_by_hash(&store, &shard_uids, &foo_hash).unwrap_err();
+        assert!(err.to_string().contains("instead of"), "{}", err);
+    }
+}
@@ -0,0 +1,131 @@
+use near_store::{DBCol, Store};
+use std::cmp::Ordering;
+
+/// How an entry of one database differs from the other one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Difference {
+    OnlyInFirst,
+    OnlyInSecond,
+    DifferentValues,
+}
+
+/// Numbers of differing entries of a column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DiffSummary {
+    pub only_in_first: u64,
+    pub only_in_second: u64,
+    pub different_values: u64,
+}
+
+impl DiffSummary {
+    pub fn total(&self) -> u64 {
+        self.only_in_first + self.only_in_second + self.different_values
+    }
+
+    fn add(&mut self, difference: Difference) {
+        match difference {
+            Difference::OnlyInFirst => self.only_in_first += 1,
+            Difference::OnlyInSecond => self.only_in_second += 1,
+            Difference::DifferentValues => self.different_values += 1,
+        }
+    }
+}
+
+/// Compares `col` in the two databases and calls `on_difference` for every
+/// key which differs, in key order.  Stops once `max_diffs` differences are
+/// found, so the summary only covers the keys up to there.
+///
+/// Both columns are iterated at once in key order, so neither is held in
+/// memory.
+pub(crate) fn diff_column(
+    first: &Store,
+    second: &Store,
+    col: DBCol,
+    max_diffs: u64,
+    on_difference: &mut dyn FnMut(&[u8], Difference),
+) -> DiffSummary {
+    let mut summary = DiffSummary::default();
+    let mut first_iter = first.iter(col).peekable();
+    let mut second_iter = second.iter(col).peekable();
+    while summary.total() < max_diffs {
+        let (key, difference) = match (first_iter.peek(), second_iter.peek()) {
+            (None, None) => break,
+            (Some(_), None) => (first_iter.next().unwrap().0, Difference::OnlyInFirst),
+            (None, Some(_)) => (second_iter.next().unwrap().0, Difference::OnlyInSecond),
+            (Some((first_key, _)), Some((second_key, _))) => match first_key.cmp(second_key) {
+                Ordering::Less => (first_iter.next().unwrap().0, Difference::OnlyInFirst),
+                Ordering::Greater => (second_iter.next().unwrap().0, Difference::OnlyInSecond),
+                Ordering::Equal => {
+                    let (key, first_value) = first_iter.next().unwrap();
+                    let (_, second_value) = second_iter.next().unwrap();
+                    if first_value == second_value {
+                        continue;
+                    }
+                    (key, Difference::DifferentValues)
+                }
+            },
+        };
+        on_difference(&key, difference);
+        summary.add(difference);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff_column, DiffSummary, Difference};
+    use near_store::test_utils::create_test_store;
+    use near_store::DBCol;
+
+    #[test]
+    fn test_diff_column() {
+        let first = create_test_store();
+        let second = create_test_store();
+        let mut update = first.store_update();
+        update.set(DBCol::BlockMisc, b"a", b"1");
+        update.set(DBCol::BlockMisc, b"b", b"2");
+        update.set(DBCol::BlockMisc, b"d", b"4");
+        update.set(DBCol::BlockMisc, b"e", b"5");
+        update.commit().unwrap();
+        let mut update = second.store_update();
+        update.set(DBCol::BlockMisc, b"b", b"2");
+        update.set(DBCol::BlockMisc, b"c", b"3");
+        update.set(DBCol::BlockMisc, b"d", b"x");
+        update.commit().unwrap();
+
+        let diff = |max_diffs| {
+            let mut differences = vec![];
+            let summary = diff_column(
+                &first,
+                &second,
+                DBCol::BlockMisc,
+                max_diffs,
+                &mut |key, difference| differences.push((key.to_vec(), difference)),
+            );
+            (summary, differences)
+        };
+        let (summary, differences) = diff(u64::MAX);
+        assert_eq!(
+            differences,
+            vec![
+                (b"a".to_vec(), Difference::OnlyInFirst),
+                (b"c".to_vec(), Difference::OnlyInSecond),
+                (b"d".to_vec(), Difference::DifferentValues),
+                (b"e".to_vec(), Difference::OnlyInFirst),
+            ]
+        );
+        assert_eq!(
+            summary,
+            DiffSummary { only_in_first: 2, only_in_second: 1, different_values: 1 }
+        );
+
+        let (summary, differences) = diff(2);
+        assert_eq!(differences.len(), 2);
+        assert_eq!(summary.total(), 2);
+
+        let summary = diff_column(&first, &first, DBCol::BlockMisc, u64::MAX, &mut |_, _| {
+            panic!("a database differs from itself")
+        });
+        assert_eq!(summary, DiffSummary::default());
+    }
+}
@@ -0,0 +1,112 @@
+use crate::column_values::{decode_key, decoder};
+use near_store::{DBCol, Store};
+use serde_json::{json, Value};
+use std::io::Write;
+
+/// Decodes an entry of `col` into a JSON object with the key in hex, also
+/// decoded if its layout is known, and the decoded value, or the value in hex
+/// if it can't be decoded.
+pub(crate) fn entry_to_json(col: DBCol, key: &[u8], value: &[u8]) -> Value {
+    let decoded = match decoder(col) {
+        Some(decode) => decode(value).map_err(|err| err.to_string()),
+        None => Err(format!("values of {:?} have no single type", col)),
+    };
+    let mut entry = match decoded {
+        Ok(value) => json!({ "key": hex::encode(key), "value": value }),
+        Err(err) => {
+            json!({ "key": hex::encode(key), "value_hex": hex::encode(value), "error": err })
+        }
+    };
+    if let Some(decoded_key) = decode_key(col, key) {
+        entry["decoded_key"] = Value::String(decoded_key);
+    }
+    entry
+}
+
+/// Writes the entries of `col` whose keys start with `prefix` as JSON lines,
+/// at most `limit` of them.  Returns the number of entries written.
+pub(crate) fn dump_column(
+    store: &Store,
+    col: DBCol,
+    prefix: &[u8],
+    limit: Option<usize>,
+    output: &mut dyn Write,
+) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for (key, value) in store.iter_prefix(col, prefix).take(limit.unwrap_or(usize::MAX)) {
+        serde_json::to_writer(&mut *output, &entry_to_json(col, &key, &value))?;
+        writeln!(output)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{dump_column, entry_to_json};
+    use crate::column_values::COLUMN_VALUES;
+    use crate::test_utils::setup_env;
+    use near_primitives::serialize::to_base;
+    use near_store::DBCol;
+    use serde_json::Value;
+
+    #[test]
+    fn test_dump_column() {
+        let mut setup = setup_env(&["test0", "test1"], 0);
+        setup.send_money(1, "test0", "test1");
+        setup.produce_blocks(1..6);
+        let store = setup.store;
+
+        // Every entry of every column with a decoder decodes.
+        let mut decoded_cols = vec![];
+        for (col, _) in COLUMN_VALUES {
+            for (key, value) in store.iter(*col) {
+                let entry = entry_to_json(*col, &key, &value);
+                if *col == DBCol::EpochInfo && entry.get("error").is_some() {
+                    // The epoch info aggregator.
+                    continue;
+                }
+                assert!(entry.get("value").is_some(), "{:?}: {}", col, entry);
+                decoded_cols.push(*col);
+            }
+        }
+        decoded_cols.dedup();
+        for col in [
+            DBCol::Block,
+            DBCol::BlockHeader,
+            DBCol::TransactionResult,
+            DBCol::Transactions,
+            DBCol::Receipts,
+            DBCol::EpochInfo,
+        ] {
+            assert!(decoded_cols.contains(&col), "no {:?} entries", col);
+        }
+
+        // Limit and prefix.
+        let dump = |prefix: &[u8], limit| {
+            let mut output = vec![];
+            let count =
+                dump_column(&store, DBCol::BlockHeader, prefix, limit, &mut output).unwrap();
+            let lines: Vec<Value> = std::str::from_utf8(&output)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            assert_eq!(lines.len(), count);
+            lines
+        };
+        let all = dump(&[], None);
+        assert_eq!(all.len(), 6);
+        assert_eq!(dump(&[], Some(2)), all[..2]);
+        let key = all[3]["key"].as_str().unwrap();
+        assert_eq!(all[3]["value"]["hash"], to_base(hex::decode(key).unwrap()));
+        let prefixed = dump(&hex::decode(key).unwrap()[..4], None);
+        assert_eq!(prefixed, all[3..4]);
+
+        // Values of columns without a decoder are dumped in hex.
+        let mut output = vec![];
+        dump_column(&store, DBCol::BlockMisc, &[], Some(1), &mut output).unwrap();
+        let entry: Value = serde_json::from_slice(&output).unwrap();
+        assert!(entry["value_hex"].is_string(), "{}", entry);
+    }
+}
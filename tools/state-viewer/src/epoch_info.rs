@@ -7,7 +7,9 @@ use near_primitives::account::id::AccountId;
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::epoch_manager::AGGREGATOR_KEY;
 use near_primitives::hash::CryptoHash;
-use near_primitives::types::{BlockHeight, EpochHeight, EpochId, ProtocolVersion, ShardId};
+use near_primitives::types::{
+    BlockHeight, EpochHeight, EpochId, ProtocolVersion, ShardId, ValidatorId,
+};
 use near_store::{DBCol, Store};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -33,12 +35,17 @@ pub(crate) enum EpochSelection {
 pub(crate) fn print_epoch_info(
     epoch_selection: EpochSelection,
     validator_account_id: Option<AccountId>,
+    list: bool,
     store: Store,
     chain_store: &mut ChainStore,
     epoch_manager: &mut EpochManager,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
 ) {
-    let epoch_ids = get_epoch_ids(epoch_selection, store, chain_store, epoch_manager);
+    let epoch_ids = get_epoch_ids(epoch_selection, store.clone(), chain_store, epoch_manager);
+    if list {
+        list_epochs(&epoch_ids, &store, epoch_manager);
+        return;
+    }
 
     let head_block_info =
         epoch_manager.get_block_info(&chain_store.head().unwrap().last_block_hash).unwrap();
@@ -67,6 +74,65 @@ pub(crate) fn print_epoch_info(
     println!("Found {} epochs", epoch_ids.len());
 }
 
+// Prints a line per epoch, sorted by epoch height, with its start height from DBCol::EpochStart.
+// Epochs which haven't started yet have no start height.
+fn list_epochs(epoch_ids: &[EpochId], store: &Store, epoch_manager: &mut EpochManager) {
+    let mut epochs: Vec<(EpochId, Arc<EpochInfo>)> = epoch_ids
+        .iter()
+        .map(|epoch_id| (epoch_id.clone(), epoch_manager.get_epoch_info(epoch_id).unwrap()))
+        .collect();
+    epochs.sort_by_key(|(_, epoch_info)| epoch_info.epoch_height());
+    for (epoch_id, epoch_info) in epochs {
+        let start_height = store
+            .get_ser::<BlockHeight>(DBCol::EpochStart, epoch_id.as_ref())
+            .unwrap()
+            .map_or("not started".to_string(), |height| height.to_string());
+        println!(
+            "#{} {:?} start height: {}, protocol version: {}, validators: {}",
+            epoch_info.epoch_height(),
+            epoch_id,
+            start_height,
+            epoch_info.protocol_version(),
+            epoch_info.validators_iter().len()
+        );
+    }
+    println!("Found {} epochs", epoch_ids.len());
+}
+
+// Describes the validators of an epoch: per validator its stake, its block producer seats and
+// the shards it produces chunks of, followed by the kicked out validators.
+fn validators_summary(epoch_info: &EpochInfo) -> Vec<String> {
+    let mut lines = vec![];
+    for (validator_id, validator) in epoch_info.validators_iter().enumerate() {
+        let validator_id = validator_id as ValidatorId;
+        let block_producer_seats = epoch_info
+            .block_producers_settlement()
+            .iter()
+            .filter(|&&id| id == validator_id)
+            .count();
+        let chunk_producer_shards: Vec<ShardId> = epoch_info
+            .chunk_producers_settlement()
+            .iter()
+            .enumerate()
+            .filter(|(_, validator_ids)| validator_ids.contains(&validator_id))
+            .map(|(shard_id, _)| shard_id as ShardId)
+            .collect();
+        lines.push(format!(
+            "Validator {} stake: {}, block producer seats: {}, chunk producer of shards: {:?}",
+            validator.account_id(),
+            validator.stake(),
+            block_producer_seats,
+            chunk_producer_shards
+        ));
+    }
+    let mut kickouts: Vec<_> = epoch_info.validator_kickout().iter().collect();
+    kickouts.sort_by_key(|(account_id, _)| *account_id);
+    for (account_id, reason) in kickouts {
+        lines.push(format!("Kicked out {}: {:?}", account_id, reason));
+    }
+    lines
+}
+
 // Iterate over each epoch starting from the head. Find the requested epoch and its previous epoch
 // and use that to determine the block range corresponding to the epoch.
 fn get_block_height_range(
@@ -169,6 +235,9 @@ fn display_epoch_info(
     runtime_adapter: Arc<dyn RuntimeAdapter>,
 ) {
     println!("{:?}: {:#?}", epoch_id, epoch_info);
+    for line in validators_summary(epoch_info) {
+        println!("{}", line);
+    }
     if epoch_info.epoch_height() >= *head_epoch_height {
         println!("Epoch information for this epoch is not yet available, skipping.");
         return;
@@ -242,3 +311,35 @@ fn display_validator_info(
         );
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::validators_summary;
+    use near_epoch_manager::test_utils::epoch_info;
+    use near_primitives::types::ValidatorKickoutReason;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_validators_summary() {
+        let epoch_info = epoch_info(
+            3,
+            vec![("test0".parse().unwrap(), 300), ("test1".parse().unwrap(), 100)],
+            vec![0, 0, 1],
+            vec![vec![0], vec![0, 1]],
+            vec![],
+            vec![],
+            BTreeMap::new(),
+            vec![("test2".parse().unwrap(), ValidatorKickoutReason::Unstaked)],
+            HashMap::new(),
+            0,
+        );
+        assert_eq!(
+            validators_summary(&epoch_info),
+            vec![
+                "Validator test0 stake: 300, block producer seats: 2, chunk producer of shards: [0, 1]",
+                "Validator test1 stake: 100, block producer seats: 1, chunk producer of shards: [1]",
+                "Kicked out test2: Unstaked",
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::output::Record;
+use near_primitives::serialize::from_base;
+use near_store::{DBCol, Store};
+use serde::Serialize;
+
+/// Parses a pattern given as `0x` prefixed hex, as text if `text` is set, or
+/// as base58 otherwise, which is how hashes are usually printed.
+pub(crate) fn parse_pattern(pattern: &str, text: bool) -> anyhow::Result<Vec<u8>> {
+    let bytes = if text {
+        pattern.as_bytes().to_vec()
+    } else if let Some(hex) = pattern.strip_prefix("0x") {
+        hex::decode(hex)?
+    } else {
+        from_base(pattern).map_err(|err| anyhow::anyhow!("{} is not base58: {}", pattern, err))?
+    };
+    anyhow::ensure!(!bytes.is_empty(), "the pattern is empty");
+    Ok(bytes)
+}
+
+/// A key containing the pattern.
+#[derive(Serialize)]
+pub(crate) struct FoundKey {
+    pub column: &'static str,
+    /// The key in hex.
+    pub key: String,
+    /// The key decoded, if the column's keys have a known layout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded_key: Option<String>,
+    pub value_size: usize,
+}
+
+impl Record for FoundKey {
+    fn human(&self) -> String {
+        match &self.decoded_key {
+            Some(decoded) => {
+                format!("{} {} ({}) {} bytes", self.column, self.key, decoded, self.value_size)
+            }
+            None => format!("{} {} {} bytes", self.column, self.key, self.value_size),
+        }
+    }
+}
+
+fn contains(key: &[u8], pattern: &[u8]) -> bool {
+    key.windows(pattern.len()).any(|window| window == pattern)
+}
+
+/// Calls `on_match` with the column, key and value size of every key of
+/// `cols` containing `pattern`, or starting with it if `prefix_only` is set,
+/// until `max_results` are found.  Returns the number of matches.
+///
+/// Every key has to be read unless `prefix_only` is set, in which case only
+/// the matching keys are.
+pub(crate) fn find_keys(
+    store: &Store,
+    cols: &[DBCol],
+    pattern: &[u8],
+    prefix_only: bool,
+    max_results: usize,
+    on_match: &mut dyn FnMut(DBCol, &[u8], usize),
+) -> usize {
+    let mut num_matches = 0;
+    for &col in cols {
+        let entries = if prefix_only { store.iter_prefix(col, pattern) } else { store.iter(col) };
+        for (key, value) in entries {
+            if num_matches == max_results {
+                return num_matches;
+            }
+            if prefix_only || contains(&key, pattern) {
+                on_match(col, &key, value.len());
+                num_matches += 1;
+            }
+        }
+    }
+    num_matches
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_keys, parse_pattern};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::utils::get_block_shard_id;
+    use near_store::test_utils::create_test_store;
+    use near_store::DBCol;
+
+    #[test]
+    fn test_parse_pattern() {
+        let hash = CryptoHash::hash_bytes(b"block");
+        assert_eq!(parse_pattern(&hash.to_string(), false).unwrap(), hash.as_ref());
+        assert_eq!(parse_pattern("0x0aff", false).unwrap(), vec![10, 255]);
+        assert_eq!(parse_pattern("test0", true).unwrap(), b"test0");
+        assert!(parse_pattern("0xzz", false).is_err());
+        assert!(parse_pattern("0OIl", false).is_err());
+        assert!(parse_pattern("", true).is_err());
+    }
+
+    #[test]
+    fn test_find_keys() {
+        let store = create_test_store();
+        let hash = CryptoHash::hash_bytes(b"block");
+        let other = CryptoHash::hash_bytes(b"other");
+        let mut update = store.store_update();
+        update.set(DBCol::BlockHeader, hash.as_ref(), b"header");
+        update.set(DBCol::BlockHeader, other.as_ref(), b"other header");
+        update.set(DBCol::ChunkExtra, &get_block_shard_id(&hash, 1), b"extra");
+        update.set(DBCol::IncomingReceipts, &get_block_shard_id(&other, 0), b"receipts");
+        update.set(DBCol::BlockMisc, &[b"prefix", hash.as_ref()].concat(), b"misc");
+        update.commit().unwrap();
+
+        let cols =
+            [DBCol::BlockHeader, DBCol::ChunkExtra, DBCol::IncomingReceipts, DBCol::BlockMisc];
+        let find = |prefix_only, max_results| {
+            let mut matches = vec![];
+            let num_matches = find_keys(
+                &store,
+                &cols,
+                hash.as_ref(),
+                prefix_only,
+                max_results,
+                &mut |col, key, value_size| matches.push((col, key.to_vec(), value_size)),
+            );
+            assert_eq!(num_matches, matches.len());
+            matches
+        };
+        assert_eq!(
+            find(false, usize::MAX),
+            vec![
+                (DBCol::BlockHeader, hash.as_ref().to_vec(), 6),
+                (DBCol::ChunkExtra, get_block_shard_id(&hash, 1), 5),
+                (DBCol::BlockMisc, [b"prefix", hash.as_ref()].concat(), 4),
+            ]
+        );
+        // The key in BlockMisc doesn't start with the hash.
+        assert_eq!(find(true, usize::MAX).len(), 2);
+        assert_eq!(find(false, 2).len(), 2);
+    }
+}
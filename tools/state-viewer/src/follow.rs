@@ -0,0 +1,94 @@
+use crate::commands::chunk_mask_to_str;
+use near_primitives::block::{BlockHeader, Tip};
+use near_primitives::types::BlockHeight;
+use near_store::{try_create_store_with_config, DBCol, Store, StoreConfig, HEAD_KEY};
+use std::path::Path;
+use std::time::Duration;
+
+/// Returns headers of the canonical chain above `last_height` up to the head,
+/// oldest first, or just the head if `last_height` is `None`.
+pub(crate) fn blocks_since(
+    store: &Store,
+    last_height: Option<BlockHeight>,
+) -> anyhow::Result<Vec<BlockHeader>> {
+    let head = store
+        .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+        .ok_or_else(|| anyhow::anyhow!("the database has no head"))?;
+    let mut headers = vec![];
+    let mut hash = head.last_block_hash;
+    while let Some(header) = store.get_ser::<BlockHeader>(DBCol::BlockHeader, hash.as_ref())? {
+        if last_height.map_or(!headers.is_empty(), |last_height| header.height() <= last_height) {
+            break;
+        }
+        hash = *header.prev_hash();
+        headers.push(header);
+    }
+    headers.reverse();
+    Ok(headers)
+}
+
+pub(crate) fn format_block(header: &BlockHeader) -> String {
+    format!(
+        "{} {} {} {}",
+        header.height(),
+        header.hash(),
+        header.timestamp(),
+        chunk_mask_to_str(header.chunk_mask())
+    )
+}
+
+/// Prints a line per new block on the canonical chain, polling the database
+/// every `interval`.
+///
+/// A read-only RocksDB instance doesn't see writes made after it was opened,
+/// so the database is reopened on every poll.  Failing to open or read it,
+/// e.g. because the node deleted files after a compaction in the meantime, is
+/// reported and retried on the next poll.
+pub(crate) fn follow_head(store_path: &Path, store_config: &StoreConfig, interval: Duration) -> ! {
+    let mut last_height = None;
+    loop {
+        let headers = try_create_store_with_config(store_path, store_config)
+            .map_err(anyhow::Error::from)
+            .and_then(|store| blocks_since(&store, last_height));
+        match headers {
+            Ok(headers) => {
+                for header in headers {
+                    println!("{}", format_block(&header));
+                    last_height = Some(header.height());
+                }
+            }
+            Err(err) => eprintln!("Failed to read the head, retrying: {:#}", err),
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{blocks_since, format_block};
+    use crate::test_utils::setup_env;
+
+    #[test]
+    fn test_blocks_since() {
+        let mut setup = setup_env(&["test0", "test1"], 3);
+        let store = setup.store.clone();
+        let heights = |last_height| {
+            blocks_since(&store, last_height)
+                .unwrap()
+                .iter()
+                .map(|header| header.height())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(heights(None), vec![3]);
+        assert_eq!(heights(Some(3)), Vec::<u64>::new());
+        // Heights without blocks are skipped.
+        setup.produce_blocks(5..7);
+        assert_eq!(heights(Some(3)), vec![5, 6]);
+
+        let head = &blocks_since(&store, None).unwrap()[0];
+        let line = format_block(head);
+        assert!(line.starts_with(&format!("6 {} ", head.hash())), "{}", line);
+        assert!(line.ends_with(" ."), "{}", line);
+    }
+}
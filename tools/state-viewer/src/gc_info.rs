@@ -0,0 +1,258 @@
+use crate::block_refs::{Referenced, COLUMN_BLOCK_REFS};
+use crate::output::Record;
+use near_chain::RuntimeAdapter;
+use near_primitives::block::{BlockHeader, Tip};
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::to_base;
+use near_primitives::types::BlockHeight;
+use near_primitives::utils::index_to_bytes;
+use near_store::{DBCol, Store, CHUNK_TAIL_KEY, FORK_TAIL_KEY, TAIL_KEY};
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+
+/// The heights up to which GC has removed data, as stored in `BlockMisc`.
+/// They are the genesis height until GC runs for the first time.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct Tails {
+    /// Height of the last block collected on the canonical chain.
+    pub tail: BlockHeight,
+    /// Chunks created below this height are collected.
+    pub chunk_tail: BlockHeight,
+    /// Height down to which forks are collected.
+    pub fork_tail: BlockHeight,
+}
+
+pub(crate) fn read_tails(store: &Store, genesis_height: BlockHeight) -> anyhow::Result<Tails> {
+    let read = |key: &[u8]| -> anyhow::Result<BlockHeight> {
+        Ok(store.get_ser(DBCol::BlockMisc, key)?.unwrap_or(genesis_height))
+    };
+    Ok(Tails {
+        tail: read(TAIL_KEY)?,
+        chunk_tail: read(CHUNK_TAIL_KEY)?,
+        fork_tail: read(FORK_TAIL_KEY)?,
+    })
+}
+
+/// Counts the epochs which have blocks above the tail, from the epoch of the
+/// head back.  The oldest of them is usually partially collected.
+pub(crate) fn count_epochs(
+    store: &Store,
+    runtime: &dyn RuntimeAdapter,
+    head_hash: &CryptoHash,
+    tail: BlockHeight,
+) -> anyhow::Result<u64> {
+    let mut num_epochs = 0;
+    let mut hash = *head_hash;
+    loop {
+        num_epochs += 1;
+        let start_height = runtime.get_epoch_start_height(&hash)?;
+        if start_height <= tail + 1 {
+            return Ok(num_epochs);
+        }
+        let first_block_hash: CryptoHash = store
+            .get_ser(DBCol::BlockHeight, &index_to_bytes(start_height))?
+            .ok_or_else(|| anyhow::anyhow!("no block at epoch start height {}", start_height))?;
+        let prev_hash = *header(store, &first_block_hash)?.prev_hash();
+        if header(store, &prev_hash)?.height() <= tail {
+            return Ok(num_epochs);
+        }
+        hash = prev_hash;
+    }
+}
+
+fn header(store: &Store, hash: &CryptoHash) -> anyhow::Result<BlockHeader> {
+    store
+        .get_ser(DBCol::BlockHeader, hash.as_ref())?
+        .ok_or_else(|| anyhow::anyhow!("header of {} is not in the database", hash))
+}
+
+/// Entries of a column which GC should have removed.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct ColumnLeak {
+    #[serde(rename = "column", serialize_with = "serialize_col")]
+    pub col: DBCol,
+    pub num_keys: u64,
+    /// The first keys found, up to the requested number.
+    #[serde(serialize_with = "serialize_hex_keys")]
+    pub example_keys: Vec<Vec<u8>>,
+}
+
+fn serialize_col<S: Serializer>(col: &DBCol, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(col.into())
+}
+
+fn serialize_hex_keys<S: Serializer>(keys: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(keys.iter().map(hex::encode))
+}
+
+/// What `gc-info` reports.
+#[derive(Serialize)]
+pub(crate) struct GcInfo {
+    pub head: Tip,
+    pub gc_stop_height: BlockHeight,
+    #[serde(flatten)]
+    pub tails: Tails,
+    /// Epochs with blocks above the tail.
+    pub num_epochs: u64,
+    pub leaks: Vec<ColumnLeak>,
+}
+
+impl Record for GcInfo {
+    fn human(&self) -> String {
+        let mut lines = vec![
+            format!("Head {}, GC stop height {}", self.head, self.gc_stop_height),
+            format!(
+                "Tail {}, chunk tail {}, fork tail {}",
+                self.tails.tail, self.tails.chunk_tail, self.tails.fork_tail
+            ),
+            format!("{} epochs of history above the tail", self.num_epochs),
+        ];
+        if self.leaks.is_empty() {
+            lines.push("No entries below the tails".to_string());
+        }
+        for leak in &self.leaks {
+            lines.push(format!(
+                "{}: {} entries below the tails, e.g.",
+                <&str>::from(leak.col),
+                leak.num_keys
+            ));
+            lines.extend(leak.example_keys.iter().map(|key| format!("  {}", hex::encode(key))));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Finds the entries below the tails in the columns GC cleans, i.e. the
+/// entries GC missed.  Entries referring to blocks whose header is missing
+/// are skipped since their height is unknown.
+pub(crate) fn find_leaks(
+    store: &Store,
+    genesis_height: BlockHeight,
+    tails: &Tails,
+    max_examples: usize,
+) -> anyhow::Result<Vec<ColumnLeak>> {
+    let mut block_heights: HashMap<CryptoHash, Option<BlockHeight>> = HashMap::new();
+    let mut leaks = vec![];
+    for &(col, block_ref, gc) in COLUMN_BLOCK_REFS {
+        let mut leak = ColumnLeak { col, num_keys: 0, example_keys: vec![] };
+        for (key, value) in store.iter(col) {
+            let referenced = block_ref
+                .referenced(&key, &value)
+                .map_err(|err| anyhow::anyhow!("{:?} entry {}: {}", col, to_base(&key), err))?;
+            let is_leak = match referenced {
+                Referenced::Blocks(block_hashes) => {
+                    let mut collected = !block_hashes.is_empty();
+                    for hash in block_hashes {
+                        let height = match block_heights.get(&hash) {
+                            Some(height) => *height,
+                            None => {
+                                let header = store
+                                    .get_ser::<BlockHeader>(DBCol::BlockHeader, hash.as_ref())?;
+                                let height = header.map(|header| header.height());
+                                block_heights.insert(hash, height);
+                                height
+                            }
+                        };
+                        if !height
+                            .map_or(false, |height| gc.collects(height, tails, genesis_height))
+                        {
+                            collected = false;
+                            break;
+                        }
+                    }
+                    collected
+                }
+                Referenced::Height(height) => gc.collects(height, tails, genesis_height),
+            };
+            if is_leak {
+                leak.num_keys += 1;
+                if leak.example_keys.len() < max_examples {
+                    leak.example_keys.push(key.to_vec());
+                }
+            }
+        }
+        if leak.num_keys > 0 {
+            leaks.push(leak);
+        }
+    }
+    Ok(leaks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{count_epochs, find_leaks, read_tails, ColumnLeak, GcInfo, Tails};
+    use crate::output::Record;
+    use crate::test_utils::setup_env_with_genesis;
+    use near_chain::{ChainGenesis, RuntimeAdapter};
+    use near_chain_configs::Genesis;
+    use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
+    use near_store::test_utils::create_test_store;
+    use near_store::DBCol;
+    use nearcore::config::GenesisExt;
+
+    #[test]
+    fn test_gc_info() {
+        let mut genesis =
+            Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+        genesis.config.epoch_length = 5;
+        let chain_genesis = ChainGenesis::from(&genesis);
+        let mut setup = setup_env_with_genesis(genesis, chain_genesis, 0);
+        let mut blocks = vec![setup.env.clients[0].chain.genesis_block().clone()];
+        blocks.extend(setup.produce_blocks(1..30));
+        let head = setup.env.clients[0].chain.head().unwrap();
+        let genesis_height = setup.genesis.config.genesis_height;
+        let (store, runtime) = (setup.store, setup.runtime);
+
+        // GC keeps the epochs from the GC stop height and the tail is the
+        // last height it collected.
+        let gc_stop_height = runtime.get_gc_stop_height(&head.last_block_hash);
+        let tails = read_tails(&store, genesis_height).unwrap();
+        assert_eq!(tails.tail, gc_stop_height - 1);
+        assert!(tails.chunk_tail > genesis_height);
+        assert_eq!(
+            read_tails(&create_test_store(), genesis_height).unwrap(),
+            Tails { tail: genesis_height, chunk_tail: genesis_height, fork_tail: genesis_height }
+        );
+        // Epochs of 5 blocks from the GC stop height to the head.
+        let num_epochs = count_epochs(&store, &*runtime, &head.last_block_hash, tails.tail);
+        assert_eq!(num_epochs.unwrap(), (head.height - tails.tail + 4) / 5);
+        assert_eq!(find_leaks(&store, genesis_height, &tails, 2).unwrap(), vec![]);
+
+        // Trie changes are collected with the block at the tail, other data
+        // of a block when the next height is collected.
+        let tail_block = blocks[tails.tail as usize].hash();
+        let old_block = blocks[tails.tail as usize - 1].hash();
+        let shard_uid = ShardUId::single_shard();
+        let mut update = store.store_update();
+        update.set(DBCol::TrieChanges, &get_block_shard_uid(tail_block, &shard_uid), b"changes");
+        update.set(DBCol::TrieChanges, &get_block_shard_uid(old_block, &shard_uid), b"changes");
+        update.set(DBCol::NextBlockHashes, tail_block.as_ref(), b"next");
+        update.set(DBCol::NextBlockHashes, old_block.as_ref(), b"next");
+        update.commit().unwrap();
+        let leaks = find_leaks(&store, genesis_height, &tails, 1).unwrap();
+        assert_eq!(
+            leaks,
+            vec![
+                ColumnLeak {
+                    col: DBCol::NextBlockHashes,
+                    num_keys: 1,
+                    example_keys: vec![old_block.as_ref().to_vec()]
+                },
+                ColumnLeak {
+                    col: DBCol::TrieChanges,
+                    num_keys: 2,
+                    example_keys: vec![leaks[1].example_keys[0].clone()]
+                },
+            ]
+        );
+        let info = GcInfo { head: head.clone(), gc_stop_height, tails, num_epochs: 5, leaks };
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["head"]["height"], head.height);
+        assert_eq!(json["tail"], gc_stop_height - 1);
+        assert_eq!(json["leaks"][0]["column"], "NextBlockHashes");
+        assert_eq!(json["leaks"][0]["example_keys"][0], hex::encode(old_block));
+        let human = info.human();
+        assert!(human.starts_with(&format!("Head {}, GC stop height", head)), "{}", human);
+        assert!(human.ends_with(&format!("NextBlockHashes: 1 entries below the tails, e.g.\n  {}\nTrieChanges: 2 entries below the tails, e.g.\n  {}", hex::encode(old_block), hex::encode(&info.leaks[1].example_keys[0]))), "{}", human);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::block::block_hashes_at_height;
+use crate::output::Record;
+use near_primitives::block::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_store::{DBCol, Store};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::RangeInclusive;
+
+/// Heights with and without blocks in `BlockPerHeight`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct HeightGaps {
+    /// Number of heights with at least one block.
+    pub num_stored: u64,
+    /// Number of heights with more than one block.
+    pub num_forked: u64,
+    /// Runs of heights without blocks.  Most are heights which were skipped
+    /// by block producers.
+    pub gaps: Vec<RangeInclusive<BlockHeight>>,
+    /// Heights without blocks even though a stored block has its previous
+    /// block there, with the hash of that block, which means they were
+    /// garbage collected or the database is corrupted.
+    pub referenced: BTreeMap<BlockHeight, CryptoHash>,
+}
+
+impl HeightGaps {
+    pub fn longest_gap(&self) -> Option<&RangeInclusive<BlockHeight>> {
+        // Earliest of the longest gaps.
+        self.gaps.iter().rev().max_by_key(|gap| gap.end() - gap.start())
+    }
+
+    /// The gaps, with the referenced heights in them.
+    pub fn records(&self) -> Vec<Gap> {
+        self.gaps
+            .iter()
+            .map(|gap| Gap {
+                start_height: *gap.start(),
+                end_height: *gap.end(),
+                num_heights: gap.end() - gap.start() + 1,
+                missing_blocks: self
+                    .referenced
+                    .range(gap.clone())
+                    .map(|(height, hash)| MissingBlock { height: *height, hash: *hash })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// A run of heights without blocks.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct Gap {
+    pub start_height: BlockHeight,
+    pub end_height: BlockHeight,
+    pub num_heights: u64,
+    /// Blocks in the gap which stored blocks build on.
+    pub missing_blocks: Vec<MissingBlock>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct MissingBlock {
+    pub height: BlockHeight,
+    pub hash: CryptoHash,
+}
+
+impl Record for Gap {
+    fn human(&self) -> String {
+        let mut line = format!("No blocks at heights {}..={}", self.start_height, self.end_height);
+        for block in &self.missing_blocks {
+            line += &format!(
+                "\nBlock {} at height {} is missing but a stored block builds on it",
+                block.hash, block.height
+            );
+        }
+        line
+    }
+}
+
+/// Returns the height of the previous block of `header`, `None` for genesis.
+fn prev_height(store: &Store, header: &BlockHeader) -> anyhow::Result<Option<BlockHeight>> {
+    if header.prev_hash() == &CryptoHash::default() {
+        return Ok(None);
+    }
+    // Headers of old protocol versions don't have the previous height, but
+    // headers aren't garbage collected so the previous one is usually there.
+    let prev_header =
+        store.get_ser::<BlockHeader>(DBCol::BlockHeader, header.prev_hash().as_ref())?;
+    Ok(prev_header.map(|prev_header| prev_header.height()).or_else(|| header.prev_height()))
+}
+
+/// Looks up every height of `heights` in `BlockPerHeight`.
+pub(crate) fn find_gaps(
+    store: &Store,
+    heights: RangeInclusive<BlockHeight>,
+) -> anyhow::Result<HeightGaps> {
+    let mut result = HeightGaps::default();
+    let mut missing = HashSet::new();
+    let mut gap_start = None;
+    for height in heights.clone() {
+        let hashes = block_hashes_at_height(store, height)?;
+        if hashes.is_empty() {
+            missing.insert(height);
+            gap_start.get_or_insert(height);
+            continue;
+        }
+        if let Some(start) = gap_start.take() {
+            result.gaps.push(start..=height - 1);
+        }
+        result.num_stored += 1;
+        if hashes.len() > 1 {
+            result.num_forked += 1;
+        }
+        for hash in hashes {
+            let header = match store.get_ser::<BlockHeader>(DBCol::BlockHeader, hash.as_ref())? {
+                Some(header) => header,
+                None => continue,
+            };
+            if let Some(prev_height) = prev_height(store, &header)? {
+                if missing.contains(&prev_height) {
+                    result.referenced.insert(prev_height, *header.prev_hash());
+                }
+            }
+        }
+    }
+    if let Some(start) = gap_start {
+        result.gaps.push(start..=*heights.end());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_gaps, MissingBlock};
+    use crate::test_utils::setup_env;
+    use near_primitives::block::BlocksPerHeight;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::EpochId;
+    use near_primitives::utils::index_to_bytes;
+    use near_store::DBCol;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_find_gaps() {
+        let mut setup = setup_env(&["test0", "test1"], 0);
+        let genesis_height = setup.env.clients[0].chain.genesis().height();
+        let heights: Vec<u64> = (1..4).chain(5..7).chain(9..10).collect();
+        let blocks: HashMap<u64, _> =
+            heights.iter().copied().zip(setup.produce_blocks(heights.clone())).collect();
+        let store = setup.store;
+
+        let gaps = find_gaps(&store, genesis_height..=10).unwrap();
+        assert_eq!(gaps.num_stored, 7);
+        assert_eq!(gaps.num_forked, 0);
+        assert_eq!(gaps.gaps, vec![4..=4, 7..=8, 10..=10]);
+        assert_eq!(gaps.longest_gap(), Some(&(7..=8)));
+        assert!(gaps.referenced.is_empty());
+
+        // A fork at height 2 and a block at height 3 which is gone.
+        let mut update = store.store_update();
+        let mut per_height = BlocksPerHeight::default();
+        per_height.insert(EpochId::default(), *blocks[&2].hash());
+        per_height.insert(EpochId::default(), CryptoHash::hash_bytes(b"fork"));
+        update.set_ser(DBCol::BlockPerHeight, &index_to_bytes(2), &per_height).unwrap();
+        update.delete(DBCol::BlockPerHeight, &index_to_bytes(3));
+        update.commit().unwrap();
+
+        let gaps = find_gaps(&store, genesis_height..=10).unwrap();
+        assert_eq!(gaps.num_stored, 6);
+        assert_eq!(gaps.num_forked, 1);
+        assert_eq!(gaps.gaps, vec![3..=4, 7..=8, 10..=10]);
+        assert_eq!(gaps.longest_gap(), Some(&(3..=4)));
+        let records = gaps.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].num_heights, 2);
+        assert_eq!(
+            records[0].missing_blocks,
+            vec![MissingBlock { height: 3, hash: *blocks[&3].hash() }]
+        );
+        assert_eq!(records[1].missing_blocks, vec![]);
+        assert_eq!(gaps.referenced.into_iter().collect::<Vec<_>>(), vec![(3, *blocks[&3].hash())]);
+
+        // Heights before the range aren't known to be missing.
+        let gaps = find_gaps(&store, 5..=6).unwrap();
+        assert_eq!(gaps.gaps, vec![]);
+        assert!(gaps.referenced.is_empty());
+    }
+}
@@ -1,11 +1,42 @@
 #![doc = include_str!("../README.md")]
 
+mod account;
 mod apply_chain_range;
 mod apply_chunk;
+mod archive;
+mod bench;
+mod block;
+mod block_refs;
+mod check_chain;
+mod check_genesis;
+mod chunk;
+mod chunk_parts;
 pub mod cli;
+mod column_stats;
+mod column_values;
 mod commands;
+mod contract_code;
+mod db_diff;
+mod dump_column;
 mod epoch_info;
+mod find;
+mod follow;
+mod gc_info;
+mod height_gaps;
+mod light_proof;
+mod orphans;
+mod output;
+mod raw;
+mod repl;
 mod rocksdb_stats;
 mod state_dump;
+mod state_size;
+#[cfg(test)]
+mod test_utils;
+mod tx_lookup;
+mod validators;
+mod verify_headers;
+mod verify_state;
 
 pub use cli::StateViewerSubCommand;
+pub use output::OutputFormat;
@@ -0,0 +1,379 @@
+use anyhow::{anyhow, ensure};
+use borsh::BorshSerialize;
+use near_chain::{
+    create_light_client_block_view, get_epoch_block_producers_view, Chain, ChainGenesis,
+    ChainStoreAccess, DoomslugThresholdMode, Error,
+};
+use near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse;
+use near_primitives::block::Approval;
+use near_primitives::block_header::{ApprovalInner, BlockHeaderInnerLite};
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::merkle::{
+    combine_hash, compute_root_from_path, compute_root_from_path_and_item, merklize, verify_path,
+    MerklePath,
+};
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{Balance, BlockHeight};
+use near_primitives::views::{
+    BlockHeaderInnerLiteView, LightClientBlockLiteView, LightClientBlockView,
+};
+use near_store::Store;
+use nearcore::{NearConfig, NightshadeRuntime};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Proof of an execution outcome against a light client head, in the shape of
+/// the `light_client_proof` RPC response plus the light client block of the
+/// head, which carries the approvals and the next block producers.
+#[derive(Serialize)]
+pub(crate) struct LightProof {
+    #[serde(flatten)]
+    pub proof: RpcLightClientExecutionProofResponse,
+    pub light_client_head: LightClientBlockView,
+}
+
+/// Maps missing data to an error which says what is missing, as an archival
+/// node has everything and any other node may have garbage collected it.
+fn required<T>(result: Result<T, Error>, what: impl FnOnce() -> String) -> anyhow::Result<T> {
+    result.map_err(|err| match err {
+        Error::DBNotFoundErr(_) => anyhow!(
+            "{} is missing from the database, it may have been garbage collected: {}",
+            what(),
+            err
+        ),
+        err => anyhow!("{}: {}", what(), err),
+    })
+}
+
+/// Builds the proof of the outcome of transaction or receipt `id` against the
+/// final block `head_hash` from the local database and verifies it.
+pub(crate) fn light_proof(
+    chain: &mut Chain,
+    id: &CryptoHash,
+    head_hash: &CryptoHash,
+) -> anyhow::Result<LightProof> {
+    let head = required(chain.get_block_header(head_hash), || format!("head block {}", head_hash))?
+        .clone();
+    chain
+        .check_block_final_and_canonical(head_hash)
+        .map_err(|err| anyhow!("head block {} is not final and canonical: {}", head_hash, err))?;
+
+    let mut outcome_proof =
+        required(chain.get_execution_outcome(id), || format!("outcome of {}", id))?;
+    let outcome_block_height = required(chain.get_block_header(&outcome_proof.block_hash), || {
+        format!("block {} of the outcome", outcome_proof.block_hash)
+    })?
+    .height();
+    ensure!(
+        outcome_block_height < head.height(),
+        "outcome of {} is in block {} at height {}, not before the head at height {}",
+        id,
+        outcome_proof.block_hash,
+        outcome_block_height,
+        head.height()
+    );
+
+    // The chunk outcome root is committed to by the first block after the
+    // outcome which includes the next chunk of the shard.
+    let chunk_outcome_root = compute_root_from_path_and_item(
+        &outcome_proof.proof,
+        &outcome_proof.outcome_with_id.to_hashes(),
+    );
+    let (block_hash, outcome_root_proof) =
+        find_outcome_root(chain, &outcome_proof.block_hash, &chunk_outcome_root, head.height())?;
+    outcome_proof.block_hash = block_hash;
+    let header =
+        required(chain.get_block_header(&block_hash), || format!("block {}", block_hash))?.clone();
+    let block_proof = required(chain.get_block_proof(&block_hash, head_hash), || {
+        format!("block merkle tree between {} and {}", block_hash, head_hash)
+    })?
+    .path;
+
+    let next_block_producers = required(
+        get_epoch_block_producers_view(head.next_epoch_id(), head_hash, &*chain.runtime_adapter),
+        || format!("block producers of epoch {:?}", head.next_epoch_id()),
+    )?;
+    let light_client_head = required(
+        create_light_client_block_view(&head, chain.mut_store(), Some(next_block_producers)),
+        || format!("two blocks after the head {}", head_hash),
+    )?;
+    let block_producers = required(
+        chain.runtime_adapter.get_epoch_block_producers_ordered(head.epoch_id(), head_hash),
+        || format!("block producers of epoch {:?}", head.epoch_id()),
+    )?;
+
+    let proof = LightProof {
+        proof: RpcLightClientExecutionProofResponse {
+            outcome_proof: outcome_proof.into(),
+            outcome_root_proof,
+            block_header_lite: header.into(),
+            block_proof,
+        },
+        light_client_head,
+    };
+    let block_producers: Vec<_> = block_producers.into_iter().map(|(bp, _)| bp).collect();
+    verify(&proof, head_hash, &block_producers)?;
+    Ok(proof)
+}
+
+/// Walks the chain forward from `outcome_block_hash` up to `max_height` to
+/// the block with a new chunk whose outcome root is `chunk_outcome_root`.
+/// Returns the block and the path from the chunk outcome root to the block's
+/// outcome root.
+/// Prints the proof of the outcome of transaction or receipt `id` against
+/// the final block `head_hash` as JSON.
+pub(crate) fn print_light_proof(
+    id: &CryptoHash,
+    head_hash: &CryptoHash,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let runtime = NightshadeRuntime::with_config(
+        home_dir,
+        store,
+        &near_config,
+        None,
+        near_config.client_config.max_gas_burnt_view,
+    );
+    let mut chain = Chain::new_for_view_client(
+        Arc::new(runtime),
+        &ChainGenesis::from(&near_config.genesis),
+        DoomslugThresholdMode::TwoThirds,
+        !near_config.client_config.archive,
+    )?;
+    let proof = light_proof(&mut chain, id, head_hash)?;
+    println!("{}", serde_json::to_string_pretty(&proof)?);
+    Ok(())
+}
+
+fn find_outcome_root(
+    chain: &mut Chain,
+    outcome_block_hash: &CryptoHash,
+    chunk_outcome_root: &CryptoHash,
+    max_height: BlockHeight,
+) -> anyhow::Result<(CryptoHash, MerklePath)> {
+    let mut block_hash = *outcome_block_hash;
+    loop {
+        block_hash = *required(chain.mut_store().get_next_block_hash(&block_hash), || {
+            format!("next block of {}", block_hash)
+        })?;
+        let block = required(chain.get_block(&block_hash), || format!("block {}", block_hash))?;
+        let height = block.header().height();
+        ensure!(
+            height <= max_height,
+            "outcome root of the chunk of the outcome isn't included in a block up to the head"
+        );
+        let chunks = block.chunks();
+        let found = chunks.iter().position(|chunk| {
+            chunk.height_included() == height && chunk.outcome_root() == *chunk_outcome_root
+        });
+        if let Some(index) = found {
+            let outcome_roots: Vec<_> = chunks.iter().map(|chunk| chunk.outcome_root()).collect();
+            return Ok((block_hash, merklize(&outcome_roots).1.swap_remove(index)));
+        }
+    }
+}
+
+fn lite_block_hash(
+    prev_block_hash: &CryptoHash,
+    inner_rest_hash: &CryptoHash,
+    inner_lite: &BlockHeaderInnerLiteView,
+) -> anyhow::Result<CryptoHash> {
+    let inner_lite = BlockHeaderInnerLite::from(inner_lite.clone()).try_to_vec()?;
+    Ok(combine_hash(&combine_hash(&hash(&inner_lite), inner_rest_hash), prev_block_hash))
+}
+
+/// Checks the proof the way a light client which trusts `head_hash` and knows
+/// the `block_producers` of its epoch would.
+pub(crate) fn verify(
+    proof: &LightProof,
+    head_hash: &CryptoHash,
+    block_producers: &[ValidatorStake],
+) -> anyhow::Result<()> {
+    let LightProof { proof, light_client_head: head } = proof;
+
+    // Outcome -> chunk outcome root -> block outcome root.
+    let chunk_outcome_root = compute_root_from_path_and_item(
+        &proof.outcome_proof.proof,
+        &proof.outcome_proof.to_hashes(),
+    );
+    let LightClientBlockLiteView { prev_block_hash, inner_rest_hash, inner_lite } =
+        &proof.block_header_lite;
+    ensure!(
+        verify_path(inner_lite.outcome_root, &proof.outcome_root_proof, &chunk_outcome_root),
+        "outcome root proof doesn't match the outcome root of block {}",
+        proof.outcome_proof.block_hash
+    );
+    let block_hash = lite_block_hash(prev_block_hash, inner_rest_hash, inner_lite)?;
+    ensure!(
+        block_hash == proof.outcome_proof.block_hash,
+        "block header lite hashes to {}, not {}",
+        block_hash,
+        proof.outcome_proof.block_hash
+    );
+
+    // Block -> head.
+    let computed_head_hash =
+        lite_block_hash(&head.prev_block_hash, &head.inner_rest_hash, &head.inner_lite)?;
+    ensure!(
+        computed_head_hash == *head_hash,
+        "light client head hashes to {}, not {}",
+        computed_head_hash,
+        head_hash
+    );
+    if block_hash != *head_hash {
+        let root = compute_root_from_path(&proof.block_proof, block_hash);
+        ensure!(
+            root == head.inner_lite.block_merkle_root,
+            "block proof of {} doesn't match the block merkle root of the head",
+            block_hash
+        );
+    }
+
+    // Head approvals, as in the light client block validation.
+    let next_block_hash = combine_hash(&head.next_block_inner_hash, head_hash);
+    let message = Approval::get_data_for_sig(
+        &ApprovalInner::Endorsement(next_block_hash),
+        head.inner_lite.height + 2,
+    );
+    ensure!(
+        head.approvals_after_next.len() <= block_producers.len(),
+        "more approvals than block producers"
+    );
+    let mut total_stake: Balance = 0;
+    let mut approved_stake: Balance = 0;
+    for (i, block_producer) in block_producers.iter().enumerate() {
+        total_stake += block_producer.stake();
+        if let Some(Some(signature)) = head.approvals_after_next.get(i) {
+            ensure!(
+                signature.verify(&message, block_producer.public_key()),
+                "invalid approval of {} for the head",
+                block_producer.account_id()
+            );
+            approved_stake += block_producer.stake();
+        }
+    }
+    ensure!(
+        approved_stake * 3 > total_stake * 2,
+        "head is approved by {} of {} stake",
+        approved_stake,
+        total_stake
+    );
+
+    if let Some(next_bps) = &head.next_bps {
+        let next_bps: Vec<ValidatorStake> =
+            next_bps.iter().cloned().map(|bp| bp.into_validator_stake()).collect();
+        let bp_hash = hash(&next_bps.try_to_vec()?);
+        let bp_hash_v1 =
+            hash(&next_bps.into_iter().map(|bp| bp.into_v1()).collect::<Vec<_>>().try_to_vec()?);
+        ensure!(
+            head.inner_lite.next_bp_hash == bp_hash || head.inner_lite.next_bp_hash == bp_hash_v1,
+            "next block producers don't match the next_bp_hash of the head"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{light_proof, verify, LightProof};
+    use crate::test_utils::{setup_env_with_genesis, test_runtime};
+    use near_chain::{Chain, ChainGenesis, DoomslugThresholdMode};
+    use near_chain_configs::Genesis;
+    use near_crypto::KeyType;
+    use near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse;
+    use near_primitives::block::Approval;
+    use near_primitives::block_header::ApprovalType;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::validator_signer::InMemoryValidatorSigner;
+    use near_store::{DBCol, Store};
+    use nearcore::config::GenesisExt;
+
+    fn view_chain(store: &Store, genesis: &Genesis) -> Chain {
+        let mut chain_genesis = ChainGenesis::test();
+        chain_genesis.epoch_length = genesis.config.epoch_length;
+        Chain::new_for_view_client(
+            test_runtime(store, genesis),
+            &chain_genesis,
+            DoomslugThresholdMode::NoApprovals,
+            true,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_light_proof() {
+        let mut genesis =
+            Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+        genesis.config.epoch_length = 5;
+        let mut chain_genesis = ChainGenesis::test();
+        chain_genesis.epoch_length = genesis.config.epoch_length;
+        let mut setup = setup_env_with_genesis(genesis, chain_genesis, 0);
+        let tx_hash = setup.send_money(1, "test0", "test1").get_hash();
+        let validator_signer =
+            InMemoryValidatorSigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+        for height in 1..20 {
+            let block_hash = *setup.produce_blocks([height])[0].hash();
+            // The test environment doesn't deliver approvals, and the light
+            // client needs them.
+            let approval = Approval::new(block_hash, height, height + 1, &validator_signer);
+            setup.env.clients[0].collect_block_approval(&approval, ApprovalType::SelfApproval);
+        }
+        let (store, genesis) = (setup.store, setup.genesis);
+        let mut chain = view_chain(&store, &genesis);
+        let receipt_id =
+            chain.get_execution_outcome(&tx_hash).unwrap().outcome_with_id.outcome.receipt_ids[0];
+        // In a later epoch than the outcomes.
+        let head_hash = *chain.get_header_by_height(12).unwrap().hash();
+        let epoch_id = chain.get_block_header(&head_hash).unwrap().epoch_id().clone();
+        let block_producers: Vec<_> = chain
+            .runtime_adapter
+            .get_epoch_block_producers_ordered(&epoch_id, &head_hash)
+            .unwrap()
+            .into_iter()
+            .map(|(bp, _)| bp)
+            .collect();
+
+        for id in [tx_hash, receipt_id] {
+            let proof = light_proof(&mut chain, &id, &head_hash).unwrap();
+            assert_eq!(proof.proof.outcome_proof.id, id);
+            verify(&proof, &head_hash, &block_producers).unwrap();
+            let json = serde_json::to_string(&proof).unwrap();
+            let response: RpcLightClientExecutionProofResponse =
+                serde_json::from_str(&json).unwrap();
+            assert_eq!(response.outcome_proof.id, id);
+
+            let mut tamper = |f: &dyn Fn(&mut LightProof)| {
+                let mut proof: LightProof = light_proof(&mut chain, &id, &head_hash).unwrap();
+                f(&mut proof);
+                assert!(verify(&proof, &head_hash, &block_producers).is_err());
+            };
+            tamper(&|proof| proof.proof.outcome_proof.outcome.gas_burnt += 1);
+            tamper(&|proof| {
+                proof.proof.block_header_lite.inner_lite.outcome_root = CryptoHash::default()
+            });
+            tamper(&|proof| proof.proof.block_header_lite.inner_lite.height += 1);
+            tamper(&|proof| proof.proof.block_proof.clear());
+            tamper(&|proof| proof.light_client_head.inner_lite.timestamp_nanosec += 1);
+            tamper(&|proof| proof.light_client_head.approvals_after_next[0] = None);
+            tamper(&|proof| proof.light_client_head.next_bps.as_mut().unwrap().clear());
+        }
+        // The head must be after the outcome.
+        let tx_block_hash = chain.get_execution_outcome(&tx_hash).unwrap().block_hash;
+        assert!(light_proof(&mut chain, &tx_hash, &tx_block_hash).is_err());
+        assert!(light_proof(&mut chain, &CryptoHash::default(), &head_hash).is_err());
+
+        // Remove a block between the outcome and the head as garbage
+        // collection would.
+        let outcome_block_hash =
+            light_proof(&mut chain, &tx_hash, &head_hash).unwrap().proof.outcome_proof.block_hash;
+        let mut store_update = store.store_update();
+        store_update.delete(DBCol::Block, outcome_block_hash.as_ref());
+        store_update.commit().unwrap();
+        let mut chain = view_chain(&store, &genesis);
+        let err = light_proof(&mut chain, &tx_hash, &head_hash).err().unwrap().to_string();
+        assert!(err.contains("garbage collected"), "{}", err);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::block_refs::{BlockRef, GcBoundary, Referenced, COLUMN_BLOCK_REFS};
+use crate::gc_info::{read_tails, Tails};
+use crate::output::{Output, Record};
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::to_base;
+use near_primitives::types::BlockHeight;
+use near_store::{DBCol, Store};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Entries of a column which refer only to blocks that are not in the
+/// database anymore, or to heights garbage collection has already collected.
+#[derive(Debug, Default)]
+pub(crate) struct Orphans {
+    pub num_entries: usize,
+    pub keys: Vec<Box<[u8]>>,
+    /// Total size of keys and values of the orphaned entries.
+    pub bytes: usize,
+}
+
+/// Remembers which blocks exist, as many entries usually refer to the same
+/// block.
+struct BlockCache<'a> {
+    store: &'a Store,
+    exists: HashMap<CryptoHash, bool>,
+}
+
+impl BlockCache<'_> {
+    fn exists(&mut self, block_hash: CryptoHash) -> anyhow::Result<bool> {
+        // The epoch manager keeps a `BlockInfo` for the previous block of
+        // genesis, which never existed.
+        if block_hash == CryptoHash::default() {
+            return Ok(true);
+        }
+        if let Some(exists) = self.exists.get(&block_hash) {
+            return Ok(*exists);
+        }
+        let exists = self.store.exists(DBCol::Block, block_hash.as_ref())?;
+        self.exists.insert(block_hash, exists);
+        Ok(exists)
+    }
+}
+
+/// Finds the orphaned entries of `col`.  Entries referring to blocks are
+/// orphans when none of the blocks exist, entries referring to a height, e.g.
+/// chunks, when garbage collection has passed the height.
+pub(crate) fn find_orphans(
+    store: &Store,
+    col: DBCol,
+    (block_ref, gc): (BlockRef, GcBoundary),
+    tails: &Tails,
+    genesis_height: BlockHeight,
+) -> anyhow::Result<Orphans> {
+    let mut blocks = BlockCache { store, exists: HashMap::new() };
+    let mut orphans = Orphans::default();
+    for (key, value) in store.iter(col) {
+        orphans.num_entries += 1;
+        let referenced = block_ref
+            .referenced(&key, &value)
+            .map_err(|err| anyhow::anyhow!("{:?} entry {}: {}", col, to_base(&key), err))?;
+        let orphaned = match referenced {
+            Referenced::Blocks(block_hashes) => {
+                let mut orphaned = true;
+                for block_hash in block_hashes {
+                    if blocks.exists(block_hash)? {
+                        orphaned = false;
+                        break;
+                    }
+                }
+                orphaned
+            }
+            Referenced::Height(height) => gc.collects(height, tails, genesis_height),
+        };
+        if orphaned {
+            orphans.bytes += key.len() + value.len();
+            orphans.keys.push(key);
+        }
+    }
+    Ok(orphans)
+}
+
+/// Number and size of the orphaned entries of a column.
+#[derive(Serialize)]
+pub(crate) struct ColumnOrphans {
+    pub column: &'static str,
+    pub num_entries: usize,
+    pub num_orphans: usize,
+    pub orphan_bytes: usize,
+}
+
+impl Record for ColumnOrphans {
+    fn human(&self) -> String {
+        format!(
+            "{: <28} {: >12} {: >12} {: >14}",
+            self.column, self.num_entries, self.num_orphans, self.orphan_bytes
+        )
+    }
+}
+
+/// Prints the number and size of orphaned entries in `cols`, all columns
+/// referring to blocks if `None`.  With `keys_output`, writes `<column> <key>`
+/// with the key in base58 for every orphaned entry.
+pub(crate) fn print_orphans<W: Write>(
+    store: &Store,
+    genesis_height: BlockHeight,
+    cols: Option<DBCol>,
+    mut keys_output: Option<&mut dyn Write>,
+    output: &mut Output<W>,
+) -> anyhow::Result<()> {
+    let tails = read_tails(store, genesis_height)?;
+    let mut total_orphans = 0;
+    let mut total_bytes = 0;
+    output.line(&format!(
+        "{: <28} {: >12} {: >12} {: >14}",
+        "column", "entries", "orphans", "orphan bytes"
+    ))?;
+    for (col, block_ref, gc) in COLUMN_BLOCK_REFS {
+        if cols.map_or(false, |c| c != *col) {
+            continue;
+        }
+        let orphans = find_orphans(store, *col, (*block_ref, *gc), &tails, genesis_height)?;
+        output.record(&ColumnOrphans {
+            column: col.into(),
+            num_entries: orphans.num_entries,
+            num_orphans: orphans.keys.len(),
+            orphan_bytes: orphans.bytes,
+        })?;
+        if let Some(keys_output) = keys_output.as_mut() {
+            for key in &orphans.keys {
+                writeln!(keys_output, "{} {}", <&str>::from(col), to_base(key))?;
+            }
+        }
+        total_orphans += orphans.keys.len();
+        total_bytes += orphans.bytes;
+    }
+    output.line(&format!("{} orphaned entries, {} bytes", total_orphans, total_bytes))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_orphans;
+    use crate::block_refs::{block_ref, COLUMN_BLOCK_REFS};
+    use crate::gc_info::{read_tails, Tails};
+    use crate::test_utils::setup_env;
+    use near_store::DBCol;
+
+    #[test]
+    fn test_find_orphans() {
+        let setup = setup_env(&["test0"], 5);
+        let store = setup.store.clone();
+        let genesis_height = setup.genesis.config.genesis_height;
+        let tails = read_tails(&store, genesis_height).unwrap();
+        for (col, block_ref, gc) in COLUMN_BLOCK_REFS {
+            let orphans =
+                find_orphans(&store, *col, (*block_ref, *gc), &tails, genesis_height).unwrap();
+            assert!(orphans.keys.is_empty(), "{:?} has orphans", col);
+        }
+
+        // Remove the blocks at heights 2 and 3 but none of their data.
+        let removed = [2, 3]
+            .map(|height| setup.env.clients[0].chain.get_block_hash_by_height(height).unwrap());
+        let mut store_update = store.store_update();
+        for block_hash in &removed {
+            store_update.delete(DBCol::Block, block_hash.as_ref());
+        }
+        store_update.commit().unwrap();
+
+        let find = |col, tails: &Tails| {
+            find_orphans(&store, col, block_ref(col).unwrap(), tails, genesis_height).unwrap()
+        };
+        let block_hash_orphans = |col| {
+            let orphans = find(col, &tails);
+            assert!(orphans.bytes > 0);
+            let mut hashes: Vec<_> = orphans.keys.iter().map(|key| key[..32].to_vec()).collect();
+            hashes.sort();
+            hashes.dedup();
+            hashes
+        };
+        let mut expected: Vec<_> = removed.iter().map(|hash| hash.as_ref().to_vec()).collect();
+        expected.sort();
+        for col in [DBCol::BlockInfo, DBCol::ChunkExtra, DBCol::TrieChanges, DBCol::OutcomeIds] {
+            assert_eq!(block_hash_orphans(col), expected, "{:?}", col);
+        }
+        // Chunks are collected by the height they were created at rather
+        // than with a block, so removing blocks orphans none of them.
+        let chunks = find(DBCol::Chunks, &tails);
+        assert!(chunks.keys.is_empty());
+        assert_eq!(chunks.num_entries, 6);
+        // With the chunk tail at 3, the chunks created at heights 0 to 2 are.
+        let chunks = find(DBCol::Chunks, &Tails { chunk_tail: 3, ..tails });
+        assert_eq!(chunks.keys.len(), 3);
+    }
+}
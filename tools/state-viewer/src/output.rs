@@ -0,0 +1,204 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+
+/// Format of what the subcommands print, given by the global `--format`.
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Text meant to be read by people.
+    Human,
+    /// One JSON object per line, e.g. to pipe into `jq`.
+    Json,
+    /// A header with the fields of the records followed by a row per record.
+    /// Fields which aren't strings or numbers are written as JSON.
+    Csv,
+}
+
+/// Something a subcommand prints, e.g. a row of a report.  In the human
+/// readable format it's printed as `human()`, otherwise its fields are
+/// serialized, so hashes, account ids and balances are strings.
+pub(crate) trait Record: Serialize {
+    fn human(&self) -> String;
+}
+
+/// Lookups which are already JSON are printed pretty for people.
+impl Record for Value {
+    fn human(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Writes the records of a subcommand in the selected format.
+pub(crate) struct Output<W: Write> {
+    format: OutputFormat,
+    writer: W,
+    /// Fields of the first record, written as the CSV header.
+    csv_fields: Option<Vec<String>>,
+}
+
+impl Output<std::io::Stdout> {
+    pub fn stdout(format: OutputFormat) -> Self {
+        Output::new(format, std::io::stdout())
+    }
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(format: OutputFormat, writer: W) -> Self {
+        Output { format, writer, csv_fields: None }
+    }
+
+    #[cfg(test)]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes a line which is only part of the human readable output, e.g. a
+    /// table header or a summary which can be computed from the records.
+    pub fn line(&mut self, line: &str) -> anyhow::Result<()> {
+        if self.format == OutputFormat::Human {
+            writeln!(self.writer, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    pub fn record(&mut self, record: &impl Record) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Human => writeln!(self.writer, "{}", record.human())?,
+            OutputFormat::Json => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                writeln!(self.writer)?;
+            }
+            OutputFormat::Csv => self.csv_row(serde_json::to_value(record)?)?,
+        }
+        Ok(())
+    }
+
+    fn csv_row(&mut self, record: Value) -> anyhow::Result<()> {
+        let fields = match record {
+            Value::Object(fields) => fields,
+            _ => anyhow::bail!("only records with fields can be written as CSV"),
+        };
+        let names: Vec<String> = fields.keys().cloned().collect();
+        match &self.csv_fields {
+            Some(header) => {
+                anyhow::ensure!(
+                    *header == names,
+                    "CSV rows must have the same fields, got {:?} after {:?}",
+                    names,
+                    header
+                );
+            }
+            None => {
+                let header: Vec<String> = names.iter().map(|name| csv_cell(name)).collect();
+                writeln!(self.writer, "{}", header.join(","))?;
+                self.csv_fields = Some(names);
+            }
+        }
+        let cells: Vec<String> = fields
+            .values()
+            .map(|value| match value {
+                Value::Null => String::new(),
+                Value::String(value) => csv_cell(value),
+                value => csv_cell(&value.to_string()),
+            })
+            .collect();
+        writeln!(self.writer, "{}", cells.join(","))?;
+        Ok(())
+    }
+}
+
+/// Quotes the cell if it has a separator, quote or line break in it.
+fn csv_cell(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Output, OutputFormat, Record};
+    use near_primitives::account::id::AccountId;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::Balance;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Row {
+        hash: CryptoHash,
+        account_id: AccountId,
+        #[serde(with = "near_primitives::serialize::u128_dec_format")]
+        balance: Balance,
+        note: Option<String>,
+        heights: Vec<u64>,
+    }
+
+    impl Record for Row {
+        fn human(&self) -> String {
+            format!("{} has {}", self.account_id, self.balance)
+        }
+    }
+
+    fn write(format: OutputFormat, rows: &[Row]) -> String {
+        let mut output = Output::new(format, vec![]);
+        output.line("header").unwrap();
+        for row in rows {
+            output.record(row).unwrap();
+        }
+        String::from_utf8(output.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_output() {
+        let hash = CryptoHash::hash_bytes(b"block");
+        let rows = [
+            Row {
+                hash,
+                account_id: "test0".parse().unwrap(),
+                balance: u128::MAX,
+                note: Some("a \"quoted\", note".to_string()),
+                heights: vec![1, 2],
+            },
+            Row {
+                hash,
+                account_id: "test1".parse().unwrap(),
+                balance: 1,
+                note: None,
+                heights: vec![],
+            },
+        ];
+        assert_eq!(
+            write(OutputFormat::Human, &rows),
+            format!("header\ntest0 has {}\ntest1 has 1\n", u128::MAX)
+        );
+        assert_eq!(
+            write(OutputFormat::Json, &rows),
+            format!(
+                "{{\"hash\":\"{}\",\"account_id\":\"test0\",\"balance\":\"{}\",\
+                 \"note\":\"a \\\"quoted\\\", note\",\"heights\":[1,2]}}\n\
+                 {{\"hash\":\"{}\",\"account_id\":\"test1\",\"balance\":\"1\",\
+                 \"note\":null,\"heights\":[]}}\n",
+                hash,
+                u128::MAX,
+                hash
+            )
+        );
+        assert_eq!(
+            write(OutputFormat::Csv, &rows),
+            format!(
+                "hash,account_id,balance,note,heights\n\
+                 {},test0,{},\"a \"\"quoted\"\", note\",\"[1,2]\"\n\
+                 {},test1,1,,[]\n",
+                hash,
+                u128::MAX,
+                hash
+            )
+        );
+
+        let mut output = Output::new(OutputFormat::Csv, vec![]);
+        assert!(output.record(&serde_json::json!([1, 2])).is_err());
+        output.record(&serde_json::json!({"a": 1})).unwrap();
+        assert!(output.record(&serde_json::json!({"b": 1})).is_err());
+    }
+}
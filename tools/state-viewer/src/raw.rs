@@ -0,0 +1,147 @@
+use near_store::{DBCol, Store};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Returns the id of the process holding the lock RocksDB takes on the `LOCK`
+/// file of the database at `store_path`, i.e. of a running node, if any.
+///
+/// RocksDB locks the file with `fcntl`, so it's left behind on exit and only
+/// the lock tells whether the database is in use.  Locks held by this process
+/// aren't reported.
+pub(crate) fn lock_holder(store_path: &Path) -> anyhow::Result<Option<libc::pid_t>> {
+    let file = match std::fs::File::open(store_path.join("LOCK")) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    // Safe because `flock` is plain old data and the file is open.
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as _;
+    lock.l_whence = libc::SEEK_SET as _;
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(if i32::from(lock.l_type) == libc::F_UNLCK { None } else { Some(lock.l_pid) })
+}
+
+/// Fails if a running node holds the database at `store_path`, which must
+/// not be written to then.
+pub(crate) fn ensure_unlocked(store_path: &Path) -> anyhow::Result<()> {
+    match lock_holder(store_path)? {
+        None => Ok(()),
+        Some(pid) => anyhow::bail!(
+            "the database at {} is locked by process {}, stop the node first",
+            store_path.display(),
+            pid
+        ),
+    }
+}
+
+/// Overwrites the value of `key` in `col` and returns the previous value.
+///
+/// Reference counted and insert-only columns are refused since their values
+/// are merged with the count or never meant to change.
+pub(crate) fn raw_set(
+    store: &Store,
+    col: DBCol,
+    key: &[u8],
+    value: &[u8],
+) -> anyhow::Result<Option<Vec<u8>>> {
+    anyhow::ensure!(!col.is_rc(), "{:?} is reference counted, so its values can't be set", col);
+    anyhow::ensure!(!col.is_insert_only(), "{:?} is insert-only, so its values can't be set", col);
+    let previous = store.get(col, key)?;
+    let mut update = store.store_update();
+    update.set(col, key, value);
+    update.commit()?;
+    Ok(previous)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ensure_unlocked, lock_holder, raw_set};
+    use near_store::test_utils::create_test_store;
+    use near_store::DBCol;
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    #[test]
+    fn test_raw_set() {
+        let store = create_test_store();
+        assert_eq!(raw_set(&store, DBCol::BlockMisc, b"HEAD", b"old").unwrap(), None);
+        assert_eq!(
+            raw_set(&store, DBCol::BlockMisc, b"HEAD", b"new").unwrap(),
+            Some(b"old".to_vec())
+        );
+        assert_eq!(store.get(DBCol::BlockMisc, b"HEAD").unwrap(), Some(b"new".to_vec()));
+        assert!(raw_set(&store, DBCol::State, b"key", b"value").is_err());
+        assert!(raw_set(&store, DBCol::Chunks, b"key", b"value").is_err());
+    }
+
+    #[test]
+    fn test_lock_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(lock_holder(dir.path()).unwrap(), None);
+        // A node which exited leaves the file behind without a lock.
+        std::fs::write(dir.path().join("LOCK"), b"").unwrap();
+        assert_eq!(lock_holder(dir.path()).unwrap(), None);
+        ensure_unlocked(dir.path()).unwrap();
+
+        let node = LockingProcess::spawn(dir.path());
+        assert_eq!(lock_holder(dir.path()).unwrap(), Some(node.0));
+        let err = ensure_unlocked(dir.path()).unwrap_err();
+        assert!(err.to_string().contains(&format!("locked by process {}", node.0)), "{}", err);
+        drop(node);
+        assert_eq!(lock_holder(dir.path()).unwrap(), None);
+    }
+
+    /// A child process holding the lock on `LOCK` the way RocksDB does, since
+    /// locks of this process aren't visible to it.  Killed when dropped.
+    struct LockingProcess(libc::pid_t);
+
+    impl LockingProcess {
+        fn spawn(store_path: &Path) -> Self {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .open(store_path.join("LOCK"))
+                .unwrap();
+            let mut fds = [0; 2];
+            assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+            let pid = unsafe { libc::fork() };
+            assert!(pid >= 0, "{}", std::io::Error::last_os_error());
+            if pid == 0 {
+                // The test harness is multithreaded, so the child may only
+                // make async-signal-safe calls.
+                unsafe {
+                    let mut lock: libc::flock = std::mem::zeroed();
+                    lock.l_type = libc::F_WRLCK as _;
+                    lock.l_whence = libc::SEEK_SET as _;
+                    let locked = (libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &lock) == 0) as u8;
+                    libc::write(fds[1], &locked as *const u8 as *const libc::c_void, 1);
+                    loop {
+                        libc::pause();
+                    }
+                }
+            }
+            let process = Self(pid);
+            let mut locked = 0u8;
+            let read =
+                unsafe { libc::read(fds[0], &mut locked as *mut u8 as *mut libc::c_void, 1) };
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+            assert_eq!((read, locked), (1, 1), "the child failed to take the lock");
+            process
+        }
+    }
+
+    impl Drop for LockingProcess {
+        fn drop(&mut self) {
+            unsafe {
+                libc::kill(self.0, libc::SIGKILL);
+                libc::waitpid(self.0, std::ptr::null_mut(), 0);
+            }
+        }
+    }
+}
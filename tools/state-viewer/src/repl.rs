@@ -0,0 +1,204 @@
+use crate::block::print_blocks;
+use crate::cli::parse_column;
+use crate::dump_column::{dump_column, entry_to_json};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_store::{DBCol, Store};
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+const HELP: &str = "\
+get <column> <key>             print the value of a key
+iter <column> [prefix] [limit] print entries whose keys start with prefix, 10 by default
+height <height>                print the blocks at a height
+block <hash>                   print a block
+history                        print the previous commands
+!<n>                           run the n-th previous command again
+help                           print this help
+quit                           exit
+Keys and prefixes starting with 0x are hex, other ones are taken as is, e.g. HEAD.
+Columns are named as in DBCol, e.g. BlockMisc or ColBlockMisc.";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Get { col: DBCol, key: Vec<u8> },
+    Iter { col: DBCol, prefix: Vec<u8>, limit: usize },
+    Height(BlockHeight),
+    Block(CryptoHash),
+    History,
+    Help,
+    Quit,
+}
+
+/// Parses a key given as `0x` prefixed hex or as the bytes of the string.
+fn parse_key(key: &str) -> anyhow::Result<Vec<u8>> {
+    match key.strip_prefix("0x") {
+        Some(hex) => Ok(hex::decode(hex)?),
+        None => Ok(key.as_bytes().to_vec()),
+    }
+}
+
+fn parse_command(line: &str) -> anyhow::Result<Command> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let col = |name: &str| parse_column(name).map_err(anyhow::Error::msg);
+    let command = match words.as_slice() {
+        ["get", col_name, key] => Command::Get { col: col(col_name)?, key: parse_key(key)? },
+        ["iter", col_name, rest @ ..] if rest.len() <= 2 => Command::Iter {
+            col: col(col_name)?,
+            prefix: rest.first().map(|prefix| parse_key(prefix)).transpose()?.unwrap_or_default(),
+            limit: rest.get(1).map(|limit| limit.parse()).transpose()?.unwrap_or(10),
+        },
+        ["height", height] => Command::Height(height.parse()?),
+        ["block", hash] => Command::Block(CryptoHash::from_str(hash).map_err(anyhow::Error::msg)?),
+        ["history"] => Command::History,
+        ["help"] => Command::Help,
+        ["quit"] | ["exit"] => Command::Quit,
+        _ => anyhow::bail!("can't parse `{}`, see `help`", line),
+    };
+    Ok(command)
+}
+
+/// Runs one command, returns false if the REPL should exit.
+fn run_command(
+    store: &Store,
+    command: Command,
+    history: &[String],
+    output: &mut dyn Write,
+) -> anyhow::Result<bool> {
+    match command {
+        Command::Get { col, key } => match store.get(col, &key)? {
+            Some(value) => writeln!(output, "{}", entry_to_json(col, &key, &value))?,
+            None => writeln!(output, "{} {} not found", <&str>::from(col), hex::encode(&key))?,
+        },
+        Command::Iter { col, prefix, limit } => {
+            dump_column(store, col, &prefix, Some(limit), output)?;
+        }
+        Command::Height(height) => print_blocks(None, Some(height), store, output)?,
+        Command::Block(hash) => print_blocks(Some(hash), None, store, output)?,
+        Command::History => {
+            for (i, line) in history.iter().enumerate() {
+                writeln!(output, "{:>4} {}", i + 1, line)?;
+            }
+        }
+        Command::Help => writeln!(output, "{}", HELP)?,
+        Command::Quit => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Reads commands from `input` until `quit` or the end of the input and
+/// prints their results to `output`, so that the database is only opened
+/// once for many lookups.  Errors are printed and don't end the REPL.
+pub(crate) fn run_repl(
+    store: &Store,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let mut history: Vec<String> = vec![];
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let mut line = line.trim().to_string();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(n) = line.strip_prefix('!') {
+            match n.parse::<usize>().ok().and_then(|n| history.get(n.wrapping_sub(1))) {
+                Some(previous) => line = previous.clone(),
+                None => {
+                    writeln!(output, "error: no command {} in the history", n)?;
+                    continue;
+                }
+            }
+        }
+        let result = parse_command(&line).and_then(|command| {
+            if command != Command::History {
+                history.push(line.clone());
+            }
+            run_command(store, command, &history, output)
+        });
+        match result {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(err) => writeln!(output, "error: {:#}", err)?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_command, run_repl, Command};
+    use crate::test_utils::setup_env;
+    use near_primitives::utils::index_to_bytes;
+    use near_store::DBCol;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("get ColBlockMisc HEAD").unwrap(),
+            Command::Get { col: DBCol::BlockMisc, key: b"HEAD".to_vec() }
+        );
+        assert_eq!(
+            parse_command("iter State 0x0a0b").unwrap(),
+            Command::Iter { col: DBCol::State, prefix: vec![10, 11], limit: 10 }
+        );
+        assert_eq!(
+            parse_command("  iter  State  0x 3 ").unwrap(),
+            Command::Iter { col: DBCol::State, prefix: vec![], limit: 3 }
+        );
+        assert_eq!(parse_command("height 12345").unwrap(), Command::Height(12345));
+        assert!(parse_command("get NoSuchCol HEAD").is_err());
+        assert!(parse_command("iter State 0xzz").is_err());
+        assert!(parse_command("height").is_err());
+    }
+
+    #[test]
+    fn test_run_repl() {
+        let setup = setup_env(&["test0", "test1"], 2);
+        let store = setup.store;
+        let hash = setup.env.clients[0].chain.head().unwrap().last_block_hash;
+        let height_key = hex::encode(index_to_bytes(2));
+
+        let input = format!(
+            "get ColBlockMisc HEAD\nget BlockHeight 0x{}\n\nblock {}\nheight 7\n\
+             iter BlockHeight 0x 2\nfoo\n!2\n!9\nhistory\nquit\nheight 1\n",
+            height_key, hash
+        );
+        let mut output = vec![];
+        run_repl(&store, &mut input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let responses: Vec<&str> = output.split("> ").skip(1).collect();
+        assert_eq!(responses.len(), 11, "{}", output);
+        // BlockMisc has values of different types so they aren't decoded.
+        assert!(
+            responses[0].starts_with(&format!("{{\"key\":\"{}\"", hex::encode("HEAD"))),
+            "{}",
+            output
+        );
+        assert!(responses[0].contains("\"error\":\"values of BlockMisc"), "{}", output);
+        assert_eq!(
+            responses[1],
+            format!("{{\"key\":\"{}\",\"value\":\"{}\"}}\n", height_key, hash)
+        );
+        assert!(responses[3].contains(&format!("\"hash\": \"{}\"", hash)), "{}", output);
+        assert_eq!(responses[4], "error: no blocks at height 7\n");
+        assert_eq!(responses[5].lines().count(), 2);
+        assert!(responses[6].starts_with("error: can't parse `foo`"), "{}", output);
+        assert_eq!(responses[7], responses[1]);
+        assert_eq!(responses[8], "error: no command 9 in the history\n");
+        assert_eq!(
+            responses[9],
+            format!(
+                "   1 get ColBlockMisc HEAD\n   2 get BlockHeight 0x{}\n   3 block {}\n   \
+                 4 height 7\n   5 iter BlockHeight 0x 2\n   6 get BlockHeight 0x{}\n",
+                height_key, hash, height_key
+            )
+        );
+        // Nothing is read after `quit`.
+        assert_eq!(responses[10], "");
+    }
+}
@@ -0,0 +1,207 @@
+use crate::output::Record;
+use near_primitives::account::id::AccountId;
+use near_primitives::trie_key::trie_key_parsers;
+use near_primitives::types::StateRoot;
+use near_store::{Trie, TrieIterator};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::iter::Peekable;
+
+/// Bytes of the keys and values an account has in the trie of its shard.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct AccountSize {
+    pub total: u64,
+    pub account_id: AccountId,
+    pub account: u64,
+    pub contract_code: u64,
+    pub access_keys: u64,
+    pub contract_data: u64,
+}
+
+impl Record for AccountSize {
+    fn human(&self) -> String {
+        format!(
+            "{} {} bytes: account {}, contract code {}, access keys {}, contract data {}",
+            self.account_id,
+            self.total,
+            self.account,
+            self.contract_code,
+            self.access_keys,
+            self.contract_data
+        )
+    }
+}
+
+/// Totals of all accounts of the shard.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ShardSize {
+    pub num_accounts: u64,
+    pub account: u64,
+    pub contract_code: u64,
+    pub access_keys: u64,
+    pub contract_data: u64,
+}
+
+type ParseAccountId = fn(&[u8]) -> Result<AccountId, std::io::Error>;
+
+/// The entries of one of the per-account trie columns, which are sorted by
+/// account id since the separator after the id sorts before any character
+/// an id can have.
+struct Column<'a> {
+    prefix: Vec<u8>,
+    parse: ParseAccountId,
+    iter: Peekable<TrieIterator<'a>>,
+}
+
+impl Column<'_> {
+    /// Returns the account of the next entry of the column.
+    fn peek(&mut self) -> anyhow::Result<Option<AccountId>> {
+        match self.iter.peek() {
+            None => Ok(None),
+            Some(Err(err)) => Err(anyhow::anyhow!("{}", err)),
+            Some(Ok((key, _))) if !key.starts_with(&self.prefix) => Ok(None),
+            Some(Ok((key, _))) => Ok(Some((self.parse)(key)?)),
+        }
+    }
+
+    /// Sums the sizes of the keys and values of the entries of `account_id`.
+    fn take(&mut self, account_id: &AccountId) -> anyhow::Result<u64> {
+        let mut bytes = 0;
+        while self.peek()?.as_ref() == Some(account_id) {
+            let (key, value) = self.iter.next().unwrap()?;
+            bytes += (key.len() + value.len()) as u64;
+        }
+        Ok(bytes)
+    }
+}
+
+/// Walks the account, contract code, access key and contract data entries of
+/// the trie under `state_root` and returns the `top` accounts with the most
+/// bytes of keys and values, largest first, and the totals of the shard.
+///
+/// The four columns are walked side by side, so every account is summed up
+/// in one go and only the `top` largest ones are kept in memory.
+pub(crate) fn state_size(
+    trie: &Trie,
+    state_root: &StateRoot,
+    top: usize,
+) -> anyhow::Result<(Vec<AccountSize>, ShardSize)> {
+    let parsers: [ParseAccountId; 4] = [
+        trie_key_parsers::parse_account_id_from_account_key,
+        trie_key_parsers::parse_account_id_from_contract_code_key,
+        trie_key_parsers::parse_account_id_from_access_key_key,
+        trie_key_parsers::parse_account_id_from_contract_data_key,
+    ];
+    let mut columns = vec![];
+    for (prefix, parse) in
+        trie_key_parsers::get_raw_prefixes_for_account_id_prefix("").into_iter().zip(parsers)
+    {
+        let mut iter = TrieIterator::new(trie, state_root)?;
+        iter.seek(&prefix)?;
+        columns.push(Column { prefix, parse, iter: iter.peekable() });
+    }
+
+    let mut largest = BinaryHeap::new();
+    let mut shard = ShardSize::default();
+    loop {
+        let mut next: Option<AccountId> = None;
+        for column in &mut columns {
+            if let Some(account_id) = column.peek()? {
+                if next.as_ref().map_or(true, |next| account_id < *next) {
+                    next = Some(account_id);
+                }
+            }
+        }
+        let account_id = match next {
+            Some(account_id) => account_id,
+            None => break,
+        };
+        let mut size = AccountSize {
+            account: columns[0].take(&account_id)?,
+            contract_code: columns[1].take(&account_id)?,
+            access_keys: columns[2].take(&account_id)?,
+            contract_data: columns[3].take(&account_id)?,
+            account_id,
+            total: 0,
+        };
+        size.total = size.account + size.contract_code + size.access_keys + size.contract_data;
+        shard.num_accounts += 1;
+        shard.account += size.account;
+        shard.contract_code += size.contract_code;
+        shard.access_keys += size.access_keys;
+        shard.contract_data += size.contract_data;
+        largest.push(Reverse(size));
+        if largest.len() > top {
+            largest.pop();
+        }
+    }
+    let largest = largest.into_sorted_vec().into_iter().map(|Reverse(size)| size).collect();
+    Ok((largest, shard))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{state_size, AccountSize};
+    use crate::output::Record;
+    use crate::test_utils::setup_env_with_genesis;
+    use near_chain::{ChainGenesis, RuntimeAdapter};
+    use near_chain_configs::Genesis;
+    use near_primitives::state_record::StateRecord;
+    use nearcore::config::GenesisExt;
+
+    #[test]
+    fn test_state_size() {
+        let mut genesis = Genesis::test(
+            vec!["test0".parse().unwrap(), "test1".parse().unwrap(), "test1-a".parse().unwrap()],
+            1,
+        );
+        genesis.records.0.push(StateRecord::Data {
+            account_id: "test1".parse().unwrap(),
+            data_key: b"key".to_vec(),
+            value: vec![0; 10_000],
+        });
+        let mut setup = setup_env_with_genesis(genesis, ChainGenesis::test(), 0);
+        let block = setup.produce_blocks([1]).pop().unwrap();
+        let state_root = block.chunks()[0].prev_state_root();
+        let trie = setup.runtime.get_trie_for_shard(0, block.header().prev_hash()).unwrap();
+
+        let (all, shard) = state_size(&trie, &state_root, 100).unwrap();
+        // The genesis has the accounts given to it and the protocol treasury.
+        assert_eq!(shard.num_accounts, all.len() as u64);
+        assert!(all.len() >= 3, "{:?}", all);
+        let largest = &all[0];
+        assert_eq!(largest.account_id.as_ref(), "test1");
+        assert!(largest.contract_data > 10_000);
+        assert_eq!(
+            largest.total,
+            largest.account + largest.contract_code + largest.access_keys + largest.contract_data
+        );
+        assert!(all.windows(2).all(|pair| pair[0] >= pair[1]));
+        // Every test account has an account record and a full access key.
+        let test1_a = all.iter().find(|size| size.account_id.as_ref() == "test1-a").unwrap();
+        assert!(test1_a.account > 0);
+        assert!(test1_a.access_keys > 0);
+        assert_eq!(test1_a.contract_data, 0);
+        assert_eq!(shard.contract_data, largest.contract_data);
+        assert_eq!(shard.access_keys, all.iter().map(|size| size.access_keys).sum::<u64>());
+
+        let (top, top_shard) = state_size(&trie, &state_root, 2).unwrap();
+        assert_eq!(top, all[..2]);
+        assert_eq!(top_shard, shard);
+        assert!(state_size(&trie, &state_root, 0).unwrap().0.is_empty());
+
+        let size = AccountSize {
+            total: 3,
+            account_id: "test0".parse().unwrap(),
+            account: 1,
+            contract_code: 0,
+            access_keys: 2,
+            contract_data: 0,
+        };
+        assert_eq!(
+            size.human(),
+            "test0 3 bytes: account 1, contract code 0, access keys 2, contract data 0"
+        );
+    }
+}
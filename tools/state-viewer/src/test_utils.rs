@@ -0,0 +1,99 @@
+//! Single node chain the tests of the commands run on.
+use near_chain::{ChainGenesis, Provenance, RuntimeAdapter};
+use near_chain_configs::Genesis;
+use near_client::test_utils::TestEnv;
+use near_crypto::{InMemorySigner, KeyType};
+use near_network::types::NetworkClientResponses;
+use near_primitives::block::Block;
+use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{BlockHeight, Nonce};
+use near_store::test_utils::create_test_store;
+use near_store::Store;
+use nearcore::config::GenesisExt;
+use nearcore::{NightshadeRuntime, TrackedConfig};
+use std::path::Path;
+use std::sync::Arc;
+
+pub(crate) struct TestSetup {
+    pub genesis: Genesis,
+    /// The store of the node, which the commands read.
+    pub store: Store,
+    pub runtime: Arc<NightshadeRuntime>,
+    pub env: TestEnv,
+}
+
+impl TestSetup {
+    /// Produces and processes a block at each of the heights.
+    pub fn produce_blocks(&mut self, heights: impl IntoIterator<Item = BlockHeight>) -> Vec<Block> {
+        heights
+            .into_iter()
+            .map(|height| {
+                let block = self.env.clients[0].produce_block(height).unwrap().unwrap();
+                self.env.process_block(0, block.clone(), Provenance::PRODUCED);
+                block
+            })
+            .collect()
+    }
+
+    /// Submits a transaction sending 100 yocto from `signer_id` to
+    /// `receiver_id`, signed with the key the test genesis gives the account.
+    pub fn send_money(
+        &mut self,
+        nonce: Nonce,
+        signer_id: &str,
+        receiver_id: &str,
+    ) -> SignedTransaction {
+        let signer =
+            InMemorySigner::from_seed(signer_id.parse().unwrap(), KeyType::ED25519, signer_id);
+        let genesis_hash = *self.env.clients[0].chain.genesis().hash();
+        let tx = SignedTransaction::send_money(
+            nonce,
+            signer_id.parse().unwrap(),
+            receiver_id.parse().unwrap(),
+            &signer,
+            100,
+            genesis_hash,
+        );
+        let response = self.env.clients[0].process_tx(tx.clone(), false, false);
+        assert_eq!(response, NetworkClientResponses::ValidTx);
+        tx
+    }
+}
+
+/// Runtime tracking all shards of `genesis`, storing its data in `store`.
+pub(crate) fn test_runtime(store: &Store, genesis: &Genesis) -> Arc<NightshadeRuntime> {
+    Arc::new(NightshadeRuntime::test_with_runtime_config_store(
+        Path::new("."),
+        store.clone(),
+        genesis,
+        TrackedConfig::AllShards,
+        RuntimeConfigStore::test(),
+        None,
+    ))
+}
+
+/// Sets up a single shard chain with the test accounts, the first one of
+/// which is the only validator, and produces the blocks at heights 1 to
+/// `num_blocks`.
+pub(crate) fn setup_env(accounts: &[&str], num_blocks: BlockHeight) -> TestSetup {
+    let genesis =
+        Genesis::test(accounts.iter().map(|account_id| account_id.parse().unwrap()).collect(), 1);
+    setup_env_with_genesis(genesis, ChainGenesis::test(), num_blocks)
+}
+
+/// Like `setup_env`, for tests which need to tweak the genesis.
+pub(crate) fn setup_env_with_genesis(
+    genesis: Genesis,
+    chain_genesis: ChainGenesis,
+    num_blocks: BlockHeight,
+) -> TestSetup {
+    let store = create_test_store();
+    let runtime = test_runtime(&store, &genesis);
+    let env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(vec![runtime.clone() as Arc<dyn RuntimeAdapter>])
+        .build();
+    let mut setup = TestSetup { genesis, store, runtime, env };
+    setup.produce_blocks(1..=num_blocks);
+    setup
+}
@@ -0,0 +1,235 @@
+use crate::output::Record;
+use near_primitives::account::id::AccountId;
+use near_primitives::block::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::Receipt;
+use near_primitives::transaction::{ExecutionOutcomeWithIdAndProof, SignedTransaction};
+use near_primitives::types::Gas;
+use near_primitives::utils::index_to_bytes;
+use near_primitives::views::{
+    ExecutionOutcomeWithIdView, ExecutionStatusView, ReceiptView, SignedTransactionView,
+};
+use near_store::{DBCol, Store};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Outcomes of the transaction or receipt, one per block it was executed in,
+/// more than one if it was executed on forks.
+fn outcomes(store: &Store, id: &CryptoHash) -> anyhow::Result<Vec<ExecutionOutcomeWithIdView>> {
+    let outcomes = store
+        .get_ser::<Vec<ExecutionOutcomeWithIdAndProof>>(DBCol::TransactionResult, id.as_ref())?
+        .unwrap_or_default();
+    Ok(outcomes.into_iter().map(Into::into).collect())
+}
+
+/// Looks up a transaction and its outcomes.  Either may be missing, e.g.
+/// transactions are garbage collected while their outcomes aren't.
+pub(crate) fn lookup_transaction(store: &Store, hash: &CryptoHash) -> anyhow::Result<Value> {
+    let transaction = store.get_ser::<SignedTransaction>(DBCol::Transactions, hash.as_ref())?;
+    let outcomes = outcomes(store, hash)?;
+    if transaction.is_none() && outcomes.is_empty() {
+        anyhow::bail!("{} was not found in the Transactions and TransactionResult columns", hash);
+    }
+    Ok(json!({
+        "transaction": transaction.map(SignedTransactionView::from),
+        "outcomes": outcomes,
+    }))
+}
+
+/// Looks up a receipt and its outcomes, see [`lookup_transaction`].
+pub(crate) fn lookup_receipt(store: &Store, id: &CryptoHash) -> anyhow::Result<Value> {
+    let receipt = store.get_ser::<Receipt>(DBCol::Receipts, id.as_ref())?;
+    let outcomes = outcomes(store, id)?;
+    if receipt.is_none() && outcomes.is_empty() {
+        anyhow::bail!("{} was not found in the Receipts and TransactionResult columns", id);
+    }
+    Ok(json!({ "receipt": receipt.map(ReceiptView::from), "outcomes": outcomes }))
+}
+
+/// Whether the block is the one on the canonical chain at its height.
+fn is_canonical(store: &Store, block_hash: &CryptoHash) -> anyhow::Result<bool> {
+    let header = match store.get_ser::<BlockHeader>(DBCol::BlockHeader, block_hash.as_ref())? {
+        Some(header) => header,
+        None => return Ok(false),
+    };
+    let canonical: Option<CryptoHash> =
+        store.get_ser(DBCol::BlockHeight, &index_to_bytes(header.height()))?;
+    Ok(canonical.as_ref() == Some(block_hash))
+}
+
+/// A transaction or receipt of a traced transaction, with its outcome in a
+/// canonical block, or in any block if it was only executed on forks.
+#[derive(Serialize, Debug)]
+pub(crate) struct TraceNode {
+    /// 0 for the transaction, 1 for the receipt it was converted to, and so
+    /// on for the receipts created by the outcomes.
+    pub depth: usize,
+    pub kind: &'static str,
+    pub id: CryptoHash,
+    /// The transaction or receipt whose outcome created this receipt.
+    pub parent_id: Option<CryptoHash>,
+    /// The outcome fields are empty if there is no outcome, i.e. the receipt
+    /// hasn't been executed yet or its outcome was garbage collected.
+    pub block_hash: Option<CryptoHash>,
+    pub executor_id: Option<AccountId>,
+    pub status: Option<ExecutionStatusView>,
+    pub gas_burnt: Option<Gas>,
+    pub logs: Vec<String>,
+}
+
+impl Record for TraceNode {
+    fn human(&self) -> String {
+        let indent = "  ".repeat(self.depth);
+        let (status, executor_id, block_hash, gas_burnt) =
+            match (&self.status, &self.executor_id, &self.block_hash, self.gas_burnt) {
+                (Some(status), Some(executor_id), Some(block_hash), Some(gas_burnt)) => {
+                    (status, executor_id, block_hash, gas_burnt)
+                }
+                _ => {
+                    return format!(
+                        "{}{} {}: no outcome, not executed yet or garbage collected",
+                        indent, self.kind, self.id
+                    )
+                }
+            };
+        let mut line = format!(
+            "{}{} {} on {} in block {}: {:?}, {} gas burnt",
+            indent, self.kind, self.id, executor_id, block_hash, status, gas_burnt
+        );
+        for log in &self.logs {
+            line += &format!("\n{}  log: {}", indent, log);
+        }
+        line
+    }
+}
+
+impl TraceNode {
+    fn new(
+        depth: usize,
+        id: CryptoHash,
+        parent_id: Option<CryptoHash>,
+        outcome: Option<&ExecutionOutcomeWithIdView>,
+    ) -> Self {
+        TraceNode {
+            depth,
+            kind: if parent_id.is_none() { "transaction" } else { "receipt" },
+            id,
+            parent_id,
+            block_hash: outcome.map(|outcome| outcome.block_hash),
+            executor_id: outcome.map(|outcome| outcome.outcome.executor_id.clone()),
+            status: outcome.map(|outcome| outcome.outcome.status.clone()),
+            gas_burnt: outcome.map(|outcome| outcome.outcome.gas_burnt),
+            logs: outcome.map(|outcome| outcome.outcome.logs.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// Follows the receipts created by the outcome of the transaction and by the
+/// outcomes of those receipts, like the `EXPERIMENTAL_tx_status` RPC, and
+/// calls `on_node` for each of them depth first, the transaction first.
+/// Receipts without an outcome are reported as such and have no children.
+pub(crate) fn trace_transaction(
+    store: &Store,
+    hash: &CryptoHash,
+    on_node: &mut dyn FnMut(TraceNode) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !outcomes(store, hash)?.is_empty(),
+        "{} has no outcome in the TransactionResult column",
+        hash
+    );
+    let mut stack = vec![(0, *hash, None)];
+    while let Some((depth, id, parent_id)) = stack.pop() {
+        let mut outcome = None;
+        for candidate in outcomes(store, &id)? {
+            if is_canonical(store, &candidate.block_hash)? {
+                outcome = Some(candidate);
+                break;
+            }
+            outcome.get_or_insert(candidate);
+        }
+        if let Some(outcome) = &outcome {
+            let children = &outcome.outcome.receipt_ids;
+            stack.extend(children.iter().rev().map(|child| (depth + 1, *child, Some(id))));
+        }
+        on_node(TraceNode::new(depth, id, parent_id, outcome.as_ref()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lookup_receipt, lookup_transaction, trace_transaction, TraceNode};
+    use crate::output::Record;
+    use crate::test_utils::setup_env;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::views::ExecutionStatusView;
+    use near_store::DBCol;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_lookup() {
+        let mut setup = setup_env(&["test0", "test1"], 0);
+        let tx = setup.send_money(1, "test0", "test1");
+        setup.produce_blocks(1..5);
+        let store = setup.store;
+
+        let found = lookup_transaction(&store, &tx.get_hash()).unwrap();
+        assert_eq!(found["transaction"]["receiver_id"], "test1");
+        assert_eq!(found["transaction"]["actions"][0]["Transfer"]["deposit"], "100");
+        let outcomes = found["outcomes"].as_array().unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert!(outcomes[0]["outcome"]["gas_burnt"].as_u64().unwrap() > 0);
+
+        let receipt_id = outcomes[0]["outcome"]["receipt_ids"][0].as_str().unwrap();
+        let receipt_id = CryptoHash::from_str(receipt_id).unwrap();
+        let found = lookup_receipt(&store, &receipt_id).unwrap();
+        assert_eq!(found["receipt"]["predecessor_id"], "test0");
+        assert_eq!(found["receipt"]["receiver_id"], "test1");
+        assert_eq!(found["outcomes"].as_array().unwrap().len(), 1);
+
+        let missing = CryptoHash::hash_bytes(b"missing");
+        let err = lookup_transaction(&store, &missing).unwrap_err().to_string();
+        assert!(err.contains("Transactions and TransactionResult"), "{}", err);
+        let err = lookup_receipt(&store, &missing).unwrap_err().to_string();
+        assert!(err.contains("Receipts and TransactionResult"), "{}", err);
+
+        let trace = |hash: &CryptoHash| {
+            let mut nodes: Vec<TraceNode> = vec![];
+            trace_transaction(&store, hash, &mut |node| {
+                nodes.push(node);
+                Ok(())
+            })
+            .map(|()| nodes)
+        };
+        let nodes = trace(&tx.get_hash()).unwrap();
+        assert!(nodes.len() >= 2, "{:?}", nodes);
+        assert_eq!(nodes[0].kind, "transaction");
+        assert_eq!(nodes[0].status, Some(ExecutionStatusView::SuccessReceiptId(receipt_id)));
+        assert_eq!((nodes[1].depth, nodes[1].id), (1, receipt_id));
+        assert_eq!(nodes[1].parent_id, Some(tx.get_hash()));
+        assert_eq!(nodes[1].executor_id.as_ref().unwrap().as_ref(), "test1");
+        assert!(matches!(nodes[1].status, Some(ExecutionStatusView::SuccessValue(_))));
+        assert!(nodes[1].gas_burnt.unwrap() > 0);
+        let line = nodes[1].human();
+        assert!(
+            line.starts_with(&format!("  receipt {} on test1 in block ", receipt_id)),
+            "{}",
+            line
+        );
+        assert!(trace(&missing).is_err());
+
+        // Without the outcome of the receipt, the trace stops at it.
+        let mut update = store.store_update();
+        update.delete(DBCol::TransactionResult, receipt_id.as_ref());
+        update.commit().unwrap();
+        let nodes = trace(&tx.get_hash()).unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1].id, receipt_id);
+        assert_eq!(nodes[1].status, None);
+        assert_eq!(
+            nodes[1].human(),
+            format!("  receipt {}: no outcome, not executed yet or garbage collected", receipt_id)
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use crate::output::Record;
+use near_chain::types::ValidatorInfoIdentifier;
+use near_epoch_manager::EpochManager;
+use near_primitives::account::id::AccountId;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{
+    Balance, BlockHeight, EpochHeight, EpochId, NumBlocks, ValidatorKickoutReason,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Produced and expected blocks and chunks of a validator in an epoch.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct ValidatorPerformance {
+    pub account_id: AccountId,
+    #[serde(with = "near_primitives::serialize::u128_dec_format")]
+    pub stake: Balance,
+    pub produced_blocks: NumBlocks,
+    pub expected_blocks: NumBlocks,
+    pub produced_chunks: NumBlocks,
+    pub expected_chunks: NumBlocks,
+    /// Why the validator was kicked out at the end of the epoch.
+    pub kickout: Option<ValidatorKickoutReason>,
+}
+
+#[derive(Debug)]
+pub(crate) struct EpochPerformance {
+    pub epoch_id: EpochId,
+    pub epoch_height: EpochHeight,
+    pub start_height: BlockHeight,
+    /// Whether the epoch is over, otherwise the numbers are up to the head
+    /// and the kickouts aren't known yet.
+    pub finished: bool,
+    /// The validators of the epoch, followed by the accounts which weren't
+    /// validators in the epoch but were kicked out at its end, e.g. proposals
+    /// with not enough stake, with no stake and nothing expected.
+    pub validators: Vec<ValidatorPerformance>,
+}
+
+/// Collects the block and chunk production of the validators of an epoch
+/// from the epoch summary the epoch manager stores at the end of the epoch,
+/// or from the aggregated info up to the head for the epoch of the head.
+pub(crate) fn epoch_performance(
+    epoch_manager: &EpochManager,
+    epoch_id: &EpochId,
+    head_hash: &CryptoHash,
+) -> anyhow::Result<EpochPerformance> {
+    let finished = epoch_manager.get_epoch_id(head_hash)? != *epoch_id;
+    let (identifier, mut kickouts) = if finished {
+        let summary = epoch_manager.get_epoch_validator_info(epoch_id)?;
+        (ValidatorInfoIdentifier::EpochId(epoch_id.clone()), summary.validator_kickout)
+    } else {
+        (ValidatorInfoIdentifier::BlockHash(*head_hash), HashMap::new())
+    };
+    let info = epoch_manager.get_validator_info(identifier)?;
+    let mut validators: Vec<_> = info
+        .current_validators
+        .into_iter()
+        .map(|validator| ValidatorPerformance {
+            kickout: kickouts.remove(&validator.account_id),
+            account_id: validator.account_id,
+            stake: validator.stake,
+            produced_blocks: validator.num_produced_blocks,
+            expected_blocks: validator.num_expected_blocks,
+            produced_chunks: validator.num_produced_chunks,
+            expected_chunks: validator.num_expected_chunks,
+        })
+        .collect();
+    let mut other_kickouts: Vec<_> = kickouts.into_iter().collect();
+    other_kickouts.sort_by(|(a, _), (b, _)| a.cmp(b));
+    validators.extend(other_kickouts.into_iter().map(|(account_id, reason)| {
+        ValidatorPerformance {
+            account_id,
+            stake: 0,
+            produced_blocks: 0,
+            expected_blocks: 0,
+            produced_chunks: 0,
+            expected_chunks: 0,
+            kickout: Some(reason),
+        }
+    }));
+    Ok(EpochPerformance {
+        epoch_id: epoch_id.clone(),
+        epoch_height: info.epoch_height,
+        start_height: info.epoch_start_height,
+        finished,
+        validators,
+    })
+}
+
+/// Returns the ids of up to `n` epochs, newest first, going back from the
+/// epoch of the head.
+pub(crate) fn last_epochs(
+    epoch_manager: &EpochManager,
+    head_hash: &CryptoHash,
+    n: usize,
+) -> anyhow::Result<Vec<EpochId>> {
+    let mut epoch_ids: Vec<EpochId> = vec![];
+    let mut hash = *head_hash;
+    while epoch_ids.len() < n {
+        let block_info = epoch_manager.get_block_info(&hash)?;
+        // Genesis is in the same epoch as the first blocks after it.
+        if epoch_ids.last() == Some(block_info.epoch_id()) {
+            break;
+        }
+        epoch_ids.push(block_info.epoch_id().clone());
+        let first_block_info = epoch_manager.get_block_info(block_info.epoch_first_block())?;
+        if *first_block_info.prev_hash() == CryptoHash::default() {
+            break;
+        }
+        hash = *first_block_info.prev_hash();
+    }
+    Ok(epoch_ids)
+}
+
+fn percent(produced: NumBlocks, expected: NumBlocks) -> String {
+    if expected == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", produced as f64 * 100.0 / expected as f64)
+}
+
+pub(crate) fn epoch_line(epoch: &EpochPerformance) -> String {
+    format!(
+        "Epoch #{} {:?} starting at height {}{}",
+        epoch.epoch_height,
+        epoch.epoch_id,
+        epoch.start_height,
+        if epoch.finished { "" } else { ", in progress" }
+    )
+}
+
+/// A validator of an epoch, with the epoch to tell the rows of several epochs
+/// apart.
+#[derive(Serialize)]
+pub(crate) struct ValidatorRecord<'a> {
+    pub epoch_id: &'a EpochId,
+    pub epoch_height: EpochHeight,
+    #[serde(flatten)]
+    pub validator: &'a ValidatorPerformance,
+}
+
+impl Record for ValidatorRecord<'_> {
+    fn human(&self) -> String {
+        let validator = self.validator;
+        let mut line = format!(
+            "{} stake {}: blocks {}/{} ({}), chunks {}/{} ({})",
+            validator.account_id,
+            validator.stake,
+            validator.produced_blocks,
+            validator.expected_blocks,
+            percent(validator.produced_blocks, validator.expected_blocks),
+            validator.produced_chunks,
+            validator.expected_chunks,
+            percent(validator.produced_chunks, validator.expected_chunks),
+        );
+        if let Some(reason) = &validator.kickout {
+            line += &format!(", kicked out: {:?}", reason);
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{epoch_line, epoch_performance, last_epochs, ValidatorRecord};
+    use crate::output::Record;
+    use crate::test_utils::setup_env_with_genesis;
+    use near_chain::ChainGenesis;
+    use near_chain_configs::Genesis;
+    use near_epoch_manager::EpochManager;
+    use nearcore::config::GenesisExt;
+
+    #[test]
+    fn test_epoch_performance() {
+        let mut genesis =
+            Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+        genesis.config.epoch_length = 5;
+        let chain_genesis = ChainGenesis::from(&genesis);
+        let setup = setup_env_with_genesis(genesis, chain_genesis, 13);
+        let head = setup.env.clients[0].chain.head().unwrap();
+        let epoch_manager =
+            EpochManager::new_from_genesis_config(setup.store.clone(), &setup.genesis.config)
+                .unwrap();
+
+        let epoch_ids = last_epochs(&epoch_manager, &head.last_block_hash, 10).unwrap();
+        assert_eq!(epoch_ids.len(), 3);
+        assert_eq!(epoch_ids[0], head.epoch_id);
+        assert_eq!(last_epochs(&epoch_manager, &head.last_block_hash, 2).unwrap(), epoch_ids[..2]);
+
+        let current =
+            epoch_performance(&epoch_manager, &epoch_ids[0], &head.last_block_hash).unwrap();
+        assert!(!current.finished);
+        let previous =
+            epoch_performance(&epoch_manager, &epoch_ids[1], &head.last_block_hash).unwrap();
+        assert!(previous.finished);
+        assert_eq!(previous.validators.len(), 1);
+        let validator = &previous.validators[0];
+        assert_eq!(validator.account_id.as_ref(), "test0");
+        assert!(validator.expected_blocks > 0);
+        assert_eq!(validator.produced_blocks, validator.expected_blocks);
+        assert_eq!(validator.produced_chunks, validator.expected_chunks);
+        assert_eq!(validator.kickout, None);
+
+        let line = epoch_line(&previous);
+        assert!(line.starts_with(&format!("Epoch #{} ", previous.epoch_height)), "{}", line);
+        assert!(!line.ends_with("in progress"));
+        let record = ValidatorRecord {
+            epoch_id: &previous.epoch_id,
+            epoch_height: previous.epoch_height,
+            validator,
+        };
+        let expected = format!(
+            "test0 stake {}: blocks {}/{} (100.0%), chunks {}/{} (100.0%)",
+            validator.stake,
+            validator.produced_blocks,
+            validator.expected_blocks,
+            validator.produced_chunks,
+            validator.expected_chunks
+        );
+        assert_eq!(record.human(), expected);
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["epoch_id"], previous.epoch_id.0.to_string());
+        assert_eq!(json["account_id"], "test0");
+        assert_eq!(json["stake"], validator.stake.to_string());
+        assert!(epoch_line(&current).ends_with(", in progress"));
+    }
+}
@@ -0,0 +1,97 @@
+use near_primitives::block::{validate_header_chain, BlockHeader, HeaderValidationOptions, Tip};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_store::{DBCol, Store, HEADER_HEAD_KEY};
+use std::ops::RangeInclusive;
+
+/// Reads the headers of the canonical header chain with heights in `heights`,
+/// oldest first, walking back from the header head.
+pub(crate) fn read_headers(
+    store: &Store,
+    heights: RangeInclusive<BlockHeight>,
+) -> anyhow::Result<Vec<BlockHeader>> {
+    let head = store
+        .get_ser::<Tip>(DBCol::BlockMisc, HEADER_HEAD_KEY)?
+        .ok_or_else(|| anyhow::anyhow!("the database has no header head"))?;
+    let mut headers = vec![];
+    let mut hash = head.last_block_hash;
+    loop {
+        let header = store
+            .get_ser::<BlockHeader>(DBCol::BlockHeader, hash.as_ref())?
+            .ok_or_else(|| anyhow::anyhow!("header {} is missing", hash))?;
+        if header.height() < *heights.start() {
+            break;
+        }
+        let is_first =
+            header.height() == *heights.start() || header.prev_hash() == &CryptoHash::default();
+        hash = *header.prev_hash();
+        if heights.contains(&header.height()) {
+            headers.push(header);
+        }
+        if is_first {
+            break;
+        }
+    }
+    headers.reverse();
+    Ok(headers)
+}
+
+/// Runs `validate_header_chain` on the canonical headers with heights in
+/// `heights` and returns how many were checked.
+///
+/// The first header is anchored at its own prev hash, or at itself for the
+/// genesis header, so any range can be checked on its own.
+pub(crate) fn verify_headers(
+    store: &Store,
+    heights: RangeInclusive<BlockHeight>,
+    options: HeaderValidationOptions,
+) -> anyhow::Result<usize> {
+    let headers = read_headers(store, heights)?;
+    let anchor = match headers.first() {
+        Some(first) if first.prev_hash() == &CryptoHash::default() => *first.hash(),
+        Some(first) => *first.prev_hash(),
+        None => return Ok(0),
+    };
+    validate_header_chain(&headers, &anchor, options)?;
+    Ok(headers.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::setup_env;
+    use near_primitives::block::HeaderValidationError;
+
+    #[test]
+    fn test_verify_headers() {
+        let mut setup = setup_env(&["test0", "test1"], 0);
+        let genesis_height = setup.env.clients[0].chain.genesis().height();
+        setup.produce_blocks([1, 2, 4, 5]);
+        let store = setup.store;
+
+        assert_eq!(
+            verify_headers(&store, genesis_height..=5, HeaderValidationOptions::default()).unwrap(),
+            5
+        );
+        assert_eq!(verify_headers(&store, 2..=4, HeaderValidationOptions::default()).unwrap(), 2);
+        assert_eq!(verify_headers(&store, 3..=3, HeaderValidationOptions::default()).unwrap(), 0);
+        let heights = read_headers(&store, 2..=5)
+            .unwrap()
+            .iter()
+            .map(|header| header.height())
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![2, 4, 5]);
+
+        // A header whose contents don't match its hash is reported.
+        let mut header = read_headers(&store, 4..=4).unwrap().pop().unwrap();
+        header.get_mut().inner_lite.outcome_root = CryptoHash::hash_bytes(b"outcome");
+        let mut update = store.store_update();
+        update.set_ser(DBCol::BlockHeader, header.hash().as_ref(), &header).unwrap();
+        update.commit().unwrap();
+        let err = verify_headers(&store, 2..=5, HeaderValidationOptions::default()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HeaderValidationError>(),
+            Some(HeaderValidationError::HashMismatch { index: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::output::Record;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::types::StateRoot;
+use near_store::raw_node::decode_trie_node;
+use near_store::{DBCol, Store};
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// Number of nodes between calls of the progress callback.
+const PROGRESS_INTERVAL: u64 = 100_000;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TrieStats {
+    pub num_nodes: u64,
+    pub num_values: u64,
+    /// Bytes of the nodes and values read.
+    pub bytes: u64,
+}
+
+/// A trie node or value which is missing or doesn't hash correctly.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct BrokenNode {
+    /// Nibbles of the key leading from the root to the node, serialized as a
+    /// hex digit each.
+    #[serde(serialize_with = "serialize_nibbles")]
+    pub nibbles: Vec<u8>,
+    /// Hashes of the nodes from the root down to the broken node or value.
+    pub path: Vec<CryptoHash>,
+    pub reason: String,
+}
+
+fn nibbles_to_string(nibbles: &[u8]) -> String {
+    nibbles.iter().map(|nibble| char::from_digit(*nibble as u32, 16).unwrap()).collect()
+}
+
+fn serialize_nibbles<S: Serializer>(nibbles: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&nibbles_to_string(nibbles))
+}
+
+impl fmt::Display for BrokenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nibbles = nibbles_to_string(&self.nibbles);
+        write!(f, "{} at key nibbles '{}', path from the root:", self.reason, nibbles)?;
+        for hash in &self.path {
+            write!(f, "\n  {}", hash)?;
+        }
+        Ok(())
+    }
+}
+
+impl Record for BrokenNode {
+    fn human(&self) -> String {
+        self.to_string()
+    }
+}
+
+struct Verifier<'a> {
+    store: &'a Store,
+    shard_uid: ShardUId,
+    stats: TrieStats,
+    broken: Vec<BrokenNode>,
+    nibbles: Vec<u8>,
+    path: Vec<CryptoHash>,
+    on_progress: &'a mut dyn FnMut(&TrieStats),
+}
+
+impl Verifier<'_> {
+    /// Reads the node or value with the given hash and checks its hash.
+    fn read(&mut self, expected: &CryptoHash) -> anyhow::Result<Option<Vec<u8>>> {
+        let key = [&self.shard_uid.to_bytes()[..], expected.as_ref()].concat();
+        let value = match self.store.get(DBCol::State, &key)? {
+            Some(value) => value,
+            None => {
+                self.report("missing from State".to_string());
+                return Ok(None);
+            }
+        };
+        self.stats.bytes += value.len() as u64;
+        let actual = hash(&value);
+        if actual != *expected {
+            self.report(format!("hashes to {}", actual));
+            return Ok(None);
+        }
+        Ok(Some(value))
+    }
+
+    fn report(&mut self, reason: String) {
+        let node = BrokenNode { nibbles: self.nibbles.clone(), path: self.path.clone(), reason };
+        self.broken.push(node);
+    }
+
+    fn visit_value(&mut self, value_hash: &CryptoHash, value_length: u32) -> anyhow::Result<()> {
+        self.path.push(*value_hash);
+        if let Some(value) = self.read(value_hash)? {
+            self.stats.num_values += 1;
+            if value.len() != value_length as usize {
+                self.report(format!("has {} bytes instead of {}", value.len(), value_length));
+            }
+        }
+        self.path.pop();
+        Ok(())
+    }
+
+    fn visit_node(&mut self, node_hash: &CryptoHash) -> anyhow::Result<()> {
+        self.path.push(*node_hash);
+        if let Some(bytes) = self.read(node_hash)? {
+            self.stats.num_nodes += 1;
+            if self.stats.num_nodes % PROGRESS_INTERVAL == 0 {
+                (self.on_progress)(&self.stats);
+            }
+            match decode_trie_node(&bytes) {
+                Ok(node) => {
+                    if let Some((nibbles, value_hash, value_length)) = node.value {
+                        self.nibbles.extend(&nibbles);
+                        self.visit_value(&value_hash, value_length)?;
+                        self.nibbles.truncate(self.nibbles.len() - nibbles.len());
+                    }
+                    for (nibbles, child) in node.children {
+                        self.nibbles.extend(&nibbles);
+                        self.visit_node(&child)?;
+                        self.nibbles.truncate(self.nibbles.len() - nibbles.len());
+                    }
+                }
+                Err(err) => self.report(format!("can't be decoded: {}", err)),
+            }
+        }
+        self.path.pop();
+        Ok(())
+    }
+}
+
+/// Walks the whole trie of `shard_uid` under `root` and checks that every
+/// node and value is in `DBCol::State` and hashes to the hash it's referred
+/// to by.  Broken nodes are reported and their subtrees skipped.
+///
+/// `on_progress` is called every [`PROGRESS_INTERVAL`] nodes.
+pub(crate) fn verify_trie(
+    store: &Store,
+    shard_uid: ShardUId,
+    root: &StateRoot,
+    on_progress: &mut dyn FnMut(&TrieStats),
+) -> anyhow::Result<(TrieStats, Vec<BrokenNode>)> {
+    let mut verifier = Verifier {
+        store,
+        shard_uid,
+        stats: TrieStats::default(),
+        broken: vec![],
+        nibbles: vec![],
+        path: vec![],
+        on_progress,
+    };
+    // The empty trie has no nodes.
+    if *root != CryptoHash::default() {
+        verifier.visit_node(root)?;
+    }
+    Ok((verifier.stats, verifier.broken))
+}
+
+#[cfg(test)]
+mod test {
+    use super::verify_trie;
+    use crate::test_utils::setup_env;
+    use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::shard_layout::ShardUId;
+    use near_store::raw_node::decode_trie_node;
+    use near_store::{decode_value_with_rc, DBCol};
+
+    #[test]
+    fn test_verify_trie() {
+        let mut setup = setup_env(&["test0", "test1"], 2);
+        let head = setup.env.clients[0].chain.head().unwrap();
+        let block = setup.env.clients[0].chain.get_block(&head.last_block_hash).unwrap().clone();
+        let store = setup.store;
+        let root = block.chunks()[0].prev_state_root();
+        let shard_uid = ShardUId::single_shard();
+        let verify = || verify_trie(&store, shard_uid, &root, &mut |_| {}).unwrap();
+
+        let (stats, broken) = verify();
+        assert_eq!(broken, vec![]);
+        assert!(stats.num_nodes > 1);
+        assert!(stats.num_values > 1);
+        assert!(stats.bytes > 0);
+        let (empty_stats, _) =
+            verify_trie(&store, shard_uid, &CryptoHash::default(), &mut |_| {}).unwrap();
+        assert_eq!(empty_stats, Default::default());
+
+        // Remove the first child of the root, as if GC removed it.
+        let key = |hash: &CryptoHash| [&shard_uid.to_bytes()[..], hash.as_ref()].concat();
+        let root_node = decode_trie_node(&store.get(DBCol::State, &key(&root)).unwrap().unwrap());
+        let (nibbles, child) = root_node.unwrap().children.remove(0);
+        let rc = store
+            .iter_raw_bytes(DBCol::State)
+            .find(|(db_key, _)| db_key[..] == key(&child)[..])
+            .map(|(_, value)| decode_value_with_rc(&value).1)
+            .unwrap();
+        let mut update = store.store_update();
+        update.update_refcount(DBCol::State, &key(&child), &[], -rc);
+        update.commit().unwrap();
+        let (corrupted_stats, broken) = verify();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].nibbles, nibbles);
+        assert_eq!(broken[0].path, vec![root, child]);
+        assert_eq!(broken[0].reason, "missing from State");
+        assert!(corrupted_stats.num_nodes < stats.num_nodes);
+        let message = broken[0].to_string();
+        assert!(message.ends_with(&format!(":\n  {}\n  {}", root, child)), "{}", message);
+        let json = serde_json::to_value(&broken[0]).unwrap();
+        let nibbles: String = nibbles.iter().map(|nibble| format!("{:x}", nibble)).collect();
+        assert_eq!(json["nibbles"], nibbles);
+        assert_eq!(json["path"][1], child.to_string());
+
+        // And now store different bytes under its hash.
+        let mut update = store.store_update();
+        update.update_refcount(DBCol::State, &key(&child), b"garbage", 1);
+        update.commit().unwrap();
+        let (_, broken) = verify();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].reason, format!("hashes to {}", hash(b"garbage")));
+    }
+}